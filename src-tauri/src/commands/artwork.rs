@@ -17,15 +17,19 @@
 // | Rust Command                | TypeScript Function               |
 // |-----------------------------|-----------------------------------|
 // | download_animated_artwork   | downloadAnimatedArtwork(urls, dir)|
+// | recheck_album_artwork       | recheckAlbumArtwork(urls, dir)    |
 //
 // ## References
 //
 // - Tauri IPC commands: https://v2.tauri.app/develop/calling-rust/
 // - animated_artwork_service: src-tauri/src/services/animated_artwork_service.rs
 
+use std::path::Path;
+
 use tauri::AppHandle;
 
 use crate::services::animated_artwork_service::{self, ArtworkResult};
+use crate::services::config_service;
 
 /// Manually download animated artwork for an album.
 ///
@@ -52,5 +56,50 @@ pub async fn download_animated_artwork(
     urls: Vec<String>,
     output_dir: String,
 ) -> Result<ArtworkResult, String> {
-    animated_artwork_service::process_album_artwork(&app, &urls, &output_dir).await
+    animated_artwork_service::process_album_artwork(&app, &urls, &output_dir, None).await
+}
+
+/// Re-checks an already-downloaded album for animated artwork and fetches
+/// the variants its folder doesn't have yet.
+///
+/// **Frontend caller:** `recheckAlbumArtwork(urls, albumDir)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// For albums downloaded before their motion artwork existed, or before
+/// animated artwork was enabled. Variants already saved (`FrontCover.mp4`,
+/// ...) are kept; the new ones are hidden when
+/// `AppSettings::hide_animated_artwork` is on, as after a download.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for accessing settings, keychain, and FFmpeg path
+/// * `urls` - Apple Music URL(s) for the album (used to extract storefront/ID)
+/// * `album_dir` - The existing album directory
+///
+/// # Returns
+/// * `Ok(ArtworkResult)` - Which artwork types were newly downloaded
+/// * `Err(String)` - The folder doesn't exist, animated artwork is turned off
+///   or a MusicKit credential is missing (the message says which), or the
+///   process failed
+#[tauri::command]
+pub async fn recheck_album_artwork(
+    app: AppHandle,
+    urls: Vec<String>,
+    album_dir: String,
+) -> Result<ArtworkResult, String> {
+    let dir = Path::new(&album_dir);
+    if !dir.is_dir() {
+        return Err(format!("Album folder not found: {}", album_dir));
+    }
+
+    let result = animated_artwork_service::recheck_album_artwork(&app, &urls, &album_dir).await?;
+
+    let settings = config_service::load_settings(&app).unwrap_or_default();
+    if settings.hide_animated_artwork {
+        for file in result.downloaded_files() {
+            if let Err(e) = animated_artwork_service::hide_file(&dir.join(file)).await {
+                log::debug!("Failed to hide {}: {}", file, e);
+            }
+        }
+    }
+    Ok(result)
 }
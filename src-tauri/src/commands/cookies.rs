@@ -9,6 +9,7 @@
 //   1. Detect which browsers are installed on the user's system
 //   2. Import Apple Music cookies from a selected browser
 //   3. Check macOS Full Disk Access status (needed for Safari)
+//   4. Check that a cookies file actually signs in to Apple Music
 //
 // All heavy lifting is delegated to `services::cookie_service`. These
 // command handlers are thin wrappers that extract arguments and call
@@ -21,6 +22,7 @@
 // | detect_browsers                | detectBrowsers()                 |
 // | import_cookies_from_browser    | importCookiesFromBrowser(id)     |
 // | check_full_disk_access         | checkFullDiskAccess()            |
+// | test_cookies_live              | testCookiesLive(path)            |
 //
 // ## References
 //
@@ -30,7 +32,7 @@
 use tauri::AppHandle;
 
 use crate::services::cookie_service;
-use crate::services::cookie_service::{CookieImportResult, DetectedBrowser};
+use crate::services::cookie_service::{CookieImportResult, CookieLiveTest, DetectedBrowser};
 
 /// Detects which browsers are installed on the user's system.
 ///
@@ -102,3 +104,23 @@ pub async fn import_cookies_from_browser(
 pub async fn check_full_disk_access() -> Result<bool, String> {
     Ok(cookie_service::check_full_disk_access())
 }
+
+/// Tests a cookies file against Apple Music with one authenticated request.
+///
+/// **Frontend caller:** `testCookiesLive(path)` in `src/lib/tauri-commands.ts`
+///
+/// Unlike `validate_cookies_file`, which only inspects the file, this asks
+/// Apple Music whether the sign-in token still works.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for the proxy settings
+/// * `path` - Path to the Netscape-format cookies file
+///
+/// # Returns
+/// * `Ok(CookieLiveTest)` - `valid`, `malformed`, `expired`, or `unverified`
+///   (the check itself failed) with a message and, when valid, the storefront.
+#[tauri::command]
+pub async fn test_cookies_live(app: AppHandle, path: String) -> Result<CookieLiveTest, String> {
+    log::info!("Testing cookies against Apple Music: {}", path);
+    Ok(cookie_service::test_cookies_live(&app, &path).await)
+}
@@ -21,8 +21,15 @@
 //
 // Currently used for:
 // - Wrapper URL (for GAMDL's Apple Music API wrapper)
+// - MusicKit private key (`musickit_private_key`, for animated artwork)
+// - Headless API token (`headless_api_token`, see services/headless_api.rs)
 // - Future: YouTube Music / Spotify API keys
 //
+// Values stored under known keys are validated before they reach the
+// keychain (e.g., the MusicKit key must be a PEM `PRIVATE KEY` block).
+// Failures are returned as a structured `CredentialError` so the frontend
+// can tell a malformed value apart from a keychain problem.
+//
 // ## Frontend Mapping (src/lib/tauri-commands.ts)
 //
 // | Rust Command       | TypeScript Function       | Line |
@@ -30,6 +37,8 @@
 // | store_credential   | storeCredential(k, v)     | ~133 |
 // | get_credential     | getCredential(k)          | ~138 |
 // | delete_credential  | deleteCredential(k)       | ~143 |
+// | verify_musickit_credentials | verifyMusickitCredentials() | ~500 |
+// | get_headless_api_token | getHeadlessApiToken() | ~720 |
 //
 // ## References
 //
@@ -38,6 +47,16 @@
 // - macOS Keychain Services: https://developer.apple.com/documentation/security/keychain_services
 // - Windows Credential Manager: https://learn.microsoft.com/en-us/windows/win32/secauthn/credential-manager
 
+use serde::Serialize;
+use tauri::AppHandle;
+
+// animated_artwork_service owns MusicKit key validation and token signing.
+use crate::services::animated_artwork_service;
+// config_service loads the MusicKit Team ID / Key ID from settings.
+use crate::services::config_service;
+// headless_api owns the API token's keychain entry.
+use crate::services::headless_api;
+
 /// The service name used as the namespace in the OS keychain.
 /// All credentials stored by this app use this identifier.
 ///
@@ -52,6 +71,77 @@
 /// See: https://docs.rs/keyring/latest/keyring/struct.Entry.html
 const SERVICE_NAME: &str = "io.github.meedyadl";
 
+/// Structured error returned by credential commands.
+///
+/// Serialized to the frontend as `{ code, key, message }`. `code` is a
+/// stable machine-readable identifier; `message` is safe to show as-is.
+///
+/// | Code                   | Meaning                                            |
+/// |------------------------|----------------------------------------------------|
+/// | `invalid_private_key`  | MusicKit key is not a PEM `PRIVATE KEY` EC key     |
+/// | `invalid_team_id`      | Team ID is not 10 alphanumeric characters          |
+/// | `invalid_key_id`       | Key ID is not 10 alphanumeric characters           |
+/// | `missing_credential`   | A credential needed for verification isn't set     |
+/// | `verification_failed`  | Apple rejected the token or the request failed     |
+/// | `keychain`             | The OS keychain could not be accessed              |
+#[derive(Debug, Clone, Serialize)]
+pub struct CredentialError {
+    /// Machine-readable error code (see table above).
+    pub code: &'static str,
+    /// The credential or setting the error refers to (e.g., `"musickit_key_id"`).
+    pub key: String,
+    /// Human-readable description of the problem.
+    pub message: String,
+}
+
+impl CredentialError {
+    fn new(code: &'static str, key: &str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            key: key.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for CredentialError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Validates a credential value against the format expected for its key.
+/// Keys without a known format are accepted as-is.
+fn validate_credential(key: &str, value: &str) -> Result<(), CredentialError> {
+    match key {
+        animated_artwork_service::PRIVATE_KEY_CREDENTIAL => {
+            animated_artwork_service::validate_private_key(value)
+                .map_err(|e| CredentialError::new("invalid_private_key", key, e))
+        }
+        "musickit_team_id" => validate_musickit_id(key, value, "invalid_team_id", "Team ID"),
+        "musickit_key_id" => validate_musickit_id(key, value, "invalid_key_id", "Key ID"),
+        _ => Ok(()),
+    }
+}
+
+/// Checks the 10-character alphanumeric format shared by Team IDs and Key IDs.
+fn validate_musickit_id(
+    key: &str,
+    value: &str,
+    code: &'static str,
+    label: &str,
+) -> Result<(), CredentialError> {
+    if animated_artwork_service::is_valid_musickit_id(value) {
+        Ok(())
+    } else {
+        Err(CredentialError::new(
+            code,
+            key,
+            format!("{} must be exactly 10 letters or digits", label),
+        ))
+    }
+}
+
 /// Stores a credential securely in the OS keychain.
 ///
 /// **Frontend caller:** `storeCredential(key, value)` in `src/lib/tauri-commands.ts`
@@ -70,25 +160,37 @@ const SERVICE_NAME: &str = "io.github.meedyadl";
 ///   This becomes the "account" field in the keychain entry.
 /// * `value` - The secret value to store. Stored as the "password" field.
 ///
+/// Values for known keys are validated first (see `validate_credential`);
+/// storing a new MusicKit key over an old one is how the key is rotated.
+///
 /// # Returns
 /// * `Ok(())` - Credential stored (or overwritten) successfully.
-/// * `Err(String)` - Keychain access error (locked keychain, permission denied, etc.).
+/// * `Err(CredentialError)` - The value is malformed for its key, or the
+///   keychain could not be accessed (locked keychain, permission denied, etc.).
 ///
 /// # Security Note
 /// The value is never logged — only the key name is logged for auditability.
 #[tauri::command]
-pub async fn store_credential(key: String, value: String) -> Result<(), String> {
+pub async fn store_credential(key: String, value: String) -> Result<(), CredentialError> {
+    // Reject malformed values before they overwrite a working credential
+    validate_credential(&key, &value)?;
+
     // Create a keyring entry handle for the (service, key) pair.
     // Entry::new() can fail if the OS keychain backend is unavailable.
     // See: https://docs.rs/keyring/latest/keyring/struct.Entry.html#method.new
-    let entry = keyring::Entry::new(SERVICE_NAME, &key)
-        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+    let entry = keyring::Entry::new(SERVICE_NAME, &key).map_err(|e| {
+        CredentialError::new("keychain", &key, format!("Failed to create keyring entry: {}", e))
+    })?;
 
     // Store the credential in the OS keychain.
     // set_password() creates or overwrites the credential atomically.
-    entry
-        .set_password(&value)
-        .map_err(|e| format!("Failed to store credential '{}': {}", key, e))?;
+    entry.set_password(&value).map_err(|e| {
+        CredentialError::new(
+            "keychain",
+            &key,
+            format!("Failed to store credential '{}': {}", key, e),
+        )
+    })?;
 
     // Log the key name only (never the value) for debugging and auditing
     log::info!("Credential '{}' stored securely", key);
@@ -174,3 +276,73 @@ pub async fn delete_credential(key: String) -> Result<(), String> {
         Err(e) => Err(format!("Failed to delete credential '{}': {}", key, e)),
     }
 }
+
+/// Verifies that the configured MusicKit credentials actually work.
+///
+/// **Frontend caller:** `verifyMusickitCredentials()` in `src/lib/tauri-commands.ts`
+///
+/// Checks the Team ID and Key ID from settings and the private key from
+/// the keychain, then signs a developer token and makes one lightweight
+/// Apple Music catalog request with it. Lets the user confirm the setup
+/// on the settings page instead of discovering a problem after a download.
+///
+/// # Returns
+/// * `Ok(())` - Apple accepted the signed token.
+/// * `Err(CredentialError)` - A credential is missing or malformed
+///   (`missing_credential`, `invalid_*`), the keychain is inaccessible
+///   (`keychain`), or Apple rejected the token (`verification_failed`).
+#[tauri::command]
+pub async fn verify_musickit_credentials(app: AppHandle) -> Result<(), CredentialError> {
+    let settings = config_service::load_settings(&app).unwrap_or_default();
+
+    // Team ID and Key ID live in settings (not secret)
+    let team_id = settings.musickit_team_id.unwrap_or_default();
+    let key_id = settings.musickit_key_id.unwrap_or_default();
+    for (key, value, label) in [
+        ("musickit_team_id", &team_id, "Team ID"),
+        ("musickit_key_id", &key_id, "Key ID"),
+    ] {
+        if value.is_empty() {
+            return Err(CredentialError::new(
+                "missing_credential",
+                key,
+                format!("MusicKit {} is not set", label),
+            ));
+        }
+        validate_credential(key, value)?;
+    }
+
+    // The private key lives in the keychain
+    let key_name = animated_artwork_service::PRIVATE_KEY_CREDENTIAL;
+    let private_key = animated_artwork_service::get_private_key_from_keychain()
+        .map_err(|e| CredentialError::new("keychain", key_name, e))?
+        .ok_or_else(|| {
+            CredentialError::new(
+                "missing_credential",
+                key_name,
+                "MusicKit private key is not stored in the keychain",
+            )
+        })?;
+    validate_credential(key_name, &private_key)?;
+
+    animated_artwork_service::verify_credentials(&app, &team_id, &key_id, &private_key)
+        .await
+        .map_err(|e| CredentialError::new("verification_failed", key_name, e))?;
+
+    log::info!("MusicKit credentials verified");
+    Ok(())
+}
+
+/// Returns the bearer token scripts send to the headless API, generating
+/// and storing one in the keychain on first use.
+///
+/// **Frontend caller:** `getHeadlessApiToken()` in `src/lib/tauri-commands.ts`
+///
+/// # Returns
+/// * `Ok(String)` - The token.
+/// * `Err(CredentialError)` - The keychain could not be accessed.
+#[tauri::command]
+pub async fn get_headless_api_token() -> Result<String, CredentialError> {
+    headless_api::ensure_token()
+        .map_err(|e| CredentialError::new("keychain", headless_api::TOKEN_CREDENTIAL, e))
+}
@@ -27,8 +27,32 @@
 // | start_download       | startDownload()        | ~99  |
 // | cancel_download      | cancelDownload()       | ~104 |
 // | retry_download       | retryDownload()        | ~109 |
+// | resume_all_cancelled | resumeAllCancelled()   | ~410 |
+// | retry_failed_tracks  | retryFailedTracks()    | ~405 |
+// | change_download_codec| changeDownloadCodec()  | ~376 |
+// | set_session_codec_override | setSessionCodecOverride() | ~422 |
+// | get_session_codec_override | getSessionCodecOverride() | ~440 |
 // | clear_queue          | clearQueue()           | ~114 |
+// | clear_completed      | clearCompleted()       | ~409 |
+// | clear_failed         | clearFailed()          | ~425 |
+// | reconcile_queue      | reconcileQueue()       | ~586 |
 // | get_queue_status     | getQueueStatus()       | ~119 |
+// | get_queue_status_filtered | getQueueStatusFiltered() | ~475 |
+// | get_batch_report     | getBatchReport()       | ~555 |
+// | set_download_label   | setDownloadLabel()     | ~490 |
+// | keep_download        | keepDownload()         | ~500 |
+// | set_download_output_path | setDownloadOutputPath() | ~495 |
+// | merge_downloads      | mergeDownloads()       | ~565 |
+// | get_speed_samples    | getSpeedSamples()      | ~458 |
+// | estimate_size        | estimateSize()         | ~510 |
+// | preflight            | preflight()            | ~550 |
+// | run_test_download    | runTestDownload()      | ~555 |
+// | import_csv           | importCsv()            | ~795 |
+// | download_companions_only | downloadCompanionsOnly() | ~570 |
+// | reveal_output        | revealOutput()         | ~408 |
+// | get_download_log     | getDownloadLog()       | ~650 |
+// | get_effective_options | getEffectiveOptions() | ~710 |
+// | open_download_log    | openDownloadLog()      | ~665 |
 // | check_gamdl_update   | checkGamdlUpdate()     | ~124 |
 //
 // ## References
@@ -37,6 +61,8 @@
 // - Tauri State management: https://v2.tauri.app/develop/state-management/
 // - Tauri Events (emit): https://v2.tauri.app/develop/calling-frontend/
 
+use std::path::Path;
+
 // serde::Serialize is required for any struct returned to the frontend — Tauri
 // serializes return values to JSON before sending them over the IPC bridge.
 use serde::Serialize;
@@ -44,14 +70,55 @@ use serde::Serialize;
 // Emitter allows sending events from Rust to the frontend (e.g., "download-queued").
 // State<'_, T> is Tauri's dependency injection for managed state (see main.rs setup).
 use tauri::{AppHandle, Emitter, State};
+// ShellExt provides `app.shell()` for opening folders (and download logs)
+// in the OS file manager or viewer.
+use tauri_plugin_shell::ShellExt;
 
 // DownloadRequest: the deserialized JSON payload from the frontend containing
 // URLs and optional per-download quality/format overrides.
+// DownloadState: the lifecycle state used to pick items to clear.
 // QueueItemStatus: per-item status info (id, state, progress, error message).
-use crate::models::download::{DownloadRequest, QueueItemStatus};
+// OutputCollision: payload of the "output-collision" event.
+// SkippedTrack: a track left out before queueing (unavailable in the catalog).
+// SpeedSample: one point of an item's throughput history.
+use crate::models::download::{
+    DownloadRequest, DownloadState, OutputCollision, QueueItemStatus, SkippedTrack, SpeedSample,
+};
+// SongCodec: the codec requested by add_codec_version(), and the primary
+// codec given to download_companions_only().
+// GamdlOptions: the redacted options returned by get_effective_options().
+use crate::models::gamdl_options::{GamdlOptions, SongCodec};
+// ContentPreference: whether start_download() swaps explicit/clean versions.
+use crate::models::settings::ContentPreference;
 // download_queue module contains the queue processing logic (process_queue).
 // QueueHandle is an Arc<Mutex<DownloadQueue>> shared across all command invocations.
 use crate::services::download_queue::{self, QueueHandle};
+// artist_expansion_service: Splits artist URLs into per-album requests.
+use crate::services::artist_expansion_service;
+// playlist_sync_service: Narrows re-submitted playlists to their new tracks.
+use crate::services::playlist_sync_service;
+// track_availability_service: Leaves out tracks the catalog no longer offers.
+use crate::services::track_availability_service;
+// content_rating_service: Swaps tracks for their explicit or clean version.
+use crate::services::content_rating_service;
+// download_log_service: Per-download GAMDL output logs.
+use crate::services::download_log_service;
+// album_resume_service: Catalog URLs of an album's unfinished tracks.
+use crate::services::album_resume_service;
+// size_estimate_service: Catalog-based download size estimates.
+use crate::services::size_estimate_service::{self, SizeEstimate};
+// preflight_service: Toolchain health check run before downloads.
+use crate::services::preflight_service::{self, PreflightReport};
+// test_download_service: One-track download that checks the whole pipeline.
+use crate::services::test_download_service::{self, TestDownloadResult};
+// csv_import_service: Parses CSV files of downloads with per-row options.
+use crate::services::csv_import_service::{self, CsvImportReport};
+// batch_report_service: Per-URL outcome table of a set of downloads.
+use crate::services::batch_report_service::{self, BatchReport};
+// storefront_service: Validates the per-download storefront override.
+use crate::services::storefront_service;
+// platform: resolve_reveal_dir() for reveal_output().
+use crate::utils::platform;
 
 /// Status of all items in the download queue.
 ///
@@ -73,6 +140,9 @@ pub struct QueueStatus {
     pub completed: usize,
     /// Number of items that failed with errors (state == Failed)
     pub failed: usize,
+    /// Progress of the unfinished items as one percentage (0.0 to 100.0),
+    /// weighted by track count. See `DownloadQueue::overall_progress()`.
+    pub overall_progress: f32,
     /// Detailed status for each queue item, including per-item progress,
     /// error messages, and the original download request parameters.
     pub items: Vec<QueueItemStatus>,
@@ -87,6 +157,28 @@ pub struct QueueStatus {
 /// settings are used. The download is added to the queue and will be
 /// processed when a slot becomes available (default: 1 concurrent).
 ///
+/// With `AppSettings::expand_artist_urls` on, Apple Music artist URLs are
+/// first expanded into one queue item per album (see
+/// `artist_expansion_service::expand_request()`).
+///
+/// With `AppSettings::content_preference` set to explicit or clean, album
+/// and song URLs are swapped, track by track, for the other release where
+/// it has the preferred version (see
+/// `content_rating_service::apply_preference()`).
+///
+/// With `AppSettings::playlist_sync` on, an Apple Music playlist URL that
+/// was synced before only queues the tracks added since (see
+/// `playlist_sync_service::sync_request()`); the count is reported via the
+/// `"playlist-sync"` event.
+///
+/// With `AppSettings::skip_unavailable_tracks` on, album and playlist tracks
+/// the Apple Music catalog no longer offers are left out and recorded on the
+/// queue item as skipped (see
+/// `track_availability_service::filter_request()`).
+///
+/// With `AppSettings::preflight_checks` on, the toolchain is checked first
+/// (see `preflight()`) and nothing is queued if any check fails.
+///
 /// Returns a unique download ID (UUID) for tracking progress and cancellation.
 ///
 /// # Arguments
@@ -99,34 +191,172 @@ pub struct QueueStatus {
 ///   Contains `urls: Vec<String>` and optional override fields.
 ///
 /// # Returns
-/// * `Ok(String)` - The unique download ID (UUID v4) assigned to this download.
-/// * `Err(String)` - Human-readable error message if the event emission fails.
+/// * `Ok(String)` - The unique download ID (UUID v4) assigned to this download
+///   (the first one, if an artist URL was expanded into several).
+/// * `Err(String)` - Human-readable error message if the storefront override
+///   isn't a two-letter code, a pre-flight check failed, a synced playlist
+///   has no new tracks, no track is available, or the event emission fails.
 ///
 /// # Events Emitted
-/// * `"download-queued"` - Emitted with the download ID after successful enqueue.
+/// * `"download-queued"` - Emitted with each download ID after successful enqueue.
 ///   The frontend listens for this to update the queue UI immediately.
 ///   See: https://v2.tauri.app/develop/calling-frontend/
+/// * `"output-collision"` - An `OutputCollision` for each new download that
+///   saves into the same album folder as an unfinished one; the frontend
+///   can offer `merge_downloads`.
 #[tauri::command]
 pub async fn start_download(
     app: AppHandle,
     queue: State<'_, QueueHandle>,
-    request: DownloadRequest,
+    mut request: DownloadRequest,
 ) -> Result<String, String> {
+    // Reject a malformed storefront override before anything is queued.
+    if let Some(options) = request.options.as_mut() {
+        storefront_service::normalize_override(options)?;
+    }
+
     // Load current settings for merging with per-download overrides.
     // If settings can't be loaded (corrupted file, etc.), fall back to defaults
     // so the download can still proceed with sensible quality/format choices.
     let settings = crate::services::config_service::load_settings(&app)
         .unwrap_or_default();
 
-    // Acquire the queue lock and enqueue the download. The lock is scoped
+    // Catch expired cookies, missing tools or an unwritable output folder
+    // now rather than halfway through the album.
+    if settings.preflight_checks {
+        let options = download_queue::merge_options(
+            request.options.as_ref(),
+            &settings,
+            download_queue::request_kind(&request),
+        );
+        let report = preflight_service::run(&app, &options, &request.urls).await;
+        if let Some(summary) = report.failure_summary() {
+            return Err(summary);
+        }
+    }
+
+    // Split artist URLs into one request per album, so each album gets its
+    // own progress, fallback and retry. Falls back to the request as-is.
+    let requests = if settings.expand_artist_urls {
+        artist_expansion_service::expand_request(&app, request).await
+    } else {
+        vec![request]
+    };
+
+    // Swap tracks for their explicit or clean version. GAMDL can't choose,
+    // so the URLs themselves point at the preferred release.
+    let requests = if settings.content_preference != ContentPreference::AsIs {
+        let mut swapped = Vec::new();
+        for request in requests {
+            swapped.push(
+                content_rating_service::apply_preference(
+                    &app,
+                    request,
+                    settings.content_preference,
+                )
+                .await,
+            );
+        }
+        swapped
+    } else {
+        requests
+    };
+
+    // Narrow playlists that were synced before to their new tracks. Each
+    // synced request carries the track IDs to record once it completes.
+    let requests: Vec<(DownloadRequest, Option<playlist_sync_service::PlaylistSyncBatch>)> =
+        if settings.playlist_sync {
+            let mut synced = Vec::new();
+            for request in requests {
+                synced.extend(playlist_sync_service::sync_request(&app, request).await);
+            }
+            if synced.is_empty() {
+                return Err("No new tracks since the last sync".to_string());
+            }
+            synced
+        } else {
+            requests.into_iter().map(|request| (request, None)).collect()
+        };
+
+    // Leave out tracks the catalog no longer offers, so GAMDL doesn't fail
+    // on each of them. A request with nothing left is dropped.
+    let requests: Vec<(
+        DownloadRequest,
+        Option<playlist_sync_service::PlaylistSyncBatch>,
+        Vec<SkippedTrack>,
+    )> = if settings.skip_unavailable_tracks {
+        let mut filtered = Vec::new();
+        for (request, playlist_sync) in requests {
+            let (request, skipped) =
+                track_availability_service::filter_request(&app, request).await;
+            if !request.urls.is_empty() {
+                filtered.push((request, playlist_sync, skipped));
+            }
+        }
+        if filtered.is_empty() {
+            return Err("None of the tracks are available on Apple Music".to_string());
+        }
+        filtered
+    } else {
+        requests
+            .into_iter()
+            .map(|(request, playlist_sync)| (request, playlist_sync, Vec::new()))
+            .collect()
+    };
+
+    // Give each track the best codec of the fallback chain (the download's
+    // own, if it has one) it offers, as one job per codec. A download with a
+    // codec override keeps it. The sync batch and skipped tracks stay with
+    // the first job.
+    let requests = if settings.per_track_best_codec {
+        let mut split = Vec::new();
+        for (request, playlist_sync, skipped) in requests {
+            if request
+                .options
+                .as_ref()
+                .is_some_and(|o| o.song_codec.is_some())
+            {
+                split.push((request, playlist_sync, skipped));
+                continue;
+            }
+            let chain = request
+                .fallback_chain_override
+                .clone()
+                .unwrap_or_else(|| settings.music_fallback_chain.clone());
+            let mut jobs = track_availability_service::split_by_best_codec(&app, request, &chain)
+                .await
+                .into_iter();
+            if let Some(first) = jobs.next() {
+                split.push((first, playlist_sync, skipped));
+            }
+            split.extend(jobs.map(|request| (request, None, Vec::new())));
+        }
+        split
+    } else {
+        requests
+    };
+
+    // Acquire the queue lock and enqueue the download(s). The lock is scoped
     // to this block to release it before the async process_queue() call,
     // avoiding potential deadlocks.
-    let download_id = {
+    let download_ids: Vec<String> = {
         let mut q = queue.lock().await;
-        q.enqueue(request, &settings)
+        requests
+            .into_iter()
+            .map(|(request, playlist_sync, skipped)| {
+                let id = q.enqueue(request, &settings);
+                if let Some(batch) = playlist_sync {
+                    q.set_playlist_sync(&id, batch);
+                }
+                if !skipped.is_empty() {
+                    q.set_skipped_tracks(&id, skipped);
+                }
+                id
+            })
+            .collect()
     };
 
-    log::info!("Download {} queued", download_id);
+    log::info!("Download(s) {} queued", download_ids.join(", "));
 
     // Persist the updated queue to disk for crash recovery.
     // This ensures the new item survives an unexpected app close/crash.
@@ -135,13 +365,164 @@ pub async fn start_download(
 
     // Emit a Tauri event to notify the frontend that the download has been queued.
     // The frontend listens for "download-queued" events to refresh the queue UI.
-    app.emit("download-queued", &download_id)
-        .map_err(|e| format!("Failed to emit event: {}", e))?;
+    for download_id in &download_ids {
+        app.emit("download-queued", download_id)
+            .map_err(|e| format!("Failed to emit event: {}", e))?;
+    }
+
+    // Offer to merge a new download with an unfinished one that writes
+    // the same album folder (e.g., an album URL and its track URLs queued
+    // by two playlist imports), rather than letting them race.
+    let collisions: Vec<OutputCollision> = {
+        let q = queue.lock().await;
+        download_ids
+            .iter()
+            .filter_map(|id| {
+                q.find_output_collision(id).map(|existing_id| OutputCollision {
+                    download_id: id.clone(),
+                    existing_id,
+                })
+            })
+            .collect()
+    };
+    for collision in collisions {
+        log::info!(
+            "Download {} saves into the same album folder as {}",
+            collision.download_id,
+            collision.existing_id
+        );
+        let _ = app.emit("output-collision", &collision);
+    }
 
     // Trigger queue processing — this will start the download immediately if
     // there are available concurrency slots, or leave it queued for later.
     download_queue::process_queue(app, queue_handle).await;
 
+    download_ids
+        .into_iter()
+        .next()
+        .ok_or_else(|| "No URLs to download".to_string())
+}
+
+/// Adds another codec version of an already-downloaded album to its folder.
+///
+/// Enqueues a job that downloads the same URL(s) in `codec`, with the
+/// codec's filename suffix (`[Lossless]` or `[Dolby Atmos]`) applied so the
+/// new files sit alongside the existing clean-named ones without touching
+/// them. This is one companion tier triggered on demand; see
+/// `DownloadQueue::enqueue_codec_version()` for the suffix rules.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings access and event emission.
+/// * `queue` - Managed download queue state.
+/// * `urls` - Apple Music URL(s) of the album that was already downloaded.
+/// * `codec` - The codec to add (e.g., `"alac"` or `"atmos"`).
+///
+/// # Returns
+/// * `Ok(String)` - The download ID of the new queue item.
+/// * `Err(String)` - The codec has no filename suffix (lossy codecs would
+///   overwrite the existing files), or no URLs were given.
+///
+/// # Events Emitted
+/// * `"download-queued"` - Emitted with the download ID after successful enqueue.
+#[tauri::command]
+pub async fn add_codec_version(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    urls: Vec<String>,
+    codec: SongCodec,
+) -> Result<String, String> {
+    if urls.is_empty() {
+        return Err("No URLs provided".to_string());
+    }
+
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+
+    let codec_name = codec.display_name().to_string();
+    let download_id = {
+        let mut q = queue.lock().await;
+        q.enqueue_codec_version(urls, codec, &settings)
+    }
+    .ok_or_else(|| {
+        format!(
+            "{} files would overwrite the existing download; only lossless \
+             and Dolby Atmos versions can be added",
+            codec_name
+        )
+    })?;
+
+    log::info!("Codec version download {} queued ({})", download_id, codec_name);
+
+    let queue_handle = queue.inner().clone();
+    download_queue::save_queue_to_disk(&app, &queue_handle).await;
+
+    let _ = app.emit("download-queued", &download_id);
+    download_queue::process_queue(app, queue_handle).await;
+
+    Ok(download_id)
+}
+
+/// Downloads only the companion versions of an album that is already on
+/// disk, skipping the primary download.
+///
+/// **Frontend caller:** `downloadCompanionsOnly(urls, primaryCodec)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// Enqueues a job that runs the companion tiers a `primary_codec` download
+/// would have planned under the current companion mode (e.g. ALAC
+/// `[Lossless]` + AAC for Atmos in `AtmosToLosslessAndLossy`), with the
+/// same filename suffixes, so the new files sit next to the existing
+/// primaries. See `DownloadQueue::enqueue_companions_only()`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings access and event emission.
+/// * `queue` - Managed download queue state.
+/// * `urls` - Apple Music URL(s) of the album that was already downloaded.
+/// * `primary_codec` - The codec the existing files were downloaded in.
+///
+/// # Returns
+/// * `Ok(String)` - The download ID of the new queue item.
+/// * `Err(String)` - No URLs were given, or the companion mode plans no
+///   companions for `primary_codec`.
+///
+/// # Events Emitted
+/// * `"download-queued"` - Emitted with the download ID after successful enqueue.
+/// * `"companion-downloaded"` - Emitted for each tier that downloads.
+#[tauri::command]
+pub async fn download_companions_only(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    urls: Vec<String>,
+    primary_codec: SongCodec,
+) -> Result<String, String> {
+    if urls.is_empty() {
+        return Err("No URLs provided".to_string());
+    }
+
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+
+    let codec_name = primary_codec.display_name().to_string();
+    let download_id = {
+        let mut q = queue.lock().await;
+        q.enqueue_companions_only(urls, primary_codec, &settings)
+    }
+    .ok_or_else(|| {
+        format!(
+            "The current companion mode adds no companions to {} downloads",
+            codec_name
+        )
+    })?;
+
+    log::info!("Companion-only download {} queued ({})", download_id, codec_name);
+
+    let queue_handle = queue.inner().clone();
+    download_queue::save_queue_to_disk(&app, &queue_handle).await;
+
+    let _ = app.emit("download-queued", &download_id);
+    download_queue::process_queue(app, queue_handle).await;
+
     Ok(download_id)
 }
 
@@ -184,8 +565,9 @@ pub async fn cancel_download(
 
     if cancelled {
         // Persist the updated queue (cancelled item removed from active set)
+        // right away, so quitting straight after doesn't resume it
         let queue_handle = queue.inner().clone();
-        download_queue::save_queue_to_disk(&app, &queue_handle).await;
+        download_queue::flush_queue_to_disk(&app, &queue_handle).await;
 
         // Notify the frontend so it can update the item's UI state immediately.
         // We use `let _ =` to ignore emission errors — the cancellation itself
@@ -253,6 +635,223 @@ pub async fn retry_download(
     }
 }
 
+/// Resumes every cancelled download at once.
+///
+/// **Frontend caller:** `resumeAllCancelled()` in `src/lib/tauri-commands.ts`
+///
+/// Each cancelled item is reset as by `retry_download` and keeps its place
+/// in the queue; failed items are left for `retry_download`. See
+/// `DownloadQueue::resume_all_cancelled()`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings, persistence and events.
+/// * `queue` - Managed download queue state.
+///
+/// # Returns
+/// * `Ok(count)` - The number of downloads re-queued (0 if none were
+///   cancelled).
+///
+/// # Events Emitted
+/// * `"download-queued"` - With the ID of each re-queued download.
+#[tauri::command]
+pub async fn resume_all_cancelled(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+) -> Result<usize, String> {
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+
+    let resumed = queue.lock().await.resume_all_cancelled(&settings);
+    log::info!("Resumed {} cancelled download(s)", resumed.len());
+
+    if !resumed.is_empty() {
+        let queue_handle = queue.inner().clone();
+        download_queue::save_queue_to_disk(&app, &queue_handle).await;
+        for download_id in &resumed {
+            let _ = app.emit("download-queued", download_id);
+        }
+        download_queue::process_queue(app, queue_handle).await;
+    }
+    Ok(resumed.len())
+}
+
+/// Re-downloads only the tracks of an album download that didn't finish.
+///
+/// **Frontend caller:** `retryFailedTracks(downloadId)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// For an album whose finished positions were recorded, the catalog URLs
+/// of the missing tracks are looked up (`album_resume_service`) and queued
+/// as a new job with the album's options, so they land in the same folder
+/// without GAMDL going over the whole album again. Without per-track
+/// results, or if the lookup fails, the download is retried in full: in
+/// place for a failed or cancelled item (as `retry_download`), as a new job
+/// for a completed one.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings, the catalog lookup and events.
+/// * `queue` - Managed download queue state.
+/// * `download_id` - The finished, failed or cancelled download.
+///
+/// # Returns
+/// * `Ok(String)` - The ID of the job that will run: a new one, or
+///   `download_id` itself when retried in place.
+/// * `Err(String)` - The download is missing, still queued or running, a
+///   companion job, or has no failed tracks.
+///
+/// # Events Emitted
+/// * `"download-queued"` - With the returned ID.
+#[tauri::command]
+pub async fn retry_failed_tracks(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+) -> Result<String, String> {
+    log::info!("Retry of failed tracks requested for download: {}", download_id);
+
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+    let source = queue.lock().await.failed_track_source(&download_id)?;
+
+    // The catalog lookup runs without the queue lock held
+    let track_urls = match &source {
+        Some((urls, progress)) => album_resume_service::resume_urls(&app, urls, progress)
+            .await
+            .map(|(track_urls, _)| track_urls),
+        None => None,
+    };
+
+    let queued_id = {
+        let mut q = queue.lock().await;
+        match track_urls {
+            Some(track_urls) => {
+                log::info!(
+                    "Retrying {} failed track(s) of download {}",
+                    track_urls.len(),
+                    download_id
+                );
+                q.enqueue_retry_of(&download_id, track_urls, &settings)
+            }
+            None if q.retry(&download_id, &settings) => Some(download_id.clone()),
+            None => {
+                let urls = q
+                    .get_status()
+                    .into_iter()
+                    .find(|s| s.id == download_id)
+                    .map(|s| s.urls)
+                    .unwrap_or_default();
+                q.enqueue_retry_of(&download_id, urls, &settings)
+            }
+        }
+    }
+    .ok_or_else(|| format!("Download {} cannot be retried", download_id))?;
+
+    let queue_handle = queue.inner().clone();
+    download_queue::save_queue_to_disk(&app, &queue_handle).await;
+    let _ = app.emit("download-queued", &queued_id);
+    download_queue::process_queue(app, queue_handle).await;
+    Ok(queued_id)
+}
+
+/// Switches a queued, failed, or running download to a different codec.
+///
+/// **Frontend caller:** `changeDownloadCodec(downloadId, codec)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// Lets the user pick e.g. AAC mid-album instead of waiting for the ALAC
+/// fallbacks to run out. Queued and failed items are (re-)queued with the
+/// new codec; a running download is killed and restarted with it. The
+/// network retry budget is kept. See `DownloadQueue::change_codec()`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings access and event emission.
+/// * `queue` - Managed download queue state.
+/// * `download_id` - The unique ID of the download to change.
+/// * `codec` - The codec to switch to (e.g., `"aac"`).
+///
+/// # Returns
+/// * `Ok(true)` - The codec was changed.
+/// * `Ok(false)` - The download already uses this codec; nothing changed.
+/// * `Err(String)` - The download was not found or has already finished.
+///
+/// # Events Emitted
+/// * `"download-queued"` - Emitted with the download ID when a queued or
+///   failed item was (re-)queued with the new codec.
+#[tauri::command]
+pub async fn change_download_codec(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+    codec: SongCodec,
+) -> Result<bool, String> {
+    log::info!(
+        "Codec change to {} requested for download: {}",
+        codec.to_cli_string(),
+        download_id
+    );
+
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+
+    let (changed, restarting) = {
+        let mut q = queue.lock().await;
+        let changed = q.change_codec(&download_id, codec, &settings)?;
+        (changed, q.is_codec_restart_pending(&download_id))
+    };
+
+    if changed {
+        let queue_handle = queue.inner().clone();
+        download_queue::save_queue_to_disk(&app, &queue_handle).await;
+
+        // A running download re-queues itself once its process is killed;
+        // anything else is queued now and can start straight away.
+        if !restarting {
+            let _ = app.emit("download-queued", &download_id);
+            download_queue::process_queue(app, queue_handle).await;
+        }
+    }
+
+    Ok(changed)
+}
+
+/// Sets or clears a queue-wide codec for the rest of the session.
+///
+/// **Frontend caller:** `setSessionCodecOverride(codec)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// Every queued and newly added download starts with `codec` (e.g., AAC
+/// on a metered connection) instead of its own, without changing the
+/// default codec in settings. Clearing it restores each item's codec.
+/// Running downloads keep theirs. See
+/// `DownloadQueue::set_session_codec_override()`.
+///
+/// # Arguments
+/// * `queue` - Managed download queue state.
+/// * `codec` - The codec to use, or `None` to clear the override.
+#[tauri::command]
+pub async fn set_session_codec_override(
+    queue: State<'_, QueueHandle>,
+    codec: Option<SongCodec>,
+) -> Result<(), String> {
+    match &codec {
+        Some(codec) => log::info!("Session codec override set to {}", codec.to_cli_string()),
+        None => log::info!("Session codec override cleared"),
+    }
+    queue.lock().await.set_session_codec_override(codec);
+    Ok(())
+}
+
+/// Returns the session codec override, if one is set.
+///
+/// **Frontend caller:** `getSessionCodecOverride()` in
+/// `src/lib/tauri-commands.ts`
+#[tauri::command]
+pub async fn get_session_codec_override(
+    queue: State<'_, QueueHandle>,
+) -> Result<Option<SongCodec>, String> {
+    Ok(queue.lock().await.session_codec_override().cloned())
+}
+
 /// Clears all completed, failed, and cancelled items from the queue.
 ///
 /// **Frontend caller:** `clearQueue()` in `src/lib/tauri-commands.ts`
@@ -271,19 +870,100 @@ pub async fn clear_queue(
     app: AppHandle,
     queue: State<'_, QueueHandle>,
 ) -> Result<usize, String> {
-    let removed = {
+    let (ids, removed) = {
         let mut q = queue.lock().await;
+        // Take the IDs first so the removed items' logs can be deleted;
         // clear_finished() drains all terminal-state items and returns the count
-        q.clear_finished()
+        (
+            q.ids_to_clear(&download_queue::FINISHED_STATES),
+            q.clear_finished(),
+        )
     };
+    download_log_service::delete_logs(&app, &ids);
 
     // Persist the updated queue (or clear the file if nothing remains)
     let queue_handle = queue.inner().clone();
-    download_queue::save_queue_to_disk(&app, &queue_handle).await;
+    download_queue::flush_queue_to_disk(&app, &queue_handle).await;
+
+    Ok(removed)
+}
+
+/// Removes only the completed items from the download queue.
+///
+/// **Frontend caller:** `clearCompleted()` in `src/lib/tauri-commands.ts`
+///
+/// Failed and cancelled items are kept so the user can still inspect or
+/// retry them.
+///
+/// # Returns
+/// * `Ok(usize)` - The number of items that were removed from the queue.
+#[tauri::command]
+pub async fn clear_completed(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+) -> Result<usize, String> {
+    clear_states(&app, &queue, &[DownloadState::Complete]).await
+}
+
+/// Removes only the failed items from the download queue.
+///
+/// **Frontend caller:** `clearFailed()` in `src/lib/tauri-commands.ts`
+///
+/// Completed and cancelled items are kept.
+///
+/// # Returns
+/// * `Ok(usize)` - The number of items that were removed from the queue.
+#[tauri::command]
+pub async fn clear_failed(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+) -> Result<usize, String> {
+    clear_states(&app, &queue, &[DownloadState::Error]).await
+}
+
+/// Shared body of `clear_completed` and `clear_failed`: removes items in
+/// the given terminal states, deletes their logs and persists the queue.
+async fn clear_states(
+    app: &AppHandle,
+    queue: &State<'_, QueueHandle>,
+    states: &[DownloadState],
+) -> Result<usize, String> {
+    let (ids, removed) = {
+        let mut q = queue.lock().await;
+        (q.ids_to_clear(states), q.clear_by_state(states))
+    };
+    download_log_service::delete_logs(app, &ids);
+
+    // Persist the updated queue (or clear the file if nothing remains)
+    let queue_handle = queue.inner().clone();
+    download_queue::flush_queue_to_disk(app, &queue_handle).await;
 
     Ok(removed)
 }
 
+/// Frees download slots held by tasks that ended without releasing them.
+///
+/// **Frontend caller:** `reconcileQueue()` in `src/lib/tauri-commands.ts`
+///
+/// Manual counterpart of the queue watchdog: recomputes the active count
+/// from the running items (`DownloadQueue::reconcile()`) and, if that
+/// freed slots, starts the next queued items.
+///
+/// # Returns
+/// * `Ok(true)` - The count was wrong and has been corrected.
+/// * `Ok(false)` - Nothing needed fixing.
+#[tauri::command]
+pub async fn reconcile_queue(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+) -> Result<bool, String> {
+    let corrected = queue.lock().await.reconcile();
+    if corrected {
+        download_queue::process_queue(app, queue.inner().clone()).await;
+    }
+    Ok(corrected)
+}
+
 /// Returns the current status of all items in the download queue.
 ///
 /// **Frontend caller:** `getQueueStatus()` in `src/lib/tauri-commands.ts`
@@ -308,6 +988,7 @@ pub async fn get_queue_status(
     let (total, active, queued, completed, failed) = q.get_counts();
     // get_status() returns a Vec<QueueItemStatus> with per-item details
     let items = q.get_status();
+    let overall_progress = q.overall_progress();
 
     // Assemble and return the complete queue snapshot
     Ok(QueueStatus {
@@ -316,10 +997,425 @@ pub async fn get_queue_status(
         queued,
         completed,
         failed,
+        overall_progress,
         items,
     })
 }
 
+/// Returns the status of the queue items matching a search query.
+///
+/// **Frontend caller:** `getQueueStatusFiltered(query)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// Backs the queue panel's search box. The query is matched
+/// case-insensitively against each item's label, URLs, and current track
+/// name; an empty query returns every item. See
+/// `DownloadQueue::get_status_filtered()`.
+///
+/// # Arguments
+/// * `queue` - Managed download queue state (injected by Tauri).
+/// * `query` - The search text.
+///
+/// # Returns
+/// * `Ok(Vec<QueueItemStatus>)` - The matching items, in queue order.
+#[tauri::command]
+pub async fn get_queue_status_filtered(
+    queue: State<'_, QueueHandle>,
+    query: String,
+) -> Result<Vec<QueueItemStatus>, String> {
+    Ok(queue.lock().await.get_status_filtered(&query))
+}
+
+/// Returns the per-URL outcome table of a set of downloads.
+///
+/// **Frontend caller:** `getBatchReport(downloadIds)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// Backs the summary shown after a batch of URLs was queued ("47
+/// succeeded, 3 failed (auth), 1 skipped (unavailable)"). Built from the
+/// items' current statuses; see `batch_report_service::build_report()`.
+///
+/// # Arguments
+/// * `queue` - Managed download queue state (injected by Tauri).
+/// * `download_ids` - The downloads to report on. IDs that are no longer
+///   in the queue (e.g. cleared) are left out.
+///
+/// # Returns
+/// * `Ok(BatchReport)` - One row per URL and skipped track, the counts,
+///   and the summary line.
+#[tauri::command]
+pub async fn get_batch_report(
+    queue: State<'_, QueueHandle>,
+    download_ids: Vec<String>,
+) -> Result<BatchReport, String> {
+    let items: Vec<QueueItemStatus> = queue
+        .lock()
+        .await
+        .get_status()
+        .into_iter()
+        .filter(|item| download_ids.contains(&item.id))
+        .collect();
+    Ok(batch_report_service::build_report(&items))
+}
+
+/// Keeps a completed download in the queue, calling off its automatic
+/// removal (`AppSettings::auto_clear_completed_after_secs`).
+///
+/// **Frontend caller:** `keepDownload(downloadId)` in
+/// `src/lib/tauri-commands.ts`, when the user clicks the item.
+///
+/// # Arguments
+/// * `queue` - Managed download queue state.
+/// * `download_id` - The unique ID of the download.
+///
+/// # Returns
+/// * `Ok(true)` - A removal was scheduled and is now called off.
+/// * `Ok(false)` - Nothing was scheduled for the item (or it's gone).
+#[tauri::command]
+pub async fn keep_download(
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+) -> Result<bool, String> {
+    Ok(queue.lock().await.keep_item(&download_id))
+}
+
+/// Sets or clears the user's label on a download.
+///
+/// **Frontend caller:** `setDownloadLabel(downloadId, label)` in
+/// `src/lib/tauri-commands.ts`
+///
+/// Labels are notes for the user only and don't change what GAMDL
+/// downloads. They are saved with the queue and included in exports.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for persisting the queue.
+/// * `queue` - Managed download queue state.
+/// * `download_id` - The unique ID of the download.
+/// * `label` - The new label; `None` or a blank string clears it.
+///
+/// # Returns
+/// * `Ok(true)` - The label was updated.
+/// * `Ok(false)` - No item with that ID is in the queue.
+#[tauri::command]
+pub async fn set_download_label(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+    label: Option<String>,
+) -> Result<bool, String> {
+    let updated = queue.lock().await.set_label(&download_id, label);
+    if updated {
+        let queue_handle = queue.inner().clone();
+        download_queue::save_queue_to_disk(&app, &queue_handle).await;
+    }
+    Ok(updated)
+}
+
+/// Sends a queued or failed download to another output folder.
+///
+/// **Frontend caller:** `setDownloadOutputPath()` in `src/lib/tauri-commands.ts`
+///
+/// The folder (typically chosen with a folder picker) is checked first:
+/// it must be absolute and writable, and is created if missing (see
+/// `platform::ensure_writable_dir()`). The change is saved to `queue.json`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for persisting the queue.
+/// * `queue` - Managed download queue state.
+/// * `download_id` - The unique ID of the download.
+/// * `path` - The new output folder.
+///
+/// # Returns
+/// * `Ok(())` - The download will be saved under `path`.
+/// * `Err(String)` - The folder can't be used, the download wasn't found,
+///   or it is already downloading (its destination can't change
+///   mid-flight) or finished.
+#[tauri::command]
+pub async fn set_download_output_path(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+    path: String,
+) -> Result<(), String> {
+    let dir = platform::ensure_writable_dir(Path::new(path.trim()))?;
+    queue
+        .lock()
+        .await
+        .set_output_path(&download_id, dir.to_string_lossy().to_string())?;
+
+    log::info!("Download {} output folder set to {}", download_id, dir.display());
+    let queue_handle = queue.inner().clone();
+    download_queue::save_queue_to_disk(&app, &queue_handle).await;
+    Ok(())
+}
+
+/// Merges two queued downloads that save into the same album folder.
+///
+/// **Frontend caller:** `mergeDownloads()` in `src/lib/tauri-commands.ts`
+///
+/// The answer to an `"output-collision"` event: the source's URLs are
+/// added to the target (track URLs its album URL already covers are
+/// dropped) and the source leaves the queue, so one job writes the folder
+/// instead of two racing. See `DownloadQueue::merge_items()`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for persisting the queue and emitting events.
+/// * `queue` - Managed download queue state.
+/// * `target_id` - The download that keeps running, with both URL sets.
+/// * `source_id` - The download merged into it and removed.
+///
+/// # Returns
+/// * `Ok(())` - Merged.
+/// * `Err(String)` - Either download is missing, not queued or a batch
+///   job, the two don't share an album folder, or their labels or codec
+///   fallback chains differ.
+///
+/// # Events Emitted
+/// * `"download-queued"` - With `target_id`, so the queue view refreshes.
+#[tauri::command]
+pub async fn merge_downloads(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    target_id: String,
+    source_id: String,
+) -> Result<(), String> {
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+    queue.lock().await.merge_items(&target_id, &source_id, &settings)?;
+
+    let queue_handle = queue.inner().clone();
+    download_queue::save_queue_to_disk(&app, &queue_handle).await;
+    app.emit("download-queued", &target_id)
+        .map_err(|e| format!("Failed to emit event: {}", e))
+}
+
+/// Returns the recent download speeds of one queue item, oldest first.
+///
+/// **Frontend caller:** `getSpeedSamples()` in `src/lib/tauri-commands.ts`
+///
+/// Feeds the live throughput graph. Samples are taken about once a second
+/// while the item downloads and the last two minutes are kept; the list
+/// starts over when the item is retried or restarted.
+///
+/// # Arguments
+/// * `queue` - Managed download queue state (injected by Tauri).
+/// * `download_id` - The unique ID of the download.
+///
+/// # Returns
+/// * `Ok(Vec<SpeedSample>)` - The samples (empty before the first one).
+/// * `Err(String)` - If no item with that ID is in the queue.
+#[tauri::command]
+pub async fn get_speed_samples(
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+) -> Result<Vec<SpeedSample>, String> {
+    queue
+        .lock()
+        .await
+        .speed_samples(&download_id)
+        .ok_or_else(|| format!("Download {} not found in queue", download_id))
+}
+
+/// Estimates how much disk space downloading `urls` in `codec` will take.
+///
+/// **Frontend caller:** `estimateSize()` in `src/lib/tauri-commands.ts`
+///
+/// Looks up the track durations of each album, song or playlist via the
+/// Apple Music catalog API and multiplies them by the codec's average
+/// bitrate. This is an estimate, not a HEAD of every stream: AAC sizes are
+/// close, while ALAC and Dolby Atmos vary widely and also come with a
+/// `range`. See `services::size_estimate_service` for the accuracy notes.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings, credentials and proxy.
+/// * `urls` - The Apple Music URLs to size.
+/// * `codec` - The codec to estimate for; the default song codec from
+///   settings if `None`.
+///
+/// # Returns
+/// * `Ok(SizeEstimate)` - `{ track_count, estimated_bytes, range, codec,
+///   tracks, unresolved_urls }`.
+/// * `Err(String)` - MusicKit credentials are missing.
+#[tauri::command]
+pub async fn estimate_size(
+    app: AppHandle,
+    urls: Vec<String>,
+    codec: Option<SongCodec>,
+) -> Result<SizeEstimate, String> {
+    let codec = match codec {
+        Some(codec) => codec,
+        None => {
+            crate::services::config_service::load_settings(&app)
+                .unwrap_or_default()
+                .default_song_codec
+        }
+    };
+    size_estimate_service::estimate(&app, &urls, &codec).await
+}
+
+/// Checks that everything a download of `urls` needs is in place.
+///
+/// **Frontend caller:** `preflight(urls)` in `src/lib/tauri-commands.ts`
+///
+/// Verifies the cookies file (present, with an unexpired
+/// `media-user-token`), the Python runtime and GAMDL, FFmpeg (and
+/// mp4decrypt when the codec or a music video needs it), that the output
+/// folder is writable, and that the URLs are Apple Music links. See
+/// `services::preflight_service`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings and tool paths.
+/// * `urls` - The URLs about to be downloaded.
+///
+/// # Returns
+/// * `Ok(PreflightReport)` - `{ passed, checks }`; each check carries a
+///   remediation `hint` when it failed. Failed checks are not an `Err`.
+#[tauri::command]
+pub async fn preflight(app: AppHandle, urls: Vec<String>) -> Result<PreflightReport, String> {
+    let settings = crate::services::config_service::load_settings(&app).unwrap_or_default();
+    let options =
+        download_queue::merge_options(None, &settings, download_queue::download_kind(&urls));
+    Ok(preflight_service::run(&app, &options, &urls).await)
+}
+
+/// Downloads one short, widely available song with the current settings
+/// to check the whole pipeline end to end.
+///
+/// The song is saved to a scratch folder in the system temp directory,
+/// outside the queue and the library, checked to be a decodable audio
+/// file, then deleted. See `services::test_download_service`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings and tool paths.
+///
+/// # Returns
+/// * `Ok(TestDownloadResult)` - `{ passed, failed_stage, message, ... }`;
+///   `failed_stage` names the first stage that failed (`"auth"`,
+///   `"codec"`, `"decrypt"`, `"remux"`, ...). A failed test is not an `Err`.
+#[tauri::command]
+pub async fn run_test_download(app: AppHandle) -> Result<TestDownloadResult, String> {
+    let settings = crate::services::config_service::load_settings(&app).unwrap_or_default();
+    Ok(test_download_service::run(&app, &settings).await)
+}
+
+/// Opens the folder containing a download's output in the OS file manager
+/// (Finder, Explorer, or the Linux desktop's default file manager).
+///
+/// Resolves the folder from the item's recorded `output_path`: the parent
+/// directory for a single-track file, or the directory itself for an album.
+/// See `platform::resolve_reveal_dir()`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle, used to reach the shell plugin.
+/// * `queue` - Managed download queue state (injected by Tauri).
+/// * `download_id` - The unique ID of the download to reveal.
+///
+/// # Returns
+/// * `Ok(())` - The file manager was asked to open the folder.
+/// * `Err(String)` - The download has no output yet, the output was moved
+///   or deleted, or the file manager couldn't be launched.
+#[tauri::command]
+pub async fn reveal_output(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+) -> Result<(), String> {
+    // Copy the path out so the queue lock isn't held while the OS opens the folder
+    let output_path = queue
+        .lock()
+        .await
+        .get_output_path(&download_id)
+        .ok_or_else(|| format!("Download {} has no output to reveal", download_id))?;
+
+    let dir = platform::resolve_reveal_dir(Path::new(&output_path))?;
+
+    // The shell plugin's `open` is deprecated in favour of tauri-plugin-opener,
+    // but it is the plugin this app already ships and grants `shell:allow-open`.
+    #[allow(deprecated)]
+    app.shell()
+        .open(dir.to_string_lossy().to_string(), None)
+        .map_err(|e| format!("Failed to open {}: {}", dir.display(), e))
+}
+
+/// Returns the GAMDL output captured for a download, for a bug report.
+///
+/// **Frontend caller:** `getDownloadLog(downloadId)` in `src/lib/tauri-commands.ts`
+///
+/// Reads `{app_data}/logs/downloads/{download_id}.log`, written while
+/// `save_download_logs` is on (every run of the job, retries included).
+/// The cookies file path is replaced with `<cookies file>`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for the log folder and the cookies path.
+/// * `download_id` - The unique ID of the download.
+///
+/// # Returns
+/// * `Ok(String)` - The redacted stdout/stderr of the job's runs.
+/// * `Err(String)` - "No log captured for this download..." when logging
+///   was off for its runs, or a read error.
+#[tauri::command]
+pub async fn get_download_log(app: AppHandle, download_id: String) -> Result<String, String> {
+    let settings = crate::services::config_service::load_settings(&app).unwrap_or_default();
+    download_log_service::read_log(
+        &download_log_service::log_path(&app, &download_id),
+        settings.cookies_path.as_deref(),
+    )
+}
+
+/// Opens a download's log file in the OS default text viewer.
+///
+/// **Frontend caller:** `openDownloadLog(downloadId)` in `src/lib/tauri-commands.ts`
+///
+/// The file is opened as written, so unlike `get_download_log` it shows
+/// the cookies path.
+///
+/// # Returns
+/// * `Ok(())` - The viewer was asked to open the file.
+/// * `Err(String)` - No log was captured for the download, or the file
+///   couldn't be opened.
+#[tauri::command]
+pub async fn open_download_log(app: AppHandle, download_id: String) -> Result<(), String> {
+    let path = download_log_service::log_path(&app, &download_id);
+    if !path.is_file() {
+        return Err(download_log_service::NO_LOG_CAPTURED.to_string());
+    }
+
+    #[allow(deprecated)]
+    app.shell()
+        .open(path.to_string_lossy().to_string(), None)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))
+}
+
+/// Returns the options a download runs with, to see why it used the codec
+/// or filenames it did.
+///
+/// **Frontend caller:** `getEffectiveOptions(downloadId)` in `src/lib/tauri-commands.ts`
+///
+/// Read-only. These are the item's merged options: the settings and
+/// per-download overrides, plus any codec fallback and the filename suffix
+/// it applied (see `DownloadQueue::effective_options()`). The cookies path,
+/// wrapper account URL, `.wvd` device and tool paths are replaced with
+/// `<redacted>`.
+///
+/// # Arguments
+/// * `queue` - Managed download queue state.
+/// * `download_id` - The unique ID of the download.
+///
+/// # Returns
+/// * `Ok(GamdlOptions)` - The redacted options.
+/// * `Err(String)` - No download with that ID is in the queue.
+#[tauri::command]
+pub async fn get_effective_options(
+    queue: State<'_, QueueHandle>,
+    download_id: String,
+) -> Result<GamdlOptions, String> {
+    queue
+        .lock()
+        .await
+        .effective_options(&download_id)
+        .ok_or_else(|| format!("Download {} not found", download_id))
+}
+
 /// Checks the latest GAMDL version available on PyPI.
 ///
 /// **Frontend caller:** `checkGamdlUpdate()` in `src/lib/tauri-commands.ts`
@@ -328,18 +1424,17 @@ pub async fn get_queue_status(
 /// version is available. Queries the PyPI JSON API at:
 ///   https://pypi.org/pypi/gamdl/json
 ///
-/// This command takes no parameters because it only needs network access.
-/// It does not require the `AppHandle` or `State` since it doesn't access
-/// any local state or managed resources.
+/// The `AppHandle` is only used to read the user's proxy settings.
 ///
 /// # Returns
 /// * `Ok(String)` - The latest version string (e.g., "2.8.4").
 /// * `Err(String)` - Network error or PyPI API parsing failure.
 #[tauri::command]
-pub async fn check_gamdl_update() -> Result<String, String> {
+pub async fn check_gamdl_update(app: AppHandle) -> Result<String, String> {
     // Delegates to the gamdl_service which handles the HTTP request and
     // JSON parsing of the PyPI API response.
-    crate::services::gamdl_service::check_latest_gamdl_version().await
+    let proxy = crate::services::config_service::load_proxy_config(&app);
+    crate::services::gamdl_service::check_latest_gamdl_version(&proxy).await
 }
 
 /// Exports the current download queue to a `.meedyadl` file.
@@ -372,7 +1467,7 @@ pub async fn export_queue(
 
     // Build the export file structure
     let export_file = download_queue::QueueExportFile {
-        version: 1,
+        version: download_queue::EXPORT_FORMAT_VERSION,
         app: "MeedyaDL".to_string(),
         exported_at: chrono::Utc::now().to_rfc3339(),
         items,
@@ -435,30 +1530,20 @@ pub async fn import_queue(
     let json = std::fs::read_to_string(path.as_path().unwrap())
         .map_err(|e| format!("Failed to read import file: {}", e))?;
 
-    let export_file: download_queue::QueueExportFile = serde_json::from_str(&json)
-        .map_err(|e| format!("Invalid queue file format: {}", e))?;
-
-    // Validate schema version
-    if export_file.version != 1 {
-        return Err(format!(
-            "Unsupported queue file version: {} (expected 1)",
-            export_file.version
-        ));
-    }
-
-    if export_file.items.is_empty() {
-        return Err("Queue file contains no items".to_string());
-    }
+    // Validate the whole file before enqueuing anything, so a single bad
+    // entry can't leave the queue half-imported
+    let export_file = download_queue::parse_export_file(&json)?;
 
     // Load current settings for option merging on the importing device
     let settings = crate::services::config_service::load_settings(&app)
         .unwrap_or_default();
 
-    // Import items into the queue
-    let count = {
+    // Import items into the queue. The count is of file entries, which
+    // differs from the number of queue jobs when batch mode combines them.
+    let count = export_file.items.len();
+    let job_count = {
         let mut q = queue.lock().await;
-        let ids = q.import_items(export_file.items, &settings);
-        ids.len()
+        q.import_items(export_file.items, &settings).len()
     };
 
     // Persist the updated queue
@@ -468,10 +1553,84 @@ pub async fn import_queue(
     // Notify the frontend that items were imported
     let _ = app.emit("queue-imported", count);
 
-    log::info!("Imported {} queue item(s) from file", count);
+    log::info!(
+        "Imported {} queue item(s) from file as {} job(s)",
+        count,
+        job_count
+    );
 
     // Start processing the imported items
     download_queue::process_queue(app, queue_handle).await;
 
     Ok(count)
 }
+
+/// Queues one download per row of a CSV file, each with its own codec,
+/// output path and label.
+///
+/// **Frontend caller:** `importCsv()` in `src/lib/tauri-commands.ts`
+///
+/// The file has the columns `url,codec,output_path,label` (header row
+/// optional; only `url` is required). A row with a broken URL, an unknown
+/// codec or a relative output path is left out and reported with its line
+/// number; the other rows are still queued. See
+/// `services::csv_import_service`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for settings, events and queue persistence.
+/// * `queue` - Managed download queue state (injected by Tauri).
+/// * `path` - Path of the CSV file.
+///
+/// # Returns
+/// * `Ok(CsvImportReport)` - `{ download_ids, errors }`; rows that failed
+///   are part of the report, not an `Err`.
+/// * `Err(String)` - The file couldn't be read, or its header names an
+///   unknown column.
+///
+/// # Events Emitted
+/// * `"download-queued"` - The download ID of each queued row.
+#[tauri::command]
+pub async fn import_csv(
+    app: AppHandle,
+    queue: State<'_, QueueHandle>,
+    path: String,
+) -> Result<CsvImportReport, String> {
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    let import = csv_import_service::parse_csv(&text)?;
+    for error in &import.errors {
+        log::warn!("Skipping line {} of {}: {}", error.line, path, error.message);
+    }
+
+    let settings = crate::services::config_service::load_settings(&app)
+        .unwrap_or_default();
+
+    let download_ids: Vec<String> = {
+        let mut q = queue.lock().await;
+        import
+            .requests
+            .into_iter()
+            .map(|(_, request)| q.enqueue(request, &settings))
+            .collect()
+    };
+    log::info!(
+        "Imported {} download(s) from {} ({} row(s) skipped)",
+        download_ids.len(),
+        path,
+        import.errors.len()
+    );
+
+    if !download_ids.is_empty() {
+        let queue_handle = queue.inner().clone();
+        download_queue::save_queue_to_disk(&app, &queue_handle).await;
+        for download_id in &download_ids {
+            let _ = app.emit("download-queued", download_id);
+        }
+        download_queue::process_queue(app, queue_handle).await;
+    }
+
+    Ok(CsvImportReport {
+        download_ids,
+        errors: import.errors,
+    })
+}
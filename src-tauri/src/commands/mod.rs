@@ -27,7 +27,7 @@
 //
 // Module map:
 //   commands/
-//   +-- system.rs       -- Platform info, app data directory path
+//   +-- system.rs       -- Platform info, app data directory path, environment report
 //   +-- dependencies.rs -- Check/install Python, GAMDL, and external tools
 //   +-- settings.rs     -- Read/write app settings, validate cookies file
 //   +-- gamdl.rs        -- Start/cancel/retry downloads, queue management
@@ -40,7 +40,8 @@
 /// System information commands (platform detection, directory paths).
 ///
 /// Provides `get_platform_info` and `get_app_data_dir` for the frontend
-/// to discover the current OS, architecture, and data directory at startup.
+/// to discover the current OS, architecture, and data directory at startup,
+/// and `get_environment_report` (tool paths and versions) for bug reports.
 pub mod system;
 
 /// Dependency management commands (Python, GAMDL, FFmpeg, mp4decrypt, etc.).
@@ -52,21 +53,32 @@ pub mod dependencies;
 
 /// Application settings commands (read, write, validate).
 ///
-/// Provides `get_settings`, `save_settings`, `validate_cookies_file`, and
-/// `get_default_output_path`. Delegates to `services::config_service`.
+/// Provides `get_settings`, `save_settings`, `validate_cookies_file`,
+/// `get_default_output_path`, `validate_template`, `export_settings` and
+/// `import_settings`. Delegates to `services::config_service` and
+/// `services::settings_transfer_service`.
 pub mod settings;
 
 /// GAMDL download execution commands (start, cancel, retry, queue status).
 ///
 /// Provides `start_download`, `cancel_download`, `retry_download`,
-/// `clear_queue`, `get_queue_status`, and `check_gamdl_update`. Delegates
-/// to `services::download_queue` and `services::gamdl_service`.
+/// `resume_all_cancelled`, `retry_failed_tracks`, `clear_queue`,
+/// `clear_completed`, `clear_failed`, `reconcile_queue`,
+/// `get_queue_status`, `get_queue_status_filtered`, `get_batch_report`,
+/// `set_download_label`, `keep_download`, `set_download_output_path`,
+/// `merge_downloads`,
+/// `get_speed_samples`, `estimate_size`, `preflight`, `run_test_download`,
+/// `download_companions_only`, `get_download_log`, `open_download_log`,
+/// `get_effective_options`, `import_csv`, and `check_gamdl_update`. Delegates to `services::download_queue` and
+/// `services::gamdl_service`.
 pub mod gamdl;
 
 /// Secure credential storage commands (store, retrieve, delete).
 ///
 /// Uses the OS keychain (macOS Keychain, Windows Credential Vault, Linux
 /// Secret Service) to store sensitive values like API tokens or cookies.
+/// `get_headless_api_token` returns (creating on first use) the token for
+/// `services::headless_api`.
 pub mod credentials;
 
 /// Update checking commands (check versions, upgrade GAMDL).
@@ -77,8 +89,8 @@ pub mod updates;
 
 /// Cookie management commands (browser detection, auto-import, FDA check).
 ///
-/// Provides `detect_browsers`, `import_cookies_from_browser`, and
-/// `check_full_disk_access`. Delegates to `services::cookie_service`.
+/// Provides `detect_browsers`, `import_cookies_from_browser`,
+/// `check_full_disk_access`, and `test_cookies_live`. Delegates to `services::cookie_service`.
 pub mod cookies;
 
 /// Embedded Apple Music login window commands (open, extract, close).
@@ -90,6 +102,8 @@ pub mod login_window;
 /// Animated artwork download commands (manual trigger for album artwork).
 ///
 /// Provides `download_animated_artwork` for explicitly fetching animated
-/// cover art from Apple Music for a specific album. Delegates to
+/// cover art from Apple Music for a specific album, and
+/// `recheck_album_artwork` for adding missing variants to an album
+/// downloaded earlier. Delegates to
 /// `services::animated_artwork_service` for the actual API query and download.
 pub mod artwork;
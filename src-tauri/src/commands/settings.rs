@@ -27,6 +27,9 @@
 // | save_settings           | saveSettings(settings)     | ~80  |
 // | validate_cookies_file   | validateCookiesFile(path)  | ~85  |
 // | get_default_output_path | getDefaultOutputPath()     | ~90  |
+// | validate_template       | validateTemplate(template) | ~305 |
+// | export_settings         | exportSettings(path)       | ~360 |
+// | import_settings         | importSettings(path, ...)  | ~380 |
 //
 // ## References
 //
@@ -42,9 +45,24 @@ use tauri::AppHandle;
 // It implements both Serialize (for returning to frontend) and Deserialize
 // (for accepting from frontend when saving).
 use crate::models::settings::AppSettings;
+// TemplateCheck / check_template(): template syntax, placeholder and
+// file name character checks for validate_template() and save_settings().
+use crate::models::gamdl_options::{
+    check_template, validate_date_tag_template, validate_uploaded_video_quality, TemplateCheck,
+};
+// MusicServiceId selects the per-service subfolder for get_default_output_path().
+use crate::models::music_service::MusicServiceId;
 // config_service handles the actual file I/O: reading/writing settings.json
 // and syncing to GAMDL's config.ini file.
 use crate::services::config_service;
+// download_schedule: parse_clock_time() for the schedule fields in save_settings().
+use crate::services::download_schedule;
+// settings_transfer_service: export file content and import merging for
+// export_settings() / import_settings().
+use crate::services::settings_transfer_service::{self, SettingsImportReport};
+// platform: validate_niceness() for process_niceness in save_settings().
+// proxy: validate_proxy_url() for the proxy fields in save_settings().
+use crate::utils::{platform, proxy};
 
 /// Result of validating a Netscape-format cookies file.
 ///
@@ -115,17 +133,212 @@ pub async fn get_settings(app: AppHandle) -> Result<AppSettings, String> {
 ///   Deserialized from the JSON payload sent by `invoke("save_settings", { settings })`.
 ///   See: https://v2.tauri.app/develop/calling-rust/#command-arguments
 ///
+/// Proxy URLs (`http_proxy`, `https_proxy`) are validated before anything
+/// is written; an invalid URL rejects the whole save. The same applies to
+/// the download window times (`schedule_start`, `schedule_end`) while
+/// scheduling is enabled, to a negative `min_free_space_gb`, to a
+/// `max_background_tasks` of zero, to a `process_niceness` outside
+/// `-20..=19`, to a folder/file template that `validate_template` would flag, to a
+/// `date_tag_template` that isn't a plausible strftime pattern, to an
+/// `uploaded_video_quality` other than "best", and to a
+/// `transcode_companion` bitrate the MP3/Opus encoder doesn't accept. The
+/// checks live in `validate_settings()`.
+///
 /// # Returns
 /// * `Ok(())` - Settings saved and synced successfully.
-/// * `Err(String)` - File write or serialization error.
+/// * `Err(String)` - Invalid proxy URL, schedule time, free space
+///   threshold, background task limit, process niceness, template, date tag template, uploaded
+///   video quality or transcode bitrate, file write, or serialization error.
 #[tauri::command]
 pub async fn save_settings(app: AppHandle, settings: AppSettings) -> Result<(), String> {
+    validate_settings(&settings)?;
+
     // save_settings() in config_service performs two writes:
     //   1. settings.json — full AppSettings struct as JSON
     //   2. config.ini — relevant fields translated to GAMDL's INI format
     config_service::save_settings(&app, &settings)
 }
 
+/// Checks the settings `save_settings` would write, returning the first
+/// problem found. See `save_settings` for the list of checks.
+pub fn validate_settings(settings: &AppSettings) -> Result<(), String> {
+    // Reject malformed proxy URLs up front so GAMDL and the HTTP client never
+    // receive a value they can't use. Blank values mean "no proxy".
+    for (label, value) in [
+        ("HTTP proxy", &settings.http_proxy),
+        ("HTTPS proxy", &settings.https_proxy),
+    ] {
+        if let Some(url) = value.as_deref().filter(|v| !v.trim().is_empty()) {
+            proxy::validate_proxy_url(url).map_err(|e| format!("Invalid {}: {}", label, e))?;
+        }
+    }
+
+    // The download window must parse as HH:MM, otherwise the scheduler
+    // would silently ignore it.
+    if settings.schedule_enabled {
+        for (label, value) in [
+            ("schedule start", &settings.schedule_start),
+            ("schedule end", &settings.schedule_end),
+        ] {
+            download_schedule::parse_clock_time(value)
+                .map_err(|e| format!("Invalid {}: {}", label, e))?;
+        }
+    }
+
+    if let Some(gb) = settings.min_free_space_gb {
+        if !gb.is_finite() || gb < 0.0 {
+            return Err(format!(
+                "Invalid minimum free space: {} (expected a positive number of GB)",
+                gb
+            ));
+        }
+    }
+
+    if let Some(niceness) = settings.process_niceness {
+        platform::validate_niceness(niceness)
+            .map_err(|e| format!("Invalid process niceness: {}", e))?;
+    }
+
+    if settings.max_background_tasks == 0 {
+        return Err("Invalid background task limit: 0 (expected at least 1)".to_string());
+    }
+
+    // A typo such as {albumartist} would otherwise end up as a literal
+    // folder name, and a ':' in a Windows folder name fails every download.
+    for (label, template) in [
+        ("album folder template", &settings.album_folder_template),
+        ("compilation folder template", &settings.compilation_folder_template),
+        ("no album folder template", &settings.no_album_folder_template),
+        ("single disc file template", &settings.single_disc_file_template),
+        ("multi disc file template", &settings.multi_disc_file_template),
+        ("no album file template", &settings.no_album_file_template),
+        ("playlist file template", &settings.playlist_file_template),
+    ] {
+        if let Some(problem) = check_template(template, cfg!(target_os = "windows")).problem {
+            return Err(format!("Invalid {}: {}", label, problem));
+        }
+    }
+    if let Some(template) = settings
+        .singles_folder_template
+        .as_deref()
+        .filter(|t| !t.trim().is_empty())
+    {
+        if let Some(problem) = check_template(template, cfg!(target_os = "windows")).problem {
+            return Err(format!("Invalid singles folder template: {}", problem));
+        }
+    }
+    for (codec, subfolder) in settings
+        .companion_subfolders
+        .iter()
+        .filter(|(_, s)| !s.trim().is_empty())
+    {
+        if let Some(problem) = check_template(subfolder, cfg!(target_os = "windows")).problem {
+            return Err(format!("Invalid {} companion subfolder: {}", codec, problem));
+        }
+    }
+
+    // GAMDL would fail every track's tagging on a bad strftime directive.
+    if let Some(template) = settings.date_tag_template.as_deref().filter(|t| !t.trim().is_empty()) {
+        validate_date_tag_template(template)
+            .map_err(|e| format!("Invalid date tag template: {}", e))?;
+    }
+
+    // "best" is the only value that works without a terminal: anything
+    // else fails every uploaded video download, and "ask" waits for an
+    // answer on stdin that never comes.
+    if let Some(quality) = settings.uploaded_video_quality.as_deref() {
+        validate_uploaded_video_quality(quality)
+            .map_err(|e| format!("Invalid uploaded video quality: {}", e))?;
+    }
+
+    // FFmpeg would fail every transcode on a bitrate the encoder rejects.
+    if let Some(config) = settings.transcode_companion {
+        let range = config.format.bitrate_range();
+        if !range.contains(&config.bitrate_kbps) {
+            return Err(format!(
+                "Invalid transcode bitrate: {} kbps (expected {}-{} kbps for {})",
+                config.bitrate_kbps,
+                range.start(),
+                range.end(),
+                config.format.extension()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes the current settings to a file for sharing or backup, without
+/// the secret fields.
+///
+/// **Frontend caller:** `exportSettings(path)` in `src/lib/tauri-commands.ts`
+///
+/// The cookies file path, MusicKit IDs, custom tool paths and proxy URLs
+/// are left out. See `services::settings_transfer_service`.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for loading the settings.
+/// * `path` - Path of the JSON file to write.
+///
+/// # Returns
+/// * `Ok(())` - The file was written.
+/// * `Err(String)` - Settings load, serialization or file write error.
+#[tauri::command]
+pub async fn export_settings(app: AppHandle, path: String) -> Result<(), String> {
+    let settings = config_service::load_settings(&app)?;
+    let json = settings_transfer_service::export_json(&settings, &chrono::Utc::now().to_rfc3339())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write settings file: {}", e))?;
+    log::info!("Exported settings to {}", path);
+    Ok(())
+}
+
+/// Merges the settings of an exported file over the current settings and
+/// saves the result.
+///
+/// **Frontend caller:** `importSettings(path, includePaths)` in `src/lib/tauri-commands.ts`
+///
+/// Fields the file doesn't have keep their current values, and so do the
+/// output path, cookies file path and custom tool paths unless
+/// `include_paths` is set. The merged settings go through the same checks
+/// as `save_settings` (`validate_settings()`), so a bad template or codec
+/// in the file changes nothing.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle for loading and saving the settings.
+/// * `path` - Path of a file written by `export_settings`.
+/// * `include_paths` - Whether the file's machine-specific paths are
+///   applied too. Default: `false`.
+///
+/// # Returns
+/// * `Ok(SettingsImportReport)` - The fields that changed, and the paths
+///   that were left alone.
+/// * `Err(String)` - The file couldn't be read, isn't a settings export, or
+///   fails a settings check; nothing is saved then.
+#[tauri::command]
+pub async fn import_settings(
+    app: AppHandle,
+    path: String,
+    include_paths: Option<bool>,
+) -> Result<SettingsImportReport, String> {
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    let current = config_service::load_settings(&app)?;
+    let import =
+        settings_transfer_service::merge_import(&current, &json, include_paths.unwrap_or(false))?;
+    validate_settings(&import.settings)?;
+    config_service::save_settings(&app, &import.settings)?;
+
+    log::info!(
+        "Imported settings from {} ({} field(s) changed)",
+        path,
+        import.changed.len()
+    );
+    Ok(SettingsImportReport {
+        changed: import.changed,
+        kept_paths: import.kept_paths,
+    })
+}
+
 /// Validates a Netscape-format cookies file.
 ///
 /// **Frontend caller:** `validateCookiesFile(path)` in `src/lib/tauri-commands.ts`
@@ -247,21 +460,138 @@ pub async fn validate_cookies_file(path: String) -> Result<CookieValidation, Str
 ///
 /// **Frontend caller:** `getDefaultOutputPath()` in `src/lib/tauri-commands.ts`
 ///
-/// Uses the platform-appropriate music directory as the base, with an
-/// "Apple Music" subdirectory:
-/// - macOS: `~/Music/Apple Music/`
+/// Uses the platform-appropriate music directory as the base, with a
+/// per-service subdirectory so downloads from different backends stay
+/// segregated:
+/// - macOS: `~/Music/Apple Music/`, `~/Music/YouTube Music/`, ...
 /// - Windows: `~\Music\Apple Music\`
-/// - Linux: `~/Music/Apple Music/`
+/// - Linux: `$XDG_MUSIC_DIR/Apple Music/` (from `user-dirs.dirs`),
+///   falling back to `~/Music/Apple Music/`
+///
+/// The folder is created if it doesn't exist yet.
 ///
 /// This is a synchronous command (no `async`) because it only resolves
-/// paths using environment variables — no I/O or network access needed.
+/// paths and creates at most one directory — no network access needed.
 /// Note: Tauri allows both sync and async command handlers.
 /// See: https://v2.tauri.app/develop/calling-rust/#async-commands
 ///
+/// # Arguments
+/// * `service` - Optional service ID (e.g., `"YouTubeMusic"`). Defaults to
+///   Apple Music when omitted.
+///
 /// # Returns
 /// * `Ok(String)` - The absolute path to the default music output directory.
-/// * `Err(String)` - If the user's home/music directory cannot be determined.
+/// * `Err(String)` - If the user's home/music directory cannot be determined
+///   or the folder cannot be created.
+#[tauri::command]
+pub fn get_default_output_path(service: Option<MusicServiceId>) -> Result<String, String> {
+    config_service::get_default_output_path(service)
+}
+
+/// Checks a folder/file naming template as the user types it.
+///
+/// **Frontend caller:** `validateTemplate(template)` in `src/lib/tauri-commands.ts`
+///
+/// Parses the `{placeholder}` tokens against GAMDL's placeholder set and
+/// looks for characters the file system doesn't allow in the literal text
+/// (Windows rules on Windows). A typo such as `{albumartist}` would
+/// otherwise produce a folder literally named `{albumartist}`.
+///
+/// `save_settings` runs the same check on every template field.
+///
+/// # Arguments
+/// * `template` - The template text, e.g. `"{album_artist}/{album}"`.
+///
+/// # Returns
+/// * `Ok(TemplateCheck)` - Validity, unknown placeholders, illegal
+///   characters and the normalized template.
 #[tauri::command]
-pub fn get_default_output_path() -> Result<String, String> {
-    config_service::get_default_output_path()
+pub fn validate_template(template: String) -> Result<TemplateCheck, String> {
+    Ok(check_template(&template, cfg!(target_os = "windows")))
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies that the default settings pass the save-time checks.
+    #[test]
+    fn default_settings_are_valid() {
+        assert!(validate_settings(&AppSettings::default()).is_ok());
+    }
+
+    /// Verifies that "best" is accepted as the uploaded video quality and
+    /// any other value, including GAMDL's interactive "ask", rejects the
+    /// save.
+    #[test]
+    fn validate_settings_checks_uploaded_video_quality() {
+        let mut settings = AppSettings::default();
+        settings.uploaded_video_quality = Some("best".to_string());
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.uploaded_video_quality = Some("ask".to_string());
+        assert!(validate_settings(&settings).is_err());
+
+        settings.uploaded_video_quality = Some("4k".to_string());
+        let err = validate_settings(&settings).unwrap_err();
+        assert!(err.contains("uploaded video quality"), "{}", err);
+    }
+
+    /// Verifies that the transcode bitrate is checked against the range of
+    /// the chosen format.
+    #[test]
+    fn validate_settings_checks_transcode_bitrate() {
+        use crate::models::settings::{TranscodeConfig, TranscodeFormat};
+
+        let mut settings = AppSettings::default();
+        settings.transcode_companion = Some(TranscodeConfig {
+            format: TranscodeFormat::Opus,
+            bitrate_kbps: 500,
+        });
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.transcode_companion = Some(TranscodeConfig {
+            format: TranscodeFormat::Mp3,
+            bitrate_kbps: 500,
+        });
+        let err = validate_settings(&settings).unwrap_err();
+        assert!(err.contains("transcode bitrate"), "{}", err);
+    }
+
+    /// Verifies that a background task limit of zero, which would stall
+    /// every artwork and companion task, rejects the save.
+    #[test]
+    fn validate_settings_rejects_zero_background_tasks() {
+        let mut settings = AppSettings::default();
+        settings.max_background_tasks = 0;
+        let err = validate_settings(&settings).unwrap_err();
+        assert!(err.contains("background task limit"), "{}", err);
+    }
+
+    /// Verifies that an imported template goes through the save-time
+    /// checks like one typed in the settings.
+    #[test]
+    fn imported_settings_are_validated() {
+        let json = r#"{"version": 1, "settings": {"album_folder_template": "{albumartist}"}}"#;
+        let import =
+            settings_transfer_service::merge_import(&AppSettings::default(), json, false).unwrap();
+        let err = validate_settings(&import.settings).unwrap_err();
+        assert!(err.contains("album folder template"), "{}", err);
+    }
+
+    /// Verifies that a niceness outside -20..=19 rejects the save.
+    #[test]
+    fn validate_settings_checks_process_niceness() {
+        let mut settings = AppSettings::default();
+        settings.process_niceness = Some(10);
+        assert!(validate_settings(&settings).is_ok());
+
+        settings.process_niceness = Some(25);
+        let err = validate_settings(&settings).unwrap_err();
+        assert!(err.contains("process niceness"), "{}", err);
+    }
 }
@@ -14,13 +14,16 @@
 //   2. `getAppDataDir()` -> used for displaying paths in the settings page
 //
 // Both commands are lightweight (no I/O, no network) and return immediately.
+// `get_environment_report` is the exception: it runs every tool's version
+// command, so it is only called on demand (e.g., when filing a bug report).
 //
 // ## Frontend Mapping (src/lib/tauri-commands.ts)
 //
-// | Rust Command       | TypeScript Function  | Line |
-// |--------------------|----------------------|------|
-// | get_platform_info  | getPlatformInfo()    | ~27  |
-// | get_app_data_dir   | getAppDataDir()      | ~32  |
+// | Rust Command           | TypeScript Function     | Line |
+// |------------------------|-------------------------|------|
+// | get_platform_info      | getPlatformInfo()       | ~27  |
+// | get_app_data_dir       | getAppDataDir()         | ~32  |
+// | get_environment_report | getEnvironmentReport()  | ~37  |
 //
 // ## References
 //
@@ -33,6 +36,8 @@ use serde::Serialize;
 // application data directory via Tauri's path resolver.
 use tauri::AppHandle;
 
+// Environment report (tool paths and versions) for get_environment_report().
+use crate::services::{config_service, environment_service};
 // Platform utilities module providing app data directory resolution
 // and other platform-specific path helpers.
 use crate::utils::platform;
@@ -139,3 +144,29 @@ pub fn get_app_data_dir(app: AppHandle) -> Result<String, String> {
         .map(|s| s.to_string())
         .ok_or_else(|| "Failed to convert app data path to string".to_string())
 }
+
+/// Returns the environment report for bug reports: the resolved path and
+/// detected version of Python, GAMDL, FFmpeg, mp4decrypt, N_m3u8DL-RE and
+/// MP4Box, the app data directory and its free disk space.
+///
+/// **Frontend caller:** `getEnvironmentReport()` in `src/lib/tauri-commands.ts`
+///
+/// Unlike `get_platform_info`, this runs each tool's version command
+/// (each cut off after `environment_service::VERSION_TIMEOUT`), so it can
+/// take a few seconds. Only the cookies are redacted: the report says
+/// whether a cookies file is configured, not where.
+///
+/// # Arguments
+/// * `app` - Tauri AppHandle, for the settings and tool paths.
+///
+/// # Returns
+/// * `Ok(EnvironmentReport)` - The report; tools that are missing or whose
+///   version command failed carry an `error` instead of a `version`.
+/// * `Err(String)` - If the settings could not be loaded.
+#[tauri::command]
+pub async fn get_environment_report(
+    app: AppHandle,
+) -> Result<environment_service::EnvironmentReport, String> {
+    let settings = config_service::load_settings(&app)?;
+    Ok(environment_service::collect_report(&app, &settings).await)
+}
@@ -73,9 +73,11 @@ pub mod utils;
 ///    - `.plugin()` -- register Tauri plugins that bridge native OS APIs.
 ///    - `.invoke_handler()` -- register `#[tauri::command]` functions for IPC.
 ///    - `.setup()` -- run one-time initialisation after the webview is ready.
-/// 3. `.run(tauri::generate_context!())` starts the event loop. The macro
-///    reads `tauri.conf.json` at **compile time** to embed window config,
-///    bundle identifiers, and other metadata into the binary.
+/// 3. `.build(tauri::generate_context!())` creates the app and `.run()`
+///    starts the event loop, routing exit requests through
+///    `services::shutdown`. The macro reads `tauri.conf.json` at
+///    **compile time** to embed window config, bundle identifiers, and
+///    other metadata into the binary.
 ///
 /// # Panics
 /// Panics with a descriptive message if the Tauri event loop fails to start
@@ -186,6 +188,7 @@ pub fn run() {
             // System information and platform detection commands
             commands::system::get_platform_info,
             commands::system::get_app_data_dir,
+            commands::system::get_environment_report,
             // Dependency management commands (Python, GAMDL, tools)
             commands::dependencies::check_python_status,
             commands::dependencies::install_python,
@@ -198,20 +201,50 @@ pub fn run() {
             commands::settings::save_settings,
             commands::settings::validate_cookies_file,
             commands::settings::get_default_output_path,
+            commands::settings::validate_template,
+            commands::settings::export_settings,
+            commands::settings::import_settings,
             // GAMDL download and queue management commands
             commands::gamdl::start_download,
             commands::gamdl::cancel_download,
             commands::gamdl::retry_download,
+            commands::gamdl::resume_all_cancelled,
+            commands::gamdl::retry_failed_tracks,
+            commands::gamdl::change_download_codec,
+            commands::gamdl::set_session_codec_override,
+            commands::gamdl::get_session_codec_override,
             commands::gamdl::clear_queue,
+            commands::gamdl::clear_completed,
+            commands::gamdl::clear_failed,
+            commands::gamdl::reconcile_queue,
+            commands::gamdl::get_speed_samples,
+            commands::gamdl::estimate_size,
+            commands::gamdl::preflight,
+            commands::gamdl::run_test_download,
             commands::gamdl::get_queue_status,
+            commands::gamdl::get_queue_status_filtered,
+            commands::gamdl::get_batch_report,
+            commands::gamdl::set_download_label,
+            commands::gamdl::keep_download,
+            commands::gamdl::set_download_output_path,
+            commands::gamdl::merge_downloads,
+            commands::gamdl::reveal_output,
+            commands::gamdl::get_download_log,
+            commands::gamdl::open_download_log,
+            commands::gamdl::get_effective_options,
             commands::gamdl::check_gamdl_update,
             // Queue export/import commands
             commands::gamdl::export_queue,
             commands::gamdl::import_queue,
+            commands::gamdl::import_csv,
+            commands::gamdl::add_codec_version,
+            commands::gamdl::download_companions_only,
             // Credential storage commands
             commands::credentials::store_credential,
             commands::credentials::get_credential,
             commands::credentials::delete_credential,
+            commands::credentials::verify_musickit_credentials,
+            commands::credentials::get_headless_api_token,
             // Update checking commands
             commands::updates::check_all_updates,
             commands::updates::upgrade_gamdl,
@@ -220,12 +253,14 @@ pub fn run() {
             commands::cookies::detect_browsers,
             commands::cookies::import_cookies_from_browser,
             commands::cookies::check_full_disk_access,
+            commands::cookies::test_cookies_live,
             // Embedded Apple Music login window commands
             commands::login_window::open_apple_login,
             commands::login_window::extract_login_cookies,
             commands::login_window::close_apple_login,
             // Animated artwork download command
             commands::artwork::download_animated_artwork,
+            commands::artwork::recheck_album_artwork,
         ])
 
         // ---------------------------------------------------------------
@@ -304,8 +339,11 @@ pub fn run() {
             let separator1 = PredefinedMenuItem::separator(app)?;
 
             // "Downloads: None" — disabled info item that displays current download status.
-            // The frontend can update this text via the tray menu API as downloads progress.
-            let downloads_item = MenuItemBuilder::with_id("downloads_status", "Downloads: None")
+            // Kept up to date by `services::tray_status` once the tray is built (below).
+            let downloads_item = MenuItemBuilder::with_id(
+                "downloads_status",
+                services::tray_status::IDLE_LABEL,
+            )
                 .enabled(false)
                 .build(app)?;
 
@@ -360,7 +398,9 @@ pub fn run() {
                                 let _ = window.emit("tray-check-updates", ());
                             }
                         }
-                        // Cleanly exit the application
+                        // Cleanly exit the application. The exit request goes
+                        // through services::shutdown (see `.run()` below),
+                        // which saves the queue and stops GAMDL first.
                         "quit" => {
                             app.exit(0);
                         }
@@ -394,6 +434,60 @@ pub fn run() {
 
             log::info!("System tray icon initialized");
 
+            // Keep the "Downloads" status item in sync with the queue. The
+            // updater task owns a clone of the item handle, so the label stays
+            // updatable after `.setup()` returns.
+            {
+                let queue_handle: tauri::State<'_, services::download_queue::QueueHandle> =
+                    app.state();
+                services::tray_status::spawn_tray_status_updater(
+                    queue_handle.inner().clone(),
+                    downloads_item.clone(),
+                );
+            }
+
+            // Start the quiet-hours ticker. It is a no-op until the user
+            // enables a download window in Settings.
+            {
+                let queue_handle: tauri::State<'_, services::download_queue::QueueHandle> =
+                    app.state();
+                services::download_schedule::spawn_schedule_ticker(
+                    app.handle().clone(),
+                    queue_handle.inner().clone(),
+                );
+            }
+
+            // Recover the queue if a download task dies without releasing
+            // its slot (see DownloadQueue::reconcile()).
+            {
+                let queue_handle: tauri::State<'_, services::download_queue::QueueHandle> =
+                    app.state();
+                services::download_queue::spawn_queue_watchdog(
+                    app.handle().clone(),
+                    queue_handle.inner().clone(),
+                );
+            }
+
+            // Route Ctrl+C and SIGTERM through the graceful shutdown
+            // instead of letting them kill the process mid-download.
+            services::shutdown::spawn_signal_listener(app.handle().clone());
+
+            // Warn (via `gamdl-flag-mismatch`) when the installed GAMDL no
+            // longer accepts flags MeedyaDL sends. Runs in the background
+            // after a short delay; the parsed help is cached per version.
+            services::update_checker::spawn_gamdl_flag_check(app.handle().clone());
+
+            // Start the localhost control API for scripts, if enabled in
+            // Settings > Advanced. Requests share the GUI's queue.
+            {
+                let queue_handle: tauri::State<'_, services::download_queue::QueueHandle> =
+                    app.state();
+                services::headless_api::spawn_server(
+                    app.handle().clone(),
+                    queue_handle.inner().clone(),
+                );
+            }
+
             // -------------------------------------------------------
             // Queue Persistence: Restore on Startup
             // -------------------------------------------------------
@@ -406,9 +500,18 @@ pub fn run() {
             // This provides crash recovery: if the app closes (or crashes)
             // while downloads are queued/active, those items are restored
             // and automatically resumed on next launch.
+            //
+            // Per-download logs of items that weren't restored (finished
+            // before the restart) can no longer be opened, so they go now.
             {
                 let app_handle = app.handle().clone();
                 let persisted_items = services::download_queue::load_queue_from_disk(&app_handle);
+                let queued_ids: Vec<String> =
+                    persisted_items.iter().map(|item| item.id.clone()).collect();
+                services::download_log_service::prune_logs(
+                    &services::download_log_service::log_dir(&app_handle),
+                    &queued_ids,
+                );
                 if !persisted_items.is_empty() {
                     let count = persisted_items.len();
                     let settings = services::config_service::load_settings(&app_handle)
@@ -454,17 +557,27 @@ pub fn run() {
         // ---------------------------------------------------------------
         // Start the Tauri event loop
         // ---------------------------------------------------------------
-        // `.run()` consumes the builder and enters the platform's native
-        // event loop (NSApplication on macOS, Win32 message loop on Windows,
-        // GTK main loop on Linux). This call **blocks** until the application
-        // exits (via `app.exit()`, window close, or OS termination).
+        // `.build()` consumes the builder and creates the app;
+        // `App::run()` then enters the platform's native event loop
+        // (NSApplication on macOS, Win32 message loop on Windows, GTK main
+        // loop on Linux). This call **blocks** until the application exits
+        // (via `app.exit()`, window close, or OS termination).
         //
         // `tauri::generate_context!()` is a compile-time macro that reads
         // `tauri.conf.json` and embeds configuration (window settings,
         // bundle identifier, icons, permissions) into the binary.
         //
-        // Reference: https://docs.rs/tauri/latest/tauri/struct.Builder.html#method.run
+        // Exit requests are held back by `services::shutdown` until
+        // running downloads are stopped and the queue is saved.
+        //
+        // Reference: https://docs.rs/tauri/latest/tauri/struct.Builder.html#method.build
+        // Reference: https://docs.rs/tauri/latest/tauri/struct.App.html#method.run
         // Reference: https://docs.rs/tauri/latest/tauri/macro.generate_context.html
-        .run(tauri::generate_context!())
-        .expect("Failed to start MeedyaDL application");
+        .build(tauri::generate_context!())
+        .expect("Failed to start MeedyaDL application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { api, .. } = event {
+                services::shutdown::on_exit_requested(app, &api);
+            }
+        });
 }
@@ -46,7 +46,7 @@
 
 use serde::{Deserialize, Serialize};
 
-use super::gamdl_options::GamdlOptions;
+use super::gamdl_options::{GamdlOptions, SongCodec};
 
 /// A download request submitted by the user from the React frontend.
 ///
@@ -82,6 +82,55 @@ pub struct DownloadRequest {
     /// See `GamdlOptions` in `gamdl_options.rs` for why all fields are
     /// `Option<T>` and how the merge works.
     pub options: Option<GamdlOptions>,
+
+    /// Optional user-supplied note (e.g., "for the road trip playlist").
+    /// Purely metadata -- it never reaches the GAMDL command line. Copied
+    /// to `QueueItemStatus::label` on enqueue and editable afterwards via
+    /// the `set_download_label` command. `#[serde(default)]` keeps older
+    /// `queue.json` files and frontend requests without it loading.
+    #[serde(default)]
+    pub label: Option<String>,
+
+    /// Queue priority: higher starts first, equal priorities in the order
+    /// they were added. `0` (the default, and what older frontends and
+    /// `queue.json` files get) is normal priority. With
+    /// `AppSettings::preempt_lower_priority` on, a waiting item can also
+    /// stop a running lower-priority one; see
+    /// `DownloadQueue::preempt_for_priority()`.
+    #[serde(default)]
+    pub priority: u8,
+
+    /// Codecs to fall back through for this download instead of
+    /// `AppSettings::music_fallback_chain`, e.g. `[Atmos, Aac]` to only try
+    /// Dolby Atmos and then AAC. The download starts with the first one
+    /// unless `options` picks a codec. `None` (the default, and what older
+    /// frontends and `queue.json` files get) uses the global chain.
+    #[serde(default)]
+    pub fallback_chain_override: Option<Vec<SongCodec>>,
+
+    /// What the user queued: an album, single tracks, a playlist. Recorded
+    /// by `DownloadQueue::enqueue()`, so a download whose URLs are later
+    /// rewritten (an album narrowed to some of its song URLs) keeps the
+    /// folder templates of its kind. `None` (older frontends and
+    /// `queue.json` files) classifies the URLs; see
+    /// `download_queue::request_kind()`.
+    #[serde(default)]
+    pub kind: Option<DownloadKind>,
+}
+
+/// What the URLs of a download point at, for options that only apply to
+/// one kind of download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DownloadKind {
+    /// Single tracks (`/song/` links or album links with `?i=`)
+    Single,
+    /// Whole albums
+    Album,
+    /// Catalog playlists
+    Playlist,
+    /// Anything else (music videos, artists, no URLs) or a mix of kinds
+    Other,
 }
 
 /// The possible states of a download queue item.
@@ -180,6 +229,13 @@ pub struct QueueItemStatus {
     /// Parsed from GAMDL's stdout progress lines.
     pub current_track: Option<String>,
 
+    /// What GAMDL is doing besides downloading a stream, e.g.
+    /// `"Fetching decryption keys"` or a processing step such as
+    /// `"Remuxing to M4A"`, so the UI can show where a slow download is.
+    /// `None` while a stream downloads and before the run starts.
+    #[serde(default)]
+    pub current_step: Option<String>,
+
     /// Total number of tracks to download. `Some(n)` for albums/playlists,
     /// `None` for single-track downloads. Used by the frontend to render
     /// "Track 3 of 12" style progress indicators.
@@ -222,10 +278,131 @@ pub struct QueueItemStatus {
     /// The frontend uses this to show a warning indicator.
     pub fallback_occurred: bool,
 
+    /// Why the last codec fallback happened, e.g. "Lossless (ALAC) isn't
+    /// available for this release". Set together with `fallback_occurred`
+    /// so the UI can explain the quality change; `None` without a fallback.
+    #[serde(default)]
+    pub fallback_reason: Option<String>,
+
+    /// The codec each saved track actually got, as `(track, codec)` pairs in
+    /// the order they were saved (e.g., `("Artist - Title", "alac")`). On
+    /// mixed albums a fallback only re-downloads the tracks the first codec
+    /// missed, so the entries can differ; the UI summarises them as
+    /// "10 ALAC, 2 AAC".
+    #[serde(default)]
+    pub track_codecs: Vec<(String, String)>,
+
+    /// Tracks left out of the download before GAMDL ran, e.g. playlist
+    /// tracks the catalog reports as no longer available (see
+    /// `AppSettings::skip_unavailable_tracks`). Empty when nothing was
+    /// skipped.
+    #[serde(default)]
+    pub skipped_tracks: Vec<SkippedTrack>,
+
+    /// Tracks saved without cover art because fetching it failed and the
+    /// download ran again without covers (see
+    /// `AppSettings::continue_on_cover_failure`). 0 when every track got
+    /// its cover.
+    #[serde(default)]
+    pub cover_missing_tracks: u32,
+
+    /// Where each URL of a multi-URL download stands, in URL order, so the
+    /// UI can show "Album 2 of 5". Filled in once GAMDL reports its first
+    /// URL (see `process::GamdlOutputEvent::UrlStart`); empty for
+    /// single-URL downloads and before the run starts.
+    #[serde(default)]
+    pub url_progress: Vec<UrlProgress>,
+
     /// ISO 8601 timestamp (`YYYY-MM-DDTHH:MM:SS.sssZ`) when this item
     /// was added to the queue. Used for sorting the queue display and
     /// for calculating elapsed time.
     pub created_at: String,
+
+    /// The user's note for this download, mirrored from
+    /// `DownloadRequest::label`. `None` when no label was set.
+    /// Matched by the `get_queue_status_filtered` search.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// A codec fallback that was just applied to a download.
+///
+/// Payload of the `fallback-applied` event, emitted when the queue moves a
+/// failed download on to the next codec of the fallback chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FallbackApplied {
+    /// The download's queue ID
+    pub download_id: String,
+    /// The codec given up (GAMDL CLI name, e.g. "alac")
+    pub from_codec: String,
+    /// The codec tried next (e.g. "aac")
+    pub to_codec: String,
+    /// Human-readable reason, from the classified error
+    /// (see `DownloadError::fallback_reason()`)
+    pub reason: String,
+}
+
+/// Two unfinished downloads that save into the same album folder.
+///
+/// Payload of the `output-collision` event, emitted by `start_download`
+/// when a new item would race an existing one on the same files (see
+/// `DownloadQueue::find_output_collision()`). The frontend can offer
+/// `merge_downloads` in response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OutputCollision {
+    /// The download just queued
+    pub download_id: String,
+    /// The unfinished download it collides with
+    pub existing_id: String,
+}
+
+/// A track left out of a download up front.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SkippedTrack {
+    /// Catalog ID of the song
+    pub id: String,
+    /// Song title (empty if the catalog didn't return one)
+    pub name: String,
+    /// Why it was skipped (e.g. "unavailable")
+    pub reason: String,
+}
+
+/// Where one URL of a multi-URL download stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UrlState {
+    /// GAMDL hasn't got to it yet
+    Pending,
+    /// GAMDL is processing it
+    Downloading,
+    /// GAMDL moved past it (or the download completed)
+    Done,
+}
+
+/// Progress of one URL of a multi-URL download.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UrlProgress {
+    /// The Apple Music URL
+    pub url: String,
+    /// Whether GAMDL has got to it
+    pub state: UrlState,
+    /// Tracks of this URL saved in the current run
+    pub completed_tracks: u32,
+    /// Number of tracks, once GAMDL reported it ("track N of M")
+    pub total_tracks: Option<u32>,
+}
+
+/// One point on a download's throughput graph.
+///
+/// Returned (oldest first) by the `get_speed_samples` command. Recorded
+/// from the parsed speed of GAMDL's progress lines while the item is
+/// downloading; see `DownloadQueue::speed_samples()`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpeedSample {
+    /// Unix timestamp in milliseconds when the sample was taken
+    pub timestamp_ms: i64,
+    /// Download speed in bytes per second
+    pub bytes_per_sec: f64,
 }
 
 // ============================================================
@@ -328,6 +505,10 @@ mod tests {
                 "https://music.apple.com/us/album/another/987654321".to_string(),
             ],
             options: None,
+            label: None,
+            priority: 0,
+            fallback_chain_override: None,
+            kind: None,
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -349,6 +530,10 @@ mod tests {
                 overwrite: Some(true),
                 ..Default::default()
             }),
+            label: Some("Road trip".to_string()),
+            priority: 5,
+            fallback_chain_override: Some(vec![SongCodec::Atmos, SongCodec::Aac]),
+            kind: Some(DownloadKind::Album),
         };
 
         let json = serde_json::to_string(&request).unwrap();
@@ -359,6 +544,27 @@ mod tests {
         let opts = deserialized.options.unwrap();
         assert_eq!(opts.song_codec, Some(super::super::gamdl_options::SongCodec::Alac));
         assert_eq!(opts.overwrite, Some(true));
+        assert_eq!(deserialized.label.as_deref(), Some("Road trip"));
+        assert_eq!(deserialized.priority, 5);
+        assert_eq!(
+            deserialized.fallback_chain_override,
+            Some(vec![SongCodec::Atmos, SongCodec::Aac])
+        );
+        assert_eq!(deserialized.kind, Some(DownloadKind::Album));
+    }
+
+    /// Verifies that requests without `label`, `priority`,
+    /// `fallback_chain_override` and `kind` keys (older frontends and
+    /// `queue.json` files) still deserialize, with no label, normal
+    /// priority, the global fallback chain and no recorded kind.
+    #[test]
+    fn download_request_without_label_deserializes() {
+        let json = r#"{"urls":["https://music.apple.com/us/album/test/111"],"options":null}"#;
+        let request: DownloadRequest = serde_json::from_str(json).unwrap();
+        assert!(request.label.is_none());
+        assert_eq!(request.priority, 0);
+        assert!(request.fallback_chain_override.is_none());
+        assert!(request.kind.is_none());
     }
 
     // ----------------------------------------------------------
@@ -376,6 +582,7 @@ mod tests {
             state: DownloadState::Downloading,
             progress: 42.5,
             current_track: Some("Track Name".to_string()),
+            current_step: Some("Fetching decryption keys".to_string()),
             total_tracks: Some(12),
             completed_tracks: Some(5),
             speed: Some("2.5 MB/s".to_string()),
@@ -384,7 +591,13 @@ mod tests {
             output_path: None,
             codec_used: Some("alac".to_string()),
             fallback_occurred: false,
+            fallback_reason: None,
+            track_codecs: Vec::new(),
+            skipped_tracks: Vec::new(),
+            cover_missing_tracks: 0,
+            url_progress: Vec::new(),
             created_at: "2025-01-15T10:30:00.000Z".to_string(),
+            label: Some("Weekend mix".to_string()),
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -395,6 +608,7 @@ mod tests {
         assert_eq!(deserialized.state, status.state);
         assert!((deserialized.progress - status.progress).abs() < f64::EPSILON);
         assert_eq!(deserialized.current_track, status.current_track);
+        assert_eq!(deserialized.current_step, status.current_step);
         assert_eq!(deserialized.total_tracks, status.total_tracks);
         assert_eq!(deserialized.completed_tracks, status.completed_tracks);
         assert_eq!(deserialized.speed, status.speed);
@@ -404,6 +618,7 @@ mod tests {
         assert_eq!(deserialized.codec_used, status.codec_used);
         assert_eq!(deserialized.fallback_occurred, status.fallback_occurred);
         assert_eq!(deserialized.created_at, status.created_at);
+        assert_eq!(deserialized.label, status.label);
     }
 
     /// Verifies that a `QueueItemStatus` in the error terminal state
@@ -417,6 +632,7 @@ mod tests {
             state: DownloadState::Error,
             progress: 0.0,
             current_track: None,
+            current_step: None,
             total_tracks: None,
             completed_tracks: None,
             speed: None,
@@ -425,7 +641,13 @@ mod tests {
             output_path: None,
             codec_used: None,
             fallback_occurred: false,
+            fallback_reason: None,
+            track_codecs: Vec::new(),
+            skipped_tracks: Vec::new(),
+            cover_missing_tracks: 0,
+            url_progress: Vec::new(),
             created_at: "2025-02-01T08:00:00.000Z".to_string(),
+            label: None,
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -448,6 +670,7 @@ mod tests {
             state: DownloadState::Complete,
             progress: 100.0,
             current_track: None,
+            current_step: None,
             total_tracks: Some(1),
             completed_tracks: Some(1),
             speed: None,
@@ -456,7 +679,25 @@ mod tests {
             output_path: Some("/Users/test/Music/Artist/Album/01 Track.m4a".to_string()),
             codec_used: Some("aac".to_string()),
             fallback_occurred: true,
+            fallback_reason: Some("Lossless (ALAC) isn't available for this release".to_string()),
+            track_codecs: vec![
+                ("Track One".to_string(), "alac".to_string()),
+                ("Track Two".to_string(), "aac".to_string()),
+            ],
+            skipped_tracks: vec![SkippedTrack {
+                id: "1440935810".to_string(),
+                name: "Gone Track".to_string(),
+                reason: "unavailable".to_string(),
+            }],
+            cover_missing_tracks: 2,
+            url_progress: vec![UrlProgress {
+                url: "https://music.apple.com/us/album/a/1".to_string(),
+                state: UrlState::Downloading,
+                completed_tracks: 3,
+                total_tracks: Some(12),
+            }],
             created_at: "2025-03-10T14:22:00.000Z".to_string(),
+            label: None,
         };
 
         let json = serde_json::to_string(&status).unwrap();
@@ -466,6 +707,8 @@ mod tests {
         assert_eq!(deserialized.output_path, Some("/Users/test/Music/Artist/Album/01 Track.m4a".to_string()));
         assert_eq!(deserialized.codec_used, Some("aac".to_string()));
         assert!(deserialized.fallback_occurred);
+        assert_eq!(deserialized.cover_missing_tracks, 2);
+        assert_eq!(deserialized.url_progress, status.url_progress);
         assert!((deserialized.progress - 100.0).abs() < f64::EPSILON);
     }
 }
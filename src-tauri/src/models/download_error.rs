@@ -0,0 +1,494 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// Structured download errors.
+// ===========================
+//
+// `DownloadError` is what a failed GAMDL run returns
+// (`services::download_queue::run_download_with_events()`). Each variant
+// is one failure category, and `process_queue()` matches on the variant to
+// pick the recovery: codec fallback, network retry, storefront fallback,
+// re-queue after a deliberate stop, or none.
+//
+// The variant is built from GAMDL's last error line by
+// `DownloadError::from_message()`, which holds the keyword rules that used
+// to live in `process::classify_error()` (now a shim returning
+// `from_message(..).category()`).
+//
+// ## Event payload
+//
+// The `download-error` event carries the error twice: as the plain
+// `error` string and `category` the frontend already reads, and as
+// `details`, the enum serialized as a tagged object:
+//
+//   { "kind": "subscription", "message": "No active subscription" }
+//   { "kind": "cancelled", "reason": "user" }
+//
+// Reference: https://serde.rs/enum-representations.html#internally-tagged
+
+use serde::Serialize;
+
+use crate::utils::process;
+
+/// Why a running download was stopped on purpose rather than failing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    /// The user cancelled the download.
+    User,
+    /// The user switched the download to another codec
+    /// (`DownloadQueue::change_codec()`); it is re-queued straight away.
+    CodecChange,
+    /// The download window closed (`DownloadQueue::pause_for_schedule()`);
+    /// it is re-queued to resume when the window reopens.
+    SchedulePause,
+    /// The app is quitting (`services::shutdown`); the item is saved as
+    /// Queued and resumes on the next launch.
+    Shutdown,
+    /// A higher-priority download needed the slot
+    /// (`DownloadQueue::preempt_for_priority()`); it is re-queued and
+    /// resumes with its remaining tracks.
+    Preempted,
+}
+
+/// A failed download, by failure category.
+///
+/// | Variant                 | Category (`category()`)    | Recovery in `process_queue()` |
+/// |-------------------------|----------------------------|-------------------------------|
+/// | `Codec`                 | `"codec"`                  | Next codec of the chain       |
+/// | `CodecUnavailable`      | `"codec_unavailable"`      | Whole-job codec fallback      |
+/// | `Network`               | `"network"`                | Retry with the same options   |
+/// | `RateLimit`             | `"rate_limit"`             | Retry after a cooldown        |
+/// | `StorefrontUnavailable` | `"storefront_unavailable"` | Next fallback storefront      |
+/// | `InvalidUrl`            | `"invalid_url"`            | None: the URL must be fixed   |
+/// | `Disk`                  | `"disk"`                   | None: hold the queue until space frees up |
+/// | `Drm`                   | `"drm"`                    | None: the decryption setup must be fixed |
+/// | `NoCookies`             | `"no_cookies"`             | None: the user must sign in   |
+/// | `CorruptOutput`         | `"corrupt_output"`         | Re-download the damaged files once |
+/// | `CoverMissing`          | `"cover_missing"`          | Run once more without cover art |
+/// | `Stall`                 | `"stall"`                  | Retry like `Network`          |
+/// | `Cancelled`             | `"cancelled"`              | Re-queue (codec change/window/priority) or none |
+/// | all others              | see `category()`           | None: the item fails          |
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DownloadError {
+    /// The requested codec isn't available for a track.
+    Codec { message: String },
+    /// The requested codec isn't offered for the account tier or region at
+    /// all (detected on the first track).
+    CodecUnavailable { message: String },
+    /// Transient connection problem.
+    Network { message: String },
+    /// Missing or expired cookies / login.
+    Auth { message: String },
+    /// No cookies file is configured at all and the wrapper is off (raised
+    /// before spawning by `gamdl_service::check_cookies_config()`).
+    NoCookies { message: String },
+    /// Apple Music is throttling requests.
+    RateLimit { message: String },
+    /// The account has no active subscription, or the plan doesn't include
+    /// the content.
+    Subscription { message: String },
+    /// The content isn't available in the requested storefront.
+    StorefrontUnavailable { message: String },
+    /// The URL is malformed or of a kind GAMDL doesn't support (rejected
+    /// before spawning by `gamdl_service::validate_urls()`, or by GAMDL).
+    InvalidUrl { message: String },
+    /// The output drive ran out of space (or the user's disk quota).
+    Disk { message: String },
+    /// A track couldn't be decrypted: the Widevine device is missing or
+    /// invalid, or no decryption key could be obtained (also raised before
+    /// spawning by `gamdl_service::check_drm_config()`).
+    Drm { message: String },
+    /// The URL doesn't point at existing content.
+    NotFound { message: String },
+    /// An external tool (FFmpeg, mp4decrypt, ...) failed.
+    Tool { message: String },
+    /// GAMDL exited cleanly without saving anything
+    /// (see `process::NO_OUTPUT_ERROR`).
+    NoOutput { message: String },
+    /// Files GAMDL saved failed the audio check (see
+    /// `process::CORRUPT_OUTPUT_ERROR`).
+    CorruptOutput { message: String },
+    /// Tracks failed only on their cover art, with
+    /// `AppSettings::continue_on_cover_failure` on (see
+    /// `process::COVER_MISSING_ERROR`).
+    CoverMissing { message: String },
+    /// GAMDL printed nothing for `AppSettings::download_stall_timeout_secs`
+    /// and was killed (see `process::STALL_ERROR`).
+    Stall { message: String },
+    /// The download was stopped on purpose.
+    Cancelled { reason: StopReason },
+    /// GAMDL crashed with an unhandled Python exception that matches no
+    /// other category; `message` is the exception summary.
+    UpstreamBug { message: String },
+    /// Anything else.
+    Unknown { message: String },
+}
+
+impl DownloadError {
+    /// Classifies an error message (usually GAMDL's last error line).
+    ///
+    /// Rules are checked in order; the first match wins:
+    ///
+    /// | Variant                 | Matched by                                       |
+    /// |-------------------------|--------------------------------------------------|
+    /// | `NoOutput`              | `process::is_no_output_error()`                  |
+    /// | `CorruptOutput`         | `process::is_corrupt_output_error()`             |
+    /// | `CoverMissing`          | `process::is_cover_missing_error()`              |
+    /// | `Stall`                 | `process::is_stall_error()`                      |
+    /// | `NoCookies`             | `process::is_no_cookies_error()`                 |
+    /// | `Drm`                   | `process::is_drm_config_error()`                 |
+    /// | `InvalidUrl`            | `process::is_invalid_url_error()`                |
+    /// | `Disk`                  | `process::is_disk_full_error()`                  |
+    /// | `Subscription`          | `process::is_subscription_error()`               |
+    /// | `StorefrontUnavailable` | `process::is_storefront_unavailable_error()`     |
+    /// | `Auth`                  | cookie, auth, login                              |
+    /// | `Network`               | `process::is_network_error()`                    |
+    /// | `Drm`                   | `process::is_drm_error()`                        |
+    /// | `CodecUnavailable`      | `process::is_codec_unavailable_error()`          |
+    /// | `Codec`                 | `process::is_codec_error()`                      |
+    /// | `NotFound`              | not found, 404, no results                       |
+    /// | `RateLimit`             | rate limit, 429, too many                        |
+    /// | `Tool`                  | ffmpeg, mp4decrypt, mp4box, nm3u8dl              |
+    /// | `Unknown`               | (default)                                        |
+    pub fn from_message(message: impl Into<String>) -> Self {
+        let message = message.into();
+        let lower = message.to_lowercase();
+
+        // Clean exit with nothing saved: checked first because the message is
+        // produced by us and must not be mistaken for any keyword below.
+        if process::is_no_output_error(&message) {
+            Self::NoOutput { message }
+        // Failed audio check: also produced by us, and its FFmpeg error text
+        // would otherwise read as a tool error.
+        } else if process::is_corrupt_output_error(&message) {
+            Self::CorruptOutput { message }
+        // Cover art failure: also ours; GAMDL's own cover error lines keep
+        // their usual category, so nothing changes with the setting off.
+        } else if process::is_cover_missing_error(&message) {
+            Self::CoverMissing { message }
+        // Killed for going silent: ours too, and retried like a network error.
+        } else if process::is_stall_error(&message) {
+            Self::Stall { message }
+        // No cookies configured: ours, and must not read as a generic auth
+        // error for its "cookies" keyword.
+        } else if process::is_no_cookies_error(&message) {
+            Self::NoCookies { message }
+        // Decryption setup error: ours, and quotes the `.wvd` path, which may
+        // contain any keyword below.
+        } else if process::is_drm_config_error(&message) {
+            Self::Drm { message }
+        // Malformed or unsupported URL: checked early because the message
+        // quotes the URL, whose slug may contain any keyword below.
+        } else if process::is_invalid_url_error(&message) {
+            Self::InvalidUrl { message }
+        // Disk full: checked before the keyword categories so e.g. an FFmpeg
+        // write failure isn't reported as a tool error or retried.
+        } else if process::is_disk_full_error(&message) {
+            Self::Disk { message }
+        // No active subscription: checked before the keyword categories so a
+        // message that also mentions e.g. a connection isn't retried.
+        } else if process::is_subscription_error(&message) {
+            Self::Subscription { message }
+        // Region restriction: retried against another storefront.
+        } else if process::is_storefront_unavailable_error(&message) {
+            Self::StorefrontUnavailable { message }
+        // Authentication / cookie errors: user needs to provide valid credentials.
+        } else if lower.contains("cookie") || lower.contains("auth") || lower.contains("login") {
+            Self::Auth { message }
+        // Network errors: transient, may resolve on retry.
+        } else if process::is_network_error(&message) {
+            Self::Network { message }
+        // Decryption failure: checked after the network errors so a license
+        // server that can't be reached is retried like any network error
+        // (is_drm_error() also rules those out for its direct callers).
+        } else if process::is_drm_error(&message) {
+            Self::Drm { message }
+        // Codec not offered for this tier/region at all: fall back for the whole job.
+        } else if process::is_codec_unavailable_error(&message) {
+            Self::CodecUnavailable { message }
+        // Codec/format errors: the requested quality is not available; try fallback.
+        } else if process::is_codec_error(&message) {
+            Self::Codec { message }
+        // Content not found: the URL is invalid or the content was removed.
+        } else if lower.contains("not found") || lower.contains("404") || lower.contains("no results")
+        {
+            Self::NotFound { message }
+        // Rate limiting: the server is throttling requests.
+        } else if lower.contains("rate limit") || lower.contains("429") || lower.contains("too many")
+        {
+            Self::RateLimit { message }
+        // External tool errors: FFmpeg, mp4decrypt, etc. failed during post-processing.
+        } else if lower.contains("ffmpeg")
+            || lower.contains("mp4decrypt")
+            || lower.contains("mp4box")
+            || lower.contains("nm3u8dl")
+        {
+            Self::Tool { message }
+        } else {
+            Self::Unknown { message }
+        }
+    }
+
+    /// Classifies the error of a GAMDL run that crashed: like
+    /// `from_message()`, except that an unclassified exception is reported
+    /// as `UpstreamBug` instead of `Unknown`.
+    pub fn from_crash(summary: impl Into<String>) -> Self {
+        match Self::from_message(summary) {
+            Self::Unknown { message } => Self::UpstreamBug { message },
+            other => other,
+        }
+    }
+
+    /// Returns the category string sent to the frontend (and returned by
+    /// `process::classify_error()`).
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Codec { .. } => "codec",
+            Self::CodecUnavailable { .. } => "codec_unavailable",
+            Self::Network { .. } => "network",
+            Self::Auth { .. } => "auth",
+            Self::NoCookies { .. } => "no_cookies",
+            Self::RateLimit { .. } => "rate_limit",
+            Self::Subscription { .. } => "subscription",
+            Self::StorefrontUnavailable { .. } => "storefront_unavailable",
+            Self::InvalidUrl { .. } => "invalid_url",
+            Self::Disk { .. } => "disk",
+            Self::Drm { .. } => "drm",
+            Self::NotFound { .. } => "not_found",
+            Self::Tool { .. } => "tool",
+            Self::NoOutput { .. } => "no_output",
+            Self::CorruptOutput { .. } => "corrupt_output",
+            Self::CoverMissing { .. } => "cover_missing",
+            Self::Stall { .. } => "stall",
+            Self::Cancelled { .. } => "cancelled",
+            Self::UpstreamBug { .. } => "upstream_bug",
+            Self::Unknown { .. } => "unknown",
+        }
+    }
+
+    /// Returns the underlying error message.
+    pub fn message(&self) -> &str {
+        match self {
+            Self::Cancelled { reason } => match reason {
+                StopReason::User => "Download cancelled by user",
+                StopReason::CodecChange => "Download restarting with a different codec",
+                StopReason::SchedulePause => "Download paused until the download window opens",
+                StopReason::Shutdown => "Download interrupted by app shutdown; it resumes on next launch",
+                StopReason::Preempted => "Download paused for a higher-priority download",
+            },
+            Self::Codec { message }
+            | Self::CodecUnavailable { message }
+            | Self::Network { message }
+            | Self::Auth { message }
+            | Self::NoCookies { message }
+            | Self::RateLimit { message }
+            | Self::Subscription { message }
+            | Self::StorefrontUnavailable { message }
+            | Self::InvalidUrl { message }
+            | Self::Disk { message }
+            | Self::Drm { message }
+            | Self::NotFound { message }
+            | Self::Tool { message }
+            | Self::NoOutput { message }
+            | Self::CorruptOutput { message }
+            | Self::CoverMissing { message }
+            | Self::Stall { message }
+            | Self::UpstreamBug { message }
+            | Self::Unknown { message } => message,
+        }
+    }
+
+    /// Returns the message to show the user: the raw message, explained
+    /// where GAMDL's wording says little about what to do.
+    pub fn user_message(&self) -> String {
+        match self {
+            Self::Subscription { message } => process::subscription_error_message(message),
+            Self::Disk { message } => process::disk_full_error_message(message),
+            Self::Drm { message } => process::drm_error_message(message),
+            Self::UpstreamBug { message } => format!(
+                "GAMDL crashed unexpectedly ({}). Check the log for the full traceback.",
+                message
+            ),
+            _ => self.message().to_string(),
+        }
+    }
+
+    /// Explains, for the user, why `codec` (a display name such as
+    /// "Lossless (ALAC)") was given up for the next one in the fallback
+    /// chain after this error.
+    pub fn fallback_reason(&self, codec: &str) -> String {
+        match self {
+            Self::CodecUnavailable { .. } => {
+                format!("{} isn't offered for this account or region", codec)
+            }
+            Self::Codec { .. } => format!("{} isn't available for this release", codec),
+            _ => format!("{} download failed ({})", codec, self.category()),
+        }
+    }
+}
+
+impl std::fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for DownloadError {}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Verifies that representative GAMDL error lines map to the right
+    /// variant.
+    #[test]
+    fn maps_gamdl_lines_to_variants() {
+        let cases = [
+            ("[ERROR] Failed to download track: Codec not available", "codec"),
+            ("No matching stream found for codec: alac", "codec_unavailable"),
+            ("requests.exceptions.ConnectionError: Connection aborted", "network"),
+            ("[ERROR] Invalid cookies, please re-export them", "auth"),
+            ("HTTP 429 Too Many Requests", "rate_limit"),
+            ("Exception: No active Apple Music subscription", "subscription"),
+            ("This album is not available in this storefront", "storefront_unavailable"),
+            ("HTTP 404: resource not found", "not_found"),
+            ("[ERROR] URL is not supported: https://music.apple.com/us/browse", "invalid_url"),
+            ("OSError: [Errno 28] No space left on device", "disk"),
+            ("OSError: [WinError 112] There is not enough space on the disk", "disk"),
+            ("mp4decrypt returned exit code 1", "tool"),
+            ("[ERROR] Failed to get decryption key for track 3", "drm"),
+            (process::NO_OUTPUT_ERROR, "no_output"),
+            (
+                "Downloaded files failed the audio check: 01 Song.m4a (moov atom not found)",
+                "corrupt_output",
+            ),
+            (
+                "Cover art could not be downloaded for 1 track(s)",
+                "cover_missing",
+            ),
+            (
+                "GAMDL stopped responding: no output for 300 seconds",
+                "stall",
+            ),
+            (
+                "No Apple Music cookies are configured. Sign in via Settings > Cookies.",
+                "no_cookies",
+            ),
+            ("Something odd happened", "unknown"),
+        ];
+        for (line, kind) in cases {
+            let error = DownloadError::from_message(line);
+            // The serde tag names the variant
+            assert_eq!(
+                serde_json::to_value(&error).unwrap()["kind"],
+                kind,
+                "{:?} -> {:?}",
+                line,
+                error
+            );
+            assert_eq!(error.message(), line);
+        }
+    }
+
+    /// Verifies that an unclassified crash is an upstream bug, while a crash
+    /// with a recognisable cause keeps its category (so it is still retried).
+    #[test]
+    fn crash_without_known_cause_is_upstream_bug() {
+        assert_eq!(
+            DownloadError::from_crash("KeyError: 'attributes'"),
+            DownloadError::UpstreamBug {
+                message: "KeyError: 'attributes'".to_string()
+            }
+        );
+        assert!(matches!(
+            DownloadError::from_crash("ConnectionError: Read timed out"),
+            DownloadError::Network { .. }
+        ));
+    }
+
+    /// Verifies that category() matches the strings classify_error() has
+    /// always returned.
+    #[test]
+    fn category_matches_classify_error() {
+        for line in [
+            "Codec not available",
+            "Connection refused",
+            "Login required",
+            "Rate limit exceeded",
+            "Resource not found",
+            "FFmpeg process crashed",
+            "No active subscription",
+            "whatever",
+        ] {
+            assert_eq!(
+                DownloadError::from_message(line).category(),
+                process::classify_error(line)
+            );
+        }
+        assert_eq!(
+            DownloadError::Cancelled { reason: StopReason::User }.category(),
+            "cancelled"
+        );
+    }
+
+    /// Verifies the tagged JSON representation used in the download-error
+    /// event.
+    #[test]
+    fn serializes_as_tagged_object() {
+        let json = serde_json::to_value(DownloadError::from_message("Connection refused")).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "network", "message": "Connection refused" })
+        );
+
+        let json = serde_json::to_value(DownloadError::Cancelled {
+            reason: StopReason::SchedulePause,
+        })
+        .unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({ "kind": "cancelled", "reason": "schedule_pause" })
+        );
+    }
+
+    /// Verifies that subscription and crash errors get an explanation,
+    /// and others show their own message.
+    #[test]
+    fn user_message_explains_subscription_and_crash() {
+        let subscription = DownloadError::from_message("No active subscription");
+        assert!(subscription
+            .user_message()
+            .starts_with("An active Apple Music subscription is required"));
+
+        let crash = DownloadError::from_crash("KeyError: 'attributes'");
+        assert!(crash.user_message().contains("KeyError: 'attributes'"));
+
+        let network = DownloadError::from_message("Connection refused");
+        assert_eq!(network.user_message(), "Connection refused");
+        assert_eq!(network.to_string(), "Connection refused");
+    }
+
+    /// Verifies that the fallback reason names the codec and says why it
+    /// was given up.
+    #[test]
+    fn fallback_reason_explains_codec_errors() {
+        let codec = DownloadError::from_message("Codec not available for this track");
+        assert!(matches!(codec, DownloadError::Codec { .. }));
+        assert_eq!(
+            codec.fallback_reason("Lossless (ALAC)"),
+            "Lossless (ALAC) isn't available for this release"
+        );
+
+        let network = DownloadError::from_message("Connection refused");
+        assert_eq!(
+            network.fallback_reason("Dolby Atmos"),
+            "Dolby Atmos download failed (network)"
+        );
+    }
+}
@@ -52,7 +52,7 @@ use serde::{Deserialize, Serialize};
 /// ## Reference
 ///
 /// - GAMDL `--song-codec` flag: <https://github.com/glomatico/gamdl#usage>
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub enum SongCodec {
     /// Apple Lossless Audio Codec (ALAC) -- the highest-quality option.
@@ -451,9 +451,10 @@ pub struct GamdlOptions {
     pub music_video_resolution: Option<VideoResolution>,
     /// Video container format ("mp4" or "m4v")
     pub music_video_remux_format: Option<String>,
-    /// Uploaded/post video quality ("best" or "ask")
+    /// Uploaded/post video quality ("best")
     pub uploaded_video_quality: Option<String>,
-    /// Whether to skip music videos in album/playlist downloads
+    /// Download the music videos in album/playlist downloads, which GAMDL
+    /// skips by default
     pub disable_music_video_skip: Option<bool>,
 
     // --- Lyrics ---
@@ -501,7 +502,8 @@ pub struct GamdlOptions {
     pub use_album_date: Option<bool>,
     /// Fetch extra metadata (normalization, smooth playback)
     pub fetch_extra_tags: Option<bool>,
-    /// Date format for metadata tags
+    /// Date format for metadata tags, as a strftime pattern (e.g., `"%Y"`
+    /// for year-only dates). `None` uses GAMDL's default.
     pub date_tag_template: Option<String>,
 
     // --- Templates ---
@@ -551,9 +553,67 @@ pub struct GamdlOptions {
     pub read_urls_as_txt: Option<bool>,
     /// Skip using GAMDL's own config file
     pub no_config_file: Option<bool>,
+
+    // --- MeedyaDL only (no CLI flag) ---
+    /// Apple Music storefront (two-letter country code, e.g. "jp") to
+    /// download from instead of the one in the URL. Never passed to GAMDL:
+    /// the queue rewrites the URLs before spawning (see
+    /// `services::storefront_service`).
+    pub storefront: Option<String>,
+    /// Unix niceness the GAMDL process runs at (a priority class on
+    /// Windows). Never passed to GAMDL: `gamdl_service` applies it to the
+    /// spawned process, whose FFmpeg and mp4decrypt children inherit it.
+    pub process_niceness: Option<i32>,
+    /// Whether GAMDL reads the user's own config file instead of the
+    /// config.ini MeedyaDL syncs. When `Some(true)`, `--config-path` is
+    /// omitted and no cookies path is required, since the user's file may
+    /// set it. Set from `AppSettings::use_gamdl_config_file` by
+    /// `merge_options()`; never passed to GAMDL.
+    pub use_user_config: Option<bool>,
 }
 
+/// Placeholder that `GamdlOptions::redacted()` puts in place of a secret.
+pub const REDACTED: &str = "<redacted>";
+
 impl GamdlOptions {
+    /// Clears every option that points at a file on this machine: the
+    /// cookies file and the tool binary paths. Used when exporting the
+    /// queue, since those paths are meaningless on another device; the
+    /// importing device fills them in from its own settings.
+    pub fn strip_machine_paths(&mut self) {
+        self.cookies_path = None;
+        self.ffmpeg_path = None;
+        self.mp4decrypt_path = None;
+        self.mp4box_path = None;
+        self.nm3u8dlre_path = None;
+        self.amdecrypt_path = None;
+        self.wvd_path = None;
+    }
+
+    /// Returns a copy with every value that could identify the user or
+    /// their machine replaced by [`REDACTED`]: the cookies path, the
+    /// wrapper account URL, the `.wvd` device and the tool binary paths.
+    /// Unset values stay unset, so the copy still shows which were set.
+    /// Used by the `get_effective_options` command.
+    pub fn redacted(&self) -> GamdlOptions {
+        let mut options = self.clone();
+        for value in [
+            &mut options.cookies_path,
+            &mut options.wrapper_account_url,
+            &mut options.ffmpeg_path,
+            &mut options.mp4decrypt_path,
+            &mut options.mp4box_path,
+            &mut options.nm3u8dlre_path,
+            &mut options.amdecrypt_path,
+            &mut options.wvd_path,
+        ] {
+            if value.is_some() {
+                *value = Some(REDACTED.to_string());
+            }
+        }
+        options
+    }
+
     /// Converts the options struct into a vector of CLI argument strings.
     ///
     /// Only fields that are `Some(...)` generate CLI flags. `None` fields
@@ -603,8 +663,12 @@ impl GamdlOptions {
             args.push(format.clone());
         }
         if let Some(ref quality) = self.uploaded_video_quality {
+            // "ask" would wait for an answer on a terminal the download
+            // doesn't have; settings saved before it was rejected, and
+            // per-download overrides, get the best quality instead.
+            let quality = if quality == "ask" { "best" } else { quality };
             args.push("--uploaded-video-quality".to_string());
-            args.push(quality.clone());
+            args.push(quality.to_string());
         }
         // Boolean flag pattern: only emit the flag when the value is explicitly
         // `Some(true)`. `Some(false)` and `None` both result in omission,
@@ -788,9 +852,14 @@ impl GamdlOptions {
         if self.save_playlist == Some(true) {
             args.push("--save-playlist".to_string());
         }
-        // When set, GAMDL ignores its own ~/.gamdl/config.json. We typically
-        // enable this so that the GUI's settings are the sole source of truth
-        // and do not conflict with a user's pre-existing GAMDL config.
+        // The positional arguments are paths to text files with one URL per
+        // line. Set for batch jobs, which pass their URLs through a temp file.
+        if self.read_urls_as_txt == Some(true) {
+            args.push("--read-urls-as-txt".to_string());
+        }
+        // When set, GAMDL ignores config files, including the config.ini
+        // MeedyaDL syncs, so merge_options() never sets it; only a
+        // download's own options can.
         if self.no_config_file == Some(true) {
             args.push("--no-config-file".to_string());
         }
@@ -799,6 +868,272 @@ impl GamdlOptions {
     }
 }
 
+// ============================================================
+// Template validation
+// ============================================================
+
+/// Placeholders GAMDL substitutes in folder and file templates. These are
+/// the metadata tag names GAMDL exposes to Python's `str.format()`, plus
+/// the playlist-specific fields used by `playlist_file_template`.
+///
+/// Reference: <https://github.com/glomatico/gamdl#usage>
+pub const TEMPLATE_PLACEHOLDERS: &[&str] = &[
+    "album",
+    "album_artist",
+    "album_id",
+    "album_sort",
+    "artist",
+    "artist_id",
+    "artist_sort",
+    "comment",
+    "compilation",
+    "composer",
+    "composer_id",
+    "composer_sort",
+    "copyright",
+    "date",
+    "disc",
+    "disc_total",
+    "gapless",
+    "genre",
+    "genre_id",
+    "lyrics",
+    "media_type",
+    "playlist_artist",
+    "playlist_id",
+    "playlist_title",
+    "playlist_track",
+    "rating",
+    "storefront",
+    "title",
+    "title_id",
+    "title_sort",
+    "track",
+    "track_total",
+    "xid",
+];
+
+/// Checks that a folder/file template only uses placeholders GAMDL knows.
+///
+/// Templates follow Python `str.format()` syntax: `{name}` or
+/// `{name:spec}` (e.g., `{track:02d}`), with `{{` and `}}` as literal
+/// braces. An unknown name would make GAMDL raise `KeyError` for every
+/// track, so it is caught here instead.
+///
+/// # Returns
+/// * `Ok(())` if every placeholder is known and all braces are balanced.
+/// * `Err(message)` naming the first offending placeholder or brace.
+pub fn validate_template(template: &str) -> Result<(), String> {
+    let (names, _) = parse_template(template)?;
+    match names.iter().find(|name| !TEMPLATE_PLACEHOLDERS.contains(&name.as_str())) {
+        Some(name) => Err(format!("unknown placeholder {{{}}}", name)),
+        None => Ok(()),
+    }
+}
+
+/// Characters Windows doesn't allow in file and folder names. `/` and `\`
+/// are left out: in a template they separate folders.
+const WINDOWS_ILLEGAL_CHARS: &[char] = &['<', '>', ':', '"', '|', '?', '*'];
+
+/// Everything `check_template()` found out about a template.
+///
+/// Serialized to JSON and returned to the frontend by the
+/// `validate_template` Tauri command.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TemplateCheck {
+    /// Whether GAMDL can use the template as is
+    pub valid: bool,
+    /// Unbalanced braces, if any. Placeholders aren't checked when set.
+    pub syntax_error: Option<String>,
+    /// Placeholder names GAMDL doesn't know (e.g., `"albumartist"`)
+    pub unknown_placeholders: Vec<String>,
+    /// Characters outside placeholders that the file system doesn't allow
+    pub illegal_characters: Vec<char>,
+    /// The template with `\` separators turned into `/`, empty folder
+    /// levels dropped and surrounding whitespace trimmed
+    pub normalized: String,
+    /// The first problem in words, for error messages; `None` when valid
+    pub problem: Option<String>,
+}
+
+/// Checks a folder/file template in full: brace syntax, every unknown
+/// placeholder (not just the first, unlike validate_template()), and
+/// literal characters the file system rejects. Format specs such as the
+/// `:` in `{track:02d}` are not literal text and never flagged.
+///
+/// `windows` selects the stricter Windows rules (`< > : " | ? *`); on
+/// macOS and Linux only control characters are rejected. Callers pass
+/// `cfg!(target_os = "windows")`.
+pub fn check_template(template: &str, windows: bool) -> TemplateCheck {
+    let normalized = normalize_template(template);
+    let (names, literal) = match parse_template(template) {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            return TemplateCheck {
+                valid: false,
+                syntax_error: Some(error.clone()),
+                unknown_placeholders: Vec::new(),
+                illegal_characters: Vec::new(),
+                normalized,
+                problem: Some(error),
+            }
+        }
+    };
+
+    let mut unknown_placeholders: Vec<String> = Vec::new();
+    for name in names {
+        if !TEMPLATE_PLACEHOLDERS.contains(&name.as_str()) && !unknown_placeholders.contains(&name) {
+            unknown_placeholders.push(name);
+        }
+    }
+
+    let mut illegal_characters: Vec<char> = Vec::new();
+    for c in literal.chars() {
+        let illegal = c.is_control() || (windows && WINDOWS_ILLEGAL_CHARS.contains(&c));
+        if illegal && !illegal_characters.contains(&c) {
+            illegal_characters.push(c);
+        }
+    }
+
+    let problem = if !unknown_placeholders.is_empty() {
+        let names: Vec<String> = unknown_placeholders
+            .iter()
+            .map(|name| format!("{{{}}}", name))
+            .collect();
+        Some(format!("unknown placeholder {}", names.join(", ")))
+    } else if !illegal_characters.is_empty() {
+        let chars: String = illegal_characters.iter().collect();
+        Some(format!("characters not allowed in file names: {}", chars))
+    } else {
+        None
+    };
+
+    TemplateCheck {
+        valid: problem.is_none(),
+        syntax_error: None,
+        unknown_placeholders,
+        illegal_characters,
+        normalized,
+        problem,
+    }
+}
+
+/// Splits a template into its placeholder names and its literal text
+/// (with `{{`/`}}` unescaped).
+///
+/// # Returns
+/// * `Ok((names, literal))` if all braces are balanced.
+/// * `Err(message)` naming the offending brace.
+fn parse_template(template: &str) -> Result<(Vec<String>, String), String> {
+    let mut names = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                literal.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                literal.push('}');
+            }
+            '{' => {
+                let mut field = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some('{') | None => {
+                            return Err(format!("unclosed '{{' in template \"{}\"", template));
+                        }
+                        Some(ch) => field.push(ch),
+                    }
+                }
+                // The placeholder name ends at a format spec, conversion,
+                // attribute access, or index.
+                let name = field.split([':', '!', '.', '[']).next().unwrap_or("");
+                names.push(name.to_string());
+            }
+            '}' => {
+                return Err(format!("unmatched '}}' in template \"{}\"", template));
+            }
+            c => literal.push(c),
+        }
+    }
+    Ok((names, literal))
+}
+
+/// Turns `\` folder separators into `/`, drops empty folder levels (a
+/// leading `/` would make the path absolute) and trims whitespace around
+/// each level.
+fn normalize_template(template: &str) -> String {
+    template
+        .replace('\\', "/")
+        .split('/')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Values MeedyaDL passes to `--uploaded-video-quality`. GAMDL also knows
+/// "ask", which prompts on the terminal for each video; a download has no
+/// terminal to answer it, so it isn't accepted.
+pub const UPLOADED_VIDEO_QUALITIES: &[&str] = &["best"];
+
+/// Checks an uploaded video quality against `UPLOADED_VIDEO_QUALITIES`.
+///
+/// # Returns
+/// * `Ok(())` for `"best"`.
+/// * `Err(message)` listing the accepted values otherwise.
+pub fn validate_uploaded_video_quality(quality: &str) -> Result<(), String> {
+    if UPLOADED_VIDEO_QUALITIES.contains(&quality) {
+        Ok(())
+    } else {
+        Err(format!(
+            "\"{}\" (expected one of: {})",
+            quality,
+            UPLOADED_VIDEO_QUALITIES.join(", ")
+        ))
+    }
+}
+
+/// strftime directives Python's `datetime.strftime()` (used by GAMDL for
+/// the date tag) supports on every platform, without the leading `%`.
+const STRFTIME_DIRECTIVES: &str = "aAwdbBmyYHIpMSfzZjUWcxXGuV%";
+
+/// Checks that a date tag template is a plausible strftime pattern: at
+/// least one directive, and every `%` followed by one GAMDL can format.
+///
+/// # Returns
+/// * `Ok(())` for patterns such as `"%Y"` or `"%Y-%m-%d"`.
+/// * `Err(message)` naming the offending directive.
+pub fn validate_date_tag_template(template: &str) -> Result<(), String> {
+    let mut directives = 0;
+    let mut chars = template.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            continue;
+        }
+        match chars.next() {
+            Some(d) if STRFTIME_DIRECTIVES.contains(d) => {
+                if d != '%' {
+                    directives += 1;
+                }
+            }
+            Some(d) => return Err(format!("unknown date directive %{}", d)),
+            None => return Err("template ends with a lone '%'".to_string()),
+        }
+    }
+    if directives == 0 {
+        return Err(format!(
+            "\"{}\" has no date directives (e.g., %Y for the year)",
+            template
+        ));
+    }
+    Ok(())
+}
+
 // ============================================================
 // Unit Tests
 // ============================================================
@@ -853,6 +1188,14 @@ mod tests {
         assert_eq!(LyricsFormat::Ttml.to_cli_string(), "ttml");
     }
 
+    /// Verifies that a per-download override can't ask GAMDL for WebVTT
+    /// lyrics: that's only an app-side conversion (`LyricsConversion`).
+    #[test]
+    fn lyrics_format_rejects_vtt() {
+        let json = r#"{"synced_lyrics_format":"vtt"}"#;
+        assert!(serde_json::from_str::<GamdlOptions>(json).is_err());
+    }
+
     // ----------------------------------------------------------
     // CoverFormat::to_cli_string
     // ----------------------------------------------------------
@@ -898,6 +1241,23 @@ mod tests {
         assert_eq!(args, vec!["--music-video-resolution", "1080p"]);
     }
 
+    #[test]
+    fn uploaded_video_quality_option() {
+        let with = |value: Option<&str>| GamdlOptions {
+            uploaded_video_quality: value.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(
+            with(Some("best")).to_cli_args(),
+            vec!["--uploaded-video-quality", "best"]
+        );
+        assert_eq!(
+            with(Some("ask")).to_cli_args(),
+            vec!["--uploaded-video-quality", "best"]
+        );
+        assert!(with(None).to_cli_args().is_empty());
+    }
+
     #[test]
     fn lyrics_format_option() {
         let options = GamdlOptions {
@@ -930,6 +1290,42 @@ mod tests {
         assert!(!options.to_cli_args().contains(&"--overwrite".to_string()));
     }
 
+    #[test]
+    fn storefront_is_not_a_cli_flag() {
+        let options = GamdlOptions {
+            storefront: Some("jp".to_string()),
+            process_niceness: Some(10),
+            use_user_config: Some(true),
+            ..Default::default()
+        };
+        assert!(options.to_cli_args().is_empty());
+    }
+
+    #[test]
+    fn disable_music_video_skip_only_when_true() {
+        let with = |value| GamdlOptions {
+            disable_music_video_skip: value,
+            ..Default::default()
+        };
+        assert_eq!(
+            with(Some(true)).to_cli_args(),
+            vec!["--disable-music-video-skip".to_string()]
+        );
+        assert!(with(Some(false)).to_cli_args().is_empty());
+        assert!(with(None).to_cli_args().is_empty());
+    }
+
+    #[test]
+    fn no_config_file_only_when_true() {
+        let with = |value| GamdlOptions {
+            no_config_file: value,
+            ..Default::default()
+        };
+        assert_eq!(with(Some(true)).to_cli_args(), vec!["--no-config-file".to_string()]);
+        assert!(with(Some(false)).to_cli_args().is_empty());
+        assert!(with(None).to_cli_args().is_empty());
+    }
+
     #[test]
     fn boolean_none_omits_flag() {
         let options = GamdlOptions {
@@ -1012,6 +1408,16 @@ mod tests {
         assert_eq!(args, vec!["--log-level", "DEBUG"]);
     }
 
+    #[test]
+    fn read_urls_as_txt_flag() {
+        let options = GamdlOptions {
+            read_urls_as_txt: Some(true),
+            ..Default::default()
+        };
+        let args = options.to_cli_args();
+        assert_eq!(args, vec!["--read-urls-as-txt"]);
+    }
+
     // ----------------------------------------------------------
     // GamdlOptions::to_cli_args -- multiple options combined
     // ----------------------------------------------------------
@@ -1062,4 +1468,116 @@ mod tests {
         let deserialized: VideoResolution = serde_json::from_str(&json).unwrap();
         assert_eq!(deserialized, res);
     }
+
+    // ----------------------------------------------------------
+    // validate_template
+    // ----------------------------------------------------------
+
+    #[test]
+    fn validate_template_accepts_known_placeholders() {
+        assert!(validate_template("{album_artist}/{album}").is_ok());
+        assert!(validate_template("{disc}-{track:02d} {title}").is_ok());
+        assert!(validate_template("Playlists/{playlist_artist}/{playlist_title}").is_ok());
+        assert!(validate_template("{{literal}} {title}").is_ok());
+        assert!(validate_template("No placeholders").is_ok());
+    }
+
+    #[test]
+    fn validate_template_rejects_unknown_placeholders() {
+        let err = validate_template("{album_artist}/{albun}").unwrap_err();
+        assert!(err.contains("{albun}"), "{}", err);
+        assert!(validate_template("{}").is_err());
+    }
+
+    #[test]
+    fn validate_template_rejects_unbalanced_braces() {
+        assert!(validate_template("{title").is_err());
+        assert!(validate_template("title}").is_err());
+        assert!(validate_template("{ti{tle}").is_err());
+    }
+
+    // ----------------------------------------------------------
+    // validate_uploaded_video_quality
+    // ----------------------------------------------------------
+
+    #[test]
+    fn validate_uploaded_video_quality_accepts_non_interactive_values() {
+        assert!(validate_uploaded_video_quality("best").is_ok());
+        assert!(validate_uploaded_video_quality("ask").is_err());
+        assert!(validate_uploaded_video_quality("Best").is_err());
+        assert!(validate_uploaded_video_quality("1080p").is_err());
+    }
+
+    // ----------------------------------------------------------
+    // validate_date_tag_template
+    // ----------------------------------------------------------
+
+    #[test]
+    fn validate_date_tag_template_accepts_strftime_patterns() {
+        assert!(validate_date_tag_template("%Y").is_ok());
+        assert!(validate_date_tag_template("%Y-%m-%d").is_ok());
+        assert!(validate_date_tag_template("%Y-%m-%dT%H:%M:%SZ").is_ok());
+        assert!(validate_date_tag_template("100%% %Y").is_ok());
+    }
+
+    #[test]
+    fn validate_date_tag_template_rejects_implausible_patterns() {
+        let err = validate_date_tag_template("%Q").unwrap_err();
+        assert!(err.contains("%Q"), "{}", err);
+        assert!(validate_date_tag_template("%Y %").is_err());
+        assert!(validate_date_tag_template("YYYY").is_err());
+        assert!(validate_date_tag_template("%%").is_err());
+    }
+
+    // ----------------------------------------------------------
+    // check_template
+    // ----------------------------------------------------------
+
+    /// Verifies that a valid template passes on every platform; the colon
+    /// of a format spec isn't literal text.
+    #[test]
+    fn check_template_accepts_valid_template() {
+        for windows in [false, true] {
+            let check = check_template("{disc}-{track:02d} {title}", windows);
+            assert!(check.valid, "{:?}", check);
+            assert_eq!(check.problem, None);
+        }
+    }
+
+    /// Verifies that every unknown placeholder is listed, once each.
+    #[test]
+    fn check_template_lists_unknown_placeholders() {
+        let check = check_template("{albumartist}/{album}/{titel} {albumartist}", false);
+        assert!(!check.valid);
+        assert_eq!(check.unknown_placeholders, vec!["albumartist", "titel"]);
+        assert_eq!(
+            check.problem.unwrap(),
+            "unknown placeholder {albumartist}, {titel}"
+        );
+    }
+
+    /// Verifies that a literal colon is flagged on Windows only.
+    #[test]
+    fn check_template_flags_colon_on_windows() {
+        let windows = check_template("{artist}: {album}", true);
+        assert!(!windows.valid);
+        assert_eq!(windows.illegal_characters, vec![':']);
+        assert!(windows.problem.unwrap().contains("not allowed"));
+
+        assert!(check_template("{artist}: {album}", false).valid);
+    }
+
+    /// Verifies that syntax errors are reported and the template is
+    /// normalized.
+    #[test]
+    fn check_template_reports_syntax_and_normalizes() {
+        let check = check_template("{title", false);
+        assert!(!check.valid);
+        assert!(check.syntax_error.is_some());
+
+        assert_eq!(
+            check_template(" /{album_artist} \\ {album}//", false).normalized,
+            "{album_artist}/{album}"
+        );
+    }
 }
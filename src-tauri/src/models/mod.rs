@@ -26,6 +26,7 @@
 //   +-- gamdl_options.rs -- GamdlOptions (maps to GAMDL CLI flags)
 //   +-- dependency.rs    -- DependencyInfo, DependencyStatus
 //   +-- music_service.rs -- MusicService trait, service identifiers
+//   +-- download_error.rs -- DownloadError (structured download failures)
 //
 // Reference: https://serde.rs/
 // Reference: https://v2.tauri.app/develop/calling-rust/#returning-data
@@ -64,3 +65,11 @@ pub mod dependency;
 /// download backends, and concrete identifiers for each supported service.
 /// This enables future extensibility beyond Apple Music (GAMDL).
 pub mod music_service;
+
+/// Structured download error model.
+///
+/// Defines `DownloadError` (one variant per failure category, returned by
+/// `services::download_queue::run_download_with_events()`) and
+/// `StopReason` (why a download was stopped on purpose). Serialized as a
+/// tagged object into the `download-error` event.
+pub mod download_error;
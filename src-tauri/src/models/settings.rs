@@ -31,6 +31,8 @@
 // - serde derive macros: <https://docs.rs/serde/latest/serde/>
 // - Tauri app data directory: <https://v2.tauri.app/reference/javascript/api/namespacepath/>
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 use super::gamdl_options::{
@@ -114,6 +116,140 @@ impl Default for CompanionMode {
     }
 }
 
+/// What a download does with a file that already exists in the output
+/// folder (`AppSettings::overwrite_policy`).
+///
+/// GAMDL's `--overwrite` flag only covers the first two; `Version` is
+/// implemented by downloading into a staging folder and renaming files
+/// that collide while they are moved into place (see `staging_service`).
+///
+/// ```text
+/// Artist/Album/
+///   01 Song Title.m4a       <- already there
+///   01 Song Title (1).m4a   <- Version: the new download
+/// ```
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// **[DEFAULT]** Keep the existing file and skip the track (GAMDL
+    /// without `--overwrite`).
+    #[default]
+    Skip,
+    /// Download the track again and replace the file (`--overwrite`).
+    Replace,
+    /// Keep the existing file and save the new one next to it with an
+    /// incrementing suffix: ` (1)`, ` (2)`, ...
+    Version,
+}
+
+/// Which version of a track to download when Apple Music offers both an
+/// explicit and a clean one (`AppSettings::content_preference`).
+///
+/// The two versions are separate catalog entries, usually on separate
+/// album releases; tracks without a content rating are the same in both.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentPreference {
+    /// **[DEFAULT]** Download the version the URL points at.
+    #[default]
+    AsIs,
+    /// Swap clean tracks for their explicit version where there is one.
+    Explicit,
+    /// Swap explicit tracks for their clean version where there is one.
+    Clean,
+}
+
+/// An animated artwork (motion cover art) variant that Apple Music may
+/// provide for an album. Each variant is saved as its own sidecar MP4.
+///
+/// Users pick which variants to fetch via
+/// `AppSettings::animated_artwork_variants`; variants the album does not
+/// offer are silently skipped.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ArtworkVariant {
+    /// Square (1:1) motion cover, saved as `FrontCover.mp4`.
+    Square,
+    /// Portrait (3:4) motion cover, saved as `PortraitCover.mp4`.
+    Portrait,
+    /// Landscape (16:9) motion cover, saved as `LandscapeCover.mp4`.
+    /// Only a small number of albums provide this variant.
+    Landscape,
+}
+
+/// Format the app converts downloaded TTML lyrics to
+/// (`AppSettings::convert_lyrics_to`; see `lyrics_conversion_service`).
+///
+/// Separate from `LyricsFormat`, which is passed to GAMDL: GAMDL can't
+/// write WebVTT, so it's only offered here.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricsConversion {
+    /// SubRip subtitles.
+    Srt,
+    /// WebVTT subtitles.
+    Vtt,
+    /// Timestamped LRC lyrics.
+    Lrc,
+}
+
+impl LyricsConversion {
+    /// File extension of the converted sidecar.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            LyricsConversion::Srt => "srt",
+            LyricsConversion::Vtt => "vtt",
+            LyricsConversion::Lrc => "lrc",
+        }
+    }
+}
+
+/// Lossy format of a transcode companion (`AppSettings::transcode_companion`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscodeFormat {
+    /// MP3 (LAME), for players that read nothing newer, e.g. old car stereos.
+    Mp3,
+    /// Opus in an Ogg container: better quality per bit than MP3.
+    Opus,
+}
+
+impl TranscodeFormat {
+    /// File extension of the companion file.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TranscodeFormat::Mp3 => "mp3",
+            TranscodeFormat::Opus => "opus",
+        }
+    }
+
+    /// Suffix appended to the track's file name, e.g. `01 Song [MP3].mp3`.
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            TranscodeFormat::Mp3 => "[MP3]",
+            TranscodeFormat::Opus => "[Opus]",
+        }
+    }
+
+    /// Bitrates (kbps) the encoder accepts.
+    pub fn bitrate_range(&self) -> std::ops::RangeInclusive<u32> {
+        match self {
+            TranscodeFormat::Mp3 => 32..=320,
+            TranscodeFormat::Opus => 6..=510,
+        }
+    }
+}
+
+/// A lossy copy saved next to each AAC or ALAC track after a download,
+/// for devices that can't play M4A (see `services::transcode_service`).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TranscodeConfig {
+    /// Format of the copy
+    pub format: TranscodeFormat,
+    /// Target bitrate in kbps (e.g. 320 for MP3, 160 for Opus)
+    pub bitrate_kbps: u32,
+}
+
 /// Complete application settings, persisted as `{app_data}/settings.json`.
 ///
 /// This struct contains all user-configurable preferences, organized into
@@ -146,7 +282,8 @@ pub struct AppSettings {
 
     /// Output directory for downloaded music and videos.
     /// An empty string means "use the platform's default Music directory",
-    /// which is resolved at runtime (e.g., `~/Music` on macOS).
+    /// which is resolved at runtime (e.g., `~/Music/Apple Music` on macOS)
+    /// by `config_service::resolve_default_output_path()`.
     pub output_path: String,
 
     /// Metadata language as an IETF BCP 47 language tag (e.g., `"en-US"`,
@@ -160,11 +297,142 @@ pub struct AppSettings {
     /// Maps to `GamdlOptions::overwrite` / GAMDL `--overwrite`.
     pub overwrite: bool,
 
+    /// What to do with files that already exist in the output folder:
+    /// skip, replace or keep both (see `OverwritePolicy`). `None` (the
+    /// default, and settings saved before this option existed) follows
+    /// `overwrite` instead; read it through `effective_overwrite_policy()`.
+    /// The UI keeps `overwrite` in step (true only for `Replace`).
+    pub overwrite_policy: Option<OverwritePolicy>,
+
+    /// Whether downloads are written to a staging folder of their own in
+    /// the app data directory and moved into `output_path` only once they
+    /// succeed, so a watched library folder never holds half-downloaded
+    /// albums. Staged tracks aren't compared with the library, so tracks
+    /// already in it are downloaded again (then kept or replaced as
+    /// `overwrite_policy` says). Output folders on a network filesystem are
+    /// staged even with this off, unless the policy is Skip. See
+    /// `services::staging_service`.
+    pub use_staging_dir: bool,
+
+    /// Whether an Apple Music artist URL is expanded into one download per
+    /// album (listed via the catalog API) instead of a single job for the
+    /// whole catalogue. Needs the MusicKit credentials; without them the
+    /// artist URL is downloaded as one job. See
+    /// `services::artist_expansion_service`.
+    pub expand_artist_urls: bool,
+
+    /// Whether re-submitting an Apple Music playlist URL downloads only the
+    /// tracks added since the last sync. The catalog IDs downloaded per
+    /// playlist are remembered in `playlist_sync.json`; tracks removed from
+    /// the playlist are never re-added. Needs the MusicKit credentials;
+    /// without them the playlist is downloaded in full. See
+    /// `services::playlist_sync_service`.
+    pub playlist_sync: bool,
+
+    /// Whether `start_download` asks the Apple Music catalog which tracks
+    /// of an album or playlist are still available, and leaves the others
+    /// out of the GAMDL run (reported as skipped, "unavailable") instead of
+    /// letting each one fail. Needs the MusicKit credentials. See
+    /// `services::track_availability_service`.
+    pub skip_unavailable_tracks: bool,
+
+    /// Whether `start_download` swaps album and song URLs for their
+    /// explicit or clean version, track by track, before queueing. GAMDL
+    /// has no option for this, so the other version is looked up in the
+    /// catalog (needs the MusicKit credentials). `AsIs` downloads what the
+    /// URL points at. See `services::content_rating_service`.
+    pub content_preference: ContentPreference,
+
+    /// Whether `start_download` checks the toolchain (cookies, Python,
+    /// GAMDL, FFmpeg, mp4decrypt, output folder, URLs) before queueing and
+    /// refuses the download if anything is missing. See
+    /// `services::preflight_service`.
+    pub preflight_checks: bool,
+
+    /// Whether a waiting download with a higher `DownloadRequest::priority`
+    /// stops a running lower-priority one when every slot is taken. The
+    /// stopped download is re-queued and resumes with the album tracks it
+    /// still needs. When off, priority only orders the waiting items. See
+    /// `DownloadQueue::preempt_for_priority()`.
+    pub preempt_lower_priority: bool,
+
     /// Whether to automatically check for GAMDL/tool updates on startup.
     /// When enabled, the app queries PyPI and GitHub releases for newer
     /// versions of GAMDL and its dependencies (see `dependency.rs`).
     pub auto_check_updates: bool,
 
+    /// Whether to quit the app once every download has finished. When the
+    /// last active download completes and nothing is queued, MeedyaDL emits
+    /// `queue-drained`, waits a short grace period (cancelled if new
+    /// downloads arrive), lets artwork/companion downloads finish, and
+    /// exits. See `services::auto_quit`.
+    pub auto_quit_when_done: bool,
+
+    // ================================================================
+    // Download Schedule
+    // ================================================================
+
+    /// Whether downloads only run inside the daily window from
+    /// `schedule_start` to `schedule_end`. Outside it, queued downloads
+    /// wait and start automatically when the window opens.
+    /// See `services::download_schedule`.
+    pub schedule_enabled: bool,
+
+    /// Time the download window opens, local time "HH:MM" (24-hour).
+    pub schedule_start: String,
+
+    /// Time the download window closes, local time "HH:MM" (24-hour).
+    /// An end earlier than the start means the window crosses midnight.
+    pub schedule_end: String,
+
+    /// What happens to running downloads when the window closes: `true`
+    /// stops and re-queues them (already saved tracks are skipped when
+    /// they restart), `false` lets them finish.
+    pub schedule_pause_at_close: bool,
+
+    /// Minimum free space (GiB) to keep on the output drive. When set and
+    /// the drive has less available, queued downloads are held (a
+    /// `low-disk-space` event is emitted) instead of starting, and resume
+    /// once space frees up. `None` disables the check.
+    pub min_free_space_gb: Option<f64>,
+
+    /// How many times a download that failed with a network error (dropped
+    /// connection, timeout, 5xx) is re-queued before it is marked failed.
+    /// Each queue item keeps its own count, separate from
+    /// `rate_limit_retry_budget`. Codec errors never retry; they move on
+    /// to the next codec of the fallback chain straight away.
+    pub network_retry_budget: u32,
+
+    /// How many times a download that Apple Music rate-limited (HTTP 429)
+    /// is re-queued, each after a cooldown, before it is marked failed.
+    pub rate_limit_retry_budget: u32,
+
+    /// Seconds a running download may go without printing a line before
+    /// GAMDL is taken to be hung (e.g. on a stuck HLS segment) and killed.
+    /// The download then fails with a `stall` error and is retried like a
+    /// network error. Progress lines count as output, so a slow download
+    /// that's still moving isn't stopped. `None` (default) never times out.
+    pub download_stall_timeout_secs: Option<u32>,
+
+    /// How many post-download background tasks (animated artwork, companion
+    /// downloads, music video lyrics / cover resize / FLAC conversion) may
+    /// run at once, across all downloads. Further tasks wait for a free
+    /// slot. Separate from the download queue's own concurrency.
+    pub max_background_tasks: u32,
+
+    /// Seconds to wait after a download finishes before the next queued one
+    /// starts, so back-to-back albums don't trip Apple Music's rate
+    /// limiting. The first download of a run starts straight away. 0
+    /// disables the pause.
+    pub inter_download_delay_secs: u32,
+
+    /// Seconds after which a completed download is removed from the queue,
+    /// for a self-cleaning queue. The removal is called off if the user
+    /// interacts with the item first (`keep_download`). Failed and
+    /// cancelled downloads are never removed automatically. `None` keeps
+    /// completed items until they're cleared by hand.
+    pub auto_clear_completed_after_secs: Option<u32>,
+
     // ================================================================
     // Audio Quality Defaults
     // ================================================================
@@ -195,6 +463,19 @@ pub struct AppSettings {
     /// Maps to `GamdlOptions::music_video_remux_format`.
     pub default_video_remux_format: String,
 
+    /// Quality for uploaded (post) videos: `"best"` downloads the highest
+    /// available. `None` keeps GAMDL's default. GAMDL's `"ask"` isn't
+    /// offered: it prompts on a terminal the download doesn't have. Maps to `GamdlOptions::uploaded_video_quality` / GAMDL
+    /// `--uploaded-video-quality`; other values are rejected on save.
+    pub uploaded_video_quality: Option<String>,
+
+    /// Whether the music videos on albums and playlists are downloaded
+    /// along with the songs. GAMDL skips them by default. Maps to
+    /// `GamdlOptions::disable_music_video_skip` / GAMDL
+    /// `--disable-music-video-skip`. When on, `keep_mv_lyrics` has nothing
+    /// to do, since the videos' lyrics come with them. Default: `false`.
+    pub download_album_music_videos: bool,
+
     // ================================================================
     // Fallback Quality Chains
     // ================================================================
@@ -212,6 +493,14 @@ pub struct AppSettings {
     /// reorder and prune this list in the settings UI.
     pub music_fallback_chain: Vec<SongCodec>,
 
+    /// Whether `start_download` asks the Apple Music catalog which codecs
+    /// each track of an album or playlist offers, and splits the download
+    /// into one job per codec: each track gets the first codec of
+    /// `music_fallback_chain` it supports, rather than the whole album
+    /// falling back together. Needs the MusicKit credentials. See
+    /// `services::track_availability_service`.
+    pub per_track_best_codec: bool,
+
     /// Ordered list of video resolutions to try if the preferred
     /// resolution is not available for a given music video. Works the
     /// same way as `music_fallback_chain`.
@@ -228,9 +517,60 @@ pub struct AppSettings {
     /// Specialist format files receive a codec suffix in their filenames
     /// (e.g., `[Dolby Atmos]`, `[Lossless]`) while the most universally
     /// compatible companion uses a clean filename. All versions are saved
-    /// in the same album folder. See `CompanionMode` for available modes.
+    /// in the same album folder unless a tier has a subfolder in
+    /// `companion_subfolders`. See `CompanionMode` for available modes.
     pub companion_mode: CompanionMode,
 
+    /// Per-tier subfolders for companion downloads, keyed by the tier's
+    /// codec (`"alac"` for the lossless tier, `"aac"` for the lossy one),
+    /// e.g. `{"alac": "Lossless"}`. A tier listed here is saved in that
+    /// folder inside the album folder, with clean filenames, instead of
+    /// getting a codec suffix next to the primary. Tiers not listed (the
+    /// default) keep the suffix rules of `companion_mode`.
+    pub companion_subfolders: HashMap<String, String>,
+
+    /// When enabled, an ALAC download is first fetched as AAC so the album
+    /// is playable quickly; once it completes, the lossless version is
+    /// downloaded in the background and replaces it (or sits beside it as
+    /// the lossless companion, following `companion_mode`).
+    pub preview_then_upgrade: bool,
+
+    // ================================================================
+    // Lossless Output Format
+    // ================================================================
+
+    /// When enabled, ALAC downloads are transcoded from M4A to FLAC with
+    /// FFmpeg once they finish, keeping tags and the embedded cover. Atmos
+    /// and lossy downloads are never touched. See
+    /// `services::flac_conversion_service`.
+    pub convert_lossless_to_flac: bool,
+
+    /// Keep the original ALAC M4A next to the FLAC file after conversion.
+    /// When disabled (the default), the M4A is deleted once the FLAC has
+    /// been written successfully.
+    pub keep_m4a_after_flac: bool,
+
+    // ================================================================
+    // Transcode Companion
+    // ================================================================
+
+    /// When set, every AAC or ALAC track a download saved is also
+    /// transcoded to MP3 or Opus with FFmpeg, keeping tags and the cover.
+    /// The copy is saved next to the track with a format suffix
+    /// (`01 Song [MP3].mp3`). Runs before the FLAC conversion, which may
+    /// delete the M4A. See `services::transcode_service`. Default: `None`.
+    pub transcode_companion: Option<TranscodeConfig>,
+
+    // ================================================================
+    // Output Verification
+    // ================================================================
+
+    /// When enabled, every file a download wrote is decoded once with
+    /// FFmpeg before the item completes. Files that don't decode are
+    /// deleted and re-downloaded once; see
+    /// `services::audio_verify_service`.
+    pub verify_audio: bool,
+
     // ================================================================
     // Lyrics
     // ================================================================
@@ -259,6 +599,20 @@ pub struct AppSettings {
     /// Maps to `GamdlOptions::synced_lyrics_only`.
     pub synced_lyrics_only: bool,
 
+    /// Whether to keep the lyrics of an album's music videos when the
+    /// videos themselves are skipped (GAMDL's default). After the album
+    /// downloads, a lyrics-only GAMDL run fetches the music videos' TTML
+    /// lyrics and saves each next to the audio track with the matching
+    /// title. See `services::mv_lyrics_service`.
+    pub keep_mv_lyrics: bool,
+
+    /// Format to convert downloaded TTML lyrics sidecars to (`Srt`, `Vtt`
+    /// or `Lrc`), written next to the TTML after each download. `None`
+    /// leaves them as downloaded. Only has something to convert when
+    /// `synced_lyrics_format` is `Ttml` or `keep_mv_lyrics` saved music
+    /// video lyrics. See `services::lyrics_conversion_service`.
+    pub convert_lyrics_to: Option<LyricsConversion>,
+
     // ================================================================
     // Cover Art
     // ================================================================
@@ -278,9 +632,39 @@ pub struct AppSettings {
     /// a value of 10000 requests 10000x10000 — Apple Music's CDN returns
     /// the highest available resolution (typically up to ~3000x3000).
     /// Maps to `GamdlOptions::cover_size` (which formats it as `"WxH"`
-    /// for the CLI).
+    /// for the CLI). With MusicKit credentials configured, each album's
+    /// request is capped at its native size (`artwork_probe_service`).
     pub cover_size: u32,
 
+    /// Cover size per codec, overriding `cover_size` for downloads and
+    /// companions in that codec (e.g. full size for ALAC, 600 for AAC
+    /// copies meant for a phone). Keyed by the codec's CLI name, e.g.
+    /// `{"alac": 10000, "aac": 600}`. Codecs not listed use `cover_size`;
+    /// see `cover_size_for()`.
+    pub cover_size_by_codec: HashMap<SongCodec, u32>,
+
+    /// Size in pixels for the cover embedded in each track, when it should
+    /// be smaller than `cover_size` (which the saved sidecar keeps). After
+    /// the download, `cover_resize_service` scales the embedded cover of
+    /// every M4A it wrote down with FFmpeg. `None` embeds the cover at
+    /// `cover_size`, as GAMDL writes it.
+    pub embedded_cover_size: Option<u32>,
+
+    /// Additional sizes in pixels to save the cover at, next to the
+    /// sidecar cover, as `cover-{size}.jpg` (e.g. `[600]` for a
+    /// thumbnail). After the download, `cover_resize_service` scales the
+    /// saved cover rather than downloading it again. Only used with
+    /// `save_cover` on; sizes at or above `cover_size` are skipped.
+    pub extra_cover_sizes: Vec<u32>,
+
+    /// Whether a track whose cover art can't be fetched is still saved.
+    /// GAMDL fails the whole track when its cover fails; with this on, a
+    /// download whose tracks failed only on their cover runs once more
+    /// with the cover excluded (`--exclude-tags cover`, no sidecar), so the
+    /// audio is saved and tagged. The count is shown on the item
+    /// (`QueueItemStatus::cover_missing_tracks`).
+    pub continue_on_cover_failure: bool,
+
     // ================================================================
     // Animated Artwork (Motion Cover Art)
     // ================================================================
@@ -308,6 +692,17 @@ pub struct AppSettings {
     ///   cross-compatible hiding mechanism on Linux.
     pub hide_animated_artwork: bool,
 
+    /// Which animated artwork variants to download. Variants not listed
+    /// here are skipped even when the album provides them. Defaults to
+    /// square and portrait, which is what MeedyaDL has always fetched.
+    pub animated_artwork_variants: Vec<ArtworkVariant>,
+
+    /// Maximum height (in pixels) of downloaded animated artwork. When set,
+    /// FFmpeg downscales taller streams to this height (preserving aspect
+    /// ratio) instead of copying them at full resolution. `None` (default)
+    /// keeps the original resolution.
+    pub animated_artwork_max_height: Option<u32>,
+
     /// Apple MusicKit Team ID for API authentication. This is the
     /// 10-character team identifier from the Apple Developer portal
     /// (e.g., `"ABCDE12345"`). Required when `animated_artwork_enabled`
@@ -344,6 +739,14 @@ pub struct AppSettings {
     /// Default: `"{artist}/Unknown Album"`.
     pub no_album_folder_template: String,
 
+    /// Folder naming template for single-track downloads (`?i=` album
+    /// links and `/song/` links), e.g. `"{artist}/Singles"` to collect an
+    /// artist's singles in one folder. Replaces the album, compilation and
+    /// no-album folder templates for those downloads only, since GAMDL
+    /// files a single with its album's metadata. `None` (the default) or
+    /// blank keeps the regular templates.
+    pub singles_folder_template: Option<String>,
+
     /// File naming template for tracks on single-disc albums.
     /// Default: `"{track:02d} {title}"` -- zero-padded track number + title.
     pub single_disc_file_template: String,
@@ -360,6 +763,12 @@ pub struct AppSettings {
     /// Default: `"Playlists/{playlist_artist}/{playlist_title}"`.
     pub playlist_file_template: String,
 
+    /// Whether GAMDL writes an M3U8 playlist file for each playlist it
+    /// downloads, named by `playlist_file_template`. This is GAMDL's own
+    /// file, written as the playlist downloads, unlike the `export_m3u`
+    /// command's. Maps to `GamdlOptions::save_playlist`.
+    pub save_playlist_m3u: bool,
+
     // ================================================================
     // Tool Paths (None = use managed/bundled tools)
     // ================================================================
@@ -403,6 +812,50 @@ pub struct AppSettings {
     /// Default: `Ffmpeg` because FFmpeg is a required dependency anyway.
     pub remux_mode: RemuxMode,
 
+    /// Whether importing a queue file combines its entries into a single
+    /// batch job: one GAMDL process reads all URLs from a temp file
+    /// (`--read-urls-as-txt`), saving the per-process startup cost of
+    /// hundreds of separate jobs. The tradeoff is granularity: a network
+    /// retry or codec fallback applies to the whole batch, progress is
+    /// reported as tracks done rather than per album, and per-album extras
+    /// (animated artwork, music video lyrics, companion downloads) are
+    /// skipped. Entries with their own options are still imported
+    /// individually. Default: `false`.
+    pub batch_mode: bool,
+
+    /// Whether GAMDL should suppress Python exception output. Maps to
+    /// `GamdlOptions::no_exceptions` / GAMDL `--no-exceptions`. When on, a
+    /// crash leaves no traceback to report, so failures show a less
+    /// specific message. Default: `false`.
+    pub suppress_gamdl_exceptions: bool,
+
+    /// Unix niceness (`-20` to `19`) to run GAMDL and its FFmpeg jobs at,
+    /// so downloads yield the CPU to other work on a shared machine.
+    /// Windows maps it to a priority class (see
+    /// `platform::windows_priority_class()`). Negative values need root on
+    /// Unix. Maps to `GamdlOptions::process_niceness`. Default: `None`
+    /// (normal priority).
+    pub process_niceness: Option<i32>,
+
+    /// Whether GAMDL reads the user's own config file
+    /// (`~/.gamdl/config.ini`) instead of the GUI being the only source of
+    /// settings. When off (the default), GAMDL gets `--config-path` pointing
+    /// at the config.ini MeedyaDL syncs from these settings. When on,
+    /// `--config-path` is omitted and the synced config isn't written, so
+    /// GAMDL loads its default config file.
+    ///
+    /// Precedence when on: the command-line flags MeedyaDL passes (from
+    /// these settings and per-download overrides) still win over the
+    /// user's file, which only fills in options MeedyaDL doesn't set.
+    /// Default: `false`.
+    pub use_gamdl_config_file: bool,
+
+    /// Whether each download's raw GAMDL output is saved to a log file of
+    /// its own (`{app_data}/logs/downloads/{id}.log`), for the
+    /// `get_download_log` / `open_download_log` commands. See
+    /// `services::download_log_service`. Default: `false`.
+    pub save_download_logs: bool,
+
     /// Whether to use the wrapper/amdecrypt authentication system for
     /// accessing DRM-protected content. When `false` (default), standard
     /// cookie-based authentication is used. Maps to
@@ -414,6 +867,26 @@ pub struct AppSettings {
     /// `"http://127.0.0.1:30020"` (local server).
     pub wrapper_account_url: String,
 
+    /// Whether to start the local HTTP control API for scripts (enqueue,
+    /// status, cancel, retry). It listens on 127.0.0.1 only and needs the
+    /// bearer token stored in the keychain. Read at startup, so changes
+    /// apply after a restart. See `services::headless_api`. Default: `false`.
+    pub headless_api_enabled: bool,
+
+    /// Port the headless API listens on (127.0.0.1 only). Default: `18420`.
+    pub headless_api_port: u16,
+
+    /// Proxy URL for plain-HTTP traffic (e.g., `"http://proxy.corp:8080"`).
+    /// `None` or empty = direct connection. Passed to GAMDL as the
+    /// `http_proxy` environment variable and used by the app's own HTTP
+    /// client for dependency downloads and update checks.
+    pub http_proxy: Option<String>,
+
+    /// Proxy URL for HTTPS traffic. `None` or empty = direct connection.
+    /// Passed to GAMDL as the `https_proxy` environment variable and used
+    /// by the app's own HTTP client.
+    pub https_proxy: Option<String>,
+
     /// Maximum filename length in characters. `None` = no truncation
     /// (OS limits still apply: 255 bytes on most filesystems). Useful
     /// for tracks with very long titles that would exceed filesystem
@@ -426,6 +899,18 @@ pub struct AppSettings {
     /// / GAMDL `--fetch-extra-tags`.
     pub fetch_extra_tags: bool,
 
+    /// Whether to tag every track with the album's release date instead of
+    /// its own per-track date. Keeps multi-year compilations sorted together
+    /// in library managers. Maps to `GamdlOptions::use_album_date` / GAMDL
+    /// `--use-album-date`.
+    pub use_album_date: bool,
+
+    /// strftime pattern for the date tag (e.g., `"%Y"` for year-only
+    /// dates, which sort more cleanly in some library managers). `None`
+    /// keeps GAMDL's default full timestamp. Maps to
+    /// `GamdlOptions::date_tag_template` / GAMDL `--date-tag-template`.
+    pub date_tag_template: Option<String>,
+
     /// Tags to exclude from metadata embedding. Each entry is a tag name
     /// (e.g., `"lyrics"`, `"comment"`). Stored as a `Vec` in settings
     /// but joined with commas when passed to GAMDL's `--exclude-tags`.
@@ -472,7 +957,8 @@ impl Default for AppSettings {
     ///   overrides this to TTML at download time.
     /// - **`output_path: ""`** -- An empty string signals the app to use
     ///   the platform's default Music directory at runtime (resolved by
-    ///   `dirs::audio_dir()` or equivalent).
+    ///   `config_service::resolve_default_output_path()`, which honors
+    ///   `XDG_MUSIC_DIR` on Linux).
     /// - **Templates** -- Use GAMDL's own default templates so that files
     ///   are organized identically to a standalone GAMDL installation.
     fn default() -> Self {
@@ -485,8 +971,52 @@ impl Default for AppSettings {
             language: "en-US".to_string(),
             // Do not overwrite by default to prevent accidental data loss.
             overwrite: false,
+            // Follow `overwrite` until the user picks a policy: existing
+            // files are kept and their tracks skipped.
+            overwrite_policy: None,
+            // GAMDL writes straight into the output folder unless the user
+            // opts in to staging.
+            use_staging_dir: false,
+            // Per-album jobs give artist downloads progress and retries.
+            expand_artist_urls: true,
+            // Playlists download in full unless the user opts in to syncing.
+            playlist_sync: false,
+            // GAMDL reports unavailable tracks itself unless the user opts in.
+            skip_unavailable_tracks: false,
+            // Download the version the user linked to.
+            content_preference: ContentPreference::AsIs,
+            // Downloads are queued without a pre-flight check unless the
+            // user opts in; the setup wizard already verified the tools.
+            preflight_checks: false,
+            // Priorities only reorder waiting items unless the user opts in.
+            preempt_lower_priority: false,
             // Check for updates on launch so users get security/bug fixes.
             auto_check_updates: true,
+            // Stay open after downloads finish unless the user opts in.
+            auto_quit_when_done: false,
+
+            // --- Download schedule ---
+            // Off by default; the window defaults to overnight hours.
+            schedule_enabled: false,
+            schedule_start: "23:00".to_string(),
+            schedule_end: "07:00".to_string(),
+            // Let a download that is already running finish.
+            schedule_pause_at_close: false,
+            // No free-space guard unless the user sets a threshold.
+            min_free_space_gb: None,
+            // Three quick retries ride out most connection blips.
+            network_retry_budget: 3,
+            // Rate limits clear slowly; two cooled-down retries.
+            rate_limit_retry_budget: 2,
+            // A hung GAMDL is left to the user to cancel.
+            download_stall_timeout_secs: None,
+            // Two FFmpeg/GAMDL helpers at a time keep a burst of finished
+            // albums from saturating the CPU.
+            max_background_tasks: 2,
+            // No pause between downloads unless the user hits rate limits.
+            inter_download_delay_secs: 0,
+            // Completed items stay until the user clears them.
+            auto_clear_completed_after_secs: None,
 
             // --- Audio quality ---
             // Default to the highest-quality codec (lossless ALAC).
@@ -499,6 +1029,10 @@ impl Default for AppSettings {
             // m4v is Apple's preferred container; some players handle it
             // better than mp4 for Apple-sourced content.
             default_video_remux_format: "m4v".to_string(),
+            // GAMDL's own default quality for uploaded videos
+            uploaded_video_quality: None,
+            // GAMDL skips the music videos on albums and playlists
+            download_album_music_videos: false,
 
             // --- Fallback chains (as specified in the project brief) ---
             fallback_enabled: true,
@@ -510,6 +1044,8 @@ impl Default for AppSettings {
                 SongCodec::Aac,         // 5. AAC (256kbps at up to 48kHz) -- standard lossy
                 SongCodec::AacLegacy,   // 6. AAC Legacy (256kbps at up to 44.1kHz) -- broadest compat
             ],
+            // Off by default: the whole album falls back together.
+            per_track_best_codec: false,
             video_fallback_chain: vec![
                 VideoResolution::P2160, // 1. H.265 2160p (4K UHD)
                 VideoResolution::P1440, // 2. H.265 1440p (QHD)
@@ -526,6 +1062,23 @@ impl Default for AppSettings {
             // (lossless) companion so the user has a universally playable
             // stereo version alongside the spatial audio version.
             companion_mode: CompanionMode::AtmosToLossless,
+            // Companions sit next to the primary, told apart by suffixes.
+            companion_subfolders: HashMap::new(),
+            // Off by default: ALAC downloads are fetched in ALAC directly.
+            preview_then_upgrade: false,
+
+            // --- Lossless output format ---
+            // Off by default: GAMDL's ALAC-in-M4A output is kept as-is.
+            convert_lossless_to_flac: false,
+            keep_m4a_after_flac: false,
+
+            // --- Transcode companion ---
+            // Off by default: M4A plays on most devices.
+            transcode_companion: None,
+
+            // --- Output verification ---
+            // Off by default: decoding every file takes FFmpeg time per track.
+            verify_audio: false,
 
             // --- Lyrics ---
             // Enabled by default: embed lyrics in audio metadata AND keep
@@ -537,6 +1090,10 @@ impl Default for AppSettings {
             no_synced_lyrics: false,
             // Download audio + lyrics, not lyrics-only.
             synced_lyrics_only: false,
+            // Opt-in: costs two extra lyrics-only GAMDL runs per album.
+            keep_mv_lyrics: false,
+            // Keep TTML sidecars as downloaded.
+            convert_lyrics_to: None,
 
             // --- Cover art ---
             // Save cover art by default -- most users want artwork files.
@@ -547,6 +1104,14 @@ impl Default for AppSettings {
             // CDN. The CDN returns the largest version it has (typically 3000x3000),
             // so this effectively means "give me the best you have".
             cover_size: 10000,
+            // Every codec uses cover_size.
+            cover_size_by_codec: HashMap::new(),
+            // Embed the same cover as the sidecar; no post-download step.
+            embedded_cover_size: None,
+            // No extra cover files; only the sidecar cover is saved.
+            extra_cover_sizes: Vec::new(),
+            // A cover failure fails the track, as GAMDL does by itself.
+            continue_on_cover_failure: false,
 
             // --- Animated artwork ---
             // Disabled by default: requires Apple Developer credentials.
@@ -556,6 +1121,9 @@ impl Default for AppSettings {
             // Hide animated artwork files by default to keep album folders clean.
             // Files remain accessible by name for media players and scripts.
             hide_animated_artwork: true,
+            // Square + portrait, uncapped -- the original behavior.
+            animated_artwork_variants: vec![ArtworkVariant::Square, ArtworkVariant::Portrait],
+            animated_artwork_max_height: None,
             musickit_team_id: None,
             musickit_key_id: None,
 
@@ -564,10 +1132,14 @@ impl Default for AppSettings {
             album_folder_template: "{album_artist}/{album}".to_string(),
             compilation_folder_template: "Compilations/{album}".to_string(),
             no_album_folder_template: "{artist}/Unknown Album".to_string(),
+            // Singles follow no_album_folder_template unless set
+            singles_folder_template: None,
             single_disc_file_template: "{track:02d} {title}".to_string(),
             multi_disc_file_template: "{disc}-{track:02d} {title}".to_string(),
             no_album_file_template: "{title}".to_string(),
             playlist_file_template: "Playlists/{playlist_artist}/{playlist_title}".to_string(),
+            // Off by default: playlists are downloaded as tracks only.
+            save_playlist_m3u: false,
 
             // --- Tool paths ---
             // All None = use managed (auto-installed) tools from the app's
@@ -586,17 +1158,36 @@ impl Default for AppSettings {
             // FFmpeg is the default remuxer because it is a required
             // dependency for GAMDL anyway.
             remux_mode: RemuxMode::Ffmpeg,
+            // One job per imported entry keeps retries and extras per album.
+            batch_mode: false,
+            suppress_gamdl_exceptions: false,
+            // Downloads run at normal priority.
+            process_niceness: None,
+            // The GUI is the sole source of GAMDL settings by default.
+            use_gamdl_config_file: false,
+            // Per-download logs are a troubleshooting aid; off until needed.
+            save_download_logs: false,
             // Wrapper/amdecrypt is disabled by default. Most users use
             // cookie-based auth. The wrapper is an advanced feature for
             // accessing certain DRM-protected streams.
             use_wrapper: false,
             // Default wrapper URL assumes a locally-running server.
             wrapper_account_url: "http://127.0.0.1:30020".to_string(),
+            // Scripts can't drive the queue unless the user opts in.
+            headless_api_enabled: false,
+            headless_api_port: 18420,
+            // Direct connections unless the user configures a proxy.
+            http_proxy: None,
+            https_proxy: None,
             // No filename truncation by default (OS limits still apply).
             truncate: None,
             // Fetch extra metadata (normalization, smooth playback info, etc.)
             // by default. Richer metadata is worth the small extra API overhead.
             fetch_extra_tags: true,
+            // Per-track release dates by default, matching GAMDL's own default.
+            use_album_date: false,
+            // GAMDL's own date format unless the user picks one.
+            date_tag_template: None,
             // No tags excluded by default -- embed all available metadata.
             exclude_tags: Vec::new(),
 
@@ -609,6 +1200,29 @@ impl Default for AppSettings {
     }
 }
 
+impl AppSettings {
+    /// The overwrite policy in effect: `overwrite_policy`, or for settings
+    /// saved before it existed, `Replace` if `overwrite` is on and `Skip`
+    /// otherwise.
+    pub fn effective_overwrite_policy(&self) -> OverwritePolicy {
+        self.overwrite_policy.unwrap_or(if self.overwrite {
+            OverwritePolicy::Replace
+        } else {
+            OverwritePolicy::Skip
+        })
+    }
+
+    /// The cover size for a download in `codec`: its entry in
+    /// `cover_size_by_codec`, or `cover_size` for codecs without one (and
+    /// when the codec isn't known).
+    pub fn cover_size_for(&self, codec: Option<&SongCodec>) -> u32 {
+        codec
+            .and_then(|codec| self.cover_size_by_codec.get(codec))
+            .copied()
+            .unwrap_or(self.cover_size)
+    }
+}
+
 // ============================================================
 // Unit Tests
 // ============================================================
@@ -757,7 +1371,35 @@ mod tests {
         assert_eq!(deserialized.output_path, settings.output_path);
         assert_eq!(deserialized.language, settings.language);
         assert_eq!(deserialized.overwrite, settings.overwrite);
+        assert_eq!(deserialized.overwrite_policy, settings.overwrite_policy);
+        assert_eq!(deserialized.use_staging_dir, settings.use_staging_dir);
+        assert_eq!(deserialized.expand_artist_urls, settings.expand_artist_urls);
+        assert_eq!(deserialized.playlist_sync, settings.playlist_sync);
+        assert_eq!(deserialized.skip_unavailable_tracks, settings.skip_unavailable_tracks);
+        assert_eq!(deserialized.content_preference, settings.content_preference);
+        assert_eq!(deserialized.preflight_checks, settings.preflight_checks);
+        assert_eq!(deserialized.preempt_lower_priority, settings.preempt_lower_priority);
         assert_eq!(deserialized.auto_check_updates, settings.auto_check_updates);
+        assert_eq!(deserialized.auto_quit_when_done, settings.auto_quit_when_done);
+
+        // Download schedule
+        assert_eq!(deserialized.schedule_enabled, settings.schedule_enabled);
+        assert_eq!(deserialized.schedule_start, settings.schedule_start);
+        assert_eq!(deserialized.schedule_end, settings.schedule_end);
+        assert_eq!(deserialized.schedule_pause_at_close, settings.schedule_pause_at_close);
+        assert_eq!(deserialized.min_free_space_gb, settings.min_free_space_gb);
+        assert_eq!(deserialized.network_retry_budget, settings.network_retry_budget);
+        assert_eq!(deserialized.rate_limit_retry_budget, settings.rate_limit_retry_budget);
+        assert_eq!(
+            deserialized.download_stall_timeout_secs,
+            settings.download_stall_timeout_secs
+        );
+        assert_eq!(deserialized.max_background_tasks, settings.max_background_tasks);
+        assert_eq!(deserialized.inter_download_delay_secs, settings.inter_download_delay_secs);
+        assert_eq!(
+            deserialized.auto_clear_completed_after_secs,
+            settings.auto_clear_completed_after_secs
+        );
 
         // Audio quality
         assert_eq!(deserialized.default_song_codec, settings.default_song_codec);
@@ -766,28 +1408,53 @@ mod tests {
         assert_eq!(deserialized.default_video_resolution, settings.default_video_resolution);
         assert_eq!(deserialized.default_video_codec_priority, settings.default_video_codec_priority);
         assert_eq!(deserialized.default_video_remux_format, settings.default_video_remux_format);
+        assert_eq!(deserialized.uploaded_video_quality, settings.uploaded_video_quality);
+        assert_eq!(
+            deserialized.download_album_music_videos,
+            settings.download_album_music_videos
+        );
 
         // Fallback
         assert_eq!(deserialized.fallback_enabled, settings.fallback_enabled);
         assert_eq!(deserialized.music_fallback_chain.len(), settings.music_fallback_chain.len());
+        assert_eq!(deserialized.per_track_best_codec, settings.per_track_best_codec);
         assert_eq!(deserialized.video_fallback_chain.len(), settings.video_fallback_chain.len());
 
         // Companion downloads
         assert_eq!(deserialized.companion_mode, settings.companion_mode);
+        assert_eq!(deserialized.companion_subfolders, settings.companion_subfolders);
+        assert_eq!(deserialized.preview_then_upgrade, settings.preview_then_upgrade);
+
+        // Lossless output format
+        assert_eq!(deserialized.convert_lossless_to_flac, settings.convert_lossless_to_flac);
+        assert_eq!(deserialized.keep_m4a_after_flac, settings.keep_m4a_after_flac);
+        assert_eq!(
+            deserialized.transcode_companion,
+            settings.transcode_companion
+        );
+        assert_eq!(deserialized.verify_audio, settings.verify_audio);
 
         // Lyrics
         assert_eq!(deserialized.synced_lyrics_format, settings.synced_lyrics_format);
         assert_eq!(deserialized.no_synced_lyrics, settings.no_synced_lyrics);
         assert_eq!(deserialized.synced_lyrics_only, settings.synced_lyrics_only);
+        assert_eq!(deserialized.keep_mv_lyrics, settings.keep_mv_lyrics);
+        assert_eq!(deserialized.convert_lyrics_to, settings.convert_lyrics_to);
 
         // Cover art
         assert_eq!(deserialized.save_cover, settings.save_cover);
         assert_eq!(deserialized.cover_format, settings.cover_format);
         assert_eq!(deserialized.cover_size, settings.cover_size);
+        assert_eq!(deserialized.cover_size_by_codec, settings.cover_size_by_codec);
+        assert_eq!(deserialized.embedded_cover_size, settings.embedded_cover_size);
+        assert_eq!(deserialized.extra_cover_sizes, settings.extra_cover_sizes);
+        assert_eq!(deserialized.continue_on_cover_failure, settings.continue_on_cover_failure);
 
         // Animated artwork
         assert_eq!(deserialized.animated_artwork_enabled, settings.animated_artwork_enabled);
         assert_eq!(deserialized.hide_animated_artwork, settings.hide_animated_artwork);
+        assert_eq!(deserialized.animated_artwork_variants, settings.animated_artwork_variants);
+        assert_eq!(deserialized.animated_artwork_max_height, settings.animated_artwork_max_height);
         assert_eq!(deserialized.musickit_team_id, settings.musickit_team_id);
         assert_eq!(deserialized.musickit_key_id, settings.musickit_key_id);
 
@@ -795,13 +1462,23 @@ mod tests {
         assert_eq!(deserialized.album_folder_template, settings.album_folder_template);
         assert_eq!(deserialized.compilation_folder_template, settings.compilation_folder_template);
         assert_eq!(deserialized.playlist_file_template, settings.playlist_file_template);
+        assert_eq!(deserialized.save_playlist_m3u, settings.save_playlist_m3u);
+        assert_eq!(deserialized.singles_folder_template, settings.singles_folder_template);
 
         // Advanced
         assert_eq!(deserialized.download_mode, settings.download_mode);
         assert_eq!(deserialized.remux_mode, settings.remux_mode);
+        assert_eq!(deserialized.batch_mode, settings.batch_mode);
+        assert_eq!(deserialized.suppress_gamdl_exceptions, settings.suppress_gamdl_exceptions);
+        assert_eq!(deserialized.process_niceness, settings.process_niceness);
+        assert_eq!(deserialized.use_gamdl_config_file, settings.use_gamdl_config_file);
+        assert_eq!(deserialized.save_download_logs, settings.save_download_logs);
         assert_eq!(deserialized.use_wrapper, settings.use_wrapper);
         assert_eq!(deserialized.wrapper_account_url, settings.wrapper_account_url);
+        assert_eq!(deserialized.headless_api_enabled, settings.headless_api_enabled);
+        assert_eq!(deserialized.headless_api_port, settings.headless_api_port);
         assert_eq!(deserialized.fetch_extra_tags, settings.fetch_extra_tags);
+        assert_eq!(deserialized.use_album_date, settings.use_album_date);
 
         // UI state
         assert_eq!(deserialized.sidebar_collapsed, settings.sidebar_collapsed);
@@ -825,7 +1502,10 @@ mod tests {
         assert!(deserialized.nm3u8dlre_path.is_none());
         assert!(deserialized.amdecrypt_path.is_none());
         assert!(deserialized.truncate.is_none());
+        assert!(deserialized.date_tag_template.is_none());
         assert!(deserialized.theme_override.is_none());
+        assert!(deserialized.http_proxy.is_none());
+        assert!(deserialized.https_proxy.is_none());
     }
 
     /// Verifies that `AppSettings` with all optional fields set to
@@ -841,6 +1521,7 @@ mod tests {
         settings.nm3u8dlre_path = Some("/usr/local/bin/N_m3u8DL-RE".to_string());
         settings.amdecrypt_path = Some("/usr/local/bin/amdecrypt".to_string());
         settings.truncate = Some(200);
+        settings.date_tag_template = Some("%Y".to_string());
         settings.theme_override = Some("dark".to_string());
 
         let json = serde_json::to_string(&settings).unwrap();
@@ -853,6 +1534,7 @@ mod tests {
         assert_eq!(deserialized.nm3u8dlre_path, Some("/usr/local/bin/N_m3u8DL-RE".to_string()));
         assert_eq!(deserialized.amdecrypt_path, Some("/usr/local/bin/amdecrypt".to_string()));
         assert_eq!(deserialized.truncate, Some(200));
+        assert_eq!(deserialized.date_tag_template, Some("%Y".to_string()));
         assert_eq!(deserialized.theme_override, Some("dark".to_string()));
     }
 
@@ -871,4 +1553,66 @@ mod tests {
         let settings = AppSettings::default();
         assert!(settings.auto_check_updates);
     }
+
+    /// Verifies that animated artwork defaults to square + portrait at full
+    /// resolution, and that variants serialize as snake_case strings.
+    #[test]
+    fn default_animated_artwork_variants_are_square_and_portrait() {
+        let settings = AppSettings::default();
+        assert_eq!(
+            settings.animated_artwork_variants,
+            vec![ArtworkVariant::Square, ArtworkVariant::Portrait]
+        );
+        assert_eq!(settings.animated_artwork_max_height, None);
+
+        let json = serde_json::to_string(&ArtworkVariant::Landscape).unwrap();
+        assert_eq!(json, "\"landscape\"");
+    }
+
+    /// Verifies that settings without the retry budget keys fall back to
+    /// the default budgets.
+    #[test]
+    fn retry_budgets_default_when_missing() {
+        let empty: AppSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty.network_retry_budget, 3);
+        assert_eq!(empty.rate_limit_retry_budget, 2);
+    }
+
+    /// Verifies that settings saved before `overwrite_policy` existed keep
+    /// their `overwrite` choice, and that a saved policy wins over it.
+    #[test]
+    fn overwrite_policy_follows_legacy_flag() {
+        let legacy: AppSettings = serde_json::from_str(r#"{ "overwrite": true }"#).unwrap();
+        assert_eq!(legacy.overwrite_policy, None);
+        assert_eq!(legacy.effective_overwrite_policy(), OverwritePolicy::Replace);
+
+        let empty: AppSettings = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty.effective_overwrite_policy(), OverwritePolicy::Skip);
+
+        let versioned: AppSettings =
+            serde_json::from_str(r#"{ "overwrite": true, "overwrite_policy": "version" }"#)
+                .unwrap();
+        assert_eq!(versioned.effective_overwrite_policy(), OverwritePolicy::Version);
+        assert_eq!(
+            AppSettings::default().effective_overwrite_policy(),
+            OverwritePolicy::Skip
+        );
+    }
+
+    /// Verifies that per-codec cover sizes are keyed by codec CLI name and
+    /// that codecs without an entry use `cover_size`.
+    #[test]
+    fn cover_size_by_codec_overrides_cover_size() {
+        let settings: AppSettings = serde_json::from_str(
+            r#"{ "cover_size": 1200, "cover_size_by_codec": { "alac": 10000, "aac-legacy": 600 } }"#,
+        )
+        .unwrap();
+        assert_eq!(settings.cover_size_for(Some(&SongCodec::Alac)), 10000);
+        assert_eq!(settings.cover_size_for(Some(&SongCodec::AacLegacy)), 600);
+        assert_eq!(settings.cover_size_for(Some(&SongCodec::Atmos)), 1200);
+        assert_eq!(settings.cover_size_for(None), 1200);
+
+        let json = serde_json::to_value(&settings).unwrap();
+        assert_eq!(json["cover_size_by_codec"]["aac-legacy"], 600);
+    }
 }
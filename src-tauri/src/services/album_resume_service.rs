@@ -0,0 +1,366 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// album_resume_service.rs -- Resume interrupted album downloads
+// ==============================================================
+//
+// An album download that is interrupted (app closed, crash, shutdown)
+// restarts from the queue on the next launch. GAMDL skips tracks whose
+// files already exist, but only after looking each one up again, so a
+// 40-track album that stopped at track 38 takes a while to get back to
+// where it was.
+//
+// Instead, `download_queue` records which album positions have finished
+// (`TrackProgress`, fed by GAMDL's "Getting track N of M" and "Saved to"
+// lines) and persists them with the item in `queue.json`. When such an
+// item starts again, `resume_urls()` looks up the album's track list in
+// the catalog and hands GAMDL one track URL per remaining position
+// instead of the album URL.
+//
+// Whenever anything doesn't line up -- no MusicKit credentials, the
+// catalog lookup fails, the album's track count changed, the item isn't a
+// single album URL -- the full album URL is used, exactly as before.
+//
+// @see https://developer.apple.com/documentation/applemusicapi/get_a_catalog_album_s_relationship_directly_by_name
+
+use std::collections::BTreeSet;
+
+use tauri::AppHandle;
+
+use crate::services::catalog_service::{self, CatalogResource};
+use crate::services::{artist_expansion_service, config_service};
+
+/// Which tracks of an album download have finished, and which track the
+/// running GAMDL process is working on.
+///
+/// Positions are 1-based, in album order, as GAMDL numbers them in its
+/// "Getting track N of M" lines. `completed` and `track_count` are
+/// persisted; the rest only describes the current run.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TrackProgress {
+    /// Album positions whose files are on disk
+    pub completed: BTreeSet<u32>,
+    /// Number of tracks in the album, as GAMDL reported it
+    pub track_count: Option<u32>,
+    /// Position of the track being downloaded right now
+    current: Option<u32>,
+    /// Album positions of the URLs passed to a narrowed run, in order.
+    /// `None` when the run downloads the whole album.
+    run_positions: Option<Vec<u32>>,
+    /// Number of tracks the current run has started
+    run_started: usize,
+}
+
+impl TrackProgress {
+    /// Restores the persisted part of the progress.
+    pub fn restored(completed: &[u32], track_count: Option<u32>) -> Self {
+        Self {
+            completed: completed.iter().copied().collect(),
+            track_count,
+            ..Self::default()
+        }
+    }
+
+    /// Prepares for a new GAMDL run. `run_positions` are the album
+    /// positions of a narrowed run's URLs (see `resume_urls()`), or `None`
+    /// when GAMDL gets the album URL itself.
+    pub fn begin_run(&mut self, run_positions: Option<Vec<u32>>) {
+        self.current = None;
+        self.run_positions = run_positions;
+        self.run_started = 0;
+    }
+
+    /// Records that GAMDL started a track.
+    ///
+    /// A whole-album run reports the position itself ("Getting track 3 of
+    /// 12"). A narrowed run downloads single tracks, which GAMDL reports
+    /// without a position, so the position is the next one of the run.
+    pub fn track_started(&mut self, track_number: Option<u32>, track_count: Option<u32>) {
+        self.current = match &self.run_positions {
+            Some(positions) => positions.get(self.run_started).copied(),
+            None => {
+                if track_count.is_some() {
+                    self.track_count = track_count;
+                }
+                track_number
+            }
+        };
+        self.run_started += 1;
+    }
+
+    /// Records that the current track's file is on disk (saved, or skipped
+    /// because it already existed).
+    ///
+    /// # Returns
+    /// `true` if this added a position to `completed`.
+    pub fn track_done(&mut self) -> bool {
+        self.current.is_some_and(|n| self.completed.insert(n))
+    }
+
+    /// Returns how much of the album is on disk (0.0 to 1.0), counting the
+    /// track being downloaded as `current_percent` done. `None` while the
+    /// track count is unknown.
+    pub fn fraction_done(&self, current_percent: f64) -> Option<f64> {
+        let count = self.track_count.filter(|&n| n > 0)?;
+        let current = match self.current {
+            Some(n) if !self.completed.contains(&n) => current_percent.clamp(0.0, 100.0) / 100.0,
+            _ => 0.0,
+        };
+        Some(((self.completed.len() as f64 + current) / f64::from(count)).min(1.0))
+    }
+
+    /// Forgets everything, for when the finished files no longer count
+    /// (e.g. the download switched to another codec).
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+/// Returns the `(album storefront, album ID)` of a download that can be
+/// resumed track by track: exactly one URL, pointing at a whole album.
+pub fn resumable_album(urls: &[String]) -> Option<(String, String)> {
+    match urls {
+        [url] => match catalog_service::parse_catalog_url(url)? {
+            CatalogResource::Album { storefront, id } => Some((storefront, id)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Picks the track URLs still to download.
+///
+/// # Arguments
+/// * `track_urls` - URL of every track of the album, in album order
+/// * `progress` - The download's finished positions
+///
+/// # Returns
+/// `(urls, positions)` of the remaining tracks, or `None` if the album
+/// URL should be used instead: nothing has finished yet, everything has
+/// (GAMDL then just confirms the files), or the catalog's track list no
+/// longer matches the track count GAMDL reported.
+pub fn narrow(track_urls: &[String], progress: &TrackProgress) -> Option<(Vec<String>, Vec<u32>)> {
+    if progress.completed.is_empty() || track_urls.is_empty() {
+        return None;
+    }
+    if progress.track_count != Some(track_urls.len() as u32) {
+        return None;
+    }
+
+    let (urls, positions): (Vec<String>, Vec<u32>) = track_urls
+        .iter()
+        .zip(1u32..)
+        .filter(|(_, position)| !progress.completed.contains(position))
+        .map(|(url, position)| (url.clone(), position))
+        .unzip();
+
+    if urls.is_empty() {
+        None
+    } else {
+        Some((urls, positions))
+    }
+}
+
+/// Returns the URLs to pass GAMDL for a download that already finished
+/// some tracks of its album, with their album positions.
+///
+/// # Returns
+/// `None` when the album URL should be used as is (see `narrow()`); a
+/// failed catalog lookup is logged and also returns `None`.
+pub async fn resume_urls(
+    app: &AppHandle,
+    urls: &[String],
+    progress: &TrackProgress,
+) -> Option<(Vec<String>, Vec<u32>)> {
+    if progress.completed.is_empty() {
+        return None;
+    }
+    let (storefront, album_id) = resumable_album(urls)?;
+
+    let token = match artist_expansion_service::musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            log::debug!("Not resuming album {} track by track: {}", album_id, e);
+            return None;
+        }
+    };
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Not resuming album {} track by track: {}", album_id, e);
+            return None;
+        }
+    };
+
+    match fetch_track_urls(&client, &token, &storefront, &album_id).await {
+        Ok(track_urls) => {
+            let narrowed = narrow(&track_urls, progress);
+            if narrowed.is_none() {
+                log::info!(
+                    "Album {} can't be narrowed to its remaining tracks; downloading the whole album",
+                    album_id
+                );
+            }
+            narrowed
+        }
+        Err(e) => {
+            log::warn!("Could not list the tracks of album {}: {}", album_id, e);
+            None
+        }
+    }
+}
+
+/// Lists the URL of every track of an album, in album order, following
+/// the `next` links of long albums.
+async fn fetch_track_urls(
+    client: &reqwest::Client,
+    token: &str,
+    storefront: &str,
+    album_id: &str,
+) -> Result<Vec<String>, String> {
+    let resource = CatalogResource::Album {
+        storefront: storefront.to_string(),
+        id: album_id.to_string(),
+    };
+    let pages = catalog_service::fetch_catalog_pages(client, token, &resource).await?;
+    if pages.last().is_some_and(|page| page.get("next").is_some()) {
+        return Err("Album has too many tracks to list".to_string());
+    }
+
+    let mut track_urls = Vec::new();
+    for page in &pages {
+        track_urls
+            .extend(parse_track_urls(page).ok_or("Album track without a URL in the API response")?);
+    }
+    Ok(track_urls)
+}
+
+/// Extracts the track URLs from a page of album tracks:
+///
+/// ```text
+/// { "data": [ { "id": "1440935808", "type": "songs",
+///               "attributes": { "url": "https://music.apple.com/us/album/style/1440935467?i=1440935808" } } ] }
+/// ```
+///
+/// Music videos stay in the list: GAMDL counts them in "track N of M".
+/// Returns `None` if any entry has no URL, since the positions of the
+/// remaining tracks could no longer be trusted.
+fn parse_track_urls(json: &serde_json::Value) -> Option<Vec<String>> {
+    json.get("data")?
+        .as_array()?
+        .iter()
+        .map(|t| Some(t.pointer("/attributes/url")?.as_str()?.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(n: u32) -> Vec<String> {
+        (1..=n)
+            .map(|i| format!("https://music.apple.com/us/album/a/1?i={}", i))
+            .collect()
+    }
+
+    fn progress(completed: &[u32], track_count: u32) -> TrackProgress {
+        TrackProgress::restored(completed, Some(track_count))
+    }
+
+    /// Verifies that narrowing drops finished positions and keeps album order.
+    #[test]
+    fn narrow_keeps_only_unfinished_tracks() {
+        let (narrowed, positions) = narrow(&urls(5), &progress(&[1, 2, 4], 5)).unwrap();
+        assert_eq!(positions, vec![3, 5]);
+        assert_eq!(narrowed, vec![urls(5)[2].clone(), urls(5)[4].clone()]);
+    }
+
+    /// Verifies that a download with no finished tracks keeps its album URL.
+    #[test]
+    fn narrow_uses_album_url_without_finished_tracks() {
+        assert!(narrow(&urls(5), &progress(&[], 5)).is_none());
+    }
+
+    /// Verifies that a fully finished album falls back to the album URL
+    /// rather than an empty run.
+    #[test]
+    fn narrow_uses_album_url_when_everything_finished() {
+        assert!(narrow(&urls(3), &progress(&[1, 2, 3], 3)).is_none());
+    }
+
+    /// Verifies that a track list that no longer matches the reported track
+    /// count (or an unknown count) falls back to the album URL.
+    #[test]
+    fn narrow_uses_album_url_when_track_count_changed() {
+        assert!(narrow(&urls(6), &progress(&[1, 2], 5)).is_none());
+        assert!(narrow(&urls(5), &TrackProgress::restored(&[1, 2], None)).is_none());
+    }
+
+    /// Verifies that a whole-album run records the positions GAMDL reports.
+    #[test]
+    fn whole_album_run_records_reported_positions() {
+        let mut progress = TrackProgress::default();
+        progress.begin_run(None);
+        progress.track_started(Some(1), Some(3));
+        assert!(progress.track_done());
+        // A second "Saved to" line for the same track adds nothing
+        assert!(!progress.track_done());
+        progress.track_started(Some(2), Some(3));
+        assert!(progress.track_done());
+
+        assert_eq!(progress.completed, BTreeSet::from([1, 2]));
+        assert_eq!(progress.track_count, Some(3));
+    }
+
+    /// Verifies that the tracks of a narrowed run are recorded under their
+    /// album positions, not their position in the run.
+    #[test]
+    fn narrowed_run_maps_tracks_to_album_positions() {
+        let mut progress = progress(&[1, 2, 4], 5);
+        progress.begin_run(Some(vec![3, 5]));
+        progress.track_started(None, None);
+        assert!(progress.track_done());
+
+        assert_eq!(progress.completed, BTreeSet::from([1, 2, 3, 4]));
+        assert_eq!(progress.track_count, Some(5));
+    }
+
+    /// Verifies that a saved file without a known position is not recorded.
+    #[test]
+    fn done_without_started_track_records_nothing() {
+        let mut progress = TrackProgress::default();
+        assert!(!progress.track_done());
+        // "Getting song" lines of a whole-album run carry no position
+        progress.track_started(None, None);
+        assert!(!progress.track_done());
+        assert!(progress.completed.is_empty());
+    }
+
+    /// Verifies that only a single whole-album URL can be resumed track by
+    /// track.
+    #[test]
+    fn resumable_album_accepts_single_album_url_only() {
+        let album = "https://music.apple.com/us/album/1989/1440935467".to_string();
+        let song = "https://music.apple.com/us/album/style/1440935467?i=1440935808".to_string();
+        assert_eq!(
+            resumable_album(std::slice::from_ref(&album)),
+            Some(("us".to_string(), "1440935467".to_string()))
+        );
+        assert!(resumable_album(&[song]).is_none());
+        assert!(resumable_album(&[album.clone(), album]).is_none());
+    }
+
+    /// Verifies that music videos keep their place in the track list and a
+    /// track without a URL invalidates the page.
+    #[test]
+    fn parses_track_urls_including_music_videos() {
+        let json = serde_json::json!({ "data": [
+            { "type": "songs", "attributes": { "url": "https://music.apple.com/us/album/a/1?i=2" } },
+            { "type": "music-videos", "attributes": { "url": "https://music.apple.com/us/music-video/v/3" } },
+        ]});
+        assert_eq!(parse_track_urls(&json).unwrap().len(), 2);
+
+        let missing = serde_json::json!({ "data": [ { "type": "songs", "attributes": {} } ] });
+        assert!(parse_track_urls(&missing).is_none());
+    }
+}
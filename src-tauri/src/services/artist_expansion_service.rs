@@ -0,0 +1,347 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// artist_expansion_service.rs -- Expand artist URLs into album jobs
+// ==================================================================
+//
+// Handed an artist page URL, GAMDL downloads the artist's whole catalogue
+// as one opaque job: a single progress bar, and a failure anywhere means
+// retrying everything. With `AppSettings::expand_artist_urls` on (the
+// default), `commands::gamdl::start_download()` runs each request through
+// `expand_request()` first, which:
+//
+//   1. Detects Apple Music artist URLs (`MusicServiceId::from_url()` plus
+//      the `/{storefront}/artist/{slug}/{id}` path).
+//   2. Lists the artist's albums from the Apple Music catalog API
+//      (`/v1/catalog/{storefront}/artists/{id}/albums`), following the
+//      `next` links so artists with hundreds of releases are complete.
+//   3. Returns one request per album, carrying the original request's
+//      options, so each album gets its own progress, fallback and retry.
+//
+// The catalog API needs a MusicKit developer token, built from the same
+// credentials as animated artwork. Without them, or if the lookup fails,
+// the artist URL is left as-is and GAMDL handles it as before.
+//
+// @see animated_artwork_service.rs -- generate_musickit_jwt(), credentials
+// @see catalog_service.rs -- fetch_pages(), the paged catalog requests
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+use tauri::AppHandle;
+
+use crate::models::download::DownloadRequest;
+use crate::models::music_service::MusicServiceId;
+use crate::services::{animated_artwork_service, catalog_service, config_service};
+
+/// Matches `https://music.apple.com/{storefront}/artist/{slug}/{id}`; the
+/// slug is optional (`/us/artist/1234`).
+static ARTIST_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^https?://music\.apple\.com/([a-z]{2})/artist/(?:[^/?#]+/)?(\d+)")
+        .expect("Invalid artist URL regex")
+});
+
+/// A parsed Apple Music artist URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtistUrl {
+    /// Two-letter country code (e.g., "us", "gb")
+    pub storefront: String,
+    /// Numeric artist identifier
+    pub artist_id: String,
+}
+
+/// Returns the storefront and artist ID if `url` is an Apple Music artist
+/// page, or `None` for any other URL (albums, songs, other services).
+pub fn parse_artist_url(url: &str) -> Option<ArtistUrl> {
+    if MusicServiceId::from_url(url) != Some(MusicServiceId::AppleMusic) {
+        return None;
+    }
+    ARTIST_URL_REGEX
+        .captures(url.trim())
+        .map(|caps| ArtistUrl {
+            storefront: caps[1].to_string(),
+            artist_id: caps[2].to_string(),
+        })
+}
+
+/// Expands the artist URLs in `request` into one request per album.
+///
+/// Non-artist URLs stay together in a request of their own, as submitted.
+/// An artist that can't be expanded (no MusicKit credentials, API error,
+/// no albums) keeps its URL in that request, so nothing is dropped.
+///
+/// # Returns
+/// The requests to enqueue, in order: the original request (minus the
+/// expanded artist URLs) first, if anything is left in it, then one per
+/// album.
+pub async fn expand_request(app: &AppHandle, request: DownloadRequest) -> Vec<DownloadRequest> {
+    let artists: Vec<ArtistUrl> = request.urls.iter().filter_map(|u| parse_artist_url(u)).collect();
+    if artists.is_empty() {
+        return vec![request];
+    }
+
+    let token = match musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            log::info!("Not expanding artist URL(s): {}", e);
+            return vec![request];
+        }
+    };
+
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Not expanding artist URL(s): {}", e);
+            return vec![request];
+        }
+    };
+
+    let mut albums_by_artist = Vec::new();
+    for artist in &artists {
+        match fetch_artist_album_urls(&client, &token, artist).await {
+            Ok(albums) if !albums.is_empty() => {
+                log::info!(
+                    "Expanded artist {} into {} album(s)",
+                    artist.artist_id,
+                    albums.len()
+                );
+                albums_by_artist.push((artist.clone(), albums));
+            }
+            Ok(_) => log::info!("Artist {} has no albums to expand", artist.artist_id),
+            Err(e) => log::warn!("Could not list albums for artist {}: {}", artist.artist_id, e),
+        }
+    }
+
+    split_request(request, &albums_by_artist)
+}
+
+/// Splits `request` using the album lists fetched for its artists: each
+/// expanded artist URL is replaced by one request per album. Artists
+/// missing from `albums_by_artist` keep their URL in the original request.
+fn split_request(
+    request: DownloadRequest,
+    albums_by_artist: &[(ArtistUrl, Vec<String>)],
+) -> Vec<DownloadRequest> {
+    let is_expanded = |url: &String| {
+        parse_artist_url(url)
+            .is_some_and(|artist| albums_by_artist.iter().any(|(a, _)| *a == artist))
+    };
+
+    let remaining: Vec<String> = request.urls.iter().filter(|u| !is_expanded(u)).cloned().collect();
+
+    let mut requests = Vec::new();
+    if !remaining.is_empty() {
+        requests.push(DownloadRequest {
+            urls: remaining,
+            options: request.options.clone(),
+            label: request.label.clone(),
+            priority: request.priority,
+            fallback_chain_override: request.fallback_chain_override.clone(),
+            kind: None,
+        });
+    }
+    for (_, albums) in albums_by_artist {
+        requests.extend(albums.iter().map(|album| DownloadRequest {
+            urls: vec![album.clone()],
+            options: request.options.clone(),
+            label: request.label.clone(),
+            priority: request.priority,
+            fallback_chain_override: request.fallback_chain_override.clone(),
+            kind: None,
+        }));
+    }
+    requests
+}
+
+/// Signs a MusicKit developer token from the credentials configured for
+/// animated artwork: the Team ID and Key ID from the settings, and the
+/// private key from the keychain.
+///
+/// Every Apple Music catalog lookup in the app needs this token (artist
+/// expansion, playlist sync, size estimates, content ratings, track
+/// availability, artwork probes, album resume). The credentials are
+/// optional, so callers treat an `Err` like a failed lookup: they log it
+/// and go on without the catalog data, handing GAMDL the request as it
+/// was.
+pub fn musickit_token(app: &AppHandle) -> Result<String, String> {
+    let settings = config_service::load_settings(app).unwrap_or_default();
+    let team_id = settings
+        .musickit_team_id
+        .filter(|id| !id.is_empty())
+        .ok_or("MusicKit Team ID not configured")?;
+    let key_id = settings
+        .musickit_key_id
+        .filter(|id| !id.is_empty())
+        .ok_or("MusicKit Key ID not configured")?;
+    let private_key = animated_artwork_service::get_private_key_from_keychain()?
+        .ok_or("MusicKit private key not stored in keychain")?;
+
+    animated_artwork_service::generate_musickit_jwt(&team_id, &key_id, &private_key)
+}
+
+/// Lists the album page URLs of an artist, reading every page of the
+/// artist's albums relationship.
+async fn fetch_artist_album_urls(
+    client: &reqwest::Client,
+    token: &str,
+    artist: &ArtistUrl,
+) -> Result<Vec<String>, String> {
+    let path = format!(
+        "/v1/catalog/{}/artists/{}/albums",
+        artist.storefront, artist.artist_id
+    );
+    let pages = catalog_service::fetch_pages(client, token, &path).await?;
+    Ok(pages.iter().flat_map(parse_album_page).collect())
+}
+
+/// Extracts the album URLs from one page of an artist's albums
+/// relationship:
+///
+/// ```text
+/// { "data": [ { "attributes": { "url": "https://music.apple.com/..." } } ],
+///   "next": "/v1/catalog/us/artists/123/albums?offset=100" }
+/// ```
+fn parse_album_page(json: &serde_json::Value) -> Vec<String> {
+    json.get("data")
+        .and_then(|d| d.as_array())
+        .map(|albums| {
+            albums
+                .iter()
+                .filter_map(|a| a.pointer("/attributes/url").and_then(|u| u.as_str()))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::gamdl_options::{GamdlOptions, SongCodec};
+
+    fn artist(storefront: &str, id: &str) -> ArtistUrl {
+        ArtistUrl {
+            storefront: storefront.to_string(),
+            artist_id: id.to_string(),
+        }
+    }
+
+    fn album_urls(n: usize) -> Vec<String> {
+        (1..=n)
+            .map(|i| format!("https://music.apple.com/us/album/album-{}/{}", i, 1000 + i))
+            .collect()
+    }
+
+    // ----------------------------------------------------------
+    // parse_artist_url()
+    // ----------------------------------------------------------
+
+    /// Verifies that artist pages are detected, with or without a slug.
+    #[test]
+    fn detects_artist_urls() {
+        assert_eq!(
+            parse_artist_url("https://music.apple.com/us/artist/taylor-swift/159260351"),
+            Some(artist("us", "159260351"))
+        );
+        assert_eq!(
+            parse_artist_url("https://music.apple.com/gb/artist/159260351?l=en"),
+            Some(artist("gb", "159260351"))
+        );
+    }
+
+    /// Verifies that albums, songs and other services aren't artist URLs.
+    #[test]
+    fn ignores_non_artist_urls() {
+        assert_eq!(parse_artist_url("https://music.apple.com/us/album/1989/1440935467"), None);
+        assert_eq!(parse_artist_url("https://music.apple.com/us/song/style/1440935808"), None);
+        assert_eq!(parse_artist_url("https://music.youtube.com/channel/UC123"), None);
+        assert_eq!(parse_artist_url("not a url"), None);
+    }
+
+    // ----------------------------------------------------------
+    // split_request()
+    // ----------------------------------------------------------
+
+    /// Verifies that an artist with N albums becomes N album requests that
+    /// keep the original options and label.
+    #[test]
+    fn expansion_produces_one_request_per_album() {
+        let options = GamdlOptions {
+            song_codec: Some(SongCodec::Atmos),
+            ..GamdlOptions::default()
+        };
+        let request = DownloadRequest {
+            urls: vec!["https://music.apple.com/us/artist/someone/42".to_string()],
+            options: Some(options),
+            label: Some("Discography".to_string()),
+            priority: 0,
+            fallback_chain_override: None,
+            kind: None,
+        };
+
+        let requests = split_request(request, &[(artist("us", "42"), album_urls(12))]);
+
+        assert_eq!(requests.len(), 12);
+        assert_eq!(requests[0].urls, vec![album_urls(12)[0].clone()]);
+        assert!(requests
+            .iter()
+            .all(|r| r.options.as_ref().unwrap().song_codec == Some(SongCodec::Atmos)));
+        assert!(requests.iter().all(|r| r.label.as_deref() == Some("Discography")));
+    }
+
+    /// Verifies that other URLs and artists that couldn't be expanded stay
+    /// in the original request.
+    #[test]
+    fn expansion_keeps_unexpanded_urls() {
+        let album = "https://music.apple.com/us/album/x/7".to_string();
+        let failed_artist = "https://music.apple.com/us/artist/other/99".to_string();
+        let request = DownloadRequest {
+            urls: vec![
+                album.clone(),
+                "https://music.apple.com/us/artist/someone/42".to_string(),
+                failed_artist.clone(),
+            ],
+            options: None,
+            label: None,
+            priority: 0,
+            fallback_chain_override: None,
+            kind: None,
+        };
+
+        let requests = split_request(request, &[(artist("us", "42"), album_urls(3))]);
+
+        assert_eq!(requests.len(), 4);
+        assert_eq!(requests[0].urls, vec![album, failed_artist]);
+    }
+
+    // ----------------------------------------------------------
+    // parse_album_page()
+    // ----------------------------------------------------------
+
+    /// Verifies that album URLs are read from a page, skipping entries
+    /// without one.
+    #[test]
+    fn parses_album_page() {
+        let json = serde_json::json!({
+            "data": [
+                { "id": "1", "attributes": { "url": "https://music.apple.com/us/album/a/1" } },
+                { "id": "2", "attributes": { "name": "No URL" } },
+                { "id": "3", "attributes": { "url": "https://music.apple.com/us/album/c/3" } }
+            ],
+            "next": "/v1/catalog/us/artists/42/albums?offset=100"
+        });
+
+        assert_eq!(
+            parse_album_page(&json),
+            vec![
+                "https://music.apple.com/us/album/a/1".to_string(),
+                "https://music.apple.com/us/album/c/3".to_string(),
+            ]
+        );
+        assert!(parse_album_page(&serde_json::json!({ "data": [] })).is_empty());
+    }
+}
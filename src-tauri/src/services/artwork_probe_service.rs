@@ -0,0 +1,317 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// artwork_probe_service.rs -- Cached album artwork lookup
+// ========================================================
+//
+// One catalog API call per album reads everything MeedyaDL needs to know
+// about its artwork:
+//
+//   - the native dimensions of the static cover (`attributes.artwork`),
+//     which `download_queue::process_queue()` uses to request a concrete
+//     `cover_size` instead of the 10000x10000 default. Asking the CDN for
+//     more than the source has occasionally fails the cover download;
+//   - the animated artwork HLS URLs (`attributes.editorialVideo`), which
+//     `animated_artwork_service` downloads once the album has finished.
+//
+// Results are kept in a small LRU cache keyed by album ID, so the
+// animated artwork step, companion runs and retries of the same album
+// reuse the probe made when its download started.
+//
+// Without a catalog token the probe is skipped and the cover size setting
+// is passed to GAMDL unchanged.
+//
+// @see catalog_service.rs -- get_json(), the catalog request
+// @see artist_expansion_service.rs -- musickit_token(), the catalog credentials
+// @see https://developer.apple.com/documentation/applemusicapi/artwork
+
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+use tauri::AppHandle;
+
+use crate::services::animated_artwork_service::{self, ArtworkUrls};
+use crate::services::{artist_expansion_service, catalog_service, config_service};
+
+/// Number of albums whose artwork is kept in memory.
+pub const CACHE_CAPACITY: usize = 64;
+
+/// Artwork of recently probed albums, shared by every caller of probe().
+static CACHE: LazyLock<Mutex<ArtworkCache>> =
+    LazyLock::new(|| Mutex::new(ArtworkCache::new(CACHE_CAPACITY)));
+
+/// What the catalog knows about an album's artwork.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AlbumArtwork {
+    /// Native width of the static cover in pixels (0 if not reported)
+    pub width: u32,
+    /// Native height of the static cover in pixels (0 if not reported)
+    pub height: u32,
+    /// Animated artwork streams, or `None` if the album has none
+    pub motion: Option<ArtworkUrls>,
+}
+
+impl AlbumArtwork {
+    /// Returns the cover size to request from GAMDL: the user's setting,
+    /// capped at the larger native dimension. The setting is returned
+    /// unchanged when the catalog reported no dimensions.
+    pub fn clamp_cover_size(&self, requested: u32) -> u32 {
+        match self.width.max(self.height) {
+            0 => requested,
+            native => requested.min(native),
+        }
+    }
+}
+
+/// Least-recently-used cache of album artwork, keyed by album ID.
+///
+/// A `VecDeque` ordered from most to least recently used: with a few dozen
+/// entries a linear scan is cheaper than keeping a map and a list in sync.
+#[derive(Debug)]
+pub struct ArtworkCache {
+    capacity: usize,
+    entries: VecDeque<(String, AlbumArtwork)>,
+}
+
+impl ArtworkCache {
+    /// Creates an empty cache holding at most `capacity` albums.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Returns a copy of an album's artwork and marks it as recently used.
+    pub fn get(&mut self, album_id: &str) -> Option<AlbumArtwork> {
+        let index = self.entries.iter().position(|(id, _)| id == album_id)?;
+        let entry = self.entries.remove(index)?;
+        let artwork = entry.1.clone();
+        self.entries.push_front(entry);
+        Some(artwork)
+    }
+
+    /// Stores an album's artwork, evicting the least recently used album
+    /// when the cache is full.
+    pub fn insert(&mut self, album_id: String, artwork: AlbumArtwork) {
+        self.entries.retain(|(id, _)| *id != album_id);
+        self.entries.push_front((album_id, artwork));
+        self.entries.truncate(self.capacity);
+    }
+
+    /// Returns the number of cached albums.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns whether no album is cached.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Returns an album's artwork, from the cache or from one catalog call.
+///
+/// Failed lookups aren't cached, so a later call tries again.
+pub async fn probe(
+    client: &reqwest::Client,
+    token: &str,
+    storefront: &str,
+    album_id: &str,
+) -> Result<AlbumArtwork, String> {
+    lookup(&CACHE, album_id, || fetch_album_artwork(client, token, storefront, album_id)).await
+}
+
+/// Probes the first album among a download's URLs. Returns `None` when
+/// no URL is an album URL, the MusicKit credentials are missing or the
+/// lookup failed; the caller then goes on without artwork information.
+pub async fn probe_for_urls(app: &AppHandle, urls: &[String]) -> Option<AlbumArtwork> {
+    let album = urls
+        .iter()
+        .find_map(|url| animated_artwork_service::parse_apple_music_url(url))?;
+    let token = artist_expansion_service::musickit_token(app)
+        .map_err(|e| log::debug!("Skipping artwork probe: {}", e))
+        .ok()?;
+    let client = config_service::load_proxy_config(app).build_client().ok()?;
+
+    probe(&client, &token, &album.storefront, &album.album_id)
+        .await
+        .map_err(|e| log::debug!("Artwork probe for album {} failed: {}", album.album_id, e))
+        .ok()
+}
+
+/// Cache-or-fetch behind probe(), with the fetch passed in so tests can
+/// count calls. The lock is never held across the fetch.
+async fn lookup<F, Fut>(
+    cache: &Mutex<ArtworkCache>,
+    album_id: &str,
+    fetch: F,
+) -> Result<AlbumArtwork, String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<AlbumArtwork, String>>,
+{
+    if let Some(artwork) = cache.lock().unwrap_or_else(PoisonError::into_inner).get(album_id) {
+        log::debug!("Artwork of album {} served from cache", album_id);
+        return Ok(artwork);
+    }
+
+    let artwork = fetch().await?;
+    cache
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(album_id.to_string(), artwork.clone());
+    Ok(artwork)
+}
+
+/// Fetches an album with `extend=editorialVideo`, which adds the animated
+/// artwork URLs to the regular attributes.
+async fn fetch_album_artwork(
+    client: &reqwest::Client,
+    token: &str,
+    storefront: &str,
+    album_id: &str,
+) -> Result<AlbumArtwork, String> {
+    let path = format!(
+        "/v1/catalog/{}/albums/{}?extend=editorialVideo",
+        storefront, album_id
+    );
+    let json = catalog_service::get_json(client, token, &path)
+        .await
+        .map_err(|e| format!("{} (album {})", e, album_id))?;
+
+    Ok(parse_album_artwork(&json))
+}
+
+/// Reads the cover dimensions (`data[0].attributes.artwork`) and the
+/// animated artwork URLs from an album response.
+fn parse_album_artwork(response: &serde_json::Value) -> AlbumArtwork {
+    let artwork = response
+        .get("data")
+        .and_then(|d| d.get(0))
+        .and_then(|d| d.get("attributes"))
+        .and_then(|a| a.get("artwork"));
+    let dimension = |key: &str| {
+        artwork
+            .and_then(|a| a.get(key))
+            .and_then(|v| v.as_u64())
+            .and_then(|v| u32::try_from(v).ok())
+            .unwrap_or(0)
+    };
+
+    AlbumArtwork {
+        width: dimension("width"),
+        height: dimension("height"),
+        motion: animated_artwork_service::parse_artwork_urls(response),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn artwork(size: u32) -> AlbumArtwork {
+        AlbumArtwork {
+            width: size,
+            height: size,
+            motion: None,
+        }
+    }
+
+    /// Verifies that a second lookup of the same album is answered from
+    /// the cache without calling the catalog again.
+    #[tokio::test]
+    async fn second_lookup_hits_cache() {
+        let cache = Mutex::new(ArtworkCache::new(4));
+        let calls = AtomicUsize::new(0);
+        let fetch = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok(artwork(3000))
+        };
+
+        let first = lookup(&cache, "1649434004", fetch).await.unwrap();
+        let second = lookup(&cache, "1649434004", fetch).await.unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    /// Verifies that a failed fetch isn't cached and is retried next time.
+    #[tokio::test]
+    async fn failed_lookup_is_not_cached() {
+        let cache = Mutex::new(ArtworkCache::new(4));
+        let result = lookup(&cache, "1", || async { Err("HTTP 500".to_string()) }).await;
+        assert!(result.is_err());
+        assert!(cache.lock().unwrap().is_empty());
+
+        let result = lookup(&cache, "1", || async { Ok(artwork(1400)) }).await;
+        assert_eq!(result.unwrap().width, 1400);
+    }
+
+    /// Verifies that a full cache evicts the least recently used album,
+    /// counting a get() as a use.
+    #[test]
+    fn cache_evicts_least_recently_used() {
+        let mut cache = ArtworkCache::new(2);
+        cache.insert("a".to_string(), artwork(1000));
+        cache.insert("b".to_string(), artwork(2000));
+        assert!(cache.get("a").is_some());
+
+        cache.insert("c".to_string(), artwork(3000));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("b").is_none(), "b was least recently used");
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    /// Verifies that re-inserting an album replaces its entry.
+    #[test]
+    fn cache_insert_replaces_existing_entry() {
+        let mut cache = ArtworkCache::new(2);
+        cache.insert("a".to_string(), artwork(1000));
+        cache.insert("a".to_string(), artwork(2000));
+        assert_eq!(cache.len(), 1);
+        assert_eq!(cache.get("a").unwrap().width, 2000);
+    }
+
+    /// Verifies that the cover size is capped at the native size and left
+    /// alone when it is smaller or no size was reported.
+    #[test]
+    fn clamp_cover_size_caps_at_native_size() {
+        let cover = AlbumArtwork {
+            width: 3000,
+            height: 2400,
+            motion: None,
+        };
+        assert_eq!(cover.clamp_cover_size(10000), 3000);
+        assert_eq!(cover.clamp_cover_size(1200), 1200);
+        assert_eq!(artwork(0).clamp_cover_size(10000), 10000);
+    }
+
+    /// Verifies that dimensions and motion URLs are read from one response.
+    #[test]
+    fn parse_album_artwork_reads_dimensions_and_motion() {
+        let response = serde_json::json!({
+            "data": [{
+                "attributes": {
+                    "artwork": { "width": 3000, "height": 3000, "url": "https://example.com/{w}x{h}bb.jpg" },
+                    "editorialVideo": {
+                        "motionDetailSquare": { "video": "https://example.com/square.m3u8" }
+                    }
+                }
+            }]
+        });
+        let parsed = parse_album_artwork(&response);
+        assert_eq!((parsed.width, parsed.height), (3000, 3000));
+        assert_eq!(
+            parsed.motion.unwrap().square.as_deref(),
+            Some("https://example.com/square.m3u8")
+        );
+
+        let bare = parse_album_artwork(&serde_json::json!({ "data": [{ "attributes": {} }] }));
+        assert_eq!(bare, artwork(0));
+    }
+}
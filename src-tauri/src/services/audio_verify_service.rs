@@ -0,0 +1,270 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// audio_verify_service.rs -- Post-download audio integrity check
+// ===============================================================
+//
+// Now and then GAMDL reports a track as saved although the M4A is cut
+// short or damaged (an interrupted segment, a remux that died half way).
+// Such files look fine in the folder and only fail when played. With
+// `AppSettings::verify_audio` on, every media file a successful run
+// reported as saved is decoded once with FFmpeg, discarding the output:
+//
+//   ffmpeg -v error -i <file> -map 0:a -f null -
+//
+// A file that decodes cleanly produces no output at error level. A damaged
+// one makes FFmpeg exit with an error or print decoder errors such as
+// "Invalid data found when processing input" or "moov atom not found".
+//
+// ## On failure
+//
+// download_queue.rs deletes the damaged files and fails the run with
+// `DownloadError::CorruptOutput` (category `"corrupt_output"`). The item is
+// re-downloaded once without overwriting, so GAMDL fetches only the deleted
+// tracks and skips the files that passed. If the retry is damaged as
+// well, the item stays failed.
+//
+// Only the run's own `Saved to` files are checked, never the whole output
+// folder: another download may be writing into the same folder, and its
+// half-written tracks (or the temporary files of a cover resize) would
+// fail to decode and be deleted out from under it.
+//
+// The check is skipped entirely with the setting off, and a missing FFmpeg
+// only logs a warning: the download itself succeeded.
+//
+// @see download_queue.rs -- runs verify_outputs() before completing an item
+// @see https://trac.ffmpeg.org/wiki/Null -- the null muxer
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::settings::AppSettings;
+use crate::services::{cover_resize_service, flac_conversion_service};
+use crate::utils::files;
+
+/// Media file extensions GAMDL writes and the check decodes.
+const MEDIA_EXTENSIONS: [&str; 3] = ["m4a", "mp4", "m4v"];
+
+/// FFmpeg error lines that mean the file itself is damaged, rather than
+/// a problem with the FFmpeg run.
+const CORRUPTION_MARKERS: [&str; 7] = [
+    "invalid data found when processing input",
+    "moov atom not found",
+    "error while decoding",
+    "corrupt",
+    "truncat",
+    "partial file",
+    "invalid nal unit",
+];
+
+/// Builds the FFmpeg arguments that decode a file's audio to nowhere.
+///
+/// Flags:
+///   -v error     -- print only errors, so a clean decode prints nothing
+///   -map 0:a     -- decode the audio streams (skip video and cover art)
+///   -f null -    -- discard the decoded output
+pub fn ffmpeg_verify_args(path: &Path) -> Vec<String> {
+    vec![
+        "-hide_banner".to_string(),
+        "-nostdin".to_string(),
+        "-v".to_string(),
+        "error".to_string(),
+        "-i".to_string(),
+        path.to_string_lossy().to_string(),
+        "-map".to_string(),
+        "0:a".to_string(),
+        "-f".to_string(),
+        "null".to_string(),
+        "-".to_string(),
+    ]
+}
+
+/// Decides from an FFmpeg decode run whether the file is damaged.
+///
+/// # Arguments
+/// * `succeeded` -- Whether FFmpeg exited successfully.
+/// * `stderr` -- FFmpeg's error output.
+///
+/// # Returns
+/// * `Some(reason)` -- The file is damaged; `reason` is the first
+///   relevant error line (or a generic reason for a bare failed exit).
+/// * `None` -- The file decoded. Error-level lines that don't point at
+///   the file's data are ignored, so a good file is never deleted.
+pub fn decode_problem(succeeded: bool, stderr: &str) -> Option<String> {
+    let lines: Vec<&str> = stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let corrupt = lines.iter().find(|line| {
+        let lower = line.to_lowercase();
+        CORRUPTION_MARKERS
+            .iter()
+            .any(|marker| lower.contains(marker))
+    });
+    match (corrupt, succeeded) {
+        (Some(line), _) => Some(line.to_string()),
+        (None, true) => None,
+        (None, false) => Some(
+            lines
+                .last()
+                .map(|line| line.to_string())
+                .unwrap_or_else(|| "FFmpeg could not decode the file".to_string()),
+        ),
+    }
+}
+
+/// Returns whether `path` is a media file the check decodes: one of
+/// `MEDIA_EXTENSIONS`, and not a temporary file of a cover resize.
+pub fn is_checked_media(path: &Path) -> bool {
+    files::has_extension(path, &MEDIA_EXTENSIONS) && !cover_resize_service::is_temp_path(path)
+}
+
+/// Recursively lists the media files under `dir` modified at or after
+/// `since` (the files of the run being checked).
+pub fn find_media_files_since(dir: &Path, since: SystemTime) -> Vec<PathBuf> {
+    files::walk_files(dir, |path| {
+        is_checked_media(path) && cover_resize_service::modified_since(path, since)
+    })
+}
+
+/// Decodes each of `files` that is a media file (see `is_checked_media()`)
+/// and still exists.
+///
+/// # Returns
+/// * `Ok(damaged)` -- The files that failed, each with the reason (empty
+///   when everything decoded).
+/// * `Err(message)` -- FFmpeg is missing or could not be started; nothing
+///   was checked.
+pub async fn verify_outputs(
+    app: &AppHandle,
+    settings: &AppSettings,
+    files: &[PathBuf],
+) -> Result<Vec<(PathBuf, String)>, String> {
+    let ffmpeg = flac_conversion_service::resolve_ffmpeg(app, settings, "audio verification")?;
+
+    let mut damaged = Vec::new();
+    for file in files
+        .iter()
+        .filter(|file| is_checked_media(file) && file.is_file())
+        .cloned()
+    {
+        // kill_on_drop: FFmpeg must not outlive an aborted download task.
+        let output = Command::new(&ffmpeg)
+            .args(ffmpeg_verify_args(&file))
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        match decode_problem(output.status.success(), &stderr) {
+            Some(reason) => {
+                log::warn!("Audio check failed for {}: {}", file.display(), reason);
+                damaged.push((file, reason));
+            }
+            None => log::debug!("Audio check passed for {}", file.display()),
+        }
+    }
+    Ok(damaged)
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    // ----------------------------------------------------------
+    // ffmpeg_verify_args()
+    // ----------------------------------------------------------
+
+    /// Verifies that the command decodes the audio of the given file into
+    /// the null muxer, reporting errors only.
+    #[test]
+    fn verify_args_decode_to_null() {
+        let args = ffmpeg_verify_args(Path::new("/music/01 Song.m4a"));
+        let joined = args.join(" ");
+        assert!(joined.contains("-v error"));
+        assert!(joined.contains("-i /music/01 Song.m4a"));
+        assert!(joined.contains("-map 0:a"));
+        assert!(joined.ends_with("-f null -"));
+    }
+
+    // ----------------------------------------------------------
+    // decode_problem()
+    // ----------------------------------------------------------
+
+    /// Verifies that FFmpeg's "invalid data" and truncation errors mark the
+    /// file as damaged, even when FFmpeg exits successfully.
+    #[test]
+    fn detects_invalid_data_output() {
+        let invalid = "[mov,mp4,m4a,3gp,3g2,mj2 @ 0x7f9] moov atom not found\n\
+                       /music/01 Song.m4a: Invalid data found when processing input\n";
+        let reason = decode_problem(false, invalid).unwrap();
+        assert!(reason.contains("moov atom not found"), "{}", reason);
+
+        let truncated = "[aac @ 0x55d] Input buffer exhausted before END element found\n\
+                         [aac @ 0x55d] channel element 0.0 is not allocated\n\
+                         Error while decoding stream #0:0: Invalid data found when processing input\n";
+        assert!(decode_problem(true, truncated).is_some());
+    }
+
+    /// Verifies that a clean decode passes, and that a failed exit without
+    /// a recognised marker still reports the last error line.
+    #[test]
+    fn clean_decode_passes() {
+        assert_eq!(decode_problem(true, ""), None);
+        assert_eq!(
+            decode_problem(true, "[mjpeg @ 0x1] unable to decode APP fields\n"),
+            None
+        );
+        assert_eq!(
+            decode_problem(false, "Conversion failed!\n").as_deref(),
+            Some("Conversion failed!")
+        );
+        assert!(decode_problem(false, "").is_some());
+    }
+
+    // ----------------------------------------------------------
+    // find_media_files_since()
+    // ----------------------------------------------------------
+
+    /// Verifies that only media files written since the run started are
+    /// checked, including ones in disc subfolders.
+    #[test]
+    fn finds_new_media_files_only() {
+        let dir = TestDir::new("verify");
+        let disc = dir.join("Disc 2");
+        std::fs::create_dir_all(&disc).unwrap();
+        std::fs::write(dir.join("01 Song.m4a"), b"x").unwrap();
+        std::fs::write(disc.join("01 Song.M4A"), b"x").unwrap();
+        std::fs::write(dir.join("Cover.jpg"), b"x").unwrap();
+
+        let past = SystemTime::now() - std::time::Duration::from_secs(60);
+        let files = find_media_files_since(&dir, past);
+        assert_eq!(files.len(), 2, "{:?}", files);
+
+        let future = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert!(find_media_files_since(&dir, future).is_empty());
+    }
+
+    /// Verifies that only media files are checked, and not the temporary
+    /// copy a cover resize writes next to a track.
+    #[test]
+    fn skips_non_media_and_temp_files() {
+        assert!(is_checked_media(Path::new("/music/01 Song.m4a")));
+        assert!(is_checked_media(Path::new("/music/01 Video.MP4")));
+        assert!(!is_checked_media(Path::new("/music/Cover.jpg")));
+        assert!(!is_checked_media(Path::new(
+            "/music/01 Song.cover-resize.m4a"
+        )));
+    }
+}
@@ -0,0 +1,96 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// auto_quit.rs -- Quit the app once the download queue drains
+// ============================================================
+//
+// When MeedyaDL is used as an unattended "download appliance", the user can
+// enable `AppSettings::auto_quit_when_done`. Every time a download task
+// finishes, `download_queue::process_queue()` calls `schedule_if_drained()`.
+// If nothing is active or waiting any more:
+//
+//   1. A `queue-drained` event is emitted so the UI can show a countdown.
+//   2. After `GRACE_PERIOD`, the background tasks that outlive a download
+//      (animated artwork and companion downloads) are awaited, so files are
+//      never cut off mid-write.
+//   3. If the queue is still drained and the setting is still on, the app
+//      exits with code 0.
+//
+// Adding a download during the grace period (or while background tasks are
+// finishing) cancels the quit; the next drain schedules a new one.
+//
+// @see download_queue.rs -- DownloadQueue::is_drained() and
+//      DownloadQueue::track_background_task()
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, Emitter};
+
+use crate::services::config_service;
+use crate::services::download_queue::QueueHandle;
+
+/// How long to wait after the queue drains before quitting, giving the
+/// user a chance to add more downloads.
+pub const GRACE_PERIOD: Duration = Duration::from_secs(10);
+
+/// Set while a quit is pending, so simultaneous drains (several tasks
+/// finishing at once) don't start more than one countdown.
+static QUIT_SCHEDULED: AtomicBool = AtomicBool::new(false);
+
+/// Starts the auto-quit countdown if the setting is on and the queue has
+/// just drained. Returns immediately; the countdown runs in the background.
+pub async fn schedule_if_drained(app: AppHandle, queue: QueueHandle) {
+    if !queue.lock().await.is_drained() {
+        return;
+    }
+    if !auto_quit_enabled(&app) {
+        return;
+    }
+    if QUIT_SCHEDULED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    log::info!(
+        "Download queue drained, quitting in {}s",
+        GRACE_PERIOD.as_secs()
+    );
+    let _ = app.emit(
+        "queue-drained",
+        serde_json::json!({ "grace_seconds": GRACE_PERIOD.as_secs() }),
+    );
+
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(GRACE_PERIOD).await;
+
+        // Let artwork/companion downloads finish writing. Tasks spawned while
+        // we wait are picked up by the next pass.
+        loop {
+            let tasks = queue.lock().await.take_background_tasks();
+            if tasks.is_empty() {
+                break;
+            }
+            log::info!("Waiting for {} background task(s) before quitting", tasks.len());
+            for task in tasks {
+                let _ = task.await;
+            }
+        }
+
+        let still_drained = queue.lock().await.is_drained();
+        QUIT_SCHEDULED.store(false, Ordering::SeqCst);
+
+        if still_drained && auto_quit_enabled(&app) {
+            log::info!("Auto-quit: all downloads finished, exiting");
+            app.exit(0);
+        } else {
+            log::info!("Auto-quit cancelled: new downloads were queued or the setting was turned off");
+        }
+    });
+}
+
+/// Reads `auto_quit_when_done` from the current settings.
+fn auto_quit_enabled(app: &AppHandle) -> bool {
+    config_service::load_settings(app)
+        .map(|s| s.auto_quit_when_done)
+        .unwrap_or(false)
+}
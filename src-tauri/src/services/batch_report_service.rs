@@ -0,0 +1,365 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// batch_report_service.rs -- Per-URL outcome summary of a set of downloads
+// =========================================================================
+//
+// After a batch of URLs was enqueued, the failures are spread over the
+// items and the UI has to open each one to find out what happened. The
+// `get_batch_report` command gathers the items' statuses into one table,
+// one row per URL:
+//
+//   | URL                | Outcome  | Category    | Reason                 |
+//   |--------------------|----------|-------------|------------------------|
+//   | .../album/1989/... | complete |             |                        |
+//   | .../album/red/...  | failed   | auth        | Failed to load cookies |
+//   | .../song/s/...     | skipped  | unavailable | Style                  |
+//
+// and a one-line summary such as
+// "47 succeeded, 3 failed (auth), 1 skipped (unavailable)".
+//
+// Nothing is tracked for the report: the outcome comes from the item's
+// `state`, the category from classifying its `error`
+// (`DownloadError::from_message()`), and the skipped rows from the tracks
+// left out before GAMDL ran (`QueueItemStatus::skipped_tracks`).
+//
+// @see commands/gamdl.rs -- get_batch_report()
+
+use serde::Serialize;
+
+use crate::models::download::{DownloadState, QueueItemStatus};
+use crate::models::download_error::DownloadError;
+
+/// Longest reason kept in a row; longer error messages are cut here.
+pub const MAX_REASON_CHARS: usize = 120;
+
+/// What happened to one URL of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BatchOutcome {
+    /// Downloaded
+    Complete,
+    /// Left out: the track wasn't available, or the user cancelled
+    Skipped,
+    /// The download failed
+    Failed,
+    /// Still queued or running
+    Pending,
+}
+
+/// One row of a batch report.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchReportRow {
+    /// The queue item the URL belongs to
+    pub download_id: String,
+    /// The Apple Music URL
+    pub url: String,
+    /// What happened to it
+    pub outcome: BatchOutcome,
+    /// Why it failed or was skipped, as a category (e.g. `"auth"`,
+    /// `"unavailable"`); `None` for complete and pending URLs
+    pub category: Option<String>,
+    /// Short explanation, in words; `None` for complete and pending URLs
+    pub reason: Option<String>,
+}
+
+/// Outcome table of a set of downloads, serialized to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BatchReport {
+    /// One row per URL, in queue order; skipped tracks follow the row of
+    /// the download they were left out of
+    pub rows: Vec<BatchReportRow>,
+    /// Number of complete rows
+    pub succeeded: usize,
+    /// Number of failed rows
+    pub failed: usize,
+    /// Number of skipped rows
+    pub skipped: usize,
+    /// Number of pending rows
+    pub pending: usize,
+    /// One-line summary, e.g. "47 succeeded, 3 failed (auth), 1 skipped (unavailable)"
+    pub summary: String,
+}
+
+/// Builds the report of `items`.
+pub fn build_report(items: &[QueueItemStatus]) -> BatchReport {
+    let mut rows = Vec::new();
+    for item in items {
+        let (outcome, category, reason) = match item.state {
+            DownloadState::Complete => (BatchOutcome::Complete, None, None),
+            DownloadState::Error => {
+                let error = DownloadError::from_message(item.error.clone().unwrap_or_default());
+                (
+                    BatchOutcome::Failed,
+                    Some(error.category().to_string()),
+                    Some(short_reason(error.message())),
+                )
+            }
+            DownloadState::Cancelled => (
+                BatchOutcome::Skipped,
+                Some("cancelled".to_string()),
+                Some("Cancelled".to_string()),
+            ),
+            DownloadState::Queued | DownloadState::Downloading | DownloadState::Processing => {
+                (BatchOutcome::Pending, None, None)
+            }
+        };
+        for url in &item.urls {
+            rows.push(BatchReportRow {
+                download_id: item.id.clone(),
+                url: url.clone(),
+                outcome,
+                category: category.clone(),
+                reason: reason.clone(),
+            });
+        }
+
+        let storefront = item
+            .urls
+            .first()
+            .and_then(|url| storefront_of(url))
+            .unwrap_or("us");
+        for track in &item.skipped_tracks {
+            rows.push(BatchReportRow {
+                download_id: item.id.clone(),
+                url: format!("https://music.apple.com/{}/song/s/{}", storefront, track.id),
+                outcome: BatchOutcome::Skipped,
+                category: Some(track.reason.clone()),
+                reason: Some(if track.name.is_empty() {
+                    track.id.clone()
+                } else {
+                    track.name.clone()
+                }),
+            });
+        }
+    }
+
+    let count = |outcome: BatchOutcome| rows.iter().filter(|r| r.outcome == outcome).count();
+    let (succeeded, failed, skipped, pending) = (
+        count(BatchOutcome::Complete),
+        count(BatchOutcome::Failed),
+        count(BatchOutcome::Skipped),
+        count(BatchOutcome::Pending),
+    );
+    let summary = summarize(&rows);
+    BatchReport {
+        rows,
+        succeeded,
+        failed,
+        skipped,
+        pending,
+        summary,
+    }
+}
+
+/// Formats the summary line: the count of each outcome that occurs, with
+/// the categories of the failed and skipped rows (most frequent first).
+fn summarize(rows: &[BatchReportRow]) -> String {
+    let parts: Vec<String> = [
+        (BatchOutcome::Complete, "succeeded"),
+        (BatchOutcome::Failed, "failed"),
+        (BatchOutcome::Skipped, "skipped"),
+        (BatchOutcome::Pending, "pending"),
+    ]
+    .into_iter()
+    .filter_map(|(outcome, word)| {
+        let matching: Vec<&BatchReportRow> = rows.iter().filter(|r| r.outcome == outcome).collect();
+        if matching.is_empty() {
+            return None;
+        }
+
+        // Categories by count, ties in order of first appearance
+        let mut categories: Vec<(&str, usize)> = Vec::new();
+        for category in matching.iter().filter_map(|r| r.category.as_deref()) {
+            match categories.iter_mut().find(|(c, _)| *c == category) {
+                Some((_, n)) => *n += 1,
+                None => categories.push((category, 1)),
+            }
+        }
+        categories.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+
+        let mut part = format!("{} {}", matching.len(), word);
+        if !categories.is_empty() {
+            let names: Vec<String> = categories
+                .iter()
+                .map(|(category, n)| match categories.len() {
+                    1 => category.to_string(),
+                    _ => format!("{} {}", n, category),
+                })
+                .collect();
+            part.push_str(&format!(" ({})", names.join(", ")));
+        }
+        Some(part)
+    })
+    .collect();
+
+    if parts.is_empty() {
+        "No downloads".to_string()
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// The first line of an error message, cut to `MAX_REASON_CHARS`.
+fn short_reason(message: &str) -> String {
+    let line = message.lines().next().unwrap_or_default().trim();
+    if line.chars().count() <= MAX_REASON_CHARS {
+        return line.to_string();
+    }
+    let cut: String = line.chars().take(MAX_REASON_CHARS - 3).collect();
+    format!("{}...", cut.trim_end())
+}
+
+/// The storefront code of an Apple Music URL (`"us"` in
+/// `https://music.apple.com/us/album/...`).
+fn storefront_of(url: &str) -> Option<&str> {
+    url.split('/')
+        .nth(3)
+        .filter(|s| s.len() == 2 && s.chars().all(|c| c.is_ascii_alphabetic()))
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::download::SkippedTrack;
+
+    fn status(id: &str, url: &str, state: DownloadState, error: Option<&str>) -> QueueItemStatus {
+        QueueItemStatus {
+            id: id.to_string(),
+            urls: vec![url.to_string()],
+            state,
+            progress: 0.0,
+            current_track: None,
+            current_step: None,
+            total_tracks: None,
+            completed_tracks: None,
+            speed: None,
+            eta: None,
+            error: error.map(str::to_string),
+            output_path: None,
+            codec_used: None,
+            fallback_occurred: false,
+            fallback_reason: None,
+            track_codecs: Vec::new(),
+            skipped_tracks: Vec::new(),
+            cover_missing_tracks: 0,
+            url_progress: Vec::new(),
+            created_at: String::new(),
+            label: None,
+        }
+    }
+
+    /// Verifies a report assembled from complete, failed, cancelled,
+    /// running and partly skipped items: one row per URL and skipped
+    /// track, the counts, and the summary line.
+    #[test]
+    fn report_from_mixed_statuses() {
+        let mut playlist = status(
+            "d",
+            "https://music.apple.com/gb/playlist/mix/pl.1",
+            DownloadState::Complete,
+            None,
+        );
+        playlist.skipped_tracks = vec![SkippedTrack {
+            id: "1440935808".to_string(),
+            name: "Style".to_string(),
+            reason: "unavailable".to_string(),
+        }];
+        let items = vec![
+            status(
+                "a",
+                "https://music.apple.com/us/album/a/1",
+                DownloadState::Complete,
+                None,
+            ),
+            status(
+                "b",
+                "https://music.apple.com/us/album/b/2",
+                DownloadState::Error,
+                Some("Failed to load cookies\nTraceback (most recent call last):"),
+            ),
+            status(
+                "c",
+                "https://music.apple.com/us/album/c/3",
+                DownloadState::Error,
+                Some("Connection reset by peer"),
+            ),
+            playlist,
+            status(
+                "e",
+                "https://music.apple.com/us/album/e/5",
+                DownloadState::Cancelled,
+                None,
+            ),
+            status(
+                "f",
+                "https://music.apple.com/us/album/f/6",
+                DownloadState::Downloading,
+                None,
+            ),
+        ];
+
+        let report = build_report(&items);
+        assert_eq!(report.rows.len(), 7);
+        assert_eq!(
+            (
+                report.succeeded,
+                report.failed,
+                report.skipped,
+                report.pending
+            ),
+            (2, 2, 2, 1)
+        );
+
+        let failed = &report.rows[1];
+        assert_eq!(failed.outcome, BatchOutcome::Failed);
+        assert_eq!(failed.category.as_deref(), Some("auth"));
+        assert_eq!(failed.reason.as_deref(), Some("Failed to load cookies"));
+
+        let skipped = &report.rows[4];
+        assert_eq!(skipped.download_id, "d");
+        assert_eq!(skipped.url, "https://music.apple.com/gb/song/s/1440935808");
+        assert_eq!(skipped.outcome, BatchOutcome::Skipped);
+        assert_eq!(skipped.category.as_deref(), Some("unavailable"));
+        assert_eq!(skipped.reason.as_deref(), Some("Style"));
+
+        assert_eq!(report.rows[0].category, None);
+        assert_eq!(report.rows[6].outcome, BatchOutcome::Pending);
+        assert_eq!(
+            report.summary,
+            "2 succeeded, 2 failed (1 auth, 1 network), \
+             2 skipped (1 unavailable, 1 cancelled), 1 pending"
+        );
+    }
+
+    /// Verifies the summary of a single category and of no items, and that
+    /// long reasons are cut.
+    #[test]
+    fn summary_and_reason_length() {
+        let items = vec![
+            status(
+                "a",
+                "https://music.apple.com/us/album/a/1",
+                DownloadState::Complete,
+                None,
+            ),
+            status(
+                "b",
+                "https://music.apple.com/us/album/b/2",
+                DownloadState::Error,
+                Some(&format!("Failed to load cookies: {}", "x".repeat(200))),
+            ),
+        ];
+        let report = build_report(&items);
+        assert_eq!(report.summary, "1 succeeded, 1 failed (auth)");
+        let reason = report.rows[1].reason.as_deref().unwrap();
+        assert_eq!(reason.chars().count(), MAX_REASON_CHARS);
+        assert!(reason.ends_with("..."));
+
+        assert_eq!(build_report(&[]).summary, "No downloads");
+    }
+}
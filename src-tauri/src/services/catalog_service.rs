@@ -0,0 +1,253 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// catalog_service.rs -- Apple Music catalog API client
+// =====================================================
+//
+// Every catalog lookup in the app goes through here: artist expansion,
+// size estimates, content ratings, track availability, artwork probes,
+// album resume, playlist sync and the cookie live check. The module owns
+// the request shape (host, headers, error messages) and the paging of
+// list endpoints, so those services only parse the pages they get back.
+//
+//   - `request()` builds an authorized GET, for callers that add headers
+//     of their own or map the status themselves.
+//   - `get_json()` fetches one page.
+//   - `fetch_pages()` follows the `next` links of a list endpoint, keeping
+//     the page size on each link, up to `MAX_PAGES`.
+//   - `fetch_catalog_pages()` lists the songs of an album, song or
+//     playlist URL (`parse_catalog_url()`).
+//
+// Requests are authorized with the MusicKit developer token signed by
+// `artist_expansion_service::musickit_token()` from the credentials set up
+// for animated artwork.
+//
+// @see artist_expansion_service.rs -- musickit_token(), the catalog credentials
+// @see https://developer.apple.com/documentation/applemusicapi
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+/// Base URL of the Apple Music API.
+const API_BASE_URL: &str = "https://api.music.apple.com";
+
+/// Entries requested per catalog API page (the API's maximum).
+const PAGE_LIMIT: usize = 100;
+
+/// Upper bound on pages fetched for one list, so a misbehaving `next`
+/// link can't loop forever.
+const MAX_PAGES: usize = 100;
+
+/// Matches album, song and catalog playlist URLs, capturing the storefront,
+/// the kind, the ID and (for album links to one track) the `?i=` song ID.
+static CATALOG_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^https?://music\.apple\.com/([a-z]{2})/(album|song|playlist)/(?:[^/?#]+/)?([A-Za-z0-9.\-]+)(?:\?(?:.*&)?i=(\d+))?",
+    )
+    .expect("Invalid catalog URL regex")
+});
+
+/// A catalog resource whose tracks can be listed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CatalogResource {
+    /// Every track of an album
+    Album { storefront: String, id: String },
+    /// A single song (also album URLs with `?i=`)
+    Song { storefront: String, id: String },
+    /// Every track of a catalog playlist
+    Playlist { storefront: String, id: String },
+}
+
+/// Returns the catalog resource an Apple Music URL points at, or `None`
+/// for URLs that aren't albums, songs or catalog playlists.
+pub fn parse_catalog_url(url: &str) -> Option<CatalogResource> {
+    let caps = CATALOG_URL_REGEX.captures(url.trim())?;
+    let storefront = caps[1].to_string();
+    let id = caps[3].to_string();
+    match (&caps[2], caps.get(4)) {
+        ("album", Some(song)) => Some(CatalogResource::Song {
+            storefront,
+            id: song.as_str().to_string(),
+        }),
+        ("album", None) => Some(CatalogResource::Album { storefront, id }),
+        ("song", _) => Some(CatalogResource::Song { storefront, id }),
+        ("playlist", _) if id.starts_with("pl.") => {
+            Some(CatalogResource::Playlist { storefront, id })
+        }
+        _ => None,
+    }
+}
+
+/// Builds an authorized GET for an Apple Music API URL, with the headers
+/// every catalog request sends.
+pub fn request(client: &reqwest::Client, token: &str, url: &str) -> reqwest::RequestBuilder {
+    client
+        .get(url)
+        .header("Authorization", format!("Bearer {}", token))
+        .header("User-Agent", "meedyadl")
+        .header("Origin", "https://music.apple.com")
+}
+
+/// Fetches one Apple Music API page; `path` starts at `/v1/`.
+///
+/// # Returns
+/// * `Ok(json)` -- The response body.
+/// * `Err(message)` -- The request failed, the API answered with an error
+///   status (404 = not found; 401/403 = invalid or expired token), or the
+///   body wasn't JSON.
+pub async fn get_json(
+    client: &reqwest::Client,
+    token: &str,
+    path: &str,
+) -> Result<serde_json::Value, String> {
+    let url = format!("{}{}", API_BASE_URL, path);
+    log::debug!("Apple Music API request: {}", url);
+
+    let response = request(client, token, &url)
+        .send()
+        .await
+        .map_err(|e| format!("Apple Music API request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Apple Music API returned HTTP {}",
+            response.status().as_u16()
+        ));
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse Apple Music API response: {}", e))
+}
+
+/// Fetches every page of a list endpoint, following its `next` links until
+/// the last page (or `MAX_PAGES`, after which the last page returned still
+/// has a `next` link).
+pub async fn fetch_pages(
+    client: &reqwest::Client,
+    token: &str,
+    path: &str,
+) -> Result<Vec<serde_json::Value>, String> {
+    let mut path = with_limit(path);
+    let mut pages = Vec::new();
+    for _ in 0..MAX_PAGES {
+        let json = get_json(client, token, &path).await?;
+        let next = json.get("next").and_then(|n| n.as_str()).map(with_limit);
+        pages.push(json);
+        match next {
+            Some(next) => path = next,
+            None => return Ok(pages),
+        }
+    }
+    log::warn!("Stopped listing {} after {} pages", path, MAX_PAGES);
+    Ok(pages)
+}
+
+/// Fetches the catalog pages listing a resource's songs, following the
+/// `next` links of albums and playlists. A song is one page of one entry.
+pub async fn fetch_catalog_pages(
+    client: &reqwest::Client,
+    token: &str,
+    resource: &CatalogResource,
+) -> Result<Vec<serde_json::Value>, String> {
+    match resource {
+        CatalogResource::Album { storefront, id } => {
+            let path = format!("/v1/catalog/{}/albums/{}/tracks", storefront, id);
+            fetch_pages(client, token, &path).await
+        }
+        CatalogResource::Playlist { storefront, id } => {
+            let path = format!("/v1/catalog/{}/playlists/{}/tracks", storefront, id);
+            fetch_pages(client, token, &path).await
+        }
+        CatalogResource::Song { storefront, id } => {
+            let path = format!("/v1/catalog/{}/songs/{}", storefront, id);
+            Ok(vec![get_json(client, token, &path).await?])
+        }
+    }
+}
+
+/// Adds the page size to a list path; `next` links carry only the offset.
+fn with_limit(path: &str) -> String {
+    if path.contains("limit=") {
+        path.to_string()
+    } else {
+        let separator = if path.contains('?') { '&' } else { '?' };
+        format!("{}{}limit={}", path, separator, PAGE_LIMIT)
+    }
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ----------------------------------------------------------
+    // parse_catalog_url()
+    // ----------------------------------------------------------
+
+    /// Verifies which URLs resolve to which catalog resource.
+    #[test]
+    fn parses_catalog_urls() {
+        let album = |id: &str| CatalogResource::Album {
+            storefront: "us".to_string(),
+            id: id.to_string(),
+        };
+        let song = |id: &str| CatalogResource::Song {
+            storefront: "us".to_string(),
+            id: id.to_string(),
+        };
+        assert_eq!(
+            parse_catalog_url("https://music.apple.com/us/album/1989/1440935467"),
+            Some(album("1440935467"))
+        );
+        assert_eq!(
+            parse_catalog_url("https://music.apple.com/us/album/1989/1440935467?i=1440935808"),
+            Some(song("1440935808"))
+        );
+        assert_eq!(
+            parse_catalog_url("https://music.apple.com/us/song/style/1440935808"),
+            Some(song("1440935808"))
+        );
+        assert!(matches!(
+            parse_catalog_url(
+                "https://music.apple.com/us/playlist/hits/pl.f4d106fed2bd41149aaacabb233eb5eb"
+            ),
+            Some(CatalogResource::Playlist { .. })
+        ));
+        assert_eq!(
+            parse_catalog_url("https://music.apple.com/us/artist/x/159260351"),
+            None
+        );
+        assert_eq!(
+            parse_catalog_url("https://music.apple.com/us/music-video/v/123"),
+            None
+        );
+    }
+
+    // ----------------------------------------------------------
+    // with_limit()
+    // ----------------------------------------------------------
+
+    /// Verifies that the page size is added to first paths and carried onto
+    /// `next` links.
+    #[test]
+    fn paths_keep_page_size() {
+        assert_eq!(
+            with_limit("/v1/catalog/us/artists/42/albums"),
+            "/v1/catalog/us/artists/42/albums?limit=100"
+        );
+        assert_eq!(
+            with_limit("/v1/catalog/us/artists/42/albums?offset=100"),
+            "/v1/catalog/us/artists/42/albums?offset=100&limit=100"
+        );
+        assert_eq!(
+            with_limit("/v1/catalog/us/artists/42/albums?offset=100&limit=100"),
+            "/v1/catalog/us/artists/42/albums?offset=100&limit=100"
+        );
+    }
+}
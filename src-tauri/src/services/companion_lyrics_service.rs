@@ -0,0 +1,228 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// companion_lyrics_service.rs -- Lyrics sidecars for companion downloads
+// ======================================================================
+//
+// With a companion mode on, one album folder holds the same track several
+// times: "01 Song [Dolby Atmos].m4a", "01 Song [Lossless].m4a" and
+// "01 Song.m4a". GAMDL writes the synced lyrics sidecar (`.lrc`, `.srt`
+// or `.ttml`, plus a converted `.vtt`/`.srt`/`.lrc` with
+// `convert_lyrics_to`) for the primary download only, and players look for a
+// sidecar with exactly the audio file's stem, so the companions show no
+// lyrics.
+//
+// After each companion tier succeeds, `copy_lyrics_to_companions()` groups
+// the audio files by their base stem (the stem with a codec suffix such as
+// " [Lossless]" stripped) and copies any sidecar found for one file of the
+// group to the others that lack it. Existing sidecars are never
+// overwritten. A tier saved to a subfolder of its own
+// (`AppSettings::companion_subfolders`) keeps clean filenames instead:
+//
+//   Album/01 Song.m4a            <- primary, with 01 Song.lrc
+//   Album/Lossless/01 Song.m4a   <- companion, gets Lossless/01 Song.lrc
+//
+// so its files are grouped with those at the same place in the album
+// folder.
+//
+// Skipped when `AppSettings::embed_lyrics_and_sidecar` is on: GAMDL then
+// writes a sidecar for every download itself, companions included.
+//
+// @see download_queue.rs -- calls copy_lyrics_to_companions() per tier
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::utils::files::{self, has_extension};
+
+/// Extensions of the lyrics sidecars GAMDL writes (see `LyricsFormat`),
+/// and of the converted ones (see `lyrics_conversion_service`).
+const LYRICS_EXTENSIONS: &[&str] = &["lrc", "srt", "ttml", "vtt"];
+
+/// Extensions of the audio files a sidecar can belong to (M4A as
+/// downloaded, FLAC after the optional conversion).
+const AUDIO_EXTENSIONS: &[&str] = &["m4a", "flac"];
+
+/// Copies lyrics sidecars between the versions of each track in an album
+/// folder (and its disc and companion subfolders).
+///
+/// # Arguments
+/// * `album_dir` -- Folder holding the primary and companion files.
+/// * `suffixes` -- The codec filename suffixes (e.g., "[Lossless]") that
+///   distinguish the versions of a track.
+/// * `subfolders` -- The companion subfolders (e.g., "Lossless") under
+///   `album_dir` that hold other versions of the album's tracks.
+///
+/// # Returns
+/// The number of sidecars created.
+pub fn copy_lyrics_to_companions(
+    album_dir: &Path,
+    suffixes: &[&str],
+    subfolders: &[&str],
+) -> usize {
+    let audio = files::walk_files(album_dir, |path| has_extension(path, AUDIO_EXTENSIONS));
+
+    // Audio files of each track, keyed by folder and stem without the
+    // companion subfolder or codec suffix
+    let mut tracks: HashMap<PathBuf, Vec<PathBuf>> = HashMap::new();
+    for file in audio {
+        if let Some(key) = track_key(album_dir, &file, suffixes, subfolders) {
+            tracks.entry(key).or_default().push(file);
+        }
+    }
+
+    let mut copied = 0;
+    for versions in tracks.values().filter(|v| v.len() > 1) {
+        for ext in LYRICS_EXTENSIONS {
+            let Some(source) = versions
+                .iter()
+                .map(|audio| audio.with_extension(ext))
+                .find(|sidecar| sidecar.is_file())
+            else {
+                continue;
+            };
+            for audio in versions {
+                let target = audio.with_extension(ext);
+                if target.exists() {
+                    continue;
+                }
+                match std::fs::copy(&source, &target) {
+                    Ok(_) => copied += 1,
+                    Err(e) => log::debug!("Could not copy lyrics to {}: {}", target.display(), e),
+                }
+            }
+        }
+    }
+    copied
+}
+
+/// The track an audio file is a version of: its path in `album_dir`
+/// without a leading companion subfolder, extension or codec suffix.
+/// "Lossless/Disc 2/01 Song.m4a" -> "Disc 2/01 Song".
+fn track_key(
+    album_dir: &Path,
+    file: &Path,
+    suffixes: &[&str],
+    subfolders: &[&str],
+) -> Option<PathBuf> {
+    let relative = file.strip_prefix(album_dir).ok()?;
+    let relative = subfolders
+        .iter()
+        .find_map(|subfolder| relative.strip_prefix(subfolder).ok())
+        .unwrap_or(relative);
+    let stem = relative.file_stem()?.to_str()?;
+    Some(relative.with_file_name(base_stem(stem, suffixes)))
+}
+
+/// Strips a trailing codec suffix (and the space before it) from a stem:
+/// "01 Song [Lossless]" -> "01 Song". Stems without one are returned as-is.
+fn base_stem<'a>(stem: &'a str, suffixes: &[&str]) -> &'a str {
+    suffixes
+        .iter()
+        .find_map(|suffix| stem.strip_suffix(suffix))
+        .map(str::trim_end)
+        .unwrap_or(stem)
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    const SUFFIXES: &[&str] = &["[Lossless]", "[Dolby Atmos]"];
+
+    /// Creates an empty temp album folder with the given files.
+    fn album_with(files: &[(&str, &str)]) -> TestDir {
+        let dir = TestDir::new("companion-lyrics");
+        for (name, content) in files {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+        dir
+    }
+
+    /// Verifies that codec suffixes are stripped to find the base stem.
+    #[test]
+    fn strips_codec_suffix() {
+        assert_eq!(base_stem("01 Song [Lossless]", SUFFIXES), "01 Song");
+        assert_eq!(base_stem("01 Song [Dolby Atmos]", SUFFIXES), "01 Song");
+        assert_eq!(base_stem("01 Song", SUFFIXES), "01 Song");
+        assert_eq!(base_stem("01 Song [Live]", SUFFIXES), "01 Song [Live]");
+    }
+
+    /// Verifies that the primary's `.lrc` is copied to a stem-matched name
+    /// for a suffixed companion, and in the other direction for a clean
+    /// companion of a suffixed primary.
+    #[test]
+    fn copies_primary_lyrics_to_companions() {
+        let dir = album_with(&[
+            ("01 Song.m4a", ""),
+            ("01 Song.lrc", "[00:01.00]Hello"),
+            ("01 Song [Lossless].m4a", ""),
+            ("02 Other [Dolby Atmos].m4a", ""),
+            ("02 Other [Dolby Atmos].ttml", "<tt/>"),
+            ("02 Other.m4a", ""),
+        ]);
+
+        assert_eq!(copy_lyrics_to_companions(&dir, SUFFIXES, &[]), 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("01 Song [Lossless].lrc")).unwrap(),
+            "[00:01.00]Hello"
+        );
+        assert_eq!(std::fs::read_to_string(dir.join("02 Other.ttml")).unwrap(), "<tt/>");
+    }
+
+    /// Verifies that nothing is copied without a sidecar, and that an
+    /// existing companion sidecar is left untouched.
+    #[test]
+    fn skips_missing_and_existing_sidecars() {
+        let dir = album_with(&[
+            ("01 Song.m4a", ""),
+            ("01 Song [Lossless].m4a", ""),
+            ("02 Other.m4a", ""),
+            ("02 Other.lrc", "primary"),
+            ("02 Other [Lossless].m4a", ""),
+            ("02 Other [Lossless].lrc", "companion"),
+        ]);
+
+        assert_eq!(copy_lyrics_to_companions(&dir, SUFFIXES, &[]), 0);
+        assert!(!dir.join("01 Song [Lossless].lrc").exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("02 Other [Lossless].lrc")).unwrap(),
+            "companion"
+        );
+    }
+
+    /// Verifies that companions in a tier subfolder get the primary's
+    /// sidecars, disc folders included, and that tracks in other folders
+    /// aren't matched with each other.
+    #[test]
+    fn copies_lyrics_into_companion_subfolders() {
+        let dir = album_with(&[("01 Song.m4a", ""), ("01 Song.lrc", "primary")]);
+        for folder in ["Lossless", "Disc 2", "Lossless/Disc 2"] {
+            std::fs::create_dir_all(dir.join(folder)).unwrap();
+        }
+        for (name, content) in [
+            ("Lossless/01 Song.m4a", ""),
+            ("Disc 2/01 Song.m4a", ""),
+            ("Disc 2/01 Song.ttml", "<tt/>"),
+            ("Lossless/Disc 2/01 Song.m4a", ""),
+        ] {
+            std::fs::write(dir.join(name), content).unwrap();
+        }
+
+        assert_eq!(copy_lyrics_to_companions(&dir, SUFFIXES, &["Lossless"]), 2);
+        assert_eq!(
+            std::fs::read_to_string(dir.join("Lossless/01 Song.lrc")).unwrap(),
+            "primary"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dir.join("Lossless/Disc 2/01 Song.ttml")).unwrap(),
+            "<tt/>"
+        );
+        assert!(!dir.join("Disc 2/01 Song.lrc").exists());
+    }
+}
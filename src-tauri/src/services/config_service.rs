@@ -44,15 +44,20 @@
 // - GAMDL config file format: https://github.com/glomatico/gamdl#configuration
 // - dirs crate for platform-standard directories: https://docs.rs/dirs/latest/dirs/
 
+use std::path::{Path, PathBuf};
+
 use tauri::AppHandle;
 
 // AppSettings is the Rust struct that mirrors all GUI settings.
 // It derives Serialize/Deserialize for JSON round-tripping and Default for first-run defaults.
 // Defined in models/settings.rs.
-use crate::models::settings::AppSettings;
+use crate::models::settings::{AppSettings, OverwritePolicy};
+// MusicServiceId selects the per-service subfolder of the default output path.
+use crate::models::music_service::MusicServiceId;
 // Platform utilities for resolving the app data directory and config file paths
 // across macOS, Windows, and Linux.
 use crate::utils::platform;
+use crate::utils::proxy::ProxyConfig;
 
 /// Loads the application settings from the JSON settings file.
 ///
@@ -97,6 +102,14 @@ pub fn load_settings(app: &AppHandle) -> Result<AppSettings, String> {
     }
 }
 
+/// Loads the user's proxy configuration from settings.
+///
+/// Falls back to direct connections if the settings file can't be read,
+/// so a corrupt settings file never blocks downloads or update checks.
+pub fn load_proxy_config(app: &AppHandle) -> ProxyConfig {
+    ProxyConfig::from_settings(&load_settings(app).unwrap_or_default())
+}
+
 /// Saves the application settings to the JSON settings file.
 ///
 /// Writes the settings as pretty-printed JSON for human readability.
@@ -132,7 +145,10 @@ pub fn save_settings(app: &AppHandle, settings: &AppSettings) -> Result<(), Stri
     // Sync relevant settings to GAMDL's config.ini (the derived config).
     // This is a best-effort operation: if it fails, the JSON save still succeeds.
     // GAMDL will still work via CLI flags; the config.ini is a convenience.
-    if let Err(e) = sync_to_gamdl_config(app, settings) {
+    // Skipped when the user maintains their own GAMDL config file.
+    if settings.use_gamdl_config_file {
+        log::info!("Using the user's GAMDL config file; not syncing config.ini");
+    } else if let Err(e) = sync_to_gamdl_config(app, settings) {
         log::warn!("Failed to sync settings to GAMDL config: {}", e);
         // Don't fail the save operation — the JSON settings are the source of truth
     }
@@ -277,7 +293,7 @@ fn settings_to_ini(settings: &AppSettings) -> String {
         lines.push(format!("output-path = {}", settings.output_path));
     }
     // Boolean flag: when present, existing files are overwritten without prompting.
-    if settings.overwrite {
+    if settings.effective_overwrite_policy() == OverwritePolicy::Replace {
         lines.push("overwrite".to_string());
     }
     // Truncate file/folder names to this many characters (avoids filesystem limits).
@@ -294,6 +310,15 @@ fn settings_to_ini(settings: &AppSettings) -> String {
     if settings.fetch_extra_tags {
         lines.push("fetch-extra-tags".to_string());
     }
+    // Boolean flag: when present, every track is tagged with the album's
+    // release date rather than its own.
+    if settings.use_album_date {
+        lines.push("use-album-date".to_string());
+    }
+    // strftime pattern for the date tag; GAMDL's default when unset.
+    if let Some(template) = settings.date_tag_template.as_deref().filter(|t| !t.trim().is_empty()) {
+        lines.push(format!("date-tag-template = {}", template));
+    }
 
     // === Templates ===
     // Output path templates use Python format strings with metadata placeholders.
@@ -375,28 +400,26 @@ fn settings_to_ini(settings: &AppSettings) -> String {
     lines.join("\n") + "\n"
 }
 
-/// Resolves the default output path for downloaded music.
+/// Resolves the default output path for downloaded music and creates it
+/// if it doesn't exist yet.
 ///
-/// Uses the platform's standard Music/Audio directory with an
-/// "Apple Music" subdirectory appended.
+/// Downloads are segregated per service under the platform's Music
+/// directory (e.g., `~/Music/Apple Music`, `~/Music/YouTube Music`).
+/// `None` means Apple Music, the only service implemented at runtime.
+///
+/// # Arguments
+/// * `service` - The service whose subfolder should be returned
 ///
 /// # Returns
 /// * `Ok(path)` - The default output path string
-/// * `Err(message)` - If the home directory couldn't be determined
-pub fn get_default_output_path() -> Result<String, String> {
-    // Attempt to resolve the platform's standard audio/music directory first:
-    // - macOS: ~/Music
-    // - Windows: C:\Users\{user}\Music
-    // - Linux: ~/Music (XDG_MUSIC_DIR, typically)
-    // Falls back to the home directory if no audio directory is configured.
-    // Ref: https://docs.rs/dirs/latest/dirs/fn.audio_dir.html
-    let music_dir = dirs::audio_dir()
-        .or_else(dirs::home_dir)
-        .ok_or_else(|| "Could not determine home directory".to_string())?;
+/// * `Err(message)` - If the home directory couldn't be determined or the
+///   folder couldn't be created
+pub fn get_default_output_path(service: Option<MusicServiceId>) -> Result<String, String> {
+    let output_path = resolve_default_output_path(service)?;
 
-    // Append "Apple Music" subdirectory to keep downloads organized.
-    // Example: ~/Music/Apple Music/
-    let output_path = music_dir.join("Apple Music");
+    // Create the folder so the frontend's "Browse" dialog can open it directly
+    std::fs::create_dir_all(&output_path)
+        .map_err(|e| format!("Failed to create output directory: {}", e))?;
 
     // Convert to a string for storage in settings. This can fail if the path
     // contains non-UTF-8 characters, which is extremely rare on modern systems.
@@ -406,6 +429,76 @@ pub fn get_default_output_path() -> Result<String, String> {
         .ok_or_else(|| "Failed to convert output path to string".to_string())
 }
 
+/// Resolves the default output path without touching the filesystem.
+///
+/// Used by `get_default_output_path()` and by `download_queue::merge_options()`
+/// to resolve the empty-string `AppSettings.output_path` sentinel (GAMDL
+/// creates the folder itself when the download starts).
+pub fn resolve_default_output_path(service: Option<MusicServiceId>) -> Result<PathBuf, String> {
+    let home = dirs::home_dir().ok_or_else(|| "Could not determine home directory".to_string())?;
+
+    // $XDG_CONFIG_HOME must be absolute to be honored; otherwise ~/.config
+    // Ref: https://specifications.freedesktop.org/basedir-spec/latest/
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| home.join(".config"));
+
+    let music_dir = music_dir_for(&home, &config_home);
+    Ok(service_output_dir(&music_dir, service))
+}
+
+/// Returns the platform's Music directory for the given home directory:
+/// - macOS: ~/Music
+/// - Windows: C:\Users\{user}\Music
+/// - Linux: `XDG_MUSIC_DIR` from `user-dirs.dirs`, falling back to ~/Music
+fn music_dir_for(home: &Path, config_home: &Path) -> PathBuf {
+    if cfg!(target_os = "linux") {
+        return xdg_music_dir(home, config_home).unwrap_or_else(|| home.join("Music"));
+    }
+    // Ref: https://docs.rs/dirs/latest/dirs/fn.audio_dir.html
+    dirs::audio_dir().unwrap_or_else(|| home.join("Music"))
+}
+
+/// Appends the per-service subfolder (the service's display name) to the
+/// Music directory. Example: `~/Music` + YouTube Music -> `~/Music/YouTube Music`.
+fn service_output_dir(music_dir: &Path, service: Option<MusicServiceId>) -> PathBuf {
+    let service = service.unwrap_or(MusicServiceId::AppleMusic);
+    music_dir.join(service.display_name())
+}
+
+/// Reads `XDG_MUSIC_DIR` from `{config_home}/user-dirs.dirs`.
+///
+/// The file is a shell fragment written by `xdg-user-dirs-update`:
+/// ```text
+/// XDG_MUSIC_DIR="$HOME/Music"
+/// ```
+/// Values are either absolute or relative to `$HOME`. A value equal to
+/// `$HOME` itself means the directory is disabled, so `None` is returned.
+/// Ref: https://www.freedesktop.org/wiki/Software/xdg-user-dirs/
+fn xdg_music_dir(home: &Path, config_home: &Path) -> Option<PathBuf> {
+    let contents = std::fs::read_to_string(config_home.join("user-dirs.dirs")).ok()?;
+
+    let value = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| line.strip_prefix("XDG_MUSIC_DIR="))?
+        .trim_matches('"');
+
+    let dir = match value.strip_prefix("$HOME") {
+        Some(rest) => home.join(rest.trim_start_matches('/')),
+        None if value.starts_with('/') => PathBuf::from(value),
+        None => return None,
+    };
+
+    if dir == home {
+        None
+    } else {
+        Some(dir)
+    }
+}
+
 // ============================================================
 // Unit Tests
 // ============================================================
@@ -413,6 +506,7 @@ pub fn get_default_output_path() -> Result<String, String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test_dir::TestDir;
 
     /// Helper: create default settings for testing
     fn default_settings() -> AppSettings {
@@ -649,12 +743,71 @@ mod tests {
     fn default_output_path_ends_with_apple_music() {
         // This test should work on all platforms since dirs::audio_dir()
         // or dirs::home_dir() should return a valid path.
-        let result = get_default_output_path();
+        let result = resolve_default_output_path(None);
         assert!(result.is_ok());
         let path = result.unwrap();
         assert!(path.ends_with("Apple Music"));
     }
 
+    /// Creates an empty temporary directory to act as $HOME.
+    fn temp_home() -> TestDir {
+        let home = TestDir::new("home");
+        std::fs::create_dir_all(home.join(".config")).unwrap();
+        home
+    }
+
+    #[test]
+    fn xdg_music_dir_read_from_user_dirs() {
+        let home = temp_home();
+        std::fs::write(
+            home.join(".config/user-dirs.dirs"),
+            "# Written by xdg-user-dirs-update\nXDG_DESKTOP_DIR=\"$HOME/Desktop\"\nXDG_MUSIC_DIR=\"$HOME/Musik\"\n",
+        )
+        .unwrap();
+
+        let dir = xdg_music_dir(&home, &home.join(".config"));
+        assert_eq!(dir, Some(home.join("Musik")));
+        if cfg!(target_os = "linux") {
+            assert_eq!(music_dir_for(&home, &home.join(".config")), home.join("Musik"));
+        }
+    }
+
+    #[test]
+    fn xdg_music_dir_absent_falls_back_to_home_music() {
+        let home = temp_home();
+
+        assert_eq!(xdg_music_dir(&home, &home.join(".config")), None);
+        if cfg!(target_os = "linux") {
+            assert_eq!(music_dir_for(&home, &home.join(".config")), home.join("Music"));
+        }
+    }
+
+    #[test]
+    fn xdg_music_dir_set_to_home_is_disabled() {
+        let home = temp_home();
+        std::fs::write(home.join(".config/user-dirs.dirs"), "XDG_MUSIC_DIR=\"$HOME/\"\n").unwrap();
+
+        assert_eq!(xdg_music_dir(&home, &home.join(".config")), None);
+    }
+
+    #[test]
+    fn service_output_dir_uses_per_service_subfolder() {
+        let music = PathBuf::from("/home/user/Music");
+        assert_eq!(service_output_dir(&music, None), music.join("Apple Music"));
+        assert_eq!(
+            service_output_dir(&music, Some(MusicServiceId::AppleMusic)),
+            music.join("Apple Music")
+        );
+        assert_eq!(
+            service_output_dir(&music, Some(MusicServiceId::YouTubeMusic)),
+            music.join("YouTube Music")
+        );
+        assert_eq!(
+            service_output_dir(&music, Some(MusicServiceId::Spotify)),
+            music.join("Spotify")
+        );
+    }
+
     // ----------------------------------------------------------
     // AppSettings serde roundtrip
     // ----------------------------------------------------------
@@ -0,0 +1,503 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// content_rating_service.rs -- Prefer the explicit or clean version of tracks
+// ============================================================================
+//
+// Apple Music often sells an album twice: an explicit release and a clean
+// one, each with catalog IDs of its own. GAMDL downloads whatever the URL
+// points at and has no option to pick the other version, so with
+// `AppSettings::content_preference` set to `Explicit` or `Clean`,
+// `commands::gamdl::start_download()` runs each request through
+// `apply_preference()` first, which for each album and song URL:
+//
+//   1. Lists its songs via the catalog API with their
+//      `attributes.contentRating` ("explicit", "clean", or none for songs
+//      that need no edit).
+//   2. If a song is rated the other way, fetches the album's other
+//      releases (`/albums/{id}/view/other-versions`) and picks the first
+//      one rated as preferred.
+//   3. Pairs each wrongly rated song with its counterpart on that release
+//      (same title, else same disc and track number) and replaces the URL
+//      with the song URLs, swapped where a counterpart exists:
+//
+//        album (explicit) --> song 1 (unrated, as is)
+//                             song 2 (clean counterpart)
+//                             song 3 (explicit, no clean version)
+//
+// A song without a counterpart is still downloaded in the version it has.
+// Playlists are left alone: each of their tracks comes from another
+// album, which would take a lookup per track.
+//
+// @see catalog_service.rs -- fetch_catalog_pages() and get_json(), the catalog requests
+// @see artist_expansion_service.rs -- musickit_token(), the catalog credentials
+// @see https://developer.apple.com/documentation/applemusicapi/songs/attributes-data.dictionary
+
+use tauri::AppHandle;
+
+use crate::models::download::DownloadRequest;
+use crate::models::settings::ContentPreference;
+use crate::services::catalog_service::{self, CatalogResource};
+use crate::services::{artist_expansion_service, config_service, download_queue};
+
+/// The content rating of a song or album (`attributes.contentRating`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ContentRating {
+    /// Contains explicit content
+    Explicit,
+    /// Edited version of explicit content
+    Clean,
+}
+
+impl ContentRating {
+    /// Parses the API value; content that needs no edit has no rating.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "explicit" => Some(Self::Explicit),
+            "clean" => Some(Self::Clean),
+            _ => None,
+        }
+    }
+}
+
+/// One song as listed by the catalog API, with what it takes to find its
+/// other version.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RatedTrack {
+    /// Catalog ID of the song
+    pub id: String,
+    /// Its title (empty if the API didn't return one)
+    pub name: String,
+    /// Its Apple Music page URL, if listed
+    pub url: Option<String>,
+    /// Its content rating; `None` for songs that need no edit
+    pub rating: Option<ContentRating>,
+    /// Disc number on its album (0 if not listed)
+    pub disc_number: u32,
+    /// Track number on its disc (0 if not listed)
+    pub track_number: u32,
+    /// Catalog ID of its album, if the API listed the relationship
+    pub album_id: Option<String>,
+}
+
+/// One release of an album, as listed by its `other-versions` view.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlbumVersion {
+    /// Catalog ID of the release
+    pub id: String,
+    /// Its content rating
+    pub rating: Option<ContentRating>,
+}
+
+/// The rating `preference` asks for, or `None` for `AsIs`.
+fn wanted_rating(preference: ContentPreference) -> Option<ContentRating> {
+    match preference {
+        ContentPreference::AsIs => None,
+        ContentPreference::Explicit => Some(ContentRating::Explicit),
+        ContentPreference::Clean => Some(ContentRating::Clean),
+    }
+}
+
+/// Whether a song rated `rating` should be swapped for its other version.
+/// Unrated songs never are: they're the same in both releases.
+pub fn is_wrong_version(preference: ContentPreference, rating: Option<ContentRating>) -> bool {
+    match (wanted_rating(preference), rating) {
+        (Some(wanted), Some(rating)) => wanted != rating,
+        _ => false,
+    }
+}
+
+/// Extracts the songs of one catalog page:
+///
+/// ```text
+/// { "data": [ { "id": "1440935808", "type": "songs",
+///               "attributes": { "name": "Style", "url": "https://music.apple.com/...",
+///                               "contentRating": "explicit",
+///                               "discNumber": 1, "trackNumber": 3 },
+///               "relationships": { "albums": { "data": [ { "id": "1440935467" } ] } } } ] }
+/// ```
+///
+/// Entries without an ID are ignored.
+pub fn parse_rated_tracks(page: &serde_json::Value) -> Vec<RatedTrack> {
+    let number = |t: &serde_json::Value, pointer: &str| {
+        t.pointer(pointer)
+            .and_then(|n| n.as_u64())
+            .and_then(|n| u32::try_from(n).ok())
+            .unwrap_or(0)
+    };
+    page.get("data")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|t| {
+                    Some(RatedTrack {
+                        id: t.get("id")?.as_str()?.to_string(),
+                        name: t
+                            .pointer("/attributes/name")
+                            .and_then(|n| n.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        url: t
+                            .pointer("/attributes/url")
+                            .and_then(|u| u.as_str())
+                            .map(str::to_string),
+                        rating: t
+                            .pointer("/attributes/contentRating")
+                            .and_then(|r| r.as_str())
+                            .and_then(ContentRating::parse),
+                        disc_number: number(t, "/attributes/discNumber"),
+                        track_number: number(t, "/attributes/trackNumber"),
+                        album_id: t
+                            .pointer("/relationships/albums/data/0/id")
+                            .and_then(|id| id.as_str())
+                            .map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Extracts the releases of an `other-versions` view page. Entries
+/// without an ID are ignored.
+pub fn parse_album_versions(page: &serde_json::Value) -> Vec<AlbumVersion> {
+    page.get("data")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|a| {
+                    Some(AlbumVersion {
+                        id: a.get("id")?.as_str()?.to_string(),
+                        rating: a
+                            .pointer("/attributes/contentRating")
+                            .and_then(|r| r.as_str())
+                            .and_then(ContentRating::parse),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Picks the release to take swapped songs from: the first one rated as
+/// `preference` asks.
+pub fn pick_album_version(
+    preference: ContentPreference,
+    versions: &[AlbumVersion],
+) -> Option<String> {
+    let wanted = wanted_rating(preference)?;
+    versions
+        .iter()
+        .find(|v| v.rating == Some(wanted))
+        .map(|v| v.id.clone())
+}
+
+/// Finds the version of `track` rated as `preference` asks among
+/// `alternatives`: the song with the same title (ignoring case), else the
+/// one at the same disc and track number.
+pub fn counterpart<'a>(
+    preference: ContentPreference,
+    track: &RatedTrack,
+    alternatives: &'a [RatedTrack],
+) -> Option<&'a RatedTrack> {
+    let wanted = wanted_rating(preference)?;
+    let candidates = || alternatives.iter().filter(|a| a.rating == Some(wanted));
+    candidates()
+        .find(|a| !track.name.is_empty() && a.name.eq_ignore_ascii_case(&track.name))
+        .or_else(|| {
+            candidates().find(|a| {
+                track.track_number > 0
+                    && a.disc_number == track.disc_number
+                    && a.track_number == track.track_number
+            })
+        })
+}
+
+/// Picks the song URLs to download in place of the URL `tracks` were
+/// listed from, swapping each wrongly rated song for its counterpart in
+/// `alternatives`.
+///
+/// Returns `None` when the URL should be queued as is: no song was
+/// swapped, or one of the songs has no URL of its own.
+pub fn select_versions(
+    preference: ContentPreference,
+    tracks: &[RatedTrack],
+    alternatives: &[RatedTrack],
+) -> Option<Vec<String>> {
+    let mut swapped = false;
+    let mut urls = Vec::new();
+    for track in tracks {
+        let pick = match counterpart(preference, track, alternatives) {
+            Some(other) if is_wrong_version(preference, track.rating) => {
+                swapped = true;
+                other
+            }
+            _ => track,
+        };
+        urls.push(pick.url.clone()?);
+    }
+    swapped.then_some(urls)
+}
+
+/// Swaps the album and song URLs of a request for their explicit or clean
+/// version, as `preference` asks (see the module docs).
+///
+/// URLs that aren't catalog albums or songs, that have nothing to swap,
+/// or whose lookup fails are kept. A swapped album keeps its album kind
+/// (`download_queue::with_urls()`), so its songs aren't filed as singles.
+pub async fn apply_preference(
+    app: &AppHandle,
+    request: DownloadRequest,
+    preference: ContentPreference,
+) -> DownloadRequest {
+    let swappable = |url: &String| {
+        matches!(
+            catalog_service::parse_catalog_url(url),
+            Some(CatalogResource::Album { .. } | CatalogResource::Song { .. })
+        )
+    };
+    if preference == ContentPreference::AsIs || !request.urls.iter().any(swappable) {
+        return request;
+    }
+
+    let token = match artist_expansion_service::musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            log::info!("Not picking explicit/clean versions: {}", e);
+            return request;
+        }
+    };
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Not picking explicit/clean versions: {}", e);
+            return request;
+        }
+    };
+
+    let mut urls = Vec::new();
+    for url in &request.urls {
+        match resolve_url(&client, &token, url, preference).await {
+            Ok(Some(swapped)) => {
+                log::info!(
+                    "Using the {:?} version of {} ({} track(s))",
+                    preference,
+                    url,
+                    swapped.len()
+                );
+                urls.extend(swapped);
+            }
+            Ok(None) => urls.push(url.clone()),
+            Err(e) => {
+                log::warn!("Could not look up the other version of {}: {}", url, e);
+                urls.push(url.clone());
+            }
+        }
+    }
+
+    download_queue::with_urls(request, urls)
+}
+
+/// Looks up the other version of one URL's songs.
+///
+/// # Returns
+/// * `Ok(Some(urls))` -- The song URLs to download instead.
+/// * `Ok(None)` -- Keep the URL: not an album or song, nothing rated the
+///   other way, or no release rated as preferred.
+/// * `Err(message)` -- A catalog request failed.
+async fn resolve_url(
+    client: &reqwest::Client,
+    token: &str,
+    url: &str,
+    preference: ContentPreference,
+) -> Result<Option<Vec<String>>, String> {
+    let resource = match catalog_service::parse_catalog_url(url) {
+        Some(resource @ (CatalogResource::Album { .. } | CatalogResource::Song { .. })) => resource,
+        _ => return Ok(None),
+    };
+    let tracks = list_tracks(client, token, &resource).await?;
+    if !tracks
+        .iter()
+        .any(|t| is_wrong_version(preference, t.rating))
+    {
+        return Ok(None);
+    }
+
+    let (storefront, album_id) = match resource {
+        CatalogResource::Album { storefront, id } => (storefront, Some(id)),
+        CatalogResource::Song { storefront, .. } => {
+            (storefront, tracks.first().and_then(|t| t.album_id.clone()))
+        }
+        CatalogResource::Playlist { .. } => return Ok(None),
+    };
+    let Some(album_id) = album_id else {
+        return Ok(None);
+    };
+
+    let path = format!(
+        "/v1/catalog/{}/albums/{}/view/other-versions",
+        storefront, album_id
+    );
+    let versions = parse_album_versions(&catalog_service::get_json(client, token, &path).await?);
+    let Some(version_id) = pick_album_version(preference, &versions) else {
+        log::info!("{} has no {:?} version", url, preference);
+        return Ok(None);
+    };
+
+    let other = CatalogResource::Album {
+        storefront,
+        id: version_id,
+    };
+    let alternatives = list_tracks(client, token, &other).await?;
+    Ok(select_versions(preference, &tracks, &alternatives))
+}
+
+/// Lists the songs of a catalog resource with their content ratings.
+async fn list_tracks(
+    client: &reqwest::Client,
+    token: &str,
+    resource: &CatalogResource,
+) -> Result<Vec<RatedTrack>, String> {
+    let pages = catalog_service::fetch_catalog_pages(client, token, resource).await?;
+    Ok(pages.iter().flat_map(parse_rated_tracks).collect())
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(id: &str, name: &str, number: u32, rating: Option<ContentRating>) -> RatedTrack {
+        RatedTrack {
+            id: id.to_string(),
+            name: name.to_string(),
+            url: Some(format!("https://music.apple.com/us/song/s/{}", id)),
+            rating,
+            disc_number: 1,
+            track_number: number,
+            album_id: None,
+        }
+    }
+
+    fn url(id: &str) -> String {
+        format!("https://music.apple.com/us/song/s/{}", id)
+    }
+
+    /// An explicit album: an unrated intro, two explicit songs and an
+    /// explicit bonus track the clean release leaves out.
+    fn explicit_album() -> Vec<RatedTrack> {
+        vec![
+            track("1", "Intro", 1, None),
+            track("2", "Song", 2, Some(ContentRating::Explicit)),
+            track("3", "Other Song", 3, Some(ContentRating::Explicit)),
+            track("4", "Bonus", 4, Some(ContentRating::Explicit)),
+        ]
+    }
+
+    /// The clean release of `explicit_album()`, with the second song
+    /// retitled.
+    fn clean_album() -> Vec<RatedTrack> {
+        vec![
+            track("11", "Intro", 1, None),
+            track("12", "Song", 2, Some(ContentRating::Clean)),
+            track("13", "Other Song (Edited)", 3, Some(ContentRating::Clean)),
+        ]
+    }
+
+    /// Verifies parsing of ratings, positions and the album relationship,
+    /// and of the releases of an other-versions view.
+    #[test]
+    fn parses_ratings_and_versions() {
+        let page = serde_json::json!({ "data": [
+            { "id": "2", "attributes": { "name": "Song", "url": url("2"),
+                "contentRating": "explicit", "discNumber": 1, "trackNumber": 2 },
+              "relationships": { "albums": { "data": [ { "id": "100" } ] } } },
+            { "id": "1", "attributes": { "name": "Intro" } },
+            { "attributes": { "name": "No ID" } }
+        ]});
+        let tracks = parse_rated_tracks(&page);
+        assert_eq!(tracks.len(), 2);
+        assert_eq!(tracks[0].rating, Some(ContentRating::Explicit));
+        assert_eq!((tracks[0].disc_number, tracks[0].track_number), (1, 2));
+        assert_eq!(tracks[0].album_id.as_deref(), Some("100"));
+        assert_eq!(tracks[1].rating, None);
+        assert_eq!(tracks[1].url, None);
+
+        let versions = parse_album_versions(&serde_json::json!({ "data": [
+            { "id": "200", "attributes": { "contentRating": "explicit" } },
+            { "id": "300", "attributes": { "contentRating": "clean" } },
+            { "id": "400", "attributes": {} }
+        ]}));
+        assert_eq!(
+            pick_album_version(ContentPreference::Clean, &versions).as_deref(),
+            Some("300")
+        );
+        assert_eq!(
+            pick_album_version(ContentPreference::Explicit, &versions).as_deref(),
+            Some("200")
+        );
+        assert_eq!(pick_album_version(ContentPreference::AsIs, &versions), None);
+    }
+
+    /// Verifies that preferring clean swaps each explicit song for its
+    /// counterpart (by title, else by position), keeps unrated songs, and
+    /// keeps an explicit song the clean release doesn't have.
+    #[test]
+    fn prefers_clean_track_by_track() {
+        let urls = select_versions(ContentPreference::Clean, &explicit_album(), &clean_album());
+        assert_eq!(urls, Some(vec![url("1"), url("12"), url("13"), url("4")]));
+    }
+
+    /// Verifies the other direction: a clean album swapped for the
+    /// explicit songs.
+    #[test]
+    fn prefers_explicit_track_by_track() {
+        let urls = select_versions(
+            ContentPreference::Explicit,
+            &clean_album(),
+            &explicit_album(),
+        );
+        assert_eq!(urls, Some(vec![url("11"), url("2"), url("3")]));
+    }
+
+    /// Verifies that nothing is swapped when the tracks already match the
+    /// preference, with `AsIs`, or when a song has no URL to queue.
+    #[test]
+    fn keeps_url_when_nothing_to_swap() {
+        assert_eq!(
+            select_versions(
+                ContentPreference::Explicit,
+                &explicit_album(),
+                &clean_album()
+            ),
+            None
+        );
+        assert_eq!(
+            select_versions(ContentPreference::AsIs, &explicit_album(), &clean_album()),
+            None
+        );
+
+        let mut tracks = explicit_album();
+        tracks[0].url = None;
+        assert_eq!(
+            select_versions(ContentPreference::Clean, &tracks, &clean_album()),
+            None
+        );
+    }
+
+    /// Verifies which ratings count as the wrong version.
+    #[test]
+    fn wrong_version_ignores_unrated_songs() {
+        use ContentPreference::*;
+        assert!(is_wrong_version(Clean, Some(ContentRating::Explicit)));
+        assert!(is_wrong_version(Explicit, Some(ContentRating::Clean)));
+        assert!(!is_wrong_version(Clean, Some(ContentRating::Clean)));
+        assert!(!is_wrong_version(Clean, None));
+        assert!(!is_wrong_version(AsIs, Some(ContentRating::Explicit)));
+    }
+}
@@ -42,6 +42,15 @@
 // - **Linux**: Chromium-based browsers may use the GNOME Keyring or KWallet
 //   for cookie encryption keys. The rookie crate accesses these via D-Bus.
 //
+// ## Live Check
+//
+// `test_cookies_live()` makes one authenticated Apple Music request with
+// the cookies (`/v1/me/storefront`, the lightest endpoint that needs a
+// signed-in account), sent through `catalog_service::request()` with the
+// MusicKit developer token (`artist_expansion_service::musickit_token()`).
+// It tells a malformed file, an expired or revoked token and working
+// cookies apart before a long download finds out.
+//
 // ## Privacy
 //
 // Only cookies for Apple Music domains are extracted. No other browsing data
@@ -57,9 +66,16 @@
 use serde::Serialize;
 use tauri::AppHandle;
 
-use crate::services::config_service;
+use crate::services::{artist_expansion_service, catalog_service, config_service};
 use crate::utils::platform;
 
+/// Cookie that carries the Apple Music sign-in (sent as `Media-User-Token`).
+const AUTH_COOKIE_NAME: &str = "media-user-token";
+
+/// Authenticated endpoint used by the live check: returns the account's
+/// storefront, and 401/403 when the user token is expired or revoked.
+const ME_STOREFRONT_URL: &str = "https://api.music.apple.com/v1/me/storefront";
+
 // ============================================================
 // Data Types
 // ============================================================
@@ -113,6 +129,55 @@ pub struct CookieImportResult {
     pub path: String,
 }
 
+/// Outcome of `test_cookies_live()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CookieLiveStatus {
+    /// Apple Music accepted the cookies.
+    Valid,
+    /// The file can't be read, isn't in Netscape format, or has no
+    /// `media-user-token` cookie.
+    Malformed,
+    /// The token has expired (by its cookie expiry) or Apple Music
+    /// rejected it (HTTP 401/403).
+    Expired,
+    /// The check itself failed (network error, unexpected response); the
+    /// cookies may or may not work.
+    Unverified,
+}
+
+/// Result of a live cookie check.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CookieLiveTest {
+    /// The outcome
+    pub status: CookieLiveStatus,
+    /// Human-readable explanation for the UI
+    pub message: String,
+    /// The account's storefront (e.g., "us") when the cookies are valid
+    pub storefront: Option<String>,
+}
+
+impl CookieLiveTest {
+    fn new(status: CookieLiveStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+            storefront: None,
+        }
+    }
+}
+
+/// The parts of a cookies file the live check sends.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthCookies {
+    /// Value of the `media-user-token` cookie.
+    pub media_user_token: String,
+    /// Expiry of that cookie (Unix timestamp, 0 for a session cookie).
+    pub expiry: i64,
+    /// Every apple.com cookie as a `Cookie` header value (`a=1; b=2`).
+    pub cookie_header: String,
+}
+
 // ============================================================
 // Browser Profile Directory Definitions
 // ============================================================
@@ -678,6 +743,141 @@ pub fn check_full_disk_access() -> bool {
     }
 }
 
+// ============================================================
+// Live Cookie Check
+// ============================================================
+
+/// Reads the Apple Music cookies from a Netscape cookies file.
+///
+/// # Returns
+/// * `Ok(AuthCookies)` -- The `media-user-token` (with several, the one
+///   expiring last) and the `Cookie` header for all apple.com cookies.
+/// * `Err(message)` -- No well-formed `media-user-token` line.
+pub fn parse_auth_cookies(contents: &str) -> Result<AuthCookies, String> {
+    let mut token: Option<(String, i64)> = None;
+    let mut header = Vec::new();
+    for line in contents.lines() {
+        // curl/yt-dlp mark HttpOnly cookies with a "#HttpOnly_" prefix
+        let line = line.trim().trim_start_matches("#HttpOnly_");
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        if fields.len() < 7 || !fields[0].trim_start_matches('.').ends_with("apple.com") {
+            continue;
+        }
+        let (name, value) = (fields[5], fields[6]);
+        header.push(format!("{}={}", name, value));
+        if name == AUTH_COOKIE_NAME && !value.is_empty() {
+            let expiry = fields[4].parse::<i64>().unwrap_or(0);
+            let rank = |e: i64| if e == 0 { i64::MAX } else { e };
+            if token.as_ref().is_none_or(|(_, best)| rank(expiry) > rank(*best)) {
+                token = Some((value.to_string(), expiry));
+            }
+        }
+    }
+
+    let (media_user_token, expiry) = token.ok_or_else(|| {
+        format!(
+            "No {} cookie found; the file isn't a Netscape cookies file from a signed-in music.apple.com session",
+            AUTH_COOKIE_NAME
+        )
+    })?;
+    Ok(AuthCookies {
+        media_user_token,
+        expiry,
+        cookie_header: header.join("; "),
+    })
+}
+
+/// Maps the response of the `/v1/me/storefront` request to a result.
+///
+/// | Response                         | Status       |
+/// |----------------------------------|--------------|
+/// | 200 with a storefront in `data`  | `Valid`      |
+/// | 401 / 403                        | `Expired`    |
+/// | anything else                    | `Unverified` |
+pub fn map_live_response(status: u16, body: &str) -> CookieLiveTest {
+    match status {
+        200 => {
+            let storefront = serde_json::from_str::<serde_json::Value>(body)
+                .ok()
+                .and_then(|json| json["data"][0]["id"].as_str().map(str::to_string));
+            match storefront {
+                Some(storefront) => CookieLiveTest {
+                    status: CookieLiveStatus::Valid,
+                    message: format!("Cookies work (storefront: {})", storefront),
+                    storefront: Some(storefront),
+                },
+                None => CookieLiveTest::new(
+                    CookieLiveStatus::Unverified,
+                    "Apple Music returned an unexpected response",
+                ),
+            }
+        }
+        401 | 403 => CookieLiveTest::new(
+            CookieLiveStatus::Expired,
+            "Apple Music rejected the cookies; sign in again or re-import them",
+        ),
+        other => CookieLiveTest::new(
+            CookieLiveStatus::Unverified,
+            format!("Apple Music returned HTTP {}", other),
+        ),
+    }
+}
+
+/// Checks whether the cookies in `path` actually sign in to Apple Music,
+/// with one authenticated request.
+///
+/// A file that doesn't parse, or whose token expired by its own cookie
+/// expiry, is reported without any request. Failures of the check itself
+/// (no network, no MusicKit credentials) are `Unverified`, never `Expired`.
+pub async fn test_cookies_live(app: &AppHandle, path: &str) -> CookieLiveTest {
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return CookieLiveTest::new(
+                CookieLiveStatus::Malformed,
+                format!("Cookies file can't be read: {}", e),
+            )
+        }
+    };
+    let cookies = match parse_auth_cookies(&contents) {
+        Ok(cookies) => cookies,
+        Err(e) => return CookieLiveTest::new(CookieLiveStatus::Malformed, e),
+    };
+    if cookies.expiry > 0 && cookies.expiry < chrono::Utc::now().timestamp() {
+        return CookieLiveTest::new(
+            CookieLiveStatus::Expired,
+            format!("The {} cookie has expired; sign in again", AUTH_COOKIE_NAME),
+        );
+    }
+
+    let unverified = |e: String| CookieLiveTest::new(CookieLiveStatus::Unverified, e);
+    let token = match artist_expansion_service::musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => return unverified(format!("The live check needs MusicKit credentials: {}", e)),
+    };
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => return unverified(e),
+    };
+
+    let response = catalog_service::request(&client, &token, ME_STOREFRONT_URL)
+        .header("Media-User-Token", &cookies.media_user_token)
+        .header("Cookie", &cookies.cookie_header)
+        .send()
+        .await;
+    match response {
+        Ok(response) => {
+            let status = response.status().as_u16();
+            let body = response.text().await.unwrap_or_default();
+            map_live_response(status, &body)
+        }
+        Err(e) => unverified(format!("Apple Music API request failed: {}", e)),
+    }
+}
+
 // ============================================================
 // Unit Tests
 // ============================================================
@@ -798,4 +998,50 @@ mod tests {
         // Should not panic on any platform
         let _has_fda = check_full_disk_access();
     }
+
+    // ----------------------------------------------------------
+    // parse_auth_cookies / map_live_response: live check
+    // ----------------------------------------------------------
+
+    /// Verifies that the media-user-token and the apple.com Cookie header
+    /// are read from a Netscape file, HttpOnly lines included.
+    #[test]
+    fn parses_auth_cookies_from_netscape_file() {
+        let contents = "# Netscape HTTP Cookie File\n\
+            .music.apple.com\tTRUE\t/\tTRUE\t1900000000\tmedia-user-token\tAbC123\n\
+            #HttpOnly_.apple.com\tTRUE\t/\tTRUE\t0\tmyacinfo\tXYZ\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tother\tignored\n";
+        let cookies = parse_auth_cookies(contents).unwrap();
+        assert_eq!(cookies.media_user_token, "AbC123");
+        assert_eq!(cookies.expiry, 1_900_000_000);
+        assert_eq!(cookies.cookie_header, "media-user-token=AbC123; myacinfo=XYZ");
+    }
+
+    /// Verifies that a file without a usable media-user-token is malformed.
+    #[test]
+    fn cookies_without_token_are_malformed() {
+        assert!(parse_auth_cookies("not a cookies file").is_err());
+        assert!(parse_auth_cookies(
+            ".music.apple.com\tTRUE\t/\tTRUE\t0\tmedia-user-token\t\n"
+        )
+        .is_err());
+    }
+
+    /// Verifies the mapping of mocked responses: 200 with a storefront is
+    /// valid, 401/403 is expired, and a malformed 200 or another status
+    /// can't verify the cookies either way.
+    #[test]
+    fn maps_live_responses() {
+        let valid = map_live_response(200, r#"{"data":[{"id":"gb","type":"storefronts"}]}"#);
+        assert_eq!(valid.status, CookieLiveStatus::Valid);
+        assert_eq!(valid.storefront.as_deref(), Some("gb"));
+
+        assert_eq!(map_live_response(401, "").status, CookieLiveStatus::Expired);
+        assert_eq!(map_live_response(403, "{}").status, CookieLiveStatus::Expired);
+
+        let malformed = map_live_response(200, "<html>oops</html>");
+        assert_eq!(malformed.status, CookieLiveStatus::Unverified);
+        assert_eq!(malformed.storefront, None);
+        assert_eq!(map_live_response(500, "").status, CookieLiveStatus::Unverified);
+    }
 }
@@ -0,0 +1,492 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// cover_resize_service.rs -- Smaller embedded cover art
+// ======================================================
+//
+// GAMDL uses one `--cover-size` for both the cover it embeds in every
+// track and the `cover.jpg` it saves next to them. A 3000x3000 embedded
+// cover adds a megabyte or more to each track, multiplied across an album.
+// With `AppSettings::embedded_cover_size` set below the cover size, every
+// M4A the download saved gets its embedded cover scaled down:
+//
+//   - The cover (`covr` atom) is read with `mp4ameta`, scaled with FFmpeg
+//     as a standalone image, and written back with `mp4ameta`. Only that
+//     atom changes: the audio and every other atom, including the
+//     freeform iTunes ones (iTunSMPB gapless info, iTunNORM) and the store
+//     IDs (cnID, plID, atID), stay as GAMDL wrote them. A remux through
+//     FFmpeg would drop those.
+//   - The saved sidecar cover is not an M4A and is never touched.
+//   - Only files written by this download are processed (modified after
+//     its GAMDL run started), so tracks from earlier downloads in the same
+//     folder keep their covers.
+//
+// The image is scaled between two temporary files next to the track
+// (`<name>.cover-resize.*`), which are removed afterwards. A track that
+// can't be resized keeps its cover, and the rest of the album is still
+// processed.
+//
+// ## Extra cover sizes
+//
+// With `AppSettings::extra_cover_sizes` set (and `save_cover` on), the
+// saved sidecar cover is also scaled to each listed size and saved next
+// to it as `cover-{size}.jpg` (e.g. `cover-600.jpg` as a thumbnail). The
+// sidecar is resized, not downloaded again; sizes at or above the cover
+// size the download requested are skipped.
+//
+// @see download_queue.rs -- runs resize_album() before FLAC conversion,
+//                          and save_extra_covers()
+// @see https://ffmpeg.org/ffmpeg-filters.html#scale-1
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use mp4ameta::{Img, ImgFmt, Tag};
+
+use crate::models::settings::AppSettings;
+use crate::services::flac_conversion_service;
+
+/// Returns the size to scale embedded covers to, or `None` to leave them
+/// as GAMDL wrote them.
+///
+/// # Arguments
+/// * `embedded_cover_size` -- `AppSettings::embedded_cover_size`.
+/// * `cover_size` -- The cover size the download requested (after the
+///   artwork probe capped it at the album's native size).
+///
+/// # Returns
+/// `Some(size)` only when the setting is on and smaller than the cover
+/// GAMDL embedded; scaling up would grow the files without adding detail.
+pub fn target_size(embedded_cover_size: Option<u32>, cover_size: Option<u32>) -> Option<u32> {
+    let size = embedded_cover_size.filter(|&size| size > 0)?;
+    match cover_size {
+        Some(cover_size) if size >= cover_size => None,
+        _ => Some(size),
+    }
+}
+
+/// Returns the extra cover sizes to save, largest first, without
+/// duplicates.
+///
+/// # Arguments
+/// * `sizes` -- `AppSettings::extra_cover_sizes`.
+/// * `cover_size` -- The cover size the download requested; sizes at or
+///   above it would only copy (or upscale) the saved cover.
+pub fn extra_sizes(sizes: &[u32], cover_size: Option<u32>) -> Vec<u32> {
+    let mut sizes: Vec<u32> = sizes
+        .iter()
+        .copied()
+        .filter(|&size| size > 0 && cover_size.is_none_or(|cover| size < cover))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+/// File name of the extra cover of `size` pixels, e.g. `cover-600.jpg`.
+pub fn extra_cover_name(size: u32) -> String {
+    format!("cover-{}.jpg", size)
+}
+
+/// Finds the sidecar cover GAMDL saved in `album_dir` (`Cover.jpg` or
+/// `Cover.png`, any case). If there is more than one, the largest file is
+/// taken as the highest-resolution one.
+pub fn find_saved_cover(album_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(album_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let stem = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or_default();
+            let ext = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .unwrap_or_default();
+            stem.eq_ignore_ascii_case("cover")
+                && ["jpg", "jpeg", "png"]
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+        .max_by_key(|path| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0))
+}
+
+/// Saves the sidecar cover in `album_dir` at each of `sizes` as
+/// `cover-{size}.jpg`, replacing earlier ones.
+///
+/// # Returns
+/// * `Ok(count)` -- Number of files written; `0` when the album has no
+///   saved cover.
+/// * `Err(message)` -- FFmpeg is missing, or a resize failed. Files written
+///   before the failure are kept.
+pub async fn save_extra_covers(
+    app: &AppHandle,
+    settings: &AppSettings,
+    album_dir: &Path,
+    sizes: &[u32],
+) -> Result<usize, String> {
+    let Some(cover) = find_saved_cover(album_dir) else {
+        return Ok(0);
+    };
+    let ffmpeg = flac_conversion_service::resolve_ffmpeg(app, settings, "extra cover sizes")?;
+
+    for &size in sizes {
+        let output = album_dir.join(extra_cover_name(size));
+        let result = Command::new(&ffmpeg)
+            .args(ffmpeg_extra_cover_args(&cover, &output, size))
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+        if !result.status.success() {
+            let _ = std::fs::remove_file(&output);
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            return Err(format!(
+                "Saving {} failed: {}",
+                output.display(),
+                stderr.trim()
+            ));
+        }
+    }
+    Ok(sizes.len())
+}
+
+/// Builds the FFmpeg arguments that save `input` (an image) scaled to fit
+/// within `size` x `size` as a JPEG.
+///
+/// Flags:
+///   -filter:v scale=...   -- shrink to fit, never enlarge
+///   -frames:v 1           -- one image, not an image sequence
+///   -c:v mjpeg -q:v 2     -- high-quality JPEG, whatever the input format
+pub fn ffmpeg_extra_cover_args(input: &Path, output: &Path, size: u32) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error", "-y", "-i"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.push(input.to_string_lossy().to_string());
+    args.push("-filter:v".to_string());
+    args.push(format!(
+        "scale=w='min(iw,{size})':h='min(ih,{size})':force_original_aspect_ratio=decrease",
+        size = size
+    ));
+    args.extend(
+        ["-frames:v", "1", "-c:v", "mjpeg", "-q:v", "2"]
+            .iter()
+            .map(|s| s.to_string()),
+    );
+    args.push(output.to_string_lossy().to_string());
+    args
+}
+
+/// Scales the embedded cover of every M4A under `album_dir` that was
+/// written at or after `since`.
+///
+/// A file that fails is logged and skipped; the others are still resized.
+///
+/// # Returns
+/// * `Ok(count)` -- Number of files rewritten.
+/// * `Err(message)` -- FFmpeg is missing; nothing was resized.
+pub async fn resize_album(
+    app: &AppHandle,
+    settings: &AppSettings,
+    album_dir: &Path,
+    since: SystemTime,
+    size: u32,
+) -> Result<usize, String> {
+    let ffmpeg = flac_conversion_service::resolve_ffmpeg(app, settings, "embedded cover resizing")?;
+
+    let mut resized = 0;
+    for input in flac_conversion_service::find_m4a_files(album_dir) {
+        if !modified_since(&input, since) {
+            continue;
+        }
+        match resize_file(&ffmpeg, &input, size).await {
+            Ok(true) => resized += 1,
+            Ok(false) => log::debug!("No embedded cover in {}", input.display()),
+            Err(e) => log::warn!("{}", e),
+        }
+    }
+    Ok(resized)
+}
+
+/// Builds the FFmpeg arguments that save `input` (a cover image) scaled
+/// to fit within `size` x `size`, in the same format.
+///
+/// Flags:
+///   -filter:v scale=...              -- shrink to fit, never enlarge
+///   -frames:v 1                      -- one image, not an image sequence
+///   -c:v mjpeg -q:v 2 / -c:v png     -- re-encode in the cover's format
+pub fn ffmpeg_resize_args(input: &Path, output: &Path, size: u32, format: &ImgFmt) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error", "-y", "-i"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.push(input.to_string_lossy().to_string());
+    args.push("-filter:v".to_string());
+    args.push(format!(
+        "scale=w='min(iw,{size})':h='min(ih,{size})':force_original_aspect_ratio=decrease",
+        size = size
+    ));
+    args.extend(["-frames:v", "1", "-c:v"].iter().map(|s| s.to_string()));
+    match format {
+        ImgFmt::Png => args.push("png".to_string()),
+        // Apple serves covers as JPEG; a BMP is re-encoded as one too
+        ImgFmt::Jpeg | ImgFmt::Bmp => {
+            args.push("mjpeg".to_string());
+            args.push("-q:v".to_string());
+            args.push("2".to_string());
+        }
+    }
+    args.push(output.to_string_lossy().to_string());
+    args
+}
+
+/// Replaces the embedded cover of one file with a smaller copy.
+///
+/// # Returns
+/// * `Ok(true)` -- The cover was resized.
+/// * `Ok(false)` -- The file has no embedded cover.
+/// * `Err(message)` -- Reading, scaling or writing failed; the file keeps
+///   its cover.
+async fn resize_file(ffmpeg: &Path, input: &Path, size: u32) -> Result<bool, String> {
+    let mut tag = Tag::read_from_path(input)
+        .map_err(|e| format!("Could not read the tags of {}: {}", input.display(), e))?;
+    let Some(cover) = tag.artwork() else {
+        return Ok(false);
+    };
+    let (source_ext, format) = match cover.fmt {
+        ImgFmt::Png => ("png", ImgFmt::Png),
+        ImgFmt::Jpeg => ("jpg", ImgFmt::Jpeg),
+        ImgFmt::Bmp => ("bmp", ImgFmt::Jpeg),
+    };
+    let source = temp_path(input, &format!("src.{}", source_ext));
+    let scaled = temp_path(input, if format == ImgFmt::Png { "png" } else { "jpg" });
+
+    let result = scale_image(ffmpeg, cover.data, &source, &scaled, size, &format).await;
+    let _ = std::fs::remove_file(&source);
+    let _ = std::fs::remove_file(&scaled);
+    let data =
+        result.map_err(|e| format!("Resizing the cover of {} failed: {}", input.display(), e))?;
+
+    tag.set_artwork(Img::new(format, data));
+    tag.write_to_path(input)
+        .map_err(|e| format!("Could not write the cover of {}: {}", input.display(), e))?;
+    Ok(true)
+}
+
+/// Writes `data` to `source`, scales it into `scaled` with FFmpeg and
+/// returns the scaled image.
+async fn scale_image(
+    ffmpeg: &Path,
+    data: &[u8],
+    source: &Path,
+    scaled: &Path,
+    size: u32,
+    format: &ImgFmt,
+) -> Result<Vec<u8>, String> {
+    std::fs::write(source, data).map_err(|e| e.to_string())?;
+
+    // kill_on_drop: the queue aborts this task if the download is removed.
+    let output = Command::new(ffmpeg)
+        .args(ffmpeg_resize_args(source, scaled, size, format))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    std::fs::read(scaled).map_err(|e| e.to_string())
+}
+
+/// `01 Song.m4a` + `jpg` -> `01 Song.cover-resize.jpg`, a temporary file
+/// next to the track.
+fn temp_path(input: &Path, extension: &str) -> PathBuf {
+    input.with_extension(format!("cover-resize.{}", extension))
+}
+
+/// Returns whether `path` is a temporary file of a resize in progress.
+pub fn is_temp_path(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.contains(".cover-resize."))
+}
+
+/// Returns whether the file was modified at or after `since`.
+pub fn modified_since(path: &Path, since: SystemTime) -> bool {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|modified| modified >= since)
+        .unwrap_or(false)
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    // ----------------------------------------------------------
+    // target_size()
+    // ----------------------------------------------------------
+
+    /// Verifies that resizing only runs with the setting on and below the
+    /// cover size the download requested.
+    #[test]
+    fn resizes_only_when_set_and_smaller() {
+        assert_eq!(target_size(None, Some(3000)), None, "Off by default");
+        assert_eq!(target_size(Some(600), Some(3000)), Some(600));
+        assert_eq!(target_size(Some(3000), Some(3000)), None, "Same size");
+        assert_eq!(target_size(Some(5000), Some(3000)), None, "Never upscale");
+        assert_eq!(target_size(Some(0), Some(3000)), None);
+        assert_eq!(target_size(Some(600), None), Some(600));
+    }
+
+    // ----------------------------------------------------------
+    // ffmpeg_resize_args()
+    // ----------------------------------------------------------
+
+    /// Returns the values following `flag` in `args`.
+    fn flag_value<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+        args.windows(2)
+            .filter(|w| w[0] == flag)
+            .map(|w| w[1].as_str())
+            .collect()
+    }
+
+    /// Verifies that the cover image is scaled to fit and re-encoded as a
+    /// single JPEG into the temporary file.
+    #[test]
+    fn ffmpeg_args_scale_cover_image() {
+        let track = Path::new("/music/01 Song.m4a");
+        let (source, scaled) = (temp_path(track, "src.jpg"), temp_path(track, "jpg"));
+        let args = ffmpeg_resize_args(&source, &scaled, 600, &ImgFmt::Jpeg);
+
+        assert_eq!(
+            flag_value(&args, "-i"),
+            vec![source.to_string_lossy().as_ref()]
+        );
+        assert_eq!(
+            flag_value(&args, "-filter:v"),
+            vec!["scale=w='min(iw,600)':h='min(ih,600)':force_original_aspect_ratio=decrease"]
+        );
+        assert_eq!(flag_value(&args, "-frames:v"), vec!["1"]);
+        assert_eq!(flag_value(&args, "-c:v"), vec!["mjpeg"]);
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some(scaled.to_string_lossy().as_ref())
+        );
+        assert!(is_temp_path(&scaled));
+        assert_eq!(scaled.file_name().unwrap(), "01 Song.cover-resize.jpg");
+    }
+
+    /// Verifies that PNG covers stay PNG.
+    #[test]
+    fn ffmpeg_args_keep_png_covers_png() {
+        let args = ffmpeg_resize_args(
+            Path::new("/music/a.cover-resize.src.png"),
+            Path::new("/music/a.cover-resize.png"),
+            1000,
+            &ImgFmt::Png,
+        );
+        assert_eq!(flag_value(&args, "-c:v"), vec!["png"]);
+        assert!(flag_value(&args, "-q:v").is_empty());
+    }
+
+    /// Verifies that a file whose tags can't be read fails without being
+    /// changed, before FFmpeg is run.
+    #[tokio::test]
+    async fn resize_file_leaves_unreadable_files_alone() {
+        let dir = TestDir::new("cover-resize");
+        let broken = dir.join("01 Song.m4a");
+        std::fs::write(&broken, b"not an mp4").unwrap();
+
+        let ffmpeg = Path::new("ffmpeg-that-does-not-exist");
+        assert!(resize_file(ffmpeg, &broken, 600).await.is_err());
+        assert_eq!(std::fs::read(&broken).unwrap(), b"not an mp4");
+    }
+
+    // ----------------------------------------------------------
+    // Extra cover sizes
+    // ----------------------------------------------------------
+
+    /// Verifies that extra sizes are sorted largest first without
+    /// duplicates, and that sizes not below the cover size are dropped.
+    #[test]
+    fn extra_sizes_skip_full_size_and_duplicates() {
+        assert_eq!(
+            extra_sizes(&[300, 1200, 300, 0], Some(3000)),
+            vec![1200, 300]
+        );
+        assert_eq!(extra_sizes(&[600, 3000, 5000], Some(3000)), vec![600]);
+        assert_eq!(extra_sizes(&[600], None), vec![600]);
+        assert!(extra_sizes(&[], Some(3000)).is_empty());
+    }
+
+    /// Verifies the naming and FFmpeg arguments of a couple of sizes: the
+    /// saved cover is the input, scaled to fit and written as one JPEG
+    /// named after the size.
+    #[test]
+    fn extra_cover_args_and_names() {
+        let album = Path::new("/music/Artist/Album");
+        let cover = album.join("Cover.png");
+        for (size, name) in [(1200, "cover-1200.jpg"), (300, "cover-300.jpg")] {
+            assert_eq!(extra_cover_name(size), name);
+            let args = ffmpeg_extra_cover_args(&cover, &album.join(name), size);
+
+            assert_eq!(
+                flag_value(&args, "-i"),
+                vec![cover.to_string_lossy().as_ref()]
+            );
+            assert_eq!(
+                flag_value(&args, "-filter:v"),
+                vec![format!(
+                    "scale=w='min(iw,{0})':h='min(ih,{0})':force_original_aspect_ratio=decrease",
+                    size
+                )]
+            );
+            assert_eq!(flag_value(&args, "-frames:v"), vec!["1"]);
+            assert_eq!(flag_value(&args, "-c:v"), vec!["mjpeg"]);
+            assert_eq!(
+                args.last().map(String::as_str),
+                Some(album.join(name).to_string_lossy().as_ref())
+            );
+        }
+    }
+
+    /// Verifies that the largest `Cover.*` image is taken as the saved
+    /// cover, and that other images and the extra covers are ignored.
+    #[test]
+    fn finds_largest_saved_cover() {
+        let dir = TestDir::new("cover");
+        assert_eq!(find_saved_cover(&dir), None);
+
+        std::fs::write(dir.join("cover-600.jpg"), b"small copy").unwrap();
+        std::fs::write(dir.join("FrontCover.mp4"), b"a much larger motion cover").unwrap();
+        std::fs::write(dir.join("Cover.jpg"), b"jpg").unwrap();
+        assert_eq!(find_saved_cover(&dir), Some(dir.join("Cover.jpg")));
+        std::fs::write(dir.join("cover.PNG"), b"larger png").unwrap();
+        assert_eq!(find_saved_cover(&dir), Some(dir.join("cover.PNG")));
+    }
+
+    /// Verifies that files older than the download's start are skipped.
+    #[test]
+    fn only_files_written_since_start_count() {
+        let dir = TestDir::new("cover");
+        let file = dir.join("01 Song.m4a");
+        std::fs::write(&file, b"").unwrap();
+
+        let earlier = SystemTime::now() - std::time::Duration::from_secs(60);
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert!(modified_since(&file, earlier));
+        assert!(!modified_since(&file, later));
+    }
+}
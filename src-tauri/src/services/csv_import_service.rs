@@ -0,0 +1,317 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// csv_import_service.rs -- Bulk enqueue from a CSV file
+// =======================================================
+//
+// For building a library from a script or a spreadsheet, the `import_csv`
+// command reads one download per row, each with options of its own:
+//
+//   url,codec,output_path,label
+//   https://music.apple.com/us/album/1989/1440935467,alac,/Music/Lossless,
+//   https://music.apple.com/us/album/red/1440933849,aac,,road trip
+//
+// Only `url` is required; an empty `codec`, `output_path` or `label`
+// falls back to the settings (no override). The header row is optional:
+// without one the columns are read in the order above, and with one they
+// may come in any order. Blank lines and lines starting with `#` are
+// skipped. Fields may be double-quoted (`"a, b"`, with `""` for a quote);
+// a field can't span lines, so a row is always one line of the file.
+//
+// A row with a broken URL, an unknown codec or a relative output path is
+// reported with its line number and left out; the other rows are still
+// queued. `parse_csv()` does the parsing, `commands::gamdl::import_csv()`
+// the enqueuing.
+
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::models::download::DownloadRequest;
+use crate::models::gamdl_options::{GamdlOptions, SongCodec};
+use crate::services::gamdl_service;
+
+/// Columns of an import file, in the order used when there's no header.
+pub const CSV_COLUMNS: [&str; 4] = ["url", "codec", "output_path", "label"];
+
+/// A row that couldn't be imported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CsvRowError {
+    /// Line number in the file, starting at 1
+    pub line: usize,
+    /// What's wrong with the row
+    pub message: String,
+}
+
+/// A parsed import file.
+#[derive(Debug, Clone, Default)]
+pub struct CsvImport {
+    /// One request per valid row, with its line number, in file order
+    pub requests: Vec<(usize, DownloadRequest)>,
+    /// The rows that were left out
+    pub errors: Vec<CsvRowError>,
+}
+
+/// Result of the `import_csv` command, serialized to the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvImportReport {
+    /// IDs of the downloads that were queued, in file order
+    pub download_ids: Vec<String>,
+    /// The rows that were left out
+    pub errors: Vec<CsvRowError>,
+}
+
+/// Splits one CSV line into its fields, trimmed.
+///
+/// Returns an error for a quoted field that isn't closed on the line.
+pub fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (',', false) => fields.push(std::mem::take(&mut field).trim().to_string()),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("Unclosed quote".to_string());
+    }
+    fields.push(field.trim().to_string());
+    Ok(fields)
+}
+
+/// Parses an import file into download requests and row errors.
+///
+/// # Returns
+/// * `Ok(CsvImport)` -- The valid rows as requests, and one error per
+///   invalid row. An empty file yields no requests.
+/// * `Err(message)` -- The header names a column that doesn't exist; no
+///   row can be read reliably then.
+pub fn parse_csv(text: &str) -> Result<CsvImport, String> {
+    let mut import = CsvImport::default();
+    let mut columns: Option<Vec<String>> = None;
+
+    let rows = text
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+    for (line, row) in rows {
+        let fields = match split_csv_line(row) {
+            Ok(fields) => fields,
+            Err(message) => {
+                import.errors.push(CsvRowError { line, message });
+                continue;
+            }
+        };
+
+        // A first row with a "url" column is the header
+        let first_row = columns.is_none() && import.requests.is_empty() && import.errors.is_empty();
+        if first_row && fields.iter().any(|f| f.eq_ignore_ascii_case("url")) {
+            let names: Vec<String> = fields.iter().map(|f| f.to_lowercase()).collect();
+            if let Some(unknown) = names.iter().find(|n| !CSV_COLUMNS.contains(&n.as_str())) {
+                return Err(format!(
+                    "Unknown column \"{}\" on line {} (expected {})",
+                    unknown,
+                    line,
+                    CSV_COLUMNS.join(", ")
+                ));
+            }
+            columns = Some(names);
+            continue;
+        }
+
+        let column = |name: &str| -> Option<&str> {
+            let index = match &columns {
+                Some(names) => names.iter().position(|n| n == name)?,
+                None => CSV_COLUMNS.iter().position(|n| *n == name)?,
+            };
+            fields
+                .get(index)
+                .map(String::as_str)
+                .filter(|f| !f.is_empty())
+        };
+        match parse_row(
+            column("url"),
+            column("codec"),
+            column("output_path"),
+            column("label"),
+        ) {
+            Ok(request) => import.requests.push((line, request)),
+            Err(message) => import.errors.push(CsvRowError { line, message }),
+        }
+    }
+    Ok(import)
+}
+
+/// Builds the request of one row from its non-empty fields.
+fn parse_row(
+    url: Option<&str>,
+    codec: Option<&str>,
+    output_path: Option<&str>,
+    label: Option<&str>,
+) -> Result<DownloadRequest, String> {
+    let url = url.ok_or("Missing URL")?.to_string();
+    gamdl_service::validate_urls(std::slice::from_ref(&url))?;
+
+    let mut options = GamdlOptions::default();
+    if let Some(codec) = codec {
+        options.song_codec = Some(parse_codec(codec)?);
+    }
+    if let Some(path) = output_path {
+        if !Path::new(path).is_absolute() {
+            return Err(format!("Output path \"{}\" is not an absolute path", path));
+        }
+        options.output_path = Some(path.to_string());
+    }
+    let has_overrides = options.song_codec.is_some() || options.output_path.is_some();
+
+    Ok(DownloadRequest {
+        urls: vec![url],
+        options: has_overrides.then_some(options),
+        label: label.map(str::to_string),
+        priority: 0,
+        fallback_chain_override: None,
+        kind: None,
+    })
+}
+
+/// Parses a codec by its CLI name (e.g. `"aac-legacy"`), ignoring case.
+pub fn parse_codec(name: &str) -> Result<SongCodec, String> {
+    serde_json::from_value(serde_json::Value::String(name.to_lowercase()))
+        .map_err(|_| format!("Unknown codec \"{}\"", name))
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::settings::AppSettings;
+    use crate::services::download_queue::{merge_options, request_kind};
+
+    const ALBUM_URL: &str = "https://music.apple.com/us/album/1989/1440935467";
+    const SONG_URL: &str = "https://music.apple.com/us/song/style/1440935808";
+
+    /// Verifies quoting: commas and doubled quotes inside quotes, trimmed
+    /// fields, and an error for a quote that's never closed.
+    #[test]
+    fn splits_quoted_fields() {
+        assert_eq!(
+            split_csv_line(r#"a, "b, c" ,"say ""hi""","#).unwrap(),
+            vec!["a", "b, c", r#"say "hi""#, ""]
+        );
+        assert!(split_csv_line(r#"a,"b"#).is_err());
+    }
+
+    /// Verifies that valid rows become requests while broken ones are
+    /// reported with their line numbers and don't stop the import.
+    #[test]
+    fn mixed_rows_report_line_numbers() {
+        // Absolute on every platform (a leading "/" isn't on Windows)
+        let lossless = std::env::temp_dir().join("Lossless");
+        let csv = format!(
+            "url,codec,output_path,label\n\
+             {album},alac,{lossless},\n\
+             \n\
+             # comment\n\
+             https://example.com/album/1,aac,,\n\
+             {song},flac,,\n\
+             {song},AAC-Legacy,,\"road trip, day 1\"\n\
+             {album},,Music,\n\
+             ,alac,,\n",
+            album = ALBUM_URL,
+            song = SONG_URL,
+            lossless = lossless.display()
+        );
+        let import = parse_csv(&csv).unwrap();
+
+        let lines: Vec<usize> = import.requests.iter().map(|(line, _)| *line).collect();
+        assert_eq!(lines, vec![2, 7]);
+        let errors: Vec<(usize, &str)> = import
+            .errors
+            .iter()
+            .map(|e| (e.line, e.message.as_str()))
+            .collect();
+        assert_eq!(errors.len(), 4);
+        assert_eq!(errors[0].0, 5);
+        assert!(
+            errors[0].1.contains("Invalid Apple Music URL"),
+            "{}",
+            errors[0].1
+        );
+        assert_eq!(errors[1], (6, "Unknown codec \"flac\""));
+        assert_eq!(errors[2].0, 8);
+        assert!(errors[2].1.contains("absolute"), "{}", errors[2].1);
+        assert_eq!(errors[3], (9, "Missing URL"));
+
+        let (_, road_trip) = &import.requests[1];
+        assert_eq!(road_trip.label.as_deref(), Some("road trip, day 1"));
+        assert_eq!(
+            road_trip.options.as_ref().unwrap().song_codec,
+            Some(SongCodec::AacLegacy)
+        );
+    }
+
+    /// Verifies that without a header the columns are positional, that a
+    /// URL-only row carries no overrides, and that a header may reorder
+    /// the columns but not name unknown ones.
+    #[test]
+    fn header_is_optional_and_orders_columns() {
+        let import = parse_csv(&format!("{}\n{},aac\n", SONG_URL, ALBUM_URL)).unwrap();
+        assert_eq!(import.requests.len(), 2);
+        assert!(import.requests[0].1.options.is_none());
+        assert_eq!(
+            import.requests[1].1.options.as_ref().unwrap().song_codec,
+            Some(SongCodec::Aac)
+        );
+
+        let import = parse_csv(&format!("Label,URL\nmine,{}\n", SONG_URL)).unwrap();
+        assert!(import.errors.is_empty());
+        assert_eq!(import.requests[0].1.urls, vec![SONG_URL.to_string()]);
+        assert_eq!(import.requests[0].1.label.as_deref(), Some("mine"));
+
+        assert!(parse_csv("url,quality\n").unwrap_err().contains("quality"));
+    }
+
+    /// Verifies that a row's codec and output path override the settings
+    /// when merged, and everything else still comes from the settings.
+    #[test]
+    fn row_overrides_merge_over_settings() {
+        let mut settings = AppSettings::default();
+        settings.output_path = "/Music/Apple Music".to_string();
+        settings.save_cover = false;
+        let car = std::env::temp_dir().join("Car").display().to_string();
+        let import = parse_csv(&format!(
+            "{album},aac,{car},\n{album},,,\n",
+            album = ALBUM_URL,
+            car = car
+        ))
+        .unwrap();
+
+        let (_, custom) = &import.requests[0];
+        let merged = merge_options(custom.options.as_ref(), &settings, request_kind(custom));
+        assert_eq!(merged.song_codec, Some(SongCodec::Aac));
+        assert_eq!(merged.output_path.as_deref(), Some(car.as_str()));
+        assert_eq!(merged.save_cover, Some(false));
+
+        let (_, plain) = &import.requests[1];
+        let merged = merge_options(plain.options.as_ref(), &settings, request_kind(plain));
+        assert_eq!(merged.song_codec, Some(settings.default_song_codec.clone()));
+        assert_eq!(merged.output_path.as_deref(), Some("/Music/Apple Music"));
+    }
+}
@@ -18,6 +18,8 @@
 //                     get_tool_download_url() --> platform-specific URL
 //                        |
 //                     archive::download_and_extract() --> {app_data}/tools/ffmpeg/
+//                        |  (progress_events() --> dependency-download-progress,
+//                        |                         dependency-extract-progress)
 //                        |
 //                     find_binary_recursive() --> locate binary in extracted dir
 //                        |
@@ -50,12 +52,17 @@
 // - Tokio async filesystem operations: https://docs.rs/tokio/latest/tokio/fs/
 
 use std::path::PathBuf;
-use tauri::AppHandle;
+use std::sync::{Arc, Mutex};
+
+use serde::Serialize;
+use tauri::{AppHandle, Emitter};
 
 // `archive` provides download_and_extract() for streaming HTTP download + archive extraction,
-// and set_executable() for chmod +x on Unix systems.
+// its progress reports, and set_executable() for chmod +x on Unix systems.
 // `platform` provides get_tools_dir() for resolving the {app_data}/tools/ directory.
 use crate::utils::{archive, platform};
+// `config_service` provides the user's proxy settings for the archive download.
+use crate::services::config_service;
 
 // ============================================================
 // Tool metadata: describes each external dependency
@@ -366,12 +373,105 @@ fn resolve_tool_id(name_or_id: &str) -> Result<&'static str, String> {
     Err(format!("Unknown tool: {}", name_or_id))
 }
 
+// ============================================================
+// Install progress events
+// ============================================================
+
+/// Event emitted while a dependency's archive downloads, with a
+/// [`DependencyDownloadProgress`] payload.
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "dependency-download-progress";
+
+/// Event emitted while a dependency's archive is extracted, with a
+/// [`DependencyExtractProgress`] payload.
+pub const EXTRACT_PROGRESS_EVENT: &str = "dependency-extract-progress";
+
+/// Bytes between two events of a download of unknown size.
+const UNKNOWN_SIZE_EVENT_BYTES: u64 = 1024 * 1024;
+
+/// Payload of [`DOWNLOAD_PROGRESS_EVENT`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyDownloadProgress {
+    /// What is being installed: a tool ID (e.g. `"ffmpeg"`) or `"python"`
+    pub name: String,
+    /// Bytes received so far
+    pub downloaded: u64,
+    /// Size of the archive, or `None` when the server didn't send one
+    pub total: Option<u64>,
+    /// 0-100, or `None` (indeterminate) when the size is unknown
+    pub percent: Option<f64>,
+}
+
+/// Payload of [`EXTRACT_PROGRESS_EVENT`].
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyExtractProgress {
+    /// What is being installed: a tool ID (e.g. `"ffmpeg"`) or `"python"`
+    pub name: String,
+    /// Entries (ZIP) or compressed bytes (TAR.GZ) extracted so far
+    pub extracted: u64,
+    /// Entries or compressed bytes in all
+    pub total: Option<u64>,
+    /// 0-100, or `None` when the total is unknown
+    pub percent: Option<f64>,
+}
+
+/// Builds the progress callback of a `download_and_extract()` call that
+/// emits its reports as install progress events for `name`.
+///
+/// Reports arrive once per network chunk or read, so events are only sent
+/// when the whole percentage changes (every MiB when the size is unknown),
+/// and at the start and end of each stage.
+pub fn progress_events(app: &AppHandle, name: &str) -> archive::ProgressFn {
+    let app = app.clone();
+    let name = name.to_string();
+    // Stage, whole percentage and byte count of the last event sent
+    let last: Mutex<Option<(archive::ProgressStage, Option<u64>, u64)>> = Mutex::new(None);
+    Arc::new(move |stage, progress| {
+        let percent = progress.percent();
+        let whole = percent.map(|p| p as u64);
+        {
+            let mut last = last.lock().unwrap_or_else(|e| e.into_inner());
+            let due = match *last {
+                Some((s, w, done)) if s == stage => match whole {
+                    Some(_) => whole != w,
+                    None => progress.done.saturating_sub(done) >= UNKNOWN_SIZE_EVENT_BYTES,
+                },
+                _ => true,
+            };
+            if !due {
+                return;
+            }
+            *last = Some((stage, whole, progress.done));
+        }
+
+        let _ = match stage {
+            archive::ProgressStage::Download => app.emit(
+                DOWNLOAD_PROGRESS_EVENT,
+                DependencyDownloadProgress {
+                    name: name.clone(),
+                    downloaded: progress.done,
+                    total: progress.total,
+                    percent,
+                },
+            ),
+            archive::ProgressStage::Extract => app.emit(
+                EXTRACT_PROGRESS_EVENT,
+                DependencyExtractProgress {
+                    name: name.clone(),
+                    extracted: progress.done,
+                    total: progress.total,
+                    percent,
+                },
+            ),
+        };
+    })
+}
+
 /// Downloads and installs a specific tool dependency.
 ///
 /// Performs the complete installation pipeline:
 /// 1. Determines the download URL for the current platform
-/// 2. Downloads the archive
-/// 3. Extracts to the tool's directory
+/// 2. Downloads the archive, emitting [`DOWNLOAD_PROGRESS_EVENT`]s
+/// 3. Extracts to the tool's directory, emitting [`EXTRACT_PROGRESS_EVENT`]s
 /// 4. Locates the binary within the extracted contents
 /// 5. Sets executable permissions (Unix)
 /// 6. Verifies the binary works by running --version (if supported)
@@ -408,7 +508,9 @@ pub async fn install_tool(app: &AppHandle, name_or_id: &str) -> Result<String, S
 
     // Step 3: Download and extract the archive
     log::info!("Downloading {} from {}", tool_id, url);
-    archive::download_and_extract(&url, &tool_dir, format).await?;
+    let proxy = config_service::load_proxy_config(app);
+    let progress = progress_events(app, tool_id);
+    archive::download_and_extract(&url, &tool_dir, format, &proxy, Some(progress)).await?;
 
     // Step 4: Find the binary in the extracted contents.
     // Archives often contain nested directory structures. For example:
@@ -511,6 +613,20 @@ fn find_binary_recursive(dir: &PathBuf, tool_id: &str) -> Option<PathBuf> {
     None
 }
 
+/// Returns the flag that makes a tool print its version.
+///
+/// - FFmpeg and MP4Box use single-dash "-version" (non-standard but that's how they work)
+/// - Most other tools use double-dash "--version" (GNU convention)
+///
+/// Also used by `environment_service` for the environment report.
+pub fn version_flag(tool_id: &str) -> &'static str {
+    match tool_id {
+        "ffmpeg" => "-version",   // e.g., "ffmpeg version N-112479-..."
+        "mp4box" => "-version",   // e.g., "MP4Box - GPAC version 2.4-DEV..."
+        _ => "--version",         // Standard GNU-style flag
+    }
+}
+
 /// Attempts to get the version of an installed tool binary.
 ///
 /// Runs the binary with common version flags (--version, -version) and
@@ -520,14 +636,8 @@ fn find_binary_recursive(dir: &PathBuf, tool_id: &str) -> Option<PathBuf> {
 /// * `binary_path` - Path to the tool binary
 /// * `tool_id` - The tool identifier (for tool-specific parsing)
 async fn get_tool_version(binary_path: &PathBuf, tool_id: &str) -> Result<String, String> {
-    // Different tools use different version flags:
-    // - FFmpeg and MP4Box use single-dash "-version" (non-standard but that's how they work)
-    // - Most other tools use double-dash "--version" (GNU convention)
-    let version_flag = match tool_id {
-        "ffmpeg" => "-version",   // e.g., "ffmpeg version N-112479-..."
-        "mp4box" => "-version",   // e.g., "MP4Box - GPAC version 2.4-DEV..."
-        _ => "--version",         // Standard GNU-style flag
-    };
+    // Different tools use different version flags (see version_flag())
+    let version_flag = version_flag(tool_id);
 
     // Run the binary with the version flag and capture output.
     // This serves as both a version check and a basic health check
@@ -0,0 +1,251 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// download_log_service.rs -- Per-download GAMDL output logs
+// ==========================================================
+//
+// When a download fails, the app log holds GAMDL's output interleaved with
+// every other job (and only at debug level). With
+// `AppSettings::save_download_logs` on, each job's raw stdout/stderr is
+// also written to a file of its own:
+//
+//   {app_data}/logs/downloads/{download_id}.log
+//
+// Every GAMDL run of the job (retries, codec fallbacks) appends to the same
+// file, starting with a header line that holds the command line:
+//
+//   === 2026-01-15T10:30:00+00:00 gamdl --cookies-path /path/cookies.txt ...
+//   [stdout] Getting track 1 of 12: Song by Artist
+//   [stderr] [download] 45.2% of ~ 48.5MiB at 2.51MiB/s ETA 00:01
+//
+// The file is written as captured; `read_log()` redacts the cookies path
+// (which points at the user's Apple Music session) before the text leaves
+// the backend, so it can be pasted into a bug report as is.
+//
+// A log lives as long as its queue item: `delete_logs()` removes it when
+// the item is cleared (by hand or by auto-clear), and `prune_logs()` runs
+// at startup to drop the logs of items that didn't survive the restart.
+//
+// @see download_queue.rs -- run_download_with_events() writes the log
+// @see commands/gamdl.rs -- get_download_log / open_download_log
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+use regex::Regex;
+use tauri::AppHandle;
+
+use crate::utils::platform;
+
+/// Shown in place of the cookies path in returned log text.
+pub const REDACTED_COOKIES: &str = "<cookies file>";
+
+/// Error returned by read_log() when the job has no log file.
+pub const NO_LOG_CAPTURED: &str =
+    "No log captured for this download. Turn on \"Save Download Logs\" and run it again.";
+
+/// Matches the value of GAMDL's `--cookies-path` flag (quoted or not).
+static COOKIES_FLAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(--cookies-path[=\s]+)("[^"]*"|'[^']*'|\S+)"#)
+        .expect("Invalid cookies flag regex")
+});
+
+/// An open per-download log file, shared by the stdout and stderr readers.
+pub struct DownloadLog {
+    file: Mutex<std::fs::File>,
+}
+
+impl DownloadLog {
+    /// Opens (or creates) the log at `path` for appending and writes the
+    /// header line for a new run.
+    pub fn open(path: &Path, command_line: &str) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        writeln!(
+            file,
+            "=== {} {}",
+            chrono::Local::now().to_rfc3339(),
+            command_line
+        )?;
+        Ok(Self {
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Appends one output line, tagged with the stream it came from
+    /// ("stdout" or "stderr"). Write errors are logged and otherwise
+    /// ignored: a full log disk must not fail the download.
+    pub fn write_line(&self, stream: &str, line: &str) {
+        let mut file = self.file.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = writeln!(file, "[{}] {}", stream, line) {
+            log::warn!("Failed to write download log: {}", e);
+        }
+    }
+}
+
+/// Returns the folder holding the per-download logs.
+pub fn log_dir(app: &AppHandle) -> PathBuf {
+    platform::get_app_data_dir(app)
+        .join("logs")
+        .join("downloads")
+}
+
+/// Returns the log file path of a download.
+pub fn log_path(app: &AppHandle, download_id: &str) -> PathBuf {
+    log_dir(app).join(format!("{}.log", download_id))
+}
+
+/// Deletes the logs of downloads that left the queue. Jobs that ran with
+/// logging off have no file, which is fine.
+pub fn delete_logs(app: &AppHandle, download_ids: &[String]) {
+    for id in download_ids {
+        remove_log(&log_path(app, id));
+    }
+}
+
+/// Deletes every log in `dir` that doesn't belong to one of `keep_ids`,
+/// i.e. the logs of downloads no longer in the queue.
+///
+/// # Returns
+/// The number of logs deleted.
+pub fn prune_logs(dir: &Path, keep_ids: &[String]) -> usize {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return 0;
+    };
+    let mut pruned = 0;
+    for path in entries.flatten().map(|e| e.path()) {
+        let is_log = path.extension().is_some_and(|ext| ext == "log");
+        let kept = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .is_some_and(|id| keep_ids.iter().any(|k| k == id));
+        if is_log && !kept && remove_log(&path) {
+            pruned += 1;
+        }
+    }
+    if pruned > 0 {
+        log::info!(
+            "Deleted {} download log(s) of items no longer queued",
+            pruned
+        );
+    }
+    pruned
+}
+
+/// Deletes one log file; a missing file counts as nothing to delete.
+///
+/// # Returns
+/// `true` if a file was deleted.
+fn remove_log(path: &Path) -> bool {
+    match std::fs::remove_file(path) {
+        Ok(()) => true,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            log::warn!("Failed to delete {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+/// Hides the cookies path in log text: the `--cookies-path` value, and any
+/// other mention of `cookies_path` (e.g., in a GAMDL error message).
+pub fn redact(text: &str, cookies_path: Option<&str>) -> String {
+    let mut redacted = COOKIES_FLAG_REGEX
+        .replace_all(text, |caps: &regex::Captures| {
+            format!("{}{}", &caps[1], REDACTED_COOKIES)
+        })
+        .into_owned();
+    if let Some(path) = cookies_path.filter(|p| !p.trim().is_empty()) {
+        redacted = redacted.replace(path, REDACTED_COOKIES);
+    }
+    redacted
+}
+
+/// Reads a download's log with the cookies path redacted.
+///
+/// # Returns
+/// * `Ok(text)` -- The captured output of every run of the job.
+/// * `Err(NO_LOG_CAPTURED)` -- Logging was off for the job's runs.
+/// * `Err(message)` -- The file exists but couldn't be read.
+pub fn read_log(path: &Path, cookies_path: Option<&str>) -> Result<String, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(redact(&text, cookies_path)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(NO_LOG_CAPTURED.to_string()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    /// Verifies that the cookies path is hidden both as the flag value and
+    /// where GAMDL quotes it in a message, and that other text is kept.
+    #[test]
+    fn redacts_cookies_path() {
+        let text = "=== 2026-01-15T10:30:00+00:00 gamdl --cookies-path /home/me/cookies.txt --song-codec alac\n\
+                    [stderr] Error: could not read /home/me/cookies.txt\n";
+        let redacted = redact(text, Some("/home/me/cookies.txt"));
+        assert!(!redacted.contains("/home/me/cookies.txt"), "{}", redacted);
+        assert!(redacted.contains("--cookies-path <cookies file> --song-codec alac"));
+        assert!(redacted.contains("could not read <cookies file>"));
+
+        // Without a known path, a quoted flag value is still hidden
+        let quoted = redact("gamdl --cookies-path \"/a b/c.txt\" url", None);
+        assert_eq!(quoted, "gamdl --cookies-path <cookies file> url");
+    }
+
+    /// Verifies that a job without a log file reports that nothing was
+    /// captured, and that a written log reads back redacted.
+    #[test]
+    fn reads_captured_log_or_reports_none() {
+        let dir = TestDir::new("dllog");
+        let path = dir.join("missing.log");
+        assert_eq!(read_log(&path, None).unwrap_err(), NO_LOG_CAPTURED);
+
+        let path = dir.join("job.log");
+        let log = DownloadLog::open(&path, "gamdl --cookies-path /c.txt url").unwrap();
+        log.write_line("stdout", "Getting track 1 of 2: Song by Artist");
+        log.write_line("stderr", "ERROR: boom");
+        drop(log);
+
+        let text = read_log(&path, Some("/c.txt")).unwrap();
+        assert!(
+            text.contains("--cookies-path <cookies file> url"),
+            "{}",
+            text
+        );
+        assert!(text.contains("[stdout] Getting track 1 of 2: Song by Artist"));
+        assert!(text.contains("[stderr] ERROR: boom"));
+    }
+
+    /// Verifies that pruning deletes the logs of downloads not in the queue
+    /// and keeps the queued ones' logs and unrelated files.
+    #[test]
+    fn prunes_logs_of_unqueued_downloads() {
+        let dir = TestDir::new("dllog-prune");
+        for name in ["queued.log", "gone-1.log", "gone-2.log", "notes.txt"] {
+            std::fs::write(dir.join(name), "x").unwrap();
+        }
+
+        assert_eq!(prune_logs(&dir, &["queued".to_string()]), 2);
+        assert!(dir.join("queued.log").exists());
+        assert!(!dir.join("gone-1.log").exists());
+        assert!(!dir.join("gone-2.log").exists());
+        assert!(dir.join("notes.txt").exists());
+
+        // A missing folder (logging never on) prunes nothing
+        assert_eq!(prune_logs(&dir.join("missing"), &[]), 0);
+    }
+}
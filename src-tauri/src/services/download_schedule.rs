@@ -0,0 +1,244 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// download_schedule.rs -- Quiet-hours download window
+// ===================================================
+//
+// Users on a metered daytime connection can restrict downloads to a daily
+// window (e.g., 23:00-07:00) with `AppSettings::schedule_enabled`,
+// `schedule_start` and `schedule_end` (local time, "HH:MM").
+//
+// ## How the window is enforced
+//
+//   - `process_queue()` hands the current `DownloadSchedule` to the queue
+//     before asking for the next item, and `DownloadQueue::next_pending()`
+//     returns `None` while the window is closed. Items stay Queued, so the
+//     queue is effectively paused (and auto-quit won't fire).
+//   - A background ticker (`spawn_schedule_ticker()`) checks the window
+//     every `TICK_INTERVAL`. When it opens, the ticker calls
+//     `process_queue()` to start the deferred downloads. When it closes
+//     and `AppSettings::schedule_pause_at_close` is on, running downloads
+//     are stopped and re-queued (`DownloadQueue::pause_for_schedule()`);
+//     otherwise they are allowed to finish.
+//
+// A window whose end is earlier than its start crosses midnight. A window
+// with equal start and end times covers the whole day.
+//
+// @see download_queue.rs -- DownloadQueue::next_pending(),
+//      DownloadQueue::pause_for_schedule()
+
+use std::time::Duration;
+
+use chrono::NaiveTime;
+use tauri::{AppHandle, Emitter};
+
+use crate::models::settings::AppSettings;
+use crate::services::config_service;
+use crate::services::download_queue::{self, QueueHandle};
+
+/// How often the ticker checks whether the window has opened or closed.
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A daily download window in local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DownloadSchedule {
+    /// Time the window opens (inclusive)
+    pub start: NaiveTime,
+    /// Time the window closes (exclusive)
+    pub end: NaiveTime,
+}
+
+impl DownloadSchedule {
+    /// Builds the schedule from the user's settings. Returns `None` when
+    /// scheduling is disabled, or when a time can't be parsed (logged; the
+    /// settings command rejects such values, so this only guards against
+    /// hand-edited settings files).
+    pub fn from_settings(settings: &AppSettings) -> Option<Self> {
+        if !settings.schedule_enabled {
+            return None;
+        }
+        match (
+            parse_clock_time(&settings.schedule_start),
+            parse_clock_time(&settings.schedule_end),
+        ) {
+            (Ok(start), Ok(end)) => Some(Self { start, end }),
+            (Err(e), _) | (_, Err(e)) => {
+                log::warn!("Ignoring download schedule: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Returns whether downloads may run at the given local time.
+    ///
+    /// | Window        | Open when                 |
+    /// |---------------|---------------------------|
+    /// | start < end   | `start <= now < end`      |
+    /// | start > end   | `now >= start \|\| now < end` (crosses midnight) |
+    /// | start == end  | always (24-hour window)   |
+    pub fn is_open_at(&self, now: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= now && now < self.end
+        } else if self.start > self.end {
+            now >= self.start || now < self.end
+        } else {
+            true
+        }
+    }
+
+    /// Returns whether downloads may run right now.
+    pub fn is_open_now(&self) -> bool {
+        self.is_open_at(chrono::Local::now().time())
+    }
+}
+
+/// Parses a "HH:MM" time of day (24-hour clock).
+///
+/// # Returns
+/// * `Ok(time)` for values like "07:00" or "23:30".
+/// * `Err(message)` naming the rejected value.
+pub fn parse_clock_time(value: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| format!("'{}' is not a valid time (expected HH:MM)", value))
+}
+
+/// Starts the background task that opens and closes the download window.
+///
+/// Each tick reloads the settings (so edits apply without a restart),
+/// passes the schedule to the queue, and acts on transitions:
+///   - closed -> open: emits `download-window-opened` and calls
+///     `process_queue()`.
+///   - open -> closed: emits `download-window-closed` and, if
+///     `schedule_pause_at_close` is on, pauses running downloads.
+pub fn spawn_schedule_ticker(app: AppHandle, queue: QueueHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        // The first tick only records the current state: downloads
+        // restored at startup are started by the restore logic in lib.rs.
+        let mut was_open: Option<bool> = None;
+
+        loop {
+            interval.tick().await;
+
+            let settings = config_service::load_settings(&app).unwrap_or_default();
+            let schedule = DownloadSchedule::from_settings(&settings);
+            queue.lock().await.set_schedule(schedule);
+
+            let open = schedule.is_none_or(|s| s.is_open_now());
+            let previous = was_open.replace(open);
+
+            match (previous, open) {
+                (Some(false), true) => {
+                    log::info!("Download window opened, resuming queue");
+                    let _ = app.emit("download-window-opened", ());
+                    download_queue::process_queue(app.clone(), queue.clone()).await;
+                }
+                (Some(true), false) => {
+                    let _ = app.emit("download-window-closed", ());
+                    if settings.schedule_pause_at_close {
+                        let paused = queue.lock().await.pause_for_schedule();
+                        log::info!(
+                            "Download window closed, pausing {} running download(s)",
+                            paused
+                        );
+                    } else {
+                        log::info!(
+                            "Download window closed, letting running downloads finish"
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+    });
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(value: &str) -> NaiveTime {
+        parse_clock_time(value).unwrap()
+    }
+
+    fn schedule(start: &str, end: &str) -> DownloadSchedule {
+        DownloadSchedule {
+            start: time(start),
+            end: time(end),
+        }
+    }
+
+    // ----------------------------------------------------------
+    // parse_clock_time()
+    // ----------------------------------------------------------
+
+    /// Verifies that 24-hour HH:MM values parse and others are rejected.
+    #[test]
+    fn parses_hh_mm_times() {
+        assert_eq!(time("07:30"), NaiveTime::from_hms_opt(7, 30, 0).unwrap());
+        assert_eq!(time(" 23:05 "), NaiveTime::from_hms_opt(23, 5, 0).unwrap());
+        assert!(parse_clock_time("24:00").is_err());
+        assert!(parse_clock_time("7pm").is_err());
+        assert!(parse_clock_time("").is_err());
+    }
+
+    // ----------------------------------------------------------
+    // DownloadSchedule::is_open_at()
+    // ----------------------------------------------------------
+
+    /// Verifies a same-day window: start inclusive, end exclusive.
+    #[test]
+    fn same_day_window() {
+        let window = schedule("09:00", "17:00");
+        assert!(!window.is_open_at(time("08:59")));
+        assert!(window.is_open_at(time("09:00")));
+        assert!(window.is_open_at(time("12:00")));
+        assert!(!window.is_open_at(time("17:00")));
+        assert!(!window.is_open_at(time("23:00")));
+    }
+
+    /// Verifies a window that crosses midnight.
+    #[test]
+    fn midnight_crossing_window() {
+        let window = schedule("23:00", "07:00");
+        assert!(window.is_open_at(time("23:00")));
+        assert!(window.is_open_at(time("23:59")));
+        assert!(window.is_open_at(time("00:00")));
+        assert!(window.is_open_at(time("06:59")));
+        assert!(!window.is_open_at(time("07:00")));
+        assert!(!window.is_open_at(time("12:00")));
+        assert!(!window.is_open_at(time("22:59")));
+    }
+
+    /// Verifies that equal start and end times mean "always open".
+    #[test]
+    fn equal_start_and_end_is_always_open() {
+        let window = schedule("02:00", "02:00");
+        assert!(window.is_open_at(time("02:00")));
+        assert!(window.is_open_at(time("14:00")));
+    }
+
+    // ----------------------------------------------------------
+    // DownloadSchedule::from_settings()
+    // ----------------------------------------------------------
+
+    /// Verifies that a disabled or invalid schedule imposes no window.
+    #[test]
+    fn from_settings_requires_enabled_and_valid_times() {
+        let mut settings = AppSettings::default();
+        assert_eq!(DownloadSchedule::from_settings(&settings), None);
+
+        settings.schedule_enabled = true;
+        assert_eq!(
+            DownloadSchedule::from_settings(&settings),
+            Some(schedule(&settings.schedule_start, &settings.schedule_end))
+        );
+
+        settings.schedule_end = "late".to_string();
+        assert_eq!(DownloadSchedule::from_settings(&settings), None);
+    }
+}
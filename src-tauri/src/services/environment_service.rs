@@ -0,0 +1,353 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// environment_service.rs -- Environment report for bug reports
+// =============================================================
+//
+// `get_platform_info` says which OS the app runs on, not what is
+// installed. The environment report adds everything a bug report needs:
+//
+//   - The resolved path and detected version of Python, GAMDL, FFmpeg,
+//     mp4decrypt, N_m3u8DL-RE and MP4Box (the user's configured path, or
+//     the managed install, as `preflight_service` resolves them).
+//   - The app version, the app data directory and the free space on its
+//     drive.
+//
+// Each version comes from running the tool's own version command, cut off
+// after VERSION_TIMEOUT so a hung binary can't stall the report. Nothing is
+// redacted except the cookies: the report only says whether a cookies
+// file is configured, never its path or content.
+//
+// @see commands/system.rs -- get_environment_report
+// @see dependency_manager.rs -- version_flag()
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::settings::AppSettings;
+use crate::services::dependency_manager;
+use crate::utils::platform;
+
+/// How long a tool's version command may run before it is killed and
+/// reported as timed out.
+pub const VERSION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// One tool in the environment report.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolReport {
+    /// Tool identifier ("python", "gamdl", "ffmpeg", ...).
+    pub id: String,
+    /// Display name ("Python", "GAMDL", "FFmpeg", ...).
+    pub name: String,
+    /// The binary the app runs. GAMDL runs as a module of the Python
+    /// binary (`python -m gamdl`), so its path is Python's.
+    pub path: String,
+    /// Whether the binary exists at `path`.
+    pub installed: bool,
+    /// The detected version, when the version command produced one.
+    pub version: Option<String>,
+    /// Why no version was detected (spawn failure, timeout, not installed).
+    pub error: Option<String>,
+}
+
+/// Everything needed for a bug report about the app's environment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EnvironmentReport {
+    /// MeedyaDL version.
+    pub app_version: String,
+    /// `std::env::consts::OS`, e.g. "macos".
+    pub os: String,
+    /// `std::env::consts::ARCH`, e.g. "aarch64".
+    pub arch: String,
+    /// The app data directory (Python, tools, settings, logs).
+    pub app_data_dir: String,
+    /// Free bytes on the app data directory's drive, when it could be read.
+    pub free_space_bytes: Option<u64>,
+    /// Whether a cookies file is configured. Its path and content are
+    /// never included in the report.
+    pub cookies_configured: bool,
+    /// Python, GAMDL and the external tools, in that order.
+    pub tools: Vec<ToolReport>,
+}
+
+/// A tool to report on, with the output of its version command.
+#[derive(Debug, Clone)]
+pub struct ToolProbe {
+    /// Tool identifier (see `ToolReport::id`).
+    pub id: &'static str,
+    /// Display name (see `ToolReport::name`).
+    pub name: &'static str,
+    /// The binary the app runs.
+    pub path: PathBuf,
+    /// `None` when the binary doesn't exist (the command wasn't run),
+    /// otherwise the command's stdout followed by its stderr, or why it
+    /// couldn't be run.
+    pub output: Option<Result<String, String>>,
+}
+
+/// Extracts the version from a tool's version command output.
+///
+/// - GAMDL: the `Version:` line of `pip show gamdl`.
+/// - Python: `Python 3.12.8` -> `3.12.8`.
+/// - Everything else: the first non-empty line, as printed (e.g.
+///   "ffmpeg version 7.1 Copyright (c) 2000-2024 ..."), since the tools
+///   don't share a format.
+pub fn parse_version(tool_id: &str, output: &str) -> Option<String> {
+    let mut lines = output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    match tool_id {
+        "gamdl" => lines
+            .find_map(|line| line.strip_prefix("Version:"))
+            .map(|version| version.trim().to_string()),
+        "python" => lines
+            .next()
+            .map(|line| line.strip_prefix("Python ").unwrap_or(line).to_string()),
+        _ => lines.next().map(str::to_string),
+    }
+}
+
+/// Builds the report from the already collected probe outputs.
+///
+/// Kept free of I/O so the assembly can be tested with mocked tool
+/// outputs; `collect_report()` gathers the real ones.
+pub fn assemble_report(
+    app_version: &str,
+    app_data_dir: &Path,
+    free_space: Result<u64, String>,
+    cookies_path: Option<&str>,
+    probes: Vec<ToolProbe>,
+) -> EnvironmentReport {
+    let tools = probes
+        .into_iter()
+        .map(|probe| {
+            let (installed, version, error) = match probe.output {
+                None => (false, None, Some("Not installed".to_string())),
+                Some(Err(e)) => (true, None, Some(e)),
+                Some(Ok(output)) => match parse_version(probe.id, &output) {
+                    Some(version) => (true, Some(version), None),
+                    None => (true, None, Some("No version in output".to_string())),
+                },
+            };
+            ToolReport {
+                id: probe.id.to_string(),
+                name: probe.name.to_string(),
+                path: probe.path.to_string_lossy().to_string(),
+                installed,
+                version,
+                error,
+            }
+        })
+        .collect();
+
+    EnvironmentReport {
+        app_version: app_version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        app_data_dir: app_data_dir.to_string_lossy().to_string(),
+        free_space_bytes: free_space
+            .map_err(|e| log::warn!("Environment report: no free space for data dir: {}", e))
+            .ok(),
+        cookies_configured: cookies_path.is_some_and(|p| !p.trim().is_empty()),
+        tools,
+    }
+}
+
+/// Collects the environment report: resolves every tool, runs the version
+/// commands concurrently and reads the free disk space.
+pub async fn collect_report(app: &AppHandle, settings: &AppSettings) -> EnvironmentReport {
+    let python = platform::get_python_binary_path(&platform::get_python_dir(app));
+
+    // (id, name, binary, version command arguments)
+    let mut commands: Vec<(&'static str, &'static str, PathBuf, Vec<&'static str>)> = vec![
+        ("python", "Python", python.clone(), vec!["--version"]),
+        ("gamdl", "GAMDL", python, vec!["-m", "pip", "show", "gamdl"]),
+    ];
+    for tool in dependency_manager::get_all_tools() {
+        let configured = match tool.id {
+            "ffmpeg" => settings.ffmpeg_path.as_deref(),
+            "mp4decrypt" => settings.mp4decrypt_path.as_deref(),
+            "nm3u8dlre" => settings.nm3u8dlre_path.as_deref(),
+            "mp4box" => settings.mp4box_path.as_deref(),
+            _ => None,
+        };
+        let path = match configured.filter(|p| !p.trim().is_empty()) {
+            Some(path) => PathBuf::from(path),
+            None => dependency_manager::get_tool_binary_path(app, tool.id),
+        };
+        commands.push((
+            tool.id,
+            tool.name,
+            path,
+            vec![dependency_manager::version_flag(tool.id)],
+        ));
+    }
+
+    let handles: Vec<_> = commands
+        .into_iter()
+        .map(|(id, name, path, args)| {
+            tokio::spawn(async move {
+                let output = if path.exists() {
+                    Some(run_version_command(&path, &args).await)
+                } else {
+                    None
+                };
+                ToolProbe {
+                    id,
+                    name,
+                    path,
+                    output,
+                }
+            })
+        })
+        .collect();
+    let mut probes = Vec::with_capacity(handles.len());
+    for handle in handles {
+        match handle.await {
+            Ok(probe) => probes.push(probe),
+            Err(e) => log::warn!("Environment report: version probe task failed: {}", e),
+        }
+    }
+
+    let app_data_dir = platform::get_app_data_dir(app);
+    let free_space = platform::available_space_bytes(&app_data_dir).await;
+    assemble_report(
+        &app.package_info().version.to_string(),
+        &app_data_dir,
+        free_space,
+        settings.cookies_path.as_deref(),
+        probes,
+    )
+}
+
+/// Runs one version command with VERSION_TIMEOUT and returns its stdout
+/// followed by its stderr (FFmpeg and some Python builds print the version
+/// to stderr).
+async fn run_version_command(path: &Path, args: &[&str]) -> Result<String, String> {
+    // kill_on_drop: a command that times out is killed when the future drops.
+    let command = Command::new(path).args(args).kill_on_drop(true).output();
+    let output = tokio::time::timeout(VERSION_TIMEOUT, command)
+        .await
+        .map_err(|_| format!("Timed out after {}s", VERSION_TIMEOUT.as_secs()))?
+        .map_err(|e| format!("Failed to run {}: {}", path.display(), e))?;
+    Ok(format!(
+        "{}\n{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    ))
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a probe with the given version command output.
+    fn probe(
+        id: &'static str,
+        name: &'static str,
+        output: Option<Result<&str, &str>>,
+    ) -> ToolProbe {
+        ToolProbe {
+            id,
+            name,
+            path: PathBuf::from(format!("/data/tools/{}", id)),
+            output: output.map(|o| o.map(str::to_string).map_err(str::to_string)),
+        }
+    }
+
+    /// Verifies that each tool's version is read from its own output
+    /// format, including versions printed to stderr.
+    #[test]
+    fn parses_versions_per_tool() {
+        assert_eq!(
+            parse_version("python", "Python 3.12.8\n\n"),
+            Some("3.12.8".into())
+        );
+        assert_eq!(
+            parse_version("gamdl", "Name: gamdl\nVersion: 2.8.4\nSummary: ...\n"),
+            Some("2.8.4".into())
+        );
+        // FFmpeg -version output arrives after an empty stdout
+        assert_eq!(
+            parse_version(
+                "ffmpeg",
+                "\nffmpeg version 7.1 Copyright (c) 2000-2024\nbuilt with clang"
+            ),
+            Some("ffmpeg version 7.1 Copyright (c) 2000-2024".into())
+        );
+        assert_eq!(
+            parse_version("gamdl", "WARNING: Package(s) not found: gamdl"),
+            None
+        );
+        assert_eq!(parse_version("mp4box", "\n\n"), None);
+    }
+
+    /// Verifies that the report is assembled from mocked tool outputs:
+    /// versions, missing tools, failed commands, free space, and a cookies
+    /// path that is reduced to "configured".
+    #[test]
+    fn assembles_report_from_tool_outputs() {
+        let report = assemble_report(
+            "1.4.0",
+            Path::new("/data"),
+            Ok(42_000_000_000),
+            Some("/Users/me/Secret/cookies.txt"),
+            vec![
+                probe("python", "Python", Some(Ok("Python 3.12.8\n"))),
+                probe("gamdl", "GAMDL", Some(Ok("Name: gamdl\nVersion: 2.8.4\n"))),
+                probe("ffmpeg", "FFmpeg", Some(Err("Timed out after 10s"))),
+                probe("mp4decrypt", "mp4decrypt", None),
+            ],
+        );
+
+        assert_eq!(report.app_version, "1.4.0");
+        assert_eq!(report.app_data_dir, "/data");
+        assert_eq!(report.free_space_bytes, Some(42_000_000_000));
+        assert!(report.cookies_configured);
+        let json = serde_json::to_string(&report).unwrap();
+        assert!(
+            !json.contains("cookies.txt"),
+            "Cookies path must be redacted"
+        );
+
+        let ids: Vec<&str> = report.tools.iter().map(|t| t.id.as_str()).collect();
+        assert_eq!(ids, vec!["python", "gamdl", "ffmpeg", "mp4decrypt"]);
+        assert_eq!(report.tools[0].version.as_deref(), Some("3.12.8"));
+        assert_eq!(report.tools[1].version.as_deref(), Some("2.8.4"));
+        assert_eq!(report.tools[1].path, "/data/tools/gamdl");
+
+        let ffmpeg = &report.tools[2];
+        assert!(ffmpeg.installed);
+        assert_eq!(ffmpeg.version, None);
+        assert_eq!(ffmpeg.error.as_deref(), Some("Timed out after 10s"));
+
+        let mp4decrypt = &report.tools[3];
+        assert!(!mp4decrypt.installed);
+        assert_eq!(mp4decrypt.error.as_deref(), Some("Not installed"));
+    }
+
+    /// Verifies that an unreadable free space and a missing cookies file
+    /// are reported as absent rather than failing the report.
+    #[test]
+    fn missing_free_space_and_cookies_are_absent() {
+        let report = assemble_report(
+            "1.4.0",
+            Path::new("/data"),
+            Err("no drive".into()),
+            Some("  "),
+            vec![],
+        );
+        assert_eq!(report.free_space_bytes, None);
+        assert!(!report.cookies_configured);
+        assert!(report.tools.is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// flac_conversion_service.rs -- Post-download ALAC to FLAC conversion
+// ====================================================================
+//
+// GAMDL saves lossless downloads as ALAC inside M4A containers, which many
+// non-Apple players can't handle. With `AppSettings::convert_lossless_to_flac`
+// on, every ALAC track of a finished download is transcoded to FLAC with the
+// bundled FFmpeg. Both codecs are lossless, so no quality is lost.
+//
+// ## What is carried over
+//
+//   - All tags (`-map_metadata 0`), including the custom `isLossless` tag
+//     written by metadata_tag_service.rs just before this runs.
+//   - The embedded cover, copied as-is and marked as the FLAC front cover
+//     picture (`-c:v copy -disposition:v attached_pic`).
+//
+// ## Gating
+//
+// Conversion only runs when the item's `codec_used` is ALAC. Because an
+// album folder can also hold AAC or Dolby Atmos files (companion downloads
+// without a suffix, earlier runs), each `.m4a` is additionally probed with
+// FFmpeg and skipped unless its audio stream really is ALAC.
+//
+// Only files written since the download's GAMDL run started are converted,
+// so tracks left in the folder by earlier downloads are not touched again.
+//
+// The original M4A is deleted after a successful conversion unless
+// `AppSettings::keep_m4a_after_flac` is on.
+//
+// @see download_queue.rs -- spawns convert_album() after the custom tags
+// @see https://ffmpeg.org/ffmpeg-formats.html#flac-1 -- FLAC muxer
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::gamdl_options::SongCodec;
+use crate::models::settings::AppSettings;
+use crate::services::{cover_resize_service, dependency_manager};
+use crate::utils::files;
+
+/// Returns whether a finished download should be converted to FLAC.
+///
+/// # Arguments
+/// * `settings` -- Current app settings (`convert_lossless_to_flac`).
+/// * `codec_used` -- The item's `QueueItemStatus::codec_used` (CLI string).
+///
+/// # Returns
+/// `true` only for ALAC downloads with the setting on; Atmos and all lossy
+/// codecs are never converted.
+pub fn should_convert(settings: &AppSettings, codec_used: Option<&str>) -> bool {
+    settings.convert_lossless_to_flac && codec_used == Some(SongCodec::Alac.to_cli_string())
+}
+
+/// Converts every ALAC `.m4a` under an album folder that was written at or
+/// after `since` to FLAC.
+///
+/// # Arguments
+/// * `app` -- Tauri app handle (to locate the managed FFmpeg).
+/// * `settings` -- Current app settings (custom FFmpeg path, keep flag).
+/// * `album_dir` -- Folder holding the downloaded tracks; searched
+///   recursively so multi-disc layouts are covered.
+/// * `since` -- When the download's GAMDL run started.
+///
+/// # Returns
+/// * `Ok(count)` -- Number of files converted (0 if none were ALAC).
+/// * `Err(message)` -- FFmpeg is missing, or a conversion failed. Files
+///   converted before the failure are kept.
+pub async fn convert_album(
+    app: &AppHandle,
+    settings: &AppSettings,
+    album_dir: &Path,
+    since: SystemTime,
+) -> Result<usize, String> {
+    let ffmpeg = resolve_ffmpeg(app, settings, "FLAC conversion")?;
+
+    let mut converted = 0;
+    for input in find_m4a_files(album_dir) {
+        if !cover_resize_service::modified_since(&input, since) {
+            continue;
+        }
+        if !probe_is_alac(&ffmpeg, &input).await {
+            log::debug!("Skipping non-ALAC file {}", input.display());
+            continue;
+        }
+        convert_file(&ffmpeg, &input, settings.keep_m4a_after_flac).await?;
+        converted += 1;
+    }
+    Ok(converted)
+}
+
+/// Builds the FFmpeg arguments for a lossless M4A (ALAC) to FLAC transcode.
+///
+/// Flags:
+///   -map 0:a:0                       -- the audio stream
+///   -map 0:v?                        -- the embedded cover, if there is one
+///   -c:a flac                        -- encode audio as FLAC (lossless)
+///   -c:v copy                        -- keep the cover image bytes as-is
+///   -disposition:v attached_pic      -- store the cover as a FLAC picture
+///   -map_metadata 0                  -- copy all tags from the input
+///   -y                               -- overwrite a stale output file
+///   -loglevel error                  -- only report failures
+pub fn ffmpeg_flac_args(input: &Path, output: &Path) -> Vec<String> {
+    vec![
+        "-hide_banner".to_string(),
+        "-loglevel".to_string(),
+        "error".to_string(),
+        "-y".to_string(),
+        "-i".to_string(),
+        input.to_string_lossy().to_string(),
+        "-map".to_string(),
+        "0:a:0".to_string(),
+        "-map".to_string(),
+        "0:v?".to_string(),
+        "-c:a".to_string(),
+        "flac".to_string(),
+        "-c:v".to_string(),
+        "copy".to_string(),
+        "-disposition:v".to_string(),
+        "attached_pic".to_string(),
+        "-map_metadata".to_string(),
+        "0".to_string(),
+        output.to_string_lossy().to_string(),
+    ]
+}
+
+/// Resolves the FFmpeg binary: the user's custom path if set, otherwise the
+/// managed install. `purpose` names the step that needs it in the error.
+pub fn resolve_ffmpeg(
+    app: &AppHandle,
+    settings: &AppSettings,
+    purpose: &str,
+) -> Result<PathBuf, String> {
+    let ffmpeg = match settings.ffmpeg_path.as_deref().filter(|p| !p.is_empty()) {
+        Some(custom) => PathBuf::from(custom),
+        None => dependency_manager::get_tool_binary_path(app, "ffmpeg"),
+    };
+    if !ffmpeg.exists() {
+        return Err(format!("FFmpeg not installed — required for {}", purpose));
+    }
+    Ok(ffmpeg)
+}
+
+/// Transcodes one file, removing a partial FLAC on failure and the source
+/// M4A on success (unless `keep_m4a`).
+async fn convert_file(ffmpeg: &Path, input: &Path, keep_m4a: bool) -> Result<(), String> {
+    let output_path = input.with_extension("flac");
+
+    // kill_on_drop: the queue aborts this task if the download is removed.
+    let output = Command::new(ffmpeg)
+        .args(ffmpeg_flac_args(input, &output_path))
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&output_path);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "FLAC conversion of {} failed: {}",
+            input.display(),
+            stderr.trim()
+        ));
+    }
+
+    if !keep_m4a {
+        if let Err(e) = std::fs::remove_file(input) {
+            log::warn!("Could not remove {} after conversion: {}", input.display(), e);
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the file's audio stream is ALAC.
+async fn probe_is_alac(ffmpeg: &Path, path: &Path) -> bool {
+    probe_audio_codec(ffmpeg, path).await.as_deref() == Some("alac")
+}
+
+/// Returns the FFmpeg name of the file's first audio codec (e.g. `"alac"`,
+/// `"aac"`, `"eac3"`), by reading the stream summary FFmpeg prints for
+/// `ffmpeg -i <file>`. `None` when the file can't be probed.
+pub async fn probe_audio_codec(ffmpeg: &Path, path: &Path) -> Option<String> {
+    // Without an output file FFmpeg exits with an error after printing the
+    // input's stream info, so only stderr matters here.
+    match Command::new(ffmpeg)
+        .arg("-hide_banner")
+        .arg("-i")
+        .arg(path)
+        .kill_on_drop(true)
+        .output()
+        .await
+    {
+        Ok(output) => audio_codec_from_stream_info(&String::from_utf8_lossy(&output.stderr))
+            .map(str::to_string),
+        Err(e) => {
+            log::debug!("Could not probe {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Parses FFmpeg's stream listing, e.g.
+/// `Stream #0:0[0x1](und): Audio: alac (alac / 0x63616C61), 44100 Hz, ...`,
+/// for the codec of the first audio stream.
+fn audio_codec_from_stream_info(stderr: &str) -> Option<&str> {
+    stderr
+        .lines()
+        .filter(|line| line.trim_start().starts_with("Stream #"))
+        .find_map(|line| line.split_once("Audio: "))
+        .and_then(|(_, rest)| rest.split([' ', ',']).next())
+        .filter(|codec| !codec.is_empty())
+}
+
+/// Recursively lists the `.m4a` files under `dir` (case-insensitive).
+pub fn find_m4a_files(dir: &Path) -> Vec<PathBuf> {
+    files::walk_files(dir, |path| files::has_extension(path, &["m4a"]))
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ----------------------------------------------------------
+    // should_convert()
+    // ----------------------------------------------------------
+
+    /// Verifies that only ALAC downloads are converted, and only with the
+    /// setting on.
+    #[test]
+    fn converts_only_alac_when_enabled() {
+        let mut settings = AppSettings::default();
+        assert!(!should_convert(&settings, Some("alac")), "Off by default");
+
+        settings.convert_lossless_to_flac = true;
+        assert!(should_convert(&settings, Some("alac")));
+        for codec in ["atmos", "aac", "aac-he", "aac-binaural", "ac3", "aac-legacy"] {
+            assert!(!should_convert(&settings, Some(codec)), "{} must not convert", codec);
+        }
+        assert!(!should_convert(&settings, None));
+    }
+
+    // ----------------------------------------------------------
+    // ffmpeg_flac_args()
+    // ----------------------------------------------------------
+
+    /// Returns the value following `flag` in `args`.
+    fn flag_value<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+        args.windows(2)
+            .filter(|w| w[0] == flag)
+            .map(|w| w[1].as_str())
+            .collect()
+    }
+
+    /// Verifies that the command keeps tags and the cover, encodes FLAC and
+    /// writes to the given output.
+    #[test]
+    fn ffmpeg_args_preserve_metadata_and_cover() {
+        let args = ffmpeg_flac_args(Path::new("/music/01 Song.m4a"), Path::new("/music/01 Song.flac"));
+
+        assert_eq!(flag_value(&args, "-i"), vec!["/music/01 Song.m4a"]);
+        assert_eq!(flag_value(&args, "-map"), vec!["0:a:0", "0:v?"]);
+        assert_eq!(flag_value(&args, "-c:a"), vec!["flac"]);
+        assert_eq!(flag_value(&args, "-c:v"), vec!["copy"]);
+        assert_eq!(flag_value(&args, "-disposition:v"), vec!["attached_pic"]);
+        assert_eq!(flag_value(&args, "-map_metadata"), vec!["0"]);
+        assert_eq!(args.last().map(String::as_str), Some("/music/01 Song.flac"));
+    }
+
+    // ----------------------------------------------------------
+    // audio_codec_from_stream_info()
+    // ----------------------------------------------------------
+
+    /// Verifies that the probe tells ALAC streams from AAC/Atmos.
+    #[test]
+    fn detects_alac_streams_only() {
+        let alac = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'a.m4a':\n  \
+                    Stream #0:0[0x1](und): Audio: alac (alac / 0x63616C61), 44100 Hz, stereo, s32p (24 bit)\n  \
+                    Stream #0:1[0x0]: Video: mjpeg (Baseline), yuvj420p, 600x600 (attached pic)";
+        let aac = "  Stream #0:0[0x1](und): Audio: aac (LC) (mp4a / 0x6134706D), 44100 Hz, stereo, fltp";
+        let atmos = "  Stream #0:0[0x1](und): Audio: eac3 (ec-3 / 0x332D6365), 48000 Hz, 5.1(side)";
+
+        assert_eq!(audio_codec_from_stream_info(alac), Some("alac"));
+        assert_eq!(audio_codec_from_stream_info(aac), Some("aac"));
+        assert_eq!(audio_codec_from_stream_info(atmos), Some("eac3"));
+        assert_eq!(
+            audio_codec_from_stream_info("Title : Audio: alac remix"),
+            None,
+            "Only stream lines count"
+        );
+    }
+}
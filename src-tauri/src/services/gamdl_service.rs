@@ -36,6 +36,36 @@
 // build commands and manages its own stdout/stderr reading with additional
 // queue-level progress tracking.
 //
+// ## Optional Tool Fallbacks
+//
+// `--remux-mode mp4box` and `--download-mode nm3u8dlre` need tools that
+// aren't always installed; GAMDL would only fail at the remux (or first
+// download) step, with a cryptic error. `build_gamdl_command_public()`
+// checks that the tool resolves to a runnable binary and otherwise falls
+// back to `ffmpeg` / `ytdlp`, emitting `remux-fallback` /
+// `download-mode-fallback` with a `ToolFallback` payload.
+//
+// ## Decryption Setup
+//
+// Apple Music streams are encrypted. GAMDL gets their keys from a Widevine
+// device -- the `.wvd` file of `--wvd-path`, else its built-in one -- or,
+// for the non-legacy song codecs, from the wrapper (`--use-wrapper`). The
+// legacy AAC codecs (GAMDL's default) and music videos always use the
+// device. A `.wvd` path that is missing or points at something else would
+// only surface as an opaque decryption error on the first track, so
+// `build_gamdl_command_public()` checks the setup the chosen codec needs
+// first (`check_drm_config()`) and fails with a `"drm"` error.
+//
+// ## Missing Cookies
+//
+// GAMDL needs the Apple Music session cookies for every API call. With no
+// cookies file set (and the wrapper off) it only fails once it reaches the
+// catalog, with an error that doesn't say what's missing.
+// `check_cookies_config()` catches this before the process is spawned and
+// fails with a `"no_cookies"` error asking the user to sign in. It is
+// skipped when GAMDL reads the user's own config.ini, which may set the
+// cookies path itself.
+//
 // ## References
 //
 // - GAMDL CLI usage: https://github.com/glomatico/gamdl
@@ -59,10 +89,11 @@ use tokio::process::Command;
 
 // GamdlOptions is the typed representation of GAMDL CLI arguments.
 // It provides `to_cli_args()` which converts the struct fields into a Vec<String> of CLI flags.
-use crate::models::gamdl_options::GamdlOptions;
+use crate::models::gamdl_options::{DownloadMode, GamdlOptions, RemuxMode, SongCodec};
 // dependency_manager provides paths to managed tool binaries (FFmpeg, mp4decrypt, etc.)
-use crate::services::dependency_manager;
+use crate::services::{config_service, dependency_manager};
 // `platform` provides cross-platform path resolution; `process` provides GAMDL output parsing.
+use crate::utils::proxy::ProxyConfig;
 use crate::utils::{platform, process};
 
 // ============================================================
@@ -192,6 +223,37 @@ pub async fn get_gamdl_version(app: &AppHandle) -> Result<Option<String>, String
     Ok(version)
 }
 
+/// Returns the output of `python -m gamdl --help`, which lists every CLI
+/// flag the installed GAMDL accepts (see
+/// `update_checker::check_gamdl_flags()`).
+///
+/// # Returns
+/// * `Ok(help)` - GAMDL's help text.
+/// * `Err(message)` - Python is missing or GAMDL could not be run.
+pub async fn get_gamdl_help(app: &AppHandle) -> Result<String, String> {
+    let python_dir = platform::get_python_dir(app);
+    let python_bin = platform::get_python_binary_path(&python_dir);
+    if !python_bin.exists() {
+        return Err("Python is not installed".to_string());
+    }
+
+    let output = Command::new(&python_bin)
+        .args(["-m", "gamdl", "--help"])
+        .output()
+        .await
+        .map_err(|e| format!("Failed to run gamdl --help: {}", e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "gamdl --help exited with code {}: {}",
+            output.status.code().unwrap_or(-1),
+            stderr.lines().last().unwrap_or("")
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
 /// Executes a GAMDL download as a subprocess and streams parsed events to the frontend.
 ///
 /// This is the core download execution function. It:
@@ -338,6 +400,10 @@ pub async fn run_gamdl(
 /// Constructs a `tokio::process::Command` that runs:
 /// `{python} -m gamdl {urls...} {--option value...}`
 ///
+/// Structurally broken URLs are rejected here (see `validate_urls()`) so
+/// the download fails with an `invalid_url` error instead of GAMDL's late
+/// and vaguer one.
+///
 /// # Arguments
 /// * `app` - The Tauri app handle (for path resolution)
 /// * `urls` - Apple Music URLs to download
@@ -347,7 +413,324 @@ pub fn build_gamdl_command_public(
     urls: &[String],
     options: &GamdlOptions,
 ) -> Result<Command, String> {
-    build_gamdl_command(app, urls, options)
+    // With `--read-urls-as-txt` the arguments are text files, not URLs
+    if options.read_urls_as_txt != Some(true) {
+        validate_urls(urls)?;
+    }
+    check_cookies_config(urls, options)?;
+    check_drm_config(urls, options)?;
+
+    // Fall back to the default tools when MP4Box / N_m3u8DL-RE are missing,
+    // rather than failing half way through the download
+    let mut options = options.clone();
+    let mp4box_ok = options.remux_mode != Some(RemuxMode::Mp4box)
+        || tool_available(app, options.mp4box_path.as_deref(), "mp4box", "MP4Box");
+    let nm3u8dlre_ok = options.download_mode != Some(DownloadMode::Nm3u8dlre)
+        || tool_available(app, options.nm3u8dlre_path.as_deref(), "nm3u8dlre", "N_m3u8DL-RE");
+    for fallback in apply_tool_fallbacks(&mut options, mp4box_ok, nm3u8dlre_ok) {
+        log::warn!(
+            "{} {} unavailable ({}), using {}",
+            fallback.flag,
+            fallback.requested,
+            fallback.reason,
+            fallback.substitute
+        );
+        let event = match fallback.flag {
+            "--remux-mode" => "remux-fallback",
+            _ => "download-mode-fallback",
+        };
+        let _ = app.emit(event, &fallback);
+    }
+
+    build_gamdl_command(app, urls, &options)
+}
+
+/// Checks that a download has Apple Music cookies to sign in with: a
+/// cookies file path, or the wrapper, which holds its own account.
+///
+/// A command without URLs (e.g. built only to list its flags) isn't
+/// checked, and neither is one that lets GAMDL read its own config file
+/// (`use_user_config == Some(true)`, see AppSettings::use_gamdl_config_file),
+/// where the cookies path usually comes from `config.ini`.
+///
+/// # Returns
+/// * `Ok(())` -- A cookies path is set, the wrapper is on, GAMDL reads its
+///   own config file, or there is nothing to download.
+/// * `Err(message)` -- Starting with `process::NO_COOKIES_ERROR` and
+///   saying how to sign in, classified as `no_cookies` by `DownloadError`.
+pub fn check_cookies_config(urls: &[String], options: &GamdlOptions) -> Result<(), String> {
+    if urls.is_empty() || options.use_wrapper == Some(true) || options.use_user_config == Some(true)
+    {
+        return Ok(());
+    }
+    let has_cookies = options
+        .cookies_path
+        .as_deref()
+        .is_some_and(|path| !path.trim().is_empty());
+    if has_cookies {
+        return Ok(());
+    }
+    Err(format!(
+        "{}. Sign in via Settings > Cookies, or enable the wrapper in Settings > Advanced.",
+        process::NO_COOKIES_ERROR
+    ))
+}
+
+/// First bytes of every pywidevine device file (`.wvd`).
+const WVD_MAGIC: &[u8; 3] = b"WVD";
+
+/// Checks that a download which needs decryption has a setup that can
+/// provide the keys: for tracks the wrapper decrypts, a wrapper account
+/// URL; for tracks the Widevine device decrypts, either no `wvd_path`
+/// (GAMDL's built-in device) or one pointing at a readable device file.
+///
+/// # Returns
+/// * `Ok(())` -- Nothing to decrypt, or the setup looks usable.
+/// * `Err(message)` -- Starting with `process::DRM_CONFIG_ERROR` and saying
+///   what to fix, classified as `drm` by `DownloadError`.
+pub fn check_drm_config(urls: &[String], options: &GamdlOptions) -> Result<(), String> {
+    if needs_wrapper_keys(urls, options)
+        && options
+            .wrapper_account_url
+            .as_deref()
+            .is_some_and(|url| url.trim().is_empty())
+    {
+        return Err(format!(
+            "{}: the wrapper is enabled but its account URL is empty. Set the wrapper \
+             URL in Settings > Advanced, or turn the wrapper off.",
+            process::DRM_CONFIG_ERROR
+        ));
+    }
+    if !needs_device_keys(urls, options) {
+        return Ok(());
+    }
+    let Some(path) = options
+        .wvd_path
+        .as_deref()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    else {
+        return Ok(());
+    };
+    match wvd_problem(std::path::Path::new(path)) {
+        Some(problem) => Err(format!(
+            "{}: the Widevine device file \"{}\" {}. Choose a valid .wvd file, clear the \
+             path to use GAMDL's built-in device, or enable the wrapper in Settings > Advanced.",
+            process::DRM_CONFIG_ERROR,
+            path,
+            problem
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Returns whether GAMDL decrypts songs with the legacy AAC codecs, which
+/// it does when no codec is chosen.
+fn uses_legacy_codec(options: &GamdlOptions) -> bool {
+    matches!(
+        options.song_codec,
+        None | Some(SongCodec::AacLegacy) | Some(SongCodec::AacHeLegacy)
+    )
+}
+
+/// Returns whether any of a download's songs get their keys from the
+/// wrapper: it is on and a non-legacy codec is chosen. Uploaded videos
+/// (`/post/` links) aren't encrypted; URL list files
+/// (`--read-urls-as-txt`) are assumed to hold songs.
+fn needs_wrapper_keys(urls: &[String], options: &GamdlOptions) -> bool {
+    options.use_wrapper == Some(true)
+        && !uses_legacy_codec(options)
+        && (options.read_urls_as_txt == Some(true)
+            || urls
+                .iter()
+                .any(|u| !u.contains("/post/") && !u.contains("/music-video/")))
+}
+
+/// Returns whether any of a download's tracks get their keys from the
+/// Widevine device: music videos always do, and songs do unless the
+/// wrapper decrypts them. URL list files are assumed to need it.
+fn needs_device_keys(urls: &[String], options: &GamdlOptions) -> bool {
+    let songs_use_device = options.use_wrapper != Some(true) || uses_legacy_codec(options);
+    options.read_urls_as_txt == Some(true)
+        || urls
+            .iter()
+            .filter(|u| !u.contains("/post/"))
+            .any(|u| songs_use_device || u.contains("/music-video/"))
+}
+
+/// Returns what's wrong with a Widevine device file, if anything.
+fn wvd_problem(path: &std::path::Path) -> Option<&'static str> {
+    use std::io::Read;
+
+    let mut file = match std::fs::File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Some("doesn't exist"),
+        Err(_) => return Some("can't be read"),
+    };
+    let mut header = [0u8; 3];
+    match file.read_exact(&mut header) {
+        Ok(()) if &header == WVD_MAGIC => None,
+        _ => Some("isn't a Widevine device file"),
+    }
+}
+
+/// A GAMDL option switched to its default tool because the chosen one
+/// isn't installed. Payload of the `remux-fallback` and
+/// `download-mode-fallback` events.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ToolFallback {
+    /// The GAMDL flag that changed (`--remux-mode` or `--download-mode`)
+    pub flag: &'static str,
+    /// The value the user chose (e.g., "mp4box")
+    pub requested: &'static str,
+    /// The value used instead (e.g., "ffmpeg")
+    pub substitute: &'static str,
+    /// Why the requested tool can't be used
+    pub reason: String,
+}
+
+/// Switches `--remux-mode mp4box` to `ffmpeg` when MP4Box isn't available,
+/// and `--download-mode nm3u8dlre` to `ytdlp` when N_m3u8DL-RE isn't.
+///
+/// # Returns
+/// The substitutions made (empty when the chosen tools are available or
+/// weren't chosen).
+pub fn apply_tool_fallbacks(
+    options: &mut GamdlOptions,
+    mp4box_available: bool,
+    nm3u8dlre_available: bool,
+) -> Vec<ToolFallback> {
+    let mut fallbacks = Vec::new();
+    if options.remux_mode == Some(RemuxMode::Mp4box) && !mp4box_available {
+        options.remux_mode = Some(RemuxMode::Ffmpeg);
+        fallbacks.push(ToolFallback {
+            flag: "--remux-mode",
+            requested: "mp4box",
+            substitute: "ffmpeg",
+            reason: "MP4Box is not installed or not runnable".to_string(),
+        });
+    }
+    if options.download_mode == Some(DownloadMode::Nm3u8dlre) && !nm3u8dlre_available {
+        options.download_mode = Some(DownloadMode::Ytdlp);
+        fallbacks.push(ToolFallback {
+            flag: "--download-mode",
+            requested: "nm3u8dlre",
+            substitute: "ytdlp",
+            reason: "N_m3u8DL-RE is not installed or not runnable".to_string(),
+        });
+    }
+    fallbacks
+}
+
+/// Returns whether GAMDL will find a runnable binary for an optional tool,
+/// resolved the way GAMDL resolves it: the custom path if set, else the
+/// managed install (which inject_tool_paths() passes), else `command` on
+/// the `PATH`.
+pub fn tool_available(app: &AppHandle, custom: Option<&str>, tool_id: &str, command: &str) -> bool {
+    if let Some(custom) = custom.filter(|p| !p.is_empty()) {
+        return is_runnable(std::path::Path::new(custom));
+    }
+    if is_runnable(&dependency_manager::get_tool_binary_path(app, tool_id)) {
+        return true;
+    }
+    let exe = format!("{}{}", command, std::env::consts::EXE_SUFFIX);
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| is_runnable(&dir.join(&exe))))
+}
+
+/// Returns whether `path` is a file the OS can execute (on Unix: has an
+/// execute permission bit).
+pub fn is_runnable(path: &std::path::Path) -> bool {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return false;
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        metadata.is_file() && metadata.permissions().mode() & 0o111 != 0
+    }
+    #[cfg(not(unix))]
+    {
+        metadata.is_file()
+    }
+}
+
+/// Hosts serving Apple Music catalog pages that GAMDL accepts.
+const APPLE_MUSIC_HOSTS: [&str; 3] = [
+    "music.apple.com",
+    "beta.music.apple.com",
+    "classical.music.apple.com",
+];
+
+/// Rejects Apple Music URLs that can't point at anything: not a URL, the
+/// wrong host, or no catalog ID where GAMDL expects one.
+///
+/// This only checks the URL's shape, e.g.
+/// `https://music.apple.com/{storefront}/album/{slug}/{id}`; whether the
+/// content exists is left to GAMDL. Links to other music services fail on
+/// the host check (their service is identified separately by
+/// `MusicServiceId::from_url()`).
+///
+/// # Returns
+/// * `Ok(())` -- Every URL is well-formed.
+/// * `Err(message)` -- `Invalid Apple Music URL "...": <reason>` for the
+///   first broken URL, classified as `invalid_url` by `DownloadError`.
+pub fn validate_urls(urls: &[String]) -> Result<(), String> {
+    for url in urls {
+        if let Some(problem) = url_problem(url.trim()) {
+            return Err(format!("Invalid Apple Music URL \"{}\": {}", url.trim(), problem));
+        }
+    }
+    Ok(())
+}
+
+/// Returns what's structurally wrong with an Apple Music URL, if anything.
+fn url_problem(url: &str) -> Option<&'static str> {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return Some("not a valid link");
+    };
+    if !matches!(parsed.scheme(), "http" | "https") {
+        return Some("not a web link");
+    }
+    let host = parsed.host_str().unwrap_or_default().to_lowercase();
+    if !APPLE_MUSIC_HOSTS.contains(&host.as_str()) {
+        return Some("not a music.apple.com link");
+    }
+
+    // Path: [storefront/]kind/[slug/]id, e.g. us/album/1989/1440935467
+    let mut segments: Vec<&str> = parsed
+        .path_segments()
+        .map(|s| s.filter(|seg| !seg.is_empty()).collect())
+        .unwrap_or_default();
+    if segments
+        .first()
+        .is_some_and(|seg| seg.len() == 2 && seg.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        segments.remove(0);
+    }
+
+    let is_numeric = |id: &str| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit());
+    let id = segments.get(1..).and_then(|rest| rest.last()).copied();
+    let well_formed = match segments.first().copied() {
+        None => return Some("no album, song, playlist, music video or artist in the link"),
+        Some("album" | "song" | "music-video" | "artist" | "post") => id.is_some_and(is_numeric),
+        Some("playlist") => id.is_some_and(|id| id.len() > 3 && id.starts_with("pl.")),
+        // library/{playlist|albums}/{id}
+        Some("library") => segments.len() >= 3,
+        Some(_) => return Some("not an album, song, playlist, music video or artist link"),
+    };
+    if !well_formed {
+        return Some("no catalog ID (the link may be cut off)");
+    }
+
+    // Songs linked through their album: album/{slug}/{id}?i={song id}
+    let bad_song_id = parsed
+        .query_pairs()
+        .any(|(key, value)| key == "i" && !is_numeric(&value));
+    if bad_song_id {
+        return Some("the song ID (?i=) isn't a number");
+    }
+    None
 }
 
 /// Builds the complete GAMDL command with all arguments.
@@ -376,46 +759,81 @@ fn build_gamdl_command(
         return Err("Python is not installed. Run the setup wizard first.".to_string());
     }
 
-    // Start building the command: `python -m gamdl`
-    // The `-m gamdl` flag runs GAMDL as a Python module, equivalent to running
-    // the `gamdl` command-line entry point but ensuring we use our managed Python.
-    // Ref: https://github.com/glomatico/gamdl#usage
-    let mut cmd = Command::new(&python_bin);
-    cmd.args(["-m", "gamdl"]);
-
-    // Add the Apple Music URLs as positional arguments.
-    // GAMDL accepts one or more URLs (albums, playlists, songs, music videos).
-    // Example: python -m gamdl https://music.apple.com/us/album/... https://...
-    for url in urls {
-        cmd.arg(url);
-    }
-
-    // Convert the typed GamdlOptions struct into CLI argument strings.
-    // GamdlOptions::to_cli_args() maps each field to its corresponding GAMDL
-    // CLI flag (e.g., song_codec: Some(Alac) -> ["--song-codec", "alac"]).
-    // See models/gamdl_options.rs for the mapping implementation.
-    let cli_args = options.to_cli_args();
-    cmd.args(&cli_args);
+    // Start building the command: `python -m gamdl {urls...} {options...}`,
+    // routed through the user's proxy if one is configured.
+    let proxy = config_service::load_proxy_config(app);
+    let mut cmd = gamdl_base_command(&python_bin, urls, options, &proxy);
 
     // Inject managed tool paths (FFmpeg, mp4decrypt, etc.) if the user hasn't
     // specified custom paths. This auto-detection allows the app to work
     // out-of-the-box with the tools installed by dependency_manager.rs.
     inject_tool_paths(app, &mut cmd, options);
 
-    // Always pass our managed GAMDL config path (config.ini) to keep
-    // configuration self-contained within the app data directory.
-    // This config.ini is synced from GUI settings by config_service::sync_to_gamdl_config().
+    // Pass our managed GAMDL config path (config.ini) to keep configuration
+    // self-contained within the app data directory. This config.ini is
+    // synced from GUI settings by config_service::sync_to_gamdl_config().
+    // Skipped when the user's own GAMDL config file is respected
+    // (`use_user_config == Some(true)`, see AppSettings::use_gamdl_config_file),
+    // so GAMDL falls back to its default config path.
     let config_path = platform::get_gamdl_config_path(app);
-    if config_path.exists() {
+    if options.use_user_config != Some(true) && config_path.exists() {
         cmd.arg("--config-path");
         cmd.arg(config_path);
     }
 
-    log::debug!("GAMDL command: python -m gamdl {:?} {:?}", urls, cli_args);
+    // Run at the user's chosen priority; GAMDL's FFmpeg and mp4decrypt
+    // children inherit it.
+    if let Some(niceness) = options.process_niceness {
+        platform::apply_niceness(&mut cmd, niceness);
+    }
+
+    log::debug!("GAMDL command: python -m gamdl {:?} {:?}", urls, options.to_cli_args());
 
     Ok(cmd)
 }
 
+/// Creates the `python -m gamdl {urls...} {options...}` command, with the
+/// proxy environment variables set.
+///
+/// Ref: https://github.com/glomatico/gamdl#usage
+fn gamdl_base_command(
+    python_bin: &std::path::Path,
+    urls: &[String],
+    options: &GamdlOptions,
+    proxy: &ProxyConfig,
+) -> Command {
+    let mut cmd = Command::new(python_bin);
+    cmd.args(gamdl_args(urls, options));
+
+    // Route GAMDL's (and yt-dlp's) network traffic through the user's proxy.
+    // Python's HTTP stack reads these env vars itself.
+    proxy.apply_to_command(&mut cmd);
+    cmd
+}
+
+/// Builds the Python arguments for a GAMDL run: `-m gamdl {urls...}
+/// {--option value...}`. Tool paths and the config path are appended
+/// separately by build_gamdl_command(), since they depend on what is
+/// installed.
+pub fn gamdl_args(urls: &[String], options: &GamdlOptions) -> Vec<String> {
+    // The `-m gamdl` flag runs GAMDL as a Python module, equivalent to running
+    // the `gamdl` command-line entry point but ensuring we use our managed Python.
+    let mut args = vec!["-m".to_string(), "gamdl".to_string()];
+
+    // Add the Apple Music URLs as positional arguments.
+    // GAMDL accepts one or more URLs (albums, playlists, songs, music videos),
+    // or with `--read-urls-as-txt`, paths to text files listing them.
+    // Example: python -m gamdl https://music.apple.com/us/album/... https://...
+    args.extend(urls.iter().cloned());
+
+    // Convert the typed GamdlOptions struct into CLI argument strings.
+    // GamdlOptions::to_cli_args() maps each field to its corresponding GAMDL
+    // CLI flag (e.g., song_codec: Some(Alac) -> ["--song-codec", "alac"]).
+    // See models/gamdl_options.rs for the mapping implementation.
+    args.extend(options.to_cli_args());
+    args
+}
+
 /// Injects paths to managed tool installations into the GAMDL command.
 ///
 /// For each tool (FFmpeg, mp4decrypt, etc.), if the user hasn't specified
@@ -484,17 +902,23 @@ fn inject_tool_paths(app: &AppHandle, cmd: &mut Command, options: &GamdlOptions)
 /// Queries the PyPI JSON API to determine the latest published version,
 /// which is used for update notifications.
 ///
+/// # Arguments
+/// * `proxy` - Proxy settings for the request (see `utils::proxy`)
+///
 /// # Returns
 /// * `Ok(version)` - The latest version on PyPI (e.g., "2.8.4")
 /// * `Err(message)` - If the PyPI API request failed
-pub async fn check_latest_gamdl_version() -> Result<String, String> {
+pub async fn check_latest_gamdl_version(proxy: &ProxyConfig) -> Result<String, String> {
     // Query the PyPI JSON API for the GAMDL package.
     // The PyPI JSON API returns package metadata including the latest version.
     // API format: https://pypi.org/pypi/{package}/json
     // Response structure: { "info": { "version": "2.8.4", ... }, "releases": { ... } }
     // Ref: https://warehouse.pypa.io/api-reference/json.html
     let url = "https://pypi.org/pypi/gamdl/json";
-    let response = reqwest::get(url)
+    let response = proxy
+        .build_client()?
+        .get(url)
+        .send()
         .await
         .map_err(|e| format!("Failed to check PyPI: {}", e))?;
 
@@ -517,3 +941,284 @@ pub async fn check_latest_gamdl_version() -> Result<String, String> {
         .map(|s| s.to_string())
         .ok_or_else(|| "Could not find version in PyPI response".to_string())
 }
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    /// Verifies that a missing or blank cookies path fails with a
+    /// `no_cookies` error, while a set path or the wrapper passes.
+    #[test]
+    fn cookies_config_requires_path_or_wrapper() {
+        let urls = vec!["https://music.apple.com/us/album/style/1440935467".to_string()];
+        let mut options = GamdlOptions::default();
+
+        for path in [None, Some(""), Some("  ")] {
+            options.cookies_path = path.map(str::to_string);
+            let error = check_cookies_config(&urls, &options).unwrap_err();
+            assert!(error.starts_with(process::NO_COOKIES_ERROR), "{}", error);
+            assert!(error.contains("Settings > Cookies"), "{}", error);
+            assert_eq!(process::classify_error(&error), "no_cookies");
+        }
+
+        options.cookies_path = Some("/home/me/cookies.txt".to_string());
+        assert_eq!(check_cookies_config(&urls, &options), Ok(()));
+
+        // The wrapper signs in with its own account
+        options.cookies_path = None;
+        options.use_wrapper = Some(true);
+        assert_eq!(check_cookies_config(&urls, &options), Ok(()));
+
+        // Nothing to download, e.g. the flag check's command
+        options.use_wrapper = None;
+        assert_eq!(check_cookies_config(&[], &options), Ok(()));
+    }
+
+    /// Verifies that a download using the user's own GAMDL config file
+    /// isn't rejected for lacking a cookies path, which config.ini may set.
+    #[test]
+    fn cookies_config_skipped_with_own_config_file() {
+        let urls = vec!["https://music.apple.com/us/album/style/1440935467".to_string()];
+        let mut options = GamdlOptions {
+            use_user_config: Some(true),
+            ..GamdlOptions::default()
+        };
+        assert_eq!(check_cookies_config(&urls, &options), Ok(()));
+
+        // `--no-config-file` doesn't read the user's file either
+        options.use_user_config = None;
+        options.no_config_file = Some(true);
+        assert!(check_cookies_config(&urls, &options).is_err());
+    }
+
+    /// Verifies the decryption setup check: no `.wvd` path or a valid
+    /// device file passes, a missing or foreign file fails with a `drm`
+    /// error, the wrapper needs its URL only for the codecs it decrypts,
+    /// and uploaded videos need nothing.
+    #[test]
+    fn drm_config_requires_valid_device_or_wrapper() {
+        let dir = TestDir::new("wvd");
+        let device = dir.join("device.wvd");
+        std::fs::write(&device, b"WVD\x02\x02\x03device").unwrap();
+        let foreign = dir.join("cookies.txt");
+        std::fs::write(&foreign, "# Netscape HTTP Cookie File").unwrap();
+
+        let urls = vec!["https://music.apple.com/us/album/style/1440935467".to_string()];
+        let mut options = GamdlOptions::default();
+        assert_eq!(check_drm_config(&urls, &options), Ok(()));
+        options.wvd_path = Some(device.to_string_lossy().to_string());
+        assert_eq!(check_drm_config(&urls, &options), Ok(()));
+
+        for (path, problem) in [
+            (dir.join("missing.wvd"), "doesn't exist"),
+            (foreign.clone(), "isn't a Widevine device file"),
+        ] {
+            options.wvd_path = Some(path.to_string_lossy().to_string());
+            let error = check_drm_config(&urls, &options).unwrap_err();
+            assert!(error.starts_with(process::DRM_CONFIG_ERROR), "{}", error);
+            assert!(error.contains(problem), "{}", error);
+            assert_eq!(process::classify_error(&error), "drm");
+        }
+
+        // Uploaded videos aren't encrypted
+        let post = vec!["https://music.apple.com/us/post/1234567890".to_string()];
+        assert_eq!(check_drm_config(&post, &options), Ok(()));
+
+        // The wrapper replaces the device for non-legacy codecs, but needs
+        // its URL
+        options.use_wrapper = Some(true);
+        options.song_codec = Some(SongCodec::Alac);
+        assert_eq!(check_drm_config(&urls, &options), Ok(()));
+        options.wrapper_account_url = Some(" ".to_string());
+        let error = check_drm_config(&urls, &options).unwrap_err();
+        assert_eq!(process::classify_error(&error), "drm");
+
+        // Legacy AAC and music videos still use the device, not the wrapper
+        options.wrapper_account_url = None;
+        let video = vec!["https://music.apple.com/us/music-video/v/1234567890".to_string()];
+        assert!(check_drm_config(&video, &options).is_err());
+        options.song_codec = Some(SongCodec::AacLegacy);
+        assert!(check_drm_config(&urls, &options).is_err());
+        options.wrapper_account_url = Some(" ".to_string());
+        options.wvd_path = None;
+        assert_eq!(
+            check_drm_config(&urls, &options),
+            Ok(()),
+            "An empty wrapper URL doesn't block legacy AAC"
+        );
+    }
+
+    /// Verifies that album, song, playlist, music video and artist links
+    /// pass, with or without storefront and slug.
+    #[test]
+    fn accepts_well_formed_apple_music_urls() {
+        let urls: Vec<String> = [
+            "https://music.apple.com/us/album/1989-taylors-version/1708308989",
+            "https://music.apple.com/us/album/style/1440935467?i=1440935808",
+            "https://music.apple.com/album/1440935467",
+            "https://music.apple.com/gb/song/style/1440935808",
+            "https://music.apple.com/us/playlist/todays-hits/pl.f4d106fed2bd41149aaacabb233eb5eb",
+            "https://music.apple.com/us/music-video/anti-hero/1650841512",
+            "https://music.apple.com/us/artist/taylor-swift/159260351",
+            "  https://beta.music.apple.com/us/library/playlist/p.V7VYlrDCLd4NX3  ",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        assert_eq!(validate_urls(&urls), Ok(()));
+    }
+
+    /// Verifies that an album URL cut off before (or inside) its ID is
+    /// rejected.
+    #[test]
+    fn rejects_truncated_album_url() {
+        for url in [
+            "https://music.apple.com/us/album/1989-taylors-version/",
+            "https://music.apple.com/us/album/1989-taylors-ver",
+            "https://music.apple.com/us/album/style/1440935467?i=",
+        ] {
+            let error = validate_urls(&[url.to_string()]).unwrap_err();
+            assert!(error.starts_with("Invalid Apple Music URL"), "{}", error);
+            assert_eq!(process::classify_error(&error), "invalid_url", "{}", url);
+        }
+    }
+
+    /// Verifies that links without any catalog ID are rejected.
+    #[test]
+    fn rejects_url_without_id() {
+        for url in [
+            "https://music.apple.com/us",
+            "https://music.apple.com/us/album",
+            "https://music.apple.com/us/playlist/todays-hits",
+            "https://music.apple.com/us/browse",
+        ] {
+            assert!(validate_urls(&[url.to_string()]).is_err(), "{}", url);
+        }
+    }
+
+    /// Verifies that other hosts and non-URLs are rejected, naming the
+    /// offending URL.
+    #[test]
+    fn rejects_wrong_host() {
+        for url in [
+            "https://music.apple.co/us/album/1989/1440935467",
+            "https://open.spotify.com/album/1o59UpKw81iHR0HPiSkJR0",
+            "music.apple.com/us/album/1989/1440935467",
+            "ftp://music.apple.com/us/album/1989/1440935467",
+        ] {
+            let error = validate_urls(&[url.to_string()]).unwrap_err();
+            assert!(error.contains(url), "{}", error);
+        }
+    }
+
+    // ----------------------------------------------------------
+    // apply_tool_fallbacks() / is_runnable()
+    // ----------------------------------------------------------
+
+    /// Verifies that a missing MP4Box switches the remux mode to FFmpeg,
+    /// and a missing N_m3u8DL-RE the download mode to yt-dlp.
+    #[test]
+    fn missing_optional_tools_fall_back_to_defaults() {
+        let missing = std::path::Path::new("/nonexistent/meedyadl/MP4Box");
+        assert!(!is_runnable(missing));
+
+        let mut options = GamdlOptions {
+            remux_mode: Some(RemuxMode::Mp4box),
+            download_mode: Some(DownloadMode::Nm3u8dlre),
+            mp4box_path: Some(missing.to_string_lossy().to_string()),
+            ..GamdlOptions::default()
+        };
+        let fallbacks = apply_tool_fallbacks(&mut options, is_runnable(missing), false);
+
+        assert_eq!(options.remux_mode, Some(RemuxMode::Ffmpeg));
+        assert_eq!(options.download_mode, Some(DownloadMode::Ytdlp));
+        assert_eq!(fallbacks.len(), 2);
+        assert_eq!(fallbacks[0].substitute, "ffmpeg");
+        let args = options.to_cli_args();
+        let flag = args.iter().position(|a| a == "--remux-mode").unwrap();
+        assert_eq!(args[flag + 1], "ffmpeg");
+    }
+
+    /// Verifies that available tools, or modes that don't need them, are
+    /// left alone.
+    #[test]
+    fn available_optional_tools_are_kept() {
+        let mut options = GamdlOptions {
+            remux_mode: Some(RemuxMode::Mp4box),
+            download_mode: Some(DownloadMode::Nm3u8dlre),
+            ..GamdlOptions::default()
+        };
+        assert!(apply_tool_fallbacks(&mut options, true, true).is_empty());
+        assert_eq!(options.remux_mode, Some(RemuxMode::Mp4box));
+        assert_eq!(options.download_mode, Some(DownloadMode::Nm3u8dlre));
+
+        let mut defaults = GamdlOptions {
+            remux_mode: Some(RemuxMode::Ffmpeg),
+            ..GamdlOptions::default()
+        };
+        assert!(apply_tool_fallbacks(&mut defaults, false, false).is_empty());
+    }
+
+    /// Verifies that an existing executable counts as runnable.
+    #[cfg(unix)]
+    #[test]
+    fn executable_file_is_runnable() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = TestDir::new("mp4box");
+        let path = dir.join("MP4Box");
+        std::fs::write(&path, b"#!/bin/sh\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(!is_runnable(&path), "Not executable yet");
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(is_runnable(&path));
+    }
+
+    /// Verifies that the proxies set in the settings reach the GAMDL
+    /// command's environment, and that none are set without them.
+    #[test]
+    fn gamdl_command_uses_settings_proxy() {
+        use crate::models::settings::AppSettings;
+        use std::ffi::OsStr;
+
+        let urls = vec!["https://music.apple.com/us/album/style/1440935467".to_string()];
+        let python = std::path::Path::new("/opt/python/bin/python3");
+        let env_of = |cmd: &Command, key: &str| {
+            cmd.as_std()
+                .get_envs()
+                .find(|(k, _)| *k == OsStr::new(key))
+                .and_then(|(_, v)| v.map(|v| v.to_string_lossy().to_string()))
+        };
+
+        let mut settings = AppSettings::default();
+        settings.http_proxy = Some("http://proxy.corp:8080".to_string());
+        settings.https_proxy = Some("http://proxy.corp:8443".to_string());
+        let cmd = gamdl_base_command(
+            python,
+            &urls,
+            &GamdlOptions::default(),
+            &ProxyConfig::from_settings(&settings),
+        );
+        assert_eq!(
+            env_of(&cmd, "HTTP_PROXY").as_deref(),
+            Some("http://proxy.corp:8080")
+        );
+        assert_eq!(
+            env_of(&cmd, "HTTPS_PROXY").as_deref(),
+            Some("http://proxy.corp:8443")
+        );
+        assert!(cmd.as_std().get_args().any(|a| a == OsStr::new(&urls[0])));
+
+        let cmd = gamdl_base_command(
+            python,
+            &urls,
+            &GamdlOptions::default(),
+            &ProxyConfig::from_settings(&AppSettings::default()),
+        );
+        assert_eq!(cmd.as_std().get_envs().count(), 0);
+    }
+}
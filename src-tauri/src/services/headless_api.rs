@@ -0,0 +1,681 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// headless_api.rs -- Local HTTP control API
+// =========================================
+//
+// Lets scripts drive the download queue without the GUI. With
+// `AppSettings::headless_api_enabled` on, a small HTTP/1.1 server listens on
+// `127.0.0.1:<headless_api_port>` (never on other interfaces) and exposes:
+//
+//   GET  /downloads               -- every queue item (`QueueItemStatus[]`)
+//   POST /downloads               -- enqueue a `DownloadRequest` JSON body,
+//                                    201 `{ "download_id": "..." }`
+//   GET  /downloads/{id}          -- one queue item
+//   POST /downloads/{id}/cancel   -- same as the `cancel_download` command
+//   POST /downloads/{id}/retry    -- same as the `retry_download` command
+//
+// Every request needs `Authorization: Bearer <token>`. The token is
+// generated on first use and kept in the OS keychain under
+// `headless_api_token` (the Advanced settings tab copies it). Responses are
+// JSON; errors are `{ "error": "..." }` with a 4xx status.
+//
+// Routing (`handle()`) works on a locked `DownloadQueue` and is pure, so
+// it's unit tested without a running app. The server applies the side
+// effects it reports (persisting `queue.json`, emitting `download-queued` /
+// `download-cancelled`, starting the queue) exactly as the commands do.
+// An enqueued request is taken as-is: artist expansion, playlist sync and
+// pre-flight checks only run for downloads started from the GUI.
+//
+// The server starts with the app; turning the setting on or changing the
+// port takes effect after a restart.
+//
+// @see commands/gamdl.rs -- the Tauri commands these endpoints mirror
+// @see https://developer.mozilla.org/en-US/docs/Web/HTTP/Authentication#bearer
+
+use serde_json::json;
+use tauri::{AppHandle, Emitter};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::models::download::{DownloadRequest, QueueItemStatus};
+use crate::models::settings::AppSettings;
+use crate::services::download_queue::{self, DownloadQueue, QueueHandle};
+use crate::services::{config_service, gamdl_service, storefront_service};
+
+/// Keychain account holding the bearer token.
+pub const TOKEN_CREDENTIAL: &str = "headless_api_token";
+
+/// Largest request body accepted (a `DownloadRequest` is a few KB at most).
+const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Largest request line plus headers accepted. They are read before the
+/// token is checked, so an unauthenticated client can't grow them further.
+const MAX_HEADER_BYTES: u64 = 16 * 1024;
+
+/// Most header lines accepted in one request.
+const MAX_HEADERS: usize = 64;
+
+/// Longest a client may take to send its request before it's dropped.
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// A parsed HTTP request.
+#[derive(Debug, Clone, Default)]
+pub struct ApiRequest {
+    /// Request method, upper case (`"GET"`, `"POST"`).
+    pub method: String,
+    /// Request path without the query string (`"/downloads"`).
+    pub path: String,
+    /// Headers as (lower-case name, value) pairs.
+    pub headers: Vec<(String, String)>,
+    /// Request body (empty without `Content-Length`).
+    pub body: Vec<u8>,
+}
+
+impl ApiRequest {
+    /// Returns the value of the first header named `name` (case-insensitive).
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_ascii_lowercase();
+        self.headers
+            .iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Queue change a handled request made, applied by the server after the
+/// queue lock is released.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QueueEffect {
+    /// The item was enqueued or re-queued: save, emit `download-queued`
+    /// and start processing.
+    Queued(String),
+    /// The item was cancelled: save and emit `download-cancelled`.
+    Cancelled(String),
+}
+
+/// Response to an API request.
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    /// HTTP status code.
+    pub status: u16,
+    /// JSON body.
+    pub body: serde_json::Value,
+    /// Queue change to apply, if any.
+    pub effect: Option<QueueEffect>,
+}
+
+impl ApiResponse {
+    fn ok(status: u16, body: serde_json::Value) -> Self {
+        Self {
+            status,
+            body,
+            effect: None,
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::ok(status, json!({ "error": message.into() }))
+    }
+
+    fn with_effect(mut self, effect: QueueEffect) -> Self {
+        self.effect = Some(effect);
+        self
+    }
+}
+
+/// Returns whether the request carries `Authorization: Bearer <token>`.
+/// The comparison takes the same time wherever the tokens differ.
+pub fn is_authorized(request: &ApiRequest, token: &str) -> bool {
+    let Some(presented) = request
+        .header("authorization")
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    let (a, b) = (presented.trim().as_bytes(), token.as_bytes());
+    !token.is_empty()
+        && a.len() == b.len()
+        && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Authenticates and routes one request against the queue.
+///
+/// # Arguments
+/// * `request` - The parsed request.
+/// * `token` - The bearer token from the keychain.
+/// * `queue` - The locked download queue.
+/// * `settings` - Current settings, for enqueue and retry.
+pub fn handle(
+    request: &ApiRequest,
+    token: &str,
+    queue: &mut DownloadQueue,
+    settings: &AppSettings,
+) -> ApiResponse {
+    if !is_authorized(request, token) {
+        return ApiResponse::error(401, "Missing or invalid bearer token");
+    }
+
+    let segments: Vec<&str> = request
+        .path
+        .trim_matches('/')
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("GET", ["downloads"]) => ApiResponse::ok(200, json!(queue.get_status())),
+        ("POST", ["downloads"]) => enqueue(&request.body, queue, settings),
+        ("GET", ["downloads", id]) => match find_item(queue, id) {
+            Some(item) => ApiResponse::ok(200, json!(item)),
+            None => not_found(id),
+        },
+        ("POST", ["downloads", id, "cancel"]) => {
+            if find_item(queue, id).is_none() {
+                not_found(id)
+            } else if queue.cancel(id) {
+                ApiResponse::ok(200, json!({ "download_id": id }))
+                    .with_effect(QueueEffect::Cancelled(id.to_string()))
+            } else {
+                ApiResponse::error(409, format!("Download {} already finished", id))
+            }
+        }
+        ("POST", ["downloads", id, "retry"]) => {
+            if find_item(queue, id).is_none() {
+                not_found(id)
+            } else if queue.retry(id, settings) {
+                ApiResponse::ok(200, json!({ "download_id": id }))
+                    .with_effect(QueueEffect::Queued(id.to_string()))
+            } else {
+                ApiResponse::error(409, format!("Download {} cannot be retried", id))
+            }
+        }
+        (_, ["downloads"]) | (_, ["downloads", _]) | (_, ["downloads", _, "cancel" | "retry"]) => {
+            ApiResponse::error(405, "Method not allowed")
+        }
+        _ => ApiResponse::error(404, "Unknown endpoint"),
+    }
+}
+
+/// Handles `POST /downloads`: parses, validates and enqueues the request.
+fn enqueue(body: &[u8], queue: &mut DownloadQueue, settings: &AppSettings) -> ApiResponse {
+    let mut request: DownloadRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(e) => return ApiResponse::error(400, format!("Invalid DownloadRequest: {}", e)),
+    };
+    if request.urls.is_empty() {
+        return ApiResponse::error(400, "No URLs to download");
+    }
+    if let Some(options) = request.options.as_mut() {
+        if let Err(e) = storefront_service::normalize_override(options) {
+            return ApiResponse::error(400, e);
+        }
+    }
+    if let Err(e) = gamdl_service::validate_urls(&request.urls) {
+        return ApiResponse::error(400, e);
+    }
+
+    let download_id = queue.enqueue(request, settings);
+    ApiResponse::ok(201, json!({ "download_id": download_id }))
+        .with_effect(QueueEffect::Queued(download_id))
+}
+
+fn find_item(queue: &DownloadQueue, download_id: &str) -> Option<QueueItemStatus> {
+    queue
+        .get_status()
+        .into_iter()
+        .find(|item| item.id == download_id)
+}
+
+fn not_found(download_id: &str) -> ApiResponse {
+    ApiResponse::error(404, format!("Download {} not found", download_id))
+}
+
+/// Reads one HTTP/1.1 request (request line, headers, `Content-Length`
+/// body) from `reader`.
+///
+/// # Returns
+/// * `Ok(request)` -- The parsed request.
+/// * `Err(response)` -- The error to answer with: 431 when the request
+///   line and headers exceed `MAX_HEADER_BYTES` or `MAX_HEADERS`, 400 for
+///   a malformed request or a body over `MAX_BODY_BYTES`.
+pub async fn read_request<R: AsyncRead + Unpin>(reader: R) -> Result<ApiRequest, ApiResponse> {
+    let too_large = || ApiResponse::error(431, "Request headers too large");
+    let bad_request = |message: String| ApiResponse::error(400, message);
+
+    // The limit covers the request line and headers; the body is read
+    // from the inner reader once they're parsed
+    let mut reader = BufReader::new(reader).take(MAX_HEADER_BYTES);
+    let mut line = String::new();
+    reader
+        .read_line(&mut line)
+        .await
+        .map_err(|e| bad_request(format!("Failed to read request: {}", e)))?;
+    if !line.ends_with('\n') && reader.limit() == 0 {
+        return Err(too_large());
+    }
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        return Err(bad_request("Malformed request line".to_string()));
+    };
+    let mut request = ApiRequest {
+        method: method.to_ascii_uppercase(),
+        path: target.split('?').next().unwrap_or_default().to_string(),
+        ..Default::default()
+    };
+
+    loop {
+        line.clear();
+        let read = reader
+            .read_line(&mut line)
+            .await
+            .map_err(|e| bad_request(format!("Failed to read headers: {}", e)))?;
+        if !line.ends_with('\n') && reader.limit() == 0 {
+            return Err(too_large());
+        }
+        let header = line.trim_end();
+        if read == 0 || header.is_empty() {
+            break;
+        }
+        if request.headers.len() == MAX_HEADERS {
+            return Err(too_large());
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            request
+                .headers
+                .push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+
+    let length = match request.header("content-length") {
+        Some(value) => value
+            .parse::<usize>()
+            .map_err(|_| bad_request("Invalid Content-Length".to_string()))?,
+        None => 0,
+    };
+    if length > MAX_BODY_BYTES {
+        return Err(bad_request("Request body too large".to_string()));
+    }
+    request.body = vec![0; length];
+    reader
+        .into_inner()
+        .read_exact(&mut request.body)
+        .await
+        .map_err(|e| bad_request(format!("Failed to read body: {}", e)))?;
+    Ok(request)
+}
+
+/// Returns the bearer token, generating and storing one in the keychain on
+/// first use.
+pub fn ensure_token() -> Result<String, String> {
+    const SERVICE_NAME: &str = "io.github.meedyadl";
+
+    let entry = keyring::Entry::new(SERVICE_NAME, TOKEN_CREDENTIAL)
+        .map_err(|e| format!("Failed to create keyring entry: {}", e))?;
+    match entry.get_password() {
+        Ok(token) if !token.is_empty() => Ok(token),
+        Ok(_) | Err(keyring::Error::NoEntry) => {
+            // Two v4 UUIDs: 244 random bits, URL- and header-safe
+            let token = format!(
+                "{}{}",
+                uuid::Uuid::new_v4().simple(),
+                uuid::Uuid::new_v4().simple()
+            );
+            entry
+                .set_password(&token)
+                .map_err(|e| format!("Failed to store API token: {}", e))?;
+            log::info!("Generated headless API token");
+            Ok(token)
+        }
+        Err(e) => Err(format!("Failed to retrieve API token: {}", e)),
+    }
+}
+
+/// Starts the API server in the background when
+/// `AppSettings::headless_api_enabled` is on. Failures (no keychain, port
+/// in use) are logged and leave the app running without the API.
+pub fn spawn_server(app: AppHandle, queue: QueueHandle) {
+    let settings = config_service::load_settings(&app).unwrap_or_default();
+    if !settings.headless_api_enabled {
+        return;
+    }
+    let port = settings.headless_api_port;
+
+    tauri::async_runtime::spawn(async move {
+        let token = match ensure_token() {
+            Ok(token) => token,
+            Err(e) => {
+                log::error!("Headless API disabled: {}", e);
+                return;
+            }
+        };
+        let listener = match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Headless API could not listen on 127.0.0.1:{}: {}", port, e);
+                return;
+            }
+        };
+        log::info!("Headless API listening on http://127.0.0.1:{}", port);
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    log::warn!("Headless API accept failed: {}", e);
+                    continue;
+                }
+            };
+            let (app, queue, token) = (app.clone(), queue.clone(), token.clone());
+            tauri::async_runtime::spawn(async move {
+                serve_connection(app, queue, &token, stream).await;
+            });
+        }
+    });
+}
+
+/// Answers one request on `stream`, then applies its queue effect.
+async fn serve_connection(app: AppHandle, queue: QueueHandle, token: &str, mut stream: TcpStream) {
+    let timeout = std::time::Duration::from_secs(REQUEST_TIMEOUT_SECS);
+    let request = match tokio::time::timeout(timeout, read_request(&mut stream)).await {
+        Ok(Ok(request)) => request,
+        Ok(Err(response)) => {
+            let _ = write_response(&mut stream, &response).await;
+            return;
+        }
+        Err(_) => return,
+    };
+
+    let settings = config_service::load_settings(&app).unwrap_or_default();
+    let response = {
+        let mut q = queue.lock().await;
+        handle(&request, token, &mut q, &settings)
+    };
+    log::debug!(
+        "Headless API {} {} -> {}",
+        request.method,
+        request.path,
+        response.status
+    );
+    let _ = write_response(&mut stream, &response).await;
+
+    match response.effect {
+        Some(QueueEffect::Queued(download_id)) => {
+            download_queue::save_queue_to_disk(&app, &queue).await;
+            let _ = app.emit("download-queued", &download_id);
+            download_queue::process_queue(app, queue).await;
+        }
+        Some(QueueEffect::Cancelled(download_id)) => {
+            download_queue::flush_queue_to_disk(&app, &queue).await;
+            let _ = app.emit("download-cancelled", &download_id);
+        }
+        None => {}
+    }
+}
+
+async fn write_response(stream: &mut TcpStream, response: &ApiResponse) -> std::io::Result<()> {
+    let body = response.body.to_string();
+    let head = format!(
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason_phrase(response.status),
+        body.len()
+    );
+    stream.write_all(head.as_bytes()).await?;
+    stream.write_all(body.as_bytes()).await?;
+    stream.shutdown().await
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        431 => "Request Header Fields Too Large",
+        _ => "Error",
+    }
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::download::DownloadState;
+
+    const TOKEN: &str = "0123456789abcdef0123456789abcdef";
+    const ALBUM_URL: &str = "https://music.apple.com/us/album/example/1234567890";
+
+    fn api_request(method: &str, path: &str, token: Option<&str>, body: &str) -> ApiRequest {
+        let mut headers = Vec::new();
+        if let Some(token) = token {
+            headers.push(("authorization".to_string(), format!("Bearer {}", token)));
+        }
+        ApiRequest {
+            method: method.to_string(),
+            path: path.to_string(),
+            headers,
+            body: body.as_bytes().to_vec(),
+        }
+    }
+
+    // ----------------------------------------------------------
+    // Authentication
+    // ----------------------------------------------------------
+
+    /// Verifies that requests without the exact bearer token are rejected
+    /// with 401 and leave the queue untouched.
+    #[test]
+    fn rejects_missing_or_wrong_token() {
+        let mut queue = DownloadQueue::new();
+        let settings = AppSettings::default();
+        let body = json!({ "urls": [ALBUM_URL] }).to_string();
+
+        let wrong = "0123456789abcdef0123456789abcdee";
+        let truncated = &TOKEN[..TOKEN.len() - 1];
+        for token in [None, Some(wrong), Some(truncated), Some("")] {
+            let response = handle(
+                &api_request("POST", "/downloads", token, &body),
+                TOKEN,
+                &mut queue,
+                &settings,
+            );
+            assert_eq!(response.status, 401, "{:?}", token);
+            assert!(response.effect.is_none());
+        }
+
+        let mut basic = api_request("GET", "/downloads", None, "");
+        basic
+            .headers
+            .push(("authorization".to_string(), format!("Basic {}", TOKEN)));
+        assert_eq!(handle(&basic, TOKEN, &mut queue, &settings).status, 401);
+        assert!(queue.get_status().is_empty());
+    }
+
+    // ----------------------------------------------------------
+    // Endpoints
+    // ----------------------------------------------------------
+
+    /// Verifies that an enqueued request shows up in the status endpoints
+    /// as a Queued item with its URLs, and reports the enqueue effect.
+    #[test]
+    fn enqueue_round_trip() {
+        let mut queue = DownloadQueue::new();
+        let settings = AppSettings::default();
+        let body = json!({ "urls": [ALBUM_URL], "label": "from a script" }).to_string();
+
+        let created = handle(
+            &api_request("POST", "/downloads", Some(TOKEN), &body),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(created.status, 201);
+        let id = created.body["download_id"].as_str().unwrap().to_string();
+        assert_eq!(created.effect, Some(QueueEffect::Queued(id.clone())));
+
+        let listed = handle(
+            &api_request("GET", "/downloads", Some(TOKEN), ""),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(listed.status, 200);
+        assert_eq!(listed.body.as_array().unwrap().len(), 1);
+
+        let item = handle(
+            &api_request("GET", &format!("/downloads/{}", id), Some(TOKEN), ""),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(item.status, 200);
+        let status: QueueItemStatus = serde_json::from_value(item.body).unwrap();
+        assert_eq!(status.id, id);
+        assert_eq!(status.urls, vec![ALBUM_URL.to_string()]);
+        assert_eq!(status.state, DownloadState::Queued);
+        assert_eq!(status.label.as_deref(), Some("from a script"));
+    }
+
+    /// Verifies that malformed bodies and URLs are rejected before
+    /// anything is queued.
+    #[test]
+    fn enqueue_rejects_bad_requests() {
+        let mut queue = DownloadQueue::new();
+        let settings = AppSettings::default();
+        for body in [
+            "not json".to_string(),
+            json!({ "urls": [] }).to_string(),
+            json!({ "urls": ["https://example.com/album/1"] }).to_string(),
+        ] {
+            let response = handle(
+                &api_request("POST", "/downloads", Some(TOKEN), &body),
+                TOKEN,
+                &mut queue,
+                &settings,
+            );
+            assert_eq!(response.status, 400, "{}", body);
+        }
+        assert!(queue.get_status().is_empty());
+    }
+
+    /// Verifies that cancel and retry call through to the queue and
+    /// report unknown or finished items.
+    #[test]
+    fn cancel_and_retry() {
+        let mut queue = DownloadQueue::new();
+        let settings = AppSettings::default();
+        let id = queue.enqueue(
+            DownloadRequest {
+                urls: vec![ALBUM_URL.to_string()],
+                options: None,
+                label: None,
+                priority: 0,
+                fallback_chain_override: None,
+                kind: None,
+            },
+            &settings,
+        );
+        let post = |path: String| api_request("POST", &path, Some(TOKEN), "");
+
+        let cancelled = handle(
+            &post(format!("/downloads/{}/cancel", id)),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(cancelled.status, 200);
+        assert_eq!(cancelled.effect, Some(QueueEffect::Cancelled(id.clone())));
+        let again = handle(
+            &post(format!("/downloads/{}/cancel", id)),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(again.status, 409);
+
+        let retried = handle(
+            &post(format!("/downloads/{}/retry", id)),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(retried.status, 200);
+        assert_eq!(retried.effect, Some(QueueEffect::Queued(id.clone())));
+
+        let missing = handle(
+            &post("/downloads/nope/retry".to_string()),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(missing.status, 404);
+        let method = handle(
+            &api_request("DELETE", "/downloads", Some(TOKEN), ""),
+            TOKEN,
+            &mut queue,
+            &settings,
+        );
+        assert_eq!(method.status, 405);
+    }
+
+    // ----------------------------------------------------------
+    // read_request()
+    // ----------------------------------------------------------
+
+    /// Verifies that the request line, headers and body are parsed and the
+    /// query string is dropped from the path.
+    #[tokio::test]
+    async fn reads_request_with_body() {
+        let raw = "POST /downloads?x=1 HTTP/1.1\r\nHost: 127.0.0.1\r\nAuthorization: Bearer abc\r\nContent-Length: 4\r\n\r\n{}\r\n";
+        let request = read_request(raw.as_bytes()).await.unwrap();
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/downloads");
+        assert_eq!(request.header("AUTHORIZATION"), Some("Bearer abc"));
+        assert_eq!(request.body, b"{}\r\n");
+
+        let oversized = format!(
+            "POST /downloads HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY_BYTES + 1
+        );
+        assert_eq!(
+            read_request(oversized.as_bytes()).await.unwrap_err().status,
+            400
+        );
+    }
+
+    /// Verifies that an endless header line, too many headers and an
+    /// endless request line are cut off with a 431 before the token is
+    /// checked, and that headers just under the limits still parse.
+    #[tokio::test]
+    async fn rejects_oversized_headers() {
+        let long_value = "a".repeat(MAX_HEADER_BYTES as usize);
+        let long_header = format!("GET /downloads HTTP/1.1\r\nX-Junk: {}\r\n\r\n", long_value);
+        let response = read_request(long_header.as_bytes()).await.unwrap_err();
+        assert_eq!(response.status, 431);
+
+        let many_headers = format!(
+            "GET /downloads HTTP/1.1\r\n{}\r\n",
+            "X-Junk: a\r\n".repeat(MAX_HEADERS + 1)
+        );
+        let response = read_request(many_headers.as_bytes()).await.unwrap_err();
+        assert_eq!(response.status, 431);
+
+        let long_target = format!("GET /{} HTTP/1.1\r\n\r\n", long_value);
+        let response = read_request(long_target.as_bytes()).await.unwrap_err();
+        assert_eq!(response.status, 431);
+
+        let at_limit = format!(
+            "GET /downloads HTTP/1.1\r\n{}\r\n",
+            "X-Junk: a\r\n".repeat(MAX_HEADERS)
+        );
+        let request = read_request(at_limit.as_bytes()).await.unwrap();
+        assert_eq!(request.headers.len(), MAX_HEADERS);
+    }
+}
@@ -0,0 +1,486 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// lyrics_conversion_service.rs -- TTML lyrics to SRT / WebVTT / LRC
+// ==================================================================
+//
+// Apple Music serves lyrics as TTML, and GAMDL saves them as-is with
+// `--synced-lyrics-format ttml`. Many players want WebVTT or SRT instead,
+// and GAMDL's own SRT output is not always clean for word-timed lyrics.
+// With `AppSettings::convert_lyrics_to` set, every `.ttml` sidecar a
+// download wrote is converted to a sibling `.vtt`, `.srt` or `.lrc` file
+// after the download completes; the TTML is kept.
+//
+// One cue per TTML `<p>` line:
+//
+//   - Line timing comes from the `<p>`'s `begin` / `end` (or `dur`).
+//   - Word-timed TTML (`<span begin=".." end="..">` per word or syllable)
+//     is flattened to the line's text; a `<p>` without timing of its own
+//     takes the first span's `begin` and the last span's `end`.
+//   - `<br/>` becomes a line break within the cue; other markup is
+//     dropped and XML entities are decoded.
+//
+// Only files modified since the download's GAMDL run started are
+// converted, so sidecars from earlier downloads in the same folder are
+// left alone.
+//
+// @see download_queue.rs -- runs convert_album() after a download
+// @see https://www.w3.org/TR/ttml2/#timing-value-timeExpression
+// @see https://www.w3.org/TR/webvtt1/#file-structure
+
+use std::path::Path;
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use regex::Regex;
+
+use crate::models::settings::LyricsConversion;
+use crate::services::cover_resize_service::modified_since;
+use crate::utils::files;
+
+/// One timed lyrics line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cue {
+    /// Start time in milliseconds.
+    pub start_ms: u64,
+    /// End time in milliseconds.
+    pub end_ms: u64,
+    /// The line's text; `\n` separates lines within the cue.
+    pub text: String,
+}
+
+/// Matches a `<p>` element with its attributes and content.
+static P_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?s)<p\b([^>]*)>(.*?)</p>").expect("Invalid TTML paragraph regex")
+});
+
+/// Matches the attributes of an opening `<span>` tag.
+static SPAN_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<span\b([^>]*)>").expect("Invalid TTML span regex"));
+
+/// Matches one `name="value"` attribute; the name may carry a namespace
+/// prefix (`ttm:role`).
+static ATTR_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"([\w:.-]+)\s*=\s*(?:"([^"]*)"|'([^']*)')"#).expect("Invalid TTML attribute regex")
+});
+
+/// Matches a `<br>` line break in any of its spellings.
+static BR_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)<br\s*/?>").expect("Invalid TTML line break regex"));
+
+/// Matches any remaining tag.
+static TAG_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"<[^>]+>").expect("Invalid TTML tag regex"));
+
+/// Parses a TTML time expression into milliseconds.
+///
+/// Accepts clock times (`01:02:03.456`, `02:03.456`) and offset times
+/// (`3.456`, `3.456s`, `3456ms`, `2m`, `1h`), as Apple's TTML uses both.
+pub fn parse_time(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let seconds: f64 = if let Some(ms) = value.strip_suffix("ms") {
+        ms.parse::<f64>().ok()? / 1000.0
+    } else if let Some(s) = value.strip_suffix('s') {
+        s.parse().ok()?
+    } else if let Some(m) = value.strip_suffix('m') {
+        m.parse::<f64>().ok()? * 60.0
+    } else if let Some(h) = value.strip_suffix('h') {
+        h.parse::<f64>().ok()? * 3600.0
+    } else {
+        let parts: Vec<&str> = value.split(':').collect();
+        if parts.is_empty() || parts.len() > 3 {
+            return None;
+        }
+        let mut total = 0.0;
+        for part in &parts {
+            total = total * 60.0 + part.parse::<f64>().ok()?;
+        }
+        total
+    };
+    (seconds.is_finite() && seconds >= 0.0).then(|| (seconds * 1000.0).round() as u64)
+}
+
+/// Parses the lines of a TTML document into cues, in document order.
+///
+/// # Returns
+/// * `Ok(cues)` -- One cue per timed, non-empty `<p>`.
+/// * `Err(message)` -- The document has no timed lines.
+pub fn parse_ttml(ttml: &str) -> Result<Vec<Cue>, String> {
+    // Lines live in the body; the head may hold metadata such as
+    // translations that must not become cues.
+    let body = ttml.find("<body").map_or(ttml, |at| &ttml[at..]);
+
+    let mut lines: Vec<(u64, Option<u64>, String)> = Vec::new();
+    for p in P_REGEX.captures_iter(body) {
+        let content = &p[2];
+        let text = plain_text(content);
+        if text.is_empty() {
+            continue;
+        }
+
+        let attrs = attributes(&p[1]);
+        let spans: Vec<_> = SPAN_REGEX
+            .captures_iter(content)
+            .map(|span| attributes(&span[1]))
+            .collect();
+        let begin = attr_time(&attrs, "begin")
+            .or_else(|| spans.iter().find_map(|span| attr_time(span, "begin")));
+        let end = attr_time(&attrs, "end")
+            .or_else(|| Some(begin? + attr_time(&attrs, "dur")?))
+            .or_else(|| spans.iter().rev().find_map(|span| attr_time(span, "end")));
+
+        // An untimed line can't be placed in a timed format
+        if let Some(begin) = begin {
+            lines.push((begin, end, text));
+        }
+    }
+
+    if lines.is_empty() {
+        return Err("No timed lyrics lines found in TTML".to_string());
+    }
+
+    // A line without an end lasts until the next one starts
+    let starts: Vec<u64> = lines.iter().map(|(begin, _, _)| *begin).collect();
+    Ok(lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, (start_ms, end, text))| {
+            let end_ms = end
+                .or_else(|| starts.get(index + 1).copied())
+                .unwrap_or(start_ms)
+                .max(start_ms);
+            Cue {
+                start_ms,
+                end_ms,
+                text,
+            }
+        })
+        .collect())
+}
+
+/// Formats cues as SubRip: numbered blocks with `HH:MM:SS,mmm` times.
+pub fn to_srt(cues: &[Cue]) -> String {
+    cues.iter()
+        .enumerate()
+        .map(|(index, cue)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                index + 1,
+                clock(cue.start_ms, ','),
+                clock(cue.end_ms, ','),
+                cue.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Formats cues as WebVTT: the `WEBVTT` header, then blocks with
+/// `HH:MM:SS.mmm` times.
+pub fn to_vtt(cues: &[Cue]) -> String {
+    let blocks: Vec<String> = cues
+        .iter()
+        .map(|cue| {
+            format!(
+                "{} --> {}\n{}\n",
+                clock(cue.start_ms, '.'),
+                clock(cue.end_ms, '.'),
+                cue.text
+            )
+        })
+        .collect();
+    format!("WEBVTT\n\n{}", blocks.join("\n"))
+}
+
+/// Formats cues as LRC: one `[mm:ss.xx]` line per cue (LRC has no end
+/// times, and no line breaks within a line).
+pub fn to_lrc(cues: &[Cue]) -> String {
+    cues.iter()
+        .map(|cue| {
+            let centis = cue.start_ms / 10;
+            format!(
+                "[{:02}:{:02}.{:02}]{}\n",
+                centis / 6000,
+                centis / 100 % 60,
+                centis % 100,
+                cue.text.replace('\n', " ")
+            )
+        })
+        .collect()
+}
+
+/// Converts a TTML document to `format`.
+///
+/// # Returns
+/// * `Ok(text)` -- The converted file content.
+/// * `Err(message)` -- The document has no timed lines.
+pub fn convert(ttml: &str, format: LyricsConversion) -> Result<String, String> {
+    let cues = parse_ttml(ttml)?;
+    Ok(match format {
+        LyricsConversion::Srt => to_srt(&cues),
+        LyricsConversion::Vtt => to_vtt(&cues),
+        LyricsConversion::Lrc => to_lrc(&cues),
+    })
+}
+
+/// Converts every `.ttml` file under `album_dir` modified at or after
+/// `since` to a sibling file in `format`, replacing an older conversion.
+///
+/// # Returns
+/// * `Ok(count)` -- Number of files written.
+/// * `Err(message)` -- A file couldn't be read, parsed or written. Files
+///   converted before the failure are kept.
+pub fn convert_album(
+    album_dir: &Path,
+    since: SystemTime,
+    format: LyricsConversion,
+) -> Result<usize, String> {
+    let mut converted = 0;
+    let inputs = files::walk_files(album_dir, |path| {
+        files::has_extension(path, &["ttml"]) && modified_since(path, since)
+    });
+    for input in inputs {
+        let ttml = std::fs::read_to_string(&input)
+            .map_err(|e| format!("Could not read {}: {}", input.display(), e))?;
+        let output = convert(&ttml, format).map_err(|e| format!("{}: {}", input.display(), e))?;
+        let target = input.with_extension(format.extension());
+        std::fs::write(&target, output)
+            .map_err(|e| format!("Could not write {}: {}", target.display(), e))?;
+        converted += 1;
+    }
+    Ok(converted)
+}
+
+/// Returns the attributes of a tag as (local name, value) pairs, with
+/// namespace prefixes stripped (`itunes:key` -> `key`).
+fn attributes(tag: &str) -> Vec<(String, String)> {
+    ATTR_REGEX
+        .captures_iter(tag)
+        .map(|attr| {
+            let name = attr[1].rsplit(':').next().unwrap_or(&attr[1]).to_string();
+            let value = attr
+                .get(2)
+                .or_else(|| attr.get(3))
+                .map_or("", |v| v.as_str());
+            (name, value.to_string())
+        })
+        .collect()
+}
+
+/// Returns the parsed time of the attribute `name`, if present and valid.
+fn attr_time(attrs: &[(String, String)], name: &str) -> Option<u64> {
+    attrs
+        .iter()
+        .find(|(key, _)| key == name)
+        .and_then(|(_, value)| parse_time(value))
+}
+
+/// Returns the text of a `<p>`'s content: whitespace runs collapsed (word
+/// spans are often separated by newlines and indentation), `<br/>` as a
+/// line break, other tags dropped and entities decoded.
+fn plain_text(content: &str) -> String {
+    let collapsed = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let with_breaks = BR_REGEX.replace_all(&collapsed, "\n");
+    let text = decode_entities(&TAG_REGEX.replace_all(&with_breaks, ""));
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Decodes the predefined XML entities and numeric character references.
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(at) = rest.find('&') {
+        out.push_str(&rest[..at]);
+        rest = &rest[at..];
+        let decoded = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse()))
+                    .and_then(Result::ok)
+                    .and_then(char::from_u32),
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Formats milliseconds as `HH:MM:SS<separator>mmm`.
+fn clock(ms: u64, separator: char) -> String {
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        ms / 3_600_000,
+        ms / 60_000 % 60,
+        ms / 1000 % 60,
+        separator,
+        ms % 1000
+    )
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    /// Line-timed TTML, with a line break, an entity and an untimed
+    /// empty line.
+    const LINE_TTML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<tt xmlns="http://www.w3.org/ns/ttml" xmlns:itunes="http://music.apple.com/lyric-ttml-internal" itunes:timing="Line">
+  <head><metadata><iTunesMetadata><translations/></iTunesMetadata></metadata></head>
+  <body dur="1:05.000">
+    <div begin="12.100" end="1:05.000">
+      <p begin="12.100" end="15.250" itunes:key="L1">Rock &amp; roll<br/>all night</p>
+      <p begin="15.250" end="1:01.5" itunes:key="L2">Party every day</p>
+      <p begin="1:02.000" end="1:05.000" itunes:key="L3"></p>
+    </div>
+  </body>
+</tt>"#;
+
+    /// Word-timed TTML: the first line has its own timing, the second only
+    /// on its spans (one word split into syllables).
+    const WORD_TTML: &str = r#"<tt xmlns="http://www.w3.org/ns/ttml" itunes:timing="Word">
+  <body>
+    <div>
+      <p begin="00:00:01.000" end="00:00:02.500"><span begin="00:00:01.000" end="00:00:01.400">Hello</span> <span begin="00:00:01.400" end="00:00:02.500">world</span></p>
+      <p>
+        <span begin="2.6s" end="3.0s">Beau</span><span begin="3.0s" end="3.4s">tiful</span>
+        <span begin="3.4s" end="4200ms">day</span>
+      </p>
+    </div>
+  </body>
+</tt>"#;
+
+    /// Verifies clock and offset time expressions.
+    #[test]
+    fn parses_time_expressions() {
+        assert_eq!(parse_time("01:02:03.456"), Some(3_723_456));
+        assert_eq!(parse_time("1:05.000"), Some(65_000));
+        assert_eq!(parse_time("12.1"), Some(12_100));
+        assert_eq!(parse_time("3.4s"), Some(3_400));
+        assert_eq!(parse_time("4200ms"), Some(4_200));
+        assert_eq!(parse_time("2m"), Some(120_000));
+        assert_eq!(parse_time("abc"), None);
+        assert_eq!(parse_time("-1"), None);
+    }
+
+    /// Verifies the SubRip output of line-timed TTML.
+    #[test]
+    fn converts_line_timed_ttml_to_srt() {
+        let srt = convert(LINE_TTML, LyricsConversion::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:12,100 --> 00:00:15,250\nRock & roll\nall night\n\n\
+             2\n00:00:15,250 --> 00:01:01,500\nParty every day\n"
+        );
+    }
+
+    /// Verifies the WebVTT output of line-timed TTML.
+    #[test]
+    fn converts_line_timed_ttml_to_vtt() {
+        let vtt = convert(LINE_TTML, LyricsConversion::Vtt).unwrap();
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n\
+             00:00:12.100 --> 00:00:15.250\nRock & roll\nall night\n\n\
+             00:00:15.250 --> 00:01:01.500\nParty every day\n"
+        );
+    }
+
+    /// Verifies that word-timed TTML becomes one cue per line, timed by the
+    /// spans when the line has no timing of its own, with syllables joined.
+    #[test]
+    fn converts_word_timed_ttml() {
+        let srt = convert(WORD_TTML, LyricsConversion::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:01,000 --> 00:00:02,500\nHello world\n\n\
+             2\n00:00:02,600 --> 00:00:04,200\nBeautiful day\n"
+        );
+        let vtt = convert(WORD_TTML, LyricsConversion::Vtt).unwrap();
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n\
+             00:00:01.000 --> 00:00:02.500\nHello world\n\n\
+             00:00:02.600 --> 00:00:04.200\nBeautiful day\n"
+        );
+    }
+
+    /// Verifies LRC output, and that a document without timed lines is an
+    /// error.
+    #[test]
+    fn converts_to_lrc_and_rejects_untimed_documents() {
+        let lrc = convert(LINE_TTML, LyricsConversion::Lrc).unwrap();
+        assert_eq!(
+            lrc,
+            "[00:12.10]Rock & roll all night\n[00:15.25]Party every day\n"
+        );
+        assert!(convert(
+            "<tt><body><p>No timing</p></body></tt>",
+            LyricsConversion::Srt
+        )
+        .is_err());
+    }
+
+    /// Verifies that a line without an end lasts until the next line.
+    #[test]
+    fn open_ended_lines_run_to_the_next_line() {
+        let cues = parse_ttml(
+            r#"<tt><body><p begin="1.0">One</p><p begin="4.0" dur="2.0">Two</p></body></tt>"#,
+        )
+        .unwrap();
+        assert_eq!((cues[0].start_ms, cues[0].end_ms), (1_000, 4_000));
+        assert_eq!((cues[1].start_ms, cues[1].end_ms), (4_000, 6_000));
+    }
+
+    /// Verifies that only sidecars written since the download started are
+    /// converted, next to the TTML, which is kept.
+    #[test]
+    fn converts_only_new_sidecars_in_album() {
+        let dir = TestDir::new("lyrics");
+        std::fs::create_dir_all(dir.join("Disc 2")).unwrap();
+        let ttml = dir.join("Disc 2").join("01 Song.ttml");
+        std::fs::write(&ttml, LINE_TTML).unwrap();
+
+        let later = SystemTime::now() + std::time::Duration::from_secs(60);
+        assert_eq!(
+            convert_album(&dir, later, LyricsConversion::Vtt).unwrap(),
+            0
+        );
+
+        let earlier = SystemTime::now() - std::time::Duration::from_secs(60);
+        assert_eq!(
+            convert_album(&dir, earlier, LyricsConversion::Vtt).unwrap(),
+            1
+        );
+        let vtt = std::fs::read_to_string(ttml.with_extension("vtt")).unwrap();
+        assert!(vtt.starts_with("WEBVTT\n\n00:00:12.100"));
+        assert!(ttml.exists());
+    }
+}
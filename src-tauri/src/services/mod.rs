@@ -30,6 +30,30 @@
 //   +-- login_window_service.rs  -- Embedded Apple Music login webview
 //   +-- animated_artwork_service -- Animated cover art via MusicKit API
 //   +-- metadata_tag_service.rs  -- Custom codec metadata tagging for M4A files
+//   +-- flac_conversion_service  -- Post-download ALAC (M4A) to FLAC transcode
+//   +-- storefront_service.rs    -- Per-download storefront override and fallback
+//   +-- companion_lyrics_service -- Lyrics sidecars copied to companion files
+//   +-- playlist_sync_service.rs -- Download only tracks new to a playlist
+//   +-- size_estimate_service.rs -- Download size estimate from track durations
+//   +-- catalog_service.rs       -- Apple Music catalog API requests and paging
+//   +-- artwork_probe_service.rs -- Cached album artwork dimensions and motion URLs
+//   +-- shutdown.rs              -- Stop downloads and save the queue before exit
+//   +-- album_resume_service.rs  -- Resume interrupted albums from their remaining tracks
+//   +-- preflight_service.rs     -- Toolchain health check before a download
+//   +-- cover_resize_service.rs  -- Smaller embedded covers than the saved sidecar
+//   +-- headless_api.rs          -- Localhost HTTP API for scripting the queue
+//   +-- environment_service.rs   -- Tool paths/versions report for bug reports
+//   +-- lyrics_conversion_service -- TTML lyrics to SRT/WebVTT/LRC after download
+//   +-- audio_verify_service     -- Decode check of downloaded files (verify_audio)
+//   +-- download_log_service     -- Per-download GAMDL output logs (save_download_logs)
+//   +-- track_availability_service -- Skips tracks the catalog no longer offers
+//   +-- staging_service          -- Staging folder, moved to output on success
+//   +-- test_download_service    -- One-track download that checks the whole setup
+//   +-- csv_import_service       -- Bulk enqueue from a CSV with per-row options
+//   +-- batch_report_service     -- Per-URL outcome summary of a batch of downloads
+//   +-- content_rating_service   -- Swaps tracks for their explicit/clean version
+//   +-- settings_transfer_service -- Settings export/import without secrets
+//   +-- transcode_service        -- MP3/Opus copies of downloads for older devices
 //
 // Thread safety:
 //   Services that access shared state (like the download queue) use
@@ -122,3 +146,151 @@ pub mod animated_artwork_service;
 /// `SpatialType = Dolby Atmos` in both the Apple iTunes and MeedyaMeta
 /// namespaces. Safe for all audio stream types (ALAC, EC-3, AAC).
 pub mod metadata_tag_service;
+
+/// Music video lyrics service: when an album's music videos are skipped,
+/// fetches just their lyrics with lyrics-only GAMDL runs and saves them
+/// next to the audio tracks whose titles match (gated by
+/// `AppSettings::keep_mv_lyrics`).
+pub mod mv_lyrics_service;
+
+/// Tray status updater: keeps the tray menu's "Downloads" item in sync
+/// with the download queue (e.g., "Downloading: Artist - Title (3 queued)").
+/// Polls a small snapshot of the queue and updates the menu item outside
+/// the queue lock.
+pub mod tray_status;
+
+/// Auto-quit: exits the app after a grace period once the download queue
+/// drains, when `AppSettings::auto_quit_when_done` is on. Waits for
+/// artwork and companion background tasks before quitting.
+pub mod auto_quit;
+
+/// Download schedule: restricts downloads to a daily quiet-hours window
+/// (`AppSettings::schedule_enabled`). A background ticker resumes the
+/// queue when the window opens and optionally pauses it when it closes.
+pub mod download_schedule;
+
+/// Artist URL expansion: turns an Apple Music artist URL into one download
+/// request per album, listed (with pagination) from the catalog API, so
+/// each album gets its own progress, fallback and retry.
+pub mod artist_expansion_service;
+
+/// Lossless output conversion: transcodes finished ALAC downloads from M4A
+/// to FLAC with FFmpeg (`AppSettings::convert_lossless_to_flac`), carrying
+/// over tags and the embedded cover. Each file is probed first so AAC and
+/// Atmos files sharing the folder are never touched.
+pub mod flac_conversion_service;
+
+/// Storefront override: rewrites the country segment of Apple Music URLs
+/// (`/us/` to `/jp/`) for downloads with `GamdlOptions::storefront` set,
+/// and lists the storefronts the queue falls back to when content isn't
+/// available in the one requested.
+pub mod storefront_service;
+
+/// Companion lyrics: copies the lyrics sidecars GAMDL wrote for a primary
+/// download to stem-matched names for its companion files (e.g.,
+/// "01 Song [Lossless].lrc"), so players find lyrics for every version.
+pub mod companion_lyrics_service;
+
+/// Playlist sync: remembers the catalog IDs downloaded per Apple Music
+/// playlist and turns a re-submitted playlist URL into a download of the
+/// tracks added since the last sync.
+pub mod playlist_sync_service;
+
+/// Size estimate: sums the catalog durations of the tracks behind a set of
+/// URLs and converts them to bytes with the chosen codec's average bitrate.
+pub mod size_estimate_service;
+
+/// Catalog client: the one place that sends Apple Music API requests
+/// (authorized with the MusicKit developer token) and follows the `next`
+/// links of list endpoints, for every service that reads the catalog.
+pub mod catalog_service;
+
+/// Artwork probe: one cached catalog call per album for its native cover
+/// dimensions (so `cover_size` never exceeds the source) and its animated
+/// artwork URLs. An LRU cache keyed by album ID serves later lookups.
+pub mod artwork_probe_service;
+
+/// Graceful shutdown: holds back app exit (tray quit, window close,
+/// Ctrl+C/SIGTERM) until running downloads and their child processes are
+/// killed and the interrupted items are saved as Queued in `queue.json`.
+pub mod shutdown;
+
+/// Album resume: records which album tracks of a download are on disk and,
+/// when an interrupted album download restarts, passes GAMDL the URLs of
+/// the remaining tracks instead of the whole album.
+pub mod album_resume_service;
+
+/// Pre-flight checks: cookies, Python, GAMDL, FFmpeg/mp4decrypt, output
+/// folder and URLs, each reported as pass/fail with a remediation hint.
+/// Backs the `preflight` command and the optional check in `start_download`.
+pub mod preflight_service;
+
+/// Embedded cover resize: scales the cover embedded in each downloaded M4A
+/// down to `embedded_cover_size` with FFmpeg, leaving the sidecar cover at
+/// the full download size, and saves the sidecar at `extra_cover_sizes`.
+pub mod cover_resize_service;
+
+/// Headless API: an optional token-protected HTTP server on 127.0.0.1 that
+/// enqueues, lists, cancels and retries downloads through the same queue
+/// methods as the Tauri commands.
+pub mod headless_api;
+
+/// Environment report: resolved paths and detected versions of Python,
+/// GAMDL and the external tools, plus the app data directory and its free
+/// space, for bug reports. Backs the `get_environment_report` command.
+pub mod environment_service;
+
+/// Lyrics conversion: converts the TTML lyrics sidecars a download wrote
+/// to SRT, WebVTT or LRC (`AppSettings::convert_lyrics_to`), one cue per
+/// lyrics line, for line- and word-timed TTML alike.
+pub mod lyrics_conversion_service;
+
+/// Audio verification: decodes each file a download wrote with FFmpeg
+/// (`AppSettings::verify_audio`) so truncated or corrupt files are caught
+/// and re-downloaded instead of being reported as complete.
+pub mod audio_verify_service;
+
+/// Per-download logs: each job's raw GAMDL output in a file of its own
+/// (`AppSettings::save_download_logs`), read back with the cookies path
+/// redacted for bug reports.
+pub mod download_log_service;
+
+/// Track availability: leaves out album and playlist tracks the Apple Music
+/// catalog no longer offers before queueing
+/// (`AppSettings::skip_unavailable_tracks`).
+pub mod track_availability_service;
+
+/// Download staging: GAMDL writes into a per-download folder in the app
+/// data directory, moved into the output path once the download succeeds
+/// (`AppSettings::use_staging_dir`).
+pub mod staging_service;
+
+/// Test download: downloads one known track into a scratch folder with the
+/// current settings and reports the first pipeline stage that failed (auth,
+/// codec, decrypt, remux, ...). Backs the `run_test_download` command.
+pub mod test_download_service;
+
+/// CSV import: parses a CSV file of downloads (`url,codec,output_path,label`)
+/// into one request per row for the `import_csv` command, reporting broken
+/// rows by line number instead of failing the whole file.
+pub mod csv_import_service;
+
+/// Batch reports: one row per URL of a set of downloads (complete, skipped,
+/// failed or pending, with the failure category and a short reason) and a
+/// summary line. Backs the `get_batch_report` command.
+pub mod batch_report_service;
+
+/// Explicit/clean preference: swaps album and song URLs for the other
+/// release's version of each track before queueing
+/// (`AppSettings::content_preference`).
+pub mod content_rating_service;
+
+/// Settings export/import: writes the settings to a shareable file without
+/// the cookies path, MusicKit IDs, tool paths and proxies, and merges such
+/// a file over the current settings, keeping machine-specific paths.
+pub mod settings_transfer_service;
+
+/// Transcode companion: saves an MP3 or Opus copy of each AAC/ALAC track a
+/// download wrote, with its tags and cover, for devices that can't play
+/// M4A (`AppSettings::transcode_companion`).
+pub mod transcode_service;
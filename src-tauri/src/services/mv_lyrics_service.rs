@@ -0,0 +1,518 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// mv_lyrics_service.rs -- Keep the lyrics of skipped album music videos
+// =====================================================================
+//
+// By default GAMDL skips the music videos on an album (see
+// `GamdlOptions::disable_music_video_skip`). Their lyrics are often worth
+// keeping though -- music videos carry Apple's TTML lyrics, and some
+// tracks only have synced lyrics on the video. When
+// `AppSettings::keep_mv_lyrics` is on, this service runs after a
+// successful album download:
+//
+//   1. Two lyrics-only GAMDL runs into a temporary folder: one with the
+//      music video skip on (songs only) and one with it off (songs and
+//      music videos). The files only the second run produced belong to
+//      the music videos. This avoids needing MusicKit credentials to
+//      look up which tracks are videos.
+//   2. Each music video's title is matched against the titles of the
+//      downloaded audio tracks (read from their metadata). Titles are
+//      normalised first so "Song (feat. X) [Official Video]" and
+//      "Song (Live)" still find "Song"; unconfident or ambiguous matches
+//      are skipped rather than guessed.
+//   3. The lyrics file is copied next to the matching audio file, named
+//      after it. An existing lyrics file is never overwritten; the music
+//      video's copy gets a " [Music Video]" suffix instead.
+//
+// Runs as a background task after the download completes; failures are
+// logged and never affect the download's status.
+//
+// @see download_queue.rs -- Spawns save_music_video_lyrics() on success
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+use regex::Regex;
+use tauri::AppHandle;
+
+use crate::models::gamdl_options::{GamdlOptions, LyricsFormat};
+use crate::services::gamdl_service;
+use crate::utils::files;
+
+/// Minimum word similarity (0.0 to 1.0) between a music video title and
+/// an audio track title for the match to count as confident.
+const MATCH_THRESHOLD: f64 = 0.85;
+
+/// File templates for the temporary lyrics-only runs. Fixed so the title
+/// can be read back from the file name regardless of the user's templates.
+const SINGLE_DISC_TEMPLATE: &str = "{track:02d} {title}";
+const MULTI_DISC_TEMPLATE: &str = "{disc}-{track:02d} {title}";
+
+/// Words that mark a bracketed part of a title as decoration rather than
+/// part of the song's name, e.g. "(feat. X)", "(Live)", "[Official Video]".
+const NOISE_WORDS: &[&str] = &[
+    "feat",
+    "ft",
+    "featuring",
+    "live",
+    "video",
+    "official",
+    "visualizer",
+    "lyric",
+    "lyrics",
+    "audio",
+    "explicit",
+    "clean",
+];
+
+/// Matches a parenthesised or square-bracketed part of a title.
+static BRACKETED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[(\[]([^)\]]*)[)\]]").expect("Invalid bracketed regex")
+});
+
+/// Matches an unbracketed featuring credit and everything after it,
+/// e.g. " feat. Artist" in "Song feat. Artist".
+static TRAILING_FEAT_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\s+(?:feat\.?|ft\.?|featuring)\s+.*$").expect("Invalid featuring regex")
+});
+
+/// Matches a leading track number as produced by the default file
+/// templates: "01 ", "1-03 ", "01. ", "01 - ".
+static TRACK_PREFIX_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\d{1,3}(?:-\d{1,3})?(?:\s*[.\-]\s*|\s+)").expect("Invalid track prefix regex")
+});
+
+/// Returns `true` for Apple Music album URLs (not a single track picked
+/// from an album via `?i=`), the only downloads that can contain skipped
+/// music videos.
+pub fn is_album_url(url: &str) -> bool {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return false;
+    };
+    let is_album_path = parsed
+        .path_segments()
+        .is_some_and(|mut segments| segments.any(|s| s == "album"));
+    is_album_path && !parsed.query_pairs().any(|(key, _)| key == "i")
+}
+
+/// Fetches the lyrics of an album's skipped music videos and saves them
+/// next to the matching audio tracks in `album_dir`.
+///
+/// # Arguments
+/// * `app` - The Tauri app handle (for building the GAMDL command)
+/// * `urls` - The album URL(s) that were downloaded
+/// * `base_options` - The options the album was downloaded with (cookies,
+///   tool paths, language)
+/// * `album_dir` - The folder holding the downloaded audio files
+/// * `download_id` - Used to name the temporary folder
+///
+/// # Returns
+/// * `Ok(count)` - Number of lyrics files saved (0 if the album has no
+///   music videos or none matched)
+/// * `Err(message)` - A lyrics-only GAMDL run failed
+pub async fn save_music_video_lyrics(
+    app: &AppHandle,
+    urls: &[String],
+    base_options: &GamdlOptions,
+    album_dir: &Path,
+    download_id: &str,
+) -> Result<usize, String> {
+    let temp_root = std::env::temp_dir().join(format!("meedyadl-mv-lyrics-{}", download_id));
+    let result = save_with_temp_dir(app, urls, base_options, album_dir, &temp_root).await;
+    if let Err(e) = tokio::fs::remove_dir_all(&temp_root).await {
+        log::debug!("Failed to remove {}: {}", temp_root.display(), e);
+    }
+    result
+}
+
+/// Does the work of [`save_music_video_lyrics`] inside `temp_root`, which
+/// the caller removes afterwards.
+async fn save_with_temp_dir(
+    app: &AppHandle,
+    urls: &[String],
+    base_options: &GamdlOptions,
+    album_dir: &Path,
+    temp_root: &Path,
+) -> Result<usize, String> {
+    let songs_dir = temp_root.join("songs");
+    let all_dir = temp_root.join("all");
+
+    run_lyrics_only(app, urls, &lyrics_only_options(base_options, &songs_dir, false)).await?;
+    run_lyrics_only(app, urls, &lyrics_only_options(base_options, &all_dir, true)).await?;
+
+    // A missing folder (GAMDL found no lyrics at all) yields no files
+    let song_lyrics: HashSet<PathBuf> = files::walk_files(&songs_dir, |_| true)
+        .into_iter()
+        .filter_map(|p| p.strip_prefix(&songs_dir).ok().map(Path::to_path_buf))
+        .collect();
+    let video_lyrics: Vec<PathBuf> = files::walk_files(&all_dir, |_| true)
+        .into_iter()
+        .filter(|p| {
+            p.strip_prefix(&all_dir)
+                .map(|rel| !song_lyrics.contains(rel))
+                .unwrap_or(false)
+        })
+        .collect();
+    if video_lyrics.is_empty() {
+        log::debug!("No music video lyrics found for {}", album_dir.display());
+        return Ok(0);
+    }
+
+    let tracks = audio_tracks(album_dir);
+    let titles: Vec<String> = tracks.iter().map(|(_, title)| title.clone()).collect();
+
+    let mut saved = 0;
+    for lyrics in video_lyrics {
+        let Some(stem) = lyrics.file_stem().and_then(|s| s.to_str()) else {
+            continue;
+        };
+        let video_title = strip_track_prefix(stem);
+        let Some(index) = best_title_match(video_title, &titles) else {
+            log::info!("No confident audio match for music video \"{}\"; skipping", video_title);
+            continue;
+        };
+        let Some(dest) = sidecar_path(&tracks[index].0, &lyrics) else {
+            continue;
+        };
+        match tokio::fs::copy(&lyrics, &dest).await {
+            Ok(_) => {
+                log::info!("Saved music video lyrics to {}", dest.display());
+                saved += 1;
+            }
+            Err(e) => log::debug!("Failed to save {}: {}", dest.display(), e),
+        }
+    }
+    Ok(saved)
+}
+
+/// Derives the options for a lyrics-only GAMDL run into `output_dir`.
+///
+/// Lyrics are fetched as TTML (Apple's native format, and the format
+/// music video lyrics default to) with fixed file templates so titles can
+/// be read back from the file names.
+fn lyrics_only_options(
+    base: &GamdlOptions,
+    output_dir: &Path,
+    include_music_videos: bool,
+) -> GamdlOptions {
+    let mut options = base.clone();
+    options.output_path = Some(output_dir.to_string_lossy().to_string());
+    options.synced_lyrics_only = Some(true);
+    options.no_synced_lyrics = Some(false);
+    options.synced_lyrics_format = Some(LyricsFormat::Ttml);
+    options.disable_music_video_skip = Some(include_music_videos);
+    options.single_disc_file_template = Some(SINGLE_DISC_TEMPLATE.to_string());
+    options.multi_disc_file_template = Some(MULTI_DISC_TEMPLATE.to_string());
+    options.save_cover = Some(false);
+    options.save_playlist = Some(false);
+    options.overwrite = Some(true);
+    options
+}
+
+/// Runs GAMDL to completion, capturing its output for error reporting.
+async fn run_lyrics_only(
+    app: &AppHandle,
+    urls: &[String],
+    options: &GamdlOptions,
+) -> Result<(), String> {
+    let mut cmd = gamdl_service::build_gamdl_command_public(app, urls, options)?;
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+    // Runs inside a queue background task; if that task is aborted (the
+    // parent item was removed), take GAMDL down with it.
+    cmd.kill_on_drop(true);
+
+    let output = cmd
+        .spawn()
+        .map_err(|e| format!("Failed to start lyrics-only GAMDL run: {}", e))?
+        .wait_with_output()
+        .await
+        .map_err(|e| format!("Lyrics-only GAMDL run failed: {}", e))?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        Err(format!(
+            "Lyrics-only GAMDL run failed: {}",
+            stderr.lines().last().unwrap_or("unknown error")
+        ))
+    }
+}
+
+/// Lists the audio files under `album_dir` with their titles, read from
+/// the file's metadata or, failing that, from its name.
+fn audio_tracks(album_dir: &Path) -> Vec<(PathBuf, String)> {
+    files::walk_files(album_dir, |path| files::has_extension(path, &["m4a"]))
+        .into_iter()
+        .filter_map(|path| {
+            let title = mp4ameta::Tag::read_from_path(&path)
+                .ok()
+                .and_then(|tag| tag.title().map(str::to_string))
+                .or_else(|| {
+                    path.file_stem()
+                        .and_then(|s| s.to_str())
+                        .map(|s| strip_track_prefix(s).to_string())
+                })?;
+            Some((path, title))
+        })
+        .collect()
+}
+
+/// Picks where to save a music video's lyrics next to an audio file:
+/// `<audio stem>.<ext>`, or `<audio stem> [Music Video].<ext>` if the
+/// track already has a lyrics file in that format. Returns `None` if both
+/// are taken.
+fn sidecar_path(audio: &Path, lyrics: &Path) -> Option<PathBuf> {
+    let stem = audio.file_stem()?.to_str()?;
+    let ext = lyrics.extension()?.to_str()?;
+    [format!("{}.{}", stem, ext), format!("{} [Music Video].{}", stem, ext)]
+        .into_iter()
+        .map(|name| audio.with_file_name(name))
+        .find(|path| !path.exists())
+}
+
+/// Removes a leading track number ("01 ", "1-03 ", "01. ") from a file stem.
+fn strip_track_prefix(stem: &str) -> &str {
+    match TRACK_PREFIX_REGEX.find(stem) {
+        Some(m) if m.end() < stem.len() => &stem[m.end()..],
+        _ => stem,
+    }
+}
+
+/// Reduces a title to its lowercase words, dropping featuring credits and
+/// decorations like "(Live)" or "[Official Video]" so the same song
+/// compares equal across its audio and video releases.
+fn normalize_title(title: &str) -> String {
+    let lower = title.to_lowercase();
+    let without_noise = BRACKETED_REGEX.replace_all(&lower, |caps: &regex::Captures| {
+        let inner = &caps[1];
+        let is_noise = words(inner).iter().any(|w| NOISE_WORDS.contains(&w.as_str()));
+        if is_noise {
+            " ".to_string()
+        } else {
+            format!(" {} ", inner)
+        }
+    });
+    let without_feat = TRAILING_FEAT_REGEX.replace(&without_noise, "");
+    words(&without_feat).join(" ")
+}
+
+/// Splits text into lowercase alphanumeric words. Apostrophes are dropped
+/// ("Don't" -> "dont") and "&" reads as "and".
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .replace(['\'', '\u{2019}'], "")
+        .replace('&', " and ")
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Dice coefficient of the two titles' normalised word sets: 1.0 for the
+/// same words, 0.0 for none in common.
+fn title_similarity(a: &str, b: &str) -> f64 {
+    let a: HashSet<String> = words(&normalize_title(a)).into_iter().collect();
+    let b: HashSet<String> = words(&normalize_title(b)).into_iter().collect();
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(&b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+/// Finds the audio track a music video belongs to.
+///
+/// The best-scoring candidate wins if it reaches `MATCH_THRESHOLD`. When
+/// several candidates tie (e.g. "Song" and "Song (Live)" both normalise
+/// to "song"), the one whose title is literally the same as the video's
+/// is chosen; if there is no such single candidate, the match is
+/// ambiguous and `None` is returned.
+fn best_title_match(video_title: &str, candidates: &[String]) -> Option<usize> {
+    let scores: Vec<f64> = candidates
+        .iter()
+        .map(|c| title_similarity(video_title, c))
+        .collect();
+    let best = scores.iter().copied().fold(0.0, f64::max);
+    if best < MATCH_THRESHOLD {
+        return None;
+    }
+
+    let top: Vec<usize> = (0..candidates.len())
+        .filter(|&i| (scores[i] - best).abs() < f64::EPSILON)
+        .collect();
+    if let [only] = top[..] {
+        return Some(only);
+    }
+
+    let literal = words(video_title);
+    let exact: Vec<usize> = top
+        .into_iter()
+        .filter(|&i| words(&candidates[i]) == literal)
+        .collect();
+    match exact[..] {
+        [only] => Some(only),
+        _ => None,
+    }
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn titles(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    // ----------------------------------------------------------
+    // normalize_title()
+    // ----------------------------------------------------------
+
+    /// Verifies that featuring credits are dropped, bracketed or not.
+    #[test]
+    fn normalize_drops_featuring_credits() {
+        assert_eq!(normalize_title("Song (feat. Other Artist)"), "song");
+        assert_eq!(normalize_title("Song [ft. Other Artist]"), "song");
+        assert_eq!(normalize_title("Song feat. Other Artist"), "song");
+    }
+
+    /// Verifies that "(Live)" and video decorations are dropped.
+    #[test]
+    fn normalize_drops_live_and_video_decorations() {
+        assert_eq!(normalize_title("Song (Live)"), "song");
+        assert_eq!(normalize_title("Song [Official Music Video]"), "song");
+        assert_eq!(normalize_title("Song (Live at Wembley) [Official Video]"), "song");
+    }
+
+    /// Verifies that meaningful parentheticals and punctuation survive.
+    #[test]
+    fn normalize_keeps_meaningful_parentheticals() {
+        assert_eq!(normalize_title("Song (Acoustic)"), "song acoustic");
+        assert_eq!(normalize_title("Don't Stop Me Now"), "dont stop me now");
+        assert_eq!(normalize_title("Rock & Roll"), "rock and roll");
+    }
+
+    // ----------------------------------------------------------
+    // strip_track_prefix()
+    // ----------------------------------------------------------
+
+    /// Verifies the track number prefixes of the default templates.
+    #[test]
+    fn strips_track_number_prefixes() {
+        assert_eq!(strip_track_prefix("01 Song"), "Song");
+        assert_eq!(strip_track_prefix("1-03 Song"), "Song");
+        assert_eq!(strip_track_prefix("01. Song"), "Song");
+        assert_eq!(strip_track_prefix("Song"), "Song");
+        // A title that is only a number is left alone.
+        assert_eq!(strip_track_prefix("1999"), "1999");
+    }
+
+    // ----------------------------------------------------------
+    // best_title_match()
+    // ----------------------------------------------------------
+
+    /// Verifies that a decorated video title finds the plain audio title.
+    #[test]
+    fn matches_video_with_featuring_and_video_suffix() {
+        let audio = titles(&["Intro", "Song (feat. Other Artist)", "Outro"]);
+        assert_eq!(
+            best_title_match("Song (feat. Other Artist) [Official Video]", &audio),
+            Some(1)
+        );
+        assert_eq!(best_title_match("Song", &audio), Some(1));
+    }
+
+    /// Verifies that a live video matches the studio track.
+    #[test]
+    fn matches_live_video_to_studio_track() {
+        let audio = titles(&["First Song", "Second Song"]);
+        assert_eq!(best_title_match("Second Song (Live)", &audio), Some(1));
+    }
+
+    /// Verifies that small spelling differences still match.
+    #[test]
+    fn matches_punctuation_differences() {
+        let audio = titles(&["Dont Stop Me Now", "Bicycle Race"]);
+        assert_eq!(best_title_match("Don't Stop Me Now", &audio), Some(0));
+    }
+
+    /// Verifies that unrelated or only partly overlapping titles are skipped.
+    #[test]
+    fn skips_unconfident_matches() {
+        let audio = titles(&["Love Me Do", "Yesterday"]);
+        assert_eq!(best_title_match("Love Me", &audio), None);
+        assert_eq!(best_title_match("Something Else", &audio), None);
+        assert_eq!(best_title_match("Anything", &[]), None);
+    }
+
+    /// Verifies that ties are broken by the literal title, and skipped if
+    /// that doesn't settle them.
+    #[test]
+    fn resolves_or_skips_ambiguous_matches() {
+        let audio = titles(&["Song", "Song (Live)"]);
+        assert_eq!(best_title_match("Song (Live)", &audio), Some(1));
+        assert_eq!(best_title_match("Song", &audio), Some(0));
+        assert_eq!(best_title_match("Song [Official Video]", &audio), None);
+    }
+
+    // ----------------------------------------------------------
+    // is_album_url()
+    // ----------------------------------------------------------
+
+    /// Verifies that only whole-album URLs qualify.
+    #[test]
+    fn detects_album_urls() {
+        assert!(is_album_url("https://music.apple.com/us/album/midnights/1649434004"));
+        assert!(!is_album_url(
+            "https://music.apple.com/us/album/midnights/1649434004?i=1649434280"
+        ));
+        assert!(!is_album_url("https://music.apple.com/us/playlist/todays-hits/pl.123"));
+        assert!(!is_album_url("not a url"));
+    }
+
+    // ----------------------------------------------------------
+    // lyrics_only_options() / sidecar_path()
+    // ----------------------------------------------------------
+
+    /// Verifies the overrides for the temporary lyrics-only runs.
+    #[test]
+    fn lyrics_only_options_override_output_and_templates() {
+        let base = GamdlOptions {
+            cookies_path: Some("/cookies.txt".to_string()),
+            single_disc_file_template: Some("{title}".to_string()),
+            ..Default::default()
+        };
+        let options = lyrics_only_options(&base, Path::new("/tmp/all"), true);
+
+        assert_eq!(options.output_path.as_deref(), Some("/tmp/all"));
+        assert_eq!(options.synced_lyrics_only, Some(true));
+        assert_eq!(options.disable_music_video_skip, Some(true));
+        assert_eq!(options.synced_lyrics_format, Some(LyricsFormat::Ttml));
+        assert_eq!(options.single_disc_file_template.as_deref(), Some(SINGLE_DISC_TEMPLATE));
+        // Authentication is inherited from the album download.
+        assert_eq!(options.cookies_path.as_deref(), Some("/cookies.txt"));
+    }
+
+    /// Verifies that an existing lyrics file is never overwritten.
+    #[test]
+    fn sidecar_path_avoids_existing_lyrics() {
+        let dir = TestDir::new("mv-test");
+        let audio = dir.join("01 Song.m4a");
+        let lyrics = Path::new("/tmp/all/05 Song.ttml");
+
+        assert_eq!(sidecar_path(&audio, lyrics), Some(dir.join("01 Song.ttml")));
+
+        std::fs::write(dir.join("01 Song.ttml"), "").unwrap();
+        assert_eq!(
+            sidecar_path(&audio, lyrics),
+            Some(dir.join("01 Song [Music Video].ttml"))
+        );
+    }
+}
@@ -0,0 +1,466 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// playlist_sync_service.rs -- Incremental playlist downloads
+// ===========================================================
+//
+// Downloading a followed playlist again makes GAMDL re-check every track.
+// With `AppSettings::playlist_sync` on, `commands::gamdl::start_download()`
+// runs each request through `sync_request()` first, which:
+//
+//   1. Detects Apple Music catalog playlist URLs
+//      (`/{storefront}/playlist/{slug}/pl.{id}`).
+//   2. Lists the playlist's current tracks from the catalog API
+//      (`/v1/catalog/{storefront}/playlists/{id}/tracks`).
+//   3. Compares their catalog IDs with the ones already downloaded for
+//      that playlist (`diff_new_tracks()`) and queues only the new tracks,
+//      reporting the count ("7 new tracks") via the `playlist-sync` event.
+//
+// The first sync of a playlist downloads the playlist URL as usual.
+//
+// ## Seen-set
+//
+// The IDs downloaded per playlist are stored in
+// `{app_data_dir}/playlist_sync.json`, next to `queue.json`. They are
+// recorded when the sync's download completes (`record_batch()`), so a
+// failed or cancelled download is offered again on the next sync. Tracks
+// removed from the playlist stay in the seen-set: if they come back they
+// aren't downloaded a second time.
+//
+// The catalog API needs a MusicKit developer token (see
+// `artist_expansion_service::musickit_token()`). Without one, or if the
+// lookup fails, the playlist URL is downloaded in full as before.
+//
+// @see artist_expansion_service.rs -- same expansion flow for artist URLs
+// @see download_queue.rs -- records the batch when the download completes
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::{LazyLock, Mutex, PoisonError};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter};
+
+use crate::models::download::{DownloadKind, DownloadRequest};
+use crate::services::catalog_service::{self, CatalogResource};
+use crate::services::{artist_expansion_service, config_service};
+
+/// File (in the app data dir) holding the seen-sets.
+const STORE_FILE: &str = "playlist_sync.json";
+
+/// Serializes access to `STORE_FILE`. Downloads complete on their own
+/// tasks, so two syncs finishing together would otherwise both load the
+/// store, each add their batch, and the second save would drop the first
+/// batch. Readers take it too, so they never see a half-written file.
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Matches `https://music.apple.com/{storefront}/playlist/{slug}/pl.{id}`;
+/// the slug is optional. Library playlists (`p.{id}`) need a user token and
+/// aren't matched.
+static PLAYLIST_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^https?://music\.apple\.com/([a-z]{2})/playlist/(?:[^/?#]+/)?(pl\.[A-Za-z0-9.\-]+)")
+        .expect("Invalid playlist URL regex")
+});
+
+/// A parsed Apple Music catalog playlist URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistUrl {
+    /// Two-letter country code (e.g., "us", "gb")
+    pub storefront: String,
+    /// Catalog playlist identifier (e.g., "pl.f4d106fed2bd41149aaacabb233eb5eb")
+    pub playlist_id: String,
+}
+
+/// One track of a playlist, as listed by the catalog API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistTrack {
+    /// Catalog ID of the song or music video
+    pub id: String,
+    /// Its Apple Music page URL
+    pub url: String,
+}
+
+/// The catalog IDs a sync download covers. Attached to the queue item and
+/// added to the playlist's seen-set once the download completes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaylistSyncBatch {
+    /// Catalog playlist identifier
+    pub playlist_id: String,
+    /// IDs of the tracks being downloaded
+    pub track_ids: Vec<String>,
+}
+
+/// Seen-set of one playlist, as stored in `playlist_sync.json`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlaylistSyncEntry {
+    /// Catalog IDs of every track downloaded from this playlist
+    pub seen: BTreeSet<String>,
+    /// RFC 3339 timestamp of the last completed sync
+    pub last_synced: Option<String>,
+}
+
+/// Contents of `playlist_sync.json`, keyed by playlist ID.
+pub type PlaylistSyncStore = HashMap<String, PlaylistSyncEntry>;
+
+/// Returns the storefront and playlist ID if `url` is an Apple Music
+/// catalog playlist, or `None` for any other URL.
+pub fn parse_playlist_url(url: &str) -> Option<PlaylistUrl> {
+    PLAYLIST_URL_REGEX
+        .captures(url.trim())
+        .map(|caps| PlaylistUrl {
+            storefront: caps[1].to_string(),
+            playlist_id: caps[2].to_string(),
+        })
+}
+
+/// Returns the tracks of `current` whose IDs aren't in `seen`, in playlist
+/// order and without duplicates.
+///
+/// Only additions count: IDs in `seen` that are no longer in `current`
+/// (tracks removed from the playlist) are ignored, so a playlist that
+/// shrank yields nothing to download.
+pub fn diff_new_tracks(seen: &BTreeSet<String>, current: &[PlaylistTrack]) -> Vec<PlaylistTrack> {
+    let mut queued = HashSet::new();
+    current
+        .iter()
+        .filter(|track| !seen.contains(&track.id) && queued.insert(track.id.as_str()))
+        .cloned()
+        .collect()
+}
+
+/// Adds a completed batch to the store, keeping the IDs already there.
+pub fn merge_batch(store: &mut PlaylistSyncStore, batch: &PlaylistSyncBatch, synced_at: &str) {
+    let entry = store.entry(batch.playlist_id.clone()).or_default();
+    entry.seen.extend(batch.track_ids.iter().cloned());
+    entry.last_synced = Some(synced_at.to_string());
+}
+
+/// Turns the playlist URLs of `request` into sync requests.
+///
+/// Each playlist is split into a request of its own, paired with the batch
+/// to record when it completes:
+///   - first sync: the playlist URL itself, covering every current track;
+///   - later syncs: the URLs of the new tracks only;
+///   - no new tracks: no request at all.
+///
+/// Non-playlist URLs, and playlists that can't be listed (no MusicKit
+/// credentials, API error), stay in the original request without a batch.
+pub async fn sync_request(
+    app: &AppHandle,
+    request: DownloadRequest,
+) -> Vec<(DownloadRequest, Option<PlaylistSyncBatch>)> {
+    let playlists: Vec<(String, PlaylistUrl)> = request
+        .urls
+        .iter()
+        .filter_map(|u| parse_playlist_url(u).map(|p| (u.clone(), p)))
+        .collect();
+    if playlists.is_empty() {
+        return vec![(request, None)];
+    }
+
+    let token = match artist_expansion_service::musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            log::info!("Not syncing playlist URL(s): {}", e);
+            return vec![(request, None)];
+        }
+    };
+
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Not syncing playlist URL(s): {}", e);
+            return vec![(request, None)];
+        }
+    };
+
+    let store = {
+        let _guard = STORE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        load_store(app)
+    };
+    let mut synced_urls = Vec::new();
+    let mut requests = Vec::new();
+    for (url, playlist) in &playlists {
+        let current = match fetch_playlist_tracks(&client, &token, playlist).await {
+            Ok(tracks) => tracks,
+            Err(e) => {
+                log::warn!("Could not list tracks of playlist {}: {}", playlist.playlist_id, e);
+                continue;
+            }
+        };
+        synced_urls.push(url.clone());
+
+        let (urls, new_tracks) = match store.get(&playlist.playlist_id) {
+            None => (vec![url.clone()], diff_new_tracks(&BTreeSet::new(), &current)),
+            Some(entry) => {
+                let new_tracks = diff_new_tracks(&entry.seen, &current);
+                (new_tracks.iter().map(|t| t.url.clone()).collect(), new_tracks)
+            }
+        };
+
+        log::info!(
+            "Playlist {}: {} new track(s) of {}",
+            playlist.playlist_id,
+            new_tracks.len(),
+            current.len()
+        );
+        let _ = app.emit(
+            "playlist-sync",
+            serde_json::json!({
+                "url": url,
+                "new_tracks": new_tracks.len(),
+                "total_tracks": current.len(),
+                "message": format!("{} new tracks", new_tracks.len()),
+            }),
+        );
+
+        if !new_tracks.is_empty() {
+            let batch = PlaylistSyncBatch {
+                playlist_id: playlist.playlist_id.clone(),
+                track_ids: new_tracks.into_iter().map(|t| t.id).collect(),
+            };
+            requests.push((
+                DownloadRequest {
+                    urls,
+                    options: request.options.clone(),
+                    label: request.label.clone(),
+                    priority: request.priority,
+                    fallback_chain_override: request.fallback_chain_override.clone(),
+                    // Song URLs, but still a playlist: not filed as singles
+                    kind: Some(DownloadKind::Playlist),
+                },
+                Some(batch),
+            ));
+        }
+    }
+
+    let remaining: Vec<String> = request
+        .urls
+        .iter()
+        .filter(|u| !synced_urls.contains(u))
+        .cloned()
+        .collect();
+    if !remaining.is_empty() {
+        requests.insert(
+            0,
+            (
+                DownloadRequest {
+                    urls: remaining,
+                    ..request
+                },
+                None,
+            ),
+        );
+    }
+    requests
+}
+
+/// Adds a completed download's tracks to its playlist's seen-set and
+/// saves the store, holding `STORE_LOCK` from the load to the save.
+pub fn record_batch(app: &AppHandle, batch: &PlaylistSyncBatch) {
+    let _guard = STORE_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut store = load_store(app);
+    merge_batch(&mut store, batch, &chrono::Utc::now().to_rfc3339());
+    let path = crate::utils::platform::get_app_data_dir(app).join(STORE_FILE);
+    match serde_json::to_string_pretty(&store) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                log::warn!("Failed to save playlist sync state: {}", e);
+            }
+        }
+        Err(e) => log::warn!("Failed to serialize playlist sync state: {}", e),
+    }
+}
+
+/// Loads `playlist_sync.json`. A missing or unreadable file is an empty
+/// store: every playlist then gets a full first sync. Callers hold
+/// `STORE_LOCK`.
+fn load_store(app: &AppHandle) -> PlaylistSyncStore {
+    let path = crate::utils::platform::get_app_data_dir(app).join(STORE_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+            log::warn!("Failed to parse {}: {}", STORE_FILE, e);
+            PlaylistSyncStore::new()
+        }),
+        Err(_) => PlaylistSyncStore::new(),
+    }
+}
+
+/// Lists the tracks of a playlist, following the API's `next` links until
+/// every page has been read (see `catalog_service::fetch_catalog_pages`).
+async fn fetch_playlist_tracks(
+    client: &reqwest::Client,
+    token: &str,
+    playlist: &PlaylistUrl,
+) -> Result<Vec<PlaylistTrack>, String> {
+    let resource = CatalogResource::Playlist {
+        storefront: playlist.storefront.clone(),
+        id: playlist.playlist_id.clone(),
+    };
+    let pages = catalog_service::fetch_catalog_pages(client, token, &resource).await?;
+    Ok(pages.iter().flat_map(parse_track_page).collect())
+}
+
+/// Extracts the tracks from one page of a playlist's tracks relationship:
+///
+/// ```text
+/// { "data": [ { "id": "1440935808",
+///               "attributes": { "url": "https://music.apple.com/..." } } ],
+///   "next": "/v1/catalog/us/playlists/pl.x/tracks?offset=100" }
+/// ```
+///
+/// Entries without an ID or URL (unavailable tracks) are skipped.
+fn parse_track_page(json: &serde_json::Value) -> Vec<PlaylistTrack> {
+    json.get("data")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|t| {
+                    Some(PlaylistTrack {
+                        id: t.get("id")?.as_str()?.to_string(),
+                        url: t.pointer("/attributes/url")?.as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracks(ids: &[&str]) -> Vec<PlaylistTrack> {
+        ids.iter()
+            .map(|id| PlaylistTrack {
+                id: id.to_string(),
+                url: format!("https://music.apple.com/us/song/s/{}", id),
+            })
+            .collect()
+    }
+
+    fn seen(ids: &[&str]) -> BTreeSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    fn ids(tracks: &[PlaylistTrack]) -> Vec<&str> {
+        tracks.iter().map(|t| t.id.as_str()).collect()
+    }
+
+    // ----------------------------------------------------------
+    // parse_playlist_url()
+    // ----------------------------------------------------------
+
+    /// Verifies that catalog playlists are detected, with or without a slug.
+    #[test]
+    fn detects_playlist_urls() {
+        let expected = PlaylistUrl {
+            storefront: "us".to_string(),
+            playlist_id: "pl.f4d106fed2bd41149aaacabb233eb5eb".to_string(),
+        };
+        assert_eq!(
+            parse_playlist_url(
+                "https://music.apple.com/us/playlist/todays-hits/pl.f4d106fed2bd41149aaacabb233eb5eb"
+            ),
+            Some(expected.clone())
+        );
+        assert_eq!(
+            parse_playlist_url("https://music.apple.com/us/playlist/pl.f4d106fed2bd41149aaacabb233eb5eb?l=en"),
+            Some(expected)
+        );
+    }
+
+    /// Verifies that albums, songs and library playlists aren't matched.
+    #[test]
+    fn ignores_non_catalog_playlists() {
+        assert_eq!(parse_playlist_url("https://music.apple.com/us/album/1989/1440935467"), None);
+        assert_eq!(parse_playlist_url("https://music.apple.com/library/playlist/p.abc123"), None);
+        assert_eq!(parse_playlist_url("https://music.youtube.com/playlist?list=PL1"), None);
+    }
+
+    // ----------------------------------------------------------
+    // diff_new_tracks()
+    // ----------------------------------------------------------
+
+    /// Verifies that only tracks added since the last sync are returned,
+    /// in playlist order.
+    #[test]
+    fn returns_only_added_tracks() {
+        let new = diff_new_tracks(&seen(&["1", "2", "3"]), &tracks(&["4", "1", "2", "5", "3"]));
+        assert_eq!(ids(&new), vec!["4", "5"]);
+    }
+
+    /// Verifies that a playlist that shrank yields nothing, and removed
+    /// tracks are not re-added.
+    #[test]
+    fn shrunk_playlist_yields_nothing() {
+        let new = diff_new_tracks(&seen(&["1", "2", "3"]), &tracks(&["1", "3"]));
+        assert!(new.is_empty());
+    }
+
+    /// Verifies that removals and additions in the same sync only add.
+    #[test]
+    fn additions_and_removals_together() {
+        let new = diff_new_tracks(&seen(&["1", "2", "3"]), &tracks(&["2", "6", "7"]));
+        assert_eq!(ids(&new), vec!["6", "7"]);
+    }
+
+    /// Verifies that an empty seen-set returns every track once, even when
+    /// the playlist lists a track twice.
+    #[test]
+    fn empty_seen_set_returns_all_tracks_once() {
+        let new = diff_new_tracks(&BTreeSet::new(), &tracks(&["1", "2", "1"]));
+        assert_eq!(ids(&new), vec!["1", "2"]);
+    }
+
+    // ----------------------------------------------------------
+    // merge_batch()
+    // ----------------------------------------------------------
+
+    /// Verifies that a batch is added to the seen-set without dropping IDs
+    /// of tracks that were removed from the playlist since.
+    #[test]
+    fn merge_keeps_previous_ids() {
+        let mut store = PlaylistSyncStore::new();
+        let batch = |track_ids: &[&str]| PlaylistSyncBatch {
+            playlist_id: "pl.x".to_string(),
+            track_ids: track_ids.iter().map(|id| id.to_string()).collect(),
+        };
+        merge_batch(&mut store, &batch(&["1", "2"]), "2026-01-01T00:00:00Z");
+        merge_batch(&mut store, &batch(&["3"]), "2026-02-01T00:00:00Z");
+
+        let entry = &store["pl.x"];
+        assert_eq!(entry.seen, seen(&["1", "2", "3"]));
+        assert_eq!(entry.last_synced.as_deref(), Some("2026-02-01T00:00:00Z"));
+
+        // A removed track that comes back is not downloaded again
+        assert!(diff_new_tracks(&entry.seen, &tracks(&["3", "1"])).is_empty());
+    }
+
+    // ----------------------------------------------------------
+    // parse_track_page()
+    // ----------------------------------------------------------
+
+    /// Verifies that track IDs and URLs are read from a page, skipping
+    /// entries without a URL.
+    #[test]
+    fn parses_track_page() {
+        let json = serde_json::json!({
+            "data": [
+                { "id": "11", "attributes": { "url": "https://music.apple.com/us/album/a/1?i=11" } },
+                { "id": "12", "attributes": { "name": "Unavailable" } },
+                { "id": "13", "attributes": { "url": "https://music.apple.com/us/music-video/v/13" } }
+            ],
+            "next": "/v1/catalog/us/playlists/pl.x/tracks?offset=100"
+        });
+
+        let page = parse_track_page(&json);
+        assert_eq!(ids(&page), vec!["11", "13"]);
+        assert_eq!(page[0].url, "https://music.apple.com/us/album/a/1?i=11");
+    }
+}
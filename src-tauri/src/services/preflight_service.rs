@@ -0,0 +1,471 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// preflight_service.rs -- Toolchain health check before a download
+// =================================================================
+//
+// Most failures that surface halfway through an album have a cause that
+// was already there before GAMDL started: expired cookies, a missing
+// FFmpeg, an output folder on an unplugged drive. `run()` checks all of it
+// up front and returns one `PreflightCheck` per item, each with a hint on
+// how to fix it:
+//
+//   | Check         | Fails when                                            |
+//   |---------------|-------------------------------------------------------|
+//   | `cookies`     | No cookies file, file missing, no `media-user-token`, |
+//   |               | or the token has expired (skipped with the wrapper    |
+//   |               | or the user's own GAMDL config file)                  |
+//   | `python`      | The portable Python runtime is missing or won't run  |
+//   | `gamdl`       | GAMDL isn't installed in that runtime                 |
+//   | `ffmpeg`      | No FFmpeg at the configured path, the managed install |
+//   |               | or on the PATH                                        |
+//   | `mp4decrypt`  | Needed (codec or music video) but not found           |
+//   | `output_path` | The output folder can't be created or written to      |
+//   | `urls`        | A URL isn't an Apple Music link                       |
+//
+// The checks run on the options the download will actually use
+// (`download_queue::merge_options()` of the request over the settings), so
+// a per-download codec or output folder is what gets checked. Tools are
+// looked up the way the download finds them (`gamdl_service::tool_available()`).
+//
+// Used by the `preflight` command and, with `AppSettings::preflight_checks`
+// on, by `start_download` before anything is queued.
+
+use std::path::Path;
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::models::gamdl_options::{GamdlOptions, SongCodec};
+use crate::models::music_service::MusicServiceId;
+use crate::services::{gamdl_service, python_manager};
+
+/// Cookie GAMDL authenticates with; without it every download fails.
+const AUTH_COOKIE_NAME: &str = "media-user-token";
+
+/// Prefix curl and browser exporters put before HttpOnly cookie lines.
+/// Such lines look like comments but hold real cookies.
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+/// Result of one pre-flight check, serialized to the frontend.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PreflightCheck {
+    /// Stable identifier (e.g., `"cookies"`, `"ffmpeg"`)
+    pub id: String,
+    /// Whether the check passed
+    pub passed: bool,
+    /// What was found, in words
+    pub message: String,
+    /// How to fix a failed check; `None` when it passed
+    pub hint: Option<String>,
+}
+
+impl PreflightCheck {
+    fn pass(id: &str, message: impl Into<String>) -> Self {
+        Self {
+            id: id.to_string(),
+            passed: true,
+            message: message.into(),
+            hint: None,
+        }
+    }
+
+    fn fail(id: &str, message: impl Into<String>, hint: &str) -> Self {
+        Self {
+            id: id.to_string(),
+            passed: false,
+            message: message.into(),
+            hint: Some(hint.to_string()),
+        }
+    }
+}
+
+/// All pre-flight checks for a download.
+#[derive(Debug, Clone, Serialize)]
+pub struct PreflightReport {
+    /// Whether every check passed
+    pub passed: bool,
+    /// Results in the order they were run
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    fn new(checks: Vec<PreflightCheck>) -> Self {
+        Self {
+            passed: checks.iter().all(|c| c.passed),
+            checks,
+        }
+    }
+
+    /// Failed checks as one message, for `start_download`'s error.
+    /// `None` when everything passed.
+    pub fn failure_summary(&self) -> Option<String> {
+        let failed: Vec<String> = self
+            .checks
+            .iter()
+            .filter(|c| !c.passed)
+            .map(|c| match &c.hint {
+                Some(hint) => format!("{} ({})", c.message, hint),
+                None => c.message.clone(),
+            })
+            .collect();
+        (!failed.is_empty()).then(|| format!("Pre-flight check failed: {}", failed.join("; ")))
+    }
+}
+
+/// Runs every pre-flight check for downloading `urls` with `options`,
+/// the request's options merged over the settings.
+pub async fn run(app: &AppHandle, options: &GamdlOptions, urls: &[String]) -> PreflightReport {
+    let mut checks = Vec::new();
+    if needs_cookies(options) {
+        let now = chrono::Utc::now().timestamp();
+        checks.push(check_cookies(options.cookies_path.as_deref(), now));
+    }
+
+    let python = python_manager::check_python_status(app).await.unwrap_or(None);
+    let python_ok = python.is_some();
+    checks.push(check_python(python));
+    let gamdl = if python_ok {
+        gamdl_service::get_gamdl_version(app).await.unwrap_or(None)
+    } else {
+        None
+    };
+    checks.push(check_gamdl(gamdl, python_ok));
+
+    let ffmpeg =
+        gamdl_service::tool_available(app, options.ffmpeg_path.as_deref(), "ffmpeg", "ffmpeg");
+    checks.push(check_tool("ffmpeg", "FFmpeg", ffmpeg));
+    let mp4decrypt_needed = options
+        .song_codec
+        .as_ref()
+        .is_none_or(|codec| needs_mp4decrypt(codec, urls));
+    if mp4decrypt_needed {
+        let mp4decrypt = gamdl_service::tool_available(
+            app,
+            options.mp4decrypt_path.as_deref(),
+            "mp4decrypt",
+            "mp4decrypt",
+        );
+        checks.push(check_tool("mp4decrypt", "mp4decrypt", mp4decrypt));
+    }
+
+    match options.output_path.as_deref().filter(|p| !p.is_empty()) {
+        Some(dir) => checks.push(check_output_path(Path::new(dir))),
+        None => checks.push(PreflightCheck::fail(
+            "output_path",
+            "No output folder could be determined",
+            "Choose an output folder in Settings > General",
+        )),
+    }
+
+    checks.push(check_urls(urls));
+    PreflightReport::new(checks)
+}
+
+/// Returns whether the download needs the cookies file: it does unless
+/// the wrapper is on, which signs in with its own account, or GAMDL reads
+/// the user's own config file, whose `config.ini` may set the cookies path
+/// (as in `gamdl_service::check_cookies_config()`).
+pub fn needs_cookies(options: &GamdlOptions) -> bool {
+    options.use_wrapper != Some(true) && options.use_user_config != Some(true)
+}
+
+/// Checks the cookies file GAMDL will use: it exists and holds an
+/// unexpired `media-user-token`. `now` is a Unix timestamp.
+pub fn check_cookies(path: Option<&str>, now: i64) -> PreflightCheck {
+    const HINT: &str = "Sign in to Apple Music or import cookies in Settings > Cookies";
+    let Some(path) = path.filter(|p| !p.trim().is_empty()) else {
+        return PreflightCheck::fail("cookies", "No cookies file configured", HINT);
+    };
+    let contents = match std::fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            return PreflightCheck::fail(
+                "cookies",
+                format!("Cookies file {} can't be read: {}", path, e),
+                HINT,
+            )
+        }
+    };
+
+    match auth_cookie_expiry(&contents) {
+        None => PreflightCheck::fail(
+            "cookies",
+            format!("No {} cookie in {}", AUTH_COOKIE_NAME, path),
+            HINT,
+        ),
+        Some(expiry) if expiry > 0 && expiry < now => PreflightCheck::fail(
+            "cookies",
+            format!("The {} cookie has expired", AUTH_COOKIE_NAME),
+            HINT,
+        ),
+        Some(_) => PreflightCheck::pass("cookies", format!("{} found", AUTH_COOKIE_NAME)),
+    }
+}
+
+/// Returns the expiry (Unix timestamp, 0 for a session cookie) of the
+/// `media-user-token` cookie in a Netscape cookies file, or `None` if the
+/// file has none. With several, the latest expiry wins.
+fn auth_cookie_expiry(contents: &str) -> Option<i64> {
+    contents
+        .lines()
+        .map(|line| line.trim().trim_start_matches(HTTP_ONLY_PREFIX))
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split('\t').collect();
+            (fields.len() >= 7 && fields[5] == AUTH_COOKIE_NAME)
+                .then(|| fields[4].parse::<i64>().unwrap_or(0))
+        })
+        .max_by_key(|&expiry| if expiry == 0 { i64::MAX } else { expiry })
+}
+
+/// Checks that the portable Python runtime runs (its version, if it does).
+pub fn check_python(version: Option<String>) -> PreflightCheck {
+    match version {
+        Some(version) => PreflightCheck::pass("python", format!("Python {}", version)),
+        None => PreflightCheck::fail(
+            "python",
+            "Python runtime is missing or doesn't run",
+            "Reinstall Python from the setup wizard",
+        ),
+    }
+}
+
+/// Checks that GAMDL is installed (its version, if it is).
+pub fn check_gamdl(version: Option<String>, python_ok: bool) -> PreflightCheck {
+    match version {
+        Some(version) => PreflightCheck::pass("gamdl", format!("GAMDL {}", version)),
+        None if !python_ok => PreflightCheck::fail(
+            "gamdl",
+            "GAMDL can't run without Python",
+            "Reinstall Python, then GAMDL, from the setup wizard",
+        ),
+        None => PreflightCheck::fail(
+            "gamdl",
+            "GAMDL is not installed",
+            "Install GAMDL from the setup wizard",
+        ),
+    }
+}
+
+/// Checks that a tool was found (at its configured path, the managed
+/// install or on the PATH; see `gamdl_service::tool_available()`).
+pub fn check_tool(id: &str, name: &str, available: bool) -> PreflightCheck {
+    if available {
+        PreflightCheck::pass(id, format!("{} found", name))
+    } else {
+        PreflightCheck::fail(
+            id,
+            format!("{} not found", name),
+            "Install it from the setup wizard, or fix its path in Settings > Paths",
+        )
+    }
+}
+
+/// Checks that files can be written to `dir`, creating it if needed.
+/// Writes and removes a small probe file.
+pub fn check_output_path(dir: &Path) -> PreflightCheck {
+    const HINT: &str = "Choose a writable output folder in Settings > General";
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        return PreflightCheck::fail(
+            "output_path",
+            format!("Output folder {} can't be created: {}", dir.display(), e),
+            HINT,
+        );
+    }
+    let probe = dir.join(format!(".meedyadl-write-test-{}", uuid::Uuid::new_v4()));
+    match std::fs::write(&probe, b"") {
+        Ok(()) => {
+            let _ = std::fs::remove_file(&probe);
+            PreflightCheck::pass("output_path", format!("{} is writable", dir.display()))
+        }
+        Err(e) => PreflightCheck::fail(
+            "output_path",
+            format!("Output folder {} isn't writable: {}", dir.display(), e),
+            HINT,
+        ),
+    }
+}
+
+/// Checks that every URL is an Apple Music link.
+pub fn check_urls(urls: &[String]) -> PreflightCheck {
+    const HINT: &str = "Paste a music.apple.com album, song, playlist or artist link";
+    if urls.is_empty() {
+        return PreflightCheck::fail("urls", "No URLs given", HINT);
+    }
+    let invalid: Vec<&str> = urls
+        .iter()
+        .map(|u| u.trim())
+        .filter(|u| {
+            !matches!(url::Url::parse(u), Ok(parsed) if matches!(parsed.scheme(), "http" | "https"))
+                || MusicServiceId::from_url(u) != Some(MusicServiceId::AppleMusic)
+        })
+        .collect();
+    if invalid.is_empty() {
+        PreflightCheck::pass("urls", format!("{} Apple Music URL(s)", urls.len()))
+    } else {
+        PreflightCheck::fail(
+            "urls",
+            format!("Not an Apple Music URL: {}", invalid.join(", ")),
+            HINT,
+        )
+    }
+}
+
+/// Returns whether the download needs mp4decrypt: music videos always do,
+/// and so does every song codec except the legacy AAC ones, which GAMDL
+/// decrypts without it.
+pub fn needs_mp4decrypt(codec: &SongCodec, urls: &[String]) -> bool {
+    !matches!(codec, SongCodec::AacLegacy | SongCodec::AacHeLegacy)
+        || urls.iter().any(|u| u.contains("/music-video/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    use crate::models::download::DownloadKind;
+    use crate::models::settings::AppSettings;
+    use crate::services::download_queue::merge_options;
+    use crate::utils::test_dir::TestDir;
+
+    const NOW: i64 = 1_800_000_000;
+
+    /// Writes a cookies file into `dir` with one `media-user-token` line
+    /// expiring at `expiry` (or none) and returns its path.
+    fn cookies_file(dir: &Path, expiry: Option<i64>) -> PathBuf {
+        let path = dir.join(format!("cookies-{}.txt", expiry.unwrap_or_default()));
+        let mut contents = String::from(
+            "# Netscape HTTP Cookie File\n.apple.com\tTRUE\t/\tTRUE\t1900000000\tmyacinfo\tabc\n",
+        );
+        if let Some(expiry) = expiry {
+            contents.push_str(&format!(
+                "#HttpOnly_.music.apple.com\tTRUE\t/\tTRUE\t{}\tmedia-user-token\ttoken\n",
+                expiry
+            ));
+        }
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Verifies that an unexpired media-user-token passes, including on a
+    /// `#HttpOnly_` line.
+    #[test]
+    fn cookies_with_valid_token_pass() {
+        let dir = TestDir::new("cookies");
+        let path = cookies_file(&dir, Some(NOW + 86_400));
+        let check = check_cookies(path.to_str(), NOW);
+        assert!(check.passed, "{:?}", check);
+    }
+
+    /// Verifies that no configured file, an unreadable path, a missing
+    /// token and an expired token each fail with a hint.
+    #[test]
+    fn cookies_failures_are_reported() {
+        assert!(!check_cookies(None, NOW).passed);
+        assert!(!check_cookies(Some("/nonexistent/cookies.txt"), NOW).passed);
+
+        let dir = TestDir::new("cookies");
+        let without_token = cookies_file(&dir, None);
+        let check = check_cookies(without_token.to_str(), NOW);
+        assert!(!check.passed);
+        assert!(check.message.contains("media-user-token"), "{}", check.message);
+
+        let expired = cookies_file(&dir, Some(NOW - 1));
+        let check = check_cookies(expired.to_str(), NOW);
+        assert!(!check.passed);
+        assert!(check.message.contains("expired"), "{}", check.message);
+        assert!(check.hint.is_some());
+    }
+
+    /// Verifies the Python and GAMDL checks, including GAMDL's message when
+    /// Python itself is broken.
+    #[test]
+    fn python_and_gamdl_checks() {
+        assert!(check_python(Some("3.12.8".to_string())).passed);
+        assert!(!check_python(None).passed);
+
+        assert!(check_gamdl(Some("2.8.4".to_string()), true).passed);
+        assert!(check_gamdl(None, true).message.contains("not installed"));
+        assert!(check_gamdl(None, false).message.contains("Python"));
+    }
+
+    /// Verifies that a missing tool binary fails.
+    #[test]
+    fn missing_tool_fails() {
+        let check = check_tool("ffmpeg", "FFmpeg", false);
+        assert!(!check.passed);
+        assert_eq!(check.id, "ffmpeg");
+        assert!(check_tool("ffmpeg", "FFmpeg", true).passed);
+    }
+
+    /// Verifies that mp4decrypt is only required for non-legacy codecs and
+    /// music videos.
+    #[test]
+    fn mp4decrypt_requirement_follows_codec_and_urls() {
+        let album = vec!["https://music.apple.com/us/album/x/1".to_string()];
+        let video = vec!["https://music.apple.com/us/music-video/y/2".to_string()];
+        assert!(needs_mp4decrypt(&SongCodec::Alac, &album));
+        assert!(!needs_mp4decrypt(&SongCodec::AacLegacy, &album));
+        assert!(needs_mp4decrypt(&SongCodec::AacLegacy, &video));
+    }
+
+    /// Verifies that the cookies file is only checked with the wrapper off
+    /// and MeedyaDL's synced GAMDL config in use.
+    #[test]
+    fn cookies_requirement_follows_wrapper_and_config_file() {
+        let merged = |settings: &AppSettings| merge_options(None, settings, DownloadKind::Album);
+        let mut settings = AppSettings::default();
+        assert!(needs_cookies(&merged(&settings)));
+        settings.use_wrapper = true;
+        assert!(!needs_cookies(&merged(&settings)));
+        settings.use_wrapper = false;
+        settings.use_gamdl_config_file = true;
+        assert!(!needs_cookies(&merged(&settings)));
+    }
+
+    /// Verifies that a writable folder passes (and is created) and that a
+    /// path under a regular file fails.
+    #[test]
+    fn output_path_writability() {
+        let dir = TestDir::new("preflight");
+        let check = check_output_path(&dir.join("music"));
+        assert!(check.passed, "{:?}", check);
+
+        let file = dir.join("not-a-dir");
+        std::fs::write(&file, b"").unwrap();
+        let check = check_output_path(&file.join("music"));
+        assert!(!check.passed);
+        assert_eq!(check.id, "output_path");
+    }
+
+    /// Verifies that non-Apple Music URLs and an empty list fail.
+    #[test]
+    fn url_check_rejects_other_services() {
+        assert!(check_urls(&["https://music.apple.com/us/album/x/1".to_string()]).passed);
+        let check = check_urls(&["https://open.spotify.com/album/1".to_string()]);
+        assert!(!check.passed);
+        assert!(check.message.contains("spotify"), "{}", check.message);
+        assert!(!check_urls(&["not a url".to_string()]).passed);
+        assert!(!check_urls(&[]).passed);
+    }
+
+    /// Verifies that the report fails if any check does and summarizes the
+    /// failures with their hints.
+    #[test]
+    fn report_summarizes_failures() {
+        let report = PreflightReport::new(vec![
+            check_python(Some("3.12.8".to_string())),
+            check_gamdl(None, true),
+        ]);
+        assert!(!report.passed);
+        let summary = report.failure_summary().unwrap();
+        assert!(summary.contains("GAMDL is not installed"), "{}", summary);
+        assert!(summary.contains("setup wizard"), "{}", summary);
+
+        assert!(PreflightReport::new(vec![check_python(Some("3".to_string()))])
+            .failure_summary()
+            .is_none());
+    }
+}
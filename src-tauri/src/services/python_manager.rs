@@ -35,6 +35,9 @@ use tauri::AppHandle;
 // `archive` provides download_and_extract() and set_executable() helpers.
 // `platform` provides cross-platform path resolution for Python directories and binaries.
 use crate::utils::{archive, platform};
+// `config_service` provides the user's proxy settings for the runtime download.
+// `dependency_manager` provides the install progress events of the download.
+use crate::services::{config_service, dependency_manager};
 
 // ============================================================
 // Python version constants
@@ -169,7 +172,16 @@ pub async fn install_python(app: &AppHandle) -> Result<String, String> {
     // and flate2/tar to extract the tar.gz archive.
     // Ref: https://docs.rs/reqwest/latest/reqwest/ (HTTP streaming download)
     log::info!("Downloading and extracting Python to {}", app_data_dir.display());
-    archive::download_and_extract(&url, &app_data_dir, archive::ArchiveFormat::TarGz).await?;
+    let proxy = config_service::load_proxy_config(app);
+    let progress = dependency_manager::progress_events(app, "python");
+    archive::download_and_extract(
+        &url,
+        &app_data_dir,
+        archive::ArchiveFormat::TarGz,
+        &proxy,
+        Some(progress),
+    )
+    .await?;
 
     // Step 5: Verify the installation by checking the binary exists
     let python_bin = platform::get_python_binary_path(&python_dir);
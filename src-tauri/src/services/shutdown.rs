@@ -0,0 +1,146 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// shutdown.rs -- Graceful shutdown of the download queue
+// ========================================================
+//
+// However the app is asked to quit (tray "Quit", closing the last window,
+// auto-quit, Ctrl+C or SIGTERM), the exit is held back until the queue is
+// in a state worth resuming from:
+//
+//   1. `DownloadQueue::begin_shutdown()` stops new downloads from starting
+//      and makes every running download kill its GAMDL process tree
+//      (including FFmpeg and N_m3u8DL-RE) at its next poll.
+//   2. Background tasks (animated artwork, companion downloads, FLAC
+//      conversion) are aborted; their processes use `kill_on_drop`.
+//   3. Up to `DRAIN_TIMEOUT` is spent waiting for the running downloads to
+//      release their slots.
+//   4. Active items are demoted to Queued and `queue.json` is written one
+//      last time (flushed, without the save debounce), so they restart on
+//      the next launch.
+//   5. The app exits with code 0.
+//
+// A second quit request while this runs is swallowed; a second Ctrl+C or
+// SIGTERM exits immediately.
+//
+// @see download_queue.rs -- DownloadQueue::begin_shutdown() and
+//      DownloadQueue::demote_active_for_shutdown()
+// @see lib.rs -- RunEvent::ExitRequested hook
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use tauri::{AppHandle, ExitRequestApi, Manager};
+
+use crate::services::download_queue::{self, QueueHandle};
+
+/// Longest time to wait for running downloads to stop before saving the
+/// queue and exiting anyway.
+pub const DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the drain wait checks the queue's active count.
+const DRAIN_POLL: Duration = Duration::from_millis(100);
+
+/// Set once the shutdown sequence has started.
+static SHUTDOWN_STARTED: AtomicBool = AtomicBool::new(false);
+
+/// Set once the shutdown sequence has finished and the exit may proceed.
+static SHUTDOWN_FINISHED: AtomicBool = AtomicBool::new(false);
+
+/// Handles `RunEvent::ExitRequested`. The first request is held back with
+/// `prevent_exit()` while `shutdown()` runs in the background, which then
+/// calls `app.exit(0)` again; that second request is let through.
+pub fn on_exit_requested(app: &AppHandle, api: &ExitRequestApi) {
+    if SHUTDOWN_FINISHED.load(Ordering::SeqCst) {
+        return;
+    }
+    api.prevent_exit();
+    if SHUTDOWN_STARTED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let app = app.clone();
+    let queue = app.state::<QueueHandle>().inner().clone();
+    tauri::async_runtime::spawn(async move {
+        shutdown(&app, &queue).await;
+        SHUTDOWN_FINISHED.store(true, Ordering::SeqCst);
+        app.exit(0);
+    });
+}
+
+/// Stops the queue and saves it (steps 1-4 above).
+async fn shutdown(app: &AppHandle, queue: &QueueHandle) {
+    log::info!("Shutting down: stopping downloads and saving the queue");
+    queue.lock().await.begin_shutdown();
+
+    let tasks = queue.lock().await.take_background_tasks();
+    for task in &tasks {
+        task.abort();
+    }
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    let deadline = tokio::time::Instant::now() + DRAIN_TIMEOUT;
+    loop {
+        let active = queue.lock().await.active_count();
+        if active == 0 {
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            log::warn!("{} download(s) still stopping at shutdown, saving anyway", active);
+            break;
+        }
+        tokio::time::sleep(DRAIN_POLL).await;
+    }
+
+    let demoted = queue.lock().await.demote_active_for_shutdown();
+    download_queue::flush_queue_to_disk(app, queue).await;
+    log::info!("Queue saved, {} interrupted download(s) will resume on next launch", demoted);
+}
+
+/// Starts the task that turns Ctrl+C (and SIGTERM on macOS/Linux) into a
+/// normal quit, so the shutdown sequence runs instead of the process
+/// dying with its downloads. A second signal exits immediately.
+pub fn spawn_signal_listener(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut received = false;
+        loop {
+            if !wait_for_signal().await {
+                return;
+            }
+            if received {
+                log::warn!("Second termination signal, exiting without saving the queue");
+                std::process::exit(1);
+            }
+            received = true;
+            log::info!("Termination signal received, quitting");
+            app.exit(0);
+        }
+    });
+}
+
+/// Waits for Ctrl+C or SIGTERM. Returns `false` if the handlers can't be
+/// installed.
+async fn wait_for_signal() -> bool {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut term = match signal(SignalKind::terminate()) {
+            Ok(term) => term,
+            Err(e) => {
+                log::warn!("Failed to listen for SIGTERM: {}", e);
+                return tokio::signal::ctrl_c().await.is_ok();
+            }
+        };
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.is_ok(),
+            _ = term.recv() => true,
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await.is_ok()
+    }
+}
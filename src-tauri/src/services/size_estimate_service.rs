@@ -0,0 +1,359 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// size_estimate_service.rs -- Download size estimate before queueing
+// ==================================================================
+//
+// `commands::gamdl::estimate_size()` tells the user roughly how much disk a
+// download will take before it is queued. `estimate()`:
+//
+//   1. Resolves each URL (album, song, catalog playlist) to its tracks and
+//      their durations via the Apple Music catalog API.
+//   2. Multiplies each duration by the average bitrate of the chosen
+//      `SongCodec` (`codec_bitrate()`).
+//
+// ## Accuracy
+//
+// This is an estimate from durations and average bitrates, not a HEAD
+// request per stream:
+//
+//   - AAC variants are constant-bitrate streams; expect the total within
+//     a few percent (container overhead and the embedded cover add a
+//     little per track).
+//   - ALAC and Dolby Atmos vary widely: ALAC with how well the music
+//     compresses and with the master's resolution (16-bit/44.1 kHz up to
+//     24-bit/192 kHz), Atmos with the stream's bitrate (448 or 768 kbps).
+//     For those the estimate also carries a `range` from the lowest to
+//     the highest plausible size.
+//   - Tracks that fall back to another codec during the download aren't
+//     accounted for.
+//
+// @see catalog_service.rs -- parse_catalog_url(), fetch_catalog_pages()
+// @see artist_expansion_service.rs -- musickit_token(), the catalog credentials
+// @see https://developer.apple.com/documentation/applemusicapi/songs/attributes-data.dictionary
+
+use serde::Serialize;
+use tauri::AppHandle;
+
+use crate::models::gamdl_options::SongCodec;
+use crate::services::catalog_service;
+use crate::services::{artist_expansion_service, config_service};
+
+/// Average bitrates of a codec's streams, in kbit/s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CodecBitrate {
+    /// Bitrate used for `estimated_bytes`
+    pub typical_kbps: u64,
+    /// Lowest plausible average bitrate
+    pub min_kbps: u64,
+    /// Highest plausible average bitrate
+    pub max_kbps: u64,
+}
+
+/// Lowest and highest plausible size, for codecs whose bitrate varies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ByteRange {
+    pub min: u64,
+    pub max: u64,
+}
+
+/// Estimated size of one track.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TrackEstimate {
+    /// Catalog ID of the song
+    pub id: String,
+    /// Song title (empty if the API didn't return one)
+    pub name: String,
+    /// Duration in milliseconds
+    pub duration_ms: u64,
+    /// Estimated file size at the typical bitrate
+    pub estimated_bytes: u64,
+}
+
+/// Result of `estimate_size`, returned to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SizeEstimate {
+    /// Number of tracks found
+    pub track_count: usize,
+    /// Estimated total size at the codec's typical bitrate
+    pub estimated_bytes: u64,
+    /// Lowest to highest plausible total, for ALAC and Dolby Atmos only
+    pub range: Option<ByteRange>,
+    /// The codec estimated for (CLI string, e.g. "alac")
+    pub codec: String,
+    /// Per-track breakdown, in URL and track order
+    pub tracks: Vec<TrackEstimate>,
+    /// URLs that couldn't be sized (artists, music videos, other services,
+    /// lookup failures); not included in the totals
+    pub unresolved_urls: Vec<String>,
+}
+
+/// Returns the average bitrates of a codec's Apple Music streams.
+///
+/// | Codec              | kbit/s (typical, range)          |
+/// |--------------------|----------------------------------|
+/// | ALAC               | 1,000 (700 - 4,600)              |
+/// | Dolby Atmos        | 768 (448 - 768)                  |
+/// | AC-3               | 640                              |
+/// | AAC (256 variants) | 256                              |
+/// | HE-AAC variants    | 64                               |
+///
+/// ALAC's typical value is a 16-bit/44.1 kHz master; the top of its range
+/// is a 24-bit/192 kHz one.
+pub fn codec_bitrate(codec: &SongCodec) -> CodecBitrate {
+    let fixed = |kbps| CodecBitrate {
+        typical_kbps: kbps,
+        min_kbps: kbps,
+        max_kbps: kbps,
+    };
+    match codec {
+        SongCodec::Alac => CodecBitrate {
+            typical_kbps: 1000,
+            min_kbps: 700,
+            max_kbps: 4600,
+        },
+        SongCodec::Atmos => CodecBitrate {
+            typical_kbps: 768,
+            min_kbps: 448,
+            max_kbps: 768,
+        },
+        SongCodec::Ac3 => fixed(640),
+        SongCodec::Aac | SongCodec::AacLegacy | SongCodec::AacBinaural | SongCodec::AacDownmix => {
+            fixed(256)
+        }
+        SongCodec::AacHe
+        | SongCodec::AacHeLegacy
+        | SongCodec::AacHeBinaural
+        | SongCodec::AacHeDownmix => fixed(64),
+    }
+}
+
+/// Returns the size of `duration_ms` of audio at `kbps`, in bytes.
+pub fn bytes_for_duration(duration_ms: u64, kbps: u64) -> u64 {
+    // kbit/s * ms = bit; / 8 for bytes
+    duration_ms * kbps / 8
+}
+
+/// Builds the estimate from the track durations found.
+///
+/// # Arguments
+/// * `tracks` -- `(id, name, duration_ms)` of every track, in order.
+/// * `codec` -- The codec to estimate for.
+/// * `unresolved_urls` -- URLs left out of the totals.
+pub fn build_estimate(
+    tracks: Vec<(String, String, u64)>,
+    codec: &SongCodec,
+    unresolved_urls: Vec<String>,
+) -> SizeEstimate {
+    let bitrate = codec_bitrate(codec);
+    let total_ms: u64 = tracks.iter().map(|(_, _, ms)| ms).sum();
+    let range = (bitrate.min_kbps != bitrate.max_kbps).then(|| ByteRange {
+        min: bytes_for_duration(total_ms, bitrate.min_kbps),
+        max: bytes_for_duration(total_ms, bitrate.max_kbps),
+    });
+    let tracks: Vec<TrackEstimate> = tracks
+        .into_iter()
+        .map(|(id, name, duration_ms)| TrackEstimate {
+            estimated_bytes: bytes_for_duration(duration_ms, bitrate.typical_kbps),
+            id,
+            name,
+            duration_ms,
+        })
+        .collect();
+
+    SizeEstimate {
+        track_count: tracks.len(),
+        estimated_bytes: tracks.iter().map(|t| t.estimated_bytes).sum(),
+        range,
+        codec: codec.to_cli_string().to_string(),
+        tracks,
+        unresolved_urls,
+    }
+}
+
+/// Estimates the total size of downloading `urls` in `codec`.
+///
+/// # Returns
+/// * `Ok(estimate)` -- Including the URLs that couldn't be sized.
+/// * `Err(message)` -- No MusicKit credentials, or the HTTP client could
+///   not be built.
+pub async fn estimate(
+    app: &AppHandle,
+    urls: &[String],
+    codec: &SongCodec,
+) -> Result<SizeEstimate, String> {
+    let token = artist_expansion_service::musickit_token(app)
+        .map_err(|e| format!("Size estimates need MusicKit credentials: {}", e))?;
+    let client = config_service::load_proxy_config(app).build_client()?;
+
+    let mut tracks = Vec::new();
+    let mut unresolved = Vec::new();
+    for url in urls {
+        let Some(resource) = catalog_service::parse_catalog_url(url) else {
+            unresolved.push(url.clone());
+            continue;
+        };
+        match catalog_service::fetch_catalog_pages(&client, &token, &resource).await {
+            Ok(pages) => tracks.extend(pages.iter().flat_map(parse_track_durations)),
+            Err(e) => {
+                log::warn!("Could not size {}: {}", url, e);
+                unresolved.push(url.clone());
+            }
+        }
+    }
+    Ok(build_estimate(tracks, codec, unresolved))
+}
+
+/// Extracts `(id, name, duration_ms)` from a page of songs:
+///
+/// ```text
+/// { "data": [ { "id": "1440935808", "type": "songs",
+///               "attributes": { "name": "Style", "durationInMillis": 231000 } } ] }
+/// ```
+///
+/// Entries without a duration (music videos in playlists report one too,
+/// but with `"type": "music-videos"`) are skipped.
+fn parse_track_durations(json: &serde_json::Value) -> Vec<(String, String, u64)> {
+    json.get("data")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter(|t| t.get("type").and_then(|k| k.as_str()) != Some("music-videos"))
+                .filter_map(|t| {
+                    let id = t.get("id")?.as_str()?.to_string();
+                    let duration = t.pointer("/attributes/durationInMillis")?.as_u64()?;
+                    let name = t
+                        .pointer("/attributes/name")
+                        .and_then(|n| n.as_str())
+                        .unwrap_or_default()
+                        .to_string();
+                    Some((id, name, duration))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A four-minute track.
+    const FOUR_MINUTES_MS: u64 = 240_000;
+
+    fn track(id: &str, duration_ms: u64) -> (String, String, u64) {
+        (id.to_string(), format!("Track {}", id), duration_ms)
+    }
+
+    // ----------------------------------------------------------
+    // bytes_for_duration() / codec_bitrate()
+    // ----------------------------------------------------------
+
+    /// Verifies the byte arithmetic: 4 minutes of 256 kbit/s AAC is 7.68 MB.
+    #[test]
+    fn bytes_for_duration_converts_kbps() {
+        assert_eq!(bytes_for_duration(FOUR_MINUTES_MS, 256), 7_680_000);
+        assert_eq!(bytes_for_duration(FOUR_MINUTES_MS, 64), 1_920_000);
+        assert_eq!(bytes_for_duration(0, 1000), 0);
+    }
+
+    /// Verifies that every AAC variant estimates the same size per
+    /// duration, and HE-AAC a quarter of it.
+    #[test]
+    fn aac_variants_share_bitrates() {
+        for codec in [SongCodec::Aac, SongCodec::AacLegacy, SongCodec::AacBinaural, SongCodec::AacDownmix] {
+            assert_eq!(codec_bitrate(&codec).typical_kbps, 256, "{:?}", codec);
+        }
+        for codec in [
+            SongCodec::AacHe,
+            SongCodec::AacHeLegacy,
+            SongCodec::AacHeBinaural,
+            SongCodec::AacHeDownmix,
+        ] {
+            assert_eq!(codec_bitrate(&codec).typical_kbps, 64, "{:?}", codec);
+        }
+    }
+
+    // ----------------------------------------------------------
+    // build_estimate()
+    // ----------------------------------------------------------
+
+    /// Verifies per-track and total bytes for a fixed-bitrate codec, which
+    /// has no range.
+    #[test]
+    fn estimate_for_fixed_bitrate_codec() {
+        let estimate = build_estimate(
+            vec![track("1", FOUR_MINUTES_MS), track("2", FOUR_MINUTES_MS / 2)],
+            &SongCodec::Aac,
+            vec![],
+        );
+        assert_eq!(estimate.track_count, 2);
+        assert_eq!(estimate.codec, "aac");
+        assert_eq!(estimate.tracks[0].estimated_bytes, 7_680_000);
+        assert_eq!(estimate.tracks[1].estimated_bytes, 3_840_000);
+        assert_eq!(estimate.estimated_bytes, 11_520_000);
+        assert_eq!(estimate.range, None);
+    }
+
+    /// Verifies that ALAC and Atmos estimates carry a range around the
+    /// typical value.
+    #[test]
+    fn estimate_for_variable_codecs_has_range() {
+        let alac = build_estimate(vec![track("1", FOUR_MINUTES_MS)], &SongCodec::Alac, vec![]);
+        assert_eq!(alac.estimated_bytes, 30_000_000);
+        assert_eq!(
+            alac.range,
+            Some(ByteRange {
+                min: 21_000_000,
+                max: 138_000_000
+            })
+        );
+
+        let atmos = build_estimate(vec![track("1", FOUR_MINUTES_MS)], &SongCodec::Atmos, vec![]);
+        assert_eq!(atmos.estimated_bytes, 23_040_000);
+        let range = atmos.range.unwrap();
+        assert!(range.min < range.max);
+        assert_eq!(range.max, atmos.estimated_bytes);
+    }
+
+    /// Verifies that an empty estimate is all zeros and keeps unresolved URLs.
+    #[test]
+    fn empty_estimate_keeps_unresolved_urls() {
+        let url = "https://music.apple.com/us/artist/x/1".to_string();
+        let estimate = build_estimate(vec![], &SongCodec::Alac, vec![url.clone()]);
+        assert_eq!(estimate.track_count, 0);
+        assert_eq!(estimate.estimated_bytes, 0);
+        assert_eq!(estimate.range, Some(ByteRange { min: 0, max: 0 }));
+        assert_eq!(estimate.unresolved_urls, vec![url]);
+    }
+
+    // ----------------------------------------------------------
+    // parse_track_durations()
+    // ----------------------------------------------------------
+
+    /// Verifies that durations are read from a page, skipping music videos
+    /// and entries without a duration.
+    #[test]
+    fn parses_track_durations() {
+        let json = serde_json::json!({
+            "data": [
+                { "id": "1", "type": "songs", "attributes": { "name": "A", "durationInMillis": 200000 } },
+                { "id": "2", "type": "music-videos", "attributes": { "name": "V", "durationInMillis": 250000 } },
+                { "id": "3", "type": "songs", "attributes": { "name": "No duration" } },
+                { "id": "4", "type": "songs", "attributes": { "durationInMillis": 100000 } }
+            ]
+        });
+        assert_eq!(
+            parse_track_durations(&json),
+            vec![
+                ("1".to_string(), "A".to_string(), 200000),
+                ("4".to_string(), String::new(), 100000),
+            ]
+        );
+    }
+}
@@ -0,0 +1,404 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// staging_service.rs -- Download into a staging folder, publish on success
+// ==========================================================================
+//
+// GAMDL writes an album track by track, so a library folder watched by a
+// media server (Plex, Jellyfin, Music.app) picks up half-downloaded albums.
+// With `AppSettings::use_staging_dir` on, `process_queue()` points GAMDL at
+// a folder of the download's own:
+//
+//   {app_data}/staging/{download_id}/Artist/Album/01 Song.m4a
+//
+// and only once the run (and its audio check) succeeds, `publish()` moves
+// the tree into the real output path:
+//
+//   {output_path}/Artist/Album/01 Song.m4a
+//
+// A folder that doesn't exist at the destination yet is moved with a single
+// rename, so a new album appears all at once. Where the destination folder
+// already exists (a second album by the same artist) the entries are merged
+// into it one by one. When the staging folder is on another drive than the
+// library, a rename isn't possible and each file is copied, then deleted.
+//
+// A file that's already at the destination is handled by the download's
+// overwrite policy (`AppSettings::overwrite_policy`, unless the download
+// set its own `overwrite` option; see `download_overwrite_policy()`).
+// GAMDL can't see the library from the staging folder, so it downloads
+// those tracks again:
+//
+//   - `Skip` keeps the library file and drops the staged one.
+//   - `Replace` moves the staged file over it.
+//   - `Version` (which always stages) keeps it and saves the new one next
+//     to it as `01 Song (1).m4a`, then ` (2)`, and so on
+//     (`versioned_path()`). A staged file identical to the one already
+//     there (a re-saved cover, say) is dropped instead.
+//
+// Downloads into a folder on a network filesystem (NFS, SMB, ...; see
+// `platform::detect_remote_filesystem()`) are staged as well, whatever
+// the setting: GAMDL and FFmpeg then write locally, and only `publish()`
+// copies the finished files across. Except under `Skip`, where GAMDL has
+// to see the library to skip the tracks that are already there.
+//
+// The staging folder outlives retries (network errors, codec fallbacks) so
+// resumed albums find the tracks they already have; it is discarded once
+// the download fails for good or is cancelled.
+//
+// @see download_queue.rs -- process_queue() stages and publishes downloads
+
+use std::io;
+use std::path::{Path, PathBuf};
+
+use tauri::AppHandle;
+
+use crate::models::settings::OverwritePolicy;
+use crate::utils::platform;
+
+/// Returns the folder holding every download's staging folder.
+pub fn staging_root(app: &AppHandle) -> PathBuf {
+    platform::get_app_data_dir(app).join("staging")
+}
+
+/// Returns the staging folder of a download.
+pub fn staging_dir(app: &AppHandle, download_id: &str) -> PathBuf {
+    staging_root(app).join(download_id)
+}
+
+/// Where a staged file or folder ended up once published: `(staged path,
+/// destination path)`. A folder moved whole is one entry for everything
+/// in it (see published_path()).
+pub type PublishedMove = (PathBuf, PathBuf);
+
+/// Moves everything in `staging` into `output`, then removes `staging`.
+///
+/// Files already at the destination are kept or replaced as `policy`
+/// says (see move_tree()). A staging folder that was never created (GAMDL
+/// wrote nothing) publishes nothing.
+///
+/// # Returns
+/// Where each staged entry went, for paths that pointed into staging to
+/// follow their files, including the renamed copies of the `Version`
+/// policy.
+pub fn publish(
+    staging: &Path,
+    output: &Path,
+    policy: OverwritePolicy,
+) -> Result<Vec<PublishedMove>, String> {
+    let mut moves = Vec::new();
+    if !staging.exists() {
+        return Ok(moves);
+    }
+    move_tree(staging, output, policy, &mut moves).map_err(|e| {
+        format!(
+            "Failed to move the download from {} to {}: {}",
+            staging.display(),
+            output.display(),
+            e
+        )
+    })?;
+    discard(staging);
+    Ok(moves)
+}
+
+/// Returns where a path inside the staging folder was published to, from
+/// the moves publish() returned: the destination of the entry that is, or
+/// contains, `staged`. `None` for paths nothing was published from.
+pub fn published_path(moves: &[PublishedMove], staged: &Path) -> Option<PathBuf> {
+    moves.iter().find_map(|(from, to)| {
+        staged.strip_prefix(from).ok().map(|rest| {
+            if rest.as_os_str().is_empty() {
+                to.clone()
+            } else {
+                to.join(rest)
+            }
+        })
+    })
+}
+
+/// Deletes a staging folder and everything left in it.
+pub fn discard(staging: &Path) {
+    match std::fs::remove_dir_all(staging) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => log::warn!("Failed to remove {}: {}", staging.display(), e),
+    }
+}
+
+/// Merges the contents of `from` into the folder `to`, creating it if
+/// needed. Entries missing at the destination are renamed over whole;
+/// existing folders are merged recursively. Existing files are kept with
+/// `Skip`, replaced with `Replace`, and with `Version` kept next to a
+/// renamed copy of the new one. Staged files that aren't moved are left
+/// in `from`.
+///
+/// Each entry is recorded in `moves` with the path that now holds it: its
+/// new location, the renamed copy, or for a kept or identical file the one
+/// already at the destination.
+pub fn move_tree(
+    from: &Path,
+    to: &Path,
+    policy: OverwritePolicy,
+    moves: &mut Vec<PublishedMove>,
+) -> io::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        if entry.file_type()?.is_dir() && target.is_dir() {
+            move_tree(&source, &target, policy, moves)?;
+            let _ = std::fs::remove_dir(&source);
+            continue;
+        }
+        let destination = if !target.exists() {
+            move_entry(&source, &target)?;
+            target
+        } else {
+            match policy {
+                OverwritePolicy::Skip => target,
+                OverwritePolicy::Replace => {
+                    move_entry(&source, &target)?;
+                    target
+                }
+                OverwritePolicy::Version if same_contents(&source, &target) => {
+                    std::fs::remove_file(&source)?;
+                    target
+                }
+                OverwritePolicy::Version => {
+                    let versioned = versioned_path(&target, |p| p.exists());
+                    move_entry(&source, &versioned)?;
+                    versioned
+                }
+            }
+        };
+        moves.push((source, destination));
+    }
+    Ok(())
+}
+
+/// Returns the first free versioned name for a file that collides at
+/// `path`: `01 Song.m4a` becomes `01 Song (1).m4a`, then `01 Song (2).m4a`
+/// and so on. `exists` tells whether a candidate is taken.
+pub fn versioned_path(path: &Path, exists: impl Fn(&Path) -> bool) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1u32..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !exists(candidate))
+        .expect("unbounded range always yields a free name")
+}
+
+/// Whether two files hold the same bytes. Sizes are compared first, so
+/// differing audio files are told apart without reading them.
+fn same_contents(a: &Path, b: &Path) -> bool {
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(ma), Ok(mb)) if ma.is_file() && mb.is_file() && ma.len() == mb.len() => {
+            matches!((std::fs::read(a), std::fs::read(b)), (Ok(x), Ok(y)) if x == y)
+        }
+        _ => false,
+    }
+}
+
+/// Renames one file or folder, copying it when the rename crosses drives.
+fn move_entry(from: &Path, to: &Path) -> io::Result<()> {
+    match std::fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::CrossesDevices => copy_then_remove(from, to),
+        Err(e) => Err(e),
+    }
+}
+
+/// Copies a file or folder to `to`, then deletes the original: the
+/// cross-drive fallback of move_entry().
+fn copy_then_remove(from: &Path, to: &Path) -> io::Result<()> {
+    if from.is_dir() {
+        std::fs::create_dir_all(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_then_remove(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::remove_dir(from)
+    } else {
+        std::fs::copy(from, to)?;
+        std::fs::remove_file(from)
+    }
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    /// Verifies that a finished download lands in the output path: a new
+    /// album folder is moved whole, an existing artist folder is merged
+    /// into, a file already there is replaced, and staging is removed.
+    #[test]
+    fn publish_moves_tree_into_output() {
+        let dir = TestDir::new("staging-publish");
+        let staging = dir.join("staging").join("dl-1");
+        let output = dir.join("Music");
+        write(&staging.join("Artist/New Album/01 Song.m4a"), "new");
+        write(&staging.join("Artist/Old Album/02 Song.m4a"), "replaced");
+        write(&output.join("Artist/Old Album/01 Song.m4a"), "kept");
+        write(&output.join("Artist/Old Album/02 Song.m4a"), "stale");
+
+        let moves = publish(&staging, &output, OverwritePolicy::Replace).unwrap();
+
+        let read = |p: &str| std::fs::read_to_string(output.join(p)).unwrap();
+        assert_eq!(read("Artist/New Album/01 Song.m4a"), "new");
+        // A folder moved whole takes the paths inside it along
+        assert_eq!(
+            published_path(&moves, &staging.join("Artist/New Album/01 Song.m4a")),
+            Some(output.join("Artist/New Album/01 Song.m4a"))
+        );
+        assert_eq!(published_path(&moves, &dir.join("elsewhere.m4a")), None);
+        assert_eq!(read("Artist/Old Album/01 Song.m4a"), "kept");
+        assert_eq!(read("Artist/Old Album/02 Song.m4a"), "replaced");
+        assert!(!staging.exists());
+
+        // Nothing staged: nothing to do
+        assert!(publish(&staging, &output, OverwritePolicy::Replace)
+            .unwrap()
+            .is_empty());
+    }
+
+    /// Verifies that with the Skip policy the files already in the library
+    /// are kept, while new files are still published.
+    #[test]
+    fn skip_publish_keeps_existing_files() {
+        let dir = TestDir::new("staging-skip");
+        let staging = dir.join("staging").join("dl-3");
+        let output = dir.join("Music");
+        write(&staging.join("Album/01 Song.m4a"), "downloaded again");
+        write(&staging.join("Album/02 Song.m4a"), "new");
+        write(&output.join("Album/01 Song.m4a"), "library");
+
+        publish(&staging, &output, OverwritePolicy::Skip).unwrap();
+
+        let read = |p: &str| std::fs::read_to_string(output.join(p)).unwrap();
+        assert_eq!(read("Album/01 Song.m4a"), "library");
+        assert_eq!(read("Album/02 Song.m4a"), "new");
+        assert!(!output.join("Album/01 Song (1).m4a").exists());
+        assert!(!staging.exists());
+    }
+
+    /// Verifies that the cross-drive fallback copies a nested tree and
+    /// leaves nothing behind at the source.
+    #[test]
+    fn copy_fallback_moves_nested_tree() {
+        let dir = TestDir::new("staging-copy");
+        let from = dir.join("from");
+        let to = dir.join("to");
+        write(&from.join("Album/01 Song.m4a"), "a");
+        write(&from.join("Album/Disc 2/01 Song.m4a"), "b");
+
+        copy_then_remove(&from, &to).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(to.join("Album/Disc 2/01 Song.m4a")).unwrap(),
+            "b"
+        );
+        assert_eq!(
+            std::fs::read_to_string(to.join("Album/01 Song.m4a")).unwrap(),
+            "a"
+        );
+        assert!(!from.exists());
+    }
+
+    /// Verifies that discarding a failed download's staging folder removes
+    /// its partial files without touching the output path, and that a
+    /// folder that's already gone is fine.
+    #[test]
+    fn discard_removes_partial_download() {
+        let dir = TestDir::new("staging-discard");
+        let staging = dir.join("staging").join("dl-2");
+        let output = dir.join("Music");
+        write(&staging.join("Artist/Album/01 Song.m4a"), "partial");
+        write(&output.join("Artist/Album/cover.jpg"), "cover");
+
+        discard(&staging);
+        discard(&staging);
+
+        assert!(!staging.exists());
+        assert!(dir.join("staging").exists());
+        assert!(output.join("Artist/Album/cover.jpg").exists());
+    }
+
+    /// Verifies the names of the first, second and third kept copy, for
+    /// files with and without an extension.
+    #[test]
+    fn versioned_path_counts_up() {
+        let song = Path::new("/music/Album/01 Song.m4a");
+        let taken = |names: &'static [&'static str]| {
+            move |p: &Path| names.iter().any(|n| p == Path::new("/music/Album").join(n))
+        };
+
+        assert_eq!(
+            versioned_path(song, taken(&[])),
+            Path::new("/music/Album/01 Song (1).m4a")
+        );
+        assert_eq!(
+            versioned_path(song, taken(&["01 Song (1).m4a"])),
+            Path::new("/music/Album/01 Song (2).m4a")
+        );
+        assert_eq!(
+            versioned_path(song, taken(&["01 Song (1).m4a", "01 Song (2).m4a"])),
+            Path::new("/music/Album/01 Song (3).m4a")
+        );
+        assert_eq!(
+            versioned_path(Path::new("/music/Album/notes"), taken(&[])),
+            Path::new("/music/Album/notes (1)")
+        );
+    }
+
+    /// Verifies that a versioned publish keeps what's already there, saves
+    /// colliding files under the next free name, and doesn't duplicate a
+    /// file that's identical to the existing one.
+    #[test]
+    fn versioned_publish_keeps_existing_files() {
+        let dir = TestDir::new("staging-versioned");
+        let output = dir.join("Music");
+        write(&output.join("Album/01 Song.m4a"), "first");
+        write(&output.join("Album/cover.jpg"), "cover");
+
+        for (take, copy) in [("second", "01 Song (1).m4a"), ("third", "01 Song (2).m4a")] {
+            let staging = dir.join("staging").join(take);
+            write(&staging.join("Album/01 Song.m4a"), take);
+            write(&staging.join("Album/cover.jpg"), "cover");
+            let moves = publish(&staging, &output, OverwritePolicy::Version).unwrap();
+            assert!(!staging.exists());
+
+            // Paths into staging follow the renamed copy
+            assert_eq!(
+                published_path(&moves, &staging.join("Album/01 Song.m4a")),
+                Some(output.join("Album").join(copy))
+            );
+            assert_eq!(
+                published_path(&moves, &staging.join("Album/cover.jpg")),
+                Some(output.join("Album/cover.jpg"))
+            );
+        }
+
+        let read = |p: &str| std::fs::read_to_string(output.join(p)).unwrap();
+        assert_eq!(read("Album/01 Song.m4a"), "first");
+        assert_eq!(read("Album/01 Song (1).m4a"), "second");
+        assert_eq!(read("Album/01 Song (2).m4a"), "third");
+        assert!(!output.join("Album/cover (1).jpg").exists());
+    }
+}
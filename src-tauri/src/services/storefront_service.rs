@@ -0,0 +1,228 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// storefront_service.rs -- Per-download Apple Music storefront override
+// =====================================================================
+//
+// Apple Music URLs carry the storefront (country) as their first path
+// segment: `https://music.apple.com/us/album/...`. GAMDL looks content up
+// in the storefront of the URL, so an album that is only released in some
+// countries fails with "not available in this storefront" everywhere else.
+//
+// A download can set `GamdlOptions::storefront` (e.g., "jp"). It is not a
+// GAMDL flag: `DownloadQueue::next_pending()` rewrites the item's URLs with
+// `apply_storefront()` before the process is spawned. The catalog ID stays
+// the same, so `/us/album/x/123` becomes `/jp/album/x/123`.
+//
+// ## Storefront fallback
+//
+// When GAMDL reports the content as unavailable in the storefront
+// (`process::is_storefront_unavailable_error()`), the queue retries the
+// download against the next entry of `FALLBACK_STOREFRONTS` that hasn't
+// been tried yet (`DownloadQueue::try_storefront_fallback()`). The item
+// fails once the list is exhausted.
+//
+// @see download_queue.rs -- next_pending_at(), try_storefront_fallback()
+// @see utils/process.rs -- classify_error() "storefront_unavailable"
+
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::models::gamdl_options::GamdlOptions;
+
+/// Storefronts tried, in order, when a download isn't available in the
+/// storefront of its URL. The largest Apple Music catalogues come first.
+pub const FALLBACK_STOREFRONTS: &[&str] = &["us", "gb", "ca", "au", "jp", "de", "fr"];
+
+/// Matches an Apple Music URL and captures the part before the storefront,
+/// the storefront, and the rest of the URL (path, query, fragment).
+static STOREFRONT_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(https?://music\.apple\.com/)([a-zA-Z]{2})(/.*)$")
+        .expect("Invalid storefront URL regex")
+});
+
+/// Checks that a storefront is a two-letter country code.
+///
+/// # Returns
+/// * `Ok(code)` -- The code in lowercase, as used in Apple Music URLs.
+/// * `Err(message)` -- Naming the rejected value.
+pub fn validate_storefront(code: &str) -> Result<String, String> {
+    let code = code.trim();
+    if code.len() == 2 && code.chars().all(|c| c.is_ascii_alphabetic()) {
+        Ok(code.to_ascii_lowercase())
+    } else {
+        Err(format!(
+            "'{}' is not a valid storefront (expected a two-letter country code, e.g. \"jp\")",
+            code
+        ))
+    }
+}
+
+/// Validates and normalises the storefront override of a download's
+/// options in place. Called by `start_download` so a bad code is rejected
+/// before anything is queued.
+pub fn normalize_override(options: &mut GamdlOptions) -> Result<(), String> {
+    if let Some(code) = options.storefront.as_deref() {
+        options.storefront = Some(validate_storefront(code)?);
+    }
+    Ok(())
+}
+
+/// Returns the storefront of an Apple Music URL (lowercase), or `None`
+/// for URLs without one (other services, malformed links).
+pub fn url_storefront(url: &str) -> Option<String> {
+    STOREFRONT_URL_REGEX
+        .captures(url.trim())
+        .map(|caps| caps[2].to_ascii_lowercase())
+}
+
+/// Rewrites the storefront segment of an Apple Music URL, keeping the
+/// rest of the URL (including `?i=` song selectors) unchanged.
+///
+/// # Returns
+/// * `Ok(url)` -- The URL pointing at `storefront`.
+/// * `Err(message)` -- `storefront` isn't a two-letter code, or `url` is
+///   not an Apple Music URL with a storefront segment.
+pub fn rewrite_storefront(url: &str, storefront: &str) -> Result<String, String> {
+    let storefront = validate_storefront(storefront)?;
+    let caps = STOREFRONT_URL_REGEX
+        .captures(url.trim())
+        .ok_or_else(|| format!("\"{}\" is not an Apple Music URL with a storefront", url))?;
+    Ok(format!("{}{}{}", &caps[1], storefront, &caps[3]))
+}
+
+/// Applies a storefront override to a download's URLs. URLs that can't be
+/// rewritten (e.g., a batch file path) are passed through unchanged.
+pub fn apply_storefront(urls: &[String], storefront: Option<&str>) -> Vec<String> {
+    let Some(storefront) = storefront else {
+        return urls.to_vec();
+    };
+    urls.iter()
+        .map(|url| {
+            rewrite_storefront(url, storefront).unwrap_or_else(|e| {
+                log::debug!("Not applying storefront override: {}", e);
+                url.clone()
+            })
+        })
+        .collect()
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // ----------------------------------------------------------
+    // validate_storefront()
+    // ----------------------------------------------------------
+
+    /// Verifies that two-letter codes are accepted (lowercased) and
+    /// anything else is rejected.
+    #[test]
+    fn validates_two_letter_codes() {
+        assert_eq!(validate_storefront("jp"), Ok("jp".to_string()));
+        assert_eq!(validate_storefront(" GB "), Ok("gb".to_string()));
+        for invalid in ["", "j", "jpn", "j1", "u-", "日本"] {
+            assert!(validate_storefront(invalid).is_err(), "{:?} accepted", invalid);
+        }
+    }
+
+    /// Verifies that normalize_override() lowercases a valid override and
+    /// rejects an invalid one.
+    #[test]
+    fn normalize_override_rejects_invalid_code() {
+        let mut options = GamdlOptions {
+            storefront: Some("JP".to_string()),
+            ..GamdlOptions::default()
+        };
+        normalize_override(&mut options).unwrap();
+        assert_eq!(options.storefront.as_deref(), Some("jp"));
+
+        options.storefront = Some("japan".to_string());
+        assert!(normalize_override(&mut options).is_err());
+
+        let mut none = GamdlOptions::default();
+        assert!(normalize_override(&mut none).is_ok());
+    }
+
+    // ----------------------------------------------------------
+    // rewrite_storefront()
+    // ----------------------------------------------------------
+
+    /// Verifies the rewrite across album, song, playlist and music video
+    /// URL shapes, keeping slugs, IDs and query strings.
+    #[test]
+    fn rewrites_country_segment_of_all_url_shapes() {
+        let cases = [
+            (
+                "https://music.apple.com/us/album/1989/1440935467",
+                "https://music.apple.com/jp/album/1989/1440935467",
+            ),
+            (
+                "https://music.apple.com/us/album/1989/1440935467?i=1440935808",
+                "https://music.apple.com/jp/album/1989/1440935467?i=1440935808",
+            ),
+            (
+                "https://music.apple.com/gb/song/style/1440935808",
+                "https://music.apple.com/jp/song/style/1440935808",
+            ),
+            (
+                "https://music.apple.com/us/playlist/todays-hits/pl.f4d106fed2bd41149aaacabb233eb5eb",
+                "https://music.apple.com/jp/playlist/todays-hits/pl.f4d106fed2bd41149aaacabb233eb5eb",
+            ),
+            (
+                "https://music.apple.com/ca/music-video/video/1234567890",
+                "https://music.apple.com/jp/music-video/video/1234567890",
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(rewrite_storefront(input, "jp").unwrap(), expected);
+        }
+    }
+
+    /// Verifies that an invalid code and non-Apple Music URLs are rejected.
+    #[test]
+    fn rewrite_rejects_invalid_input() {
+        let url = "https://music.apple.com/us/album/x/1";
+        assert!(rewrite_storefront(url, "jpn").is_err());
+        assert!(rewrite_storefront(url, "").is_err());
+        assert!(rewrite_storefront("https://music.youtube.com/watch?v=1", "jp").is_err());
+        assert!(rewrite_storefront("https://music.apple.com/album/x/1", "jp").is_err());
+    }
+
+    // ----------------------------------------------------------
+    // url_storefront() / apply_storefront()
+    // ----------------------------------------------------------
+
+    /// Verifies that the storefront is read from the URL.
+    #[test]
+    fn reads_url_storefront() {
+        assert_eq!(
+            url_storefront("https://music.apple.com/GB/album/x/1"),
+            Some("gb".to_string())
+        );
+        assert_eq!(url_storefront("/tmp/meedyadl-batch-1.txt"), None);
+    }
+
+    /// Verifies that apply_storefront() leaves URLs alone without an
+    /// override and passes through URLs it can't rewrite.
+    #[test]
+    fn apply_storefront_passes_through_unrewritable_urls() {
+        let urls = vec![
+            "https://music.apple.com/us/album/x/1".to_string(),
+            "/tmp/urls.txt".to_string(),
+        ];
+        assert_eq!(apply_storefront(&urls, None), urls);
+        assert_eq!(
+            apply_storefront(&urls, Some("jp")),
+            vec![
+                "https://music.apple.com/jp/album/x/1".to_string(),
+                "/tmp/urls.txt".to_string(),
+            ]
+        );
+    }
+}
@@ -0,0 +1,484 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// test_download_service.rs -- One-track download that checks the whole setup
+// =============================================================================
+//
+// A first-time user only finds out whether cookies, codec, decryption and
+// remuxing all work when a real album fails halfway. `run()` downloads one
+// widely available song (`TEST_TRACK_URL`) with the current settings into
+// a scratch folder of its own, outside the library and the queue:
+//
+//   1. setup    -- GAMDL's command can be built (Python, GAMDL, URL, the
+//                  Widevine setup)
+//   2. GAMDL    -- the run succeeds; a failure is classified like a queued
+//                  download's (`DownloadError`) and mapped to the stage it
+//                  points at: auth, codec, decrypt, remux or download
+//   3. output   -- an audio file was actually written
+//   4. verify   -- FFmpeg decodes it (`audio_verify_service`)
+//
+// The result names the first stage that failed. The scratch folder is a
+// `ScratchDir`, removed when it goes out of scope, so nothing is left
+// behind whatever the outcome.
+//
+// Lyrics, cover and playlist files are switched off for the run: only the
+// audio path is under test.
+//
+// @see commands/gamdl.rs -- run_test_download()
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+use serde::Serialize;
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::download::DownloadKind;
+use crate::models::download_error::DownloadError;
+use crate::models::gamdl_options::{GamdlOptions, SongCodec};
+use crate::models::settings::AppSettings;
+use crate::services::{audio_verify_service, download_queue, gamdl_service};
+use crate::utils::process;
+
+/// The song the test downloads: a single track that's offered in every
+/// storefront and in every codec the app supports.
+pub const TEST_TRACK_URL: &str = "https://music.apple.com/us/song/style/1440935808";
+
+/// How long the GAMDL run may take before the test gives up.
+pub const TEST_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Pipeline stage a test download failed at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestStage {
+    /// GAMDL couldn't be started (Python, GAMDL, tools, URL)
+    Setup,
+    /// Cookies, login or subscription
+    Auth,
+    /// The codec isn't offered for the account or the track
+    Codec,
+    /// The Widevine device or decryption key
+    Decrypt,
+    /// FFmpeg, mp4decrypt or MP4Box post-processing
+    Remux,
+    /// Network, storefront or anything else during the download
+    Download,
+    /// GAMDL finished without writing an audio file, or couldn't write it
+    Output,
+    /// The written file doesn't decode
+    Verify,
+}
+
+impl TestStage {
+    /// Stage a classified GAMDL error points at.
+    pub fn of_error(error: &DownloadError) -> Self {
+        match error {
+            DownloadError::Auth { .. }
+            | DownloadError::NoCookies { .. }
+            | DownloadError::Subscription { .. } => Self::Auth,
+            DownloadError::Codec { .. } | DownloadError::CodecUnavailable { .. } => Self::Codec,
+            DownloadError::Drm { .. } => Self::Decrypt,
+            DownloadError::Tool { .. } => Self::Remux,
+            DownloadError::InvalidUrl { .. } => Self::Setup,
+            DownloadError::NoOutput { .. } | DownloadError::Disk { .. } => Self::Output,
+            DownloadError::CorruptOutput { .. } => Self::Verify,
+            _ => Self::Download,
+        }
+    }
+}
+
+/// Why a test download stopped.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StageFailure {
+    /// First stage that failed
+    pub stage: TestStage,
+    /// What went wrong, in words
+    pub message: String,
+}
+
+impl StageFailure {
+    /// A failure at the stage the classified error points at.
+    pub fn from_error(error: DownloadError) -> Self {
+        Self {
+            stage: TestStage::of_error(&error),
+            message: error.message().to_string(),
+        }
+    }
+}
+
+/// Outcome of a test download, serialized to the frontend.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TestDownloadResult {
+    /// Whether every stage passed
+    pub passed: bool,
+    /// First stage that failed; `None` when the test passed
+    pub failed_stage: Option<TestStage>,
+    /// What happened, in words
+    pub message: String,
+    /// Codec the test downloaded in (CLI name, e.g. `"alac"`)
+    pub codec: String,
+    /// Name of the file that was downloaded (and removed again)
+    pub file_name: Option<String>,
+}
+
+impl TestDownloadResult {
+    fn failed(codec: &SongCodec, failure: StageFailure) -> Self {
+        Self {
+            passed: false,
+            failed_stage: Some(failure.stage),
+            message: failure.message,
+            codec: codec.to_cli_string().to_string(),
+            file_name: None,
+        }
+    }
+}
+
+/// A scratch folder that's deleted with everything in it when dropped.
+#[derive(Debug)]
+pub struct ScratchDir(PathBuf);
+
+impl ScratchDir {
+    /// Creates a new, empty scratch folder in the system temp directory.
+    pub fn create() -> Result<Self, String> {
+        let path =
+            std::env::temp_dir().join(format!("meedyadl-test-download-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path)
+            .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+        Ok(Self(path))
+    }
+
+    /// The folder's path.
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.0) {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                log::warn!("Failed to remove {}: {}", self.0.display(), e);
+            }
+        }
+    }
+}
+
+/// The options of the test download: the current settings, saving into
+/// `dir`, without lyrics, cover or playlist files.
+pub fn test_options(settings: &AppSettings, dir: &Path) -> GamdlOptions {
+    let mut options = download_queue::merge_options(None, settings, DownloadKind::Single);
+    options.output_path = Some(dir.to_string_lossy().to_string());
+    options.overwrite = Some(true);
+    options.save_cover = Some(false);
+    options.no_synced_lyrics = Some(true);
+    options.synced_lyrics_only = Some(false);
+    options.save_playlist = Some(false);
+    options
+}
+
+/// Downloads the test track with `settings` and checks the result.
+pub async fn run(app: &AppHandle, settings: &AppSettings) -> TestDownloadResult {
+    let codec = settings.default_song_codec.clone();
+    let scratch = match ScratchDir::create() {
+        Ok(scratch) => scratch,
+        Err(message) => {
+            return TestDownloadResult::failed(
+                &codec,
+                StageFailure {
+                    stage: TestStage::Output,
+                    message,
+                },
+            )
+        }
+    };
+    let options = test_options(settings, scratch.path());
+    let codec = options.song_codec.clone().unwrap_or(codec);
+
+    let urls = [TEST_TRACK_URL.to_string()];
+    let cmd = match gamdl_service::build_gamdl_command_public(app, &urls, &options) {
+        Ok(cmd) => cmd,
+        Err(message) => {
+            // A bad Widevine setup is caught here too; keep its stage
+            let stage = match TestStage::of_error(&DownloadError::from_message(message.clone())) {
+                TestStage::Decrypt => TestStage::Decrypt,
+                _ => TestStage::Setup,
+            };
+            return TestDownloadResult::failed(&codec, StageFailure { stage, message });
+        }
+    };
+
+    log::info!("Running test download of {}", TEST_TRACK_URL);
+    let result = run_in(scratch, &codec, run_command(cmd), |dir| async move {
+        let files = audio_verify_service::find_media_files_since(&dir, UNIX_EPOCH);
+        audio_verify_service::verify_outputs(app, settings, &files).await
+    })
+    .await;
+    match &result.failed_stage {
+        Some(stage) => log::warn!("Test download failed at {:?}: {}", stage, result.message),
+        None => log::info!("Test download passed"),
+    }
+    result
+}
+
+/// Runs the test in `scratch` and interprets the outcome; the folder is
+/// removed before this returns.
+///
+/// `gamdl` is the GAMDL run, which saves into `scratch`. `verify` decodes
+/// the files under the folder it's given and returns the damaged ones
+/// (`Err` when FFmpeg can't be run).
+pub async fn run_in<G, V, VF>(
+    scratch: ScratchDir,
+    codec: &SongCodec,
+    gamdl: G,
+    verify: V,
+) -> TestDownloadResult
+where
+    G: Future<Output = Result<(), StageFailure>>,
+    V: FnOnce(PathBuf) -> VF,
+    VF: Future<Output = Result<Vec<(PathBuf, String)>, String>>,
+{
+    if let Err(failure) = gamdl.await {
+        return TestDownloadResult::failed(codec, failure);
+    }
+
+    let files = audio_verify_service::find_media_files_since(scratch.path(), UNIX_EPOCH);
+    let Some(file) = files.first() else {
+        return TestDownloadResult::failed(
+            codec,
+            StageFailure::from_error(DownloadError::from_message(process::NO_OUTPUT_ERROR)),
+        );
+    };
+    let file_name = file
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    let failure = match verify(scratch.path().to_path_buf()).await {
+        Ok(damaged) => damaged.into_iter().next().map(|(_, reason)| reason),
+        Err(e) => Some(e),
+    };
+    if let Some(reason) = failure {
+        return TestDownloadResult::failed(
+            codec,
+            StageFailure {
+                stage: TestStage::Verify,
+                message: format!(
+                    "{}: {} ({})",
+                    process::CORRUPT_OUTPUT_ERROR,
+                    file_name,
+                    reason
+                ),
+            },
+        );
+    }
+
+    TestDownloadResult {
+        passed: true,
+        failed_stage: None,
+        message: format!(
+            "Downloaded and decoded {} in {}",
+            file_name,
+            codec.display_name()
+        ),
+        codec: codec.to_cli_string().to_string(),
+        file_name: Some(file_name),
+    }
+}
+
+/// Runs a built GAMDL command to the end, and classifies a failure by the
+/// last error it printed.
+async fn run_command(mut cmd: Command) -> Result<(), StageFailure> {
+    cmd.stdout(std::process::Stdio::piped());
+    cmd.stderr(std::process::Stdio::piped());
+    // kill_on_drop: a timed-out run must not keep downloading
+    cmd.kill_on_drop(true);
+
+    let output = match tokio::time::timeout(TEST_TIMEOUT, cmd.output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            return Err(StageFailure {
+                stage: TestStage::Setup,
+                message: format!("Failed to start GAMDL process: {}", e),
+            })
+        }
+        Err(_) => {
+            return Err(StageFailure {
+                stage: TestStage::Download,
+                message: format!(
+                    "The test download didn't finish within {} seconds",
+                    TEST_TIMEOUT.as_secs()
+                ),
+            })
+        }
+    };
+    if output.status.success() {
+        return Ok(());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    Err(StageFailure::from_error(DownloadError::from_message(
+        last_error(stdout.lines().chain(stderr.lines())).unwrap_or_else(|| {
+            format!(
+                "GAMDL process exited with code {}",
+                output.status.code().unwrap_or(-1)
+            )
+        }),
+    )))
+}
+
+/// The last error GAMDL printed among `lines`, if any.
+pub fn last_error<'a>(lines: impl Iterator<Item = &'a str>) -> Option<String> {
+    lines
+        .filter_map(|line| match process::parse_gamdl_output(line) {
+            process::GamdlOutputEvent::Error { message }
+            | process::GamdlOutputEvent::CodecUnavailable { message, .. } => Some(message),
+            _ => None,
+        })
+        .last()
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, contents: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, contents).unwrap();
+    }
+
+    /// Verifies that GAMDL errors are mapped to the stage they point at.
+    #[test]
+    fn errors_map_to_stages() {
+        let stage = |message: &str| TestStage::of_error(&DownloadError::from_message(message));
+        assert_eq!(stage("Failed to load cookies"), TestStage::Auth);
+        assert_eq!(stage("No active subscription"), TestStage::Auth);
+        assert_eq!(stage(process::NO_COOKIES_ERROR), TestStage::Auth);
+        assert_eq!(stage("Codec not available"), TestStage::Codec);
+        assert_eq!(stage("ffmpeg exited with code 1"), TestStage::Remux);
+        assert_eq!(stage("Connection reset by peer"), TestStage::Download);
+        assert_eq!(stage(process::NO_OUTPUT_ERROR), TestStage::Output);
+        assert_eq!(
+            stage(&format!("{}: wvd file missing", process::DRM_CONFIG_ERROR)),
+            TestStage::Decrypt
+        );
+    }
+
+    /// Verifies that the test saves into its scratch folder without the
+    /// extra lyrics, cover and playlist files, whatever the settings say.
+    #[test]
+    fn test_options_download_audio_only() {
+        let mut settings = AppSettings::default();
+        settings.synced_lyrics_only = true;
+        settings.save_playlist_m3u = true;
+        let options = test_options(&settings, Path::new("/tmp/scratch"));
+
+        assert_eq!(options.output_path.as_deref(), Some("/tmp/scratch"));
+        assert_eq!(
+            options.song_codec,
+            Some(settings.default_song_codec.clone())
+        );
+        assert_eq!(options.save_cover, Some(false));
+        assert_eq!(options.no_synced_lyrics, Some(true));
+        assert_eq!(options.synced_lyrics_only, Some(false));
+        assert_eq!(options.save_playlist, Some(false));
+    }
+
+    /// Verifies that the last error line is picked from GAMDL's output.
+    #[test]
+    fn last_error_picks_final_error_line() {
+        let output = "Getting track 1 of 1\nERROR: Something first\nERROR: Failed to load cookies";
+        assert_eq!(
+            last_error(output.lines()).as_deref(),
+            Some("Failed to load cookies")
+        );
+        assert_eq!(last_error("Getting track 1 of 1".lines()), None);
+    }
+
+    /// Verifies that a mocked GAMDL run that writes a decodable file
+    /// passes, and that the scratch folder is removed afterwards.
+    #[tokio::test]
+    async fn passing_run_reports_file_and_cleans_up() {
+        let scratch = ScratchDir::create().unwrap();
+        let dir = scratch.path().to_path_buf();
+        let song = dir.join("Taylor Swift/1989/03 Style.m4a");
+        let gamdl = async {
+            write(&song, "audio");
+            Ok(())
+        };
+
+        let result = run_in(scratch, &SongCodec::Alac, gamdl, |checked| async move {
+            assert_eq!(
+                audio_verify_service::find_media_files_since(&checked, UNIX_EPOCH).len(),
+                1
+            );
+            Ok(Vec::new())
+        })
+        .await;
+
+        assert!(result.passed, "{:?}", result);
+        assert_eq!(result.failed_stage, None);
+        assert_eq!(result.file_name.as_deref(), Some("03 Style.m4a"));
+        assert_eq!(result.codec, "alac");
+        assert!(!dir.exists());
+    }
+
+    /// Verifies that a failed GAMDL run, a run that saved nothing and a
+    /// file that doesn't decode each report their stage, and that the
+    /// scratch folder is removed every time.
+    #[tokio::test]
+    async fn failures_report_stage_and_clean_up() {
+        let no_verify = |_: PathBuf| async { Ok::<_, String>(Vec::new()) };
+
+        // GAMDL fails on the cookies after writing a partial file
+        let scratch = ScratchDir::create().unwrap();
+        let dir = scratch.path().to_path_buf();
+        let partial = dir.join("partial.m4a");
+        let gamdl = async {
+            write(&partial, "part");
+            Err(StageFailure::from_error(DownloadError::from_message(
+                "Failed to load cookies",
+            )))
+        };
+        let result = run_in(scratch, &SongCodec::Alac, gamdl, no_verify).await;
+        assert!(!result.passed);
+        assert_eq!(result.failed_stage, Some(TestStage::Auth));
+        assert!(!dir.exists());
+
+        // GAMDL exits cleanly without saving anything
+        let scratch = ScratchDir::create().unwrap();
+        let dir = scratch.path().to_path_buf();
+        let result = run_in(scratch, &SongCodec::Aac, async { Ok(()) }, no_verify).await;
+        assert_eq!(result.failed_stage, Some(TestStage::Output));
+        assert!(!dir.exists());
+
+        // The file doesn't decode
+        let scratch = ScratchDir::create().unwrap();
+        let dir = scratch.path().to_path_buf();
+        let song = dir.join("01 Song.m4a");
+        let gamdl = async {
+            write(&song, "garbage");
+            Ok(())
+        };
+        let result = run_in(scratch, &SongCodec::Alac, gamdl, |checked| async move {
+            Ok(vec![(
+                checked.join("01 Song.m4a"),
+                "moov atom not found".to_string(),
+            )])
+        })
+        .await;
+        assert_eq!(result.failed_stage, Some(TestStage::Verify));
+        assert!(
+            result.message.contains("moov atom not found"),
+            "{}",
+            result.message
+        );
+        assert_eq!(result.file_name, None);
+        assert!(!dir.exists());
+    }
+}
@@ -0,0 +1,660 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// track_availability_service.rs -- Skip unavailable tracks up front
+// ==================================================================
+//
+// Playlists (and the odd album) keep listing tracks Apple Music no longer
+// offers, and GAMDL fails on each of them. With
+// `AppSettings::skip_unavailable_tracks` on,
+// `commands::gamdl::start_download()` runs each request through
+// `filter_request()` first, which:
+//
+//   1. Lists the songs of each album, song and catalog playlist URL via
+//      the catalog API (`catalog_service::fetch_catalog_pages()`).
+//   2. Marks a song unavailable when the catalog returns it without
+//      `attributes.playParams`, which Apple omits for content that can't
+//      be played in the storefront.
+//   3. If any song is unavailable, replaces the URL with the URLs of the
+//      playable songs and records the others on the queue item as skipped
+//      with reason "unavailable" (`QueueItemStatus::skipped_tracks`).
+//
+// A URL whose songs are all available is passed to GAMDL unchanged. When
+// tracks are skipped the rest are downloaded as songs: album tracks still
+// land in their album folder (the request keeps its kind, so they aren't
+// filed as singles), but playlist tracks follow the album templates rather
+// than the playlist ones.
+//
+// With `AppSettings::per_track_best_codec` on, `split_by_best_codec()`
+// uses the same listing to give each song the first codec of the fallback
+// chain its `attributes.audioTraits` offer (an album with two tracks that
+// were never mastered in lossless gets ALAC for the rest and AAC for those
+// two), and splits the request into one job per codec:
+//
+//   album (10 songs) --> job 1: 8 song URLs, ALAC
+//                        job 2: 2 song URLs, AAC
+//
+// so each codec is a single GAMDL run rather than one run per track, and
+// the two songs don't have to fail in ALAC before falling back.
+//
+// @see artist_expansion_service.rs -- musickit_token(), the catalog credentials
+// @see https://developer.apple.com/documentation/applemusicapi/songs/attributes-data.dictionary
+
+use tauri::AppHandle;
+
+use crate::models::download::{DownloadRequest, SkippedTrack};
+use crate::models::gamdl_options::SongCodec;
+use crate::services::catalog_service::{self, CatalogResource};
+use crate::services::{artist_expansion_service, config_service, download_queue};
+
+/// `SkippedTrack::reason` of tracks the catalog no longer offers.
+pub const REASON_UNAVAILABLE: &str = "unavailable";
+
+/// One song as listed by the catalog API.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CatalogTrack {
+    /// Catalog ID of the song or music video
+    pub id: String,
+    /// Its title (empty if the API didn't return one)
+    pub name: String,
+    /// Its Apple Music page URL, if listed
+    pub url: Option<String>,
+    /// Whether the catalog reports it as playable (`playParams` present)
+    pub available: bool,
+    /// Its `attributes.audioTraits` (e.g. "lossless", "atmos"), empty if
+    /// the API didn't list any
+    pub audio_traits: Vec<String>,
+}
+
+/// What to download for one URL after the unavailable songs are removed.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct FilteredUrl {
+    /// URLs to pass to GAMDL in place of the original one
+    pub urls: Vec<String>,
+    /// Songs left out
+    pub skipped: Vec<SkippedTrack>,
+}
+
+/// Songs of one URL that share their best codec.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodecBucket {
+    /// Codec to download them in
+    pub codec: SongCodec,
+    /// Their song URLs, in catalog order
+    pub urls: Vec<String>,
+}
+
+/// Extracts the songs of one catalog page:
+///
+/// ```text
+/// { "data": [ { "id": "1440935808", "type": "songs",
+///               "attributes": { "name": "Style", "url": "https://music.apple.com/...",
+///                               "playParams": { "id": "1440935808", "kind": "song" } } } ] }
+/// ```
+///
+/// Entries without an ID are ignored.
+pub fn parse_catalog_tracks(page: &serde_json::Value) -> Vec<CatalogTrack> {
+    page.get("data")
+        .and_then(|d| d.as_array())
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|t| {
+                    Some(CatalogTrack {
+                        id: t.get("id")?.as_str()?.to_string(),
+                        name: t
+                            .pointer("/attributes/name")
+                            .and_then(|n| n.as_str())
+                            .unwrap_or_default()
+                            .to_string(),
+                        url: t
+                            .pointer("/attributes/url")
+                            .and_then(|u| u.as_str())
+                            .map(str::to_string),
+                        available: t.pointer("/attributes/playParams").is_some(),
+                        audio_traits: t
+                            .pointer("/attributes/audioTraits")
+                            .and_then(|a| a.as_array())
+                            .map(|traits| {
+                                traits
+                                    .iter()
+                                    .filter_map(|t| t.as_str().map(str::to_string))
+                                    .collect()
+                            })
+                            .unwrap_or_default(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Removes the unavailable songs of `url`.
+///
+/// - Every song available: `url` itself, nothing skipped.
+/// - Some unavailable: the URLs of the available songs, in order.
+/// - None available: no URLs at all.
+///
+/// An available song without a URL can't be queued on its own, so the
+/// original URL is kept in that case and nothing is skipped; GAMDL then
+/// reports the unavailable songs as before.
+pub fn filter_unavailable(url: &str, tracks: &[CatalogTrack]) -> FilteredUrl {
+    let unchanged = || FilteredUrl {
+        urls: vec![url.to_string()],
+        skipped: Vec::new(),
+    };
+    if tracks.iter().all(|t| t.available) {
+        return unchanged();
+    }
+    let Some(urls) = tracks
+        .iter()
+        .filter(|t| t.available)
+        .map(|t| t.url.clone())
+        .collect::<Option<Vec<String>>>()
+    else {
+        return unchanged();
+    };
+
+    FilteredUrl {
+        urls,
+        skipped: tracks
+            .iter()
+            .filter(|t| !t.available)
+            .map(|t| SkippedTrack {
+                id: t.id.clone(),
+                name: t.name.clone(),
+                reason: REASON_UNAVAILABLE.to_string(),
+            })
+            .collect(),
+    }
+}
+
+/// Whether a song's audio traits say it can be downloaded in `codec`.
+///
+/// ALAC needs a lossless master, Atmos and AC-3 a surround one, and the
+/// binaural AAC stream a spatial one. Every song has the stereo AAC
+/// streams.
+pub fn supports_codec(track: &CatalogTrack, codec: &SongCodec) -> bool {
+    let has = |traits: &[&str]| {
+        track
+            .audio_traits
+            .iter()
+            .any(|t| traits.contains(&t.as_str()))
+    };
+    match codec {
+        SongCodec::Alac => has(&["lossless", "hi-res-lossless"]),
+        SongCodec::Atmos => has(&["atmos"]),
+        SongCodec::Ac3 => has(&["surround", "atmos"]),
+        SongCodec::AacBinaural => has(&["spatial", "atmos"]),
+        _ => true,
+    }
+}
+
+/// Groups the songs of `url` by the first codec of `chain` each supports.
+///
+/// Buckets come in chain order. A song without audio traits (or none
+/// matching the chain) goes in the first bucket, where the usual fallback
+/// still applies. Returns `None` when the URL should be queued as is:
+/// every song shares one codec, there are no songs, or one has no URL of
+/// its own.
+pub fn bucket_by_best_codec(
+    tracks: &[CatalogTrack],
+    chain: &[SongCodec],
+) -> Option<Vec<CodecBucket>> {
+    if chain.is_empty() {
+        return None;
+    }
+    let mut buckets: Vec<CodecBucket> = chain
+        .iter()
+        .map(|codec| CodecBucket {
+            codec: codec.clone(),
+            urls: Vec::new(),
+        })
+        .collect();
+    for track in tracks {
+        let url = track.url.clone()?;
+        let best = if track.audio_traits.is_empty() {
+            0
+        } else {
+            chain
+                .iter()
+                .position(|codec| supports_codec(track, codec))
+                .unwrap_or(0)
+        };
+        buckets[best].urls.push(url);
+    }
+    buckets.retain(|b| !b.urls.is_empty());
+    (buckets.len() > 1).then_some(buckets)
+}
+
+/// Removes the unavailable songs from a request's URLs.
+///
+/// Returns the request to queue (its `urls` may be empty when nothing in
+/// it is available) and the songs left out. URLs that aren't catalog
+/// albums, songs or playlists, and URLs whose lookup fails, are kept.
+pub async fn filter_request(
+    app: &AppHandle,
+    request: DownloadRequest,
+) -> (DownloadRequest, Vec<SkippedTrack>) {
+    if !request
+        .urls
+        .iter()
+        .any(|u| catalog_service::parse_catalog_url(u).is_some())
+    {
+        return (request, Vec::new());
+    }
+
+    let token = match artist_expansion_service::musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            log::info!("Not checking track availability: {}", e);
+            return (request, Vec::new());
+        }
+    };
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Not checking track availability: {}", e);
+            return (request, Vec::new());
+        }
+    };
+
+    let mut urls = Vec::new();
+    let mut skipped = Vec::new();
+    for url in &request.urls {
+        let Some(resource) = catalog_service::parse_catalog_url(url) else {
+            urls.push(url.clone());
+            continue;
+        };
+        let filtered = match lookup(&client, &token, &resource).await {
+            Ok(tracks) => filter_unavailable(url, &tracks),
+            Err(e) => {
+                log::warn!("Could not check track availability of {}: {}", url, e);
+                urls.push(url.clone());
+                continue;
+            }
+        };
+        if !filtered.skipped.is_empty() {
+            log::info!(
+                "Skipping {} unavailable track(s) of {}",
+                filtered.skipped.len(),
+                url
+            );
+        }
+        urls.extend(filtered.urls);
+        skipped.extend(filtered.skipped);
+    }
+
+    (download_queue::with_urls(request, urls), skipped)
+}
+
+/// Splits a request into one request per codec, each song going to the
+/// first codec of `chain` it supports (see `bucket_by_best_codec()`).
+///
+/// URLs that aren't catalog albums, songs or playlists, that need no
+/// split, or whose lookup fails stay together in a first request with
+/// the original options. Each per-codec request pins its codec through
+/// `options.song_codec`. A request that needs no split is returned as is.
+pub async fn split_by_best_codec(
+    app: &AppHandle,
+    request: DownloadRequest,
+    chain: &[SongCodec],
+) -> Vec<DownloadRequest> {
+    if chain.len() < 2
+        || !request
+            .urls
+            .iter()
+            .any(|u| catalog_service::parse_catalog_url(u).is_some())
+    {
+        return vec![request];
+    }
+
+    let token = match artist_expansion_service::musickit_token(app) {
+        Ok(token) => token,
+        Err(e) => {
+            log::info!("Not picking codecs per track: {}", e);
+            return vec![request];
+        }
+    };
+    let client = match config_service::load_proxy_config(app).build_client() {
+        Ok(client) => client,
+        Err(e) => {
+            log::warn!("Not picking codecs per track: {}", e);
+            return vec![request];
+        }
+    };
+
+    let mut unsplit = Vec::new();
+    let mut by_codec: Vec<CodecBucket> = Vec::new();
+    for url in &request.urls {
+        let Some(resource) = catalog_service::parse_catalog_url(url) else {
+            unsplit.push(url.clone());
+            continue;
+        };
+        let buckets = match lookup(&client, &token, &resource).await {
+            Ok(tracks) => bucket_by_best_codec(&tracks, chain),
+            Err(e) => {
+                log::warn!("Could not look up the codecs of {}: {}", url, e);
+                None
+            }
+        };
+        let Some(buckets) = buckets else {
+            unsplit.push(url.clone());
+            continue;
+        };
+        for bucket in buckets {
+            log::info!(
+                "{} track(s) of {} in {}",
+                bucket.urls.len(),
+                url,
+                bucket.codec.display_name()
+            );
+            match by_codec.iter_mut().find(|b| b.codec == bucket.codec) {
+                Some(existing) => existing.urls.extend(bucket.urls),
+                None => by_codec.push(bucket),
+            }
+        }
+    }
+    if by_codec.is_empty() {
+        return vec![request];
+    }
+    by_codec.sort_by_key(|b| chain.iter().position(|c| *c == b.codec));
+    split_request(request, unsplit, by_codec)
+}
+
+/// Builds the requests of a split: `unsplit` URLs with the original
+/// options, then one request per codec bucket pinning its codec.
+///
+/// Each keeps the kind of the original request (`DownloadRequest::kind`),
+/// so the song URLs of a split album are still filed as an album rather
+/// than as singles.
+pub fn split_request(
+    request: DownloadRequest,
+    unsplit: Vec<String>,
+    by_codec: Vec<CodecBucket>,
+) -> Vec<DownloadRequest> {
+    let request = DownloadRequest {
+        kind: Some(download_queue::request_kind(&request)),
+        ..request
+    };
+    let mut requests = Vec::new();
+    if !unsplit.is_empty() {
+        requests.push(DownloadRequest {
+            urls: unsplit,
+            ..request.clone()
+        });
+    }
+    for bucket in by_codec {
+        let mut options = request.options.clone().unwrap_or_default();
+        options.song_codec = Some(bucket.codec);
+        requests.push(DownloadRequest {
+            urls: bucket.urls,
+            options: Some(options),
+            ..request.clone()
+        });
+    }
+    requests
+}
+
+/// Lists the songs of a catalog resource with their availability.
+async fn lookup(
+    client: &reqwest::Client,
+    token: &str,
+    resource: &CatalogResource,
+) -> Result<Vec<CatalogTrack>, String> {
+    let pages = catalog_service::fetch_catalog_pages(client, token, resource).await?;
+    Ok(pages.iter().flat_map(parse_catalog_tracks).collect())
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::download::DownloadKind;
+    use crate::models::settings::AppSettings;
+
+    const PLAYLIST_URL: &str =
+        "https://music.apple.com/us/playlist/hits/pl.f4d106fed2bd41149aaacabb233eb5eb";
+
+    fn track(id: &str, available: bool) -> CatalogTrack {
+        CatalogTrack {
+            id: id.to_string(),
+            name: format!("Track {}", id),
+            url: Some(format!("https://music.apple.com/us/song/s/{}", id)),
+            available,
+            audio_traits: vec!["lossy-stereo".to_string()],
+        }
+    }
+
+    fn track_with(id: &str, traits: &[&str]) -> CatalogTrack {
+        CatalogTrack {
+            audio_traits: traits.iter().map(|t| t.to_string()).collect(),
+            ..track(id, true)
+        }
+    }
+
+    /// Verifies that playable songs are told apart from ones listed
+    /// without `playParams`, including bare playlist entries.
+    #[test]
+    fn parses_availability_from_play_params() {
+        let page = serde_json::json!({
+            "data": [
+                { "id": "1", "type": "songs", "attributes": {
+                    "name": "Style", "url": "https://music.apple.com/us/song/style/1",
+                    "playParams": { "id": "1", "kind": "song" } } },
+                { "id": "2", "type": "songs", "attributes": {
+                    "name": "Gone", "url": "https://music.apple.com/us/song/gone/2" } },
+                { "id": "3", "type": "songs" },
+                { "type": "songs" }
+            ]
+        });
+        let tracks = parse_catalog_tracks(&page);
+        assert_eq!(tracks.len(), 3);
+        assert!(tracks[0].available);
+        assert_eq!(
+            tracks[0].url.as_deref(),
+            Some("https://music.apple.com/us/song/style/1")
+        );
+        assert!(!tracks[1].available);
+        assert_eq!(tracks[1].name, "Gone");
+        assert!(!tracks[2].available);
+        assert!(tracks[2].url.is_none());
+    }
+
+    /// Verifies that a URL whose songs are all available is kept as is.
+    #[test]
+    fn all_available_keeps_url() {
+        let filtered = filter_unavailable(PLAYLIST_URL, &[track("1", true), track("2", true)]);
+        assert_eq!(filtered.urls, vec![PLAYLIST_URL.to_string()]);
+        assert!(filtered.skipped.is_empty());
+    }
+
+    /// Verifies that a mix is narrowed to the available songs, in order,
+    /// and the others are reported as skipped with reason "unavailable".
+    #[test]
+    fn mixed_availability_skips_unavailable_tracks() {
+        let filtered = filter_unavailable(
+            PLAYLIST_URL,
+            &[
+                track("1", true),
+                track("2", false),
+                track("3", true),
+                track("4", false),
+            ],
+        );
+        assert_eq!(
+            filtered.urls,
+            vec![
+                "https://music.apple.com/us/song/s/1".to_string(),
+                "https://music.apple.com/us/song/s/3".to_string(),
+            ]
+        );
+        let skipped: Vec<(&str, &str)> = filtered
+            .skipped
+            .iter()
+            .map(|s| (s.id.as_str(), s.reason.as_str()))
+            .collect();
+        assert_eq!(
+            skipped,
+            vec![("2", REASON_UNAVAILABLE), ("4", REASON_UNAVAILABLE)]
+        );
+        assert_eq!(filtered.skipped[0].name, "Track 2");
+    }
+
+    /// Verifies that nothing is left to download when no song is available.
+    #[test]
+    fn none_available_leaves_no_urls() {
+        let filtered = filter_unavailable(PLAYLIST_URL, &[track("1", false), track("2", false)]);
+        assert!(filtered.urls.is_empty());
+        assert_eq!(filtered.skipped.len(), 2);
+    }
+
+    /// Verifies that the URL is kept when an available song has no URL of
+    /// its own to queue instead.
+    #[test]
+    fn available_track_without_url_keeps_url() {
+        let mut unaddressable = track("1", true);
+        unaddressable.url = None;
+        let filtered = filter_unavailable(PLAYLIST_URL, &[unaddressable, track("2", false)]);
+        assert_eq!(filtered.urls, vec![PLAYLIST_URL.to_string()]);
+        assert!(filtered.skipped.is_empty());
+    }
+
+    /// Verifies that the audio traits are read from the catalog response.
+    #[test]
+    fn parses_audio_traits() {
+        let page = serde_json::json!({
+            "data": [
+                { "id": "1", "type": "songs", "attributes": {
+                    "audioTraits": ["atmos", "lossless", "lossy-stereo", "spatial"] } },
+                { "id": "2", "type": "songs", "attributes": {} }
+            ]
+        });
+        let tracks = parse_catalog_tracks(&page);
+        assert_eq!(
+            tracks[0].audio_traits,
+            vec!["atmos", "lossless", "lossy-stereo", "spatial"]
+        );
+        assert!(tracks[1].audio_traits.is_empty());
+    }
+
+    /// Verifies which traits each codec needs.
+    #[test]
+    fn codec_support_follows_audio_traits() {
+        let stereo = track_with("1", &["lossy-stereo"]);
+        let lossless = track_with("2", &["lossless", "lossy-stereo"]);
+        let atmos = track_with("3", &["atmos", "spatial", "lossy-stereo"]);
+
+        assert!(!supports_codec(&stereo, &SongCodec::Alac));
+        assert!(supports_codec(&stereo, &SongCodec::Aac));
+        assert!(supports_codec(&stereo, &SongCodec::AacLegacy));
+        assert!(supports_codec(&lossless, &SongCodec::Alac));
+        assert!(!supports_codec(&lossless, &SongCodec::Atmos));
+        assert!(supports_codec(&atmos, &SongCodec::Atmos));
+        assert!(supports_codec(&atmos, &SongCodec::Ac3));
+        assert!(supports_codec(&atmos, &SongCodec::AacBinaural));
+        assert!(!supports_codec(&atmos, &SongCodec::Alac));
+    }
+
+    /// Verifies that an album with 8 lossless tracks and 2 AAC-only ones
+    /// is split into an ALAC bucket and an AAC bucket, in chain order.
+    #[test]
+    fn mixed_album_splits_into_two_buckets() {
+        let tracks: Vec<CatalogTrack> = (1..=10)
+            .map(|n| {
+                if n == 4 || n == 9 {
+                    track_with(&n.to_string(), &["lossy-stereo"])
+                } else {
+                    track_with(&n.to_string(), &["lossless", "lossy-stereo"])
+                }
+            })
+            .collect();
+        let chain = [SongCodec::Alac, SongCodec::Aac, SongCodec::AacLegacy];
+
+        let buckets = bucket_by_best_codec(&tracks, &chain).unwrap();
+
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].codec, SongCodec::Alac);
+        assert_eq!(buckets[0].urls.len(), 8);
+        assert_eq!(buckets[1].codec, SongCodec::Aac);
+        assert_eq!(
+            buckets[1].urls,
+            vec![
+                "https://music.apple.com/us/song/s/4".to_string(),
+                "https://music.apple.com/us/song/s/9".to_string(),
+            ]
+        );
+    }
+
+    /// Verifies that a URL is kept whole when every song shares one codec,
+    /// when a song's traits are unknown (it joins the first bucket), and
+    /// when a song has no URL to queue on its own.
+    #[test]
+    fn single_bucket_keeps_url() {
+        let chain = [SongCodec::Alac, SongCodec::Aac];
+
+        let all_lossless = [
+            track_with("1", &["lossless"]),
+            track_with("2", &["lossless"]),
+        ];
+        assert_eq!(bucket_by_best_codec(&all_lossless, &chain), None);
+
+        let unknown = [track_with("1", &["lossless"]), track_with("2", &[])];
+        assert_eq!(bucket_by_best_codec(&unknown, &chain), None);
+
+        let mut unaddressable = track_with("2", &["lossy-stereo"]);
+        unaddressable.url = None;
+        let tracks = [track_with("1", &["lossless"]), unaddressable];
+        assert_eq!(bucket_by_best_codec(&tracks, &chain), None);
+
+        assert_eq!(bucket_by_best_codec(&all_lossless, &[]), None);
+    }
+
+    /// Verifies that the per-codec jobs of a split album keep the album's
+    /// kind, so their song URLs get the album folder rather than the
+    /// singles folder.
+    #[test]
+    fn split_album_keeps_album_path() {
+        let request = DownloadRequest {
+            urls: vec!["https://music.apple.com/us/album/1989/1440935467".to_string()],
+            options: None,
+            label: None,
+            priority: 0,
+            fallback_chain_override: None,
+            kind: None,
+        };
+        let buckets = vec![
+            CodecBucket {
+                codec: SongCodec::Alac,
+                urls: vec!["https://music.apple.com/us/song/s/1".to_string()],
+            },
+            CodecBucket {
+                codec: SongCodec::Aac,
+                urls: vec!["https://music.apple.com/us/song/s/2".to_string()],
+            },
+        ];
+        let settings = AppSettings {
+            singles_folder_template: Some("{artist}/Singles".to_string()),
+            ..AppSettings::default()
+        };
+
+        let jobs = split_request(request, Vec::new(), buckets);
+
+        assert_eq!(jobs.len(), 2);
+        for job in &jobs {
+            assert_eq!(job.kind, Some(DownloadKind::Album));
+            let options = download_queue::merge_options(
+                job.options.as_ref(),
+                &settings,
+                download_queue::request_kind(job),
+            );
+            assert_eq!(
+                options.no_album_folder_template.as_deref(),
+                Some(settings.no_album_folder_template.as_str())
+            );
+        }
+    }
+}
@@ -0,0 +1,497 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// transcode_service.rs -- MP3/Opus copies of downloaded tracks
+// =============================================================
+//
+// Some devices (old car stereos, basic MP3 players) can't play M4A at all.
+// With `AppSettings::transcode_companion` set, every AAC or ALAC track a
+// download saved is also transcoded with FFmpeg to MP3 or Opus at the
+// configured bitrate, and saved next to it with a format suffix:
+//
+//   Artist/Album/
+//     01 Song Title.m4a          <- the download
+//     01 Song Title [MP3].mp3    <- the transcode companion
+//
+// Unlike the codec companions, this is not another GAMDL run: the M4A
+// already on disk is the source.
+//
+// ## What is carried over
+//
+//   - All tags (`-map_metadata 0`). FFmpeg maps the M4A atoms to ID3v2
+//     frames (written as ID3v2.3, the version old players read) or to
+//     Vorbis comments.
+//   - The embedded cover. MP3 copies it into an ID3 picture frame
+//     (`-c:v copy -disposition:v attached_pic`). Ogg can't carry a
+//     picture stream, so for Opus the cover is read from the M4A and
+//     written as a `METADATA_BLOCK_PICTURE` comment, passed to FFmpeg in
+//     an ffmetadata file (it is too large for a command-line argument).
+//
+// ## Gating
+//
+// Only runs when the item's `codec_used` is ALAC or one of the AAC
+// codecs; Dolby Atmos and AC-3 are multichannel and left alone. Like the
+// cover resize, only files written by this download are processed, and
+// each file is probed so Atmos companions in the same folder are skipped.
+//
+// @see download_queue.rs -- runs transcode_album() before FLAC conversion
+// @see https://ffmpeg.org/ffmpeg-codecs.html#libmp3lame-1
+// @see https://ffmpeg.org/ffmpeg-codecs.html#libopus-1
+
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use tauri::AppHandle;
+use tokio::process::Command;
+
+use crate::models::gamdl_options::SongCodec;
+use crate::models::settings::{AppSettings, TranscodeConfig, TranscodeFormat};
+use crate::services::{cover_resize_service, flac_conversion_service};
+
+/// FFmpeg audio codec names of the sources that are transcoded.
+const SOURCE_CODECS: [&str; 2] = ["aac", "alac"];
+
+/// Returns the transcode to run after a finished download, if any.
+///
+/// # Arguments
+/// * `settings` -- Current app settings (`transcode_companion`).
+/// * `codec_used` -- The item's `QueueItemStatus::codec_used` (CLI string).
+///
+/// # Returns
+/// The configured transcode for ALAC and AAC downloads; `None` when the
+/// setting is off or the download was Dolby Atmos or AC-3.
+pub fn should_transcode(
+    settings: &AppSettings,
+    codec_used: Option<&str>,
+) -> Option<TranscodeConfig> {
+    let config = settings.transcode_companion?;
+    let codec = codec_used?;
+    let transcodable = codec == SongCodec::Alac.to_cli_string() || codec.starts_with("aac");
+    transcodable.then_some(config)
+}
+
+/// Transcodes every AAC or ALAC `.m4a` under an album folder that was
+/// written at or after `since`.
+///
+/// # Arguments
+/// * `app` -- Tauri app handle (to locate the managed FFmpeg).
+/// * `settings` -- Current app settings (custom FFmpeg path).
+/// * `config` -- Format and bitrate of the copies.
+/// * `album_dir` -- Folder holding the downloaded tracks; searched
+///   recursively so multi-disc layouts are covered.
+/// * `since` -- When the download's GAMDL run started.
+///
+/// # Returns
+/// * `Ok(count)` -- Number of copies saved.
+/// * `Err(message)` -- FFmpeg is missing, or a transcode failed. Copies
+///   saved before the failure are kept.
+pub async fn transcode_album(
+    app: &AppHandle,
+    settings: &AppSettings,
+    config: &TranscodeConfig,
+    album_dir: &Path,
+    since: SystemTime,
+) -> Result<usize, String> {
+    let ffmpeg = flac_conversion_service::resolve_ffmpeg(app, settings, "the transcode companion")?;
+
+    let mut saved = 0;
+    for input in flac_conversion_service::find_m4a_files(album_dir) {
+        if !cover_resize_service::modified_since(&input, since) {
+            continue;
+        }
+        let codec = flac_conversion_service::probe_audio_codec(&ffmpeg, &input).await;
+        if !codec.as_deref().is_some_and(|c| SOURCE_CODECS.contains(&c)) {
+            log::debug!("Not transcoding {} ({:?} audio)", input.display(), codec);
+            continue;
+        }
+        transcode_file(&ffmpeg, &input, config).await?;
+        saved += 1;
+    }
+    Ok(saved)
+}
+
+/// `01 Song.m4a` -> `01 Song [MP3].mp3`, in the same folder.
+pub fn output_path(input: &Path, format: TranscodeFormat) -> PathBuf {
+    let stem = input.file_stem().unwrap_or_default().to_string_lossy();
+    input.with_file_name(format!(
+        "{} {}.{}",
+        stem,
+        format.suffix(),
+        format.extension()
+    ))
+}
+
+/// Builds the FFmpeg arguments for an M4A to MP3/Opus transcode.
+///
+/// Flags (MP3):
+///   -map 0:a:0 -map 0:v?             -- the audio stream and the cover
+///   -c:a libmp3lame -b:a {n}k        -- encode MP3 at the bitrate
+///   -c:v copy                        -- keep the cover image bytes as-is
+///   -disposition:v attached_pic      -- store the cover as an ID3 picture
+///   -id3v2_version 3                 -- ID3v2.3, which old players read
+///   -map_metadata 0                  -- copy all tags from the input
+///
+/// Flags (Opus):
+///   -f ffmetadata -i {cover}         -- the cover comment, if any
+///   -map 0:a:0                       -- the audio stream only
+///   -c:a libopus -b:a {n}k           -- encode Opus at the bitrate
+///   -map_metadata 0 [-map_metadata 1] -- the input's tags, plus the cover
+pub fn ffmpeg_transcode_args(
+    input: &Path,
+    output: &Path,
+    config: &TranscodeConfig,
+    cover_metadata: Option<&Path>,
+) -> Vec<String> {
+    let mut args: Vec<String> = ["-hide_banner", "-loglevel", "error", "-y", "-i"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    args.push(input.to_string_lossy().to_string());
+
+    let bitrate = format!("{}k", config.bitrate_kbps);
+    let rest: Vec<String> = match config.format {
+        TranscodeFormat::Mp3 => [
+            "-map",
+            "0:a:0",
+            "-map",
+            "0:v?",
+            "-c:a",
+            "libmp3lame",
+            "-b:a",
+            &bitrate,
+            "-c:v",
+            "copy",
+            "-disposition:v",
+            "attached_pic",
+            "-id3v2_version",
+            "3",
+            "-map_metadata",
+            "0",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect(),
+        TranscodeFormat::Opus => {
+            let mut rest = Vec::new();
+            if let Some(cover) = cover_metadata {
+                for arg in ["-f", "ffmetadata", "-i"] {
+                    rest.push(arg.to_string());
+                }
+                rest.push(cover.to_string_lossy().to_string());
+            }
+            for arg in [
+                "-map",
+                "0:a:0",
+                "-c:a",
+                "libopus",
+                "-b:a",
+                &bitrate,
+                "-map_metadata",
+                "0",
+            ] {
+                rest.push(arg.to_string());
+            }
+            if cover_metadata.is_some() {
+                // Later -map_metadata only add keys the input's tags lack
+                rest.push("-map_metadata".to_string());
+                rest.push("1".to_string());
+            }
+            rest
+        }
+    };
+    args.extend(rest);
+    args.push(output.to_string_lossy().to_string());
+    args
+}
+
+/// Transcodes one file, removing a partial copy on failure.
+async fn transcode_file(
+    ffmpeg: &Path,
+    input: &Path,
+    config: &TranscodeConfig,
+) -> Result<(), String> {
+    let output_path = output_path(input, config.format);
+    let cover_metadata = match config.format {
+        TranscodeFormat::Opus => write_cover_metadata(input, &output_path),
+        TranscodeFormat::Mp3 => None,
+    };
+
+    // A removed download aborts its companion task; kill_on_drop stops this
+    // FFmpeg with it rather than leave it writing a copy nobody waits for.
+    let output = Command::new(ffmpeg)
+        .args(ffmpeg_transcode_args(
+            input,
+            &output_path,
+            config,
+            cover_metadata.as_deref(),
+        ))
+        .kill_on_drop(true)
+        .output()
+        .await;
+    if let Some(ref path) = cover_metadata {
+        let _ = std::fs::remove_file(path);
+    }
+    let output = output.map_err(|e| format!("Failed to spawn FFmpeg: {}", e))?;
+
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&output_path);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "Transcode of {} failed: {}",
+            input.display(),
+            stderr.trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Writes the M4A's embedded cover as a `METADATA_BLOCK_PICTURE` comment
+/// to an ffmetadata file next to `output`, and returns its path. `None`
+/// when the track has no cover (or it can't be read or written).
+fn write_cover_metadata(input: &Path, output: &Path) -> Option<PathBuf> {
+    let tag = mp4ameta::Tag::read_from_path(input).ok()?;
+    let artwork = tag.artwork()?;
+    let mime = match artwork.fmt {
+        mp4ameta::ImgFmt::Jpeg => "image/jpeg",
+        mp4ameta::ImgFmt::Png => "image/png",
+        mp4ameta::ImgFmt::Bmp => "image/bmp",
+    };
+    let comment = base64_encode(&picture_block(mime, artwork.data));
+
+    let path = output.with_extension("ffmeta");
+    let content = format!(
+        ";FFMETADATA1\nMETADATA_BLOCK_PICTURE={}\n",
+        escape_ffmetadata(&comment)
+    );
+    match std::fs::write(&path, content) {
+        Ok(()) => Some(path),
+        Err(e) => {
+            log::debug!("Could not write the cover of {}: {}", input.display(), e);
+            None
+        }
+    }
+}
+
+/// Builds a FLAC `PICTURE` block (front cover) for the Vorbis comment
+/// `METADATA_BLOCK_PICTURE`. Width, height and colour depth are left 0
+/// (unknown), which players accept.
+///
+/// @see https://xiph.org/flac/format.html#metadata_block_picture
+fn picture_block(mime: &str, data: &[u8]) -> Vec<u8> {
+    let mut block = Vec::with_capacity(32 + mime.len() + data.len());
+    // Picture type 3: front cover
+    block.extend_from_slice(&3u32.to_be_bytes());
+    block.extend_from_slice(&(mime.len() as u32).to_be_bytes());
+    block.extend_from_slice(mime.as_bytes());
+    // Description (empty), width, height, colour depth, indexed colours
+    for _ in 0..5 {
+        block.extend_from_slice(&0u32.to_be_bytes());
+    }
+    block.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    block.extend_from_slice(data);
+    block
+}
+
+/// Standard Base64 with padding (RFC 4648), as Vorbis comments expect.
+fn base64_encode(data: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | ((b as u32) << (16 - 8 * i)));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[((n >> (18 - 6 * i)) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Escapes the characters the ffmetadata format treats specially.
+fn escape_ffmetadata(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the values following `flag` in `args`.
+    fn flag_value<'a>(args: &'a [String], flag: &str) -> Vec<&'a str> {
+        args.windows(2)
+            .filter(|w| w[0] == flag)
+            .map(|w| w[1].as_str())
+            .collect()
+    }
+
+    fn config(format: TranscodeFormat, bitrate_kbps: u32) -> TranscodeConfig {
+        TranscodeConfig {
+            format,
+            bitrate_kbps,
+        }
+    }
+
+    // ----------------------------------------------------------
+    // should_transcode()
+    // ----------------------------------------------------------
+
+    /// Verifies that ALAC and AAC downloads are transcoded once the setting
+    /// is on, and Atmos and AC-3 never are.
+    #[test]
+    fn transcodes_alac_and_aac_only() {
+        let mut settings = AppSettings::default();
+        assert_eq!(
+            should_transcode(&settings, Some("alac")),
+            None,
+            "Off by default"
+        );
+
+        let mp3 = config(TranscodeFormat::Mp3, 320);
+        settings.transcode_companion = Some(mp3);
+        for codec in ["alac", "aac", "aac-legacy", "aac-he", "aac-binaural"] {
+            assert_eq!(
+                should_transcode(&settings, Some(codec)),
+                Some(mp3),
+                "{}",
+                codec
+            );
+        }
+        for codec in ["atmos", "ac3"] {
+            assert_eq!(should_transcode(&settings, Some(codec)), None, "{}", codec);
+        }
+        assert_eq!(should_transcode(&settings, None), None);
+    }
+
+    // ----------------------------------------------------------
+    // output_path()
+    // ----------------------------------------------------------
+
+    /// Verifies the suffixed names of the copies.
+    #[test]
+    fn output_names_carry_the_format_suffix() {
+        let input = Path::new("/music/Artist/Album/01 Song.m4a");
+        assert_eq!(
+            output_path(input, TranscodeFormat::Mp3),
+            Path::new("/music/Artist/Album/01 Song [MP3].mp3")
+        );
+        assert_eq!(
+            output_path(input, TranscodeFormat::Opus),
+            Path::new("/music/Artist/Album/01 Song [Opus].opus")
+        );
+    }
+
+    // ----------------------------------------------------------
+    // ffmpeg_transcode_args()
+    // ----------------------------------------------------------
+
+    /// Verifies the MP3 command: LAME at the bitrate, the cover copied as
+    /// an ID3v2.3 picture, and the tags carried over.
+    #[test]
+    fn mp3_args_set_bitrate_and_keep_tags_and_cover() {
+        let args = ffmpeg_transcode_args(
+            Path::new("/music/01 Song.m4a"),
+            Path::new("/music/01 Song [MP3].mp3"),
+            &config(TranscodeFormat::Mp3, 320),
+            None,
+        );
+
+        assert_eq!(flag_value(&args, "-i"), vec!["/music/01 Song.m4a"]);
+        assert_eq!(flag_value(&args, "-map"), vec!["0:a:0", "0:v?"]);
+        assert_eq!(flag_value(&args, "-c:a"), vec!["libmp3lame"]);
+        assert_eq!(flag_value(&args, "-b:a"), vec!["320k"]);
+        assert_eq!(flag_value(&args, "-c:v"), vec!["copy"]);
+        assert_eq!(flag_value(&args, "-disposition:v"), vec!["attached_pic"]);
+        assert_eq!(flag_value(&args, "-id3v2_version"), vec!["3"]);
+        assert_eq!(flag_value(&args, "-map_metadata"), vec!["0"]);
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("/music/01 Song [MP3].mp3")
+        );
+    }
+
+    /// Verifies the Opus command: libopus at the bitrate, audio only, the
+    /// tags carried over, and the cover comment merged in when there is one.
+    #[test]
+    fn opus_args_set_bitrate_and_merge_cover_metadata() {
+        let input = Path::new("/music/01 Song.m4a");
+        let output = Path::new("/music/01 Song [Opus].opus");
+        let opus = config(TranscodeFormat::Opus, 160);
+
+        let args = ffmpeg_transcode_args(input, output, &opus, None);
+        assert_eq!(flag_value(&args, "-i"), vec!["/music/01 Song.m4a"]);
+        assert_eq!(flag_value(&args, "-map"), vec!["0:a:0"]);
+        assert_eq!(flag_value(&args, "-c:a"), vec!["libopus"]);
+        assert_eq!(flag_value(&args, "-b:a"), vec!["160k"]);
+        assert!(
+            flag_value(&args, "-c:v").is_empty(),
+            "Ogg has no picture stream"
+        );
+        assert_eq!(flag_value(&args, "-map_metadata"), vec!["0"]);
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("/music/01 Song [Opus].opus")
+        );
+
+        let cover = Path::new("/music/01 Song [Opus].ffmeta");
+        let args = ffmpeg_transcode_args(input, output, &opus, Some(cover));
+        assert_eq!(
+            flag_value(&args, "-i"),
+            vec!["/music/01 Song.m4a", "/music/01 Song [Opus].ffmeta"]
+        );
+        assert_eq!(flag_value(&args, "-f"), vec!["ffmetadata"]);
+        assert_eq!(flag_value(&args, "-map"), vec!["0:a:0"]);
+        assert_eq!(flag_value(&args, "-map_metadata"), vec!["0", "1"]);
+    }
+
+    // ----------------------------------------------------------
+    // Opus cover comment
+    // ----------------------------------------------------------
+
+    /// Verifies the encoder against the RFC 4648 test vectors.
+    #[test]
+    fn base64_matches_rfc_vectors() {
+        for (input, expected) in [
+            ("", ""),
+            ("f", "Zg=="),
+            ("fo", "Zm8="),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg=="),
+            ("fooba", "Zm9vYmE="),
+            ("foobar", "Zm9vYmFy"),
+        ] {
+            assert_eq!(base64_encode(input.as_bytes()), expected, "{:?}", input);
+        }
+    }
+
+    /// Verifies the layout of the picture block and the escaping of the
+    /// base64 padding in the ffmetadata file.
+    #[test]
+    fn picture_block_layout_and_escaping() {
+        let block = picture_block("image/png", &[0xAB, 0xCD]);
+        assert_eq!(&block[0..4], &3u32.to_be_bytes(), "Front cover");
+        assert_eq!(&block[4..8], &9u32.to_be_bytes());
+        assert_eq!(&block[8..17], b"image/png");
+        assert_eq!(&block[17..37], &[0u8; 20]);
+        assert_eq!(&block[37..41], &2u32.to_be_bytes());
+        assert_eq!(&block[41..], &[0xAB, 0xCD]);
+
+        assert_eq!(escape_ffmetadata("Zg=="), "Zg\\=\\=");
+        assert_eq!(escape_ffmetadata("a;b#c\\d"), "a\\;b\\#c\\\\d");
+    }
+}
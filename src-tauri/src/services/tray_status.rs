@@ -0,0 +1,179 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// tray_status.rs -- Live download status for the system tray menu
+// =================================================================
+//
+// The tray context menu contains a disabled "status" item (`downloads_status`,
+// built in `lib.rs`) that shows what the download queue is doing, e.g.:
+//
+//   Downloads: None
+//   Downloads: 3 queued
+//   Downloading: Taylor Swift - Anti-Hero (3 queued)
+//
+// ## Update mechanism
+//
+// A background task polls the queue once per second. Each poll holds the
+// queue mutex only long enough to copy a small `TraySnapshot` (a few counts
+// and one string); the label is formatted and the menu item is updated after
+// the lock has been released, so a slow main-thread menu update can never
+// stall the download pipeline.
+//
+// The menu item is only touched when the label actually changes. If the
+// update fails (the tray or menu was torn down, e.g. during shutdown), the
+// task logs the error and exits quietly instead of panicking.
+//
+// @see lib.rs -- Builds the tray menu and calls spawn_tray_status_updater()
+// @see download_queue.rs -- DownloadQueue::tray_snapshot()
+
+use std::time::Duration;
+
+use tauri::menu::MenuItem;
+use tauri::Runtime;
+
+use crate::services::download_queue::QueueHandle;
+
+/// How often the tray status task samples the download queue.
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Label shown when the queue has nothing active or waiting.
+pub const IDLE_LABEL: &str = "Downloads: None";
+
+/// A lock-free copy of the queue state needed to render the tray label.
+///
+/// Produced by `DownloadQueue::tray_snapshot()` while the queue lock is
+/// held, then formatted by `format_status_label()` after it is released.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TraySnapshot {
+    /// Human-readable description of the first active download: the
+    /// current "Artist - Title" when GAMDL has reported one, otherwise the
+    /// download's first URL. `None` when nothing is downloading.
+    pub active_label: Option<String>,
+    /// Number of downloads in the Downloading or Processing state.
+    pub active: usize,
+    /// Number of downloads waiting in the Queued state.
+    pub queued: usize,
+}
+
+/// Formats the tray status label for a queue snapshot.
+///
+/// | Active | Queued | Label                                         |
+/// |--------|--------|-----------------------------------------------|
+/// | 0      | 0      | `Downloads: None`                             |
+/// | 0      | n      | `Downloads: n queued`                         |
+/// | 1      | 0      | `Downloading: <item>`                         |
+/// | 1      | n      | `Downloading: <item> (n queued)`              |
+/// | k > 1  | n      | `Downloading: <item> +(k-1) more (n queued)`  |
+pub fn format_status_label(snapshot: &TraySnapshot) -> String {
+    if snapshot.active == 0 {
+        return if snapshot.queued == 0 {
+            IDLE_LABEL.to_string()
+        } else {
+            format!("Downloads: {} queued", snapshot.queued)
+        };
+    }
+
+    let item = snapshot.active_label.as_deref().unwrap_or("1 item");
+    let mut label = format!("Downloading: {}", item);
+    if snapshot.active > 1 {
+        label.push_str(&format!(" +{} more", snapshot.active - 1));
+    }
+    if snapshot.queued > 0 {
+        label.push_str(&format!(" ({} queued)", snapshot.queued));
+    }
+    label
+}
+
+/// Spawns the background task that keeps the tray status item in sync
+/// with the download queue.
+///
+/// Takes ownership of a clone of the `MenuItem` handle so the item stays
+/// addressable after `.setup()` returns. The task runs for the lifetime of
+/// the app, or until the menu item can no longer be updated.
+pub fn spawn_tray_status_updater<R: Runtime>(queue: QueueHandle, item: MenuItem<R>) {
+    tauri::async_runtime::spawn(async move {
+        let mut interval = tokio::time::interval(POLL_INTERVAL);
+        let mut last_label = IDLE_LABEL.to_string();
+
+        loop {
+            interval.tick().await;
+
+            // Copy the state out and release the lock before touching the menu
+            let snapshot = queue.lock().await.tray_snapshot();
+            let label = format_status_label(&snapshot);
+            if label == last_label {
+                continue;
+            }
+
+            if let Err(e) = item.set_text(&label) {
+                log::debug!("Tray status item unavailable, stopping updates: {}", e);
+                break;
+            }
+            last_label = label;
+        }
+    });
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a snapshot with the given counts and active item description.
+    fn snapshot(active_label: Option<&str>, active: usize, queued: usize) -> TraySnapshot {
+        TraySnapshot {
+            active_label: active_label.map(str::to_string),
+            active,
+            queued,
+        }
+    }
+
+    /// Verifies that an empty queue shows the idle label.
+    #[test]
+    fn label_for_empty_queue_is_idle() {
+        assert_eq!(format_status_label(&TraySnapshot::default()), "Downloads: None");
+    }
+
+    /// Verifies that waiting items are counted when nothing is active.
+    #[test]
+    fn label_for_queued_only() {
+        assert_eq!(
+            format_status_label(&snapshot(None, 0, 3)),
+            "Downloads: 3 queued"
+        );
+    }
+
+    /// Verifies the single active download label, with and without a backlog.
+    #[test]
+    fn label_for_single_active_download() {
+        assert_eq!(
+            format_status_label(&snapshot(Some("Taylor Swift - Anti-Hero"), 1, 0)),
+            "Downloading: Taylor Swift - Anti-Hero"
+        );
+        assert_eq!(
+            format_status_label(&snapshot(Some("Taylor Swift - Anti-Hero"), 1, 3)),
+            "Downloading: Taylor Swift - Anti-Hero (3 queued)"
+        );
+    }
+
+    /// Verifies that additional concurrent downloads are summarised.
+    #[test]
+    fn label_for_multiple_active_downloads() {
+        assert_eq!(
+            format_status_label(&snapshot(Some("Adele - Hello"), 3, 2)),
+            "Downloading: Adele - Hello +2 more (2 queued)"
+        );
+    }
+
+    /// Verifies the fallback text when the active item has no description.
+    #[test]
+    fn label_for_active_download_without_description() {
+        assert_eq!(
+            format_status_label(&snapshot(None, 1, 0)),
+            "Downloading: 1 item"
+        );
+    }
+}
@@ -38,6 +38,17 @@
 // its CLI interface in incompatible ways. The range [MIN_COMPATIBLE_GAMDL,
 // MAX_COMPATIBLE_GAMDL] defines the known-compatible window.
 //
+// ## Flag Drift
+//
+// The version range can't catch a GAMDL release that renames or drops a
+// single flag: GAMDL would then reject an argument we build in
+// `GamdlOptions::to_cli_args()`. Shortly after startup,
+// `spawn_gamdl_flag_check()` compares the flags a download with the current
+// settings would send against those listed by `gamdl --help`, and emits
+// `gamdl-flag-mismatch` with any GAMDL no longer accepts. The parsed help is
+// cached in `gamdl_help_flags.json` per GAMDL version, so the (slow) help
+// run only happens after GAMDL is installed or updated.
+//
 // ## References
 //
 // - PyPI JSON API: https://pypi.org/pypi/{package}/json
@@ -46,12 +57,19 @@
 // - Reqwest HTTP client: https://docs.rs/reqwest/latest/reqwest/
 // - Chrono for timestamps: https://docs.rs/chrono/latest/chrono/
 
+use std::collections::BTreeSet;
+use std::sync::LazyLock;
+
+use regex::Regex;
 use serde::{Deserialize, Serialize};
-use tauri::AppHandle;
+use tauri::{AppHandle, Emitter};
 
 // gamdl_service: provides get_gamdl_version() and check_latest_gamdl_version() for GAMDL update checks.
 // python_manager: provides get_installed_python_version() and get_target_python_version() for Python update checks.
-use crate::services::{gamdl_service, python_manager};
+// config_service: provides load_proxy_config() so update checks honor the user's proxy.
+// download_queue: provides merge_options() for the flags a download sends.
+use crate::models::download::DownloadKind;
+use crate::services::{config_service, download_queue, gamdl_service, python_manager};
 // platform: provides get_python_dir() for resolving the Python installation directory.
 use crate::utils::platform;
 
@@ -197,6 +215,145 @@ fn is_newer(current: &str, latest: &str) -> bool {
     l > c
 }
 
+// ============================================================
+// GAMDL CLI flag drift
+// ============================================================
+
+/// Event emitted when the installed GAMDL no longer lists flags we send.
+pub const FLAG_MISMATCH_EVENT: &str = "gamdl-flag-mismatch";
+
+/// Seconds to wait after startup before checking, so the frontend's event
+/// listeners are registered (the queue restore waits 2 seconds).
+const FLAG_CHECK_DELAY_SECS: u64 = 3;
+
+/// Matches a long option in `--help` output (`--song-codec`,
+/// `--no-config-file`), including both halves of click's
+/// `--flag / --no-flag` pairs. The option must not follow a word
+/// character, so `foo--bar` in prose doesn't count.
+static HELP_FLAG_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?:^|[^\w-])(--[A-Za-z0-9][\w-]*)").expect("Invalid help flag regex")
+});
+
+/// Payload of the `gamdl-flag-mismatch` event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GamdlFlagMismatch {
+    /// The installed GAMDL version whose help was checked.
+    pub gamdl_version: String,
+    /// Flags a download sends that this GAMDL doesn't list, sorted.
+    pub unsupported_flags: Vec<String>,
+}
+
+/// Contents of `gamdl_help_flags.json`: the flags parsed from one GAMDL
+/// version's `--help`.
+#[derive(Debug, Serialize, Deserialize)]
+struct HelpFlagsCache {
+    gamdl_version: String,
+    flags: BTreeSet<String>,
+}
+
+/// Extracts every long option (`--flag`) from GAMDL's `--help` output.
+pub fn parse_help_flags(help: &str) -> BTreeSet<String> {
+    HELP_FLAG_REGEX
+        .captures_iter(help)
+        .map(|caps| caps[1].to_string())
+        .collect()
+}
+
+/// Returns the flags among `args` that aren't in `supported`, sorted and
+/// without duplicates. Values (anything not starting with `--`) are
+/// ignored.
+pub fn unsupported_flags(args: &[String], supported: &BTreeSet<String>) -> Vec<String> {
+    args.iter()
+        .filter(|arg| arg.starts_with("--") && !supported.contains(arg.as_str()))
+        .cloned()
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect()
+}
+
+/// Compares the flags a download with the current settings would send
+/// (including the tool and config paths injected at spawn time) against
+/// the installed GAMDL's `--help`.
+///
+/// # Returns
+/// * `Ok(None)` - GAMDL isn't installed, or it accepts every flag.
+/// * `Ok(Some(mismatch))` - Flags GAMDL no longer lists.
+/// * `Err(message)` - The help could not be read.
+pub async fn check_gamdl_flags(app: &AppHandle) -> Result<Option<GamdlFlagMismatch>, String> {
+    let Some(version) = gamdl_service::get_gamdl_version(app).await? else {
+        return Ok(None);
+    };
+    let supported = supported_flags(app, &version).await?;
+
+    let settings = config_service::load_settings(app).unwrap_or_default();
+    let options = download_queue::merge_options(None, &settings, DownloadKind::Other);
+    let cmd = gamdl_service::build_gamdl_command_public(app, &[], &options)?;
+    let args: Vec<String> = cmd
+        .as_std()
+        .get_args()
+        .map(|arg| arg.to_string_lossy().to_string())
+        .collect();
+
+    let unsupported = unsupported_flags(&args, &supported);
+    Ok((!unsupported.is_empty()).then_some(GamdlFlagMismatch {
+        gamdl_version: version,
+        unsupported_flags: unsupported,
+    }))
+}
+
+/// Returns the flags the installed GAMDL accepts, from the cache when it
+/// was written for `version`, otherwise by running `gamdl --help` and
+/// caching the result.
+async fn supported_flags(app: &AppHandle, version: &str) -> Result<BTreeSet<String>, String> {
+    let cache_path = platform::get_app_data_dir(app).join("gamdl_help_flags.json");
+    let cached = std::fs::read_to_string(&cache_path)
+        .ok()
+        .and_then(|json| serde_json::from_str::<HelpFlagsCache>(&json).ok())
+        .filter(|cache| cache.gamdl_version == version && !cache.flags.is_empty());
+    if let Some(cache) = cached {
+        return Ok(cache.flags);
+    }
+
+    let flags = parse_help_flags(&gamdl_service::get_gamdl_help(app).await?);
+    if flags.is_empty() {
+        return Err("gamdl --help listed no options".to_string());
+    }
+    let cache = HelpFlagsCache {
+        gamdl_version: version.to_string(),
+        flags,
+    };
+    match serde_json::to_string_pretty(&cache) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&cache_path, json) {
+                log::debug!("Could not cache GAMDL help flags: {}", e);
+            }
+        }
+        Err(e) => log::debug!("Could not serialize GAMDL help flags: {}", e),
+    }
+    Ok(cache.flags)
+}
+
+/// Runs `check_gamdl_flags()` in the background shortly after startup,
+/// logging and emitting `gamdl-flag-mismatch` when GAMDL no longer accepts
+/// flags we send. Failures are only logged.
+pub fn spawn_gamdl_flag_check(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_secs(FLAG_CHECK_DELAY_SECS)).await;
+        match check_gamdl_flags(&app).await {
+            Ok(Some(mismatch)) => {
+                log::warn!(
+                    "GAMDL {} no longer accepts: {}",
+                    mismatch.gamdl_version,
+                    mismatch.unsupported_flags.join(", ")
+                );
+                let _ = app.emit(FLAG_MISMATCH_EVENT, &mismatch);
+            }
+            Ok(None) => log::debug!("GAMDL accepts every flag MeedyaDL sends"),
+            Err(e) => log::debug!("GAMDL flag check skipped: {}", e),
+        }
+    });
+}
+
 // ============================================================
 // Update check functions
 // ============================================================
@@ -260,7 +417,7 @@ async fn check_gamdl_update(app: &AppHandle) -> Result<ComponentUpdate, String>
     // Get the latest version from PyPI JSON API.
     // Queries https://pypi.org/pypi/gamdl/json and extracts info.version.
     // Returns None if the request failed (network error, PyPI down, etc.).
-    let latest = gamdl_service::check_latest_gamdl_version()
+    let latest = gamdl_service::check_latest_gamdl_version(&config_service::load_proxy_config(app))
         .await
         .ok();
 
@@ -311,7 +468,7 @@ async fn check_app_update(app: &AppHandle) -> Result<ComponentUpdate, String> {
     // Required headers:
     // - User-Agent: GitHub API requires a UA string (can be anything)
     // - Accept: Request v3 JSON format
-    let client = reqwest::Client::new();
+    let client = config_service::load_proxy_config(app).build_client()?;
     let response = client
         .get("https://api.github.com/repos/MeedyaDL/MeedyaDL/releases/latest")
         .header("User-Agent", "meedyadl")
@@ -465,4 +622,87 @@ mod tests {
         // Unparseable string: incompatible (safe default)
         assert!(!is_gamdl_compatible("invalid"));
     }
+
+    /// Excerpt of `gamdl --help` (click format) from a release that renamed
+    /// `--album-folder-template` to `--template-folder-album`.
+    const SAMPLE_HELP: &str = "\
+Usage: gamdl [OPTIONS] URLS...
+
+  Download Apple Music songs/music videos/posts.
+
+Options:
+  --disable-music-video-skip      Don't skip downloading music videos in
+                                  albums/playlists.
+  -s, --save-cover                Save cover as a separate file.
+  --overwrite                     Overwrite existing files.
+  --synced-lyrics-only / --no-synced-lyrics-only
+                                  Download only the synced lyrics.
+  --config-path FILE              Path to config file.
+  --log-level [DEBUG|INFO|WARNING|ERROR|CRITICAL]
+                                  Log level.  [default: INFO]
+  --song-codec [aac-legacy|aac-he-legacy|aac|alac|atmos]
+                                  Song codec.  [default: aac-legacy]
+  --template-folder-album TEXT    Folder template for albums.
+  -n, --no-config-file            Don't load the config file.
+  --help                          Show this message and exit.
+";
+
+    /// Verifies that long options are parsed from click's help layout,
+    /// including short-option prefixes and `--x / --no-x` pairs.
+    #[test]
+    fn parses_long_options_from_help() {
+        let flags = parse_help_flags(SAMPLE_HELP);
+        for flag in [
+            "--disable-music-video-skip",
+            "--save-cover",
+            "--synced-lyrics-only",
+            "--no-synced-lyrics-only",
+            "--log-level",
+            "--song-codec",
+            "--template-folder-album",
+            "--no-config-file",
+            "--help",
+        ] {
+            assert!(flags.contains(flag), "{} missing", flag);
+        }
+        assert_eq!(flags.len(), 11);
+    }
+
+    /// Verifies that sent flags missing from the help are reported once,
+    /// sorted, while values and accepted flags are ignored.
+    #[test]
+    fn reports_flags_the_help_no_longer_lists() {
+        let supported = parse_help_flags(SAMPLE_HELP);
+        let args: Vec<String> = [
+            "-m",
+            "gamdl",
+            "--song-codec",
+            "alac",
+            "--album-folder-template",
+            "{album_artist}/{album}",
+            "--use-album-date",
+            "--no-config-file",
+            "--album-folder-template",
+            "{album}",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        assert_eq!(
+            unsupported_flags(&args, &supported),
+            vec!["--album-folder-template", "--use-album-date"]
+        );
+    }
+
+    /// Verifies that a GAMDL accepting every sent flag reports nothing.
+    #[test]
+    fn no_mismatch_when_every_flag_is_listed() {
+        let supported = parse_help_flags(SAMPLE_HELP);
+        let args: Vec<String> = ["--song-codec", "atmos", "--overwrite"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(unsupported_flags(&args, &supported).is_empty());
+    }
 }
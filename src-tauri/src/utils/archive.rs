@@ -10,6 +10,11 @@
 //   2. **Extracting** downloaded archives in ZIP or TAR.GZ format into
 //      a destination directory on disk.
 //
+// Both steps can report their progress to a `ProgressFn` callback, which
+// the install services turn into `dependency-download-progress` and
+// `dependency-extract-progress` events (see
+// `dependency_manager::progress_events()`).
+//
 // These operations are used by:
 //   - `services::python_manager` -- to download and unpack the portable
 //     Python runtime from python-build-standalone GitHub releases.
@@ -35,12 +40,16 @@
 // Reference: https://docs.rs/reqwest/latest/reqwest/
 // Reference: https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html
 
+use std::io::Read;
 use std::path::Path;
+use std::sync::Arc;
 // `AsyncWriteExt` provides `.write_all()` and `.flush()` on Tokio's
 // async `File` type, enabling non-blocking writes during download streaming.
 // Reference: https://docs.rs/tokio/latest/tokio/io/trait.AsyncWriteExt.html
 use tokio::io::AsyncWriteExt;
 
+use crate::utils::proxy::ProxyConfig;
+
 /// Supported archive formats for dependency downloads.
 ///
 /// This enum is used by [`download_and_extract`] to select the correct
@@ -64,6 +73,41 @@ pub enum ArchiveFormat {
     TarGz,
 }
 
+/// Which step of [`download_and_extract`] a progress report is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProgressStage {
+    /// [`download_file`]: `done` counts bytes received.
+    Download,
+    /// [`extract_zip`] / [`extract_tar_gz`]: `done` counts ZIP entries
+    /// extracted, or bytes of the TAR.GZ archive read.
+    Extract,
+}
+
+/// How far a download or extraction has got: `done` out of `total`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Units (bytes or entries) done so far.
+    pub done: u64,
+    /// Units in all, or `None` when unknown (a download without a
+    /// `Content-Length` header).
+    pub total: Option<u64>,
+}
+
+impl Progress {
+    /// Percentage done (0-100), or `None` when the total is unknown.
+    pub fn percent(&self) -> Option<f64> {
+        match self.total {
+            Some(0) => Some(100.0),
+            Some(total) => Some(self.done.min(total) as f64 * 100.0 / total as f64),
+            None => None,
+        }
+    }
+}
+
+/// Receives the progress reports of a download or extraction. Called from
+/// the extraction's blocking thread too, hence `Send + Sync`.
+pub type ProgressFn = Arc<dyn Fn(ProgressStage, Progress) + Send + Sync>;
+
 /// Downloads a file from a URL to a local path using streaming I/O.
 ///
 /// Writes chunks to disk as they arrive via `reqwest`'s `.chunk()` iterator
@@ -75,12 +119,19 @@ pub enum ArchiveFormat {
 /// download size is determined from the HTTP `Content-Length` header; if the
 /// server does not provide it, progress percentages are not logged.
 ///
+/// With `progress` set, every chunk is also reported to it. Without a
+/// `Content-Length` the reports have no total (indeterminate) until the
+/// last one, sent once the body is complete, whose total is the size
+/// received, so that a progress bar always ends at 100%.
+///
 /// Parent directories are created automatically if they do not exist.
 ///
 /// # Arguments
 /// * `url` - The HTTP(S) URL to download from. Redirects are followed
 ///   automatically by `reqwest`.
 /// * `dest` - The local file path to write the downloaded content to.
+/// * `proxy` - Proxy settings for the request (see `utils::proxy`).
+/// * `progress` - Optional receiver of `ProgressStage::Download` reports.
 ///
 /// # Returns
 /// * `Ok(total_bytes)` - The total number of bytes written to disk.
@@ -88,10 +139,15 @@ pub enum ArchiveFormat {
 ///   (DNS resolution, HTTP error, I/O error, etc.).
 ///
 /// # Reference
-/// - `reqwest::get`: <https://docs.rs/reqwest/latest/reqwest/fn.get.html>
+/// - `reqwest::Client`: <https://docs.rs/reqwest/latest/reqwest/struct.Client.html>
 /// - `Response::chunk`: <https://docs.rs/reqwest/latest/reqwest/struct.Response.html#method.chunk>
 /// - `tokio::fs::File`: <https://docs.rs/tokio/latest/tokio/fs/struct.File.html>
-pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
+pub async fn download_file(
+    url: &str,
+    dest: &Path,
+    proxy: &ProxyConfig,
+    progress: Option<&ProgressFn>,
+) -> Result<u64, String> {
     log::info!("Downloading: {} -> {}", url, dest.display());
 
     // Create parent directories if they don't exist
@@ -100,12 +156,14 @@ pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
             .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
     }
 
-    // Send an HTTP GET request using the default reqwest client.
-    // `reqwest::get()` creates a one-shot client, follows redirects (up to
-    // 10 by default), and returns the response with the body not yet
-    // consumed. The `mut` is needed because `.chunk()` below advances
-    // through the response body.
-    let mut response = reqwest::get(url)
+    // Send an HTTP GET request through a proxy-aware client. The client
+    // follows redirects (up to 10 by default) and returns the response with
+    // the body not yet consumed. The `mut` is needed because `.chunk()`
+    // below advances through the response body.
+    let client = proxy.build_client()?;
+    let mut response = client
+        .get(url)
+        .send()
         .await
         .map_err(|e| format!("Failed to start download from {}: {}", url, e))?;
 
@@ -117,6 +175,13 @@ pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
 
     // Get total size for progress reporting (0 if server doesn't provide Content-Length)
     let total_size = response.content_length().unwrap_or(0);
+    let report = |done: u64, total: Option<u64>| {
+        if let Some(progress) = progress {
+            progress(ProgressStage::Download, Progress { done, total });
+        }
+    };
+    let known_total = response.content_length();
+    report(0, known_total);
     if total_size > 0 {
         log::info!(
             "Download size: {:.1} MB",
@@ -147,6 +212,7 @@ pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
             .map_err(|e| format!("Failed to write to {}: {}", dest.display(), e))?;
 
         downloaded += chunk.len() as u64;
+        report(downloaded, known_total);
 
         // Log progress at every 10% milestone
         if total_size > 0 {
@@ -168,6 +234,9 @@ pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
         .await
         .map_err(|e| format!("Failed to flush file {}: {}", dest.display(), e))?;
 
+    // The final report always has a total, so the percentage reaches 100
+    report(downloaded, Some(downloaded));
+
     log::info!("Download complete: {:.1} MB", downloaded as f64 / 1_048_576.0);
     Ok(downloaded)
 }
@@ -195,6 +264,8 @@ pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
 /// # Arguments
 /// * `archive_path` - Path to the ZIP file to extract.
 /// * `dest` - Directory to extract contents into (created if it doesn't exist).
+/// * `progress` - Optional receiver of `ProgressStage::Extract` reports,
+///   counting entries.
 ///
 /// # Returns
 /// * `Ok(())` on successful extraction.
@@ -204,7 +275,11 @@ pub async fn download_file(url: &str, dest: &Path) -> Result<u64, String> {
 /// - `ZipArchive::new`: <https://docs.rs/zip/latest/zip/read/struct.ZipArchive.html#method.new>
 /// - `ZipFile::enclosed_name`: <https://docs.rs/zip/latest/zip/read/struct.ZipFile.html#method.enclosed_name>
 /// - `spawn_blocking`: <https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html>
-pub async fn extract_zip(archive_path: &Path, dest: &Path) -> Result<(), String> {
+pub async fn extract_zip(
+    archive_path: &Path,
+    dest: &Path,
+    progress: Option<ProgressFn>,
+) -> Result<(), String> {
     log::info!(
         "Extracting ZIP: {} -> {}",
         archive_path.display(),
@@ -236,9 +311,19 @@ pub async fn extract_zip(archive_path: &Path, dest: &Path) -> Result<(), String>
 
         let total_entries = archive.len();
         log::info!("ZIP contains {} entries", total_entries);
+        let report = |done: usize| {
+            if let Some(progress) = &progress {
+                let (done, total) = (done as u64, Some(total_entries as u64));
+                progress(ProgressStage::Extract, Progress { done, total });
+            }
+        };
+        report(0);
 
         // Extract each entry in the archive
         for i in 0..total_entries {
+            if i > 0 {
+                report(i);
+            }
             let mut entry = archive
                 .by_index(i)
                 .map_err(|e| format!("Failed to read ZIP entry {}: {}", i, e))?;
@@ -302,6 +387,7 @@ pub async fn extract_zip(archive_path: &Path, dest: &Path) -> Result<(), String>
             }
         }
 
+        report(total_entries);
         log::info!("ZIP extraction complete: {} entries", total_entries);
         Ok(())
     })
@@ -321,6 +407,9 @@ pub async fn extract_zip(archive_path: &Path, dest: &Path) -> Result<(), String>
 /// File permissions and ownership metadata are automatically preserved by
 /// the `tar` crate on Unix systems (via `set_preserve_permissions(true)`).
 ///
+/// A tar stream has no index of its entries, so progress is measured in
+/// bytes of the compressed file read so far, out of its size.
+///
 /// # Threading
 /// Like [`extract_zip`], the `flate2` and `tar` crates perform synchronous
 /// I/O, so extraction is wrapped in `tokio::task::spawn_blocking()`.
@@ -328,6 +417,8 @@ pub async fn extract_zip(archive_path: &Path, dest: &Path) -> Result<(), String>
 /// # Arguments
 /// * `archive_path` - Path to the `.tar.gz` file to extract.
 /// * `dest` - Directory to extract contents into (created if it doesn't exist).
+/// * `progress` - Optional receiver of `ProgressStage::Extract` reports,
+///   counting compressed bytes.
 ///
 /// # Returns
 /// * `Ok(())` on successful extraction.
@@ -337,7 +428,11 @@ pub async fn extract_zip(archive_path: &Path, dest: &Path) -> Result<(), String>
 /// - `GzDecoder`: <https://docs.rs/flate2/latest/flate2/read/struct.GzDecoder.html>
 /// - `Archive::unpack`: <https://docs.rs/tar/latest/tar/struct.Archive.html#method.unpack>
 /// - `spawn_blocking`: <https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html>
-pub async fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<(), String> {
+pub async fn extract_tar_gz(
+    archive_path: &Path,
+    dest: &Path,
+    progress: Option<ProgressFn>,
+) -> Result<(), String> {
     log::info!(
         "Extracting TAR.GZ: {} -> {}",
         archive_path.display(),
@@ -360,6 +455,16 @@ pub async fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<(), Stri
         // decompressed tar file to disk.
         let file = std::fs::File::open(&archive_path)
             .map_err(|e| format!("Failed to open archive {}: {}", archive_path.display(), e))?;
+        let total = file.metadata().map(|m| m.len()).ok();
+        let file = ProgressReader {
+            inner: file,
+            done: 0,
+            total,
+            progress: progress.clone(),
+        };
+        if let Some(progress) = &progress {
+            progress(ProgressStage::Extract, Progress { done: 0, total });
+        }
         // `GzDecoder` implements `Read` and transparently decompresses the
         // gzip stream on each read call. It detects the gzip header
         // automatically.
@@ -384,6 +489,10 @@ pub async fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<(), Stri
             .unpack(&dest)
             .map_err(|e| format!("Failed to extract tar.gz archive: {}", e))?;
 
+        if let (Some(progress), Some(total)) = (&progress, total) {
+            let (done, total) = (total, Some(total));
+            progress(ProgressStage::Extract, Progress { done, total });
+        }
         log::info!("TAR.GZ extraction complete to {}", dest.display());
         Ok(())
     })
@@ -391,6 +500,29 @@ pub async fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<(), Stri
     .map_err(|e| format!("TAR.GZ extraction task panicked: {}", e))?
 }
 
+/// A `Read` wrapper that reports the bytes read through it as
+/// `ProgressStage::Extract` progress.
+struct ProgressReader<R> {
+    inner: R,
+    done: u64,
+    total: Option<u64>,
+    progress: Option<ProgressFn>,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        if n > 0 {
+            self.done += n as u64;
+            if let Some(progress) = &self.progress {
+                let (done, total) = (self.done, self.total);
+                progress(ProgressStage::Extract, Progress { done, total });
+            }
+        }
+        Ok(n)
+    }
+}
+
 /// Downloads a file from a URL and extracts it to the destination directory.
 ///
 /// This is the **primary entry point** for installing dependencies. It
@@ -410,6 +542,9 @@ pub async fn extract_tar_gz(archive_path: &Path, dest: &Path) -> Result<(), Stri
 /// * `dest` - The directory to extract the archive contents into.
 /// * `format` - The expected archive format ([`ArchiveFormat::Zip`] or
 ///   [`ArchiveFormat::TarGz`]).
+/// * `proxy` - Proxy settings for the download (see `utils::proxy`).
+/// * `progress` - Optional receiver of the download's and then the
+///   extraction's progress reports.
 ///
 /// # Returns
 /// * `Ok(())` if both download and extraction succeeded.
@@ -422,6 +557,8 @@ pub async fn download_and_extract(
     url: &str,
     dest: &Path,
     format: ArchiveFormat,
+    proxy: &ProxyConfig,
+    progress: Option<ProgressFn>,
 ) -> Result<(), String> {
     // Derive a temp file name from the last path segment of the URL.
     // For example, "https://github.com/.../python-3.12.tar.gz" yields
@@ -436,12 +573,12 @@ pub async fn download_and_extract(
     let temp_file = temp_dir.join(file_name);
 
     // Step 1: Download the archive to the temp file
-    download_file(url, &temp_file).await?;
+    download_file(url, &temp_file, proxy, progress.as_ref()).await?;
 
     // Step 2: Extract the archive to the destination
     let result = match format {
-        ArchiveFormat::Zip => extract_zip(&temp_file, dest).await,
-        ArchiveFormat::TarGz => extract_tar_gz(&temp_file, dest).await,
+        ArchiveFormat::Zip => extract_zip(&temp_file, dest, progress).await,
+        ArchiveFormat::TarGz => extract_tar_gz(&temp_file, dest, progress).await,
     };
 
     // Step 3: Clean up the temporary file (best-effort)
@@ -502,3 +639,148 @@ pub fn set_executable(path: &Path) -> Result<(), String> {
     }
     Ok(())
 }
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+    use std::sync::Mutex;
+    use tokio::io::AsyncReadExt;
+
+    /// Serves `body` once over HTTP on a local port, in 4 KiB writes, with
+    /// or without a `Content-Length` header. Returns the URL to fetch.
+    async fn serve_once(body: Vec<u8>, content_length: bool) -> String {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", 0))
+            .await
+            .unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut buf).await.unwrap();
+                if n == 0 {
+                    return;
+                }
+                request.extend_from_slice(&buf[..n]);
+            }
+            let mut head = "HTTP/1.1 200 OK\r\nConnection: close\r\n".to_string();
+            if content_length {
+                head.push_str(&format!("Content-Length: {}\r\n", body.len()));
+            }
+            head.push_str("\r\n");
+            stream.write_all(head.as_bytes()).await.unwrap();
+            for chunk in body.chunks(4096) {
+                stream.write_all(chunk).await.unwrap();
+                stream.flush().await.unwrap();
+                tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+            }
+        });
+        format!("http://127.0.0.1:{}/tool.zip", port)
+    }
+
+    /// Reports collected by a [`recorder`] callback.
+    type Reports = Arc<Mutex<Vec<(ProgressStage, Progress)>>>;
+
+    /// A progress callback that records every report.
+    fn recorder() -> (ProgressFn, Reports) {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let sink = reports.clone();
+        let progress: ProgressFn = Arc::new(move |stage, progress| {
+            sink.lock().unwrap().push((stage, progress));
+        });
+        (progress, reports)
+    }
+
+    /// Verifies that a download with a `Content-Length` reports its
+    /// percentages in order, never going back, up to 100%.
+    #[tokio::test]
+    async fn download_progress_is_monotonic_and_reaches_100() {
+        let body: Vec<u8> = (0..200_000u32).map(|i| i as u8).collect();
+        let url = serve_once(body.clone(), true).await;
+        let (progress, reports) = recorder();
+        let dir = TestDir::new("archive");
+        let dest = dir.join("download");
+
+        let size = download_file(&url, &dest, &ProxyConfig::default(), Some(&progress))
+            .await
+            .unwrap();
+        assert_eq!(size, body.len() as u64);
+        assert_eq!(std::fs::read(&dest).unwrap(), body);
+
+        let reports = reports.lock().unwrap();
+        assert!(reports.len() > 2, "{} reports", reports.len());
+        assert!(reports.iter().all(|(stage, p)| {
+            *stage == ProgressStage::Download && p.total == Some(body.len() as u64)
+        }));
+        let percents: Vec<f64> = reports.iter().map(|(_, p)| p.percent().unwrap()).collect();
+        assert_eq!(percents[0], 0.0);
+        assert!(percents.windows(2).all(|w| w[0] <= w[1]), "{:?}", percents);
+        assert_eq!(*percents.last().unwrap(), 100.0);
+    }
+
+    /// Verifies that a download without a `Content-Length` reports no
+    /// percentage until the last report, which is 100%.
+    #[tokio::test]
+    async fn download_progress_without_length_is_indeterminate() {
+        let body = vec![7u8; 50_000];
+        let url = serve_once(body.clone(), false).await;
+        let (progress, reports) = recorder();
+        let dir = TestDir::new("archive");
+        let dest = dir.join("download");
+
+        download_file(&url, &dest, &ProxyConfig::default(), Some(&progress))
+            .await
+            .unwrap();
+
+        let reports = reports.lock().unwrap();
+        let (last, rest) = reports.split_last().unwrap();
+        assert!(!rest.is_empty());
+        assert!(rest.iter().all(|(_, p)| p.percent().is_none()));
+        assert!(rest.windows(2).all(|w| w[0].1.done <= w[1].1.done));
+        assert_eq!(last.1.done, body.len() as u64);
+        assert_eq!(last.1.percent(), Some(100.0));
+    }
+
+    /// Verifies that extracting a TAR.GZ reports monotonic extraction
+    /// progress up to 100%.
+    #[tokio::test]
+    async fn extract_progress_reaches_100() {
+        let dir = TestDir::new("archive");
+        let archive_path = dir.join("tool.tar.gz");
+        {
+            let file = std::fs::File::create(&archive_path).unwrap();
+            let encoder = flate2::write::GzEncoder::new(file, flate2::Compression::fast());
+            let mut builder = tar::Builder::new(encoder);
+            let data: Vec<u8> = (0..300_000u32).map(|i| (i * 7919 % 251) as u8).collect();
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o755);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, "tool/bin/tool", data.as_slice())
+                .unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let dest = dir.join("extracted");
+        let (progress, reports) = recorder();
+
+        extract_tar_gz(&archive_path, &dest, Some(progress))
+            .await
+            .unwrap();
+        assert!(dest.join("tool/bin/tool").exists());
+
+        let reports = reports.lock().unwrap();
+        assert!(reports
+            .iter()
+            .all(|(stage, _)| *stage == ProgressStage::Extract));
+        let percents: Vec<f64> = reports.iter().map(|(_, p)| p.percent().unwrap()).collect();
+        assert!(percents.windows(2).all(|w| w[0] <= w[1]), "{:?}", percents);
+        assert_eq!(*percents.last().unwrap(), 100.0);
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// File tree helpers.
+// ===================
+//
+// Post-processing steps (FLAC conversion, transcodes, cover resizing,
+// lyrics conversion, audio verification, companion lyrics) all look for
+// the files of an album folder by walking it. `walk_files()` is the one
+// walker they share.
+//
+// The walk never follows symbolic links: a link to a parent folder would
+// otherwise recurse until the stack overflows, and a link out of the album
+// folder would pull in files the download didn't write. Links are skipped
+// whatever they point at.
+//
+// The walk does blocking I/O; async callers run it inside
+// `tokio::task::spawn_blocking`.
+
+use std::path::{Path, PathBuf};
+
+/// Recursively lists the files under `dir` that `filter` accepts, sorted.
+/// Symbolic links are neither followed nor listed. Folders that can't be
+/// read (including a missing `dir`) are skipped.
+pub fn walk_files(dir: &Path, filter: impl Fn(&Path) -> bool) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect(dir, &filter, &mut files);
+    files.sort();
+    files
+}
+
+/// Adds the accepted files under `dir` to `files`.
+fn collect(dir: &Path, filter: &impl Fn(&Path) -> bool, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        // DirEntry::file_type() doesn't follow links, unlike Path::is_dir()
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect(&path, filter, files);
+        } else if file_type.is_file() && filter(&path) {
+            files.push(path);
+        }
+    }
+}
+
+/// Returns whether `path` has one of `extensions` (case-insensitive).
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)))
+}
+
+// ============================================================
+// Unit Tests
+// ============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_dir::TestDir;
+
+    /// Verifies that files are found in nested folders, filtered and
+    /// sorted, and that a missing folder yields nothing.
+    #[test]
+    fn walks_nested_folders() {
+        let dir = TestDir::new("walk");
+        std::fs::create_dir_all(dir.join("Disc 2")).unwrap();
+        std::fs::write(dir.join("02 B.m4a"), "x").unwrap();
+        std::fs::write(dir.join("01 A.M4A"), "x").unwrap();
+        std::fs::write(dir.join("cover.jpg"), "x").unwrap();
+        std::fs::write(dir.join("Disc 2").join("01 C.m4a"), "x").unwrap();
+
+        let files = walk_files(&dir, |p| has_extension(p, &["m4a"]));
+        assert_eq!(
+            files,
+            vec![
+                dir.join("01 A.M4A"),
+                dir.join("02 B.m4a"),
+                dir.join("Disc 2").join("01 C.m4a"),
+            ]
+        );
+        assert_eq!(walk_files(&dir, |_| true).len(), 4);
+        assert!(walk_files(&dir.join("missing"), |_| true).is_empty());
+    }
+
+    /// Verifies that a link back to a parent folder isn't followed (it
+    /// would recurse forever) and that linked files aren't listed.
+    #[cfg(unix)]
+    #[test]
+    fn does_not_follow_symlinks() {
+        let dir = TestDir::new("walk");
+        std::fs::create_dir_all(dir.join("album")).unwrap();
+        std::fs::write(dir.join("album").join("01 A.m4a"), "x").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("album").join("loop")).unwrap();
+        std::os::unix::fs::symlink(
+            dir.join("album").join("01 A.m4a"),
+            dir.join("album").join("link.m4a"),
+        )
+        .unwrap();
+
+        let files = walk_files(&dir, |_| true);
+        assert_eq!(files, vec![dir.join("album").join("01 A.m4a")]);
+    }
+}
@@ -4,7 +4,7 @@
 // Utility modules providing cross-cutting concerns.
 // ==================================================
 //
-// This module aggregates three utility sub-modules that are used throughout
+// This module aggregates five utility sub-modules that are used throughout
 // the application by both the `commands` and `services` layers. None of
 // these modules hold state; they are purely functional helpers.
 //
@@ -13,6 +13,9 @@
 //   +-- platform.rs   -- OS detection and path resolution
 //   +-- archive.rs    -- HTTP download + archive extraction (ZIP, TAR.GZ)
 //   +-- process.rs    -- GAMDL subprocess output parsing (regex-based)
+//   +-- proxy.rs      -- HTTP(S) proxy settings for subprocesses and reqwest
+//   +-- files.rs      -- Recursive file listing for post-processing steps
+//   +-- test_dir.rs   -- Self-deleting scratch folders (tests only)
 //
 // These utilities are imported by services like `python_manager`,
 // `gamdl_service`, and `dependency_manager` to perform platform-specific
@@ -34,7 +37,8 @@ pub mod platform;
 /// Provides an async `download_file()` that streams HTTP responses to disk
 /// with progress logging, plus format-specific extractors (`extract_zip`,
 /// `extract_tar_gz`) that run synchronous I/O on Tokio's blocking thread
-/// pool. The high-level `download_and_extract()` combines both steps.
+/// pool. The high-level `download_and_extract()` combines both steps, and
+/// reports the progress of each to an optional `ProgressFn` callback.
 ///
 /// Used by: `services::python_manager`, `services::dependency_manager`
 pub mod archive;
@@ -48,3 +52,30 @@ pub mod archive;
 ///
 /// Used by: `services::gamdl_service`, `services::download_queue`
 pub mod process;
+
+/// HTTP(S) proxy configuration derived from `AppSettings`.
+///
+/// Injects `http_proxy` / `https_proxy` environment variables into spawned
+/// subprocesses, builds proxy-aware `reqwest` clients, and validates proxy
+/// URLs entered in Settings.
+///
+/// Used by: `services::gamdl_service`, `services::update_checker`,
+///          `utils::archive`, `commands::settings`
+pub mod proxy;
+
+/// Recursive file listing shared by the post-processing steps.
+///
+/// `walk_files()` lists the files under a folder that a filter accepts,
+/// without following symbolic links; `has_extension()` is the usual
+/// filter.
+///
+/// Used by: `services::audio_verify_service`, `services::flac_conversion_service`,
+///          `services::lyrics_conversion_service`, `services::companion_lyrics_service`,
+///          `services::mv_lyrics_service`
+pub mod files;
+
+/// Self-deleting scratch folders for unit tests (`TestDir`).
+///
+/// Used by: the file system tests of `services` and `utils`
+#[cfg(test)]
+pub mod test_dir;
@@ -239,6 +239,433 @@ pub fn get_gamdl_config_path(app: &AppHandle) -> PathBuf {
     get_gamdl_data_dir(app).join("config.ini")
 }
 
+/// Resolves the directory to show in the OS file manager for a download's
+/// output path.
+///
+/// GAMDL reports a file path for single tracks and a folder path for albums,
+/// so the same "reveal" action has to handle both: a file resolves to its
+/// parent directory, a directory resolves to itself.
+///
+/// # Arguments
+/// * `output_path` - The download's recorded output path.
+///
+/// # Returns
+/// * `Ok(dir)` - The existing directory to open.
+/// * `Err(message)` - If the path no longer exists (e.g., the files were
+///   moved or deleted after the download finished).
+///
+/// # Connection
+/// Called by `commands::gamdl::reveal_output()`.
+pub fn resolve_reveal_dir(output_path: &Path) -> Result<PathBuf, String> {
+    if output_path.is_dir() {
+        return Ok(output_path.to_path_buf());
+    }
+
+    if output_path.is_file() {
+        if let Some(parent) = output_path.parent().filter(|p| p.is_dir()) {
+            return Ok(parent.to_path_buf());
+        }
+    }
+
+    Err(format!(
+        "Output no longer exists at {} (it may have been moved or deleted)",
+        output_path.display()
+    ))
+}
+
+/// Checks that `path` can be used as a download's output folder: it must
+/// be absolute, be (or become) a directory, and accept new files. A missing
+/// folder is created, as GAMDL would.
+///
+/// Writability is tested by creating and removing a probe file, which also
+/// catches read-only mounts and ACLs that permission bits don't show. The
+/// backend writes with `std::fs`, which the Tauri fs plugin scope doesn't
+/// govern, and the capabilities configure no custom scope, so there is no
+/// further scope to check against.
+///
+/// # Returns
+/// * `Ok(dir)` - The folder, ready to receive files.
+/// * `Err(message)` - Naming the path and the reason it can't be used.
+///
+/// # Connection
+/// Called by `commands::gamdl::set_download_output_path()`.
+pub fn ensure_writable_dir(path: &Path) -> Result<PathBuf, String> {
+    if !path.is_absolute() {
+        return Err(format!("{} is not an absolute path", path.display()));
+    }
+    std::fs::create_dir_all(path)
+        .map_err(|e| format!("Cannot use {} as an output folder: {}", path.display(), e))?;
+
+    let probe = path.join(format!(".meedyadl-write-test-{}", uuid::Uuid::new_v4()));
+    std::fs::write(&probe, b"")
+        .map_err(|e| format!("{} is not writable: {}", path.display(), e))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(path.to_path_buf())
+}
+
+/// Force-kills a process together with the processes it started.
+///
+/// Platform-specific implementation:
+/// - **macOS / Linux**: `kill -KILL -- -{pid}`, which signals the whole
+///   process group. Only works for processes spawned as group leaders
+///   (`process_group(0)`), as GAMDL is in `run_download_with_events()`.
+/// - **Windows**: `taskkill /PID {pid} /T /F`, which walks the process tree.
+///
+/// Failures are logged and otherwise ignored: the caller kills the direct
+/// child itself afterwards, so at worst a grandchild outlives it.
+///
+/// # Connection
+/// Called by `download_queue::run_download_with_events()` when the app
+/// shuts down (see `services::shutdown`) and when a download stalls
+/// (`AppSettings::download_stall_timeout_secs`).
+pub async fn kill_process_tree(pid: u32) {
+    #[cfg(not(target_os = "windows"))]
+    let result = tokio::process::Command::new("kill")
+        .args(["-KILL", "--", &format!("-{}", pid)])
+        .output()
+        .await;
+
+    #[cfg(target_os = "windows")]
+    let result = tokio::process::Command::new("taskkill")
+        .args(["/PID", &pid.to_string(), "/T", "/F"])
+        .output()
+        .await;
+
+    match result {
+        Ok(output) if !output.status.success() => log::debug!(
+            "Killing process tree {} failed: {}",
+            pid,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ),
+        Err(e) => log::debug!("Failed to kill process tree {}: {}", pid, e),
+        Ok(_) => {}
+    }
+}
+
+/// Bytes in one GiB, the unit of `AppSettings::min_free_space_gb`.
+const BYTES_PER_GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Returns the space available to the current user on the filesystem that
+/// holds `path`, in bytes.
+///
+/// The path doesn't have to exist yet (e.g., an output folder GAMDL has
+/// not created); its nearest existing ancestor is queried instead.
+///
+/// Platform-specific implementation:
+/// - **macOS / Linux**: `df -Pk` (POSIX output format, 1024-byte blocks),
+///   reading the "Available" column.
+/// - **Windows**: PowerShell's `System.IO.DriveInfo.AvailableFreeSpace`
+///   for the path's drive.
+///
+/// # Returns
+/// * `Ok(bytes)` - Free space available to the user.
+/// * `Err(message)` - The tool failed or its output couldn't be parsed.
+///
+/// # Connection
+/// Called by `download_queue::process_queue()` for the low disk space guard.
+pub async fn available_space_bytes(path: &Path) -> Result<u64, String> {
+    let existing = path
+        .ancestors()
+        .find(|p| p.exists())
+        .ok_or_else(|| format!("No existing directory for {}", path.display()))?;
+
+    #[cfg(not(target_os = "windows"))]
+    {
+        let output = tokio::process::Command::new("df")
+            .arg("-Pk")
+            .arg(existing)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run df: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("df failed: {}", stderr.trim()));
+        }
+        parse_df_available_kb(&String::from_utf8_lossy(&output.stdout))
+            .map(|kb| kb * 1024)
+            .ok_or_else(|| "Could not parse df output".to_string())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        // The path goes through an environment variable rather than the
+        // script text, so quotes in folder names can't break the command.
+        let output = tokio::process::Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-NonInteractive",
+                "-Command",
+                "[System.IO.DriveInfo]::new([System.IO.Path]::GetPathRoot($env:MEEDYADL_SPACE_PATH)).AvailableFreeSpace",
+            ])
+            .env("MEEDYADL_SPACE_PATH", existing)
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run PowerShell: {}", e))?;
+
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(format!("Free space query failed: {}", stderr.trim()));
+        }
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| "Could not parse free space query output".to_string())
+    }
+}
+
+/// Extracts the "Available" column (in KiB) from `df -Pk` output.
+///
+/// POSIX format guarantees one line per filesystem after the header, with
+/// available blocks in the fourth column:
+/// ```text
+/// Filesystem     1024-blocks      Used Available Capacity Mounted on
+/// /dev/disk3s5     482797652 301234120 160523456      66% /System/Volumes/Data
+/// ```
+fn parse_df_available_kb(output: &str) -> Option<u64> {
+    output
+        .lines()
+        .nth(1)?
+        .split_whitespace()
+        .nth(3)?
+        .parse()
+        .ok()
+}
+
+/// Niceness values accepted for `AppSettings::process_niceness`: the Unix
+/// range, from `-20` (highest priority) to `19` (lowest).
+pub const NICENESS_RANGE: std::ops::RangeInclusive<i32> = -20..=19;
+
+/// Checks that a niceness is within [`NICENESS_RANGE`].
+pub fn validate_niceness(niceness: i32) -> Result<(), String> {
+    if NICENESS_RANGE.contains(&niceness) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} is out of range (expected {} to {})",
+            niceness,
+            NICENESS_RANGE.start(),
+            NICENESS_RANGE.end()
+        ))
+    }
+}
+
+/// Returns the Windows process creation flag of the priority class closest
+/// to a Unix niceness:
+///
+/// | Niceness   | Priority class                  |
+/// |------------|---------------------------------|
+/// | below 0    | `ABOVE_NORMAL_PRIORITY_CLASS`   |
+/// | 0          | `NORMAL_PRIORITY_CLASS`         |
+/// | 1 to 9     | `BELOW_NORMAL_PRIORITY_CLASS`   |
+/// | 10 and up  | `IDLE_PRIORITY_CLASS`           |
+///
+/// Reference: https://learn.microsoft.com/en-us/windows/win32/procthread/process-creation-flags
+pub fn windows_priority_class(niceness: i32) -> u32 {
+    const IDLE_PRIORITY_CLASS: u32 = 0x0000_0040;
+    const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+    const NORMAL_PRIORITY_CLASS: u32 = 0x0000_0020;
+    const ABOVE_NORMAL_PRIORITY_CLASS: u32 = 0x0000_8000;
+    match niceness {
+        n if n < 0 => ABOVE_NORMAL_PRIORITY_CLASS,
+        0 => NORMAL_PRIORITY_CLASS,
+        1..=9 => BELOW_NORMAL_PRIORITY_CLASS,
+        _ => IDLE_PRIORITY_CLASS,
+    }
+}
+
+/// Makes a command's process run at a niceness (clamped to
+/// [`NICENESS_RANGE`]); the processes it starts inherit it.
+///
+/// Platform-specific implementation:
+/// - **macOS / Linux**: calls `nice()` in the child before it execs. A
+///   negative niceness needs root; without it the call fails and the
+///   process keeps its normal priority rather than failing to start.
+/// - **Windows**: the priority class of `windows_priority_class()`.
+pub fn apply_niceness(cmd: &mut tokio::process::Command, niceness: i32) {
+    let niceness = niceness.clamp(*NICENESS_RANGE.start(), *NICENESS_RANGE.end());
+
+    #[cfg(unix)]
+    // SAFETY: the hook runs in the forked child before exec and only
+    // calls nice(), which is async-signal-safe.
+    unsafe {
+        cmd.pre_exec(move || {
+            libc::nice(niceness);
+            Ok(())
+        });
+    }
+
+    #[cfg(windows)]
+    cmd.creation_flags(windows_priority_class(niceness));
+}
+
+/// Returns whether `available_bytes` satisfies a minimum free space
+/// threshold in GiB. A missing, zero, negative or non-finite threshold
+/// means "no minimum" and always passes.
+pub fn has_min_free_space(available_bytes: u64, min_free_gb: Option<f64>) -> bool {
+    match min_free_gb {
+        Some(gb) if gb.is_finite() && gb > 0.0 => available_bytes as f64 >= gb * BYTES_PER_GIB,
+        _ => true,
+    }
+}
+
+/// Filesystem types that are served over the network. GAMDL and FFmpeg
+/// write a temporary file and rename it into place, which fails or leaves
+/// partial files on some of these mounts (SMB in particular).
+const REMOTE_FS_TYPES: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb",
+    "smb2",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "sshfs",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "9p",
+    "ceph",
+    "glusterfs",
+    "fuse.glusterfs",
+    "lustre",
+    "afs",
+];
+
+/// Returns whether a filesystem type name (as in `/proc/mounts` or
+/// `statfs`'s `f_fstypename`) is a network filesystem.
+pub fn is_remote_fs_type(fs_type: &str) -> bool {
+    REMOTE_FS_TYPES
+        .iter()
+        .any(|remote| fs_type.eq_ignore_ascii_case(remote))
+}
+
+/// Returns whether `path` is on a network filesystem (NFS, SMB, AFP,
+/// SSHFS, ...), following symlinks, so a library folder linked onto a NAS
+/// counts as remote. The path doesn't have to exist yet; its nearest
+/// existing ancestor is checked.
+///
+/// Platform-specific implementation:
+/// - **Linux**: the filesystem type of the longest matching mount point in
+///   `/proc/self/mounts`.
+/// - **macOS**: the `f_fstypename` that `statfs()` reports.
+/// - **Windows**: UNC paths (`\\server\share`), and drive letters that
+///   `GetDriveTypeW()` reports as `DRIVE_REMOTE` (mapped network drives).
+///
+/// Returns `false` whenever the check itself fails.
+///
+/// # Connection
+/// Called by `download_queue::process_queue()`, which stages downloads
+/// whose output folder is remote (see `services::staging_service`).
+pub fn detect_remote_filesystem(path: &Path) -> bool {
+    let Some(existing) = path.ancestors().find(|p| p.exists()) else {
+        return false;
+    };
+    let resolved = std::fs::canonicalize(existing).unwrap_or_else(|_| existing.to_path_buf());
+
+    #[cfg(target_os = "linux")]
+    {
+        std::fs::read_to_string("/proc/self/mounts")
+            .ok()
+            .and_then(|mounts| mount_fs_type(&mounts, &resolved))
+            .is_some_and(|fs_type| is_remote_fs_type(&fs_type))
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        statfs_type_name(&resolved).is_some_and(|fs_type| is_remote_fs_type(&fs_type))
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        is_unc_path(&resolved) || is_remote_drive(&resolved)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        let _ = resolved;
+        false
+    }
+}
+
+/// Returns the filesystem type of the mount that holds `path`, from the
+/// contents of `/proc/mounts` (`device mount_point fs_type options ...`,
+/// with spaces in the mount point escaped as `\040`).
+#[cfg(target_os = "linux")]
+fn mount_fs_type(mounts: &str, path: &Path) -> Option<String> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            Some((PathBuf::from(mount_point), fs_type))
+        })
+        .filter(|(mount_point, _)| path.starts_with(mount_point))
+        .max_by_key(|(mount_point, _)| mount_point.components().count())
+        .map(|(_, fs_type)| fs_type.to_string())
+}
+
+/// Returns the filesystem type name `statfs()` reports for `path`.
+#[cfg(target_os = "macos")]
+fn statfs_type_name(path: &Path) -> Option<String> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: statfs only writes into the zeroed struct it's given, and
+    // f_fstypename is NUL-terminated when the call succeeds.
+    unsafe {
+        let mut stat: libc::statfs = std::mem::zeroed();
+        if libc::statfs(c_path.as_ptr(), &mut stat) != 0 {
+            return None;
+        }
+        let name = std::ffi::CStr::from_ptr(stat.f_fstypename.as_ptr());
+        Some(name.to_string_lossy().to_string())
+    }
+}
+
+/// Returns whether `path` is a UNC path (`\\server\share\...`, also in
+/// its `\\?\UNC\` form, which `canonicalize()` returns).
+#[cfg(target_os = "windows")]
+fn is_unc_path(path: &Path) -> bool {
+    use std::path::{Component, Prefix};
+    matches!(
+        path.components().next(),
+        Some(Component::Prefix(prefix))
+            if matches!(prefix.kind(), Prefix::UNC(..) | Prefix::VerbatimUNC(..))
+    )
+}
+
+/// Returns whether `path` is on a mapped network drive (`Z:\...` where
+/// `GetDriveTypeW("Z:\\")` is `DRIVE_REMOTE`).
+#[cfg(target_os = "windows")]
+fn is_remote_drive(path: &Path) -> bool {
+    use std::path::{Component, Prefix};
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetDriveTypeW(root_path_name: *const u16) -> u32;
+    }
+    const DRIVE_REMOTE: u32 = 4;
+
+    let letter = match path.components().next() {
+        Some(Component::Prefix(prefix)) => match prefix.kind() {
+            Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => letter,
+            _ => return false,
+        },
+        _ => return false,
+    };
+    let root: Vec<u16> = format!("{}:\\", letter as char)
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    // SAFETY: `root` is a NUL-terminated UTF-16 string that outlives the
+    // call, and GetDriveTypeW only reads it.
+    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+}
+
 // ============================================================
 // Unit Tests
 // ============================================================
@@ -246,6 +673,7 @@ pub fn get_gamdl_config_path(app: &AppHandle) -> PathBuf {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::test_dir::TestDir;
     use std::path::Path;
 
     // ----------------------------------------------------------
@@ -387,4 +815,224 @@ mod tests {
             );
         }
     }
+
+    // ----------------------------------------------------------
+    // resolve_reveal_dir
+    // ----------------------------------------------------------
+
+    /// Verifies that a file output (single track) resolves to its folder.
+    #[test]
+    fn reveal_dir_for_file_is_parent() {
+        let dir = TestDir::new("platform");
+        let file = dir.join("01 Song.m4a");
+        std::fs::write(&file, b"").unwrap();
+
+        assert_eq!(resolve_reveal_dir(&file).unwrap(), dir.path());
+    }
+
+    /// Verifies that a directory output (album) resolves to itself.
+    #[test]
+    fn reveal_dir_for_directory_is_itself() {
+        let dir = TestDir::new("platform");
+
+        assert_eq!(resolve_reveal_dir(&dir).unwrap(), dir.path());
+    }
+
+    /// Verifies that a missing output (files moved) is reported as an error.
+    #[test]
+    fn reveal_dir_for_missing_path_is_error() {
+        let dir = TestDir::new("platform");
+        let missing = dir.join("moved.m4a");
+
+        let err = resolve_reveal_dir(&missing).unwrap_err();
+        assert!(err.contains("no longer exists"), "Unexpected error: {}", err);
+    }
+
+    // ----------------------------------------------------------
+    // Free disk space
+    // ----------------------------------------------------------
+
+    /// Verifies that the "Available" column is read from `df -Pk` output,
+    /// including mount points containing spaces.
+    #[test]
+    fn parses_df_available_column() {
+        let output = "Filesystem     1024-blocks      Used Available Capacity Mounted on\n\
+                      /dev/disk3s5     482797652 301234120 160523456      66% /Volumes/My Drive\n";
+        assert_eq!(parse_df_available_kb(output), Some(160_523_456));
+        assert_eq!(parse_df_available_kb("Filesystem 1024-blocks Used Available\n"), None);
+        assert_eq!(parse_df_available_kb(""), None);
+    }
+
+    /// Verifies the threshold predicate with mocked free space values.
+    #[test]
+    fn min_free_space_threshold() {
+        let gib = 1024 * 1024 * 1024;
+        assert!(has_min_free_space(25 * gib, Some(20.0)));
+        assert!(has_min_free_space(20 * gib, Some(20.0)));
+        assert!(!has_min_free_space(20 * gib - 1, Some(20.0)));
+        assert!(!has_min_free_space(gib / 2, Some(0.75)));
+    }
+
+    /// Verifies that an unset or nonsensical threshold never blocks.
+    #[test]
+    fn min_free_space_disabled_thresholds_pass() {
+        assert!(has_min_free_space(0, None));
+        assert!(has_min_free_space(0, Some(0.0)));
+        assert!(has_min_free_space(0, Some(-5.0)));
+        assert!(has_min_free_space(0, Some(f64::NAN)));
+    }
+
+    /// Verifies that a not-yet-created folder is measured via its nearest
+    /// existing ancestor.
+    #[cfg(not(target_os = "windows"))]
+    #[tokio::test]
+    async fn available_space_for_missing_folder_uses_ancestor() {
+        let dir = TestDir::new("platform");
+        let missing = dir.join("Artist").join("Album");
+
+        assert!(available_space_bytes(&missing).await.is_ok());
+    }
+
+    // ----------------------------------------------------------
+    // ensure_writable_dir
+    // ----------------------------------------------------------
+
+    /// Verifies that a missing folder is created and accepted, leaving no
+    /// probe file behind.
+    #[test]
+    fn writable_dir_is_created() {
+        let dir = TestDir::new("platform");
+        let target = dir.join("Downloads").join("Jazz");
+
+        assert_eq!(ensure_writable_dir(&target), Ok(target.clone()));
+        assert!(target.is_dir());
+        assert_eq!(std::fs::read_dir(&target).unwrap().count(), 0);
+    }
+
+    /// Verifies that a path that can't hold files (below a regular file)
+    /// and a relative path are rejected.
+    #[test]
+    fn unwritable_dir_is_rejected() {
+        let dir = TestDir::new("platform");
+        let file = dir.join("not-a-folder");
+        std::fs::write(&file, b"x").unwrap();
+
+        assert!(ensure_writable_dir(&file).is_err());
+        assert!(ensure_writable_dir(&file.join("sub")).is_err());
+        assert!(ensure_writable_dir(Path::new("relative/music")).is_err());
+    }
+
+    // ----------------------------------------------------------
+    // process niceness
+    // ----------------------------------------------------------
+
+    /// Verifies the accepted niceness range and the Windows priority class
+    /// picked for each band.
+    #[test]
+    fn niceness_range_and_windows_priority_class() {
+        assert!(validate_niceness(-20).is_ok());
+        assert!(validate_niceness(19).is_ok());
+        assert!(validate_niceness(20).is_err());
+        assert!(validate_niceness(-21).is_err());
+
+        assert_eq!(windows_priority_class(-5), 0x8000);
+        assert_eq!(windows_priority_class(0), 0x20);
+        assert_eq!(windows_priority_class(5), 0x4000);
+        assert_eq!(windows_priority_class(19), 0x40);
+    }
+
+    // ----------------------------------------------------------
+    // detect_remote_filesystem
+    // ----------------------------------------------------------
+
+    /// Verifies which filesystem type names count as network filesystems.
+    #[test]
+    fn remote_fs_types() {
+        for fs_type in ["nfs4", "cifs", "smbfs", "afpfs", "fuse.sshfs", "NFS"] {
+            assert!(is_remote_fs_type(fs_type), "{}", fs_type);
+        }
+        for fs_type in ["ext4", "apfs", "btrfs", "tmpfs", "fuse", "ntfs"] {
+            assert!(!is_remote_fs_type(fs_type), "{}", fs_type);
+        }
+    }
+
+    /// Verifies that the longest matching mount point decides the type,
+    /// including mount points with escaped spaces, for representative
+    /// NAS mounts.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn mount_fs_type_picks_longest_mount_point() {
+        let mounts = "\
+/dev/nvme0n1p2 / ext4 rw,relatime 0 0
+/dev/nvme0n1p1 /home ext4 rw,relatime 0 0
+nas:/volume1/music /mnt/nas nfs4 rw,vers=4.1 0 0
+//nas/Music\\040Library /media/Music\\040Library cifs rw,vers=3.0 0 0
+/dev/sdb1 /mnt/nas/usb ext4 rw 0 0
+";
+        let fs_type = |path: &str| mount_fs_type(mounts, Path::new(path));
+        assert_eq!(fs_type("/mnt/nas/Artist/Album").as_deref(), Some("nfs4"));
+        assert_eq!(fs_type("/mnt/nas/usb/Music").as_deref(), Some("ext4"));
+        assert_eq!(
+            fs_type("/media/Music Library/Artist").as_deref(),
+            Some("cifs")
+        );
+        assert_eq!(fs_type("/home/user/Music").as_deref(), Some("ext4"));
+        assert_eq!(
+            fs_type("/mnt/nasty").as_deref(),
+            Some("ext4"),
+            "Not under /mnt/nas"
+        );
+    }
+
+    /// Verifies that UNC paths are treated as remote and drive paths not.
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn unc_paths_are_remote() {
+        assert!(is_unc_path(Path::new(r"\\nas\Music\Artist")));
+        assert!(is_unc_path(Path::new(r"\\?\UNC\nas\Music")));
+        assert!(!is_unc_path(Path::new(r"C:\Users\me\Music")));
+    }
+
+    /// Verifies that the local system drive and paths without a drive
+    /// letter aren't taken for mapped network drives.
+    #[cfg(target_os = "windows")]
+    #[test]
+    fn local_drive_is_not_remote_drive() {
+        let system_drive = std::env::var("SystemDrive").unwrap_or_else(|_| "C:".to_string());
+        assert!(!is_remote_drive(&Path::new(&system_drive).join("\\Windows")));
+        assert!(!is_remote_drive(Path::new(r"\\nas\Music")));
+    }
+
+    /// Verifies that a local folder (and a path below it that doesn't
+    /// exist yet) isn't reported as remote.
+    #[test]
+    fn local_temp_dir_is_not_remote() {
+        let dir = std::env::temp_dir();
+        assert!(!detect_remote_filesystem(&dir));
+        let missing = dir.join("meedyadl-missing/Album");
+        assert!(!detect_remote_filesystem(&missing));
+    }
+
+    /// Verifies that a command configured with a niceness starts at that
+    /// priority, as reported by `nice` itself (Unix only: Windows offers
+    /// no equivalent to query).
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn applied_niceness_reaches_the_process() {
+        let read = |output: std::process::Output| -> i32 {
+            String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse()
+                .unwrap()
+        };
+        let Ok(base) = tokio::process::Command::new("nice").output().await else {
+            return; // no `nice` binary to ask
+        };
+        let base = read(base);
+
+        let mut cmd = tokio::process::Command::new("nice");
+        apply_niceness(&mut cmd, 5);
+        let applied = read(cmd.output().await.unwrap());
+        assert_eq!(applied, (base + 5).min(19));
+    }
 }
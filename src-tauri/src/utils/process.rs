@@ -11,6 +11,7 @@
 // consume to render:
 //   - A progress bar with percentage, speed, and ETA
 //   - Track title/artist information
+//   - The decryption key fetch, where downloads most often hang
 //   - Post-processing step names (Remuxing, Tagging, etc.)
 //   - Error messages with classification (auth, network, codec, etc.)
 //   - Download completion with the output file path
@@ -42,6 +43,11 @@ use serde::Serialize;
 // Unlike `lazy_static!`, it is part of the standard library (since 1.80).
 // Reference: https://doc.rust-lang.org/std/sync/struct.LazyLock.html
 use std::sync::LazyLock;
+// `HashMap` holds per-stream byte counts in `ProgressTracker`.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+// `DownloadError` holds the keyword rules behind `classify_error()`.
+use crate::models::download_error::DownloadError;
 
 // ============================================================
 // Compiled regex patterns (initialised once via LazyLock, reused for
@@ -62,14 +68,17 @@ use std::sync::LazyLock;
 ///   3. `speed`    -- e.g. "2.51MiB/s"
 ///   4. `eta`      -- e.g. "00:01"
 ///
-/// Example input: `[download]  45.2% of ~  5.12MiB at  2.51MiB/s ETA 00:01`
+/// Example inputs:
+///   - `[download]  45.2% of ~  5.12MiB at  2.51MiB/s ETA 00:01`
+///   - `[download]   0.0% of   48.20MiB at  Unknown B/s ETA Unknown`
 ///
 /// The `~?` makes the tilde optional (yt-dlp uses `~` for estimated sizes).
 /// `\S+` matches any non-whitespace sequence, which is flexible enough to
-/// handle varying size/speed/time formats.
+/// handle varying size/time formats. The speed is matched lazily up to
+/// "ETA" because yt-dlp prints "Unknown B/s" before the first sample.
 static PROGRESS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(
-        r"\[download\]\s+(\d+\.?\d*)%\s+of\s+~?\s*(\S+)\s+at\s+(\S+)\s+ETA\s+(\S+)",
+        r"\[download\]\s+(\d+\.?\d*)%\s+of\s+~?\s*(\S+)\s+at\s+(.+?)\s+ETA\s+(\S+)",
     )
     .expect("Invalid progress regex")
 });
@@ -89,11 +98,38 @@ static PROGRESS_COMPLETE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
         .expect("Invalid progress complete regex")
 });
 
+/// Matches N_m3u8DL-RE per-stream progress output (GAMDL's `nm3u8dlre`
+/// download mode).
+///
+/// Capture groups:
+///   1. `kind`       -- "Vid", "Aud" or "Sub"
+///   2. `descriptor` -- stream description plus the progress bar,
+///      e.g. "1920x1080 | 4500 Kbps | 30 ━━━━━━"
+///   3. `percent`    -- e.g. "25.00"
+///   4. `downloaded` -- e.g. "24.43MB"
+///   5. `total`      -- e.g. "97.72MB"
+///   6. `speed`      -- e.g. "8.35MBps" (or "-" before the first sample)
+///   7. `eta`        -- e.g. "00:00:09" (or "--:--:--")
+///
+/// Example input:
+///   `Vid 1920x1080 | 4500 Kbps | 30 ━━━━━━━━━━━━━━━━━━━━ 45/180 25.00% 24.43MB/97.72MB 8.35MBps 00:00:09`
+///
+/// Video and audio streams download concurrently, so lines for different
+/// streams are interleaved; the descriptor tells them apart.
+static NM3U8DLRE_PROGRESS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^(Vid|Aud|Sub)\s+(.*?)\s*\d+/\d+\s+(\d+(?:\.\d+)?)%\s+([\d.]+[A-Za-z]*)/([\d.]+[A-Za-z]*)\s+(\S+)\s+(\S+)",
+    )
+    .expect("Invalid N_m3u8DL-RE progress regex")
+});
+
 /// Matches GAMDL track information lines.
 ///
 /// Capture groups:
 ///   1. `type`  -- either "song" or "track N of M" (e.g. "track 3 of 12")
-///   2. `info`  -- the rest of the line (title, possibly "Title by Artist")
+///   2. `number` -- N, the track's position in the album (album lines only)
+///   3. `count` -- M, the album's track count (album lines only)
+///   4. `info`  -- the rest of the line (title, possibly "Title by Artist")
 ///
 /// Example inputs:
 ///   - `Getting song: Song Title by Artist Name`
@@ -102,10 +138,30 @@ static PROGRESS_COMPLETE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
 /// The alternation `(song|track\s+\d+\s+of\s+\d+)` handles both
 /// single-track and album-track formats that GAMDL outputs.
 static TRACK_INFO_REGEX: LazyLock<Regex> = LazyLock::new(|| {
-    Regex::new(r"Getting\s+(song|track\s+\d+\s+of\s+\d+):\s+(.+)")
+    Regex::new(r"Getting\s+(song|track\s+(\d+)\s+of\s+(\d+)):\s+(.+)")
         .expect("Invalid track info regex")
 });
 
+/// Matches the line GAMDL prints when it moves on to the next URL of a
+/// multi-URL run.
+///
+/// Capture groups:
+///   1. `index` -- position of the URL, starting at 1
+///   2. `total` -- number of URLs in the run
+///   3. `url`   -- the URL itself
+///
+/// Example input: `[INFO     12:00:00] (URL 2/5) Checking "https://music.apple.com/us/album/red/1440933849"`
+///
+/// GAMDL dims the "URL 2/5" part, so color codes around it are allowed.
+/// Track lines such as `(URL 2/5 - Track 3/12) Downloading ...` don't
+/// match: the parenthesis must close right after the URL counter.
+static URL_START_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"\((?:\x1b\[[0-9;]*m)*URL\s+(\d+)/(\d+)(?:\x1b\[[0-9;]*m)*\)\s+Checking\s+"([^"]+)""#,
+    )
+    .expect("Invalid URL start regex")
+});
+
 /// Matches GAMDL "Saved to" completion lines.
 ///
 /// Capture groups:
@@ -120,6 +176,27 @@ static SAVED_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)saved\s+to:?\s+(.+)").expect("Invalid saved regex")
 });
 
+/// Matches GAMDL's lines about getting a track's decryption key from
+/// Apple's license server, the step before the stream download starts.
+///
+/// Example inputs:
+///   - `[DEBUG    12:00:00] Getting decryption key`
+///   - `Getting widevine PSSH`
+///   - `[INFO] Requesting license for track 3 of 12`
+///
+/// Anchored (after an optional `[LEVEL]` prefix) so errors that mention a
+/// license, such as "Failed getting license", stay errors.
+static FETCHING_KEYS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)^(?:\[[^\]]*\]\s*)?(?:getting|requesting|fetching|retrieving)\s+(?:the\s+)?(?:widevine\s+)?(?:decryption\s+keys?|licen[cs]e|pssh)\b",
+    )
+    .expect("Invalid fetching keys regex")
+});
+
+/// Step label of [`GamdlOutputEvent::FetchingKeys`], shown in the queue and
+/// named in stall errors.
+pub const FETCHING_KEYS_STEP: &str = "Fetching decryption keys";
+
 /// Matches lines containing explicit error indicators at the start.
 ///
 /// Capture groups:
@@ -137,6 +214,112 @@ static ERROR_PREFIX_REGEX: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"(?i)^(?:ERROR|error|Error):?\s+(.+)").expect("Invalid error regex")
 });
 
+/// Matches GAMDL's "no matching stream for codec" message, printed when the
+/// account tier or storefront region doesn't offer the requested codec.
+///
+/// Capture groups:
+///   1. `codec` -- the requested codec (e.g. "alac")
+///
+/// Example inputs:
+///   - `No matching stream for codec alac`
+///   - `ERROR: No matching stream found for codec "atmos"`
+///
+/// Not anchored, so it also matches when GAMDL prefixes the message with
+/// a log level or track name.
+static CODEC_UNAVAILABLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)no\s+matching\s+stream(?:\s+found)?\s+for\s+codec:?\s+"?([\w-]+)"?"#)
+        .expect("Invalid codec unavailable regex")
+});
+
+/// Matches GAMDL's confirmation of the codec a track is downloaded in.
+/// On mixed albums this can differ from track to track.
+///
+/// Capture groups:
+///   1. `codec` -- the codec used (e.g. "alac")
+///
+/// Example inputs:
+///   - `Using codec alac`
+///   - `[INFO] Selected song codec: "aac-legacy"`
+///
+/// Anchored (after an optional `[LEVEL]` prefix) so error text that merely
+/// mentions a codec isn't taken for a confirmation.
+static TRACK_CODEC_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)^(?:\[[^\]]*\]\s*)?(?:using|selected)\s+(?:song\s+)?codec:?\s+"?([\w-]+)"?"#)
+        .expect("Invalid track codec regex")
+});
+
+/// Matches messages saying the content isn't available in the storefront
+/// (country) it was requested from, e.g. "This album is not available in
+/// this storefront" or "... not available in your country".
+static STOREFRONT_UNAVAILABLE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)not\s+available\s+in\s+(?:this|your|the\s+\w+)\s+(?:storefront|country|region)")
+        .expect("Invalid storefront unavailable regex")
+});
+
+/// Matches messages saying a URL is malformed or not supported: GAMDL's
+/// "URL is not supported" / "Invalid URL" lines, and the "Invalid Apple
+/// Music URL" errors of `gamdl_service::validate_urls()`.
+static INVALID_URL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:invalid|unsupported|malformed)\s+(?:apple\s+music\s+)?url|url\s+(?:is\s+)?(?:not\s+supported|invalid)|no\s+valid\s+urls?",
+    )
+    .expect("Invalid URL error regex")
+});
+
+/// Matches transient network failures: timeouts ("timeout", "timed out"),
+/// refused/reset connections, DNS and name resolution errors, unreachable
+/// hosts, and urllib3's "Max retries exceeded". Decryption and license
+/// errors often carry these as their cause.
+static NETWORK_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)network|time(?:d)?\s*out|connection|dns|unreachable|name\s+resolution|max\s+retries\s+exceeded|temporarily\s+unavailable",
+    )
+    .expect("Invalid network error regex")
+});
+
+/// Matches decryption failures: errors from the Widevine CDM (pywidevine,
+/// a missing or invalid `.wvd` device file, rejected license requests) and
+/// GAMDL failing to get or use a track's decryption key. mp4decrypt merely
+/// exiting with an error is left to the `"tool"` category.
+static DRM_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)widevine|\.wvd\b|\bcdm\b|licen[cs]e\s+(?:request|challenge|response)|decryption\s+(?:keys?|failed|error)|(?:failed\s+to|could\s+not|unable\s+to|can'?t|cannot)\s+decrypt\b",
+    )
+    .expect("Invalid DRM error regex")
+});
+
+/// Matches messages saying the disk is full: Python's "[Errno 28] No space
+/// left on device" (ENOSPC) and "Disk quota exceeded" (EDQUOT) on
+/// macOS/Linux, and "[WinError 112] There is not enough space on the disk"
+/// (ERROR_DISK_FULL) on Windows, as GAMDL, FFmpeg or the OS report them.
+static DISK_FULL_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)no\s+space\s+left\s+on\s+device|\[errno\s+28\]|disk\s+quota\s+exceeded|not\s+enough\s+space\s+on\s+the\s+disk|\[winerror\s+112\]|error_disk_full|disk\s+(?:is\s+)?full",
+    )
+    .expect("Invalid disk full regex")
+});
+
+/// Matches GAMDL lines saying a track's cover art couldn't be fetched or
+/// embedded, while its audio may be fine: "Failed to download cover"
+/// style messages, and HTTP errors for an artwork URL on Apple's image
+/// host (`https://is1-ssl.mzstatic.com/image/thumb/...`). Audio failures
+/// ("Failed to download track", stream URLs) don't match.
+static COVER_FAILURE_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)(?:fail(?:ed|ure)?|error|unable|could\s+not|couldn'?t|cannot)\s+(?:to\s+|while\s+)?(?:download(?:ing)?|get(?:ting)?|fetch(?:ing)?|sav(?:e|ing)|embed(?:ding)?)\s+(?:the\s+)?(?:music\s+video\s+)?(?:cover|artwork)\b|mzstatic\.com/image/",
+    )
+    .expect("Invalid cover failure regex")
+});
+
+/// First line of every Python traceback.
+const TRACEBACK_HEADER: &str = "Traceback (most recent call last):";
+
+/// Lines Python prints between the tracebacks of chained exceptions.
+const CHAINED_EXCEPTION_MARKERS: [&str; 2] = [
+    "During handling of the above exception, another exception occurred:",
+    "The above exception was the direct cause of the following exception:",
+];
+
 // ============================================================
 // Event types emitted to the frontend
 // ============================================================
@@ -171,9 +354,27 @@ pub enum GamdlOutputEvent {
         artist: String,
         /// Album name (empty string if not parsed - album info often comes separately)
         album: String,
+        /// Position of the track in its album, from "Getting track N of M"
+        /// lines. Used to resume interrupted album downloads; not sent to
+        /// the frontend.
+        #[serde(skip)]
+        track_number: Option<u32>,
+        /// Number of tracks in the album (the M of "track N of M").
+        #[serde(skip)]
+        track_count: Option<u32>,
+    },
+
+    /// GAMDL moved on to the next URL of a multi-URL run
+    UrlStart {
+        /// Position of the URL in the run, starting at 1
+        index: u32,
+        /// Number of URLs in the run
+        total: u32,
+        /// The URL GAMDL is now processing
+        url: String,
     },
 
-    /// Download progress update from yt-dlp's output
+    /// Download progress update from yt-dlp's or N_m3u8DL-RE's output
     DownloadProgress {
         /// Progress percentage (0.0 to 100.0)
         percent: f64,
@@ -181,6 +382,10 @@ pub enum GamdlOutputEvent {
         speed: String,
         /// Estimated time remaining (e.g., "00:01")
         eta: String,
+        /// Byte counts parsed from the line, if it had any. Consumed by
+        /// [`ProgressTracker`]; not sent to the frontend.
+        #[serde(skip)]
+        bytes: Option<ByteProgress>,
     },
 
     /// A post-download processing step (remuxing, tagging, etc.)
@@ -189,12 +394,45 @@ pub enum GamdlOutputEvent {
         step: String,
     },
 
+    /// GAMDL is getting the track's decryption key from the license
+    /// server, before the stream download starts
+    FetchingKeys {
+        /// Label of the step, always [`FETCHING_KEYS_STEP`]
+        step: String,
+    },
+
+    /// The requested codec isn't offered for this account tier or region
+    /// (GAMDL's "no matching stream for codec" message)
+    CodecUnavailable {
+        /// The codec GAMDL couldn't find a stream for (e.g., "alac")
+        codec: String,
+        /// The full message, used as the download's error text
+        message: String,
+    },
+
+    /// GAMDL confirmed the codec of the track being downloaded
+    TrackCodec {
+        /// The codec used for the track (e.g., "aac-legacy")
+        codec: String,
+    },
+
     /// An error occurred during the download
     Error {
         /// Error message from GAMDL or its subprocesses
         message: String,
     },
 
+    /// GAMDL crashed with an uncaught Python exception.
+    ///
+    /// `parse_gamdl_output()` only sees the "Traceback (most recent call
+    /// last):" header, so on its own this carries just that line; a
+    /// [`TracebackCollector`] replaces it with the whole block once the
+    /// final exception line arrives.
+    Crash {
+        /// The traceback, from the header through the exception line
+        traceback: String,
+    },
+
     /// Download completed successfully for a track/file
     Complete {
         /// Path to the output file (if available)
@@ -215,13 +453,23 @@ pub enum GamdlOutputEvent {
 /// priority order to categorize each line:
 ///
 /// 1. Download progress (yt-dlp format)
-/// 2. Download completion (yt-dlp format)
-/// 3. Track information (GAMDL "Getting song/track" lines)
-/// 4. Explicit errors (ERROR/Error prefix)
-/// 5. Post-processing steps (Remuxing/Tagging/Embedding)
-/// 6. File save completion (Saved to ...)
-/// 7. Common error patterns (case-insensitive "failed", "not found", etc.)
-/// 8. Unknown (everything else)
+/// 2. Download progress (N_m3u8DL-RE format)
+/// 3. Download completion (yt-dlp format)
+/// 4. Track information (GAMDL "Getting song/track" lines)
+/// 5. Start of the next URL of a multi-URL job ("(URL 2/5) Checking ...")
+/// 6. Codec unavailable ("no matching stream for codec ...")
+/// 7. Per-track codec confirmation ("Using codec ...")
+/// 8. Start of a Python traceback (see [`TracebackCollector`])
+/// 9. Explicit errors (ERROR/Error prefix)
+/// 10. Decryption key fetch ("Getting decryption key", "Requesting license")
+/// 11. Post-processing steps (Remuxing/Tagging/Embedding)
+/// 12. File save completion (Saved to ...)
+/// 13. Common error patterns (case-insensitive "failed", "not found", etc.)
+/// 14. Unknown (everything else)
+///
+/// Progress events carry the raw per-line values. Pass them through a
+/// [`ProgressTracker`] to get a monotonic overall percent and smoothed
+/// speed/ETA for an item.
 ///
 /// # Arguments
 /// * `line` - A single line from GAMDL's stdout or stderr
@@ -260,29 +508,77 @@ pub fn parse_gamdl_output(line: &str) -> GamdlOutputEvent {
             .get(4)
             .map(|m| m.as_str().to_string())
             .unwrap_or_default();
+        // Capture group 2 = total (or estimated total) size. yt-dlp doesn't
+        // print the downloaded byte count, so derive it from the percent.
+        let bytes = captures
+            .get(2)
+            .and_then(|m| parse_size_bytes(m.as_str()))
+            .map(|total| ByteProgress {
+                stream: YTDLP_STREAM.to_string(),
+                downloaded: (total as f64 * percent / 100.0) as u64,
+                total,
+                speed: parse_size_bytes(&speed).map(|b| b as f64),
+            });
         return GamdlOutputEvent::DownloadProgress {
             percent,
             speed,
             eta,
+            bytes,
+        };
+    }
+
+    // Priority 2: N_m3u8DL-RE per-stream progress. Checked right after
+    // yt-dlp for the same reason: these lines dominate the output of
+    // music video downloads in nm3u8dlre mode.
+    if let Some(captures) = NM3U8DLRE_PROGRESS_REGEX.captures(trimmed) {
+        let group = |i: usize| captures.get(i).map(|m| m.as_str()).unwrap_or_default();
+        let percent = group(3).parse::<f64>().unwrap_or(0.0);
+        // Strip the progress bar from the descriptor so every line for the
+        // same stream gets the same key.
+        let descriptor = group(2).trim_end_matches(|c: char| !c.is_alphanumeric());
+        let bytes = match (parse_size_bytes(group(4)), parse_size_bytes(group(5))) {
+            (Some(downloaded), Some(total)) => Some(ByteProgress {
+                stream: format!("{} {}", group(1), descriptor),
+                downloaded,
+                total,
+                speed: parse_size_bytes(group(6)).map(|b| b as f64),
+            }),
+            _ => None,
+        };
+        return GamdlOutputEvent::DownloadProgress {
+            percent,
+            speed: group(6).to_string(),
+            eta: group(7).to_string(),
+            bytes,
         };
     }
 
-    // Priority 2: yt-dlp download completion (100%)
-    if PROGRESS_COMPLETE_REGEX.is_match(trimmed) {
+    // Priority 3: yt-dlp download completion (100%)
+    if let Some(captures) = PROGRESS_COMPLETE_REGEX.captures(trimmed) {
+        let bytes = captures
+            .get(1)
+            .and_then(|m| parse_size_bytes(m.as_str()))
+            .map(|total| ByteProgress {
+                stream: YTDLP_STREAM.to_string(),
+                downloaded: total,
+                total,
+                speed: None,
+            });
         return GamdlOutputEvent::DownloadProgress {
             percent: 100.0,
             speed: String::new(),
             eta: "00:00".to_string(),
+            bytes,
         };
     }
 
-    // Priority 3: Track information from GAMDL.
+    // Priority 4: Track information from GAMDL.
     // When GAMDL starts processing a new track, it prints a line like
     // "Getting song: Title by Artist" or "Getting track 3 of 12: Title".
     if let Some(captures) = TRACK_INFO_REGEX.captures(trimmed) {
-        // Capture group 2 contains the info string after the colon.
+        // Capture group 4 contains the info string after the colon.
         let info = captures
-            .get(2)
+            .get(4)
             .map(|m| m.as_str().to_string())
             .unwrap_or_default();
 
@@ -303,10 +599,62 @@ pub fn parse_gamdl_output(line: &str) -> GamdlOutputEvent {
             // Album info typically comes from a separate GAMDL output line
             // and is not available in the "Getting song/track" line.
             album: String::new(),
+            track_number: captures.get(2).and_then(|m| m.as_str().parse().ok()),
+            track_count: captures.get(3).and_then(|m| m.as_str().parse().ok()),
+        };
+    }
+
+    // Priority 5: GAMDL moved on to the next URL of the run. Album
+    // boundaries of a multi-URL job, for its per-URL progress.
+    if let Some(captures) = URL_START_REGEX.captures(trimmed) {
+        let number = |i: usize| {
+            captures
+                .get(i)
+                .and_then(|m| m.as_str().parse().ok())
+                .unwrap_or(0)
+        };
+        return GamdlOutputEvent::UrlStart {
+            index: number(1),
+            total: number(2),
+            url: captures[3].to_string(),
+        };
+    }
+
+    // Priority 6: Codec not offered for this account tier/region.
+    // Checked before the generic error prefix so "ERROR: No matching stream
+    // for codec alac" keeps its codec instead of becoming a plain Error.
+    if let Some(captures) = CODEC_UNAVAILABLE_REGEX.captures(trimmed) {
+        let codec = captures
+            .get(1)
+            .map(|m| m.as_str().to_lowercase())
+            .unwrap_or_default();
+        let message = ERROR_PREFIX_REGEX
+            .captures(trimmed)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str().to_string())
+            .unwrap_or_else(|| trimmed.to_string());
+        return GamdlOutputEvent::CodecUnavailable { codec, message };
+    }
+
+    // Priority 7: The codec the current track is downloaded in.
+    if let Some(captures) = TRACK_CODEC_REGEX.captures(trimmed) {
+        let codec = captures
+            .get(1)
+            .map(|m| m.as_str().to_lowercase())
+            .unwrap_or_default();
+        return GamdlOutputEvent::TrackCodec { codec };
+    }
+
+    // Priority 8: Start of a Python traceback. Checked before the
+    // keyword fallback below, which would report the header line itself
+    // as the error. The rest of the block is gathered by TracebackCollector.
+    if trimmed == TRACEBACK_HEADER {
+        return GamdlOutputEvent::Crash {
+            traceback: trimmed.to_string(),
         };
     }
 
-    // Priority 4: Explicit error messages with ERROR/Error prefix
+    // Priority 9: Explicit error messages with ERROR/Error prefix
     if let Some(captures) = ERROR_PREFIX_REGEX.captures(trimmed) {
         let message = captures
             .get(1)
@@ -315,7 +663,18 @@ pub fn parse_gamdl_output(line: &str) -> GamdlOutputEvent {
         return GamdlOutputEvent::Error { message };
     }
 
-    // Priority 5: Post-processing steps (remuxing, tagging, embedding artwork).
+    // Priority 10: Getting the decryption key. License server requests are
+    // where downloads most often hang, so this is told apart from both the
+    // download and the post-processing steps. Checked after the error
+    // prefix so "ERROR: Getting license failed" stays an error, and lines
+    // saying the fetch failed are left to the keyword check below.
+    if FETCHING_KEYS_REGEX.is_match(trimmed) && !trimmed.to_lowercase().contains("fail") {
+        return GamdlOutputEvent::FetchingKeys {
+            step: FETCHING_KEYS_STEP.to_string(),
+        };
+    }
+
+    // Priority 11: Post-processing steps (remuxing, tagging, embedding artwork).
     // After the raw download completes, GAMDL runs post-processing steps:
     //   - Remuxing:   converting container format (e.g. WebM -> M4A)
     //   - Tagging:    writing ID3/MP4 metadata tags
@@ -337,7 +696,7 @@ pub fn parse_gamdl_output(line: &str) -> GamdlOutputEvent {
         };
     }
 
-    // Priority 6: File save completion
+    // Priority 12: File save completion
     if let Some(captures) = SAVED_REGEX.captures(trimmed) {
         let path = captures
             .get(1)
@@ -346,7 +705,7 @@ pub fn parse_gamdl_output(line: &str) -> GamdlOutputEvent {
         return GamdlOutputEvent::Complete { path };
     }
 
-    // Priority 7: Common error patterns detected by keyword matching.
+    // Priority 13: Common error patterns detected by keyword matching.
     // These catch errors that don't have an explicit "ERROR:" prefix but
     // contain well-known error indicators. The lowercase conversion ensures
     // case-insensitive matching without regex overhead.
@@ -379,6 +738,527 @@ pub fn parse_gamdl_output(line: &str) -> GamdlOutputEvent {
     }
 }
 
+// ============================================================
+// Byte-level progress tracking
+// ============================================================
+//
+// The raw progress lines are per *stream*, not per item: a music video is
+// fetched as separate video and audio streams (sequentially by yt-dlp,
+// concurrently by N_m3u8DL-RE), and yt-dlp's estimated totals ("~ 48MiB")
+// shift as fragments arrive. Echoing each line's percent therefore makes
+// the progress bar jump backwards and the ETA flicker.
+//
+// `ProgressTracker` combines the byte counts of every stream seen for an
+// item into one overall percent, which is never allowed to decrease, and
+// derives the ETA from the remaining bytes and an exponentially smoothed
+// speed.
+
+/// Stream key used for yt-dlp progress lines, which don't identify the
+/// stream being downloaded.
+const YTDLP_STREAM: &str = "yt-dlp";
+
+/// Weight of the newest speed sample in the exponential moving average.
+/// Lower values give a steadier ETA but react more slowly to changes.
+const SPEED_SMOOTHING: f64 = 0.3;
+
+/// How far (in percentage points) a stream's progress has to fall before
+/// it is treated as the start of a new stream rather than jitter from a
+/// revised size estimate.
+const NEW_STREAM_PERCENT_DROP: f64 = 5.0;
+
+/// Byte counts parsed from a single progress line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ByteProgress {
+    /// Identifies the stream the line belongs to (e.g. "Vid 1920x1080 | 4500 Kbps")
+    pub stream: String,
+    /// Bytes downloaded so far for this stream
+    pub downloaded: u64,
+    /// Total (or estimated total) size of this stream in bytes
+    pub total: u64,
+    /// Reported speed in bytes per second, if the line had a usable one
+    pub speed: Option<f64>,
+}
+
+impl ByteProgress {
+    /// This stream's own completion percentage.
+    fn percent(&self) -> f64 {
+        if self.total == 0 {
+            0.0
+        } else {
+            self.downloaded as f64 / self.total as f64 * 100.0
+        }
+    }
+}
+
+/// Turns the per-line `DownloadProgress` events of one download into a
+/// monotonic overall percent with a smoothed speed and ETA.
+///
+/// Create one per download run and feed it every parsed event via
+/// [`observe()`](Self::observe). A `TrackInfo` event (the next track of an
+/// album) starts the tracking over, so album items keep their per-track
+/// progress bar.
+#[derive(Debug, Default)]
+pub struct ProgressTracker {
+    /// Highest overall percent reported so far
+    percent: f64,
+    /// Total size of streams that have finished and been replaced by a
+    /// later stream under the same key (yt-dlp's video-then-audio)
+    finished_bytes: u64,
+    /// Latest byte counts per stream
+    streams: HashMap<String, ByteProgress>,
+    /// Smoothed combined speed in bytes per second
+    speed: Option<f64>,
+}
+
+impl ProgressTracker {
+    /// Creates a tracker with no progress recorded.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Clears all recorded progress.
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Passes a parsed event through the tracker.
+    ///
+    /// `DownloadProgress` events come back with the overall percent and,
+    /// once a speed sample is available, the smoothed speed and ETA.
+    /// Other events are returned unchanged.
+    pub fn observe(&mut self, event: GamdlOutputEvent) -> GamdlOutputEvent {
+        match event {
+            GamdlOutputEvent::DownloadProgress {
+                percent,
+                speed,
+                eta,
+                bytes: None,
+            } => {
+                // No byte counts (e.g. an unparseable size): all we can do
+                // is keep the percent from going backwards.
+                self.percent = self.percent.max(percent.clamp(0.0, 100.0));
+                GamdlOutputEvent::DownloadProgress {
+                    percent: self.percent,
+                    speed,
+                    eta,
+                    bytes: None,
+                }
+            }
+            GamdlOutputEvent::DownloadProgress {
+                percent,
+                speed,
+                eta,
+                bytes: Some(bytes),
+            } => {
+                self.record(bytes.clone());
+
+                let (done, total) = self.byte_totals();
+                let overall = if total > 0 {
+                    done as f64 / total as f64 * 100.0
+                } else {
+                    percent
+                };
+                self.percent = self.percent.max(overall.clamp(0.0, 100.0));
+
+                let (speed, eta) = match self.speed {
+                    Some(bps) if bps > 0.0 => {
+                        let remaining = total.saturating_sub(done) as f64;
+                        (
+                            format_speed(bps),
+                            format_eta((remaining / bps).ceil() as u64),
+                        )
+                    }
+                    _ => (speed, eta),
+                };
+
+                GamdlOutputEvent::DownloadProgress {
+                    percent: self.percent,
+                    speed,
+                    eta,
+                    bytes: Some(bytes),
+                }
+            }
+            GamdlOutputEvent::TrackInfo { .. } => {
+                self.reset();
+                event
+            }
+            other => other,
+        }
+    }
+
+    /// Stores the latest byte counts for a stream and updates the smoothed
+    /// speed.
+    fn record(&mut self, bytes: ByteProgress) {
+        // A large drop for the same stream means a new download started
+        // under the same key: bank the previous stream's size.
+        if let Some(previous) = self.streams.get(&bytes.stream) {
+            if previous.percent() - bytes.percent() > NEW_STREAM_PERCENT_DROP {
+                self.finished_bytes += previous.total;
+            }
+        }
+
+        let has_speed_sample = bytes.speed.is_some();
+        self.streams.insert(bytes.stream.clone(), bytes);
+
+        if !has_speed_sample {
+            return;
+        }
+
+        // Concurrent streams share the bandwidth, so the item's speed is the
+        // sum of the streams still downloading.
+        let active: Vec<f64> = self
+            .streams
+            .values()
+            .filter(|s| s.downloaded < s.total)
+            .filter_map(|s| s.speed)
+            .collect();
+        if active.is_empty() {
+            return;
+        }
+        let sample: f64 = active.iter().sum();
+        self.speed = Some(match self.speed {
+            Some(previous) => SPEED_SMOOTHING * sample + (1.0 - SPEED_SMOOTHING) * previous,
+            None => sample,
+        });
+    }
+
+    /// Returns `(downloaded, total)` bytes across all streams seen so far.
+    fn byte_totals(&self) -> (u64, u64) {
+        self.streams.values().fold(
+            (self.finished_bytes, self.finished_bytes),
+            |(done, total), s| (done + s.downloaded.min(s.total), total + s.total),
+        )
+    }
+}
+
+/// Parses a size or speed as printed by yt-dlp or N_m3u8DL-RE into bytes
+/// (or bytes per second).
+///
+/// Accepts an optional `~` prefix, binary (`MiB`) and short (`MB`) units
+/// -- both tools use powers of 1024 -- and the `/s` and `ps` speed
+/// suffixes. Returns `None` for placeholders like "Unknown B/s" or "-".
+///
+/// Examples: "5.12MiB", "~48.20MiB", "97.72MB", "8.35MBps", "512.00KBps"
+pub fn parse_size_bytes(value: &str) -> Option<u64> {
+    parse_scaled(value).map(|bytes| bytes as u64)
+}
+
+/// Parses a download speed (e.g. "2.51MiB/s", "512.00KBps", "980B/s") into
+/// bytes per second, keeping the fractional part that `parse_size_bytes()`
+/// would truncate.
+///
+/// Handles every unit yt-dlp prints (B/s, KiB/s, MiB/s, GiB/s, TiB/s) and
+/// N_m3u8DL-RE's "KBps"/"MBps" forms. Placeholders such as
+/// "Unknown B/s", "-" or an empty string give `None`.
+pub fn parse_speed(speed: &str) -> Option<f64> {
+    parse_scaled(speed)
+}
+
+/// Shared parser behind `parse_size_bytes()` and `parse_speed()`.
+fn parse_scaled(value: &str) -> Option<f64> {
+    let value = value.trim().trim_start_matches('~').trim();
+    let value = value
+        .strip_suffix("/s")
+        .or_else(|| value.strip_suffix("ps"))
+        .unwrap_or(value);
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+
+    let multiplier: u64 = match unit.trim().chars().next().map(|c| c.to_ascii_uppercase()) {
+        None | Some('B') => 1,
+        Some('K') => 1 << 10,
+        Some('M') => 1 << 20,
+        Some('G') => 1 << 30,
+        Some('T') => 1 << 40,
+        Some(_) => return None,
+    };
+    Some(number * multiplier as f64)
+}
+
+/// Formats a speed in bytes per second the way yt-dlp does (e.g. "2.51MiB/s").
+fn format_speed(bytes_per_second: f64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes_per_second;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2}{}/s", value, UNITS[unit])
+}
+
+/// Formats a duration in seconds as "MM:SS", or "HH:MM:SS" from an hour up.
+fn format_eta(seconds: u64) -> String {
+    let (hours, minutes, secs) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+    if hours > 0 {
+        format!("{:02}:{:02}:{:02}", hours, minutes, secs)
+    } else {
+        format!("{:02}:{:02}", minutes, secs)
+    }
+}
+
+// ============================================================
+// Frontend event throttling
+// ============================================================
+//
+// A fast HLS download prints hundreds of progress lines per second. The
+// queue applies every one, but forwarding each as a `gamdl-output` event
+// floods the IPC channel and keeps the webview re-rendering. `EmitThrottle`
+// lets through at most one `DownloadProgress` per interval, holding back
+// only the latest; every other event goes out at once, after any held
+// progress so the order is kept.
+
+/// Minimum time between two `DownloadProgress` events sent to the
+/// frontend for one download (at most ~10 per second).
+pub const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Rate-limits the `DownloadProgress` events of one download on their
+/// way to the frontend.
+///
+/// Feed every event through [`push()`](Self::push) and emit what it
+/// returns; call [`flush_due()`](Self::flush_due) periodically so a held
+/// progress update isn't left behind when output pauses, and
+/// [`flush()`](Self::flush) once the output ends.
+#[derive(Debug)]
+pub struct EmitThrottle {
+    /// Minimum time between two emitted progress events
+    interval: Duration,
+    /// When the last progress event was emitted
+    last_progress: Option<Instant>,
+    /// The latest progress event not yet emitted
+    pending: Option<GamdlOutputEvent>,
+}
+
+impl EmitThrottle {
+    /// Creates a throttle letting through one progress event per `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            last_progress: None,
+            pending: None,
+        }
+    }
+
+    /// Passes an event through the throttle, returning the events to emit
+    /// now, in order.
+    ///
+    /// A `DownloadProgress` within `interval` of the last emitted one is
+    /// held (replacing any held earlier one). Anything else flushes the
+    /// held progress and is emitted right away.
+    pub fn push(&mut self, event: GamdlOutputEvent, now: Instant) -> Vec<GamdlOutputEvent> {
+        if matches!(event, GamdlOutputEvent::DownloadProgress { .. }) {
+            if self.is_due(now) {
+                self.pending = None;
+                self.last_progress = Some(now);
+                vec![event]
+            } else {
+                self.pending = Some(event);
+                Vec::new()
+            }
+        } else {
+            let mut out: Vec<_> = self.flush().into_iter().collect();
+            out.push(event);
+            out
+        }
+    }
+
+    /// Returns the held progress event once `interval` has passed since the
+    /// last emitted one.
+    pub fn flush_due(&mut self, now: Instant) -> Option<GamdlOutputEvent> {
+        if self.pending.is_some() && self.is_due(now) {
+            self.last_progress = Some(now);
+            self.pending.take()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the held progress event, if any, regardless of timing.
+    pub fn flush(&mut self) -> Option<GamdlOutputEvent> {
+        self.pending.take()
+    }
+
+    /// Whether a progress event may be emitted at `now`.
+    fn is_due(&self, now: Instant) -> bool {
+        self.last_progress
+            .is_none_or(|last| now.duration_since(last) >= self.interval)
+    }
+}
+
+// ============================================================
+// Stall detection
+// ============================================================
+//
+// GAMDL occasionally hangs for good, e.g. on an HLS segment that never
+// arrives, without printing anything or exiting. With
+// `AppSettings::download_stall_timeout_secs` set, the queue's poll loop
+// kills a download once no line has arrived for that long. Every line
+// counts, progress lines included -- the queue reads each `\r` redraw of a
+// progress bar as a line -- so a slow download that's still moving keeps
+// going. The watch also remembers the step the download was on, so
+// the error says where it hung (e.g. waiting on the license server).
+
+/// Tracks when a running download last printed a line.
+///
+/// Call [`observe()`](Self::observe) for every output line and check
+/// [`is_stalled()`](Self::is_stalled) from the poll loop.
+#[derive(Debug)]
+pub struct StallWatch {
+    /// How long the output may pause; `None` never stalls
+    timeout: Option<Duration>,
+    /// When the last line arrived (or the process started)
+    last_output: Instant,
+    /// What the download was doing when the last step line arrived
+    last_step: Option<String>,
+}
+
+impl StallWatch {
+    /// Creates a watch for a process started at `now`.
+    pub fn new(timeout: Option<Duration>, now: Instant) -> Self {
+        Self {
+            timeout,
+            last_output: now,
+            last_step: None,
+        }
+    }
+
+    /// Records an output line that arrived at `now`.
+    pub fn observe(&mut self, now: Instant) {
+        self.last_output = now;
+    }
+
+    /// Remembers the step a parsed line shows the download is on: getting
+    /// the decryption key, downloading, or a post-processing step. Other
+    /// lines leave it unchanged.
+    pub fn observe_step(&mut self, event: &GamdlOutputEvent) {
+        match event {
+            GamdlOutputEvent::FetchingKeys { step } | GamdlOutputEvent::ProcessingStep { step } => {
+                self.last_step = Some(step.clone());
+            }
+            GamdlOutputEvent::DownloadProgress { .. } => {
+                self.last_step = Some("Downloading".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether the output has paused for the whole timeout at `now`.
+    pub fn is_stalled(&self, now: Instant) -> bool {
+        self.timeout
+            .is_some_and(|timeout| now.duration_since(self.last_output) >= timeout)
+    }
+
+    /// The error a download stopped by this watch fails with, classified
+    /// as `stall`, naming the last step if one was seen.
+    pub fn error_message(&self) -> String {
+        let secs = self.timeout.unwrap_or_default().as_secs_f64();
+        match self.last_step {
+            Some(ref step) => format!(
+                "{}: no output for {} seconds (last step: {})",
+                STALL_ERROR, secs, step
+            ),
+            None => format!("{}: no output for {} seconds", STALL_ERROR, secs),
+        }
+    }
+}
+
+// ============================================================
+// Python traceback capture
+// ============================================================
+//
+// When GAMDL crashes, Python prints a multi-line block to stderr:
+//
+//   Traceback (most recent call last):
+//     File "/.../gamdl/cli.py", line 512, in main
+//       downloader.download(track)
+//     File "/.../gamdl/downloader.py", line 88, in download
+//       raise KeyError(track_id)
+//   KeyError: '1440833098'
+//
+// Line by line, only the header looks like an error, and the useful part
+// -- the exception on the last line -- looks like nothing at all. The
+// collector buffers the indented frame lines and turns the block into a
+// single `Crash` event when the exception line arrives.
+
+/// Assembles Python tracebacks from consecutive output lines.
+///
+/// Create one per output stream (stdout and stderr are read separately)
+/// and pass every line with its parsed event through
+/// [`observe()`](Self::observe). Call [`finish()`](Self::finish) when the
+/// stream ends to flush a traceback that was cut off.
+#[derive(Debug, Default)]
+pub struct TracebackCollector {
+    /// Lines of the traceback being collected, `None` outside a traceback
+    lines: Option<Vec<String>>,
+}
+
+impl TracebackCollector {
+    /// Creates a collector that is not inside a traceback.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Passes a raw output line and its parsed event through the collector.
+    ///
+    /// Lines that belong to a traceback come back as `Unknown` (so frame
+    /// lines that happen to contain "not found" etc. aren't reported as
+    /// errors), except the final exception line, which comes back as a
+    /// `Crash` holding the whole block. Other lines are returned unchanged.
+    pub fn observe(&mut self, line: &str, event: GamdlOutputEvent) -> GamdlOutputEvent {
+        let line = line.trim_end();
+
+        if let Some(lines) = self.lines.as_mut() {
+            lines.push(line.to_string());
+            // Frames and source lines are indented; the first line at the
+            // left margin is the exception that ends the block.
+            if line.is_empty() || line.starts_with(char::is_whitespace) {
+                return GamdlOutputEvent::Unknown {
+                    raw: line.trim().to_string(),
+                };
+            }
+            let traceback = lines.join("\n");
+            self.lines = None;
+            return GamdlOutputEvent::Crash { traceback };
+        }
+
+        if matches!(event, GamdlOutputEvent::Crash { .. }) {
+            self.lines = Some(vec![line.trim().to_string()]);
+            return GamdlOutputEvent::Unknown {
+                raw: line.trim().to_string(),
+            };
+        }
+        if CHAINED_EXCEPTION_MARKERS.contains(&line.trim()) {
+            return GamdlOutputEvent::Unknown {
+                raw: line.trim().to_string(),
+            };
+        }
+        event
+    }
+
+    /// Returns a traceback that was still being collected when the stream
+    /// ended (e.g. the process was killed mid-print), if any.
+    pub fn finish(&mut self) -> Option<GamdlOutputEvent> {
+        self.lines.take().map(|lines| GamdlOutputEvent::Crash {
+            traceback: lines.join("\n"),
+        })
+    }
+}
+
+/// Returns the exception line of a traceback (e.g. "KeyError: '1440833098'"),
+/// which is what the download's error message shows.
+pub fn exception_summary(traceback: &str) -> &str {
+    traceback
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+}
+
 // ============================================================
 // Unit Tests
 // ============================================================
@@ -399,6 +1279,7 @@ mod tests {
                 percent,
                 speed,
                 eta,
+                ..
             } => {
                 assert!((percent - 45.2).abs() < 0.01);
                 assert_eq!(speed, "2.51MiB/s");
@@ -431,29 +1312,521 @@ mod tests {
         }
     }
 
-    // ----------------------------------------------------------
-    // parse_gamdl_output: Track info
-    // ----------------------------------------------------------
-
+    /// yt-dlp HLS downloads append the fragment counter after the ETA.
     #[test]
-    fn parses_song_track_info_with_artist() {
-        let line = "Getting song: Anti-Hero by Taylor Swift";
+    fn parses_ytdlp_fragmented_progress_line() {
+        let line = "[download]  12.3% of ~  48.20MiB at    3.21MiB/s ETA 00:13 (frag 15/122)";
         match parse_gamdl_output(line) {
-            GamdlOutputEvent::TrackInfo { title, artist, .. } => {
-                assert_eq!(title, "Anti-Hero");
-                assert_eq!(artist, "Taylor Swift");
+            GamdlOutputEvent::DownloadProgress {
+                percent,
+                speed,
+                eta,
+                bytes: Some(bytes),
+            } => {
+                assert!((percent - 12.3).abs() < 0.01);
+                assert_eq!(speed, "3.21MiB/s");
+                assert_eq!(eta, "00:13");
+                assert_eq!(bytes.total, (48.20 * 1048576.0) as u64);
+                assert_eq!(bytes.downloaded, (bytes.total as f64 * 0.123) as u64);
+                assert_eq!(bytes.speed, Some((3.21 * 1048576.0_f64).trunc()));
             }
-            other => panic!("Expected TrackInfo, got {:?}", other),
+            other => panic!("Expected DownloadProgress with bytes, got {:?}", other),
         }
     }
 
+    /// Before the first speed sample yt-dlp prints "Unknown B/s".
     #[test]
-    fn parses_track_info_without_artist() {
-        let line = "Getting song: Bohemian Rhapsody";
+    fn parses_ytdlp_progress_with_unknown_speed() {
+        let line = "[download]   0.0% of   10.00MiB at  Unknown B/s ETA Unknown";
         match parse_gamdl_output(line) {
-            GamdlOutputEvent::TrackInfo { title, artist, .. } => {
-                assert_eq!(title, "Bohemian Rhapsody");
-                assert_eq!(artist, "");
+            GamdlOutputEvent::DownloadProgress {
+                speed,
+                eta,
+                bytes: Some(bytes),
+                ..
+            } => {
+                assert_eq!(speed, "Unknown B/s");
+                assert_eq!(eta, "Unknown");
+                assert_eq!(bytes.total, 10 * 1048576);
+                assert_eq!(bytes.speed, None);
+            }
+            other => panic!("Expected DownloadProgress with bytes, got {:?}", other),
+        }
+    }
+
+    /// Newer yt-dlp versions add the average speed to the completion line.
+    #[test]
+    fn parses_ytdlp_completion_with_average_speed() {
+        let line = "[download] 100% of   48.20MiB in 00:00:15 at 3.14MiB/s";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::DownloadProgress {
+                percent,
+                bytes: Some(bytes),
+                ..
+            } => {
+                assert!((percent - 100.0).abs() < 0.01);
+                assert_eq!(bytes.downloaded, bytes.total);
+            }
+            other => panic!("Expected DownloadProgress with bytes, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_nm3u8dlre_video_progress_line() {
+        let line = "Vid 1920x1080 | 4500 Kbps | 30 ━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━ 45/180 25.00% 24.43MB/97.72MB 8.35MBps 00:00:09";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::DownloadProgress {
+                percent,
+                speed,
+                eta,
+                bytes: Some(bytes),
+            } => {
+                assert!((percent - 25.0).abs() < 0.01);
+                assert_eq!(speed, "8.35MBps");
+                assert_eq!(eta, "00:00:09");
+                assert_eq!(bytes.stream, "Vid 1920x1080 | 4500 Kbps | 30");
+                assert_eq!(bytes.downloaded, (24.43 * 1048576.0) as u64);
+                assert_eq!(bytes.total, (97.72 * 1048576.0) as u64);
+            }
+            other => panic!("Expected DownloadProgress with bytes, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_nm3u8dlre_audio_progress_line() {
+        let line = "Aud 256 Kbps | mp4a.40.2 | en | 2CH ━━━━━━━━━━━━━━━━ 18/180 10.00% 608.00KB/6.08MB 512.00KBps 00:00:11";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::DownloadProgress {
+                percent,
+                bytes: Some(bytes),
+                ..
+            } => {
+                assert!((percent - 10.0).abs() < 0.01);
+                assert_eq!(bytes.stream, "Aud 256 Kbps | mp4a.40.2 | en | 2CH");
+                assert_eq!(bytes.downloaded, 608 * 1024);
+                assert_eq!(bytes.speed, Some(512.0 * 1024.0));
+            }
+            other => panic!("Expected DownloadProgress with bytes, got {:?}", other),
+        }
+    }
+
+    // ----------------------------------------------------------
+    // parse_size_bytes
+    // ----------------------------------------------------------
+
+    #[test]
+    fn parses_sizes_and_speeds() {
+        assert_eq!(parse_size_bytes("5.00MiB"), Some(5 * 1048576));
+        assert_eq!(parse_size_bytes("~ 2KiB"), Some(2048));
+        assert_eq!(parse_size_bytes("1.5GB"), Some(1536 * 1048576));
+        assert_eq!(parse_size_bytes("512B"), Some(512));
+        assert_eq!(parse_size_bytes("2.00MiB/s"), Some(2 * 1048576));
+        assert_eq!(parse_size_bytes("512.00KBps"), Some(512 * 1024));
+        assert_eq!(parse_size_bytes("Unknown B/s"), None);
+        assert_eq!(parse_size_bytes("-"), None);
+    }
+
+    // ----------------------------------------------------------
+    // TracebackCollector
+    // ----------------------------------------------------------
+
+    /// Feeds lines through the parser and collector, returning the events.
+    fn collect(lines: &[&str]) -> (Vec<GamdlOutputEvent>, TracebackCollector) {
+        let mut collector = TracebackCollector::new();
+        let events = lines
+            .iter()
+            .map(|line| collector.observe(line, parse_gamdl_output(line)))
+            .collect();
+        (events, collector)
+    }
+
+    /// Returns the tracebacks of the Crash events in `events`.
+    fn crashes(events: &[GamdlOutputEvent]) -> Vec<&str> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                GamdlOutputEvent::Crash { traceback } => Some(traceback.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Verifies that a multi-line traceback becomes one Crash event with the
+    /// whole block, and that its frame lines aren't reported as errors.
+    #[test]
+    fn collects_multi_line_traceback() {
+        let lines = [
+            "Getting song: Song by Artist",
+            "Traceback (most recent call last):",
+            "  File \"/app/gamdl/cli.py\", line 512, in main",
+            "    downloader.download(track)",
+            "  File \"/app/gamdl/downloader.py\", line 88, in download",
+            "    raise KeyError(\"stream not found\")",
+            "    ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^",
+            "KeyError: 'stream not found'",
+            "[download]  45.2% of 5.00MiB at 2.51MiB/s ETA 00:01",
+        ];
+        let (events, mut collector) = collect(&lines);
+
+        let crashes = crashes(&events);
+        assert_eq!(crashes.len(), 1);
+        assert_eq!(crashes[0], lines[1..8].join("\n"));
+        assert_eq!(exception_summary(crashes[0]), "KeyError: 'stream not found'");
+        assert!(
+            !events.iter().any(|e| matches!(e, GamdlOutputEvent::Error { .. })),
+            "Frame lines must not be reported as errors: {:?}",
+            events
+        );
+        assert!(matches!(events[0], GamdlOutputEvent::TrackInfo { .. }));
+        assert!(matches!(events[8], GamdlOutputEvent::DownloadProgress { .. }));
+        assert!(collector.finish().is_none());
+    }
+
+    /// Verifies that chained exceptions give one Crash per traceback, the
+    /// last one holding the final exception.
+    #[test]
+    fn collects_chained_tracebacks() {
+        let (events, _) = collect(&[
+            "Traceback (most recent call last):",
+            "  File \"a.py\", line 1, in <module>",
+            "ValueError: bad value",
+            "",
+            "During handling of the above exception, another exception occurred:",
+            "",
+            "Traceback (most recent call last):",
+            "  File \"a.py\", line 3, in <module>",
+            "RuntimeError: download failed",
+        ]);
+
+        let crashes = crashes(&events);
+        assert_eq!(crashes.len(), 2);
+        assert_eq!(exception_summary(crashes[0]), "ValueError: bad value");
+        assert_eq!(exception_summary(crashes[1]), "RuntimeError: download failed");
+        assert!(!events.iter().any(|e| matches!(e, GamdlOutputEvent::Error { .. })));
+    }
+
+    /// Verifies that a traceback cut off by the end of output is flushed.
+    #[test]
+    fn finish_flushes_unterminated_traceback() {
+        let (events, mut collector) = collect(&[
+            "Traceback (most recent call last):",
+            "  File \"a.py\", line 1, in <module>",
+        ]);
+
+        assert!(crashes(&events).is_empty());
+        match collector.finish() {
+            Some(GamdlOutputEvent::Crash { traceback }) => {
+                assert!(traceback.starts_with("Traceback"));
+                assert_eq!(exception_summary(&traceback), "File \"a.py\", line 1, in <module>");
+            }
+            other => panic!("Expected Crash, got {:?}", other),
+        }
+    }
+
+    // ----------------------------------------------------------
+    // parse_speed
+    // ----------------------------------------------------------
+
+    /// Verifies every speed unit yt-dlp prints, B/s through GiB/s.
+    #[test]
+    fn parses_speed_units() {
+        assert_eq!(parse_speed("980.00B/s"), Some(980.0));
+        assert_eq!(parse_speed("1.50KiB/s"), Some(1.5 * 1024.0));
+        assert_eq!(parse_speed("2.51MiB/s"), Some(2.51 * 1048576.0));
+        assert_eq!(parse_speed("1.00GiB/s"), Some(1073741824.0));
+        assert_eq!(parse_speed("8.35MBps"), Some(8.35 * 1048576.0));
+        assert_eq!(parse_speed(" 12KiB/s "), Some(12.0 * 1024.0));
+    }
+
+    /// Verifies that malformed or placeholder speeds give None.
+    #[test]
+    fn rejects_malformed_speeds() {
+        for value in ["", "-", "Unknown B/s", "N/A", "1.2.3MiB/s", "5XiB/s", "MiB/s", "inf"] {
+            assert_eq!(parse_speed(value), None, "{:?} should not parse", value);
+        }
+    }
+
+    // ----------------------------------------------------------
+    // EmitThrottle
+    // ----------------------------------------------------------
+
+    fn progress(percent: f64) -> GamdlOutputEvent {
+        GamdlOutputEvent::DownloadProgress {
+            percent,
+            speed: String::new(),
+            eta: String::new(),
+            bytes: None,
+        }
+    }
+
+    fn percent_of(event: &GamdlOutputEvent) -> f64 {
+        match event {
+            GamdlOutputEvent::DownloadProgress { percent, .. } => *percent,
+            other => panic!("Expected DownloadProgress, got {:?}", other),
+        }
+    }
+
+    /// Rapid progress events within one interval collapse to the latest,
+    /// which goes out once the interval has passed.
+    #[test]
+    fn throttle_collapses_rapid_progress() {
+        let mut throttle = EmitThrottle::new(PROGRESS_EMIT_INTERVAL);
+        let start = Instant::now();
+
+        assert_eq!(throttle.push(progress(1.0), start).len(), 1);
+        for (i, percent) in [2.0, 3.0, 4.0].into_iter().enumerate() {
+            let at = start + Duration::from_millis(10 * (i as u64 + 1));
+            assert!(throttle.push(progress(percent), at).is_empty());
+        }
+        assert!(throttle.flush_due(start + Duration::from_millis(50)).is_none());
+
+        let held = throttle.flush_due(start + Duration::from_millis(100)).unwrap();
+        assert_eq!(percent_of(&held), 4.0);
+        assert!(throttle.flush().is_none());
+
+        let later = start + Duration::from_millis(250);
+        let emitted = throttle.push(progress(5.0), later);
+        assert_eq!(emitted.len(), 1);
+        assert_eq!(percent_of(&emitted[0]), 5.0);
+    }
+
+    /// An error is emitted immediately, right after the held progress.
+    #[test]
+    fn throttle_flushes_errors_immediately() {
+        let mut throttle = EmitThrottle::new(PROGRESS_EMIT_INTERVAL);
+        let start = Instant::now();
+        throttle.push(progress(10.0), start);
+        throttle.push(progress(20.0), start + Duration::from_millis(5));
+
+        let error = GamdlOutputEvent::Error {
+            message: "boom".to_string(),
+        };
+        let emitted = throttle.push(error, start + Duration::from_millis(6));
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(percent_of(&emitted[0]), 20.0);
+        assert!(matches!(&emitted[1], GamdlOutputEvent::Error { message } if message == "boom"));
+        assert!(throttle.flush().is_none());
+    }
+
+    // ----------------------------------------------------------
+    // StallWatch
+    // ----------------------------------------------------------
+
+    /// Output that keeps coming, however slowly, never stalls; a pause of
+    /// the whole timeout does, and without a timeout nothing ever stalls.
+    #[test]
+    fn stall_watch_times_out_on_silence_only() {
+        let start = Instant::now();
+        let mut watch = StallWatch::new(Some(Duration::from_secs(60)), start);
+        for minute in 1..10 {
+            let at = start + Duration::from_secs(59 * minute);
+            assert!(!watch.is_stalled(at));
+            watch.observe(at);
+        }
+        let last = start + Duration::from_secs(59 * 9);
+        assert!(!watch.is_stalled(last + Duration::from_secs(59)));
+        assert!(watch.is_stalled(last + Duration::from_secs(60)));
+        assert_eq!(
+            watch.error_message(),
+            "GAMDL stopped responding: no output for 60 seconds"
+        );
+        assert!(is_stall_error(&watch.error_message()));
+
+        let watch = StallWatch::new(None, start);
+        assert!(!watch.is_stalled(start + Duration::from_secs(86_400)));
+    }
+
+    /// The stall error names the step the download hung in: the key fetch,
+    /// the download, or a processing step; other lines don't change it.
+    #[test]
+    fn stall_watch_names_last_step() {
+        let start = Instant::now();
+        let mut watch = StallWatch::new(Some(Duration::from_secs(60)), start);
+        for line in [
+            "Getting track 1 of 12: Welcome to New York",
+            "[DEBUG    12:00:00] Getting decryption key",
+            "Some other log line",
+        ] {
+            watch.observe_step(&parse_gamdl_output(line));
+        }
+        assert_eq!(
+            watch.error_message(),
+            "GAMDL stopped responding: no output for 60 seconds \
+             (last step: Fetching decryption keys)"
+        );
+        assert!(is_stall_error(&watch.error_message()));
+
+        watch.observe_step(&parse_gamdl_output(
+            "[download]  45.2% of ~  5.12MiB at  2.51MiB/s ETA 00:01",
+        ));
+        assert!(watch.error_message().ends_with("(last step: Downloading)"));
+        watch.observe_step(&parse_gamdl_output("Remuxing to M4A"));
+        assert!(watch.error_message().ends_with("(last step: Remuxing to M4A)"));
+    }
+
+    // ----------------------------------------------------------
+    // ProgressTracker
+    // ----------------------------------------------------------
+
+    /// Feeds a line through the parser and tracker, returning the
+    /// resulting `(percent, speed, eta)`.
+    fn track(tracker: &mut ProgressTracker, line: &str) -> (f64, String, String) {
+        match tracker.observe(parse_gamdl_output(line)) {
+            GamdlOutputEvent::DownloadProgress {
+                percent, speed, eta, ..
+            } => (percent, speed, eta),
+            other => panic!("Expected DownloadProgress, got {:?}", other),
+        }
+    }
+
+    /// yt-dlp revises its "~" size estimate as fragments arrive, which
+    /// makes the raw percent dip; the tracked percent must not.
+    #[test]
+    fn tracker_percent_never_decreases() {
+        let mut tracker = ProgressTracker::new();
+        let lines = [
+            "[download]  20.0% of ~  10.00MiB at    1.00MiB/s ETA 00:08 (frag 2/10)",
+            "[download]  50.0% of ~  10.00MiB at    1.00MiB/s ETA 00:05 (frag 5/10)",
+            "[download]  47.0% of ~  11.00MiB at    1.00MiB/s ETA 00:06 (frag 5/10)",
+            "[download]  49.0% of ~  11.00MiB at    1.00MiB/s ETA 00:05 (frag 6/10)",
+            "[download]  70.0% of ~  11.00MiB at    1.00MiB/s ETA 00:03 (frag 7/10)",
+            "[download] 100% of   11.00MiB in 00:00:11 at 1.00MiB/s",
+        ];
+        let mut last = 0.0;
+        for line in lines {
+            let (percent, _, _) = track(&mut tracker, line);
+            assert!(percent >= last, "{} went backwards from {}", percent, last);
+            last = percent;
+        }
+        assert!((last - 100.0).abs() < 0.01);
+    }
+
+    /// N_m3u8DL-RE downloads video and audio concurrently; the overall
+    /// percent weighs each stream by its size.
+    #[test]
+    fn tracker_combines_concurrent_nm3u8dlre_streams() {
+        let mut tracker = ProgressTracker::new();
+        track(
+            &mut tracker,
+            "Vid 1920x1080 | 4500 Kbps | 30 ━━━━━━━━━━ 45/180 25.00% 24.00MB/96.00MB 8.00MBps 00:00:09",
+        );
+        let (percent, _, _) = track(
+            &mut tracker,
+            "Aud 256 Kbps | mp4a.40.2 | en | 2CH ━━━━━━━━━━ 180/180 100.00% 4.00MB/4.00MB 0.00Bps 00:00:00",
+        );
+        // (24 + 4) / (96 + 4)
+        assert!((percent - 28.0).abs() < 0.01, "got {}", percent);
+    }
+
+    /// yt-dlp fetches a video's streams one after another under the same
+    /// key; the finished stream's bytes still count towards the total.
+    #[test]
+    fn tracker_accounts_for_sequential_ytdlp_streams() {
+        let mut tracker = ProgressTracker::new();
+        let (video, _, _) = track(
+            &mut tracker,
+            "[download]  90.0% of   40.00MiB at    4.00MiB/s ETA 00:01",
+        );
+        assert!((video - 90.0).abs() < 0.01);
+
+        // Audio starts: 41 of 50 MiB overall is below 90%, so hold.
+        let (held, _, _) = track(
+            &mut tracker,
+            "[download]  10.0% of   10.00MiB at    4.00MiB/s ETA 00:02",
+        );
+        assert!((held - 90.0).abs() < 0.01);
+
+        // 48 of 50 MiB overall.
+        let (resumed, _, _) = track(
+            &mut tracker,
+            "[download]  80.0% of   10.00MiB at    4.00MiB/s ETA 00:00",
+        );
+        assert!((resumed - 96.0).abs() < 0.01, "got {}", resumed);
+    }
+
+    /// The speed is an exponential moving average of the samples and the
+    /// ETA is computed from it and the remaining bytes.
+    #[test]
+    fn tracker_smooths_speed_and_eta() {
+        let mut tracker = ProgressTracker::new();
+        let (_, speed, eta) = track(
+            &mut tracker,
+            "[download]   0.0% of   10.00MiB at    1.00MiB/s ETA 00:10",
+        );
+        assert_eq!(speed, "1.00MiB/s");
+        assert_eq!(eta, "00:10");
+
+        // A spike to 4 MiB/s only moves the average to 1.9 MiB/s, so the
+        // remaining 9 MiB take ceil(4.74) = 5 seconds instead of the
+        // raw line's 2.
+        let (_, speed, eta) = track(
+            &mut tracker,
+            "[download]  10.0% of   10.00MiB at    4.00MiB/s ETA 00:02",
+        );
+        assert_eq!(speed, "1.90MiB/s");
+        assert_eq!(eta, "00:05");
+    }
+
+    /// Without a speed sample the raw speed/ETA text is passed through.
+    #[test]
+    fn tracker_passes_through_unknown_speed() {
+        let mut tracker = ProgressTracker::new();
+        let (_, speed, eta) = track(
+            &mut tracker,
+            "[download]   0.0% of   10.00MiB at  Unknown B/s ETA Unknown",
+        );
+        assert_eq!(speed, "Unknown B/s");
+        assert_eq!(eta, "Unknown");
+    }
+
+    /// The next album track starts a fresh progress bar.
+    #[test]
+    fn tracker_resets_on_track_info() {
+        let mut tracker = ProgressTracker::new();
+        track(&mut tracker, "[download] 100% of 5.12MiB in 00:02");
+        tracker.observe(parse_gamdl_output("Getting track 2 of 12: Second Song"));
+        let (percent, _, _) = track(
+            &mut tracker,
+            "[download]  10.0% of   5.00MiB at    1.00MiB/s ETA 00:05",
+        );
+        assert!((percent - 10.0).abs() < 0.01, "got {}", percent);
+    }
+
+    #[test]
+    fn formats_eta_with_hours() {
+        assert_eq!(format_eta(59), "00:59");
+        assert_eq!(format_eta(3661), "01:01:01");
+    }
+
+    // ----------------------------------------------------------
+    // parse_gamdl_output: Track info
+    // ----------------------------------------------------------
+
+    #[test]
+    fn parses_song_track_info_with_artist() {
+        let line = "Getting song: Anti-Hero by Taylor Swift";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::TrackInfo { title, artist, .. } => {
+                assert_eq!(title, "Anti-Hero");
+                assert_eq!(artist, "Taylor Swift");
+            }
+            other => panic!("Expected TrackInfo, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_track_info_without_artist() {
+        let line = "Getting song: Bohemian Rhapsody";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::TrackInfo {
+                title,
+                artist,
+                track_number,
+                ..
+            } => {
+                assert_eq!(title, "Bohemian Rhapsody");
+                assert_eq!(artist, "");
+                assert_eq!(track_number, None);
             }
             other => panic!("Expected TrackInfo, got {:?}", other),
         }
@@ -463,14 +1836,52 @@ mod tests {
     fn parses_numbered_track_info() {
         let line = "Getting track 3 of 12: Song Title by Artist";
         match parse_gamdl_output(line) {
-            GamdlOutputEvent::TrackInfo { title, artist, .. } => {
+            GamdlOutputEvent::TrackInfo {
+                title,
+                artist,
+                track_number,
+                track_count,
+                ..
+            } => {
                 assert_eq!(title, "Song Title");
                 assert_eq!(artist, "Artist");
+                assert_eq!(track_number, Some(3));
+                assert_eq!(track_count, Some(12));
             }
             other => panic!("Expected TrackInfo, got {:?}", other),
         }
     }
 
+    /// The "(URL n/m) Checking" line marks the start of each album of a
+    /// multi-URL run, with or without GAMDL's color codes; its track lines
+    /// don't.
+    #[test]
+    fn parses_url_transition_markers() {
+        let url = "https://music.apple.com/us/album/red/1440933849";
+        for line in [
+            format!("[INFO     12:00:00] (URL 2/5) Checking \"{}\"", url),
+            format!("(\x1b[2mURL 2/5\x1b[0m) Checking \"{}\"", url),
+        ] {
+            match parse_gamdl_output(&line) {
+                GamdlOutputEvent::UrlStart {
+                    index,
+                    total,
+                    url: parsed,
+                } => {
+                    assert_eq!((index, total), (2, 5));
+                    assert_eq!(parsed, url);
+                }
+                other => panic!("Expected UrlStart for {:?}, got {:?}", line, other),
+            }
+        }
+
+        let track_line = "[INFO     12:00:05] (URL 2/5 - Track 3/12) Downloading \"Red\"";
+        assert!(!matches!(
+            parse_gamdl_output(track_line),
+            GamdlOutputEvent::UrlStart { .. }
+        ));
+    }
+
     #[test]
     fn handles_title_containing_by() {
         // "Stand by Me by Ben E. King" -- the last "by" is the separator
@@ -521,17 +1932,77 @@ mod tests {
         }
     }
 
+    /// The traceback header starts a Crash (completed by TracebackCollector);
+    /// other lines mentioning a traceback are still keyword errors.
     #[test]
     fn parses_keyword_error_traceback() {
         let line = "Traceback (most recent call last):";
         match parse_gamdl_output(line) {
+            GamdlOutputEvent::Crash { traceback } => {
+                assert_eq!(traceback, line);
+            }
+            other => panic!("Expected Crash, got {:?}", other),
+        }
+        match parse_gamdl_output("Printing traceback for track 5") {
             GamdlOutputEvent::Error { message } => {
-                assert!(message.contains("Traceback"));
+                assert!(message.contains("traceback"));
             }
             other => panic!("Expected Error, got {:?}", other),
         }
     }
 
+    // ----------------------------------------------------------
+    // parse_gamdl_output: Codec unavailable
+    // ----------------------------------------------------------
+
+    #[test]
+    fn parses_codec_unavailable() {
+        let line = "No matching stream for codec alac";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::CodecUnavailable { codec, message } => {
+                assert_eq!(codec, "alac");
+                assert_eq!(message, line);
+            }
+            other => panic!("Expected CodecUnavailable, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn parses_codec_unavailable_with_error_prefix() {
+        let line = "ERROR: No matching stream found for codec \"atmos\"";
+        match parse_gamdl_output(line) {
+            GamdlOutputEvent::CodecUnavailable { codec, message } => {
+                assert_eq!(codec, "atmos");
+                assert_eq!(message, "No matching stream found for codec \"atmos\"");
+            }
+            other => panic!("Expected CodecUnavailable, got {:?}", other),
+        }
+    }
+
+    // ----------------------------------------------------------
+    // parse_gamdl_output: Per-track codec
+    // ----------------------------------------------------------
+
+    /// Verifies that codec confirmation lines yield the codec, with or
+    /// without a log level prefix and quotes.
+    #[test]
+    fn parses_track_codec_confirmation() {
+        for (line, expected) in [
+            ("Using codec alac", "alac"),
+            ("[INFO] Selected song codec: \"AAC-legacy\"", "aac-legacy"),
+        ] {
+            match parse_gamdl_output(line) {
+                GamdlOutputEvent::TrackCodec { codec } => assert_eq!(codec, expected),
+                other => panic!("Expected TrackCodec for {:?}, got {:?}", line, other),
+            }
+        }
+        // An error that mentions a codec stays an error
+        assert!(matches!(
+            parse_gamdl_output("ERROR: failed using codec alac"),
+            GamdlOutputEvent::Error { .. }
+        ));
+    }
+
     // ----------------------------------------------------------
     // parse_gamdl_output: Processing steps
     // ----------------------------------------------------------
@@ -558,6 +2029,48 @@ mod tests {
         }
     }
 
+    /// Verifies GAMDL's key and license lines, with and without a log level
+    /// prefix, become FetchingKeys rather than a processing step.
+    #[test]
+    fn parses_fetching_keys_step() {
+        for line in [
+            "[DEBUG    12:00:00] Getting decryption key",
+            "Getting decryption keys",
+            "Getting widevine PSSH",
+            "[INFO] Requesting license for track 3 of 12",
+            "Fetching the license",
+            "Retrieving decryption key from license server",
+        ] {
+            match parse_gamdl_output(line) {
+                GamdlOutputEvent::FetchingKeys { step } => {
+                    assert_eq!(step, FETCHING_KEYS_STEP, "{}", line);
+                }
+                other => panic!("Expected FetchingKeys for {:?}, got {:?}", line, other),
+            }
+        }
+    }
+
+    /// Verifies that failed key fetches and lines that merely mention a
+    /// license stay errors or unknown.
+    #[test]
+    fn key_fetch_failures_are_not_fetching_keys() {
+        for line in [
+            "ERROR: Getting license failed",
+            "Getting decryption key failed: 403 Forbidden",
+            "Failed getting license",
+        ] {
+            assert!(
+                matches!(parse_gamdl_output(line), GamdlOutputEvent::Error { .. }),
+                "{}",
+                line
+            );
+        }
+        assert!(matches!(
+            parse_gamdl_output("Using the license of the account"),
+            GamdlOutputEvent::Unknown { .. }
+        ));
+    }
+
     #[test]
     fn parses_decrypting_step() {
         let line = "Decrypting with mp4decrypt";
@@ -676,6 +2189,133 @@ mod tests {
         assert_eq!(classify_error("Login required"), "auth");
     }
 
+    /// Verifies that representative free-tier/expired-subscription lines are
+    /// classified as "subscription", and never as a category that triggers
+    /// a network retry or codec fallback.
+    #[test]
+    fn classifies_subscription_errors() {
+        for line in [
+            "Exception: No active Apple Music subscription",
+            "[ERROR] This account does not have an active subscription",
+            "HTTP 403: subscription required (connection closed)",
+            "Your subscription has expired. Subscribe to Apple Music to continue.",
+            "This content is not available in your plan",
+        ] {
+            assert_eq!(classify_error(line), "subscription", "{}", line);
+        }
+    }
+
+    /// Verifies that storefront (region) restrictions are not mistaken for
+    /// subscription problems.
+    #[test]
+    fn region_errors_are_not_subscription_errors() {
+        assert!(!is_subscription_error("This album is not available in your country"));
+        assert!(!is_subscription_error("Song is not available in the jp storefront"));
+        assert_ne!(classify_error("Content is not available in this storefront"), "subscription");
+    }
+
+    /// Verifies that region restrictions get their own category, which the
+    /// queue answers with a storefront fallback.
+    #[test]
+    fn classifies_storefront_unavailable_errors() {
+        for line in [
+            "This album is not available in this storefront",
+            "[ERROR] Song is not available in the jp storefront",
+            "Content not available in your country",
+        ] {
+            assert_eq!(classify_error(line), "storefront_unavailable", "{}", line);
+        }
+        assert!(!is_storefront_unavailable_error("Codec not available"));
+    }
+
+    /// Verifies that the user-facing message explains the requirement and
+    /// keeps GAMDL's wording.
+    #[test]
+    fn subscription_message_explains_requirement() {
+        let message = subscription_error_message("No active subscription");
+        assert!(message.starts_with("An active Apple Music subscription is required"));
+        assert!(message.ends_with("(GAMDL: No active subscription)"));
+        assert_eq!(classify_error(&message), "subscription");
+    }
+
+    /// Verifies that disk-full messages from macOS/Linux and Windows are
+    /// classified as "disk", including ones that also mention a keyword of
+    /// a retried category.
+    #[test]
+    fn classifies_disk_full_errors() {
+        for line in [
+            "OSError: [Errno 28] No space left on device",
+            "av_interleaved_write_frame(): No space left on device",
+            "OSError: [Errno 122] Disk quota exceeded",
+            "OSError: [WinError 112] There is not enough space on the disk",
+            "Error writing file: ERROR_DISK_FULL",
+            "Failed to write track: disk full (connection kept alive)",
+        ] {
+            assert!(is_disk_full_error(line), "{}", line);
+            assert_eq!(classify_error(line), "disk", "{}", line);
+        }
+        assert!(!is_disk_full_error("Disk space check failed: permission denied"));
+    }
+
+    /// Verifies that Widevine, device file and decryption key failures are
+    /// classified as "drm", while a plain mp4decrypt failure stays a tool
+    /// error and a license request that hits a network error stays a
+    /// network error.
+    #[test]
+    fn classifies_drm_errors() {
+        for line in [
+            "pywidevine.exceptions.InvalidLicenseMessage: Failed to parse license response",
+            "[ERROR] Failed to get decryption key for track 3",
+            "FileNotFoundError: [Errno 2] No such file or directory: '/home/me/device.wvd'",
+            "ValueError: Widevine device file is invalid or corrupt",
+            "CDM session error: license request rejected",
+            "Unable to decrypt stream with the provided key",
+        ] {
+            assert!(is_drm_error(line), "{}", line);
+            assert_eq!(classify_error(line), "drm", "{}", line);
+        }
+        assert_eq!(classify_error("mp4decrypt returned exit code 1"), "tool");
+        assert!(!is_drm_error("Invalid cookies, please re-export them"));
+        assert_eq!(
+            classify_error("Widevine license request failed: connection timed out"),
+            "network",
+            "An unreachable license server is retried"
+        );
+        for line in [
+            "Failed to get decryption key: Read timed out. (read timeout=10)",
+            "License request failed: HTTPSConnectionPool(host='play.itunes.apple.com'): Max retries exceeded",
+            "Could not decrypt track: [Errno -3] Temporary failure in name resolution",
+            "CDM license challenge failed: Network is unreachable",
+        ] {
+            assert!(!is_drm_error(line), "{}", line);
+            assert_eq!(classify_error(line), "network", "{}", line);
+        }
+    }
+
+    /// Verifies that GAMDL's decryption errors get an explanation, while the
+    /// pre-spawn setup errors, which already have one, are kept as they are.
+    #[test]
+    fn drm_message_explains_decryption() {
+        let message = drm_error_message("Failed to get decryption key");
+        assert!(message.starts_with("Apple Music streams are encrypted"));
+        assert!(message.ends_with("(GAMDL: Failed to get decryption key)"));
+        assert_eq!(classify_error(&message), "drm");
+
+        let setup = format!("{}: the wrapper URL is empty", DRM_CONFIG_ERROR);
+        assert_eq!(drm_error_message(&setup), setup);
+        assert_eq!(classify_error(&setup), "drm");
+    }
+
+    /// Verifies that the user-facing message asks for space to be freed and
+    /// keeps the original wording.
+    #[test]
+    fn disk_full_message_asks_to_free_space() {
+        let message = disk_full_error_message("[Errno 28] No space left on device");
+        assert!(message.starts_with("The output drive is out of space."));
+        assert!(message.ends_with("(GAMDL: [Errno 28] No space left on device)"));
+        assert_eq!(classify_error(&message), "disk");
+    }
+
     #[test]
     fn classifies_network_errors() {
         assert_eq!(classify_error("Network timeout"), "network");
@@ -707,6 +2347,67 @@ mod tests {
         assert_eq!(classify_error("mp4decrypt returned error"), "tool");
     }
 
+    #[test]
+    fn classifies_codec_unavailable_errors() {
+        assert_eq!(
+            classify_error("No matching stream for codec alac"),
+            "codec_unavailable"
+        );
+        assert!(!is_codec_unavailable_error("Codec not available"));
+    }
+
+    #[test]
+    fn classifies_no_output_errors() {
+        assert_eq!(classify_error(NO_OUTPUT_ERROR), "no_output");
+        assert!(!is_no_output_error("Resource not found"));
+    }
+
+    #[test]
+    fn classifies_corrupt_output_errors() {
+        let message = format!(
+            "{}: 01 Song.m4a (Invalid data found when processing input)",
+            CORRUPT_OUTPUT_ERROR
+        );
+        assert_eq!(classify_error(&message), "corrupt_output");
+        assert!(!is_corrupt_output_error("Packet corrupt"));
+    }
+
+    /// Verifies that cover art failures are told apart from audio failures
+    /// of the same track.
+    #[test]
+    fn detects_cover_only_failures() {
+        for line in [
+            "[ERROR] Failed to download cover for track 3",
+            "Error getting artwork: HTTP 404",
+            "Unable to embed cover art in \"01 Style.m4a\"",
+            "requests.exceptions.HTTPError: 404 Client Error: Not Found for url: \
+             https://is1-ssl.mzstatic.com/image/thumb/Music/v4/aa/bb/cc/1200x1200bb.jpg",
+        ] {
+            assert!(is_cover_failure(line), "{}", line);
+        }
+        for line in [
+            "[ERROR] Failed to download track 3: HTTP 403",
+            "[ERROR] Failed to download \"Style\": No matching stream for codec alac",
+            "requests.exceptions.HTTPError: 404 Client Error: Not Found for url: \
+             https://aod.itunes.apple.com/itunes-assets/HLSMusic/P.m3u8",
+            "[INFO] Saved cover to /music/Taylor Swift/1989/Cover.jpg",
+        ] {
+            assert!(!is_cover_failure(line), "{}", line);
+        }
+
+        let message = format!("{} for 2 track(s)", COVER_MISSING_ERROR);
+        assert_eq!(classify_error(&message), "cover_missing");
+        assert!(!is_cover_missing_error("Failed to download cover"));
+    }
+
+    #[test]
+    fn detects_existing_file_skips() {
+        assert!(is_existing_file_skip(
+            "[WARNING] Skipping \"Anti-Hero\": file already exists"
+        ));
+        assert!(!is_existing_file_skip("Saved to: /music/song.m4a"));
+    }
+
     #[test]
     fn classifies_unknown_errors() {
         assert_eq!(classify_error("Something completely unexpected"), "unknown");
@@ -746,72 +2447,209 @@ pub fn is_codec_error(error_message: &str) -> bool {
         || lower.contains("drm")               // DRM-protected content (cannot be decoded)
 }
 
+/// Checks if an error message is GAMDL's "no matching stream for codec"
+/// message, meaning the codec isn't offered for this account tier or
+/// storefront region at all.
+///
+/// Unlike a plain codec error (one track missing one format), this applies
+/// to every track of the album, so `services::download_queue` aborts the
+/// job on the first track and falls back for the whole album at once.
+pub fn is_codec_unavailable_error(error_message: &str) -> bool {
+    CODEC_UNAVAILABLE_REGEX.is_match(error_message)
+}
+
+/// Phrases (lowercase) of failures caused by the account rather than the
+/// content: a free-tier or expired Apple Music subscription, or content the
+/// user's plan doesn't include. Storefront messages ("not available in your
+/// country") are deliberately not listed; those are region restrictions.
+const SUBSCRIPTION_ERROR_PATTERNS: &[&str] = &[
+    "subscription",
+    "your plan",
+    "subscribe to apple music",
+];
+
+/// Checks if an error message says the account has no active Apple Music
+/// subscription, or that the content isn't included in its plan.
+///
+/// Neither a retry nor a codec fallback can fix this, so the download
+/// queue fails the job straight away, as it does for authentication errors.
+pub fn is_subscription_error(error_message: &str) -> bool {
+    let lower = error_message.to_lowercase();
+    SUBSCRIPTION_ERROR_PATTERNS.iter().any(|p| lower.contains(p))
+}
+
+/// Builds the error shown to the user for a `"subscription"` failure,
+/// explaining what is needed. GAMDL's own message is kept at the end.
+pub fn subscription_error_message(error_message: &str) -> String {
+    format!(
+        "An active Apple Music subscription is required. The account in your \
+         cookies has no active subscription, or this content isn't included in \
+         its plan; renew it or re-import cookies from a subscribed account. \
+         (GAMDL: {})",
+        error_message
+    )
+}
+
+/// Checks if an error message says the content isn't available in the
+/// storefront of its URL. The download queue then retries the same
+/// catalog ID in another storefront (see `services::storefront_service`).
+pub fn is_storefront_unavailable_error(error_message: &str) -> bool {
+    STOREFRONT_UNAVAILABLE_REGEX.is_match(error_message)
+}
+
+/// Checks if an error message says the download's URL is malformed or
+/// unsupported. Such a download fails straight away: no retry or fallback
+/// can fix the URL.
+pub fn is_invalid_url_error(error_message: &str) -> bool {
+    INVALID_URL_REGEX.is_match(error_message)
+}
+
+/// Checks if an error message says the output drive ran out of space
+/// (or the user's disk quota). Retrying would only fail again at the same
+/// point, so the download queue fails the job straight away.
+pub fn is_disk_full_error(error_message: &str) -> bool {
+    DISK_FULL_REGEX.is_match(error_message)
+}
+
+/// Builds the error shown to the user for a `"disk"` failure, asking them
+/// to free up space. The original message is kept at the end.
+pub fn disk_full_error_message(error_message: &str) -> String {
+    format!(
+        "The output drive is out of space. Free up space on it (or choose \
+         another output folder in Settings) and retry the download. \
+         (GAMDL: {})",
+        error_message
+    )
+}
+
+/// Checks if an error message is a transient network failure (see
+/// `NETWORK_REGEX`). The download queue retries these.
+pub fn is_network_error(error_message: &str) -> bool {
+    NETWORK_REGEX.is_match(error_message)
+}
+
+/// Checks if an error message says a track couldn't be decrypted: the
+/// Widevine device (`.wvd` file) is missing or invalid, or no decryption
+/// key could be obtained. Retrying with the same setup fails the same way,
+/// so the download queue fails the job straight away.
+///
+/// A license request or key fetch that failed on the network (a timeout,
+/// a dropped connection) isn't a decryption failure: it would work on a
+/// retry, so it's left to is_network_error().
+pub fn is_drm_error(error_message: &str) -> bool {
+    DRM_REGEX.is_match(error_message) && !is_network_error(error_message)
+}
+
+/// Start of the errors `gamdl_service::check_drm_config()` fails a
+/// download with before GAMDL is spawned, when its decryption setup can't
+/// work. They already say what to fix, so drm_error_message() keeps them.
+pub const DRM_CONFIG_ERROR: &str = "Can't decrypt Apple Music streams with this setup";
+
+/// Checks if an error message is a decryption setup error produced by
+/// `gamdl_service` (see [`DRM_CONFIG_ERROR`]).
+pub fn is_drm_config_error(error_message: &str) -> bool {
+    error_message.starts_with(DRM_CONFIG_ERROR)
+}
+
+/// Builds the error shown to the user for a `"drm"` failure, explaining
+/// what decryption needs. The original message is kept at the end.
+pub fn drm_error_message(error_message: &str) -> String {
+    if is_drm_config_error(error_message) {
+        return error_message.to_string();
+    }
+    format!(
+        "Apple Music streams are encrypted, and this download couldn't be \
+         decrypted. Check the Widevine device file (.wvd) set for it, clear \
+         it to use GAMDL's built-in device, or enable the wrapper in \
+         Settings > Advanced. (GAMDL: {})",
+        error_message
+    )
+}
+
+/// Start of the error `gamdl_service::check_cookies_config()` fails a
+/// download with before GAMDL is spawned, when no cookies file is set and
+/// the wrapper is off. GAMDL would otherwise fail deep into the download
+/// with a message that doesn't say what's missing.
+pub const NO_COOKIES_ERROR: &str = "No Apple Music cookies are configured";
+
+/// Checks if an error message is the missing cookies error produced by
+/// `gamdl_service` (see [`NO_COOKIES_ERROR`]).
+pub fn is_no_cookies_error(error_message: &str) -> bool {
+    error_message.starts_with(NO_COOKIES_ERROR)
+}
+
+/// Error reported when GAMDL exits successfully but never saved a file.
+///
+/// Seen when every track of an album fails individually (e.g., the
+/// music-video cover bug or storefront restrictions): GAMDL logs each
+/// failure, skips the track, and still exits 0.
+pub const NO_OUTPUT_ERROR: &str =
+    "GAMDL finished without saving any files. Every track may have failed; check the log for per-track errors.";
+
+/// Checks if an error message is the "exited cleanly but saved nothing"
+/// error produced by `services::download_queue` (see [`NO_OUTPUT_ERROR`]).
+pub fn is_no_output_error(error_message: &str) -> bool {
+    error_message.starts_with(NO_OUTPUT_ERROR)
+}
+
+/// Error reported when the audio check (`AppSettings::verify_audio`)
+/// finds downloaded files that don't decode. The names of the files
+/// follow after a colon.
+pub const CORRUPT_OUTPUT_ERROR: &str = "Downloaded files failed the audio check";
+
+/// Checks if an error message is the failed audio check error produced by
+/// `services::download_queue` (see [`CORRUPT_OUTPUT_ERROR`]).
+pub fn is_corrupt_output_error(error_message: &str) -> bool {
+    error_message.starts_with(CORRUPT_OUTPUT_ERROR)
+}
+
+/// Error reported when a download is killed for printing nothing for
+/// `AppSettings::download_stall_timeout_secs`. How long, and the last step
+/// seen, follow after a colon.
+pub const STALL_ERROR: &str = "GAMDL stopped responding";
+
+/// Checks if an error message is the stall error produced by
+/// `services::download_queue` (see [`STALL_ERROR`]).
+pub fn is_stall_error(error_message: &str) -> bool {
+    error_message.starts_with(STALL_ERROR)
+}
+
+/// Checks if a GAMDL error line reports a cover art failure rather than an
+/// audio one (see `AppSettings::continue_on_cover_failure`).
+pub fn is_cover_failure(line: &str) -> bool {
+    COVER_FAILURE_REGEX.is_match(line)
+}
+
+/// Error reported when tracks of a download failed only on their cover
+/// art and `AppSettings::continue_on_cover_failure` is on. The download
+/// runs again without covers; the track count follows.
+pub const COVER_MISSING_ERROR: &str = "Cover art could not be downloaded";
+
+/// Checks if an error message is the cover art error produced by
+/// `services::download_queue` (see [`COVER_MISSING_ERROR`]).
+pub fn is_cover_missing_error(error_message: &str) -> bool {
+    error_message.starts_with(COVER_MISSING_ERROR)
+}
+
+/// Checks if a GAMDL output line reports that a track was skipped because
+/// its file already exists in the output folder. Such a run legitimately
+/// saves nothing new, so it must not be treated as a no-output failure.
+pub fn is_existing_file_skip(line: &str) -> bool {
+    line.to_lowercase().contains("already exists")
+}
+
 /// Classifies an error message into a named category for the React UI.
 ///
-/// Error categories serve two purposes:
-///   1. **Visual feedback** -- the React download queue component uses the
-///      category to select an icon, colour, and user-friendly description.
-///   2. **Retry logic** -- the download queue manager checks the category
-///      to decide whether automatic retry or quality fallback is appropriate
-///      (e.g., "auth" errors should not be retried automatically, but
-///      "network" errors might be).
-///
-/// Categories are returned as `&'static str` (compile-time string literals)
-/// to avoid heap allocation. The frontend matches on these exact strings.
-///
-/// # Category mapping
-/// | Category       | Keywords matched                          | Retry? |
-/// |----------------|-------------------------------------------|--------|
-/// | `"auth"`       | cookie, auth, login                       | No     |
-/// | `"network"`    | network, timeout, connection, dns         | Yes    |
-/// | `"codec"`      | (delegated to `is_codec_error`)           | Fallback|
-/// | `"not_found"`  | not found, 404, no results                | No     |
-/// | `"rate_limit"` | rate limit, 429, too many                 | Delayed|
-/// | `"tool"`       | ffmpeg, mp4decrypt, mp4box, nm3u8dl       | No     |
-/// | `"unknown"`    | (default)                                 | No     |
+/// Compatibility shim over `DownloadError::from_message()`, which holds the
+/// keyword rules (see `models::download_error` for the category table).
+/// The download queue matches on the `DownloadError` variant directly;
+/// this string form is kept for callers that only need the category.
 ///
 /// # Arguments
 /// * `error_message` - The error message string to classify.
 ///
 /// # Returns
-/// A `&'static str` category identifier.
-///
-/// # Connection
-/// Called by `services::download_queue` and `commands::gamdl` when reporting
-/// errors to the frontend.
+/// A `&'static str` category identifier ("network", "codec", ...).
 pub fn classify_error(error_message: &str) -> &'static str {
-    let lower = error_message.to_lowercase();
-
-    // Authentication / cookie errors: user needs to provide valid credentials.
-    if lower.contains("cookie") || lower.contains("auth") || lower.contains("login") {
-        "auth"
-    // Network errors: transient, may resolve on retry.
-    } else if lower.contains("network")
-        || lower.contains("timeout")
-        || lower.contains("connection")
-        || lower.contains("dns")
-    {
-        "network"
-    // Codec/format errors: the requested quality is not available; try fallback.
-    } else if is_codec_error(error_message) {
-        "codec"
-    // Content not found: the URL is invalid or the content was removed.
-    } else if lower.contains("not found") || lower.contains("404") || lower.contains("no results")
-    {
-        "not_found"
-    // Rate limiting: the server is throttling requests; retry after delay.
-    } else if lower.contains("rate limit") || lower.contains("429") || lower.contains("too many")
-    {
-        "rate_limit"
-    // External tool errors: FFmpeg, mp4decrypt, etc. failed during post-processing.
-    } else if lower.contains("ffmpeg")
-        || lower.contains("mp4decrypt")
-        || lower.contains("mp4box")
-        || lower.contains("nm3u8dl")
-    {
-        "tool"
-    // Default: unclassified error.
-    } else {
-        "unknown"
-    }
+    DownloadError::from_message(error_message).category()
 }
@@ -0,0 +1,55 @@
+// Copyright (c) 2024-2026 MeedyaDL
+// Licensed under the MIT License. See LICENSE file in the project root.
+//
+// Scratch folders for unit tests.
+// ================================
+//
+// Tests that touch the file system work in a `TestDir`: a unique, empty
+// folder under the system temp directory that's deleted with everything in
+// it when the value is dropped -- including when an assertion panics, so a
+// failing test doesn't leave its files behind.
+//
+// Compiled for tests only (see `utils/mod.rs`).
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+
+/// A unique scratch folder, deleted when dropped.
+pub struct TestDir {
+    path: PathBuf,
+}
+
+impl TestDir {
+    /// Creates `{temp}/meedyadl-{name}-{uuid}`; `name` tells the tests'
+    /// folders apart when one is left open in a debugger.
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("meedyadl-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&path).expect("Failed to create test folder");
+        Self { path }
+    }
+
+    /// The folder's path.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Deref for TestDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TestDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
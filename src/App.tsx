@@ -47,7 +47,8 @@ import { useEffect, useState } from 'react';
  * Tauri event listener API for receiving events emitted from the Rust backend.
  * `listen<T>(event, handler)` returns an unlisten function for cleanup.
  * Used here to receive: gamdl-output, download-complete, download-error,
- * download-cancelled, download-queued, and tray-check-updates events.
+ * download-cancelled, download-queued, download-cleared, and
+ * tray-check-updates events.
  * @see {@link https://v2.tauri.app/reference/javascript/api/namespacevent/} - Tauri event API
  */
 import { listen } from '@tauri-apps/api/event';
@@ -146,7 +147,7 @@ import './styles/themes/base.css';
  * Mirrors the Rust struct `GamdlProgress` serialized via serde.
  * @see ./types/index.ts for the full type definition
  */
-import type { GamdlProgress } from './types';
+import type { DownloadErrorEvent, GamdlProgress } from './types';
 
 /**
  * The root component that serves as the entry point for the application UI.
@@ -443,7 +444,7 @@ function App() {
    * Event payload type: `GamdlProgress` (see src/types/index.ts)
    * - download_id: unique ID linking this event to a queue item
    * - event: discriminated union (track_info | download_progress |
-   *   processing_step | error | complete | unknown)
+   *   fetching_keys | processing_step | error | complete | unknown)
    *
    * The `listen<T>()` generic parameter provides type safety for the payload.
    * `event.payload` is automatically deserialized from the Rust struct by
@@ -485,7 +486,7 @@ function App() {
   /*
    * ─── Effect 6: Download Lifecycle Event Listeners ──────────────────
    *
-   * Subscribes to four Tauri events that track the lifecycle of each
+   * Subscribes to five Tauri events that track the lifecycle of each
    * download queue item. These are emitted by the Rust download queue
    * manager when a download transitions between states.
    *
@@ -495,7 +496,7 @@ function App() {
    *    Fired when GAMDL finishes downloading all tracks for a queue item.
    *    Updates the item state to 'complete' and refreshes the queue.
    *
-   * 2. `download-error` (payload: { download_id: string, error: string })
+   * 2. `download-error` (payload: DownloadErrorEvent)
    *    Fired when GAMDL exits with an error or the process crashes.
    *    Updates the item state to 'error' with the error message.
    *
@@ -507,7 +508,11 @@ function App() {
    *    Fired when a new download is added or an existing one is retried.
    *    Triggers a full queue refresh to pick up the new item.
    *
-   * All four listeners are registered together and cleaned up together
+   * 5. `download-cleared` (payload: string[] = download_ids)
+   *    Fired when completed items leave the queue on their own
+   *    (`settings.auto_clear_completed_after_secs`). Refreshes the queue.
+   *
+   * All five listeners are registered together and cleaned up together
    * to ensure consistent state.
    *
    * @see {@link https://v2.tauri.app/develop/calling-frontend/#listening-to-events}
@@ -518,6 +523,7 @@ function App() {
     let unlistenError: (() => void) | undefined;
     let unlistenCancelled: (() => void) | undefined;
     let unlistenQueued: (() => void) | undefined;
+    let unlistenCleared: (() => void) | undefined;
 
     const setupListeners = async () => {
       try {
@@ -536,7 +542,7 @@ function App() {
         });
 
         /* 2. Download failed with an error */
-        unlistenError = await listen<{ download_id: string; error: string }>(
+        unlistenError = await listen<DownloadErrorEvent>(
           'download-error',
           (event) => {
             try {
@@ -568,6 +574,15 @@ function App() {
             console.error('Error in download-queued handler:', err);
           }
         });
+
+        /* 5. Completed downloads removed after the auto-clear delay */
+        unlistenCleared = await listen<string[]>('download-cleared', () => {
+          try {
+            refreshQueue();
+          } catch (err) {
+            console.error('Error in download-cleared handler:', err);
+          }
+        });
       } catch {
         /* Tauri API unavailable (running in browser dev mode) */
       }
@@ -576,7 +591,7 @@ function App() {
     setupListeners();
 
     /*
-     * Cleanup: unsubscribe all four listeners.
+     * Cleanup: unsubscribe all five listeners.
      * The optional chaining (?.) handles the case where listen() hasn't
      * resolved yet when the component unmounts.
      */
@@ -585,6 +600,7 @@ function App() {
       unlistenError?.();
       unlistenCancelled?.();
       unlistenQueued?.();
+      unlistenCleared?.();
     };
   }, [refreshQueue, handleDownloadComplete, handleDownloadError, handleDownloadCancelled]);
 
@@ -89,7 +89,7 @@ import { useSettingsStore } from '@/stores/settingsStore';
 import { useUiStore } from '@/stores/uiStore';
 
 /** Reusable UI primitives from the common component library. */
-import { Button, Select } from '@/components/common';
+import { Button, Input, Select } from '@/components/common';
 
 /**
  * Type imports for Apple Music content types and audio codecs.
@@ -213,6 +213,8 @@ export function DownloadForm() {
    * @see debugging.md -- "Key Zustand Lesson" on selector anti-patterns
    */
   const defaultSongCodec = useSettingsStore((s) => s.settings.default_song_codec);
+  /** Global excluded tags, shown as the placeholder of the override field. */
+  const defaultExcludeTags = useSettingsStore((s) => s.settings.exclude_tags);
   /** Shows a toast notification (success/error) after submission. */
   const addToast = useUiStore((s) => s.addToast);
 
@@ -472,6 +474,7 @@ export function DownloadForm() {
            * is true (conditional rendering). Contains:
            *  - Audio codec Select dropdown
            *  - Video resolution Select dropdown
+           *  - Excluded tags Input
            *  - "Clear overrides" button (shown only when overrides are set)
            */}
           {showOverrides && (
@@ -529,6 +532,26 @@ export function DownloadForm() {
                 placeholder="Use default"
               />
 
+              {/*
+               * Excluded tags override.
+               *
+               * A comma-separated list (e.g., "comment") that replaces the
+               * global `settings.exclude_tags` for this download only.
+               * Clearing the field removes the override again.
+               */}
+              <Input
+                label="Excluded Tags"
+                description="Tags to leave out for this download, comma-separated (replaces the default list)"
+                placeholder={defaultExcludeTags.join(', ') || 'Use default'}
+                value={overrideOptions?.exclude_tags ?? ''}
+                onChange={(e) => {
+                  const tags = e.target.value;
+                  const { exclude_tags: _previous, ...rest } = overrideOptions ?? {};
+                  const next = tags ? { ...rest, exclude_tags: tags } : rest;
+                  setOverrideOptions(Object.keys(next).length > 0 ? next : null);
+                }}
+              />
+
               {/*
                * "Clear overrides" button.
                * Only shown when `overrideOptions` is non-null (i.e., at
@@ -26,8 +26,8 @@
  * ## Store connections
  *
  *  - {@link useDownloadStore} -- reads `queueItems[]` and calls
- *    `refreshQueue()`, `cancelDownload()`, `retryDownload()`, and
- *    `clearFinished()`.
+ *    `refreshQueue()`, `cancelDownload()`, `retryDownload()`,
+ *    `clearFinished()`, `clearCompleted()`, and `clearFailed()`.
  *  - {@link useUiStore} -- `addToast()` for success/error feedback.
  *
  * @see https://react.dev/reference/react/useEffect   -- polling setup.
@@ -126,6 +126,12 @@ export function DownloadQueue() {
    */
   const clearFinished = useDownloadStore((s) => s.clearFinished);
 
+  /** Removes only completed items, keeping failed ones for retry. */
+  const clearCompleted = useDownloadStore((s) => s.clearCompleted);
+
+  /** Removes only failed items, keeping the completed history. */
+  const clearFailed = useDownloadStore((s) => s.clearFailed);
+
   /**
    * Exports the current queue to a `.meedyadl` file via a native save dialog.
    * Only non-terminal items (queued/active) are included in the export.
@@ -241,6 +247,19 @@ export function DownloadQueue() {
     }
   };
 
+  /**
+   * Clear items of a single outcome (completed or failed) from the queue.
+   * Wraps `clearCompleted()` / `clearFailed()` with toast feedback.
+   */
+  const handleClear = async (clear: () => Promise<number>) => {
+    try {
+      const removed = await clear();
+      addToast(`Cleared ${removed} item${removed !== 1 ? 's' : ''}`, 'info');
+    } catch {
+      addToast('Failed to clear queue', 'error');
+    }
+  };
+
   // ---------------------------------------------------------------
   // Derived values
   // ---------------------------------------------------------------
@@ -254,6 +273,10 @@ export function DownloadQueue() {
     (i) => i.state === 'complete' || i.state === 'error' || i.state === 'cancelled',
   ).length;
 
+  /** Counts for the "Clear Completed" and "Clear Failed" actions. */
+  const completedCount = queueItems.filter((i) => i.state === 'complete').length;
+  const failedCount = queueItems.filter((i) => i.state === 'error').length;
+
   /**
    * Count of items eligible for export: non-terminal items that are
    * queued, downloading, or processing. The Export button is only
@@ -326,6 +349,30 @@ export function DownloadQueue() {
               </Button>
             )}
 
+            {/*
+             * "Clear Completed" / "Clear Failed" -- shown only when the
+             * queue holds both outcomes, since otherwise "Clear Finished"
+             * already does the same thing.
+             */}
+            {completedCount > 0 && failedCount > 0 && (
+              <>
+                <Button
+                  variant="ghost"
+                  size="sm"
+                  onClick={() => handleClear(clearCompleted)}
+                >
+                  Clear Completed ({completedCount})
+                </Button>
+                <Button
+                  variant="ghost"
+                  size="sm"
+                  onClick={() => handleClear(clearFailed)}
+                >
+                  Clear Failed ({failedCount})
+                </Button>
+              </>
+            )}
+
             {/*
              * Manual refresh button -- fetches the latest queue state
              * from the backend. Useful if real-time events are delayed
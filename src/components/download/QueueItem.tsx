@@ -24,7 +24,15 @@
  * chain (`settings.music_fallback_chain`). If a fallback occurs, the
  * backend sets `fallback_occurred: true` and `codec_used` on the queue
  * item. This component displays a yellow warning message showing the
- * codec that was actually used.
+ * codec that was actually used, and why (`fallback_reason`).
+ *
+ * Tracks left out before the download started (`skipped_tracks`, e.g. no
+ * longer available in the catalog) are counted below, with their titles in
+ * the tooltip. Tracks saved without cover art, because it couldn't be
+ * downloaded (`cover_missing_tracks`), are counted there too. Downloads of
+ * several URLs show which one GAMDL is on ("Album 2 of 5", from
+ * `url_progress`), and active ones the step GAMDL is on besides
+ * downloading (`current_step`, e.g. "Fetching decryption keys").
  *
  * ## Props
  *
@@ -74,12 +82,18 @@ import {
  */
 import { ProgressBar } from '@/components/common';
 
+/**
+ * Calls off the automatic removal of a completed item the user opened.
+ * @see keepDownload in @/lib/tauri-commands.ts
+ */
+import { keepDownload } from '@/lib/tauri-commands';
+
 /**
  * Type imports for queue item data and download state.
  * @see QueueItemStatus in @/types/index.ts  -- full shape of a queue item.
  * @see DownloadState in @/types/index.ts    -- 'queued' | 'downloading' | ... union.
  */
-import type { QueueItemStatus, DownloadState } from '@/types';
+import type { QueueItemStatus, DownloadState, UrlProgress } from '@/types';
 
 /**
  * Props for the {@link QueueItem} component.
@@ -159,6 +173,39 @@ const STATE_CONFIG: Record<
   },
 };
 
+/**
+ * Summarises the per-track codecs of a download as "10 ALAC, 2 AAC",
+ * most common codec first. Returns null unless the tracks got more than
+ * one codec (a single codec is already shown by the fallback indicator).
+ *
+ * @param trackCodecs - `[track, codec]` pairs from `item.track_codecs`.
+ */
+function summariseTrackCodecs(trackCodecs: [string, string][]): string | null {
+  const counts = new Map<string, number>();
+  for (const [, codec] of trackCodecs) {
+    counts.set(codec, (counts.get(codec) ?? 0) + 1);
+  }
+  if (counts.size < 2) return null;
+  return [...counts.entries()]
+    .sort((a, b) => b[1] - a[1])
+    .map(([codec, count]) => `${count} ${codec.toUpperCase()}`)
+    .join(', ');
+}
+
+/**
+ * Describes which URL of a multi-URL download GAMDL is on, as
+ * "Album 2 of 5" ("URL 2 of 5" unless every URL is an album). Returns null
+ * before GAMDL reached the first one, and for single-URL downloads.
+ *
+ * @param urlProgress - Per-URL progress from `item.url_progress`.
+ */
+function describeUrlProgress(urlProgress: UrlProgress[]): string | null {
+  const current = urlProgress.findIndex((u) => u.state === 'downloading');
+  if (current < 0) return null;
+  const noun = urlProgress.every((u) => u.url.includes('/album/')) ? 'Album' : 'URL';
+  return `${noun} ${current + 1} of ${urlProgress.length}`;
+}
+
 /**
  * Renders a single item in the download queue with status icon,
  * progress tracking, fallback indicator, and context-sensitive
@@ -202,6 +249,12 @@ export function QueueItem({ item, onCancel, onRetry }: QueueItemProps) {
    */
   const isActive = item.state === 'downloading' || item.state === 'processing';
 
+  /** "10 ALAC, 2 AAC" when the tracks got different codecs, else null. */
+  const codecSummary = summariseTrackCodecs(item.track_codecs);
+
+  /** "Album 2 of 5" while a multi-URL download is running, else null. */
+  const urlPosition = describeUrlProgress(item.url_progress);
+
   /**
    * Opens the output folder in the native file manager (Finder on macOS,
    * Explorer on Windows, or the default file manager on Linux).
@@ -218,6 +271,8 @@ export function QueueItem({ item, onCancel, onRetry }: QueueItemProps) {
    */
   const handleOpenFolder = async () => {
     if (!item.output_path) return;
+    /* Looking at the item keeps it from being auto-cleared */
+    keepDownload(item.id).catch(() => {});
     try {
       const { open } = await import('@tauri-apps/plugin-shell');
       /*
@@ -286,13 +341,27 @@ export function QueueItem({ item, onCancel, onRetry }: QueueItemProps) {
           {/*
            * Primary URL text.
            * Shows the first URL from the `item.urls` array (there is
-           * typically only one URL per download request).
+           * typically only one URL per download request; batch imports
+           * carry many, shown as a "+N more" suffix).
            * `truncate` clips long URLs with an ellipsis.
            */}
           <p className="text-sm text-content-primary truncate">
             {item.urls[0]}
+            {item.urls.length > 1 && (
+              <span className="text-content-secondary">
+                {' '}(+{item.urls.length - 1} more)
+              </span>
+            )}
           </p>
 
+          {/*
+           * User label -- a free-text note set at enqueue time or via
+           * setDownloadLabel(). Metadata only; shown above the track info.
+           */}
+          {item.label && (
+            <p className="text-xs text-accent mt-0.5 truncate">{item.label}</p>
+          )}
+
           {/*
            * Current track name -- shown when the backend reports which
            * track is currently being downloaded/processed (for album and
@@ -304,6 +373,31 @@ export function QueueItem({ item, onCancel, onRetry }: QueueItemProps) {
             </p>
           )}
 
+          {/*
+           * Current step -- what GAMDL is doing besides downloading the
+           * stream, e.g. waiting on the license server or remuxing.
+           */}
+          {item.current_step && isActive && (
+            <p className="text-xs text-content-tertiary mt-0.5 truncate">
+              {item.current_step}
+            </p>
+          )}
+
+          {/* Multi-URL download: which of its URLs GAMDL is on */}
+          {urlPosition && (
+            <p className="text-xs text-content-tertiary mt-0.5">{urlPosition}</p>
+          )}
+
+          {/*
+           * Finished track count -- reported for batch imports, whose
+           * per-track percentage says little about the whole job.
+           */}
+          {item.completed_tracks !== null && (
+            <p className="text-xs text-content-tertiary mt-0.5">
+              {item.completed_tracks} track{item.completed_tracks === 1 ? '' : 's'} done
+            </p>
+          )}
+
           {/*
            * Fallback chain indicator.
            *
@@ -313,16 +407,45 @@ export function QueueItem({ item, onCancel, onRetry }: QueueItemProps) {
            * chain (e.g., ALAC -> AAC).
            *
            * Shows a yellow warning icon + the codec that was actually
-           * used (`item.codec_used`).
+           * used (`item.codec_used`), with the reason when known
+           * (`item.fallback_reason`).
            *
            * @see settings.music_fallback_chain in @/stores/settingsStore.ts
            */}
           {item.fallback_occurred && (
             <div className="flex items-center gap-1 mt-1 text-xs text-status-warning">
               <AlertTriangle size={12} />
-              <span>Fallback used (codec: {item.codec_used})</span>
+              <span>
+                {item.fallback_reason
+                  ? `${item.fallback_reason}; downloaded ${item.codec_used} instead`
+                  : `Fallback used (codec: ${item.codec_used})`}
+              </span>
             </div>
           )}
+
+          {/* Mixed album: tracks got different codecs (e.g., "10 ALAC, 2 AAC") */}
+          {codecSummary && (
+            <p className="text-xs text-content-tertiary mt-0.5">{codecSummary}</p>
+          )}
+
+          {/* Tracks left out up front (no longer available in the catalog) */}
+          {item.skipped_tracks.length > 0 && (
+            <p
+              className="text-xs text-content-tertiary mt-0.5"
+              title={item.skipped_tracks.map((t) => t.name || t.id).join('\n')}
+            >
+              {item.skipped_tracks.length} unavailable{' '}
+              {item.skipped_tracks.length === 1 ? 'track' : 'tracks'} skipped
+            </p>
+          )}
+
+          {/* Tracks saved without their cover art */}
+          {item.cover_missing_tracks > 0 && (
+            <p className="text-xs text-content-tertiary mt-0.5">
+              {item.cover_missing_tracks}{' '}
+              {item.cover_missing_tracks === 1 ? 'track' : 'tracks'} saved without cover art
+            </p>
+          )}
         </div>
 
         {/*
@@ -38,6 +38,7 @@ function createItem(
     state,
     progress: state === 'complete' ? 100 : 0,
     current_track: null,
+    current_step: null,
     total_tracks: null,
     completed_tracks: null,
     speed: null,
@@ -46,7 +47,13 @@ function createItem(
     output_path: state === 'complete' ? '/tmp/output' : null,
     codec_used: 'alac',
     fallback_occurred: false,
+    fallback_reason: null,
+    track_codecs: [],
+    skipped_tracks: [],
+    cover_missing_tracks: 0,
+    url_progress: [],
     created_at: new Date().toISOString(),
+    label: null,
   };
 }
 
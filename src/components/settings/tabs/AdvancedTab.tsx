@@ -6,7 +6,7 @@
  *
  * Renders the "Advanced" tab within the {@link SettingsPage} component.
  * This tab exposes expert-level settings that most users will not need to
- * change, organised into three sections:
+ * change, organised into four sections:
  *
  * ## Section 1: Processing
  *
@@ -22,6 +22,25 @@
  *       - `ffmpeg` (recommended): Uses FFmpeg for remuxing
  *       - `mp4box`: Uses MP4Box as an alternative
  *
+ *   - **Batch Imports** -- Runs an imported queue file as a single GAMDL
+ *     process reading its URLs from a text file. Faster for large imports,
+ *     but retries and fallbacks apply to the whole batch and per-album
+ *     extras are skipped. Maps to `settings.batch_mode`.
+ *   - **Hide GAMDL Exceptions** -- Passes `--no-exceptions` so GAMDL
+ *     doesn't print Python tracebacks. Maps to
+ *     `settings.suppress_gamdl_exceptions`.
+ *   - **Process Priority** -- Niceness (-20 to 19) GAMDL and its FFmpeg
+ *     jobs run at, so downloads yield the CPU on a shared machine; Windows
+ *     uses the nearest priority class. Empty means normal priority. Maps
+ *     to `settings.process_niceness` (nullable number).
+ *   - **Use My GAMDL Config File** -- Lets GAMDL read the user's own
+ *     `~/.gamdl/config.ini` (omits `--config-path`). Options set in
+ *     MeedyaDL still take precedence. Maps to
+ *     `settings.use_gamdl_config_file`.
+ *   - **Save Download Logs** -- Writes each download's GAMDL output to a
+ *     log file of its own, which the queue can open or copy for a bug
+ *     report. Maps to `settings.save_download_logs`.
+ *
  * ## Section 2: Wrapper
  *
  *   - **Use Wrapper** -- Toggle to use a wrapper service for account
@@ -35,10 +54,24 @@
  *   - **Truncate Filenames** -- Maximum filename length in characters.
  *     When set, filenames exceeding this length are truncated. Maps to
  *     `settings.truncate` (nullable number).
+ *   - **Date Tag Format** -- strftime pattern for the date tag (e.g.
+ *     `%Y` for year-only dates). Empty keeps GAMDL's default. Maps to
+ *     `settings.date_tag_template` (nullable string) and GAMDL's
+ *     `--date-tag-template` flag; invalid patterns are rejected on save.
  *   - **Excluded Tags** -- Comma-separated list of metadata tags to strip
  *     from downloaded files (e.g., "lyrics, comment"). Maps to
  *     `settings.exclude_tags: string[]`.
  *
+ * ## Section 4: Headless API
+ *
+ *   - **Enable Headless API** -- Starts a localhost HTTP server scripts can
+ *     use to enqueue, list, cancel and retry downloads. Maps to
+ *     `settings.headless_api_enabled`; applies after a restart.
+ *   - **Port** -- The 127.0.0.1 port it listens on. Maps to
+ *     `settings.headless_api_port`.
+ *   - **Copy Token** -- Copies the bearer token (kept in the OS keychain,
+ *     created on first use) via `getHeadlessApiToken()`.
+ *
  * ## Store Connection
  *
  * Reads and writes the Zustand `settingsStore`.
@@ -48,12 +81,17 @@
  * @see {@link @/types/index.ts}           -- DownloadMode, RemuxMode types
  */
 
+import { useCallback, useState } from 'react';
+
 // Zustand store for reading/writing advanced settings.
 import { useSettingsStore } from '@/stores/settingsStore';
 
 // Shared form components: Select for mode dropdowns, Toggle for boolean switches,
-// Input for text/number fields.
-import { Select, Toggle, Input } from '@/components/common';
+// Input for text/number fields, Button for the token copy action.
+import { Select, Toggle, Input, Button } from '@/components/common';
+
+// Fetches (creating on first use) the headless API token from the keychain.
+import { getHeadlessApiToken } from '@/lib/tauri-commands';
 
 // TypeScript union types for download and remux mode values.
 import type { DownloadMode, RemuxMode } from '@/types';
@@ -80,9 +118,9 @@ const REMUX_MODE_OPTIONS = [
 /**
  * AdvancedTab -- Renders the Advanced settings tab.
  *
- * Contains three sections: Processing (download/remux mode), Wrapper
- * (authentication service config), and File Options (truncation and
- * excluded tags). The Wrapper URL field uses conditional rendering,
+ * Contains four sections: Processing (download/remux mode), Wrapper
+ * (authentication service config), File Options (truncation and
+ * excluded tags), and Headless API (scripting server and its token). The Wrapper URL field uses conditional rendering,
  * only appearing when `settings.use_wrapper` is true.
  */
 export function AdvancedTab() {
@@ -90,6 +128,19 @@ export function AdvancedTab() {
   const settings = useSettingsStore((s) => s.settings);
   /** Partial-update function for persisting advanced setting changes */
   const updateSettings = useSettingsStore((s) => s.updateSettings);
+  /** Whether the API token was just copied (shows "Copied!" for 2 seconds) */
+  const [tokenCopied, setTokenCopied] = useState(false);
+
+  /** Copies the headless API token to the clipboard. */
+  const handleCopyToken = useCallback(async () => {
+    try {
+      await navigator.clipboard.writeText(await getHeadlessApiToken());
+      setTokenCopied(true);
+      setTimeout(() => setTokenCopied(false), 2000);
+    } catch {
+      /* Keychain or clipboard unavailable -- leave the button as is. */
+    }
+  }, []);
 
   return (
     <div className="space-y-6 max-w-xl">
@@ -120,6 +171,50 @@ export function AdvancedTab() {
             updateSettings({ remux_mode: e.target.value as RemuxMode })
           }
         />
+
+        {/* Batch imports */}
+        <Toggle
+          label="Batch Imports"
+          description="Download imported queue files in a single GAMDL run. Much faster for hundreds of URLs, but a retry or codec fallback applies to the whole batch, and animated artwork, music video lyrics and companion downloads are skipped."
+          checked={settings.batch_mode}
+          onChange={(checked) => updateSettings({ batch_mode: checked })}
+        />
+
+        <Toggle
+          label="Hide GAMDL Exceptions"
+          description="Stop GAMDL from printing Python tracebacks when it crashes. Failed downloads then show a less specific error message."
+          checked={settings.suppress_gamdl_exceptions}
+          onChange={(checked) => updateSettings({ suppress_gamdl_exceptions: checked })}
+        />
+
+        {/* Process priority -- nullable number field; empty means normal */}
+        <Input
+          label="Process Priority"
+          description="Niceness GAMDL and its FFmpeg jobs run at, from -20 (highest) to 19 (lowest). Higher values leave more CPU to other work on a shared machine. Leave empty for normal priority."
+          type="number"
+          min={-20}
+          max={19}
+          value={settings.process_niceness?.toString() ?? ''}
+          placeholder="Normal"
+          onChange={(e) => {
+            const val = e.target.value;
+            updateSettings({ process_niceness: val ? parseInt(val, 10) : null });
+          }}
+        />
+
+        <Toggle
+          label="Use My GAMDL Config File"
+          description="Let GAMDL read your own ~/.gamdl/config.ini. Options MeedyaDL passes on the command line still take precedence; your file only fills in the rest."
+          checked={settings.use_gamdl_config_file}
+          onChange={(checked) => updateSettings({ use_gamdl_config_file: checked })}
+        />
+
+        <Toggle
+          label="Save Download Logs"
+          description="Keep each download's GAMDL output in a log file of its own, to attach to bug reports. The cookies file path is hidden when the log is shown."
+          checked={settings.save_download_logs}
+          onChange={(checked) => updateSettings({ save_download_logs: checked })}
+        />
       </div>
 
       {/* Section: Wrapper */}
@@ -174,6 +269,19 @@ export function AdvancedTab() {
           }}
         />
 
+        {/* Date tag format -- nullable string field. An empty input stores
+            `null` so GAMDL keeps its default full timestamp. */}
+        <Input
+          label="Date Tag Format"
+          description="strftime pattern for the date tag, e.g. %Y for the year only (leave empty for GAMDL's default)"
+          value={settings.date_tag_template ?? ''} /* null -> '' for display */
+          placeholder="%Y-%m-%dT%H:%M:%SZ"
+          onChange={(e) => {
+            const val = e.target.value;
+            updateSettings({ date_tag_template: val.trim() ? val : null }); // Blank -> null
+          }}
+        />
+
         {/* Excluded tags -- comma-separated string <-> string[] conversion.
             The display value joins the array with ', ' for readability.
             The onChange handler splits on commas, trims whitespace from
@@ -193,6 +301,43 @@ export function AdvancedTab() {
           }}
         />
       </div>
+
+      {/* Section: Headless API */}
+      <div className="space-y-4">
+        <h3 className="text-sm font-semibold text-content-primary mb-4">
+          Headless API
+        </h3>
+
+        <Toggle
+          label="Enable Headless API"
+          description="Let scripts enqueue, list, cancel and retry downloads over HTTP on 127.0.0.1. Requests need the token below. Takes effect after restarting MeedyaDL."
+          checked={settings.headless_api_enabled}
+          onChange={(checked) => updateSettings({ headless_api_enabled: checked })}
+        />
+
+        {settings.headless_api_enabled && (
+          <>
+            <Input
+              label="Port"
+              description="Local port the API listens on"
+              type="number"
+              min={1024}
+              max={65535}
+              value={settings.headless_api_port.toString()}
+              onChange={(e) => {
+                const port = parseInt(e.target.value, 10);
+                if (port >= 1024 && port <= 65535) {
+                  updateSettings({ headless_api_port: port });
+                }
+              }}
+            />
+
+            <Button variant="secondary" size="sm" onClick={handleCopyToken}>
+              {tokenCopied ? 'Copied!' : 'Copy Token'}
+            </Button>
+          </>
+        )}
+      </div>
     </div>
   );
 }
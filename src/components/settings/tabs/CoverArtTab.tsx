@@ -23,6 +23,23 @@
  *     art image. Valid range: 100-3000. Maps to `settings.cover_size` and
  *     GAMDL's `--cover-size` flag.
  *
+ *   - **ALAC / AAC Cover Size** -- Optional sizes that replace Cover Size
+ *     for downloads and companions in that codec (e.g. full size for
+ *     lossless copies, smaller for phone copies). Empty uses Cover Size.
+ *     Maps to `settings.cover_size_by_codec`.
+ *
+ *   - **Embedded Cover Size** -- Optional smaller size for the cover
+ *     embedded in each track; the saved cover file keeps Cover Size. Empty
+ *     embeds the full-size cover. Maps to `settings.embedded_cover_size`.
+ *
+ *   - **Extra Cover Sizes** -- Comma-separated sizes the saved cover is
+ *     also written at, as `cover-{size}.jpg` (e.g. a thumbnail). Parsed
+ *     when the field loses focus. Maps to `settings.extra_cover_sizes`.
+ *
+ *   - **Save Tracks Without Cover Art** -- When a track's cover art fails
+ *     to download, run the download once more without the cover instead
+ *     of failing it. Maps to `settings.continue_on_cover_failure`.
+ *
  * ## Animated Artwork (MusicKit API)
  *
  *   - **Download Animated Cover Art** -- Toggle to enable/disable automatic
@@ -67,8 +84,8 @@ import { Select, Toggle, Input } from '@/components/common';
 // IPC wrappers for keychain credential storage (private key).
 import { storeCredential, getCredential } from '@/lib/tauri-commands';
 
-// TypeScript union type for cover format values.
-import type { CoverFormat } from '@/types';
+// TypeScript union types for cover format and codec values.
+import type { CoverFormat, SongCodec } from '@/types';
 
 /**
  * Dropdown options for the cover art format selector.
@@ -101,6 +118,27 @@ export function CoverArtTab() {
   /** Status message for the private key save operation */
   const [keyStatus, setKeyStatus] = useState('');
 
+  /**
+   * Sets or clears (when blank) the cover size of one codec. Values
+   * outside 100-3000 are ignored, as for Cover Size.
+   */
+  const updateCodecCoverSize = (codec: SongCodec, value: string) => {
+    const sizes = { ...settings.cover_size_by_codec };
+    if (!value.trim()) {
+      delete sizes[codec];
+    } else {
+      const size = parseInt(value, 10);
+      if (isNaN(size) || size < 100 || size > 3000) return;
+      sizes[codec] = size;
+    }
+    updateSettings({ cover_size_by_codec: sizes });
+  };
+
+  /** Text of the extra cover sizes field, parsed into the setting on blur */
+  const [extraSizesInput, setExtraSizesInput] = useState(
+    settings.extra_cover_sizes.join(', '),
+  );
+
   // Check if a private key is already stored on mount and when the tab becomes visible.
   useEffect(() => {
     if (settings.animated_artwork_enabled) {
@@ -124,7 +162,12 @@ export function CoverArtTab() {
       setKeyInput(''); // Clear the input after successful save
       setKeyStatus('Private key saved securely to OS keychain');
     } catch (e) {
-      setKeyStatus(`Failed to save key: ${e instanceof Error ? e.message : String(e)}`);
+      // Validation and keychain failures arrive as a CredentialError object
+      const message =
+        e instanceof Error ? e.message
+          : typeof e === 'object' && e !== null && 'message' in e ? String(e.message)
+          : String(e);
+      setKeyStatus(`Failed to save key: ${message}`);
     }
   }, [keyInput]);
 
@@ -184,8 +227,87 @@ export function CoverArtTab() {
                   }
                 }}
               />
+
+              {/* Per-codec cover sizes -- optional; empty uses Cover Size.
+                  Also applies to companions saved in that codec. */}
+              <Input
+                label="ALAC Cover Size (pixels)"
+                description="Cover size for lossless downloads and companions. Leave empty to use the cover size above."
+                type="number"
+                min={100}
+                max={3000}
+                step={100}
+                placeholder="Same as cover size"
+                value={settings.cover_size_by_codec.alac?.toString() ?? ''}
+                onChange={(e) => updateCodecCoverSize('alac', e.target.value)}
+              />
+              <Input
+                label="AAC Cover Size (pixels)"
+                description="Cover size for AAC downloads and companions, e.g. a smaller cover for copies meant for a phone. Leave empty to use the cover size above."
+                type="number"
+                min={100}
+                max={3000}
+                step={100}
+                placeholder="Same as cover size"
+                value={settings.cover_size_by_codec.aac?.toString() ?? ''}
+                onChange={(e) => updateCodecCoverSize('aac', e.target.value)}
+              />
+
+              {/* Embedded cover size -- optional. Clearing the field stores
+                  null (embed the cover at Cover Size); otherwise the same
+                  100-3000 range applies. */}
+              <Input
+                label="Embedded Cover Size (pixels)"
+                description="Smaller size for the cover embedded in each track, keeping the saved cover file at full size. Leave empty to embed the full-size cover. Requires FFmpeg."
+                type="number"
+                min={100}
+                max={3000}
+                step={100}
+                placeholder="Same as cover size"
+                value={settings.embedded_cover_size?.toString() ?? ''}
+                onChange={(e) => {
+                  if (!e.target.value.trim()) {
+                    updateSettings({ embedded_cover_size: null });
+                    return;
+                  }
+                  const size = parseInt(e.target.value, 10);
+                  if (!isNaN(size) && size >= 100 && size <= 3000) {
+                    updateSettings({ embedded_cover_size: size });
+                  }
+                }}
+              />
+
+              {/* Extra cover sizes -- comma-separated list, parsed on blur so
+                  a half-typed list isn't rewritten while typing. Entries
+                  outside 100-3000 are dropped. */}
+              <Input
+                label="Extra Cover Sizes (pixels)"
+                description="Also save the cover at these sizes as cover-{size}.jpg, e.g. 600 for a thumbnail. Separate sizes with commas. Requires FFmpeg."
+                placeholder="None"
+                value={extraSizesInput}
+                onChange={(e) => setExtraSizesInput(e.target.value)}
+                onBlur={() => {
+                  const sizes = extraSizesInput
+                    .split(',')
+                    .map((part) => parseInt(part.trim(), 10))
+                    .filter((size) => !isNaN(size) && size >= 100 && size <= 3000);
+                  updateSettings({ extra_cover_sizes: sizes });
+                  setExtraSizesInput(sizes.join(', '));
+                }}
+              />
             </>
           )}
+
+          {/* Cover art failures -- GAMDL fails a track whose cover can't
+              be fetched; with this on, it's downloaded again without one */}
+          <Toggle
+            label="Save Tracks Without Cover Art"
+            description="If the cover art can't be downloaded, save the tracks without it instead of failing the download"
+            checked={settings.continue_on_cover_failure}
+            onChange={(checked) =>
+              updateSettings({ continue_on_cover_failure: checked })
+            }
+          />
         </div>
       </div>
 
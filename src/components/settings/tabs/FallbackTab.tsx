@@ -16,6 +16,10 @@
  *   - `settings.music_fallback_chain: SongCodec[]` -- audio fallback order
  *   - `settings.video_fallback_chain: VideoResolution[]` -- video fallback order
  *
+ * With `settings.per_track_best_codec` on ("Best Codec Per Track"), each
+ * track of an album starts at the first codec of the audio chain it
+ * offers, instead of the whole album falling back together.
+ *
  * Items at the top of the list are tried first. When the user clicks the
  * up/down arrow buttons, the item swaps position with its neighbour and
  * the new order is persisted to the store.
@@ -41,6 +45,7 @@
  * Reads and writes the Zustand `settingsStore` via:
  *   - `settings.music_fallback_chain` / `settings.video_fallback_chain`
  *   - `updateSettings({ music_fallback_chain: ... })` / `updateSettings({ video_fallback_chain: ... })`
+ *   - `settings.per_track_best_codec` / `updateSettings({ per_track_best_codec: ... })`
  *
  * @see {@link https://docs.dndkit.com/}            -- @dnd-kit documentation (future integration)
  * @see {@link ../SettingsPage.tsx}                  -- Parent container
@@ -61,8 +66,9 @@ import { useSettingsStore } from '@/stores/settingsStore';
 import { SONG_CODEC_LABELS, VIDEO_RESOLUTION_LABELS } from '@/types';
 import type { SongCodec, VideoResolution } from '@/types';
 
-// Shared Button component used for the audio/video chain toggle tabs.
-import { Button } from '@/components/common';
+// Shared Button component used for the audio/video chain toggle tabs, and
+// Toggle for the per-track codec switch.
+import { Button, Toggle } from '@/components/common';
 
 /**
  * FallbackChainList -- Generic reorderable list for a fallback chain.
@@ -224,6 +230,14 @@ export function FallbackTab() {
             labels={SONG_CODEC_LABELS}
             onChange={(chain) => updateSettings({ music_fallback_chain: chain })}
           />
+          <div className="mt-4">
+            <Toggle
+              label="Best Codec Per Track"
+              description="Check which codecs each track offers before downloading, and download every track in the first codec of this chain it has (e.g. ALAC for most of an album, AAC for tracks without a lossless master)"
+              checked={settings.per_track_best_codec}
+              onChange={(checked) => updateSettings({ per_track_best_codec: checked })}
+            />
+          </div>
         </div>
       )}
 
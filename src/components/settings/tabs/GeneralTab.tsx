@@ -20,14 +20,77 @@
  *     metadata returned by the Apple Music API. Maps to
  *     `settings.language` (ISO locale code, e.g., `"en-US"`).
  *
- *   - **Overwrite Existing Files** -- Whether to re-download and replace
- *     files that already exist in the output directory. Maps to
- *     `settings.overwrite`.
+ *   - **Existing Files** -- Skip, replace, or keep both when a file
+ *     already exists in the output directory ("keep both" saves the new
+ *     one as `Name (1).m4a` via a staging folder). Maps to
+ *     `settings.overwrite_policy`, with `settings.overwrite` kept in step.
+ *
+ *   - **Stage Downloads** -- Downloads finish in a staging folder in the
+ *     app data directory and move to the output directory only on
+ *     success. Maps to `settings.use_staging_dir`.
+ *
+ *   - **Split Artist Downloads by Album** -- Expands an artist URL into
+ *     one queue item per album (needs MusicKit credentials). Maps to
+ *     `settings.expand_artist_urls`.
+ *
+ *   - **Sync Playlists** -- Re-submitting a playlist URL downloads only
+ *     the tracks added since the last run (needs MusicKit credentials).
+ *     Maps to `settings.playlist_sync`.
+ *
+ *   - **Skip Unavailable Tracks** -- Asks the catalog which album and
+ *     playlist tracks are still available and leaves the others out
+ *     (needs MusicKit credentials). Maps to
+ *     `settings.skip_unavailable_tracks`.
+ *
+ *   - **Explicit / Clean Versions** -- Downloads the explicit or clean
+ *     release of each track where Apple Music has both, or whatever the
+ *     URL points at (needs MusicKit credentials). Maps to
+ *     `settings.content_preference`.
+ *
+ *   - **Check Tools Before Downloading** -- Runs the pre-flight checks
+ *     (cookies, Python, GAMDL, FFmpeg, output folder) before queueing and
+ *     refuses the download if one fails. Maps to `settings.preflight_checks`.
+ *
+ *   - **Let Priority Downloads Interrupt** -- A queued download with a
+ *     higher priority stops a running lower-priority one, which resumes
+ *     later with its remaining tracks. Maps to
+ *     `settings.preempt_lower_priority`.
  *
  *   - **Auto-Check for Updates** -- Whether the application checks for
  *     GAMDL and tool updates on startup. Maps to
  *     `settings.auto_check_updates`.
  *
+ *   - **Minimum Free Disk Space** -- Holds queued downloads while the
+ *     output drive has less free space than this (GiB). Empty = no check.
+ *     Maps to `settings.min_free_space_gb`.
+ *
+ *   - **Network / Rate Limit Retries** -- How many times a download is
+ *     re-queued after a network error or an HTTP 429, counted separately.
+ *     Maps to `settings.network_retry_budget` and
+ *     `settings.rate_limit_retry_budget`.
+ *
+ *   - **Stall Timeout** -- Seconds a download may print nothing before
+ *     GAMDL is taken to be hung, killed and retried like a network error.
+ *     Empty = never. Maps to `settings.download_stall_timeout_secs`.
+ *
+ *   - **Background Tasks at Once** -- How many animated artwork, companion
+ *     and post-processing tasks run concurrently after downloads finish;
+ *     the rest wait their turn. Maps to `settings.max_background_tasks`.
+ *
+ *   - **Pause Between Downloads** -- Seconds to wait after a download
+ *     finishes before the next one starts, to stay clear of rate limits.
+ *     Maps to `settings.inter_download_delay_secs`.
+ *
+ *   - **Remove Completed Downloads After** -- Seconds after which a
+ *     completed download leaves the queue; empty keeps it until cleared.
+ *     Maps to `settings.auto_clear_completed_after_secs`.
+ *
+ *   - **Download Schedule** -- Restricts downloads to a daily window
+ *     (e.g., 23:00-07:00, local time). Maps to `settings.schedule_enabled`,
+ *     `settings.schedule_start`, `settings.schedule_end` and
+ *     `settings.schedule_pause_at_close`. The backend's
+ *     `download_schedule.rs` holds queued items until the window opens.
+ *
  * ## Store Connection
  *
  * This component reads from and writes to the Zustand `settingsStore`.
@@ -52,7 +115,12 @@ import { useSettingsStore } from '@/stores/settingsStore';
 // - Toggle: renders a labelled on/off switch
 // - FilePickerButton: renders a button that opens the Tauri native file dialog
 // - Select: renders a labelled <select> dropdown
-import { Toggle, FilePickerButton, Select } from '@/components/common';
+// - Input: renders a labelled <input> (used as a time picker for the schedule)
+import { Toggle, FilePickerButton, Select, Input } from '@/components/common';
+
+// OverwritePolicy: the values of the Existing Files dropdown.
+// ContentPreference: the values of the Explicit / Clean Versions dropdown.
+import type { ContentPreference, OverwritePolicy } from '@/types';
 
 /**
  * Available language options for GAMDL's metadata language preference.
@@ -94,6 +162,20 @@ const LANGUAGE_OPTIONS = [
   { value: 'ru-RU', label: 'Russian' },
 ];
 
+/** Choices for `settings.overwrite_policy`. */
+const OVERWRITE_OPTIONS = [
+  { value: 'skip', label: 'Skip (keep existing files)' },
+  { value: 'replace', label: 'Replace' },
+  { value: 'version', label: 'Keep both (save as "Name (1)")' },
+];
+
+/** Choices for `settings.content_preference`. */
+const CONTENT_PREFERENCE_OPTIONS = [
+  { value: 'as_is', label: 'As linked' },
+  { value: 'explicit', label: 'Prefer explicit' },
+  { value: 'clean', label: 'Prefer clean' },
+];
+
 /**
  * GeneralTab -- Renders the General settings tab.
  *
@@ -169,12 +251,81 @@ export function GeneralTab() {
           onChange={(e) => updateSettings({ language: e.target.value })}
         />
 
-        {/* Overwrite existing files */}
+        {/* What to do with files that already exist */}
+        <Select
+          label="Existing Files"
+          description="What to do when a track is already in the output directory"
+          options={OVERWRITE_OPTIONS}
+          value={settings.overwrite_policy ?? (settings.overwrite ? 'replace' : 'skip')}
+          onChange={(e) => {
+            const policy = e.target.value as OverwritePolicy;
+            updateSettings({ overwrite_policy: policy, overwrite: policy === 'replace' });
+          }}
+        />
+
+        {/* Download into a staging folder first */}
+        <Toggle
+          label="Stage Downloads"
+          description="Download into a temporary folder and move albums to the output directory only once they finish, so library scanners never see half-downloaded albums (tracks already in the library are downloaded again)"
+          checked={settings.use_staging_dir}
+          onChange={(checked) => updateSettings({ use_staging_dir: checked })}
+        />
+
+        {/* Expand artist URLs into per-album downloads */}
+        <Toggle
+          label="Split Artist Downloads by Album"
+          description="Queue each album of an artist separately, with its own progress and retries (requires MusicKit credentials in Cover Art)"
+          checked={settings.expand_artist_urls}
+          onChange={(checked) =>
+            updateSettings({ expand_artist_urls: checked })
+          }
+        />
+
+        {/* Download only new playlist tracks */}
+        <Toggle
+          label="Sync Playlists"
+          description="When a playlist is downloaded again, only fetch tracks added since the last run (requires MusicKit credentials in Cover Art)"
+          checked={settings.playlist_sync}
+          onChange={(checked) => updateSettings({ playlist_sync: checked })}
+        />
+
+        {/* Leave out tracks the catalog no longer offers */}
+        <Toggle
+          label="Skip Unavailable Tracks"
+          description="Check the Apple Music catalog before downloading and leave out tracks that are no longer available instead of failing on each (requires MusicKit credentials in Cover Art)"
+          checked={settings.skip_unavailable_tracks}
+          onChange={(checked) =>
+            updateSettings({ skip_unavailable_tracks: checked })
+          }
+        />
+
+        {/* Explicit or clean release of each track */}
+        <Select
+          label="Explicit / Clean Versions"
+          description="When Apple Music has both an explicit and a clean release, download this version of each track instead of the one the URL points at (albums and songs; requires MusicKit credentials in Cover Art)"
+          options={CONTENT_PREFERENCE_OPTIONS}
+          value={settings.content_preference}
+          onChange={(e) =>
+            updateSettings({ content_preference: e.target.value as ContentPreference })
+          }
+        />
+
+        {/* Pre-flight toolchain check */}
+        <Toggle
+          label="Check Tools Before Downloading"
+          description="Verify cookies, Python, GAMDL, FFmpeg and the output folder before queueing, and stop with a fix-it hint if something is missing"
+          checked={settings.preflight_checks}
+          onChange={(checked) => updateSettings({ preflight_checks: checked })}
+        />
+
+        {/* Priority preemption */}
         <Toggle
-          label="Overwrite Existing Files"
-          description="Re-download and replace files that already exist in the output directory"
-          checked={settings.overwrite}
-          onChange={(checked) => updateSettings({ overwrite: checked })}
+          label="Let Priority Downloads Interrupt"
+          description="When all download slots are busy, a higher-priority download stops a lower-priority one, which resumes later with the tracks it still needs"
+          checked={settings.preempt_lower_priority}
+          onChange={(checked) =>
+            updateSettings({ preempt_lower_priority: checked })
+          }
         />
 
         {/* Auto-check for updates */}
@@ -186,6 +337,164 @@ export function GeneralTab() {
             updateSettings({ auto_check_updates: checked })
           }
         />
+
+        {/* Low disk space guard (empty input = disabled) */}
+        <Input
+          label="Minimum Free Disk Space (GB)"
+          description="Downloads wait while the output drive has less free space than this. Leave empty to disable."
+          type="number"
+          min={0}
+          step={1}
+          value={settings.min_free_space_gb?.toString() ?? ''}
+          onChange={(e) => {
+            const value = parseFloat(e.target.value);
+            updateSettings({
+              min_free_space_gb: Number.isNaN(value) || value <= 0 ? null : value,
+            });
+          }}
+        />
+
+        {/* Retry budgets, counted separately per failure kind */}
+        <Input
+          label="Network Error Retries"
+          description="How many times a download is retried after a dropped connection or timeout"
+          type="number"
+          min={0}
+          step={1}
+          value={settings.network_retry_budget.toString()}
+          onChange={(e) => {
+            const value = parseInt(e.target.value, 10);
+            updateSettings({
+              network_retry_budget: Number.isNaN(value) || value < 0 ? 0 : value,
+            });
+          }}
+        />
+        <Input
+          label="Rate Limit Retries"
+          description="How many times a download is retried, after a cooldown, when Apple Music rate-limits requests"
+          type="number"
+          min={0}
+          step={1}
+          value={settings.rate_limit_retry_budget.toString()}
+          onChange={(e) => {
+            const value = parseInt(e.target.value, 10);
+            updateSettings({
+              rate_limit_retry_budget: Number.isNaN(value) || value < 0 ? 0 : value,
+            });
+          }}
+        />
+
+        {/* Hung GAMDL detection (empty input = never time out) */}
+        <Input
+          label="Stall Timeout (seconds)"
+          description="Stop and retry a download that shows no output at all for this long, e.g. stuck on a segment. Slow downloads that are still progressing aren't affected. Leave empty to never time out."
+          type="number"
+          min={1}
+          step={1}
+          value={settings.download_stall_timeout_secs?.toString() ?? ''}
+          onChange={(e) => {
+            const value = parseInt(e.target.value, 10);
+            updateSettings({
+              download_stall_timeout_secs: Number.isNaN(value) || value < 1 ? null : value,
+            });
+          }}
+        />
+
+        {/* Limit for post-download artwork/companion tasks */}
+        <Input
+          label="Background Tasks at Once"
+          description="How many artwork, companion and conversion tasks run at the same time after downloads finish; the rest wait"
+          type="number"
+          min={1}
+          step={1}
+          value={settings.max_background_tasks.toString()}
+          onChange={(e) => {
+            const value = parseInt(e.target.value, 10);
+            updateSettings({
+              max_background_tasks: Number.isNaN(value) || value < 1 ? 1 : value,
+            });
+          }}
+        />
+
+        {/* Gap between queued downloads, against rate limiting */}
+        <Input
+          label="Pause Between Downloads (seconds)"
+          description="Wait this long after a download finishes before starting the next one; helps avoid Apple Music rate limits (0 = no pause)"
+          type="number"
+          min={0}
+          step={1}
+          value={settings.inter_download_delay_secs.toString()}
+          onChange={(e) => {
+            const value = parseInt(e.target.value, 10);
+            updateSettings({
+              inter_download_delay_secs: Number.isNaN(value) || value < 0 ? 0 : value,
+            });
+          }}
+        />
+
+        {/* Self-cleaning queue (empty input = keep completed items) */}
+        <Input
+          label="Remove Completed Downloads After (seconds)"
+          description="Completed downloads leave the queue after this long, unless you open them first. Failed and cancelled downloads always stay. Leave empty to keep them."
+          type="number"
+          min={0}
+          step={1}
+          value={settings.auto_clear_completed_after_secs?.toString() ?? ''}
+          onChange={(e) => {
+            const value = parseInt(e.target.value, 10);
+            updateSettings({
+              auto_clear_completed_after_secs: Number.isNaN(value) || value < 0 ? null : value,
+            });
+          }}
+        />
+      </div>
+
+      {/* Section: Download Schedule */}
+      <div className="space-y-4">
+        <h3 className="text-sm font-semibold text-content-primary mb-4">
+          Download Schedule
+        </h3>
+
+        {/* Enable quiet-hours window */}
+        <Toggle
+          label="Only Download During Set Hours"
+          description="Queued downloads wait until the window opens (local time; a window ending before it starts runs overnight)"
+          checked={settings.schedule_enabled}
+          onChange={(checked) => updateSettings({ schedule_enabled: checked })}
+        />
+
+        {/* Window times and close behaviour, only relevant when enabled */}
+        {settings.schedule_enabled && (
+          <>
+            <div className="grid grid-cols-2 gap-4">
+              <Input
+                label="Start"
+                type="time"
+                value={settings.schedule_start}
+                onChange={(e) =>
+                  updateSettings({ schedule_start: e.target.value })
+                }
+              />
+              <Input
+                label="End"
+                type="time"
+                value={settings.schedule_end}
+                onChange={(e) =>
+                  updateSettings({ schedule_end: e.target.value })
+                }
+              />
+            </div>
+
+            <Toggle
+              label="Pause Downloads When the Window Closes"
+              description="Stop running downloads at the end time and resume them when the window reopens, instead of letting them finish"
+              checked={settings.schedule_pause_at_close}
+              onChange={(checked) =>
+                updateSettings({ schedule_pause_at_close: checked })
+              }
+            />
+          </>
+        )}
       </div>
     </div>
   );
@@ -25,13 +25,22 @@
  *     Note: This is mutually exclusive with "Disable Synced Lyrics" in
  *     practice, though the UI does not enforce this constraint.
  *
+ *   - **Keep Music Video Lyrics** -- When enabled, the lyrics of an album's
+ *     music videos (which GAMDL skips) are saved next to the matching audio
+ *     tracks. Maps to `settings.keep_mv_lyrics`.
+ *
+ *   - **Convert Lyrics To** -- Converts the TTML lyrics sidecars of each
+ *     download to SRT, WebVTT or LRC, keeping the TTML. Maps to
+ *     `settings.convert_lyrics_to`; needs TTML as the synced lyrics format
+ *     (or music video lyrics) to have anything to convert.
+ *
  * ## Store Connection
  *
  * Reads and writes the Zustand `settingsStore`.
  *
  * @see {@link ../SettingsPage.tsx}        -- Parent container
  * @see {@link @/stores/settingsStore.ts}  -- Zustand store
- * @see {@link @/types/index.ts}           -- LyricsFormat type definition
+ * @see {@link @/types/index.ts}           -- LyricsFormat, LyricsConversion types
  */
 
 // Zustand store for reading/writing lyrics settings.
@@ -40,8 +49,8 @@ import { useSettingsStore } from '@/stores/settingsStore';
 // Shared form components: Select for the format dropdown, Toggle for boolean switches.
 import { Select, Toggle } from '@/components/common';
 
-// TypeScript union type for the lyrics format values.
-import type { LyricsFormat } from '@/types';
+// TypeScript union types for the lyrics format and conversion values.
+import type { LyricsConversion, LyricsFormat } from '@/types';
 
 /**
  * Dropdown options for the synced lyrics format selector.
@@ -53,11 +62,22 @@ const LYRICS_FORMAT_OPTIONS = [
   { value: 'ttml', label: 'TTML (Timed Text Markup Language)' },
 ];
 
+/**
+ * Dropdown options for converting downloaded TTML lyrics. WebVTT is only
+ * offered here: GAMDL can't download it directly.
+ */
+const CONVERT_LYRICS_OPTIONS = [
+  { value: '', label: 'Off (keep TTML only)' },
+  { value: 'vtt', label: 'WebVTT' },
+  { value: 'srt', label: 'SRT (SubRip subtitle format)' },
+  { value: 'lrc', label: 'LRC (standard lyrics format)' },
+];
+
 /**
  * LyricsTab -- Renders the Lyrics settings tab.
  *
- * Contains a single visual section ("Synced Lyrics") with three controls:
- * a format dropdown and two toggles. All controls read from and write to
+ * Contains a single visual section ("Synced Lyrics") with six controls:
+ * two format dropdowns and four toggles. All controls read from and write to
  * the shared Zustand settings store.
  */
 export function LyricsTab() {
@@ -121,6 +141,33 @@ export function LyricsTab() {
               updateSettings({ synced_lyrics_only: checked })
             }
           />
+
+          {/* Lyrics of skipped album music videos */}
+          <Toggle
+            label="Keep Music Video Lyrics"
+            description="When an album's music videos are skipped, save their lyrics next to the matching audio tracks"
+            checked={settings.keep_mv_lyrics}
+            onChange={(checked) =>
+              updateSettings({ keep_mv_lyrics: checked })
+            }
+          />
+
+          {/* Convert TTML lyrics after download */}
+          <Select
+            label="Convert Lyrics To"
+            description={
+              settings.synced_lyrics_format === 'ttml' || settings.keep_mv_lyrics
+                ? 'Convert downloaded TTML lyrics to another format, keeping the TTML file'
+                : 'Converts downloaded TTML lyrics; set the synced lyrics format to TTML to use it'
+            }
+            options={CONVERT_LYRICS_OPTIONS}
+            value={settings.convert_lyrics_to ?? ''}
+            onChange={(e) =>
+              updateSettings({
+                convert_lyrics_to: (e.target.value || null) as LyricsConversion | null,
+              })
+            }
+          />
         </div>
       </div>
     </div>
@@ -22,6 +22,27 @@
  *     download companion versions in other formats. Maps to
  *     `settings.companion_mode`.
  *
+ *   - **Lossless / Lossy Companion Subfolder** -- Saves that companion
+ *     tier in a folder inside the album folder (e.g., `Lossless/`) instead
+ *     of giving it a filename suffix. Maps to
+ *     `settings.companion_subfolders` (keyed `"alac"` / `"aac"`).
+ *
+ *   - **Preview, Then Upgrade** -- Downloads ALAC albums as AAC first, then
+ *     fetches the lossless version in the background once they finish.
+ *     Maps to `settings.preview_then_upgrade`.
+ *
+ *   - **Convert Lossless to FLAC** -- Transcodes finished ALAC downloads
+ *     from M4A to FLAC with FFmpeg, optionally keeping the M4A. Maps to
+ *     `settings.convert_lossless_to_flac` and `settings.keep_m4a_after_flac`.
+ *
+ *   - **Transcode Companion** -- Saves an MP3 or Opus copy of each AAC/ALAC
+ *     track at the chosen bitrate, keeping tags and cover art, for devices
+ *     that can't play M4A. Maps to `settings.transcode_companion`.
+ *
+ *   - **Verify Audio Integrity** -- Decodes each downloaded file with FFmpeg
+ *     and re-downloads files that turn out truncated or corrupt. Maps to
+ *     `settings.verify_audio`.
+ *
  *   - **Default Video Resolution** -- The preferred resolution for music
  *     video downloads (e.g., 2160p for 4K). Maps to
  *     `settings.default_video_resolution`.
@@ -34,6 +55,15 @@
  *     video files (M4V, MP4, or MKV). Maps to
  *     `settings.default_video_remux_format`.
  *
+ *   - **Uploaded Video Quality** -- "Best" for uploaded (post) videos, or
+ *     GAMDL's default. Maps to `settings.uploaded_video_quality`
+ *     and GAMDL's `--uploaded-video-quality` flag.
+ *
+ *   - **Download Album Music Videos** -- Downloads the music videos on
+ *     albums and playlists along with the songs, instead of skipping them.
+ *     Maps to `settings.download_album_music_videos` and GAMDL's
+ *     `--disable-music-video-skip` flag.
+ *
  * ## Store Connection
  *
  * Reads and writes the Zustand `settingsStore`, same pattern as all
@@ -54,7 +84,7 @@ import { Select, Toggle, Input } from '@/components/common';
 // Label maps and type definitions for audio codecs, video resolutions, and companion modes.
 // These Record<T, string> maps are used to populate the <Select> dropdown options.
 import { SONG_CODEC_LABELS, VIDEO_RESOLUTION_LABELS, COMPANION_MODE_LABELS } from '@/types';
-import type { SongCodec, VideoResolution, CompanionMode } from '@/types';
+import type { SongCodec, VideoResolution, CompanionMode, TranscodeFormat } from '@/types';
 
 /**
  * QualityTab -- Renders the Quality settings tab.
@@ -90,6 +120,20 @@ export function QualityTab() {
     ([value, label]) => ({ value, label }),
   );
 
+  /**
+   * Sets or clears (when blank) the subfolder of one companion tier,
+   * keyed by the tier's codec.
+   */
+  const updateCompanionSubfolder = (codec: 'alac' | 'aac', value: string) => {
+    const subfolders = { ...settings.companion_subfolders };
+    if (value.trim()) {
+      subfolders[codec] = value;
+    } else {
+      delete subfolders[codec];
+    }
+    updateSettings({ companion_subfolders: subfolders });
+  };
+
   /**
    * Same transformation for video resolution labels.
    */
@@ -108,6 +152,26 @@ export function QualityTab() {
     { value: 'mkv', label: 'MKV (Matroska)' },
   ];
 
+  /**
+   * Transcode companion formats; "Off" clears the setting.
+   */
+  const transcodeOptions = [
+    { value: '', label: 'Off' },
+    { value: 'mp3', label: 'MP3 [MP3]' },
+    { value: 'opus', label: 'Opus [Opus]' },
+  ];
+
+  /** Bitrate a newly chosen format starts at */
+  const defaultTranscodeBitrate: Record<TranscodeFormat, number> = {
+    mp3: 320,
+    opus: 160,
+  };
+
+  const uploadedQualityOptions = [
+    { value: '', label: 'GAMDL default' },
+    { value: 'best', label: 'Best available' },
+  ];
+
   return (
     <div className="space-y-6 max-w-xl">
       {/* Section: Audio */}
@@ -145,6 +209,98 @@ export function QualityTab() {
             updateSettings({ companion_mode: e.target.value as CompanionMode })
           }
         />
+
+        {/* Per-tier subfolders instead of filename suffixes */}
+        {settings.companion_mode !== 'disabled' && (
+          <>
+            <Input
+              label="Lossless Companion Subfolder"
+              description="Save the ALAC companion in this folder inside the album folder (e.g., Lossless) instead of adding [Lossless] to its filenames. Leave empty to use the suffix."
+              value={settings.companion_subfolders.alac ?? ''}
+              onChange={(e) => updateCompanionSubfolder('alac', e.target.value)}
+            />
+            <Input
+              label="Lossy Companion Subfolder"
+              description="Save the AAC companion in this folder inside the album folder (e.g., AAC). Leave empty to keep it next to the primary files."
+              value={settings.companion_subfolders.aac ?? ''}
+              onChange={(e) => updateCompanionSubfolder('aac', e.target.value)}
+            />
+          </>
+        )}
+
+        {/* Fast preview of lossless downloads */}
+        <Toggle
+          label="Preview, Then Upgrade"
+          description="Download ALAC albums as AAC first so they can be played right away, then download the lossless version in the background"
+          checked={settings.preview_then_upgrade}
+          onChange={(checked) => updateSettings({ preview_then_upgrade: checked })}
+        />
+
+        {/* Lossless output format */}
+        <Toggle
+          label="Convert Lossless to FLAC"
+          description="After an ALAC download finishes, convert each track from M4A to FLAC, keeping tags and cover art. Dolby Atmos and AAC files are left alone."
+          checked={settings.convert_lossless_to_flac}
+          onChange={(checked) => updateSettings({ convert_lossless_to_flac: checked })}
+        />
+
+        {/* Only meaningful once conversion is on */}
+        {settings.convert_lossless_to_flac && (
+          <Toggle
+            label="Keep M4A Files"
+            description="Keep the original ALAC M4A next to each FLAC file instead of deleting it"
+            checked={settings.keep_m4a_after_flac}
+            onChange={(checked) => updateSettings({ keep_m4a_after_flac: checked })}
+          />
+        )}
+
+        {/* Lossy copies for devices without M4A support */}
+        <Select
+          label="Transcode Companion"
+          description="After an AAC or ALAC download finishes, also save an MP3 or Opus copy of each track next to it, keeping tags and cover art. For car stereos and players that can't play M4A."
+          options={transcodeOptions}
+          value={settings.transcode_companion?.format ?? ''}
+          onChange={(e) => {
+            const format = e.target.value as TranscodeFormat | '';
+            updateSettings({
+              transcode_companion: format
+                ? { format, bitrate_kbps: defaultTranscodeBitrate[format] }
+                : null,
+            });
+          }}
+        />
+
+        {/* Only meaningful once a format is chosen */}
+        {settings.transcode_companion && (
+          <Input
+            label="Transcode Bitrate (kbps)"
+            description={
+              settings.transcode_companion.format === 'mp3'
+                ? 'MP3 bitrate, 32 to 320 kbps'
+                : 'Opus bitrate, 6 to 510 kbps'
+            }
+            type="number"
+            min={settings.transcode_companion.format === 'mp3' ? 32 : 6}
+            max={settings.transcode_companion.format === 'mp3' ? 320 : 510}
+            step={1}
+            value={settings.transcode_companion.bitrate_kbps.toString()}
+            onChange={(e) => {
+              const value = parseInt(e.target.value, 10);
+              if (Number.isNaN(value) || !settings.transcode_companion) return;
+              updateSettings({
+                transcode_companion: { ...settings.transcode_companion, bitrate_kbps: value },
+              });
+            }}
+          />
+        )}
+
+        {/* Output verification */}
+        <Toggle
+          label="Verify Audio Integrity"
+          description="Decode each downloaded file with FFmpeg before marking the download complete. Truncated or corrupt files are deleted and downloaded again once."
+          checked={settings.verify_audio}
+          onChange={(checked) => updateSettings({ verify_audio: checked })}
+        />
       </div>
 
       {/* Section: Video */}
@@ -186,6 +342,25 @@ export function QualityTab() {
             updateSettings({ default_video_remux_format: e.target.value })
           }
         />
+
+        {/* Uploaded (post) video quality */}
+        <Select
+          label="Uploaded Video Quality"
+          description="Quality for uploaded (post) videos"
+          options={uploadedQualityOptions}
+          value={settings.uploaded_video_quality ?? ''}
+          onChange={(e) =>
+            updateSettings({ uploaded_video_quality: e.target.value || null })
+          }
+        />
+
+        {/* Music videos on albums and playlists */}
+        <Toggle
+          label="Download Album Music Videos"
+          description="Also download the music videos on albums and playlists, which are skipped by default"
+          checked={settings.download_album_music_videos}
+          onChange={(checked) => updateSettings({ download_album_music_videos: checked })}
+        />
       </div>
     </div>
   );
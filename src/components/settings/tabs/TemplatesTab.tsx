@@ -33,10 +33,19 @@
  * | Album Folder          | album_folder_template           | --album-folder-template         |
  * | Compilation Folder    | compilation_folder_template     | --compilation-folder-template   |
  * | No Album Folder       | no_album_folder_template        | --no-album-folder-template      |
+ * | Singles Folder        | singles_folder_template         | --no-album-folder-template (singles only) |
  * | Single Disc File      | single_disc_file_template       | --single-disc-file-template     |
  * | Multi Disc File       | multi_disc_file_template        | --multi-disc-file-template      |
  * | No Album File         | no_album_file_template          | --no-album-file-template        |
  * | Playlist File         | playlist_file_template          | --playlist-file-template        |
+ * | Save Playlist File    | save_playlist_m3u               | --save-playlist                 |
+ *
+ * ## Validation
+ *
+ * Each field is checked with `validateTemplate()` as the user types, and
+ * problems (an unknown placeholder such as `{albumartist}`, or a `:` on
+ * Windows) are shown under the field. The backend's `save_settings`
+ * rejects the same templates.
  *
  * ## Store Connection
  *
@@ -46,11 +55,43 @@
  * @see {@link @/stores/settingsStore.ts}  -- Zustand store
  */
 
+// React hooks for the per-field validation state.
+import { useEffect, useState } from 'react';
+
 // Zustand store for reading/writing template settings.
 import { useSettingsStore } from '@/stores/settingsStore';
 
-// Shared Input component for text-based template fields.
-import { Input } from '@/components/common';
+// IPC wrapper for the backend template check.
+import { validateTemplate } from '@/lib/tauri-commands';
+
+// Shared Input component for text-based template fields, and Toggle for
+// the playlist file switch.
+import { Input, Toggle } from '@/components/common';
+
+/**
+ * Returns the backend's description of what is wrong with a template, or
+ * undefined when it is valid (or the check itself failed).
+ */
+function useTemplateError(template: string): string | undefined {
+  const [error, setError] = useState<string | undefined>();
+
+  useEffect(() => {
+    // Ignore results that arrive after the template changed again
+    let current = true;
+    validateTemplate(template)
+      .then((check) => {
+        if (current) setError(check.problem ?? undefined);
+      })
+      .catch(() => {
+        if (current) setError(undefined);
+      });
+    return () => {
+      current = false;
+    };
+  }, [template]);
+
+  return error;
+}
 
 /**
  * TemplatesTab -- Renders the Templates settings tab.
@@ -71,6 +112,16 @@ export function TemplatesTab() {
   /** Partial-update function for persisting template changes */
   const updateSettings = useSettingsStore((s) => s.updateSettings);
 
+  /** Per-field validation messages from the backend */
+  const albumFolderError = useTemplateError(settings.album_folder_template);
+  const compilationFolderError = useTemplateError(settings.compilation_folder_template);
+  const noAlbumFolderError = useTemplateError(settings.no_album_folder_template);
+  const singlesFolderError = useTemplateError(settings.singles_folder_template ?? '');
+  const singleDiscFileError = useTemplateError(settings.single_disc_file_template);
+  const multiDiscFileError = useTemplateError(settings.multi_disc_file_template);
+  const noAlbumFileError = useTemplateError(settings.no_album_file_template);
+  const playlistFileError = useTemplateError(settings.playlist_file_template);
+
   return (
     <div className="space-y-6 max-w-xl">
       {/* Template variable reference */}
@@ -101,6 +152,7 @@ export function TemplatesTab() {
           label="Album Folder"
           description="Folder structure for regular album downloads"
           value={settings.album_folder_template}
+          error={albumFolderError}
           onChange={(e) =>
             updateSettings({ album_folder_template: e.target.value })
           }
@@ -110,6 +162,7 @@ export function TemplatesTab() {
           label="Compilation Folder"
           description="Folder structure for compilation/various artist albums"
           value={settings.compilation_folder_template}
+          error={compilationFolderError}
           onChange={(e) =>
             updateSettings({ compilation_folder_template: e.target.value })
           }
@@ -119,10 +172,21 @@ export function TemplatesTab() {
           label="No Album Folder"
           description="Folder structure when album information is not available"
           value={settings.no_album_folder_template}
+          error={noAlbumFolderError}
           onChange={(e) =>
             updateSettings({ no_album_folder_template: e.target.value })
           }
         />
+
+        <Input
+          label="Singles Folder"
+          description="Folder structure for single-track downloads (e.g., {artist}/Singles). Leave empty to use the No Album Folder template."
+          value={settings.singles_folder_template ?? ''}
+          error={singlesFolderError}
+          onChange={(e) =>
+            updateSettings({ singles_folder_template: e.target.value || null })
+          }
+        />
       </div>
 
       {/* Section: File Templates */}
@@ -135,6 +199,7 @@ export function TemplatesTab() {
           label="Single Disc File"
           description="Filename template for tracks from single-disc albums"
           value={settings.single_disc_file_template}
+          error={singleDiscFileError}
           onChange={(e) =>
             updateSettings({ single_disc_file_template: e.target.value })
           }
@@ -144,6 +209,7 @@ export function TemplatesTab() {
           label="Multi Disc File"
           description="Filename template for tracks from multi-disc albums"
           value={settings.multi_disc_file_template}
+          error={multiDiscFileError}
           onChange={(e) =>
             updateSettings({ multi_disc_file_template: e.target.value })
           }
@@ -153,6 +219,7 @@ export function TemplatesTab() {
           label="No Album File"
           description="Filename template when album information is not available"
           value={settings.no_album_file_template}
+          error={noAlbumFileError}
           onChange={(e) =>
             updateSettings({ no_album_file_template: e.target.value })
           }
@@ -162,10 +229,18 @@ export function TemplatesTab() {
           label="Playlist File"
           description="Filename template for playlist downloads"
           value={settings.playlist_file_template}
+          error={playlistFileError}
           onChange={(e) =>
             updateSettings({ playlist_file_template: e.target.value })
           }
         />
+
+        <Toggle
+          label="Save Playlist File"
+          description="Have GAMDL write an M3U8 playlist file, named by the Playlist File template, for each playlist it downloads"
+          checked={settings.save_playlist_m3u}
+          onChange={(checked) => updateSettings({ save_playlist_m3u: checked })}
+        />
       </div>
     </div>
   );
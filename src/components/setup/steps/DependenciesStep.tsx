@@ -42,7 +42,8 @@
  * ## Store Connections
  *
  * - **dependencyStore**: `tools` array, `checkAll`, `installTool`,
- *   `isChecking`, `isInstalling`, `installingName`, `error`.
+ *   `isChecking`, `isInstalling`, `installingName`, `installProgress`
+ *   (shown as a download/extract progress bar), `error`.
  * - **setupStore**: `completeStep('dependencies')`.
  *
  * @see {@link ../SetupWizard.tsx}             -- Parent wizard container
@@ -66,7 +67,7 @@ import { useDependencyStore } from '@/stores/dependencyStore';
 import { useSetupStore } from '@/stores/setupStore';
 
 // Shared UI components.
-import { Button, LoadingSpinner } from '@/components/common';
+import { Button, LoadingSpinner, ProgressBar } from '@/components/common';
 
 /**
  * DependenciesStep -- Renders the external tools installation step.
@@ -85,6 +86,8 @@ export function DependenciesStep() {
   const isInstalling = useDependencyStore((s) => s.isInstalling);
   /** Name of the tool currently being installed (for button label) */
   const installingName = useDependencyStore((s) => s.installingName);
+  /** Download/extract progress of the running install */
+  const installProgress = useDependencyStore((s) => s.installProgress);
   /** Triggers status checks for all tools at once */
   const checkAll = useDependencyStore((s) => s.checkAll);
   /** Triggers installation of a specific tool by name */
@@ -167,6 +170,14 @@ export function DependenciesStep() {
             </Button>
           )}
 
+          {/* Download/extract progress of the tool being installed */}
+          {isInstalling && installProgress && (
+            <ProgressBar
+              value={installProgress.percent}
+              label={`${installProgress.stage === 'download' ? 'Downloading' : 'Extracting'} ${installingName}`}
+            />
+          )}
+
           {/* Tool list */}
           <div className="space-y-2">
             {tools.map((tool) => (
@@ -34,7 +34,8 @@
  * ## Store Connections
  *
  * - **dependencyStore**: Provides `python` status, `checkPython`,
- *   `installPython`, `isChecking`, `isInstalling`, and `error`.
+ *   `installPython`, `isChecking`, `isInstalling`, `installProgress`
+ *   (shown as a download/extract progress bar), and `error`.
  * - **setupStore**: Provides `completeStep` and `setStepError`.
  *
  * @see {@link ../SetupWizard.tsx}             -- Parent wizard container
@@ -55,8 +56,8 @@ import { CheckCircle, Download } from 'lucide-react';
 import { useDependencyStore } from '@/stores/dependencyStore';
 import { useSetupStore } from '@/stores/setupStore';
 
-// Shared UI components for the install button and loading indicator.
-import { Button, LoadingSpinner } from '@/components/common';
+// Shared UI components for the install button, loading and progress indicators.
+import { Button, LoadingSpinner, ProgressBar } from '@/components/common';
 
 /**
  * PythonStep -- Renders the Python installation step.
@@ -74,6 +75,8 @@ export function PythonStep() {
   const isChecking = useDependencyStore((s) => s.isChecking);
   /** True while the Python download/install is in progress */
   const isInstalling = useDependencyStore((s) => s.isInstalling);
+  /** Download/extract progress of the install (null before the first event) */
+  const installProgress = useDependencyStore((s) => s.installProgress);
   /** Triggers the backend check for Python */
   const checkPython = useDependencyStore((s) => s.checkPython);
   /** Triggers the Python download and installation */
@@ -176,6 +179,14 @@ export function PythonStep() {
             >
               {isInstalling ? 'Installing Python...' : 'Install Python'}
             </Button>
+
+            {/* Download/extract progress; indeterminate when the size is unknown */}
+            {isInstalling && installProgress && (
+              <ProgressBar
+                value={installProgress.percent}
+                label={installProgress.stage === 'download' ? 'Downloading' : 'Extracting'}
+              />
+            )}
           </div>
         )}
       </div>
@@ -63,14 +63,27 @@ import { invoke } from '@tauri-apps/api/core';
 import type {
   AppSettings,
   ArtworkResult,
+  BatchReport,
   ComponentUpdate,
   CookieImportResult,
+  CookieLiveTest,
   CookieValidation,
+  CsvImportReport,
+  TemplateCheck,
   DependencyStatus,
   DetectedBrowser,
   DownloadRequest,
+  EnvironmentReport,
+  GamdlOptions,
   PlatformInfo,
+  PreflightReport,
+  QueueItemStatus,
   QueueStatus,
+  SettingsImportReport,
+  SizeEstimate,
+  SongCodec,
+  SpeedSample,
+  TestDownloadResult,
   UpdateCheckResult,
 } from '@/types';
 
@@ -108,6 +121,23 @@ export function getAppDataDir(): Promise<string> {
   return invoke<string>('get_app_data_dir');
 }
 
+/**
+ * Returns the environment report for bug reports: resolved paths and
+ * detected versions of Python, GAMDL, FFmpeg, mp4decrypt, N_m3u8DL-RE and
+ * MP4Box, plus the app data directory and its free disk space.
+ *
+ * Rust handler: `get_environment_report()` in `src-tauri/src/commands/system.rs`
+ * Returns: `EnvironmentReport { app_version, os, arch, app_data_dir, free_space_bytes, cookies_configured, tools }`
+ *
+ * Runs every tool's version command (each with a timeout), so it may take
+ * a few seconds.
+ *
+ * @returns Promise resolving to the environment report
+ */
+export function getEnvironmentReport(): Promise<EnvironmentReport> {
+  return invoke<EnvironmentReport>('get_environment_report');
+}
+
 // ============================================================
 // Dependency Management Commands
 // ============================================================
@@ -295,6 +325,65 @@ export function getDefaultOutputPath(): Promise<string> {
   return invoke<string>('get_default_output_path');
 }
 
+/**
+ * Checks a folder/file naming template.
+ *
+ * Rust handler: `validate_template()` in `src-tauri/src/commands/settings.rs`
+ * Argument: `template` - the template text, e.g. "{album_artist}/{album}"
+ * Returns: `TemplateCheck` with unknown placeholders, characters the file
+ * system doesn't allow, and the normalized template
+ *
+ * `saveSettings()` rejects templates this check flags.
+ *
+ * Called by: TemplatesTab (inline validation of each template field)
+ *
+ * @param template - Template text to check
+ * @returns Promise resolving to the check result
+ */
+export function validateTemplate(template: string): Promise<TemplateCheck> {
+  return invoke<TemplateCheck>('validate_template', { template });
+}
+
+/**
+ * Writes the current settings to a JSON file for sharing or backup.
+ *
+ * Rust handler: `export_settings()` in `src-tauri/src/commands/settings.rs`
+ * Argument: `path` - the file to write
+ *
+ * The cookies file path, MusicKit IDs, custom tool paths, and proxy and
+ * wrapper account URLs are left out of the file.
+ *
+ * @param path - Path of the JSON file to write
+ * @returns Promise resolving once the file is written
+ */
+export function exportSettings(path: string): Promise<void> {
+  return invoke<void>('export_settings', { path });
+}
+
+/**
+ * Merges the settings of an exported file over the current settings and
+ * saves them.
+ *
+ * Rust handler: `import_settings()` in `src-tauri/src/commands/settings.rs`
+ * Arguments: `path` - a file written by `exportSettings()`;
+ * `includePaths` - whether the file's output path, cookies path and tool
+ * paths are applied too (by default they keep their current values)
+ * Returns: `SettingsImportReport { changed, kept_paths }`
+ *
+ * The merged settings go through the same checks as `saveSettings()`;
+ * nothing is saved if one fails. Reload the settings store afterwards.
+ *
+ * @param path - Path of the settings file to import
+ * @param includePaths - Whether to apply the file's machine-specific paths
+ * @returns Promise resolving to the changed fields and the paths left alone
+ */
+export function importSettings(
+  path: string,
+  includePaths = false,
+): Promise<SettingsImportReport> {
+  return invoke<SettingsImportReport>('import_settings', { path, includePaths });
+}
+
 // ============================================================
 // Download Commands
 // ============================================================
@@ -358,6 +447,83 @@ export function retryDownload(downloadId: string): Promise<void> {
   return invoke<void>('retry_download', { downloadId });
 }
 
+/**
+ * Resumes every cancelled download, in queue order.
+ *
+ * Rust handler: `resume_all_cancelled()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: number of downloads re-queued
+ *
+ * Failed downloads are not included; use `retryDownload()` for those.
+ * Emits `download-queued` for each resumed download.
+ *
+ * @returns Promise resolving to the count of resumed downloads
+ */
+export function resumeAllCancelled(): Promise<number> {
+  return invoke<number>('resume_all_cancelled');
+}
+
+/**
+ * Re-downloads only the tracks of an album download that didn't finish.
+ *
+ * Rust handler: `retry_failed_tracks()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * The missing tracks are queued as a new job that saves into the album's
+ * folder. Without per-track results the download is retried in full.
+ * Emits `download-queued` with the ID of the job that will run.
+ *
+ * @param downloadId - UUID of the finished, failed or cancelled download
+ * @returns Promise resolving to the ID of the job that will run (a new
+ *   one, or `downloadId` when retried in place)
+ */
+export function retryFailedTracks(downloadId: string): Promise<string> {
+  return invoke<string>('retry_failed_tracks', { downloadId });
+}
+
+/**
+ * Switches a queued, failed, or running download to a different codec.
+ *
+ * Rust handler: `change_download_codec()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Queued and failed items are re-queued with the new codec; a running
+ * download is stopped and restarted with it. Rejects for completed or
+ * cancelled downloads.
+ *
+ * @param downloadId - UUID of the download to change
+ * @param codec - The codec to switch to
+ * @returns Promise resolving to true if the codec changed, false if the
+ *   download already used it
+ */
+export function changeDownloadCodec(downloadId: string, codec: SongCodec): Promise<boolean> {
+  return invoke<boolean>('change_download_codec', { downloadId, codec });
+}
+
+/**
+ * Sets or clears a queue-wide codec for the rest of the session.
+ *
+ * Rust handler: `set_session_codec_override()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Every queued and newly added download starts with this codec instead of
+ * its own, without changing the default codec in settings. Clearing it
+ * restores each item's codec; running downloads keep theirs.
+ *
+ * @param codec - The codec to use, or null to clear the override
+ * @returns Promise resolving once the override is set
+ */
+export function setSessionCodecOverride(codec: SongCodec | null): Promise<void> {
+  return invoke<void>('set_session_codec_override', { codec });
+}
+
+/**
+ * Returns the session codec override, if one is set.
+ *
+ * Rust handler: `get_session_codec_override()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * @returns Promise resolving to the override codec, or null
+ */
+export function getSessionCodecOverride(): Promise<SongCodec | null> {
+  return invoke<SongCodec | null>('get_session_codec_override');
+}
+
 /**
  * Clears all completed, failed, and cancelled items from the queue.
  *
@@ -375,6 +541,52 @@ export function clearQueue(): Promise<number> {
   return invoke<number>('clear_queue');
 }
 
+/**
+ * Clears only the completed items from the queue.
+ *
+ * Rust handler: `clear_completed()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: number of items removed
+ *
+ * Failed and cancelled items are kept so they can still be retried.
+ *
+ * Called by: DownloadQueue "Clear Completed" button
+ *
+ * @returns Promise resolving to the count of cleared items
+ */
+export function clearCompleted(): Promise<number> {
+  return invoke<number>('clear_completed');
+}
+
+/**
+ * Clears only the failed items from the queue.
+ *
+ * Rust handler: `clear_failed()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: number of items removed
+ *
+ * Completed and cancelled items are kept.
+ *
+ * Called by: DownloadQueue "Clear Failed" button
+ *
+ * @returns Promise resolving to the count of cleared items
+ */
+export function clearFailed(): Promise<number> {
+  return invoke<number>('clear_failed');
+}
+
+/**
+ * Frees download slots held by downloads that ended without releasing
+ * them, so a stuck queue starts its waiting items again. A background
+ * watchdog does the same once the queue has been stuck for two minutes.
+ *
+ * Rust handler: `reconcile_queue()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: whether anything needed fixing
+ *
+ * @returns Promise resolving to true if the queue was corrected
+ */
+export function reconcileQueue(): Promise<boolean> {
+  return invoke<boolean>('reconcile_queue');
+}
+
 /**
  * Returns the current status of the entire download queue.
  *
@@ -392,6 +604,269 @@ export function getQueueStatus(): Promise<QueueStatus> {
   return invoke<QueueStatus>('get_queue_status');
 }
 
+/**
+ * Returns the queue items whose label, URL or current track matches a query.
+ *
+ * Rust handler: `get_queue_status_filtered()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `QueueItemStatus[]` in queue order (all items for an empty query)
+ *
+ * Matching is a case-insensitive substring search.
+ *
+ * @param query - The search text
+ * @returns Promise resolving to the matching items
+ */
+export function getQueueStatusFiltered(query: string): Promise<QueueItemStatus[]> {
+  return invoke<QueueItemStatus[]>('get_queue_status_filtered', { query });
+}
+
+/**
+ * Returns what happened to each URL of a set of downloads, with a summary
+ * such as "47 succeeded, 3 failed (auth), 1 skipped (unavailable)".
+ *
+ * Rust handler: `get_batch_report()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `BatchReport` (one row per URL and skipped track, counts, summary)
+ *
+ * IDs no longer in the queue are left out of the report.
+ *
+ * @param downloadIds - The downloads to report on
+ * @returns Promise resolving to the report
+ */
+export function getBatchReport(downloadIds: string[]): Promise<BatchReport> {
+  return invoke<BatchReport>('get_batch_report', { downloadIds });
+}
+
+/**
+ * Sets or clears the user's label on a download.
+ *
+ * Rust handler: `set_download_label()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Labels are notes only; they don't affect the download. A null or blank
+ * label removes it.
+ *
+ * @param downloadId - The unique ID of the download
+ * @param label - The new label, or null to clear it
+ * @returns Promise resolving to false if the ID is not in the queue
+ */
+export function setDownloadLabel(downloadId: string, label: string | null): Promise<boolean> {
+  return invoke<boolean>('set_download_label', { downloadId, label });
+}
+
+/**
+ * Keeps a completed download in the queue, calling off its automatic
+ * removal (`settings.auto_clear_completed_after_secs`).
+ *
+ * Rust handler: `keep_download()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Call when the user interacts with a completed item (opens its folder,
+ * expands it), so it doesn't vanish while they're looking at it.
+ *
+ * @param downloadId - The unique ID of the download
+ * @returns Promise resolving to true if a pending removal was called off
+ */
+export function keepDownload(downloadId: string): Promise<boolean> {
+  return invoke<boolean>('keep_download', { downloadId });
+}
+
+/**
+ * Sends a queued or failed download to another output folder.
+ *
+ * Rust handler: `set_download_output_path()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Pair with the dialog plugin's `open({ directory: true })` to let the
+ * user pick the folder. The folder must be writable; missing folders are
+ * created.
+ *
+ * @param downloadId - The unique ID of the download
+ * @param path - Absolute path of the new output folder
+ * @returns Promise resolving once changed; rejects for a running or
+ *   finished download, or a folder that can't be written to
+ */
+export function setDownloadOutputPath(downloadId: string, path: string): Promise<void> {
+  return invoke<void>('set_download_output_path', { downloadId, path });
+}
+
+/**
+ * Merges two queued downloads that save into the same album folder.
+ *
+ * Rust handler: `merge_downloads()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * The response to an `output-collision` event: the source's URLs are
+ * added to the target and the source leaves the queue, so one job writes
+ * the folder. Emits `download-queued` with the target's ID.
+ *
+ * @param targetId - The download that keeps both URL sets
+ * @param sourceId - The download merged into it and removed
+ * @returns Promise resolving once merged; rejects if either download isn't
+ *   queued or they don't share an album folder
+ */
+export function mergeDownloads(targetId: string, sourceId: string): Promise<void> {
+  return invoke<void>('merge_downloads', { targetId, sourceId });
+}
+
+/**
+ * Returns the recent download speeds of one queue item, oldest first.
+ *
+ * Rust handler: `get_speed_samples()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `SpeedSample[]` (about one per second, last two minutes)
+ *
+ * Used to draw a live throughput graph for an active download.
+ *
+ * @param downloadId - The unique ID of the download
+ * @returns Promise resolving to the samples; rejects if the ID is unknown
+ */
+export function getSpeedSamples(downloadId: string): Promise<SpeedSample[]> {
+  return invoke<SpeedSample[]>('get_speed_samples', { downloadId });
+}
+
+/**
+ * Estimates the disk space a download will take before it is queued.
+ *
+ * Rust handler: `estimate_size()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `SizeEstimate` (total, per-track breakdown, range for ALAC/Atmos)
+ *
+ * Based on catalog track durations and average codec bitrates, so it is an
+ * approximation. Needs MusicKit credentials.
+ *
+ * @param urls - Apple Music album, song or playlist URLs
+ * @param codec - Codec to estimate for (defaults to the settings' codec)
+ * @returns Promise resolving to the estimate; rejects without credentials
+ */
+export function estimateSize(urls: string[], codec?: SongCodec): Promise<SizeEstimate> {
+  return invoke<SizeEstimate>('estimate_size', { urls, codec: codec ?? null });
+}
+
+/**
+ * Checks the toolchain before a download: cookies, Python, GAMDL, FFmpeg
+ * (and mp4decrypt when needed), the output folder and the URLs.
+ *
+ * Rust handler: `preflight()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `PreflightReport` (pass/fail per check, with remediation hints)
+ *
+ * Failed checks are part of the report, not a rejection.
+ *
+ * @param urls - The URLs about to be downloaded
+ * @returns Promise resolving to the report
+ */
+export function preflight(urls: string[]): Promise<PreflightReport> {
+  return invoke<PreflightReport>('preflight', { urls });
+}
+
+/**
+ * Downloads one known song with the current settings into a temporary
+ * folder, checks that it decodes, and deletes it again.
+ *
+ * Rust handler: `run_test_download()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `TestDownloadResult` (pass/fail and the first failing stage)
+ *
+ * A failed test is part of the result, not a rejection.
+ *
+ * @returns Promise resolving to the result once the download finished
+ */
+export function runTestDownload(): Promise<TestDownloadResult> {
+  return invoke<TestDownloadResult>('run_test_download');
+}
+
+/**
+ * Adds another codec version of an album already on disk to its folder
+ * (e.g. a Dolby Atmos copy next to existing ALAC files).
+ *
+ * Rust handler: `add_codec_version()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: string download ID of the new queue item
+ *
+ * The new files get the codec's filename suffix (`[Lossless]` or
+ * `[Dolby Atmos]`), so the existing clean-named files are left alone.
+ * Rejects for lossy codecs, which have no suffix and would overwrite them.
+ *
+ * @param urls - Apple Music URL(s) of the album that was already downloaded
+ * @param codec - The codec to add
+ * @returns Promise resolving to the download ID
+ */
+export function addCodecVersion(urls: string[], codec: SongCodec): Promise<string> {
+  return invoke<string>('add_codec_version', { urls, codec });
+}
+
+/**
+ * Downloads only the companion versions of an album already on disk,
+ * skipping the primary download (e.g. ALAC + AAC next to existing Atmos
+ * files).
+ *
+ * Rust handler: `download_companions_only()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: string download ID of the new queue item
+ *
+ * The companions and their filename suffixes follow the current companion
+ * mode, as if the primary had just been downloaded. Rejects if the mode
+ * plans no companions for `primaryCodec`.
+ *
+ * @param urls - Apple Music URL(s) of the album that was already downloaded
+ * @param primaryCodec - The codec the existing files were downloaded in
+ * @returns Promise resolving to the download ID
+ */
+export function downloadCompanionsOnly(urls: string[], primaryCodec: SongCodec): Promise<string> {
+  return invoke<string>('download_companions_only', { urls, primaryCodec });
+}
+
+/**
+ * Opens the folder containing a download's output in the OS file manager.
+ *
+ * Rust handler: `reveal_output()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Single-track downloads open the track's folder; album downloads open the
+ * album folder. Rejects if the output was moved or deleted.
+ *
+ * @param downloadId - The UUID of the completed download
+ * @returns Promise that resolves once the file manager has been asked to open
+ */
+export function revealOutput(downloadId: string): Promise<void> {
+  return invoke<void>('reveal_output', { downloadId });
+}
+
+/**
+ * Returns the GAMDL output captured for a download, for a bug report.
+ *
+ * Rust handler: `get_download_log()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * Only downloads run with "Save Download Logs" on have a log; for others
+ * the promise rejects with a "No log captured" message. The cookies file
+ * path is replaced with `<cookies file>`.
+ *
+ * @param downloadId - The UUID of the download
+ * @returns Promise resolving to the redacted stdout/stderr of the job's runs
+ */
+export function getDownloadLog(downloadId: string): Promise<string> {
+  return invoke<string>('get_download_log', { downloadId });
+}
+
+/**
+ * Opens a download's log file in the OS default text viewer.
+ *
+ * Rust handler: `open_download_log()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * @param downloadId - The UUID of the download
+ * @returns Promise that resolves once the viewer has been asked to open;
+ *   rejects with a "No log captured" message when there is no log
+ */
+export function openDownloadLog(downloadId: string): Promise<void> {
+  return invoke<void>('open_download_log', { downloadId });
+}
+
+/**
+ * Returns the options a download runs with, to see why it used the codec
+ * or filenames it did.
+ *
+ * Rust handler: `get_effective_options()` in `src-tauri/src/commands/gamdl.rs`
+ *
+ * These are the settings and per-download overrides merged, plus any codec
+ * fallback and the filename suffix it applied. The cookies path, wrapper
+ * account URL, `.wvd` device and tool paths read `<redacted>`.
+ *
+ * @param downloadId - The UUID of the download
+ * @returns Promise resolving to the redacted options; rejects when the
+ *   download isn't in the queue
+ */
+export function getEffectiveOptions(downloadId: string): Promise<GamdlOptions> {
+  return invoke<GamdlOptions>('get_effective_options', { downloadId });
+}
+
 /**
  * Checks the latest GAMDL version available on PyPI.
  *
@@ -440,6 +915,23 @@ export function importQueue(): Promise<number> {
   return invoke<number>('import_queue');
 }
 
+/**
+ * Queues one download per row of a CSV file (`url,codec,output_path,label`),
+ * each with its own codec, output folder and label.
+ *
+ * Rust handler: `import_csv()` in `src-tauri/src/commands/gamdl.rs`
+ * Returns: `CsvImportReport` (queued download IDs and the skipped rows)
+ *
+ * Rows with a broken URL, an unknown codec or a relative output path are
+ * reported with their line number; the rest are still queued.
+ *
+ * @param path - Absolute path of the CSV file
+ * @returns Promise resolving to the report; rejects if the file can't be read
+ */
+export function importCsv(path: string): Promise<CsvImportReport> {
+  return invoke<CsvImportReport>('import_csv', { path });
+}
+
 // ============================================================
 // Credential Commands
 // ============================================================
@@ -454,6 +946,10 @@ export function importQueue(): Promise<number> {
  * Used for securely storing API wrapper tokens and other secrets that
  * should not be stored in plaintext in the settings file.
  *
+ * Values for known keys are validated first; the MusicKit private key must
+ * be a PEM `PRIVATE KEY` block. Rejects with a `CredentialError`
+ * (`{ code, key, message }`) on invalid input or keychain failure.
+ *
  * @param key - Credential identifier (e.g., "wrapper_api_token")
  * @param value - The secret value to store
  * @returns Promise resolving when the credential is stored
@@ -489,6 +985,35 @@ export function deleteCredential(key: string): Promise<void> {
   return invoke<void>('delete_credential', { key });
 }
 
+/**
+ * Verifies the configured MusicKit credentials against Apple's API.
+ *
+ * Rust handler: `verify_musickit_credentials()` in `src-tauri/src/commands/credentials.rs`
+ *
+ * Validates the Team ID, Key ID, and keychain private key, then signs a
+ * developer token and makes one catalog request with it. Rejects with a
+ * `CredentialError` (`{ code, key, message }`) describing the problem.
+ *
+ * @returns Promise resolving when Apple accepts the credentials
+ */
+export function verifyMusickitCredentials(): Promise<void> {
+  return invoke<void>('verify_musickit_credentials');
+}
+
+/**
+ * Returns the bearer token for the localhost headless API, generating one
+ * in the OS keychain on first use.
+ *
+ * Rust handler: `get_headless_api_token()` in `src-tauri/src/commands/credentials.rs`
+ * Returns: the token string; rejects with a `CredentialError` if the
+ * keychain can't be accessed
+ *
+ * @returns Promise resolving to the token scripts send as `Authorization: Bearer <token>`
+ */
+export function getHeadlessApiToken(): Promise<string> {
+  return invoke<string>('get_headless_api_token');
+}
+
 // ============================================================
 // Update Commands
 // ============================================================
@@ -618,6 +1143,21 @@ export function checkFullDiskAccess(): Promise<boolean> {
   return invoke<boolean>('check_full_disk_access');
 }
 
+/**
+ * Tests a cookies file against Apple Music with one authenticated request.
+ *
+ * Rust handler: `test_cookies_live()` in `src-tauri/src/commands/cookies.rs`
+ * Argument: `path` - path to the Netscape-format cookies file
+ * Returns: `CookieLiveTest` with status (valid/malformed/expired/unverified),
+ * a message, and the account's storefront when valid
+ *
+ * @param path - Path to the cookies file
+ * @returns Promise resolving to the live check result
+ */
+export function testCookiesLive(path: string): Promise<CookieLiveTest> {
+  return invoke<CookieLiveTest>('test_cookies_live', { path });
+}
+
 // ============================================================
 // Login Window Commands
 // ============================================================
@@ -703,3 +1243,25 @@ export function downloadAnimatedArtwork(
 ): Promise<ArtworkResult> {
   return invoke<ArtworkResult>('download_animated_artwork', { urls, outputDir });
 }
+
+/**
+ * Re-checks an already-downloaded album for animated artwork, fetching
+ * only the variants missing from its folder.
+ *
+ * Rust handler: `recheck_album_artwork()` in `src-tauri/src/commands/artwork.rs`
+ * Arguments: `urls` - Apple Music URL(s), `albumDir` - existing album directory
+ * Returns: `ArtworkResult` indicating which artwork types were newly downloaded
+ *
+ * For albums downloaded before their motion artwork existed or before
+ * animated artwork was enabled. Existing `FrontCover.mp4` etc. are kept.
+ *
+ * @param urls - Apple Music URL(s) for the album
+ * @param albumDir - The album directory holding the downloaded files
+ * @returns Promise resolving to the newly downloaded variants
+ */
+export function recheckAlbumArtwork(
+  urls: string[],
+  albumDir: string,
+): Promise<ArtworkResult> {
+  return invoke<ArtworkResult>('recheck_album_artwork', { urls, albumDir });
+}
@@ -23,6 +23,11 @@
  *   - `<SettingsPage>` -- Displays tool paths and versions in the advanced section.
  *   - `<App>` -- Calls `checkAll()` on startup to determine if setup is needed.
  *
+ * While Python or a tool is installed, `installProgress` follows the
+ * `dependency-download-progress` and `dependency-extract-progress` events,
+ * which the setup steps show as a progress bar. (GAMDL is a pip install and
+ * has no such events.)
+ *
  * The `isReady()` computed getter is the key decision point: it returns `true`
  * only when both Python and GAMDL are installed, which is the minimum requirement
  * for the download functionality to work.
@@ -34,14 +39,32 @@
 // Zustand store factory. Creates a React hook backed by a single store instance.
 import { create } from 'zustand';
 
+// Tauri event subscription for the install progress events.
+import { listen } from '@tauri-apps/api/event';
+
 // DependencyStatus -- { name, required, installed, version, path } shape from the backend.
 // Mirrors the Rust `DependencyStatus` struct serialized over the IPC boundary.
-import type { DependencyStatus } from '@/types';
+// The progress types are the payloads of the install progress events.
+import type {
+  DependencyStatus,
+  DependencyDownloadProgress,
+  DependencyExtractProgress,
+} from '@/types';
 
 // Type-safe wrappers for Tauri IPC commands related to dependency management.
 // Each function maps to a `#[tauri::command]` handler in the Rust backend.
 import * as commands from '@/lib/tauri-commands';
 
+/**
+ * Progress of the running install, from the latest progress event.
+ */
+export interface InstallProgress {
+  /** Whether the archive is downloading or being extracted */
+  stage: 'download' | 'extract';
+  /** 0-100, or null when the size is unknown (indeterminate) */
+  percent: number | null;
+}
+
 /**
  * Combined state + actions interface for the dependency store.
  *
@@ -107,6 +130,13 @@ interface DependencyState {
    */
   installingName: string | null;
 
+  /**
+   * Download or extraction progress of the running Python or tool install.
+   * `null` when no install is running, before its first progress event,
+   * and during GAMDL installs.
+   */
+  installProgress: InstallProgress | null;
+
   /**
    * Error message from the last failed check or install operation.
    * `null` when there is no error.
@@ -212,6 +242,7 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
   isChecking: false,      // No check in progress
   isInstalling: false,    // No installation in progress
   installingName: null,   // No component being installed
+  installProgress: null,  // No install progress to show
   error: null,            // No error
 
   // -------------------------------------------------------------------------
@@ -275,10 +306,12 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
   // -------------------------------------------------------------------------
   // Install actions -- each follows the pattern:
   //   1. Set isInstalling + installingName + clear error
+  //      (Python and tools: follow the install progress events)
   //   2. Call the Rust install command
   //   3. Re-check the dependency status to confirm
   //   4. Clear isInstalling + installingName
   //   5. On failure: store error, clear flags, re-throw
+  //   6. Python and tools: stop following progress, clear installProgress
   // -------------------------------------------------------------------------
 
   /**
@@ -297,6 +330,7 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
   installPython: async () => {
     // Signal that an installation is starting. The UI shows "Installing Python...".
     set({ isInstalling: true, installingName: 'Python', error: null });
+    const unlisten = await followInstallProgress(set);
     try {
       // Invoke the Rust `install_python` command, which downloads and extracts Python.
       const version = await commands.installPython();
@@ -311,6 +345,9 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
       set({ error: msg, isInstalling: false, installingName: null });
       // Re-throw so the calling component (e.g., SetupWizard) can handle the failure.
       throw new Error(msg);
+    } finally {
+      unlisten();
+      set({ installProgress: null });
     }
   },
 
@@ -357,6 +394,7 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
    */
   installTool: async (name: string) => {
     set({ isInstalling: true, installingName: name, error: null });
+    const unlisten = await followInstallProgress(set);
     try {
       const version = await commands.installDependency(name);
       // Re-check ALL tools (not just the installed one) because some tools
@@ -368,6 +406,9 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
       const msg = String(e);
       set({ error: msg, isInstalling: false, installingName: null });
       throw new Error(msg);
+    } finally {
+      unlisten();
+      set({ installProgress: null });
     }
   },
 
@@ -392,3 +433,34 @@ export const useDependencyStore = create<DependencyState>((set, get) => ({
     return !!(python?.installed && gamdl?.installed);
   },
 }));
+
+/**
+ * Subscribes to the install progress events, copying each into
+ * `installProgress`, for the duration of one install.
+ *
+ * Only one install runs at a time, so the events' `name` isn't checked.
+ *
+ * @param set -- The store's `set` function
+ * @returns A function that unsubscribes from both events
+ */
+async function followInstallProgress(
+  set: (partial: Partial<DependencyState>) => void,
+): Promise<() => void> {
+  try {
+    const unlistenDownload = await listen<DependencyDownloadProgress>(
+      'dependency-download-progress',
+      (event) => set({ installProgress: { stage: 'download', percent: event.payload.percent } }),
+    );
+    const unlistenExtract = await listen<DependencyExtractProgress>(
+      'dependency-extract-progress',
+      (event) => set({ installProgress: { stage: 'extract', percent: event.payload.percent } }),
+    );
+    return () => {
+      unlistenDownload();
+      unlistenExtract();
+    };
+  } catch {
+    /* Tauri API unavailable (running in browser dev mode) */
+    return () => {};
+  }
+}
@@ -27,6 +27,8 @@ vi.mock('@/lib/tauri-commands', () => ({
   cancelDownload: vi.fn(),
   retryDownload: vi.fn(),
   clearQueue: vi.fn(),
+  clearCompleted: vi.fn(),
+  clearFailed: vi.fn(),
   getQueueStatus: vi.fn(),
 }));
 
@@ -41,6 +43,7 @@ function createMockQueueItem(overrides: Partial<QueueItemStatus> = {}): QueueIte
     state: 'queued',
     progress: 0,
     current_track: null,
+    current_step: null,
     total_tracks: null,
     completed_tracks: null,
     speed: null,
@@ -49,7 +52,13 @@ function createMockQueueItem(overrides: Partial<QueueItemStatus> = {}): QueueIte
     output_path: null,
     codec_used: null,
     fallback_occurred: false,
+    fallback_reason: null,
+    track_codecs: [],
+    skipped_tracks: [],
+    cover_missing_tracks: 0,
+    url_progress: [],
     created_at: '2026-02-09T12:00:00Z',
+    label: null,
     ...overrides,
   };
 }
@@ -281,6 +290,30 @@ describe('downloadStore', () => {
       useDownloadStore.getState().handleProgressEvent(progress);
 
       expect(useDownloadStore.getState().queueItems[0].state).toBe('processing');
+      expect(useDownloadStore.getState().queueItems[0].current_step).toBe('Remuxing');
+    });
+
+    it('returns to downloading state on fetching_keys event', () => {
+      const item = createMockQueueItem({ id: 'dl-1', state: 'processing' });
+      useDownloadStore.setState({ queueItems: [item] });
+
+      const progress: GamdlProgress = {
+        download_id: 'dl-1',
+        event: { type: 'fetching_keys', step: 'Fetching decryption keys' },
+      };
+      useDownloadStore.getState().handleProgressEvent(progress);
+
+      expect(useDownloadStore.getState().queueItems[0].state).toBe('downloading');
+      expect(useDownloadStore.getState().queueItems[0].current_step).toBe(
+        'Fetching decryption keys',
+      );
+
+      // The step is cleared once the stream download starts
+      useDownloadStore.getState().handleProgressEvent({
+        download_id: 'dl-1',
+        event: { type: 'download_progress', percent: 5, speed: '1MiB/s', eta: '00:10' },
+      });
+      expect(useDownloadStore.getState().queueItems[0].current_step).toBeNull();
     });
 
     it('handles complete event in progress handler', () => {
@@ -428,7 +461,7 @@ describe('downloadStore', () => {
       const refreshedItems = [createMockQueueItem({ id: 'dl-1', state: 'queued' })];
       vi.mocked(commands.retryDownload).mockResolvedValueOnce(undefined);
       vi.mocked(commands.getQueueStatus).mockResolvedValueOnce({
-        total: 1, active: 0, queued: 1, completed: 0, failed: 0,
+        total: 1, active: 0, queued: 1, completed: 0, failed: 0, overall_progress: 0,
         items: refreshedItems,
       });
 
@@ -443,7 +476,7 @@ describe('downloadStore', () => {
     it('clears finished items and returns the count', async () => {
       vi.mocked(commands.clearQueue).mockResolvedValueOnce(3);
       vi.mocked(commands.getQueueStatus).mockResolvedValueOnce({
-        total: 1, active: 1, queued: 0, completed: 0, failed: 0,
+        total: 1, active: 1, queued: 0, completed: 0, failed: 0, overall_progress: 0,
         items: [createMockQueueItem({ id: 'dl-active', state: 'downloading' })],
       });
 
@@ -463,6 +496,44 @@ describe('downloadStore', () => {
     });
   });
 
+  describe('clearCompleted', () => {
+    it('clears completed items and refreshes the queue', async () => {
+      vi.mocked(commands.clearCompleted).mockResolvedValueOnce(2);
+      vi.mocked(commands.getQueueStatus).mockResolvedValueOnce({
+        total: 1, active: 0, queued: 0, completed: 0, failed: 1, overall_progress: 0,
+        items: [createMockQueueItem({ id: 'dl-failed', state: 'error' })],
+      });
+
+      const removed = await useDownloadStore.getState().clearCompleted();
+
+      expect(removed).toBe(2);
+      expect(useDownloadStore.getState().queueItems[0].id).toBe('dl-failed');
+    });
+  });
+
+  describe('clearFailed', () => {
+    it('clears failed items and refreshes the queue', async () => {
+      vi.mocked(commands.clearFailed).mockResolvedValueOnce(1);
+      vi.mocked(commands.getQueueStatus).mockResolvedValueOnce({
+        total: 1, active: 0, queued: 0, completed: 1, failed: 0, overall_progress: 0,
+        items: [createMockQueueItem({ id: 'dl-done', state: 'complete' })],
+      });
+
+      const removed = await useDownloadStore.getState().clearFailed();
+
+      expect(removed).toBe(1);
+      expect(useDownloadStore.getState().queueItems[0].id).toBe('dl-done');
+    });
+
+    it('returns 0 on failure', async () => {
+      vi.mocked(commands.clearFailed).mockRejectedValueOnce('Clear failed');
+
+      const removed = await useDownloadStore.getState().clearFailed();
+
+      expect(removed).toBe(0);
+    });
+  });
+
   describe('refreshQueue', () => {
     it('fetches queue status from the backend', async () => {
       const items = [
@@ -470,7 +541,7 @@ describe('downloadStore', () => {
         createMockQueueItem({ id: 'dl-2', state: 'downloading', progress: 50 }),
       ];
       vi.mocked(commands.getQueueStatus).mockResolvedValueOnce({
-        total: 2, active: 1, queued: 0, completed: 1, failed: 0,
+        total: 2, active: 1, queued: 0, completed: 1, failed: 0, overall_progress: 0,
         items,
       });
 
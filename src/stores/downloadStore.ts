@@ -28,7 +28,8 @@
  *     perform immutable updates on the `queueItems` array.
  *
  *   **Queue management**:
- *   - `cancelDownload()`, `retryDownload()`, `clearFinished()` delegate to Rust
+ *   - `cancelDownload()`, `retryDownload()`, `clearFinished()`,
+ *     `clearCompleted()`, `clearFailed()` delegate to Rust
  *     commands and then refresh the queue snapshot.
  *   - `refreshQueue()` fetches the full queue state from the backend, used on
  *     app startup and after mutations to ensure consistency.
@@ -185,6 +186,20 @@ interface DownloadState {
    */
   clearFinished: () => Promise<number>;
 
+  /**
+   * Remove only the completed items from the queue.
+   * IPC call: `commands.clearCompleted()` -> Rust `clear_completed`
+   * @returns The count of items that were cleared
+   */
+  clearCompleted: () => Promise<number>;
+
+  /**
+   * Remove only the failed items from the queue.
+   * IPC call: `commands.clearFailed()` -> Rust `clear_failed`
+   * @returns The count of items that were cleared
+   */
+  clearFailed: () => Promise<number>;
+
   /**
    * Fetch the full queue snapshot from the Rust backend.
    * IPC call: `commands.getQueueStatus()` -> Rust `get_queue_status`
@@ -218,8 +233,8 @@ interface DownloadState {
    * and emits `gamdl://progress` events with a `GamdlProgress` payload.
    *
    * This handler performs an immutable update on the matching queue item based
-   * on the event type: `download_progress`, `track_info`, `processing_step`,
-   * `complete`, or `error`.
+   * on the event type: `download_progress`, `track_info`, `fetching_keys`,
+   * `processing_step`, `complete`, or `error`.
    *
    * @param progress -- The structured progress event payload
    * @see {@link https://v2.tauri.app/develop/calling-frontend/} -- Tauri event system
@@ -411,6 +426,38 @@ export const useDownloadStore = create<DownloadState>((set, get) => ({
     }
   },
 
+  /**
+   * Clear only completed items from the queue, then refresh.
+   * IPC call: `commands.clearCompleted()` -> Rust `clear_completed`
+   */
+  clearCompleted: async () => {
+    try {
+      const removed = await commands.clearCompleted();
+      const status = await commands.getQueueStatus();
+      set({ queueItems: status.items });
+      return removed;
+    } catch (e) {
+      set({ error: String(e) });
+      return 0;
+    }
+  },
+
+  /**
+   * Clear only failed items from the queue, then refresh.
+   * IPC call: `commands.clearFailed()` -> Rust `clear_failed`
+   */
+  clearFailed: async () => {
+    try {
+      const removed = await commands.clearFailed();
+      const status = await commands.getQueueStatus();
+      set({ queueItems: status.items });
+      return removed;
+    } catch (e) {
+      set({ error: String(e) });
+      return 0;
+    }
+  },
+
   /**
    * Fetch the full queue snapshot from the Rust backend.
    * IPC call: `commands.getQueueStatus()` -> Rust `get_queue_status`
@@ -501,14 +548,23 @@ export const useDownloadStore = create<DownloadState>((set, get) => ({
             item.speed = progress.event.speed || null;
             item.eta = progress.event.eta || null;
             item.state = 'downloading'; // Transition to 'downloading' state
+            item.current_step = null; // The stream itself is downloading
             break;
           case 'track_info':
             // Update the currently-downloading track name for display.
             item.current_track = progress.event.title || null;
             break;
+          case 'fetching_keys':
+            // Getting the next track's decryption key: the download is underway
+            // again after the previous track's post-processing, but waiting on
+            // the license server rather than the stream.
+            item.state = 'downloading';
+            item.current_step = progress.event.step;
+            break;
           case 'processing_step':
             // Transition to 'processing' (post-download remuxing/tagging).
             item.state = 'processing';
+            item.current_step = progress.event.step;
             break;
           case 'complete':
             // Mark as complete with 100% progress and record output path.
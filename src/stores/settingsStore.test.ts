@@ -36,33 +36,72 @@ const MOCK_SETTINGS: AppSettings = {
   output_path: '/tmp/test-output',
   language: 'ja-JP',
   overwrite: true,
+  overwrite_policy: 'replace',
+  use_staging_dir: true,
+  expand_artist_urls: false,
+  playlist_sync: false,
+  skip_unavailable_tracks: true,
+  content_preference: 'clean',
+  preflight_checks: true,
+  preempt_lower_priority: true,
   auto_check_updates: false,
+  auto_quit_when_done: true,
+  schedule_enabled: true,
+  schedule_start: '22:30',
+  schedule_end: '06:00',
+  schedule_pause_at_close: true,
+  min_free_space_gb: 20,
+  network_retry_budget: 5,
+  rate_limit_retry_budget: 0,
+  download_stall_timeout_secs: 600,
+  max_background_tasks: 4,
+  inter_download_delay_secs: 30,
+  auto_clear_completed_after_secs: 300,
   default_song_codec: 'aac',
   default_video_resolution: '1080p',
   default_video_codec_priority: 'h264,h265',
   default_video_remux_format: 'mp4',
+  uploaded_video_quality: 'best',
+  download_album_music_videos: true,
   fallback_enabled: false,
   music_fallback_chain: ['aac', 'aac-legacy'],
+  per_track_best_codec: true,
   video_fallback_chain: ['1080p', '720p'],
   companion_mode: 'disabled',
+  companion_subfolders: { alac: 'Lossless' },
+  preview_then_upgrade: true,
+  convert_lossless_to_flac: true,
+  keep_m4a_after_flac: true,
+  transcode_companion: { format: 'mp3', bitrate_kbps: 320 },
+  verify_audio: true,
   embed_lyrics_and_sidecar: false,
   synced_lyrics_format: 'srt',
   no_synced_lyrics: true,
   synced_lyrics_only: false,
+  keep_mv_lyrics: true,
+  convert_lyrics_to: 'vtt',
   save_cover: false,
   cover_format: 'png',
   cover_size: 600,
+  cover_size_by_codec: { alac: 3000, aac: 600 },
+  embedded_cover_size: 300,
+  extra_cover_sizes: [600, 300],
+  continue_on_cover_failure: true,
   animated_artwork_enabled: true,
   hide_animated_artwork: false,
+  animated_artwork_variants: ['square'],
+  animated_artwork_max_height: 1080,
   musickit_team_id: 'TEST123456',
   musickit_key_id: 'KEY1234567',
   album_folder_template: '{artist}/{album}',
   compilation_folder_template: 'Various/{album}',
   no_album_folder_template: '{artist}/Singles',
+  singles_folder_template: '{artist}/Singles',
   single_disc_file_template: '{track} {title}',
   multi_disc_file_template: '{disc}-{track} {title}',
   no_album_file_template: '{title}',
   playlist_file_template: 'Playlists/{playlist_title}',
+  save_playlist_m3u: true,
   cookies_path: '/tmp/cookies.txt',
   ffmpeg_path: null,
   mp4decrypt_path: null,
@@ -71,10 +110,21 @@ const MOCK_SETTINGS: AppSettings = {
   amdecrypt_path: null,
   download_mode: 'nm3u8dlre',
   remux_mode: 'mp4box',
+  batch_mode: true,
+  suppress_gamdl_exceptions: true,
+  process_niceness: 10,
+  use_gamdl_config_file: false,
+  save_download_logs: true,
   use_wrapper: true,
   wrapper_account_url: 'http://localhost:9999',
+  headless_api_enabled: true,
+  headless_api_port: 9000,
+  http_proxy: 'http://proxy.test:8080',
+  https_proxy: null,
   truncate: 100,
   fetch_extra_tags: false,
+  use_album_date: true,
+  date_tag_template: '%Y',
   exclude_tags: ['rating'],
   sidebar_collapsed: true,
   theme_override: 'dark',
@@ -90,33 +140,72 @@ beforeEach(() => {
       output_path: '',
       language: 'en-US',
       overwrite: false,
+      overwrite_policy: null,
+      use_staging_dir: false,
+      expand_artist_urls: true,
+      playlist_sync: false,
+      skip_unavailable_tracks: false,
+      content_preference: 'as_is',
+      preflight_checks: false,
+      preempt_lower_priority: false,
       auto_check_updates: true,
+      auto_quit_when_done: false,
+      schedule_enabled: false,
+      schedule_start: '23:00',
+      schedule_end: '07:00',
+      schedule_pause_at_close: false,
+      min_free_space_gb: null,
+      network_retry_budget: 3,
+      rate_limit_retry_budget: 2,
+      download_stall_timeout_secs: null,
+      max_background_tasks: 2,
+      inter_download_delay_secs: 0,
+      auto_clear_completed_after_secs: null,
       default_song_codec: 'alac',
       default_video_resolution: '2160p',
       default_video_codec_priority: 'h265,h264',
       default_video_remux_format: 'm4v',
+      uploaded_video_quality: null,
+      download_album_music_videos: false,
       fallback_enabled: true,
       music_fallback_chain: ['alac', 'atmos', 'ac3', 'aac-binaural', 'aac', 'aac-legacy'],
+      per_track_best_codec: false,
       video_fallback_chain: ['2160p', '1440p', '1080p', '720p', '540p', '480p', '360p', '240p'],
       companion_mode: 'atmos_to_lossless',
+      companion_subfolders: {},
+      preview_then_upgrade: false,
+      convert_lossless_to_flac: false,
+      keep_m4a_after_flac: false,
+      transcode_companion: null,
+      verify_audio: false,
       embed_lyrics_and_sidecar: true,
       synced_lyrics_format: 'lrc',
       no_synced_lyrics: false,
       synced_lyrics_only: false,
+      keep_mv_lyrics: false,
+      convert_lyrics_to: null,
       save_cover: true,
       cover_format: 'raw',
       cover_size: 10000,
+      cover_size_by_codec: {},
+      embedded_cover_size: null,
+      extra_cover_sizes: [],
+      continue_on_cover_failure: false,
       animated_artwork_enabled: false,
       hide_animated_artwork: true,
+      animated_artwork_variants: ['square', 'portrait'],
+      animated_artwork_max_height: null,
       musickit_team_id: null,
       musickit_key_id: null,
       album_folder_template: '{album_artist}/{album}',
       compilation_folder_template: 'Compilations/{album}',
       no_album_folder_template: '{artist}/Unknown Album',
+      singles_folder_template: null,
       single_disc_file_template: '{track:02d} {title}',
       multi_disc_file_template: '{disc}-{track:02d} {title}',
       no_album_file_template: '{title}',
       playlist_file_template: 'Playlists/{playlist_artist}/{playlist_title}',
+      save_playlist_m3u: false,
       cookies_path: null,
       ffmpeg_path: null,
       mp4decrypt_path: null,
@@ -125,10 +214,21 @@ beforeEach(() => {
       amdecrypt_path: null,
       download_mode: 'ytdlp',
       remux_mode: 'ffmpeg',
+      batch_mode: false,
+      suppress_gamdl_exceptions: false,
+      process_niceness: null,
+      use_gamdl_config_file: false,
+      save_download_logs: false,
       use_wrapper: false,
       wrapper_account_url: 'http://127.0.0.1:30020',
+      headless_api_enabled: false,
+      headless_api_port: 18420,
+      http_proxy: null,
+      https_proxy: null,
       truncate: null,
       fetch_extra_tags: true,
+      use_album_date: false,
+      date_tag_template: null,
       exclude_tags: [],
       sidebar_collapsed: false,
       theme_override: null,
@@ -61,11 +61,33 @@ const DEFAULT_SETTINGS: AppSettings = {
   output_path: '',               // Resolved to ~/Music (or platform equivalent) by backend
   language: 'en-US',             // Apple Music storefront language
   overwrite: false,              // Do not overwrite existing files by default
+  overwrite_policy: null,        // Follow `overwrite` (skip existing files)
+  use_staging_dir: false,        // Write straight into the output folder
+  expand_artist_urls: true,      // One job per album for artist URLs
+  playlist_sync: false,          // Download playlists in full
+  skip_unavailable_tracks: false, // Let GAMDL report unavailable tracks
+  content_preference: 'as_is',   // Download the version that was linked
+  preflight_checks: false,       // Queue downloads without a toolchain check
+  preempt_lower_priority: false, // Priority only orders waiting downloads
   auto_check_updates: true,      // Automatically check for updates on startup
+  auto_quit_when_done: false,    // Stay open after the queue drains
+  schedule_enabled: false,       // Download at any time of day
+  schedule_start: '23:00',       // Overnight window when scheduling is on
+  schedule_end: '07:00',
+  schedule_pause_at_close: false, // Let running downloads finish at window close
+  min_free_space_gb: null,       // No free disk space guard
+  network_retry_budget: 3,       // Quick retries after connection blips
+  rate_limit_retry_budget: 2,    // Cooled-down retries after HTTP 429
+  download_stall_timeout_secs: null, // Never kill a silent download
+  max_background_tasks: 2,       // Concurrent artwork/companion tasks
+  inter_download_delay_secs: 0,  // No pause between downloads
+  auto_clear_completed_after_secs: null, // Completed items stay until cleared
   default_song_codec: 'alac',    // Preferred audio codec: Apple Lossless
   default_video_resolution: '2160p', // Preferred video quality: 4K
   default_video_codec_priority: 'h265,h264', // Try H.265 first, fall back to H.264
   default_video_remux_format: 'm4v', // Container format for remuxed music videos
+  uploaded_video_quality: null,      // GAMDL's default quality for uploaded videos
+  download_album_music_videos: false, // GAMDL skips music videos on albums
   fallback_enabled: true,        // Enable quality fallback chains when preferred unavailable
   // Music codec fallback chain: tried in order when `default_song_codec` is unavailable
   music_fallback_chain: [
@@ -76,6 +98,7 @@ const DEFAULT_SETTINGS: AppSettings = {
     'aac',            // 5th -- standard AAC 256kbps
     'aac-legacy',     // 6th -- legacy AAC (44.1kHz cap)
   ],
+  per_track_best_codec: false,   // Fall back per album, not per track
   // Video resolution fallback chain: tried in order when preferred resolution unavailable
   video_fallback_chain: [
     '2160p',  // 4K
@@ -88,26 +111,42 @@ const DEFAULT_SETTINGS: AppSettings = {
     '240p',   // Lowest
   ],
   companion_mode: 'atmos_to_lossless', // Atmos → also download ALAC companion (default)
+  companion_subfolders: {},      // Companions told apart by filename suffixes
+  preview_then_upgrade: false,   // Download ALAC directly
+  convert_lossless_to_flac: false, // Keep GAMDL's ALAC-in-M4A output
+  keep_m4a_after_flac: false,    // Replace the M4A once converted
+  transcode_companion: null,     // No MP3/Opus copies (M4A plays on most devices)
+  verify_audio: false,           // Don't decode each file after download
   embed_lyrics_and_sidecar: true,  // Embed lyrics in metadata AND keep sidecar files
   synced_lyrics_format: 'lrc',   // Default lyrics format (LRC is most widely supported)
   no_synced_lyrics: false,       // Do download synced lyrics
   synced_lyrics_only: false,     // Also download plain-text lyrics
+  keep_mv_lyrics: false,         // Don't fetch lyrics of skipped album music videos
+  convert_lyrics_to: null,       // Keep TTML lyrics as downloaded
   save_cover: true,              // Save album artwork alongside audio files
   cover_format: 'raw',           // Keep original artwork format (usually JPEG from Apple)
   cover_size: 10000,             // Request maximum available artwork resolution from Apple CDN
+  cover_size_by_codec: {},       // Every codec uses cover_size
+  embedded_cover_size: null,     // Embed the same cover as the saved file
+  extra_cover_sizes: [],         // No extra cover-{size}.jpg files
+  continue_on_cover_failure: false, // A cover art failure fails the download
   // Animated artwork (motion cover art) -- requires MusicKit credentials
   animated_artwork_enabled: false, // Disabled by default; needs Apple Developer setup
   hide_animated_artwork: true,     // Hide artwork files from default file browser views
+  animated_artwork_variants: ['square', 'portrait'], // FrontCover.mp4 + PortraitCover.mp4
+  animated_artwork_max_height: null, // Keep original resolution
   musickit_team_id: null,          // Apple Developer Team ID (10-char)
   musickit_key_id: null,           // MusicKit private key identifier (10-char)
   // File/folder naming templates -- use GAMDL's template variable syntax
   album_folder_template: '{album_artist}/{album}',
   compilation_folder_template: 'Compilations/{album}',
   no_album_folder_template: '{artist}/Unknown Album',
+  singles_folder_template: null,          // Singles use the no-album folder
   single_disc_file_template: '{track:02d} {title}',      // Zero-padded track number
   multi_disc_file_template: '{disc}-{track:02d} {title}', // Disc-track for multi-disc albums
   no_album_file_template: '{title}',
   playlist_file_template: 'Playlists/{playlist_artist}/{playlist_title}',
+  save_playlist_m3u: false,      // No GAMDL playlist M3U8 files
   // Tool paths -- null means "auto-detect from bundled/PATH"
   cookies_path: null,            // Netscape-format cookies file for authentication
   ffmpeg_path: null,             // FFmpeg binary for audio/video processing
@@ -117,10 +156,21 @@ const DEFAULT_SETTINGS: AppSettings = {
   amdecrypt_path: null,          // Apple Music decryption tool
   download_mode: 'ytdlp',       // Stream download backend: yt-dlp (default) or N_m3u8DL-RE
   remux_mode: 'ffmpeg',         // Remuxing backend: FFmpeg (default) or MP4Box
+  batch_mode: false,            // One queue job per imported entry
+  suppress_gamdl_exceptions: false, // Keep tracebacks for crash reports
+  process_niceness: null,        // Downloads run at normal priority
+  use_gamdl_config_file: false,  // The GUI is the sole source of GAMDL settings
+  save_download_logs: false,     // Per-download logs only when troubleshooting
   use_wrapper: false,            // Whether to use a remote account wrapper service
   wrapper_account_url: 'http://127.0.0.1:30020', // Default wrapper service URL (localhost)
+  headless_api_enabled: false,   // No scripting API unless the user opts in
+  headless_api_port: 18420,      // Localhost port for the scripting API
+  http_proxy: null,              // No HTTP proxy (direct connection)
+  https_proxy: null,             // No HTTPS proxy (direct connection)
   truncate: null,                // Max filename length in characters; null = no truncation
   fetch_extra_tags: true,        // Fetch extra metadata (normalization, smooth playback info)
+  use_album_date: false,         // Tag tracks with their own date, not the album's
+  date_tag_template: null,       // strftime pattern for the date tag; null = GAMDL default
   exclude_tags: [],              // Metadata tags to exclude from output files
   sidebar_collapsed: false,      // UI preference: sidebar expanded by default
   theme_override: null,          // null = follow OS theme; 'light' or 'dark' to override
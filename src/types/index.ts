@@ -95,6 +95,14 @@ export type VideoResolution =
  */
 export type LyricsFormat = 'lrc' | 'srt' | 'ttml';
 
+/**
+ * Formats the app converts downloaded TTML lyrics to (`convert_lyrics_to`).
+ * Separate from `LyricsFormat` because GAMDL can't write WebVTT.
+ *
+ * Mirrors: Rust enum `LyricsConversion` in `src-tauri/src/models/settings.rs`
+ */
+export type LyricsConversion = 'srt' | 'vtt' | 'lrc';
+
 /**
  * Cover art image format options for GAMDL's `--cover-format` flag.
  *
@@ -126,6 +134,52 @@ export type DownloadMode = 'ytdlp' | 'nm3u8dlre';
  */
 export type RemuxMode = 'ffmpeg' | 'mp4box';
 
+/**
+ * What a download does with a file that already exists in the output folder.
+ *
+ * Mirrors: Rust enum `OverwritePolicy` in `src-tauri/src/models/settings.rs`
+ *
+ * - `skip`: [DEFAULT] Keep the existing file and skip the track
+ * - `replace`: Download again and replace the file (GAMDL `--overwrite`)
+ * - `version`: Keep the existing file and save the new one as `Name (1).m4a`
+ */
+export type OverwritePolicy = 'skip' | 'replace' | 'version';
+
+/**
+ * Which version of a track to download when Apple Music has both an
+ * explicit and a clean release.
+ *
+ * Mirrors: Rust enum `ContentPreference` in `src-tauri/src/models/settings.rs`
+ *
+ * - `as_is`: [DEFAULT] Download the version the URL points at
+ * - `explicit`: Swap clean tracks for their explicit version where there is one
+ * - `clean`: Swap explicit tracks for their clean version where there is one
+ */
+export type ContentPreference = 'as_is' | 'explicit' | 'clean';
+
+/**
+ * Format of the transcode companion copies.
+ *
+ * Mirrors: Rust enum `TranscodeFormat` in `src-tauri/src/models/settings.rs`
+ *
+ * - `mp3`: LAME MP3 (32-320 kbps), saved as `01 Song [MP3].mp3`
+ * - `opus`: Opus in Ogg (6-510 kbps), saved as `01 Song [Opus].opus`
+ */
+export type TranscodeFormat = 'mp3' | 'opus';
+
+/**
+ * An MP3 or Opus copy saved next to each AAC/ALAC track after a download,
+ * for devices that can't play M4A.
+ *
+ * Mirrors: Rust struct `TranscodeConfig` in `src-tauri/src/models/settings.rs`
+ */
+export interface TranscodeConfig {
+  /** Format of the copies */
+  format: TranscodeFormat;
+  /** Target bitrate in kbps */
+  bitrate_kbps: number;
+}
+
 /**
  * Companion download mode: controls whether MeedyaDL automatically downloads
  * additional format versions alongside the primary download.
@@ -148,6 +202,17 @@ export type CompanionMode =
   | 'atmos_to_lossless_and_lossy'
   | 'specialist_to_lossy';
 
+/**
+ * Animated artwork (motion cover art) variant.
+ *
+ * Mirrors: Rust enum `ArtworkVariant` in `src-tauri/src/models/settings.rs`
+ *
+ * - `square`: 1:1, saved as `FrontCover.mp4`
+ * - `portrait`: 3:4, saved as `PortraitCover.mp4`
+ * - `landscape`: 16:9, saved as `LandscapeCover.mp4` (rarely available)
+ */
+export type ArtworkVariant = 'square' | 'portrait' | 'landscape';
+
 /**
  * Log level for GAMDL's `--log-level` CLI flag.
  *
@@ -336,6 +401,18 @@ export interface GamdlOptions {
   read_urls_as_txt?: boolean;
   /** When true, ignores the GAMDL config file (uses only CLI args) */
   no_config_file?: boolean;
+  /**
+   * Two-letter storefront (e.g. "jp") to download from instead of the URL's
+   * own. MeedyaDL rewrites the URL; this is not passed to GAMDL.
+   */
+  storefront?: string;
+  /** Unix niceness GAMDL runs at (a priority class on Windows); not passed to GAMDL */
+  process_niceness?: number;
+  /**
+   * When true, GAMDL reads the user's own config file instead of the one
+   * MeedyaDL syncs. Set from the use_gamdl_config_file setting; not passed to GAMDL.
+   */
+  use_user_config?: boolean;
 }
 
 // ============================================================
@@ -370,8 +447,48 @@ export interface AppSettings {
   language: string;
   /** Whether to overwrite existing files by default */
   overwrite: boolean;
+  /** Existing-file behaviour; null follows `overwrite` (replace when true, else skip) */
+  overwrite_policy: OverwritePolicy | null;
+  /** Whether downloads finish in a staging folder and move to output_path on success */
+  use_staging_dir: boolean;
+  /** Whether artist URLs are expanded into one download per album */
+  expand_artist_urls: boolean;
+  /** Whether playlist URLs download only tracks added since the last sync */
+  playlist_sync: boolean;
+  /** Whether album/playlist tracks the catalog no longer offers are skipped before queueing */
+  skip_unavailable_tracks: boolean;
+  /** Whether album/song URLs are swapped for their explicit or clean version before queueing */
+  content_preference: ContentPreference;
+  /** Whether start_download runs the pre-flight checks before queueing */
+  preflight_checks: boolean;
+  /** Whether a higher-priority download stops a running lower-priority one */
+  preempt_lower_priority: boolean;
   /** Whether to automatically check for updates on app startup */
   auto_check_updates: boolean;
+  /** Whether to quit the app once every download has finished */
+  auto_quit_when_done: boolean;
+  /** Whether downloads only run inside the daily schedule window */
+  schedule_enabled: boolean;
+  /** Time the download window opens (local time, "HH:MM") */
+  schedule_start: string;
+  /** Time the download window closes (local time, "HH:MM"); earlier than start crosses midnight */
+  schedule_end: string;
+  /** Whether running downloads are paused (re-queued) when the window closes */
+  schedule_pause_at_close: boolean;
+  /** Minimum free space (GiB) on the output drive before a download starts; null = no check */
+  min_free_space_gb: number | null;
+  /** Times a download is re-queued after a network error before it fails */
+  network_retry_budget: number;
+  /** Times a rate-limited download is re-queued (after a cooldown) before it fails */
+  rate_limit_retry_budget: number;
+  /** Seconds without GAMDL output before a download is killed as hung and retried (null = never) */
+  download_stall_timeout_secs: number | null;
+  /** Artwork/companion/post-processing tasks allowed to run at once (at least 1) */
+  max_background_tasks: number;
+  /** Seconds to wait between one download finishing and the next starting (0 = no pause) */
+  inter_download_delay_secs: number;
+  /** Seconds after which a completed download leaves the queue (null = keep until cleared) */
+  auto_clear_completed_after_secs: number | null;
   /** Default audio codec for song downloads */
   default_song_codec: SongCodec;
   /** Default maximum video resolution */
@@ -380,14 +497,32 @@ export interface AppSettings {
   default_video_codec_priority: string;
   /** Default container format for remuxed music videos */
   default_video_remux_format: string;
+  /** Quality for uploaded (post) videos ("best"); null = GAMDL default */
+  uploaded_video_quality: string | null;
+  /** Whether the music videos on albums and playlists are downloaded too (GAMDL skips them) */
+  download_album_music_videos: boolean;
   /** Whether fallback codec/resolution chains are enabled */
   fallback_enabled: boolean;
   /** Ordered list of codecs to try if the primary codec is unavailable */
   music_fallback_chain: SongCodec[];
+  /** Whether each track of an album gets the best codec of the chain it offers */
+  per_track_best_codec: boolean;
   /** Ordered list of resolutions to try if the primary resolution is unavailable */
   video_fallback_chain: VideoResolution[];
   /** Companion download mode: controls automatic multi-format downloads */
   companion_mode: CompanionMode;
+  /** Per-tier companion subfolders keyed by codec ("alac", "aac"), used instead of filename suffixes */
+  companion_subfolders: Record<string, string>;
+  /** Whether ALAC downloads are fetched as AAC first and upgraded to lossless in the background */
+  preview_then_upgrade: boolean;
+  /** Whether to transcode finished ALAC downloads from M4A to FLAC */
+  convert_lossless_to_flac: boolean;
+  /** Whether to keep the ALAC M4A next to the converted FLAC file */
+  keep_m4a_after_flac: boolean;
+  /** MP3/Opus copy saved next to each AAC/ALAC track, or null for none */
+  transcode_companion: TranscodeConfig | null;
+  /** Whether to decode each downloaded file with FFmpeg and re-download damaged ones */
+  verify_audio: boolean;
   /** Whether to both embed lyrics in file metadata AND keep sidecar lyrics files */
   embed_lyrics_and_sidecar: boolean;
   /** Default format for synced lyrics output */
@@ -396,16 +531,32 @@ export interface AppSettings {
   no_synced_lyrics: boolean;
   /** Whether to download only synced lyrics (no audio) */
   synced_lyrics_only: boolean;
+  /** Whether to save skipped album music videos' lyrics next to the matching audio tracks */
+  keep_mv_lyrics: boolean;
+  /** Format downloaded TTML lyrics are converted to after each download (null = keep TTML only) */
+  convert_lyrics_to: LyricsConversion | null;
   /** Whether to save album cover art as separate files */
   save_cover: boolean;
   /** Default image format for saved cover art */
   cover_format: CoverFormat;
   /** Default pixel dimensions for cover art */
   cover_size: number;
+  /** Cover size per codec (e.g., { alac: 10000, aac: 600 }); codecs not listed use cover_size */
+  cover_size_by_codec: Partial<Record<SongCodec, number>>;
+  /** Smaller pixel size for the cover embedded in each track (null = same as cover_size) */
+  embedded_cover_size: number | null;
+  /** Extra pixel sizes the saved cover is also written at, as cover-{size}.jpg */
+  extra_cover_sizes: number[];
+  /** Whether tracks whose cover art fails to download are saved without it */
+  continue_on_cover_failure: boolean;
   /** Whether to download animated cover art (motion artwork) from Apple Music */
   animated_artwork_enabled: boolean;
   /** Whether to set the OS "hidden" attribute on animated artwork files */
   hide_animated_artwork: boolean;
+  /** Which animated artwork variants to download */
+  animated_artwork_variants: ArtworkVariant[];
+  /** Maximum height in pixels for animated artwork (null = original resolution) */
+  animated_artwork_max_height: number | null;
   /** Apple MusicKit Team ID for API authentication (10-char, e.g. "ABCDE12345") */
   musickit_team_id: string | null;
   /** Apple MusicKit Key ID for API authentication (10-char, e.g. "ABC123DEFG") */
@@ -416,6 +567,8 @@ export interface AppSettings {
   compilation_folder_template: string;
   /** Template for folder naming when album folders are disabled */
   no_album_folder_template: string;
+  /** Folder template for single-track downloads; null = use no_album_folder_template */
+  singles_folder_template: string | null;
   /** Template for file naming on single-disc albums */
   single_disc_file_template: string;
   /** Template for file naming on multi-disc albums */
@@ -424,6 +577,8 @@ export interface AppSettings {
   no_album_file_template: string;
   /** Template for file naming in playlist downloads */
   playlist_file_template: string;
+  /** Whether GAMDL writes an M3U8 file for each downloaded playlist */
+  save_playlist_m3u: boolean;
   /** Path to Netscape-format cookies file, or null if not set */
   cookies_path: string | null;
   /** Custom FFmpeg binary path, or null to use bundled/PATH version */
@@ -440,14 +595,36 @@ export interface AppSettings {
   download_mode: DownloadMode;
   /** Which remux tool to use by default */
   remux_mode: RemuxMode;
+  /** Whether imported queue entries run as one GAMDL batch job (--read-urls-as-txt) */
+  batch_mode: boolean;
+  /** Whether GAMDL hides Python exceptions (`--no-exceptions`) */
+  suppress_gamdl_exceptions: boolean;
+  /** Niceness (-20 to 19) GAMDL and its FFmpeg jobs run at, or null for normal priority */
+  process_niceness: number | null;
+  /** Whether GAMDL reads the user's own config file (omits `--config-path`) */
+  use_gamdl_config_file: boolean;
+  /** Whether each download's GAMDL output is saved to its own log file */
+  save_download_logs: boolean;
   /** Whether to use the Apple Music API wrapper */
   use_wrapper: boolean;
   /** URL for the API wrapper account endpoint */
   wrapper_account_url: string;
+  /** Whether the localhost HTTP control API starts with the app */
+  headless_api_enabled: boolean;
+  /** Port of the localhost HTTP control API */
+  headless_api_port: number;
+  /** Proxy URL for HTTP traffic, or null for a direct connection */
+  http_proxy: string | null;
+  /** Proxy URL for HTTPS traffic, or null for a direct connection */
+  https_proxy: string | null;
   /** Maximum filename length, or null for no truncation */
   truncate: number | null;
   /** Whether to fetch extra metadata tags (normalization, smooth playback) */
   fetch_extra_tags: boolean;
+  /** Whether to tag every track with the album release date */
+  use_album_date: boolean;
+  /** strftime pattern for the date tag (e.g. "%Y"), or null for GAMDL's default */
+  date_tag_template: string | null;
   /** List of metadata tags to exclude from output files */
   exclude_tags: string[];
   /** Whether the sidebar is in collapsed (icon-only) mode */
@@ -476,8 +653,23 @@ export interface DownloadRequest {
   urls: string[];
   /** Optional per-download overrides (merged with global settings) */
   options?: GamdlOptions;
+  /** Optional user note for this download (never passed to GAMDL) */
+  label?: string | null;
+  /** Queue priority: higher starts first (default 0) */
+  priority?: number;
+  /** Codecs to fall back through instead of the global chain (null = global) */
+  fallback_chain_override?: SongCodec[] | null;
+  /** What was queued; recorded by the backend on enqueue (null = classify the URLs) */
+  kind?: DownloadKind | null;
 }
 
+/**
+ * What a download's URLs point at, for options that only apply to one kind.
+ *
+ * Mirrors: Rust enum `DownloadKind` in `src-tauri/src/models/download.rs`
+ */
+export type DownloadKind = 'single' | 'album' | 'playlist' | 'other';
+
 /**
  * Possible states of a download queue item (state machine).
  *
@@ -520,6 +712,8 @@ export interface QueueItemStatus {
   progress: number;
   /** Name of the track currently being downloaded, or null if not started */
   current_track: string | null;
+  /** Step GAMDL is on besides downloading (e.g., "Fetching decryption keys", "Remuxing"), or null */
+  current_step: string | null;
   /** Total number of tracks in this download, or null if unknown */
   total_tracks: number | null;
   /** Number of tracks completed so far, or null if not applicable */
@@ -536,8 +730,280 @@ export interface QueueItemStatus {
   codec_used: string | null;
   /** Whether a codec/resolution fallback was used for this download */
   fallback_occurred: boolean;
+  /** Why the last codec fallback happened (e.g., "Lossless (ALAC) isn't available for this release"), or null */
+  fallback_reason: string | null;
+  /** Codec each saved track got, as [track, codec] pairs (differs per track on mixed albums) */
+  track_codecs: [string, string][];
+  /** Tracks left out before GAMDL ran (e.g., no longer available in the catalog) */
+  skipped_tracks: SkippedTrack[];
+  /** Tracks saved without cover art because it couldn't be downloaded */
+  cover_missing_tracks: number;
+  /** Progress of each URL, in order; empty for single-URL downloads */
+  url_progress: UrlProgress[];
   /** ISO 8601 timestamp when this download was queued */
   created_at: string;
+  /** User note set at enqueue time or via setDownloadLabel(), or null */
+  label: string | null;
+}
+
+/**
+ * Payload of the `output-collision` event: a new download saves into the
+ * same album folder as an unfinished one. Answer with `mergeDownloads()`.
+ */
+export interface OutputCollision {
+  /** The download just queued */
+  download_id: string;
+  /** The unfinished download it collides with */
+  existing_id: string;
+}
+
+/**
+ * Where GAMDL is with one URL of a multi-URL download.
+ *
+ * Mirrors: Rust enum `UrlState` in `src-tauri/src/models/download.rs`
+ */
+export type UrlState = 'pending' | 'downloading' | 'done';
+
+/**
+ * Progress of one URL of a multi-URL download.
+ *
+ * Mirrors: Rust struct `UrlProgress` in `src-tauri/src/models/download.rs`
+ */
+export interface UrlProgress {
+  /** The Apple Music URL */
+  url: string;
+  /** Whether GAMDL hasn't reached it yet, is on it, or is done with it */
+  state: UrlState;
+  /** Tracks of this URL saved so far */
+  completed_tracks: number;
+  /** Number of tracks of this URL, once GAMDL reported it */
+  total_tracks: number | null;
+}
+
+/**
+ * A track left out of a download before it was queued, e.g. because the
+ * Apple Music catalog no longer offers it (`skip_unavailable_tracks`).
+ */
+export interface SkippedTrack {
+  /** Catalog ID of the song */
+  id: string;
+  /** Song title (empty if the catalog didn't return one) */
+  name: string;
+  /** Why it was skipped (e.g., "unavailable") */
+  reason: string;
+}
+
+/**
+ * Payload of the `fallback-applied` event, emitted when a failed download
+ * moves on to the next codec of the fallback chain.
+ * Maps to Rust `FallbackApplied` in `src-tauri/src/models/download.rs`.
+ */
+export interface FallbackApplied {
+  /** The download's queue ID */
+  download_id: string;
+  /** Codec given up (GAMDL CLI name, e.g. "alac") */
+  from_codec: string;
+  /** Codec tried next (e.g. "aac") */
+  to_codec: string;
+  /** Human-readable reason derived from the classified error */
+  reason: string;
+}
+
+/**
+ * One point on a download's throughput graph.
+ *
+ * Mirrors: Rust struct `SpeedSample` in `src-tauri/src/models/download.rs`
+ *
+ * Returned (oldest first) by the `get_speed_samples` IPC command.
+ */
+export interface SpeedSample {
+  /** Unix timestamp in milliseconds when the sample was taken */
+  timestamp_ms: number;
+  /** Download speed in bytes per second */
+  bytes_per_sec: number;
+}
+
+/**
+ * Estimated size of one track in a size estimate.
+ *
+ * Mirrors: Rust struct `TrackEstimate` in `src-tauri/src/services/size_estimate_service.rs`
+ */
+export interface TrackEstimate {
+  /** Catalog ID of the song */
+  id: string;
+  /** Song title (empty if unknown) */
+  name: string;
+  /** Duration in milliseconds */
+  duration_ms: number;
+  /** Estimated file size in bytes */
+  estimated_bytes: number;
+}
+
+/**
+ * Download size estimate, from catalog durations and average bitrates.
+ *
+ * Mirrors: Rust struct `SizeEstimate` in `src-tauri/src/services/size_estimate_service.rs`
+ *
+ * Returned by the `estimate_size` IPC command. An estimate, not a measured
+ * size; ALAC and Dolby Atmos also carry a min-max `range`.
+ */
+export interface SizeEstimate {
+  /** Number of tracks found */
+  track_count: number;
+  /** Estimated total size in bytes at the codec's typical bitrate */
+  estimated_bytes: number;
+  /** Lowest and highest plausible total (ALAC and Dolby Atmos only) */
+  range: { min: number; max: number } | null;
+  /** The codec estimated for */
+  codec: SongCodec;
+  /** Per-track breakdown */
+  tracks: TrackEstimate[];
+  /** URLs that couldn't be sized (artists, music videos, lookup failures) */
+  unresolved_urls: string[];
+}
+
+/**
+ * One pre-flight check result.
+ *
+ * Mirrors: Rust struct `PreflightCheck` in `src-tauri/src/services/preflight_service.rs`
+ */
+export interface PreflightCheck {
+  /** Stable identifier: cookies, python, gamdl, ffmpeg, mp4decrypt, output_path, urls */
+  id: string;
+  /** Whether the check passed */
+  passed: boolean;
+  /** What was found, in words */
+  message: string;
+  /** How to fix a failed check; null when it passed */
+  hint: string | null;
+}
+
+/**
+ * Result of the toolchain health check run before a download.
+ *
+ * Mirrors: Rust struct `PreflightReport` in `src-tauri/src/services/preflight_service.rs`
+ *
+ * Returned by the `preflight` IPC command.
+ */
+export interface PreflightReport {
+  /** Whether every check passed */
+  passed: boolean;
+  /** Results in the order they were run */
+  checks: PreflightCheck[];
+}
+
+/**
+ * A CSV import row that was left out.
+ *
+ * Mirrors: Rust struct `CsvRowError` in `src-tauri/src/services/csv_import_service.rs`
+ */
+export interface CsvRowError {
+  /** Line number in the file, starting at 1 */
+  line: number;
+  /** What's wrong with the row */
+  message: string;
+}
+
+/**
+ * Result of a CSV import.
+ *
+ * Mirrors: Rust struct `CsvImportReport` in `src-tauri/src/services/csv_import_service.rs`
+ *
+ * Returned by the `import_csv` IPC command.
+ */
+export interface CsvImportReport {
+  /** IDs of the queued downloads, in file order */
+  download_ids: string[];
+  /** Rows that were left out */
+  errors: CsvRowError[];
+}
+
+/**
+ * Result of a settings import.
+ *
+ * Mirrors: Rust struct `SettingsImportReport` in `src-tauri/src/services/settings_transfer_service.rs`
+ *
+ * Returned by the `import_settings` IPC command.
+ */
+export interface SettingsImportReport {
+  /** Settings fields the import changed */
+  changed: string[];
+  /** Machine-specific paths in the file that were left at their current values */
+  kept_paths: string[];
+}
+
+/** What happened to one URL of a batch of downloads. */
+export type BatchOutcome = 'complete' | 'skipped' | 'failed' | 'pending';
+
+/**
+ * One row of a batch report.
+ *
+ * Mirrors: Rust struct `BatchReportRow` in `src-tauri/src/services/batch_report_service.rs`
+ */
+export interface BatchReportRow {
+  /** The queue item the URL belongs to */
+  download_id: string;
+  /** The Apple Music URL */
+  url: string;
+  /** What happened to it */
+  outcome: BatchOutcome;
+  /** Failure or skip category (e.g. "auth", "unavailable"); null otherwise */
+  category: string | null;
+  /** Short explanation; null for complete and pending URLs */
+  reason: string | null;
+}
+
+/**
+ * Per-URL outcome table of a set of downloads.
+ *
+ * Mirrors: Rust struct `BatchReport` in `src-tauri/src/services/batch_report_service.rs`
+ *
+ * Returned by the `get_batch_report` IPC command.
+ */
+export interface BatchReport {
+  /** One row per URL and skipped track, in queue order */
+  rows: BatchReportRow[];
+  /** Number of complete rows */
+  succeeded: number;
+  /** Number of failed rows */
+  failed: number;
+  /** Number of skipped rows */
+  skipped: number;
+  /** Number of pending rows */
+  pending: number;
+  /** One-line summary, e.g. "47 succeeded, 3 failed (auth), 1 skipped (unavailable)" */
+  summary: string;
+}
+
+/** Pipeline stage a test download failed at. */
+export type TestStage =
+  | 'setup'
+  | 'auth'
+  | 'codec'
+  | 'decrypt'
+  | 'remux'
+  | 'download'
+  | 'output'
+  | 'verify';
+
+/**
+ * Result of a one-track test download.
+ *
+ * Mirrors: Rust struct `TestDownloadResult` in `src-tauri/src/services/test_download_service.rs`
+ *
+ * Returned by the `run_test_download` IPC command.
+ */
+export interface TestDownloadResult {
+  /** Whether every stage passed */
+  passed: boolean;
+  /** First stage that failed; null when the test passed */
+  failed_stage: TestStage | null;
+  /** What happened, in words */
+  message: string;
+  /** Codec the test downloaded in (e.g. "alac") */
+  codec: string;
+  /** Name of the downloaded (and removed) file; null on failure */
+  file_name: string | null;
 }
 
 /**
@@ -560,6 +1026,8 @@ export interface QueueStatus {
   completed: number;
   /** Number of failed items */
   failed: number;
+  /** Progress of the unfinished items (0-100), weighted by track count */
+  overall_progress: number;
   /** Full list of queue items with detailed status */
   items: QueueItemStatus[];
 }
@@ -598,8 +1066,11 @@ export interface QueueExportFile {
 export interface ExportedItem {
   /** Apple Music URL(s) for this download */
   urls: string[];
-  /** Per-download quality/format overrides (null = use importing device's defaults) */
+  /** Per-download quality/format overrides (null = use importing device's defaults).
+   *  Cookies and tool paths are stripped on export. */
   options: GamdlOptions | null;
+  /** Whether the item adds a codec version to an existing album download */
+  codec_version: boolean;
 }
 
 // ============================================================
@@ -628,6 +1099,42 @@ export interface DependencyStatus {
   path: string | null;
 }
 
+/**
+ * Payload of the `dependency-download-progress` event, emitted while the
+ * archive of Python or an external tool downloads.
+ *
+ * Mirrors: Rust struct `DependencyDownloadProgress` in
+ * `src-tauri/src/services/dependency_manager.rs`
+ */
+export interface DependencyDownloadProgress {
+  /** What is being installed: a tool ID (e.g., "ffmpeg") or "python" */
+  name: string;
+  /** Bytes received so far */
+  downloaded: number;
+  /** Size of the archive, or null when the server didn't send one */
+  total: number | null;
+  /** 0-100, or null (indeterminate) when the size is unknown */
+  percent: number | null;
+}
+
+/**
+ * Payload of the `dependency-extract-progress` event, emitted while the
+ * downloaded archive is extracted.
+ *
+ * Mirrors: Rust struct `DependencyExtractProgress` in
+ * `src-tauri/src/services/dependency_manager.rs`
+ */
+export interface DependencyExtractProgress {
+  /** What is being installed: a tool ID (e.g., "ffmpeg") or "python" */
+  name: string;
+  /** Entries (ZIP) or compressed bytes (TAR.GZ) extracted so far */
+  extracted: number;
+  /** Entries or compressed bytes in all, or null when unknown */
+  total: number | null;
+  /** 0-100, or null when the total is unknown */
+  percent: number | null;
+}
+
 // ============================================================
 // System Types
 // ============================================================
@@ -649,6 +1156,49 @@ export interface PlatformInfo {
   os_type: string;
 }
 
+/**
+ * One tool in the environment report.
+ *
+ * Mirrors: Rust struct `ToolReport` in `src-tauri/src/services/environment_service.rs`
+ */
+export interface ToolReport {
+  /** Tool identifier ("python", "gamdl", "ffmpeg", ...) */
+  id: string;
+  /** Display name ("Python", "GAMDL", "FFmpeg", ...) */
+  name: string;
+  /** The binary the app runs (GAMDL runs as a module of Python) */
+  path: string;
+  /** Whether the binary exists at `path` */
+  installed: boolean;
+  /** Detected version, when the version command produced one */
+  version: string | null;
+  /** Why no version was detected (not installed, timeout, spawn failure) */
+  error: string | null;
+}
+
+/**
+ * Environment report for bug reports (tool paths and versions, data
+ * directory, free space). Cookies are only reported as configured or not.
+ *
+ * Mirrors: Rust struct `EnvironmentReport` in `src-tauri/src/services/environment_service.rs`
+ */
+export interface EnvironmentReport {
+  /** MeedyaDL version */
+  app_version: string;
+  /** OS from `std::env::consts::OS` */
+  os: string;
+  /** CPU architecture from `std::env::consts::ARCH` */
+  arch: string;
+  /** The app data directory */
+  app_data_dir: string;
+  /** Free bytes on the data directory's drive, if readable */
+  free_space_bytes: number | null;
+  /** Whether a cookies file is configured (its path is never included) */
+  cookies_configured: boolean;
+  /** Python, GAMDL and the external tools, in that order */
+  tools: ToolReport[];
+}
+
 /**
  * Result of validating a Netscape-format cookies file.
  *
@@ -673,6 +1223,25 @@ export interface CookieValidation {
   warnings: string[];
 }
 
+/**
+ * Result of checking a folder/file naming template.
+ * Maps to Rust struct: src-tauri/src/models/gamdl_options.rs -> TemplateCheck
+ */
+export interface TemplateCheck {
+  /** Whether GAMDL can use the template as is */
+  valid: boolean;
+  /** Unbalanced braces, if any (placeholders aren't checked then) */
+  syntax_error: string | null;
+  /** Placeholder names GAMDL doesn't know, e.g. "albumartist" */
+  unknown_placeholders: string[];
+  /** Characters outside placeholders that the file system doesn't allow */
+  illegal_characters: string[];
+  /** Template with "\\" separators turned into "/" and empty folder levels dropped */
+  normalized: string;
+  /** The first problem in words (e.g. "unknown placeholder {albumartist}"); null when valid */
+  problem: string | null;
+}
+
 // ============================================================
 // Browser Cookie Import Types
 // ============================================================
@@ -720,6 +1289,32 @@ export interface CookieImportResult {
   path: string;
 }
 
+/**
+ * Outcome of a live cookie check.
+ *
+ * - `valid`: Apple Music accepted the cookies
+ * - `malformed`: unreadable file or no `media-user-token` cookie
+ * - `expired`: the token expired or Apple Music rejected it
+ * - `unverified`: the check itself failed (e.g., no network)
+ */
+export type CookieLiveStatus = 'valid' | 'malformed' | 'expired' | 'unverified';
+
+/**
+ * Result of testing a cookies file against Apple Music.
+ *
+ * Mirrors: Rust struct `CookieLiveTest` in `src-tauri/src/services/cookie_service.rs`
+ *
+ * Returned by the `test_cookies_live` IPC command.
+ */
+export interface CookieLiveTest {
+  /** The outcome of the check */
+  status: CookieLiveStatus;
+  /** Human-readable explanation */
+  message: string;
+  /** The account's storefront (e.g., "us") when the cookies are valid */
+  storefront: string | null;
+}
+
 // ============================================================
 // GAMDL Output Events (from subprocess parsing)
 // ============================================================
@@ -735,20 +1330,29 @@ export interface CookieImportResult {
  * them into these structured events via regex pattern matching.
  *
  * Variants:
+ * - `url_start`: Emitted when GAMDL moves on to the next URL of a multi-URL download
  * - `track_info`: Emitted when GAMDL starts downloading a new track
  * - `download_progress`: Emitted periodically with progress percentage
+ * - `fetching_keys`: Emitted while GAMDL gets the track's decryption key from the
+ *   license server, before the stream download starts
  * - `processing_step`: Emitted during post-download processing (remux, tag, etc.)
+ * - `track_codec`: Emitted when GAMDL confirms the codec of the current track
  * - `error`: Emitted when GAMDL reports an error
+ * - `crash`: Emitted with the full Python traceback when GAMDL crashes
  * - `complete`: Emitted when a track finishes successfully with output path
  * - `unknown`: Fallback for unparseable output lines (contains raw text)
  *
  * @see {@link https://www.typescriptlang.org/docs/handbook/2/narrowing.html#discriminated-unions} - Discriminated unions
  */
 export type GamdlOutputEvent =
+  | { type: 'url_start'; index: number; total: number; url: string }
   | { type: 'track_info'; title: string; artist: string; album: string }
   | { type: 'download_progress'; percent: number; speed: string; eta: string }
+  | { type: 'fetching_keys'; step: string }
   | { type: 'processing_step'; step: string }
+  | { type: 'track_codec'; codec: string }
   | { type: 'error'; message: string }
+  | { type: 'crash'; traceback: string }
   | { type: 'complete'; path: string }
   | { type: 'unknown'; raw: string };
 
@@ -770,6 +1374,65 @@ export interface GamdlProgress {
   event: GamdlOutputEvent;
 }
 
+/**
+ * Structured failure of a download (the `details` of `download-error`).
+ *
+ * Mirrors: Rust enum `DownloadError` in `src-tauri/src/models/download_error.rs`
+ * (serialized with `#[serde(tag = "kind")]`).
+ */
+export type DownloadErrorDetails =
+  | {
+      kind:
+        | 'codec'
+        | 'codec_unavailable'
+        | 'network'
+        | 'auth'
+        | 'no_cookies'
+        | 'rate_limit'
+        | 'subscription'
+        | 'storefront_unavailable'
+        | 'invalid_url'
+        | 'disk'
+        | 'drm'
+        | 'not_found'
+        | 'tool'
+        | 'no_output'
+        | 'corrupt_output'
+        | 'cover_missing'
+        | 'stall'
+        | 'upstream_bug'
+        | 'unknown';
+      message: string;
+    }
+  | { kind: 'cancelled'; reason: 'user' | 'codec_change' | 'schedule_pause' | 'shutdown' | 'preempted' };
+
+/**
+ * Payload of the `download-error` Tauri event, emitted once a download has
+ * failed for good (no retry or fallback left).
+ */
+export interface DownloadErrorEvent {
+  /** The failed queue item */
+  download_id: string;
+  /** Message to show the user */
+  error: string;
+  /** Error category (same as `details.kind`) */
+  category: string;
+  /** The structured error */
+  details: DownloadErrorDetails;
+}
+
+/**
+ * Payload of the `gamdl-flag-mismatch` Tauri event, emitted shortly after
+ * startup when the installed GAMDL's `--help` no longer lists flags that
+ * MeedyaDL sends with the current settings.
+ */
+export interface GamdlFlagMismatchEvent {
+  /** The installed GAMDL version */
+  gamdl_version: string;
+  /** Flags GAMDL no longer accepts, sorted (e.g. `"--album-folder-template"`) */
+  unsupported_flags: string[];
+}
+
 // ============================================================
 // URL Parser Types
 // ============================================================
@@ -1002,4 +1665,6 @@ export interface ArtworkResult {
   square_downloaded: boolean;
   /** Whether the portrait (3:4) animated cover was downloaded as PortraitCover.mp4 */
   portrait_downloaded: boolean;
+  /** Whether the landscape (16:9) animated cover was downloaded as LandscapeCover.mp4 */
+  landscape_downloaded: boolean;
 }